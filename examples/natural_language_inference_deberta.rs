@@ -69,7 +69,7 @@ fn main() -> anyhow::Result<()> {
 
     //    Forward pass
     let model_output =
-        no_grad(|| model.forward_t(Some(&input_tensor), None, None, None, None, false))?;
+        no_grad(|| model.forward_t(Some(&input_tensor), None, None, None, None, None, false))?;
 
     model_output.logits.softmax(-1, Kind::Float).print();
 
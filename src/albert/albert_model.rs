@@ -15,6 +15,9 @@ use crate::albert::encoder::AlbertTransformer;
 use crate::common::activations::Activation;
 use crate::common::dropout::Dropout;
 use crate::common::embeddings::get_shape_and_device_from_ids_embeddings_pair;
+use crate::common::loss::{
+    cross_entropy_loss, question_answering_loss, sequence_classification_loss, ProblemType,
+};
 use crate::{albert::embeddings::AlbertEmbeddings, common::activations::TensorFunction};
 use crate::{Config, RustBertError};
 use serde::{Deserialize, Serialize};
@@ -98,6 +101,8 @@ pub struct AlbertConfig {
     pub is_decoder: Option<bool>,
     pub id2label: Option<HashMap<i64, String>>,
     pub label2id: Option<HashMap<String, i64>>,
+    /// Type of problem defining the loss of the sequence classification head (default: inferred from the labels)
+    pub problem_type: Option<ProblemType>,
 }
 
 impl Config for AlbertConfig {}
@@ -129,6 +134,7 @@ impl Default for AlbertConfig {
             is_decoder: None,
             id2label: None,
             label2id: None,
+            problem_type: None,
         }
     }
 }
@@ -403,12 +409,14 @@ impl AlbertForMaskedLM {
     /// * `token_type_ids` - Optional segment id of shape (*batch size*, *sequence_length*). Convention is value of 0 for the first sentence (incl. *SEP*) and 1 for the second sentence. If None set to 0.
     /// * `position_ids` - Optional position ids of shape (*batch size*, *sequence_length*). If None, will be incremented from 0.
     /// * `input_embeds` - Optional pre-computed input embeddings of shape (*batch size*, *sequence_length*, *hidden_size*). If None, input ids must be provided (see `input_ids`)
+    /// * `labels` - Optional target token ids of shape (*batch size*, *sequence_length*) used to compute the masked language modeling loss. Positions with a label of -100 (e.g. non-masked tokens) are ignored.
     /// * `train` - boolean flag to turn on/off the dropout layers in the model. Should be set to false for inference.
     ///
     /// # Returns
     ///
     /// * `AlbertMaskedLMOutput` containing:
     ///   - `prediction_scores` - `Tensor` of shape (*batch size*, *sequence_length*, *vocab_size*)
+    ///   - `loss` - `Option<Tensor>` scalar loss, computed if labels are provided
     ///   - `all_hidden_states` - `Option<Vec<Tensor>>` of length *num_hidden_layers* with shape (*batch size*, *sequence_length*, *hidden_size*)
     ///   - `all_attentions` - `Option<Vec<Vec<Tensor>>>` of length *num_hidden_layers* of nested length *inner_group_num* with shape (*batch size*, *sequence_length*, *hidden_size*)
     ///
@@ -439,6 +447,7 @@ impl AlbertForMaskedLM {
     ///         Some(&token_type_ids),
     ///         Some(&position_ids),
     ///         None,
+    ///         None,
     ///         false,
     ///     )
     /// });
@@ -450,6 +459,7 @@ impl AlbertForMaskedLM {
         token_type_ids: Option<&Tensor>,
         position_ids: Option<&Tensor>,
         input_embeds: Option<&Tensor>,
+        labels: Option<&Tensor>,
         train: bool,
     ) -> AlbertMaskedLMOutput {
         let base_model_output = self
@@ -464,7 +474,9 @@ impl AlbertForMaskedLM {
             )
             .unwrap();
         let prediction_scores = self.predictions.forward(&base_model_output.hidden_state);
+        let loss = labels.map(|labels| cross_entropy_loss(&prediction_scores, labels));
         AlbertMaskedLMOutput {
+            loss,
             prediction_scores,
             all_hidden_states: base_model_output.all_hidden_states,
             all_attentions: base_model_output.all_attentions,
@@ -482,6 +494,7 @@ pub struct AlbertForSequenceClassification {
     albert: AlbertModel,
     dropout: Dropout,
     classifier: nn::Linear,
+    problem_type: Option<ProblemType>,
 }
 
 impl AlbertForSequenceClassification {
@@ -539,6 +552,7 @@ impl AlbertForSequenceClassification {
             albert,
             dropout,
             classifier,
+            problem_type: config.problem_type,
         })
     }
 
@@ -551,12 +565,14 @@ impl AlbertForSequenceClassification {
     /// * `token_type_ids` - Optional segment id of shape (*batch size*, *sequence_length*). Convention is value of 0 for the first sentence (incl. *SEP*) and 1 for the second sentence. If None set to 0.
     /// * `position_ids` - Optional position ids of shape (*batch size*, *sequence_length*). If None, will be incremented from 0.
     /// * `input_embeds` - Optional pre-computed input embeddings of shape (*batch size*, *sequence_length*, *hidden_size*). If None, input ids must be provided (see `input_ids`)
+    /// * `labels` - Optional labels of shape (*batch size*) used to compute the loss: class indices for single-label classification or target values for regression. Multi-label classification uses floating point targets of shape (*batch size*, *num_labels*). The loss follows the `problem_type` of the configuration, inferred from the labels if not set.
     /// * `train` - boolean flag to turn on/off the dropout layers in the model. Should be set to false for inference.
     ///
     /// # Returns
     ///
     /// * `AlbertSequenceClassificationOutput` containing:
     ///   - `logits` - `Tensor` of shape (*batch size*, *num_labels*)
+    ///   - `loss` - `Option<Tensor>` scalar loss, computed if labels are provided
     ///   - `all_hidden_states` - `Option<Vec<Tensor>>` of length *num_hidden_layers* with shape (*batch size*, *sequence_length*, *hidden_size*)
     ///   - `all_attentions` - `Option<Vec<Vec<Tensor>>>` of length *num_hidden_layers* of nested length *inner_group_num* with shape (*batch size*, *sequence_length*, *hidden_size*)
     ///
@@ -586,6 +602,7 @@ impl AlbertForSequenceClassification {
     ///                    Some(&token_type_ids),
     ///                    Some(&position_ids),
     ///                    None,
+    ///                    None,
     ///                    false)
     ///    });
     /// ```
@@ -596,6 +613,7 @@ impl AlbertForSequenceClassification {
         token_type_ids: Option<&Tensor>,
         position_ids: Option<&Tensor>,
         input_embeds: Option<&Tensor>,
+        labels: Option<&Tensor>,
         train: bool,
     ) -> AlbertSequenceClassificationOutput {
         let base_model_output = self
@@ -613,7 +631,10 @@ impl AlbertForSequenceClassification {
             .pooled_output
             .apply_t(&self.dropout, train)
             .apply(&self.classifier);
+        let loss =
+            labels.map(|labels| sequence_classification_loss(&logits, labels, self.problem_type));
         AlbertSequenceClassificationOutput {
+            loss,
             logits,
             all_hidden_states: base_model_output.all_hidden_states,
             all_attentions: base_model_output.all_attentions,
@@ -700,12 +721,14 @@ impl AlbertForTokenClassification {
     /// * `token_type_ids` - Optional segment id of shape (*batch size*, *sequence_length*). Convention is value of 0 for the first sentence (incl. *SEP*) and 1 for the second sentence. If None set to 0.
     /// * `position_ids` - Optional position ids of shape (*batch size*, *sequence_length*). If None, will be incremented from 0.
     /// * `input_embeds` - Optional pre-computed input embeddings of shape (*batch size*, *sequence_length*, *hidden_size*). If None, input ids must be provided (see `input_ids`)
+    /// * `labels` - Optional labels of shape (*batch size*, *sequence_length*) used to compute the cross-entropy loss. Positions with a label of -100 are ignored.
     /// * `train` - boolean flag to turn on/off the dropout layers in the model. Should be set to false for inference.
     ///
     /// # Returns
     ///
     /// * `AlbertTokenClassificationOutput` containing:
     ///   - `logits` - `Tensor` of shape (*batch size*, *sequence_length*, *num_labels*) containing the logits for each of the input tokens and classes
+    ///   - `loss` - `Option<Tensor>` scalar loss, computed if labels are provided
     ///   - `all_hidden_states` - `Option<Vec<Tensor>>` of length *num_hidden_layers* with shape (*batch size*, *sequence_length*, *hidden_size*)
     ///   - `all_attentions` - `Option<Vec<Vec<Tensor>>>` of length *num_hidden_layers* of nested length *inner_group_num* with shape (*batch size*, *sequence_length*, *hidden_size*)
     ///
@@ -735,6 +758,7 @@ impl AlbertForTokenClassification {
     ///                    Some(&token_type_ids),
     ///                    Some(&position_ids),
     ///                    None,
+    ///                    None,
     ///                    false)
     ///    });
     /// ```
@@ -745,6 +769,7 @@ impl AlbertForTokenClassification {
         token_type_ids: Option<&Tensor>,
         position_ids: Option<&Tensor>,
         input_embeds: Option<&Tensor>,
+        labels: Option<&Tensor>,
         train: bool,
     ) -> AlbertTokenClassificationOutput {
         let base_model_output = self
@@ -762,7 +787,9 @@ impl AlbertForTokenClassification {
             .hidden_state
             .apply_t(&self.dropout, train)
             .apply(&self.classifier);
+        let loss = labels.map(|labels| cross_entropy_loss(&logits, labels));
         AlbertTokenClassificationOutput {
+            loss,
             logits,
             all_hidden_states: base_model_output.all_hidden_states,
             all_attentions: base_model_output.all_attentions,
@@ -831,6 +858,8 @@ impl AlbertForQuestionAnswering {
     /// * `token_type_ids` - Optional segment id of shape (*batch size*, *sequence_length*). Convention is value of 0 for the first sentence (incl. *SEP*) and 1 for the second sentence. If None set to 0.
     /// * `position_ids` - Optional position ids of shape (*batch size*, *sequence_length*). If None, will be incremented from 0.
     /// * `input_embeds` - Optional pre-computed input embeddings of shape (*batch size*, *sequence_length*, *hidden_size*). If None, input ids must be provided (see `input_ids`)
+    /// * `start_positions` - Optional answer start positions of shape (*batch size*) used to compute the loss. Positions outside of the sequence are ignored.
+    /// * `end_positions` - Optional answer end positions of shape (*batch size*) used to compute the loss. Positions outside of the sequence are ignored.
     /// * `train` - boolean flag to turn on/off the dropout layers in the model. Should be set to false for inference.
     ///
     /// # Returns
@@ -838,6 +867,7 @@ impl AlbertForQuestionAnswering {
     /// * `AlbertQuestionAnsweringOutput` containing:
     ///   - `start_logits` - `Tensor` of shape (*batch size*, *sequence_length*) containing the logits for start of the answer
     ///   - `end_logits` - `Tensor` of shape (*batch size*, *sequence_length*) containing the logits for end of the answer
+    ///   - `loss` - `Option<Tensor>` scalar loss, computed if the start and end positions are provided
     ///   - `all_hidden_states` - `Option<Vec<Tensor>>` of length *num_hidden_layers* with shape (*batch size*, *sequence_length*, *hidden_size*)
     ///   - `all_attentions` - `Option<Vec<Vec<Tensor>>>` of length *num_hidden_layers* of nested length *inner_group_num* with shape (*batch size*, *sequence_length*, *hidden_size*)
     ///
@@ -867,6 +897,8 @@ impl AlbertForQuestionAnswering {
     ///                    Some(&token_type_ids),
     ///                    Some(&position_ids),
     ///                    None,
+    ///                    None,
+    ///                    None,
     ///                    false)
     ///    });
     /// ```
@@ -877,6 +909,8 @@ impl AlbertForQuestionAnswering {
         token_type_ids: Option<&Tensor>,
         position_ids: Option<&Tensor>,
         input_embeds: Option<&Tensor>,
+        start_positions: Option<&Tensor>,
+        end_positions: Option<&Tensor>,
         train: bool,
     ) -> AlbertQuestionAnsweringOutput {
         let base_model_output = self
@@ -898,7 +932,13 @@ impl AlbertForQuestionAnswering {
         let start_logits = start_logits.squeeze_dim(-1);
         let end_logits = end_logits.squeeze_dim(-1);
 
+        let loss = start_positions
+            .zip(end_positions)
+            .map(|(start_positions, end_positions)| {
+                question_answering_loss(&start_logits, &end_logits, start_positions, end_positions)
+            });
         AlbertQuestionAnsweringOutput {
+            loss,
             start_logits,
             end_logits,
             all_hidden_states: base_model_output.all_hidden_states,
@@ -1070,6 +1110,7 @@ impl AlbertForMultipleChoice {
             .view((-1, num_choices));
 
         Ok(AlbertSequenceClassificationOutput {
+            loss: None,
             logits,
             all_hidden_states: base_model_output.all_hidden_states,
             all_attentions: base_model_output.all_attentions,
@@ -1101,6 +1142,8 @@ pub struct AlbertMaskedLMOutput {
     pub all_hidden_states: Option<Vec<Tensor>>,
     /// Attention weights for all intermediate layers
     pub all_attentions: Option<Vec<Vec<Tensor>>>,
+    /// Loss computed from the labels, if provided
+    pub loss: Option<Tensor>,
 }
 
 /// Container for the ALBERT sequence classification model
@@ -1111,6 +1154,8 @@ pub struct AlbertSequenceClassificationOutput {
     pub all_hidden_states: Option<Vec<Tensor>>,
     /// Attention weights for all intermediate layers
     pub all_attentions: Option<Vec<Vec<Tensor>>>,
+    /// Loss computed from the labels, if provided
+    pub loss: Option<Tensor>,
 }
 
 /// Container for the ALBERT token classification model
@@ -1121,6 +1166,8 @@ pub struct AlbertTokenClassificationOutput {
     pub all_hidden_states: Option<Vec<Tensor>>,
     /// Attention weights for all intermediate layers
     pub all_attentions: Option<Vec<Vec<Tensor>>>,
+    /// Loss computed from the labels, if provided
+    pub loss: Option<Tensor>,
 }
 
 /// Container for the ALBERT question answering model
@@ -1133,4 +1180,6 @@ pub struct AlbertQuestionAnsweringOutput {
     pub all_hidden_states: Option<Vec<Tensor>>,
     /// Attention weights for all intermediate layers
     pub all_attentions: Option<Vec<Vec<Tensor>>>,
+    /// Loss computed from the labels, if provided
+    pub loss: Option<Tensor>,
 }
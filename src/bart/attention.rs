@@ -12,6 +12,7 @@
 // limitations under the License.
 
 use crate::common::dropout::Dropout;
+use crate::common::lora::{lora_linear, LoraConfig, LoraLinear};
 use std::borrow::Borrow;
 use tch::{nn, Tensor};
 
@@ -49,14 +50,15 @@ pub struct BartAttention {
     scaling: f64,
     encoder_decoder_attention: bool,
     output_attentions: bool,
-    k_proj: nn::Linear,
-    v_proj: nn::Linear,
-    q_proj: nn::Linear,
-    out_proj: nn::Linear,
+    k_proj: LoraLinear,
+    v_proj: LoraLinear,
+    q_proj: LoraLinear,
+    out_proj: LoraLinear,
     store_cache: bool,
 }
 
 impl BartAttention {
+    #[allow(clippy::too_many_arguments)]
     pub fn new<'p, P>(
         p: P,
         embed_dim: i64,
@@ -65,16 +67,41 @@ impl BartAttention {
         encoder_decoder_attention: bool,
         store_cache: bool,
         output_attentions: bool,
+        lora_config: Option<&LoraConfig>,
     ) -> BartAttention
     where
         P: Borrow<nn::Path<'p>>,
     {
         let p = p.borrow();
 
-        let k_proj = nn::linear(p / "k_proj", embed_dim, embed_dim, Default::default());
-        let v_proj = nn::linear(p / "v_proj", embed_dim, embed_dim, Default::default());
-        let q_proj = nn::linear(p / "q_proj", embed_dim, embed_dim, Default::default());
-        let out_proj = nn::linear(p / "out_proj", embed_dim, embed_dim, Default::default());
+        let k_proj = lora_linear(
+            p / "k_proj",
+            embed_dim,
+            embed_dim,
+            Default::default(),
+            lora_config,
+        );
+        let v_proj = lora_linear(
+            p / "v_proj",
+            embed_dim,
+            embed_dim,
+            Default::default(),
+            lora_config,
+        );
+        let q_proj = lora_linear(
+            p / "q_proj",
+            embed_dim,
+            embed_dim,
+            Default::default(),
+            lora_config,
+        );
+        let out_proj = lora_linear(
+            p / "out_proj",
+            embed_dim,
+            embed_dim,
+            Default::default(),
+            lora_config,
+        );
 
         let head_dim = embed_dim / num_heads;
         let scaling = (head_dim as f64).powf(-0.5);
@@ -111,28 +138,36 @@ impl BartAttention {
     ) -> (Tensor, Option<Tensor>, Option<LayerState>) {
         let (bs, target_length, embed_dim) = hidden_states.size3().unwrap();
 
-        let query_states = hidden_states.apply(&self.q_proj) * self.scaling;
+        let query_states = hidden_states.apply_t(&self.q_proj, train) * self.scaling;
 
         let (key_states, value_states) = if self.encoder_decoder_attention {
             if let Some(layer_state_value) = layer_state {
                 (layer_state_value.prev_key, layer_state_value.prev_value)
             } else {
                 (
-                    self._shape(key_value_states.unwrap().apply(&self.k_proj), -1, bs),
-                    self._shape(key_value_states.unwrap().apply(&self.v_proj), -1, bs),
+                    self._shape(
+                        key_value_states.unwrap().apply_t(&self.k_proj, train),
+                        -1,
+                        bs,
+                    ),
+                    self._shape(
+                        key_value_states.unwrap().apply_t(&self.v_proj, train),
+                        -1,
+                        bs,
+                    ),
                 )
             }
         } else if let Some(layer_state_value) = layer_state {
-            let key_states = self._shape(hidden_states.apply(&self.k_proj), -1, bs);
-            let value_states = self._shape(hidden_states.apply(&self.v_proj), -1, bs);
+            let key_states = self._shape(hidden_states.apply_t(&self.k_proj, train), -1, bs);
+            let value_states = self._shape(hidden_states.apply_t(&self.v_proj, train), -1, bs);
             (
                 Tensor::cat(&[layer_state_value.prev_key, key_states], 2),
                 Tensor::cat(&[layer_state_value.prev_value, value_states], 2),
             )
         } else {
             (
-                self._shape(hidden_states.apply(&self.k_proj), -1, bs),
-                self._shape(hidden_states.apply(&self.v_proj), -1, bs),
+                self._shape(hidden_states.apply_t(&self.k_proj, train), -1, bs),
+                self._shape(hidden_states.apply_t(&self.v_proj, train), -1, bs),
             )
         };
 
@@ -177,7 +212,7 @@ impl BartAttention {
             .view([bs, self.num_heads, target_length, self.head_dim])
             .transpose(1, 2)
             .reshape(&[bs, target_length, embed_dim])
-            .apply(&self.out_proj);
+            .apply_t(&self.out_proj, train);
 
         (attention_output, saved_attention_weights, new_layer_state)
     }
@@ -17,6 +17,8 @@ use crate::bart::encoder::BartEncoder;
 use crate::common::activations::Activation;
 use crate::common::dropout::Dropout;
 use crate::common::kind::get_negative_infinity;
+use crate::common::lora::LoraConfig;
+use crate::common::loss::{sequence_classification_loss, ProblemType};
 use crate::pipelines::common::{ModelType, TokenizerOption};
 use crate::pipelines::generation_utils::private_generation_utils::{
     PreparedInput, PrivateLanguageGenerator,
@@ -202,6 +204,8 @@ pub struct BartConfig {
     pub pad_token_id: Option<i64>,
     pub id2label: Option<HashMap<i64, String>>,
     pub label2id: Option<HashMap<String, i64>>,
+    /// Type of problem defining the loss of the sequence classification head (default: inferred from the labels)
+    pub problem_type: Option<ProblemType>,
     pub init_std: f64,
     pub is_decoder: Option<bool>,
     pub is_encoder_decoder: Option<bool>,
@@ -216,6 +220,7 @@ pub struct BartConfig {
     pub static_position_embeddings: Option<bool>,
     pub scale_embedding: Option<bool>,
     pub vocab_size: i64,
+    pub lora: Option<LoraConfig>,
 }
 
 impl Config for BartConfig {}
@@ -244,6 +249,7 @@ impl Default for BartConfig {
             pad_token_id: Some(1),
             id2label: None,
             label2id: None,
+            problem_type: None,
             init_std: 0.02,
             is_decoder: None,
             is_encoder_decoder: Some(true),
@@ -258,6 +264,7 @@ impl Default for BartConfig {
             static_position_embeddings: None,
             scale_embedding: Some(false),
             vocab_size: 50265,
+            lora: None,
         }
     }
 }
@@ -541,6 +548,7 @@ impl BartModel {
             train,
         );
         BartModelOutput {
+            loss: None,
             decoder_output: decoder_output.hidden_state,
             encoder_hidden_state: calc_hidden_states,
             cache: decoder_output.next_decoder_cache,
@@ -749,6 +757,7 @@ pub struct BartForSequenceClassification {
     base_model: BartModel,
     classification_head: BartClassificationHead,
     eos_token_id: i64,
+    problem_type: Option<ProblemType>,
 }
 
 impl BartForSequenceClassification {
@@ -790,6 +799,7 @@ impl BartForSequenceClassification {
             base_model,
             classification_head,
             eos_token_id,
+            problem_type: config.problem_type,
         })
     }
 
@@ -803,12 +813,14 @@ impl BartForSequenceClassification {
     /// These correspond to the encoder last hidden state and optional hidden states/attention weights for encoder layers. When provided, the encoder hidden state will not be recalculated. Useful for generation tasks.
     /// * `decoder_input_ids` - Optional input tensor of shape (*batch size*, *target_sequence_length*). Must be provided when running in generation mode (e.g. initialized with a BOS token)
     /// * `decoder_attention_mask` - Optional attention mask of shape (*batch size*, *target_sequence_length*) for the decoder positions. Positions with a mask with value 0 will be masked.
+    /// * `labels` - Optional labels of shape (*batch size*) used to compute the loss: class indices for single-label classification or target values for regression. Multi-label classification uses floating point targets of shape (*batch size*, *num_labels*). The loss follows the `problem_type` of the configuration, inferred from the labels if not set.
     /// * `train` - boolean flag to turn on/off the dropout layers in the model. Should be set to false for inference.
     ///
     /// # Returns
     ///
     /// * `BartModelOutput` containing:
     ///   - `decoder_output` - `Tensor` of shape (*batch size*, *num_classes*) representing the activations for each class and batch item
+    ///   - `loss` - `Option<Tensor>` scalar loss, computed if labels are provided
     ///   - `encoder_hidden_states` - `Option<Tensor>` of shape (*batch size*, *source_sequence_length*, *hidden_size*) representing the activations of the last encoder hidden state if it was not provided, otherwise None.
     ///   - `cache` - `(Option<Tensor>, Option<Vec<&LayerState, &LayerState>>)` of length *n_layer* containing the encoder padding mask and past keys and values for both the self attention and the encoder cross attention of each layer of the decoder.
     ///   - `all_encoder_hidden_states` - `Option<Vec<Tensor>>` of length *num_encoder_layers* with shape (*batch size*, *source_sequence_length*, *hidden_size*)
@@ -843,6 +855,7 @@ impl BartForSequenceClassification {
     ///                    None,
     ///                    Some(&target_tensor),
     ///                    Some(&decoder_attention_mask),
+    ///                    None,
     ///                    false)
     ///    });
     /// ```
@@ -853,6 +866,7 @@ impl BartForSequenceClassification {
         encoder_output: Option<&Tensor>,
         decoder_input_ids: Option<&Tensor>,
         decoder_attention_mask: Option<&Tensor>,
+        labels: Option<&Tensor>,
         train: bool,
     ) -> BartModelOutput {
         let base_model_output = self.base_model.forward_t(
@@ -882,7 +896,10 @@ impl BartForSequenceClassification {
         let logits = self
             .classification_head
             .forward_t(&sentence_representation, train);
+        let loss =
+            labels.map(|labels| sequence_classification_loss(&logits, labels, self.problem_type));
         BartModelOutput {
+            loss,
             decoder_output: logits,
             encoder_hidden_state: base_model_output.encoder_hidden_state,
             cache: None,
@@ -994,8 +1011,13 @@ impl LMHeadModel for BartForConditionalGeneration {
         Ok(LMModelOutput {
             lm_logits,
             cache: Cache::BARTCache(base_model_output.cache),
+            loss: None,
         })
     }
+
+    fn predicts_next_token(&self) -> bool {
+        false
+    }
 }
 
 /// Container holding a BART model output. The decoder output may hold the hidden state of
@@ -1017,6 +1039,8 @@ pub struct BartModelOutput {
     pub all_encoder_hidden_states: Option<Vec<Tensor>>,
     /// Attention weights for all layers of the encoder
     pub all_encoder_attentions: Option<Vec<Tensor>>,
+    /// Loss computed from the labels, if provided
+    pub loss: Option<Tensor>,
 }
 
 /// # Language generation model based on the Bart architecture
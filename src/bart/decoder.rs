@@ -58,6 +58,7 @@ impl DecoderLayer {
             false,
             true,
             output_attention,
+            config.lora.as_ref(),
         );
         let encoder_attention = BartAttention::new(
             p / "encoder_attn",
@@ -67,6 +68,7 @@ impl DecoderLayer {
             true,
             true,
             output_attention,
+            config.lora.as_ref(),
         );
         let self_attention_layer_norm = nn::layer_norm(
             p / "self_attn_layer_norm",
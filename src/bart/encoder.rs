@@ -53,6 +53,7 @@ impl EncoderLayer {
             false,
             false,
             output_attention,
+            config.lora.as_ref(),
         );
         let self_attention_layer_norm = nn::layer_norm(
             p / "self_attn_layer_norm",
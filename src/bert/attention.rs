@@ -11,11 +11,17 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use crate::bert::bert_model::BertConfig;
+use crate::bert::bert_model::{BertConfig, PositionEmbeddingType};
 use crate::common::activations::TensorFunction;
+use crate::common::attention::{
+    chunked_attention, concatenate_3_blocks, get_local_attention_mask,
+    make_3block_relative_position_ids, narrow_attention_mask, split_into_blocks,
+};
 use crate::common::dropout::Dropout;
+use crate::common::embeddings::RotaryEmbedding;
+use crate::common::lora::{lora_linear, LoraLinear};
 use std::borrow::Borrow;
-use tch::{nn, Tensor};
+use tch::{nn, Kind, Tensor};
 
 #[derive(Debug)]
 pub struct BertSelfAttention {
@@ -23,13 +29,25 @@ pub struct BertSelfAttention {
     attention_head_size: i64,
     dropout: Dropout,
     output_attentions: bool,
-    query: nn::Linear,
-    key: nn::Linear,
-    value: nn::Linear,
+    query: LoraLinear,
+    key: LoraLinear,
+    value: LoraLinear,
+    position_embedding_type: PositionEmbeddingType,
+    distance_embedding: Option<nn::Embedding>,
+    max_position_embeddings: i64,
+    rotary_embedding: Option<RotaryEmbedding>,
+    local_attention_window: Option<i64>,
+    attention_chunk_size: Option<i64>,
 }
 
 impl BertSelfAttention {
-    pub fn new<'p, P>(p: P, config: &BertConfig) -> BertSelfAttention
+    /// Build a new `BertSelfAttention` attending to the tokens within a sliding window of width
+    /// `local_attention_window` (global attention if None)
+    pub fn new<'p, P>(
+        p: P,
+        config: &BertConfig,
+        local_attention_window: Option<i64>,
+    ) -> BertSelfAttention
     where
         P: Borrow<nn::Path<'p>>,
     {
@@ -40,29 +58,55 @@ impl BertSelfAttention {
         );
         let p = p.borrow();
 
-        let query = nn::linear(
+        let query = lora_linear(
             p / "query",
             config.hidden_size,
             config.hidden_size,
             Default::default(),
+            config.lora.as_ref(),
         );
-        let key = nn::linear(
+        let key = lora_linear(
             p / "key",
             config.hidden_size,
             config.hidden_size,
             Default::default(),
+            config.lora.as_ref(),
         );
-        let value = nn::linear(
+        let value = lora_linear(
             p / "value",
             config.hidden_size,
             config.hidden_size,
             Default::default(),
+            config.lora.as_ref(),
         );
 
         let dropout = Dropout::new(config.attention_probs_dropout_prob);
         let attention_head_size = config.hidden_size / config.num_attention_heads;
         let output_attentions = config.output_attentions.unwrap_or(false);
 
+        let position_embedding_type = config.position_embedding_type.unwrap_or_default();
+        let distance_embedding = match position_embedding_type {
+            PositionEmbeddingType::RelativeKey | PositionEmbeddingType::RelativeKeyQuery => {
+                Some(nn::embedding(
+                    p / "distance_embedding",
+                    2 * config.max_position_embeddings - 1,
+                    attention_head_size,
+                    Default::default(),
+                ))
+            }
+            PositionEmbeddingType::Absolute | PositionEmbeddingType::Rotary => None,
+        };
+        // Rotary embeddings follow the RoFormer layout, rotating consecutive pairs of dimensions
+        let rotary_embedding = match position_embedding_type {
+            PositionEmbeddingType::Rotary => Some(RotaryEmbedding::new(
+                attention_head_size,
+                config.rotary_embedding_base.unwrap_or(10000.0),
+                true,
+                p.device(),
+            )),
+            _ => None,
+        };
+
         BertSelfAttention {
             num_attention_heads: config.num_attention_heads,
             attention_head_size,
@@ -71,6 +115,12 @@ impl BertSelfAttention {
             query,
             key,
             value,
+            position_embedding_type,
+            distance_embedding,
+            max_position_embeddings: config.max_position_embeddings,
+            rotary_embedding,
+            local_attention_window,
+            attention_chunk_size: config.attention_chunk_size,
         }
     }
 
@@ -85,6 +135,136 @@ impl BertSelfAttention {
             .view((bs, -1, self.num_attention_heads * dim_per_head))
     }
 
+    /// Attention scores of the relative position embeddings, for (scaled) queries of shape (..., *query_length*, *head_dim*),
+    /// keys of shape (..., *key_length*, *head_dim*) and query to key distances of shape (*query_length*, *key_length*).
+    /// Distances beyond `max_position_embeddings - 1` share the embedding of the largest distance.
+    fn relative_position_scores(
+        &self,
+        query_layer: &Tensor,
+        key_layer: &Tensor,
+        distance: &Tensor,
+    ) -> Option<Tensor> {
+        let distance_embedding = self.distance_embedding.as_ref()?;
+        let max_distance = self.max_position_embeddings - 1;
+        let positional_embedding = (distance.clamp(-max_distance, max_distance) + max_distance)
+            .apply(distance_embedding)
+            .to_kind(query_layer.kind());
+
+        let query_scores = Tensor::einsum(
+            "...ld,lrd->...lr",
+            &[query_layer, &positional_embedding],
+            None,
+        );
+        Some(match self.position_embedding_type {
+            PositionEmbeddingType::RelativeKeyQuery => {
+                let key_scores = Tensor::einsum(
+                    "...rd,lrd->...lr",
+                    &[key_layer, &positional_embedding],
+                    None,
+                );
+                query_scores + key_scores / (self.attention_head_size as f64).sqrt()
+            }
+            _ => query_scores,
+        })
+    }
+
+    /// Attention of the (scaled) queries `query_offset..query_offset + query_length` to all keys
+    fn attention(
+        &self,
+        query_layer: &Tensor,
+        key_layer: &Tensor,
+        value_layer: &Tensor,
+        mask: Option<&Tensor>,
+        query_offset: i64,
+        is_self_attention: bool,
+        train: bool,
+    ) -> (Tensor, Tensor) {
+        let (query_length, key_length) = (query_layer.size()[2], key_layer.size()[2]);
+        let device = query_layer.device();
+
+        let mut scores = query_layer.matmul(&key_layer.transpose(-1, -2));
+        if let Some(mask) = mask {
+            scores = scores + narrow_attention_mask(mask, 2, query_offset, query_length);
+        }
+        let query_positions = Tensor::arange_start(
+            query_offset,
+            query_offset + query_length,
+            (Kind::Int64, device),
+        );
+        let key_positions = Tensor::arange(key_length, (Kind::Int64, device));
+        if self.distance_embedding.is_some() {
+            let distance = query_positions.unsqueeze(1) - key_positions.unsqueeze(0);
+            scores = scores
+                + self
+                    .relative_position_scores(query_layer, key_layer, &distance)
+                    .unwrap();
+        }
+        if let (Some(local_attention_window), true) =
+            (self.local_attention_window, is_self_attention)
+        {
+            // Query-dependent masks: the sliding window is applied on the full attention scores
+            let out_of_window = (key_positions.unsqueeze(0) - query_positions.unsqueeze(1))
+                .abs()
+                .gt(local_attention_window / 2)
+                .to_kind(scores.kind());
+            scores = scores + out_of_window * -10000.0;
+        }
+
+        let weights = scores
+            .softmax(-1, scores.kind())
+            .apply_t(&self.dropout, train);
+        (weights.matmul(value_layer), weights)
+    }
+
+    /// Local attention computed on blocks of `local_attention_window / 2 + 1` tokens, each block attending to itself
+    /// and its neighbouring blocks. The attention weights have shape (*batch size*, *num_heads*, *num_blocks*, *block_len*, 3 * *block_len*).
+    fn local_attention(
+        &self,
+        query_layer: &Tensor,
+        key_layer: &Tensor,
+        value_layer: &Tensor,
+        mask: Option<&Tensor>,
+        local_attention_window: i64,
+        train: bool,
+    ) -> (Tensor, Tensor) {
+        let input_size = query_layer.size();
+        let (bs, seq_length) = (input_size[0], input_size[2]);
+        let block_len = local_attention_window / 2 + 1;
+
+        let query_blocks = split_into_blocks(query_layer, block_len, 2);
+        let key_blocks = concatenate_3_blocks(&split_into_blocks(key_layer, block_len, 2), 2, 3);
+        let value_blocks =
+            concatenate_3_blocks(&split_into_blocks(value_layer, block_len, 2), 2, 3);
+
+        let mut scores = query_blocks.matmul(&key_blocks.transpose(-1, -2));
+        let distance = make_3block_relative_position_ids(block_len, query_layer.device()).neg();
+        if let Some(relative_position_scores) =
+            self.relative_position_scores(&query_blocks, &key_blocks, &distance)
+        {
+            scores = scores + relative_position_scores;
+        }
+
+        // The extended attention mask of shape (*batch size*, 1, 1, *sequence_length*) is converted back to a padding mask
+        let padding_mask = match mask {
+            Some(mask) => mask.reshape(&[-1, seq_length]).ge(0.0).to_kind(Kind::Float),
+            None => Tensor::ones(&[bs, seq_length], (Kind::Float, query_layer.device())),
+        };
+        let local_attention_mask = get_local_attention_mask(&padding_mask, block_len)
+            .logical_not()
+            .to_kind(scores.kind())
+            * -10000.0;
+        scores = scores + local_attention_mask;
+
+        let weights = scores
+            .softmax(-1, scores.kind())
+            .apply_t(&self.dropout, train);
+        let context = weights
+            .matmul(&value_blocks)
+            .flatten(2, 3)
+            .narrow(2, 0, seq_length);
+        (context, weights)
+    }
+
     pub fn forward_t(
         &self,
         hidden_states: &Tensor,
@@ -95,50 +275,99 @@ impl BertSelfAttention {
     ) -> (Tensor, Option<Tensor>) {
         let (key_layer, value_layer, mask) = match encoder_hidden_states {
             Some(encoder_hidden_state_values) => (
-                encoder_hidden_state_values.apply(&self.key),
-                encoder_hidden_state_values.apply(&self.value),
+                encoder_hidden_state_values.apply_t(&self.key, train),
+                encoder_hidden_state_values.apply_t(&self.value, train),
                 encoder_mask,
             ),
             None => (
-                hidden_states.apply(&self.key),
-                hidden_states.apply(&self.value),
+                hidden_states.apply_t(&self.key, train),
+                hidden_states.apply_t(&self.value, train),
                 mask,
             ),
         };
+        let is_self_attention = encoder_hidden_states.is_none();
 
         let bs = hidden_states.size()[0];
 
-        let query_layer = self.split_heads(
-            hidden_states.apply(&self.query),
+        let mut query_layer = self.split_heads(
+            hidden_states.apply_t(&self.query, train),
             bs,
             self.attention_head_size,
         );
-        let key_layer = self.split_heads(key_layer, bs, self.attention_head_size);
+        let mut key_layer = self.split_heads(key_layer, bs, self.attention_head_size);
         let value_layer = self.split_heads(value_layer, bs, self.attention_head_size);
+        let query_length = query_layer.size()[2];
+
+        if let (Some(rotary_embedding), true) = (&self.rotary_embedding, is_self_attention) {
+            let position_ids =
+                Tensor::arange(query_length, (Kind::Int64, query_layer.device())).unsqueeze(0);
+            let (cos, sin) = rotary_embedding.forward(&position_ids, query_layer.kind());
+            query_layer = rotary_embedding.apply(&query_layer, &cos, &sin);
+            key_layer = rotary_embedding.apply(&key_layer, &cos, &sin);
+        }
         let query_layer: Tensor = query_layer / (self.attention_head_size as f64).sqrt();
 
-        let scores = if let Some(mask) = mask {
-            query_layer.matmul(&key_layer.transpose(-1, -2)) + mask
-        } else {
-            query_layer.matmul(&key_layer.transpose(-1, -2))
+        let (context, weights) = match self.local_attention_window {
+            Some(local_attention_window)
+                if is_self_attention && !matches!(mask, Some(mask) if mask.size()[2] > 1) =>
+            {
+                let (context, weights) = self.local_attention(
+                    &query_layer,
+                    &key_layer,
+                    &value_layer,
+                    mask,
+                    local_attention_window,
+                    train,
+                );
+                (context, Some(weights))
+            }
+            _ => match self.attention_chunk_size {
+                Some(attention_chunk_size) if !self.output_attentions => {
+                    let context = chunked_attention(
+                        query_length,
+                        attention_chunk_size,
+                        |query_offset, chunk_length| {
+                            self.attention(
+                                &query_layer.narrow(2, query_offset, chunk_length),
+                                &key_layer,
+                                &value_layer,
+                                mask,
+                                query_offset,
+                                is_self_attention,
+                                train,
+                            )
+                            .0
+                        },
+                    );
+                    (context, None)
+                }
+                _ => {
+                    let (context, weights) = self.attention(
+                        &query_layer,
+                        &key_layer,
+                        &value_layer,
+                        mask,
+                        0,
+                        is_self_attention,
+                        train,
+                    );
+                    (context, Some(weights))
+                }
+            },
         };
-
-        let weights = scores
-            .softmax(-1, scores.kind())
-            .apply_t(&self.dropout, train);
-        let context = self.flatten(weights.matmul(&value_layer), bs, self.attention_head_size);
+        let context = self.flatten(context, bs, self.attention_head_size);
 
         if !self.output_attentions {
             (context, None)
         } else {
-            (context, Some(weights))
+            (context, weights)
         }
     }
 }
 
 #[derive(Debug)]
 pub struct BertSelfOutput {
-    linear: nn::Linear,
+    linear: LoraLinear,
     layer_norm: nn::LayerNorm,
     dropout: Dropout,
 }
@@ -150,11 +379,12 @@ impl BertSelfOutput {
     {
         let p = p.borrow();
 
-        let linear = nn::linear(
+        let linear = lora_linear(
             p / "dense",
             config.hidden_size,
             config.hidden_size,
             Default::default(),
+            config.lora.as_ref(),
         );
         let layer_norm_config = nn::LayerNormConfig {
             eps: 1e-12,
@@ -174,7 +404,7 @@ impl BertSelfOutput {
     pub fn forward_t(&self, hidden_states: &Tensor, input_tensor: &Tensor, train: bool) -> Tensor {
         let hidden_states: Tensor = input_tensor
             + hidden_states
-                .apply(&self.linear)
+                .apply_t(&self.linear, train)
                 .apply_t(&self.dropout, train);
         hidden_states.apply(&self.layer_norm)
     }
@@ -188,12 +418,25 @@ pub struct BertAttention {
 
 impl BertAttention {
     pub fn new<'p, P>(p: P, config: &BertConfig) -> BertAttention
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        BertAttention::new_with_attention_window(p, config, None)
+    }
+
+    /// Build a new `BertAttention` attending to the tokens within a sliding window of width
+    /// `local_attention_window` (global attention if None)
+    pub fn new_with_attention_window<'p, P>(
+        p: P,
+        config: &BertConfig,
+        local_attention_window: Option<i64>,
+    ) -> BertAttention
     where
         P: Borrow<nn::Path<'p>>,
     {
         let p = p.borrow();
 
-        let _self = BertSelfAttention::new(p / "self", config);
+        let _self = BertSelfAttention::new(p / "self", config, local_attention_window);
         let output = BertSelfOutput::new(p / "output", config);
         BertAttention { _self, output }
     }
@@ -16,6 +16,10 @@ use crate::common::activations::Activation;
 use crate::common::dropout::Dropout;
 use crate::common::embeddings::get_shape_and_device_from_ids_embeddings_pair;
 use crate::common::linear::{linear_no_bias, LinearNoBias};
+use crate::common::lora::LoraConfig;
+use crate::common::loss::{
+    cross_entropy_loss, question_answering_loss, sequence_classification_loss, ProblemType,
+};
 use crate::{
     bert::embeddings::{BertEmbedding, BertEmbeddings},
     common::activations::TensorFunction,
@@ -67,6 +71,16 @@ impl BertModelResources {
         "all-mini-lm-l6-v2/model",
         "https://huggingface.co/sentence-transformers/all-MiniLM-L6-v2/resolve/main/rust_model.ot",
     );
+    /// Shared under Apache 2.0 license at <https://huggingface.co/sentence-transformers/paraphrase-multilingual-MiniLM-L12-v2>. Modified with conversion to C-array format.
+    pub const PARAPHRASE_MULTILINGUAL_MINI_LM_L12_V2: (&'static str, &'static str) = (
+        "paraphrase-multilingual-mini-lm-l12-v2/model",
+        "https://huggingface.co/sentence-transformers/paraphrase-multilingual-MiniLM-L12-v2/resolve/main/rust_model.ot",
+    );
+    /// Shared under Apache 2.0 license at <https://huggingface.co/sentence-transformers/LaBSE>. Modified with conversion to C-array format.
+    pub const LABSE: (&'static str, &'static str) = (
+        "labse/model",
+        "https://huggingface.co/sentence-transformers/LaBSE/resolve/main/rust_model.ot",
+    );
 }
 
 impl BertConfigResources {
@@ -100,6 +114,16 @@ impl BertConfigResources {
         "all-mini-lm-l6-v2/config",
         "https://huggingface.co/sentence-transformers/all-MiniLM-L6-v2/resolve/main/config.json",
     );
+    /// Shared under Apache 2.0 license at <https://huggingface.co/sentence-transformers/paraphrase-multilingual-MiniLM-L12-v2>. Modified with conversion to C-array format.
+    pub const PARAPHRASE_MULTILINGUAL_MINI_LM_L12_V2: (&'static str, &'static str) = (
+        "paraphrase-multilingual-mini-lm-l12-v2/config",
+        "https://huggingface.co/sentence-transformers/paraphrase-multilingual-MiniLM-L12-v2/resolve/main/config.json",
+    );
+    /// Shared under Apache 2.0 license at <https://huggingface.co/sentence-transformers/LaBSE>. Modified with conversion to C-array format.
+    pub const LABSE: (&'static str, &'static str) = (
+        "labse/config",
+        "https://huggingface.co/sentence-transformers/LaBSE/resolve/main/config.json",
+    );
 }
 
 impl BertVocabResources {
@@ -133,6 +157,26 @@ impl BertVocabResources {
         "all-mini-lm-l6-v2/vocab",
         "https://huggingface.co/sentence-transformers/all-MiniLM-L6-v2/resolve/main/vocab.txt",
     );
+    /// Shared under Apache 2.0 license at <https://huggingface.co/sentence-transformers/LaBSE>. Modified with conversion to C-array format.
+    pub const LABSE: (&'static str, &'static str) = (
+        "labse/vocab",
+        "https://huggingface.co/sentence-transformers/LaBSE/resolve/main/vocab.txt",
+    );
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+/// # Position encoding of BERT models
+pub enum PositionEmbeddingType {
+    /// Learned absolute position embeddings added to the token embeddings, limiting the input length to `max_position_embeddings`
+    #[default]
+    Absolute,
+    /// Learned relative position embeddings interacting with the queries (Shaw et al., 2018)
+    RelativeKey,
+    /// Learned relative position embeddings interacting with the queries and keys (Huang et al., 2020)
+    RelativeKeyQuery,
+    /// Rotary position embeddings applied to the queries and keys (Su et al., 2021), without learned parameters
+    Rotary,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -155,10 +199,50 @@ pub struct BertConfig {
     pub is_decoder: Option<bool>,
     pub id2label: Option<HashMap<i64, String>>,
     pub label2id: Option<HashMap<String, i64>>,
+    /// Type of problem defining the loss of the sequence classification head (default: inferred from the labels)
+    pub problem_type: Option<ProblemType>,
+    pub lora: Option<LoraConfig>,
+    /// Position encoding (default: absolute position embeddings)
+    pub position_embedding_type: Option<PositionEmbeddingType>,
+    /// Frequency base of the rotary position embeddings (default: 10000)
+    pub rotary_embedding_base: Option<f64>,
+    /// Width of the sliding window of the local attention layers. If set, layers alternate between local
+    /// attention (each token attending to the `local_attention_window / 2` tokens on each side) and global attention
+    pub local_attention_window: Option<i64>,
+    /// Every `global_attention_every_n_layers`-th layer (starting with the first layer) uses global attention when
+    /// `local_attention_window` is set, 0 for local attention in all layers (default: 3)
+    pub global_attention_every_n_layers: Option<i64>,
+    /// If set, the global attention is computed for blocks of `attention_chunk_size` queries at a time, limiting the
+    /// memory used by the attention scores (not applied when the attention weights are returned)
+    pub attention_chunk_size: Option<i64>,
 }
 
 impl Config for BertConfig {}
 
+impl BertConfig {
+    /// Width of the local attention window of the layer `layer_index`, None for global attention layers
+    pub(crate) fn layer_attention_window(&self, layer_index: i64) -> Option<i64> {
+        let local_attention_window = self.local_attention_window?;
+        let global_attention_every_n_layers = self.global_attention_every_n_layers.unwrap_or(3);
+        if global_attention_every_n_layers > 0 && layer_index % global_attention_every_n_layers == 0
+        {
+            None
+        } else {
+            Some(local_attention_window)
+        }
+    }
+
+    /// Maximum input length for learned absolute positions, None for relative or rotary positions
+    pub(crate) fn max_absolute_position(&self) -> Option<i64> {
+        match self.position_embedding_type.unwrap_or_default() {
+            PositionEmbeddingType::Absolute => Some(self.max_position_embeddings),
+            PositionEmbeddingType::RelativeKey
+            | PositionEmbeddingType::RelativeKeyQuery
+            | PositionEmbeddingType::Rotary => None,
+        }
+    }
+}
+
 impl Default for BertConfig {
     fn default() -> Self {
         BertConfig {
@@ -178,6 +262,13 @@ impl Default for BertConfig {
             is_decoder: None,
             id2label: None,
             label2id: None,
+            problem_type: None,
+            lora: None,
+            position_embedding_type: None,
+            rotary_embedding_base: None,
+            local_attention_window: None,
+            global_attention_every_n_layers: None,
+            attention_chunk_size: None,
         }
     }
 }
@@ -576,12 +667,14 @@ impl BertForMaskedLM {
     /// * `input_embeds` - Optional pre-computed input embeddings of shape (*batch size*, *sequence_length*, *hidden_size*). If None, input ids must be provided (see *input_ids*)
     /// * `encoder_hidden_states` - Optional encoder hidden state of shape (*batch size*, *encoder_sequence_length*, *hidden_size*). If the model is defined as a decoder and the *encoder_hidden_states* is not None, used in the cross-attention layer as keys and values (query from the decoder).
     /// * `encoder_mask` - Optional encoder attention mask of shape (*batch size*, *encoder_sequence_length*). If the model is defined as a decoder and the *encoder_hidden_states* is not None, used to mask encoder values. Positions with value 0 will be masked.
+    /// * `labels` - Optional target token ids of shape (*batch size*, *sequence_length*) used to compute the masked language modeling loss. Positions with a label of -100 (e.g. non-masked tokens) are ignored.
     /// * `train` - boolean flag to turn on/off the dropout layers in the model. Should be set to false for inference.
     ///
     /// # Returns
     ///
     /// * `BertMaskedLMOutput` containing:
     ///   - `prediction_scores` - `Tensor` of shape (*batch size*, *sequence_length*, *vocab_size*)
+    ///   - `loss` - `Option<Tensor>` scalar loss, computed if labels are provided
     ///   - `all_hidden_states` - `Option<Vec<Tensor>>` of length *num_hidden_layers* with shape (*batch size*, *sequence_length*, *hidden_size*)
     ///   - `all_attentions` - `Option<Vec<Tensor>>` of length *num_hidden_layers* with shape (*batch size*, *sequence_length*, *hidden_size*)
     ///
@@ -613,6 +706,7 @@ impl BertForMaskedLM {
     ///         None,
     ///         None,
     ///         None,
+    ///         None,
     ///         false,
     ///     )
     /// });
@@ -626,6 +720,7 @@ impl BertForMaskedLM {
         input_embeds: Option<&Tensor>,
         encoder_hidden_states: Option<&Tensor>,
         encoder_mask: Option<&Tensor>,
+        labels: Option<&Tensor>,
         train: bool,
     ) -> BertMaskedLMOutput {
         let base_model_output = self
@@ -643,7 +738,9 @@ impl BertForMaskedLM {
             .unwrap();
 
         let prediction_scores = self.cls.forward(&base_model_output.hidden_state);
+        let loss = labels.map(|labels| cross_entropy_loss(&prediction_scores, labels));
         BertMaskedLMOutput {
+            loss,
             prediction_scores,
             all_hidden_states: base_model_output.all_hidden_states,
             all_attentions: base_model_output.all_attentions,
@@ -660,6 +757,7 @@ pub struct BertForSequenceClassification {
     bert: BertModel<BertEmbeddings>,
     dropout: Dropout,
     classifier: nn::Linear,
+    problem_type: Option<ProblemType>,
 }
 
 impl BertForSequenceClassification {
@@ -715,6 +813,7 @@ impl BertForSequenceClassification {
             bert,
             dropout,
             classifier,
+            problem_type: config.problem_type,
         })
     }
 
@@ -727,12 +826,14 @@ impl BertForSequenceClassification {
     /// * `token_type_ids` -Optional segment id of shape (*batch size*, *sequence_length*). Convention is value of 0 for the first sentence (incl. *SEP*) and 1 for the second sentence. If None set to 0.
     /// * `position_ids` - Optional position ids of shape (*batch size*, *sequence_length*). If None, will be incremented from 0.
     /// * `input_embeds` - Optional pre-computed input embeddings of shape (*batch size*, *sequence_length*, *hidden_size*). If None, input ids must be provided (see `input_ids`)
+    /// * `labels` - Optional labels of shape (*batch size*) used to compute the loss: class indices for single-label classification or target values for regression. Multi-label classification uses floating point targets of shape (*batch size*, *num_labels*). The loss follows the `problem_type` of the configuration, inferred from the labels if not set.
     /// * `train` - boolean flag to turn on/off the dropout layers in the model. Should be set to false for inference.
     ///
     /// # Returns
     ///
     /// * `BertSequenceClassificationOutput` containing:
     ///   - `logits` - `Tensor` of shape (*batch size*, *num_labels*)
+    ///   - `loss` - `Option<Tensor>` scalar loss, computed if labels are provided
     ///   - `all_hidden_states` - `Option<Vec<Tensor>>` of length *num_hidden_layers* with shape (*batch size*, *sequence_length*, *hidden_size*)
     ///   - `all_attentions` - `Option<Vec<Tensor>>` of length *num_hidden_layers* with shape (*batch size*, *sequence_length*, *hidden_size*)
     ///
@@ -762,6 +863,7 @@ impl BertForSequenceClassification {
     ///         Some(&token_type_ids),
     ///         Some(&position_ids),
     ///         None,
+    ///         None,
     ///         false,
     ///     )
     /// });
@@ -773,6 +875,7 @@ impl BertForSequenceClassification {
         token_type_ids: Option<&Tensor>,
         position_ids: Option<&Tensor>,
         input_embeds: Option<&Tensor>,
+        labels: Option<&Tensor>,
         train: bool,
     ) -> BertSequenceClassificationOutput {
         let base_model_output = self
@@ -794,7 +897,10 @@ impl BertForSequenceClassification {
             .unwrap()
             .apply_t(&self.dropout, train)
             .apply(&self.classifier);
+        let loss =
+            labels.map(|labels| sequence_classification_loss(&logits, labels, self.problem_type));
         BertSequenceClassificationOutput {
+            loss,
             logits,
             all_hidden_states: base_model_output.all_hidden_states,
             all_attentions: base_model_output.all_attentions,
@@ -862,12 +968,14 @@ impl BertForMultipleChoice {
     /// * `mask` - Optional mask of shape (*batch size*, *sequence_length*). Masked position have value 0, non-masked value 1. If None set to 1
     /// * `token_type_ids` -Optional segment id of shape (*batch size*, *sequence_length*). Convention is value of 0 for the first sentence (incl. *SEP*) and 1 for the second sentence. If None set to 0.
     /// * `position_ids` - Optional position ids of shape (*batch size*, *sequence_length*). If None, will be incremented from 0.
+    /// * `labels` - Optional labels of shape (*batch size*) with the index of the correct choice, used to compute the cross-entropy loss.
     /// * `train` - boolean flag to turn on/off the dropout layers in the model. Should be set to false for inference.
     ///
     /// # Returns
     ///
     /// * `BertSequenceClassificationOutput` containing:
     ///   - `logits` - `Tensor` of shape (*1*, *batch size*) containing the logits for each of the alternatives given
+    ///   - `loss` - `Option<Tensor>` scalar loss, computed if labels are provided
     ///   - `all_hidden_states` - `Option<Vec<Tensor>>` of length *num_hidden_layers* with shape (*batch size*, *sequence_length*, *hidden_size*)
     ///   - `all_attentions` - `Option<Vec<Tensor>>` of length *num_hidden_layers* with shape (*batch size*, *sequence_length*, *hidden_size*)
     ///
@@ -897,6 +1005,7 @@ impl BertForMultipleChoice {
     ///         Some(&mask),
     ///         Some(&token_type_ids),
     ///         Some(&position_ids),
+    ///         None,
     ///         false,
     ///     )
     /// });
@@ -907,6 +1016,7 @@ impl BertForMultipleChoice {
         mask: Option<&Tensor>,
         token_type_ids: Option<&Tensor>,
         position_ids: Option<&Tensor>,
+        labels: Option<&Tensor>,
         train: bool,
     ) -> BertSequenceClassificationOutput {
         let num_choices = input_ids.size()[1];
@@ -938,7 +1048,9 @@ impl BertForMultipleChoice {
             .apply_t(&self.dropout, train)
             .apply(&self.classifier)
             .view((-1, num_choices));
+        let loss = labels.map(|labels| cross_entropy_loss(&logits, labels));
         BertSequenceClassificationOutput {
+            loss,
             logits,
             all_hidden_states: base_model_output.all_hidden_states,
             all_attentions: base_model_output.all_attentions,
@@ -1023,12 +1135,14 @@ impl BertForTokenClassification {
     /// * `token_type_ids` -Optional segment id of shape (*batch size*, *sequence_length*). Convention is value of 0 for the first sentence (incl. *SEP*) and 1 for the second sentence. If None set to 0.
     /// * `position_ids` - Optional position ids of shape (*batch size*, *sequence_length*). If None, will be incremented from 0.
     /// * `input_embeds` - Optional pre-computed input embeddings of shape (*batch size*, *sequence_length*, *hidden_size*). If None, input ids must be provided (see `input_ids`)
+    /// * `labels` - Optional labels of shape (*batch size*, *sequence_length*) used to compute the cross-entropy loss. Positions with a label of -100 are ignored.
     /// * `train` - boolean flag to turn on/off the dropout layers in the model. Should be set to false for inference.
     ///
     /// # Returns
     ///
     /// * `BertTokenClassificationOutput` containing:
     ///   - `logits` - `Tensor` of shape (*batch size*, *sequence_length*, *num_labels*) containing the logits for each of the input tokens and classes
+    ///   - `loss` - `Option<Tensor>` scalar loss, computed if labels are provided
     ///   - `all_hidden_states` - `Option<Vec<Tensor>>` of length *num_hidden_layers* with shape (*batch size*, *sequence_length*, *hidden_size*)
     ///   - `all_attentions` - `Option<Vec<Tensor>>` of length *num_hidden_layers* with shape (*batch size*, *sequence_length*, *hidden_size*)
     ///
@@ -1059,6 +1173,7 @@ impl BertForTokenClassification {
     ///         Some(&token_type_ids),
     ///         Some(&position_ids),
     ///         None,
+    ///         None,
     ///         false,
     ///     )
     /// });
@@ -1070,6 +1185,7 @@ impl BertForTokenClassification {
         token_type_ids: Option<&Tensor>,
         position_ids: Option<&Tensor>,
         input_embeds: Option<&Tensor>,
+        labels: Option<&Tensor>,
         train: bool,
     ) -> BertTokenClassificationOutput {
         let base_model_output = self
@@ -1090,7 +1206,9 @@ impl BertForTokenClassification {
             .hidden_state
             .apply_t(&self.dropout, train)
             .apply(&self.classifier);
+        let loss = labels.map(|labels| cross_entropy_loss(&logits, labels));
         BertTokenClassificationOutput {
+            loss,
             logits,
             all_hidden_states: base_model_output.all_hidden_states,
             all_attentions: base_model_output.all_attentions,
@@ -1159,6 +1277,8 @@ impl BertForQuestionAnswering {
     /// * `token_type_ids` -Optional segment id of shape (*batch size*, *sequence_length*). Convention is value of 0 for the first sentence (incl. *SEP*) and 1 for the second sentence. If None set to 0.
     /// * `position_ids` - Optional position ids of shape (*batch size*, *sequence_length*). If None, will be incremented from 0.
     /// * `input_embeds` - Optional pre-computed input embeddings of shape (*batch size*, *sequence_length*, *hidden_size*). If None, input ids must be provided (see `input_ids`)
+    /// * `start_positions` - Optional answer start positions of shape (*batch size*) used to compute the loss. Positions outside of the sequence are ignored.
+    /// * `end_positions` - Optional answer end positions of shape (*batch size*) used to compute the loss. Positions outside of the sequence are ignored.
     /// * `train` - boolean flag to turn on/off the dropout layers in the model. Should be set to false for inference.
     ///
     /// # Returns
@@ -1166,6 +1286,7 @@ impl BertForQuestionAnswering {
     /// * `BertQuestionAnsweringOutput` containing:
     ///   - `start_logits` - `Tensor` of shape (*batch size*, *sequence_length*) containing the logits for start of the answer
     ///   - `end_logits` - `Tensor` of shape (*batch size*, *sequence_length*) containing the logits for end of the answer
+    ///   - `loss` - `Option<Tensor>` scalar loss, computed if the start and end positions are provided
     ///   - `all_hidden_states` - `Option<Vec<Tensor>>` of length *num_hidden_layers* with shape (*batch size*, *sequence_length*, *hidden_size*)
     ///   - `all_attentions` - `Option<Vec<Vec<Tensor>>>` of length *num_hidden_layers* with shape (*batch size*, *sequence_length*, *hidden_size*)
     ///
@@ -1196,6 +1317,8 @@ impl BertForQuestionAnswering {
     ///         Some(&token_type_ids),
     ///         Some(&position_ids),
     ///         None,
+    ///         None,
+    ///         None,
     ///         false,
     ///     )
     /// });
@@ -1207,6 +1330,8 @@ impl BertForQuestionAnswering {
         token_type_ids: Option<&Tensor>,
         position_ids: Option<&Tensor>,
         input_embeds: Option<&Tensor>,
+        start_positions: Option<&Tensor>,
+        end_positions: Option<&Tensor>,
         train: bool,
     ) -> BertQuestionAnsweringOutput {
         let base_model_output = self
@@ -1229,7 +1354,13 @@ impl BertForQuestionAnswering {
         let start_logits = start_logits.squeeze_dim(-1);
         let end_logits = end_logits.squeeze_dim(-1);
 
+        let loss = start_positions
+            .zip(end_positions)
+            .map(|(start_positions, end_positions)| {
+                question_answering_loss(&start_logits, &end_logits, start_positions, end_positions)
+            });
         BertQuestionAnsweringOutput {
+            loss,
             start_logits,
             end_logits,
             all_hidden_states: base_model_output.all_hidden_states,
@@ -1262,6 +1393,8 @@ pub struct BertMaskedLMOutput {
     pub all_hidden_states: Option<Vec<Tensor>>,
     /// Attention weights for all intermediate layers
     pub all_attentions: Option<Vec<Tensor>>,
+    /// Loss computed from the labels, if provided
+    pub loss: Option<Tensor>,
 }
 
 /// Container for the BERT sequence classification model output.
@@ -1272,6 +1405,8 @@ pub struct BertSequenceClassificationOutput {
     pub all_hidden_states: Option<Vec<Tensor>>,
     /// Attention weights for all intermediate layers
     pub all_attentions: Option<Vec<Tensor>>,
+    /// Loss computed from the labels, if provided
+    pub loss: Option<Tensor>,
 }
 
 /// Container for the BERT token classification model output.
@@ -1282,6 +1417,8 @@ pub struct BertTokenClassificationOutput {
     pub all_hidden_states: Option<Vec<Tensor>>,
     /// Attention weights for all intermediate layers
     pub all_attentions: Option<Vec<Tensor>>,
+    /// Loss computed from the labels, if provided
+    pub loss: Option<Tensor>,
 }
 
 /// Container for the BERT question answering model output.
@@ -1294,6 +1431,8 @@ pub struct BertQuestionAnsweringOutput {
     pub all_hidden_states: Option<Vec<Tensor>>,
     /// Attention weights for all intermediate layers
     pub all_attentions: Option<Vec<Tensor>>,
+    /// Loss computed from the labels, if provided
+    pub loss: Option<Tensor>,
 }
 
 #[cfg(test)]
@@ -11,7 +11,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use crate::bert::bert_model::BertConfig;
+use crate::bert::bert_model::{BertConfig, PositionEmbeddingType};
 use crate::common::dropout::Dropout;
 use crate::common::embeddings::process_ids_embeddings_pair;
 use crate::RustBertError;
@@ -41,7 +41,7 @@ pub trait BertEmbedding {
 /// Implementation of the `BertEmbedding` trait for BERT models
 pub struct BertEmbeddings {
     word_embeddings: nn::Embedding,
-    position_embeddings: nn::Embedding,
+    position_embeddings: Option<nn::Embedding>,
     token_type_embeddings: nn::Embedding,
     layer_norm: nn::LayerNorm,
     dropout: Dropout,
@@ -87,12 +87,15 @@ impl BertEmbedding for BertEmbeddings {
             embedding_config,
         );
 
-        let position_embeddings: nn::Embedding = embedding(
-            p / "position_embeddings",
-            config.max_position_embeddings,
-            config.hidden_size,
-            Default::default(),
-        );
+        let position_embeddings = match config.position_embedding_type.unwrap_or_default() {
+            PositionEmbeddingType::Absolute => Some(embedding(
+                p / "position_embeddings",
+                config.max_position_embeddings,
+                config.hidden_size,
+                Default::default(),
+            )),
+            _ => None,
+        };
 
         let token_type_embeddings: nn::Embedding = embedding(
             p / "token_type_embeddings",
@@ -123,7 +126,7 @@ impl BertEmbedding for BertEmbeddings {
     ///
     /// * `input_ids` - Optional input tensor of shape (*batch size*, *sequence_length*). If None, pre-computed embeddings must be provided (see *input_embeds*)
     /// * `token_type_ids` -Optional segment id of shape (*batch size*, *sequence_length*). Convention is value of 0 for the first sentence (incl. *SEP*) and 1 for the second sentence. If None set to 0.
-    /// * `position_ids` - Optional position ids of shape (*batch size*, *sequence_length*). If None, will be incremented from 0.
+    /// * `position_ids` - Optional position ids of shape (*batch size*, *sequence_length*). If None, will be incremented from 0. Ignored if the model does not use absolute position embeddings.
     /// * `input_embeds` - Optional pre-computed input embeddings of shape (*batch size*, *sequence_length*, *hidden_size*). If None, input ids must be provided (see *input_ids*)
     /// * `train` - boolean flag to turn on/off the dropout layers in the model. Should be set to false for inference.
     ///
@@ -178,16 +181,6 @@ impl BertEmbedding for BertEmbeddings {
             input_embeds.unwrap_or_else(|| calc_input_embeddings.as_ref().unwrap());
         let seq_length = input_embeddings.size()[1];
 
-        let calc_position_ids = if position_ids.is_none() {
-            Some(
-                Tensor::arange(seq_length, (Kind::Int64, input_embeddings.device()))
-                    .unsqueeze(0)
-                    .expand(&input_shape, true),
-            )
-        } else {
-            None
-        };
-
         let calc_token_type_ids = if token_type_ids.is_none() {
             Some(Tensor::zeros(
                 &input_shape,
@@ -197,15 +190,21 @@ impl BertEmbedding for BertEmbeddings {
             None
         };
 
-        let position_ids = position_ids.unwrap_or_else(|| calc_position_ids.as_ref().unwrap());
         let token_type_ids =
             token_type_ids.unwrap_or_else(|| calc_token_type_ids.as_ref().unwrap());
-
-        let position_embeddings = position_ids.apply(&self.position_embeddings);
         let token_type_embeddings = token_type_ids.apply(&self.token_type_embeddings);
 
-        let input_embeddings: Tensor =
-            input_embeddings + position_embeddings + token_type_embeddings;
+        let mut input_embeddings: Tensor = input_embeddings + token_type_embeddings;
+        if let Some(position_embeddings) = &self.position_embeddings {
+            let position_embeddings = match position_ids {
+                Some(position_ids) => position_ids.apply(position_embeddings),
+                None => Tensor::arange(seq_length, (Kind::Int64, input_embeddings.device()))
+                    .unsqueeze(0)
+                    .expand(&input_shape, true)
+                    .apply(position_embeddings),
+            };
+            input_embeddings += position_embeddings;
+        }
         Ok(input_embeddings
             .apply(&self.layer_norm)
             .apply_t(&self.dropout, train))
@@ -55,12 +55,49 @@ impl BertLayer {
     /// let layer: BertLayer = BertLayer::new(&p.root(), &config);
     /// ```
     pub fn new<'p, P>(p: P, config: &BertConfig) -> BertLayer
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        BertLayer::new_with_attention_window(p, config, None)
+    }
+
+    /// Build a new `BertLayer` whose self-attention is restricted to a sliding window
+    ///
+    /// # Arguments
+    ///
+    /// * `p` - Variable store path for the root of the BERT model
+    /// * `config` - `BertConfig` object defining the model architecture
+    /// * `local_attention_window` - Width of the sliding window of the self-attention. Each token attends to the `local_attention_window / 2` tokens on each side. Global attention if None.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use rust_bert::bert::{BertConfig, BertLayer};
+    /// use rust_bert::Config;
+    /// use std::path::Path;
+    /// use tch::{nn, Device};
+    ///
+    /// let config_path = Path::new("path/to/config.json");
+    /// let device = Device::Cpu;
+    /// let p = nn::VarStore::new(device);
+    /// let config = BertConfig::from_file(config_path);
+    /// let layer: BertLayer = BertLayer::new_with_attention_window(&p.root(), &config, Some(128));
+    /// ```
+    pub fn new_with_attention_window<'p, P>(
+        p: P,
+        config: &BertConfig,
+        local_attention_window: Option<i64>,
+    ) -> BertLayer
     where
         P: Borrow<nn::Path<'p>>,
     {
         let p = p.borrow();
 
-        let attention = BertAttention::new(p / "attention", config);
+        let attention = BertAttention::new_with_attention_window(
+            p / "attention",
+            config,
+            local_attention_window,
+        );
         let (is_decoder, cross_attention) = match config.is_decoder {
             Some(value) => {
                 if value {
@@ -167,6 +204,8 @@ impl BertLayer {
 /// Encoder used in BERT models.
 /// It is made of a Vector of `BertLayer` through which hidden states will be passed. The encoder can also be
 /// used as a decoder (with cross-attention) if `encoder_hidden_states` are provided.
+/// If the configuration sets a `local_attention_window`, the layers alternate between global attention and
+/// sliding window attention (see `BertConfig::global_attention_every_n_layers`).
 pub struct BertEncoder {
     output_attentions: bool,
     output_hidden_states: bool,
@@ -205,7 +244,11 @@ impl BertEncoder {
 
         let mut layers: Vec<BertLayer> = vec![];
         for layer_index in 0..config.num_hidden_layers {
-            layers.push(BertLayer::new(&p / layer_index, config));
+            layers.push(BertLayer::new_with_attention_window(
+                &p / layer_index,
+                config,
+                config.layer_attention_window(layer_index),
+            ));
         }
 
         BertEncoder {
@@ -8,6 +8,13 @@
 //! - Sequence classification: `bert_model::BertForSequenceClassification`
 //! - Token classification (e.g. NER, POS tagging): `bert_model::BertForTokenClassification`
 //!
+//! The position encoding is set by `BertConfig::position_embedding_type`: learned absolute positions (default),
+//! learned relative positions (`relative_key`, `relative_key_query`) or rotary position embeddings (`rotary`).
+//! Relative and rotary positions do not limit the input length to the learned absolute positions. Combined with
+//! `BertConfig::local_attention_window`, which alternates sliding window attention layers with global attention layers,
+//! they allow encoding long documents (several thousand tokens) in a single pass. The pipelines process inputs up to
+//! `max_position_embeddings` tokens.
+//!
 //! # Model set-up and pre-trained weights loading
 //!
 //! A full working example is provided in `examples/masked_language_model_bert`, run with `cargo run --example masked_language_model_bert`.
@@ -62,7 +69,7 @@ pub use bert_model::{
     BertForQuestionAnswering, BertForSentenceEmbeddings, BertForSequenceClassification,
     BertForTokenClassification, BertMaskedLMOutput, BertModel, BertModelOutput, BertModelResources,
     BertQuestionAnsweringOutput, BertSequenceClassificationOutput, BertTokenClassificationOutput,
-    BertVocabResources,
+    BertVocabResources, PositionEmbeddingType,
 };
 pub use embeddings::{BertEmbedding, BertEmbeddings};
 pub use encoder::{BertEncoder, BertEncoderOutput, BertLayer, BertLayerOutput, BertPooler};
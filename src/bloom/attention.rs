@@ -0,0 +1,213 @@
+// Copyright 2022 HuggingFace Inc. team and BigScience workshop.
+// Copyright 2023 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::bloom::BloomConfig;
+use crate::common::dropout::Dropout;
+use std::borrow::Borrow;
+use tch::{nn, Kind, Tensor};
+
+#[derive(Debug)]
+/// # Cache for BLOOM attention layers
+/// Stores the cached value of key and value
+pub struct LayerState {
+    /// Cached keys
+    pub prev_key: Tensor,
+    /// Cached values
+    pub prev_value: Tensor,
+}
+
+impl Clone for LayerState {
+    fn clone(&self) -> Self {
+        LayerState {
+            prev_key: self.prev_key.copy(),
+            prev_value: self.prev_value.copy(),
+        }
+    }
+}
+
+impl LayerState {
+    pub(crate) fn reorder_cache(&mut self, new_indices: &Tensor) {
+        self.prev_key = self.prev_key.index_select(0, new_indices);
+        self.prev_value = self.prev_value.index_select(0, new_indices);
+    }
+}
+
+/// Computes the ALiBi slopes for each attention head ([Press et al., 2021](https://arxiv.org/abs/2108.12409)).
+/// For a number of heads that is not a power of 2, the slopes of the closest lower power of 2 are
+/// completed with every other slope of the next power of 2.
+pub(crate) fn get_alibi_slopes(num_heads: i64) -> Vec<f64> {
+    let closest_power_of_2 = 2_i64.pow((num_heads as f64).log2().floor() as u32);
+    let get_slopes = |n: i64| {
+        let base = 2_f64.powf(-(2_f64.powf(-((n as f64).log2() - 3.0))));
+        (1..=n)
+            .map(|power| base.powi(power as i32))
+            .collect::<Vec<f64>>()
+    };
+    let mut slopes = get_slopes(closest_power_of_2);
+    if closest_power_of_2 != num_heads {
+        let extra_slopes = get_slopes(2 * closest_power_of_2);
+        slopes.extend(
+            extra_slopes
+                .into_iter()
+                .step_by(2)
+                .take((num_heads - closest_power_of_2) as usize),
+        );
+    }
+    slopes
+}
+
+pub struct BloomAttention {
+    query_key_value: nn::Linear,
+    dense: nn::Linear,
+    attention_dropout: Dropout,
+    hidden_dropout: Dropout,
+    num_heads: i64,
+    head_dim: i64,
+    output_attentions: bool,
+}
+
+impl BloomAttention {
+    pub fn new<'p, P>(p: P, config: &BloomConfig) -> BloomAttention
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let p = p.borrow();
+
+        let num_heads = config.n_head;
+        let head_dim = config.hidden_size / config.n_head;
+
+        let query_key_value = nn::linear(
+            p / "query_key_value",
+            config.hidden_size,
+            3 * config.hidden_size,
+            Default::default(),
+        );
+        let dense = nn::linear(
+            p / "dense",
+            config.hidden_size,
+            config.hidden_size,
+            Default::default(),
+        );
+
+        let attention_dropout = Dropout::new(config.attention_dropout);
+        let hidden_dropout = Dropout::new(config.hidden_dropout);
+        let output_attentions = config.output_attentions.unwrap_or(false);
+
+        BloomAttention {
+            query_key_value,
+            dense,
+            attention_dropout,
+            hidden_dropout,
+            num_heads,
+            head_dim,
+            output_attentions,
+        }
+    }
+
+    /// Forward pass through the attention layer
+    ///
+    /// # Arguments
+    ///
+    /// * `hidden_states` - input tensor of shape (*batch size*, *sequence_length*, *hidden_size*)
+    /// * `residual` - residual tensor of shape (*batch size*, *sequence_length*, *hidden_size*) added to the attention output
+    /// * `alibi` - ALiBi attention biases of shape (*batch size*, *num_heads*, 1, *total_sequence_length*)
+    /// * `attention_mask` - Optional boolean mask of shape (*batch size*, 1, *sequence_length*, *total_sequence_length*), with `true` for the positions that cannot be attended to
+    /// * `layer_state` - Optional cached keys and values of the previous positions
+    /// * `train` - boolean flag to turn on/off the dropout layers in the model
+    pub fn forward_t(
+        &self,
+        hidden_states: &Tensor,
+        residual: &Tensor,
+        alibi: &Tensor,
+        attention_mask: Option<&Tensor>,
+        layer_state: Option<&LayerState>,
+        train: bool,
+    ) -> (Tensor, Option<Tensor>, LayerState) {
+        let size = hidden_states.size();
+        let (batch_size, sequence_length) = (size[0], size[1]);
+
+        // The fused projection is laid out as (query, key, value) for each head
+        let query_key_value = hidden_states
+            .apply(&self.query_key_value)
+            .view([
+                batch_size,
+                sequence_length,
+                self.num_heads,
+                3 * self.head_dim,
+            ])
+            .transpose(1, 2);
+        let query = query_key_value.narrow(-1, 0, self.head_dim);
+        let mut key = query_key_value.narrow(-1, self.head_dim, self.head_dim);
+        let mut value = query_key_value.narrow(-1, 2 * self.head_dim, self.head_dim);
+
+        if let Some(layer_state) = layer_state {
+            key = Tensor::cat(&[&layer_state.prev_key, &key], 2);
+            value = Tensor::cat(&[&layer_state.prev_value, &value], 2);
+        }
+        let new_layer_state = LayerState {
+            prev_key: key.copy(),
+            prev_value: value.copy(),
+        };
+
+        let mut attention_scores = query
+            .to_kind(Kind::Float)
+            .matmul(&key.to_kind(Kind::Float).transpose(-1, -2))
+            / (self.head_dim as f64).sqrt()
+            + alibi.to_kind(Kind::Float);
+        if let Some(attention_mask) = attention_mask {
+            attention_scores = attention_scores.masked_fill(attention_mask, f32::MIN as f64);
+        }
+        let attention_weights = attention_scores
+            .softmax(-1, Kind::Float)
+            .to_kind(value.kind())
+            .apply_t(&self.attention_dropout, train);
+
+        let attention_output = attention_weights
+            .matmul(&value)
+            .transpose(1, 2)
+            .contiguous()
+            .view([batch_size, sequence_length, self.num_heads * self.head_dim])
+            .apply(&self.dense)
+            .apply_t(&self.hidden_dropout, train)
+            + residual;
+
+        let attention_weights = if self.output_attentions {
+            Some(attention_weights)
+        } else {
+            None
+        };
+
+        (attention_output, attention_weights, new_layer_state)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn alibi_slopes() {
+        let slopes = get_alibi_slopes(8);
+        let expected = (1..=8)
+            .map(|power| 2_f64.powi(-power))
+            .collect::<Vec<f64>>();
+        assert_eq!(slopes, expected);
+
+        // 12 heads: slopes of 8 heads completed with every other slope of 16 heads
+        let slopes = get_alibi_slopes(12);
+        assert_eq!(slopes.len(), 12);
+        assert_eq!(slopes[..8], expected[..]);
+        for (slope, power) in slopes[8..].iter().zip([0.5, 1.5, 2.5, 3.5].iter()) {
+            assert!((slope - 2_f64.powf(-power)).abs() < 1e-12);
+        }
+    }
+}
@@ -0,0 +1,700 @@
+// Copyright 2022 HuggingFace Inc. team and BigScience workshop.
+// Copyright 2023 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::bloom::attention::get_alibi_slopes;
+use crate::bloom::decoder::BloomBlock;
+use crate::bloom::LayerState;
+use crate::common::embeddings::process_ids_embeddings_pair;
+use crate::pipelines::common::{ModelType, TokenizerOption};
+use crate::pipelines::generation_utils::private_generation_utils::{
+    PreparedInput, PrivateLanguageGenerator,
+};
+use crate::pipelines::generation_utils::{
+    Cache, GenerateConfig, LMHeadModel, LMModelOutput, LanguageGenerator,
+};
+use crate::{Config, RustBertError};
+use rust_tokenizers::tokenizer::Gpt2Tokenizer;
+use rust_tokenizers::vocab::Gpt2Vocab;
+use serde::{Deserialize, Serialize};
+use std::borrow::{Borrow, BorrowMut};
+use tch::{nn, Kind, Tensor};
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+/// # BLOOM model configuration
+/// Defines the BLOOM model architecture (e.g. number of layers, hidden layer size, number of heads...).
+/// The field names follow the configuration files of the [Transformers library](https://github.com/huggingface/transformers).
+pub struct BloomConfig {
+    pub vocab_size: i64,
+    #[serde(alias = "n_embed")]
+    pub hidden_size: i64,
+    pub n_layer: i64,
+    pub n_head: i64,
+    pub layer_norm_epsilon: f64,
+    pub initializer_range: f64,
+    pub apply_residual_connection_post_layernorm: Option<bool>,
+    pub hidden_dropout: f64,
+    pub attention_dropout: f64,
+    pub bos_token_id: Option<i64>,
+    pub eos_token_id: Option<i64>,
+    pub pad_token_id: Option<i64>,
+    pub output_attentions: Option<bool>,
+    pub output_hidden_states: Option<bool>,
+}
+
+impl Config for BloomConfig {}
+
+impl Default for BloomConfig {
+    fn default() -> Self {
+        BloomConfig {
+            vocab_size: 250880,
+            hidden_size: 1024,
+            n_layer: 24,
+            n_head: 16,
+            layer_norm_epsilon: 1e-5,
+            initializer_range: 0.02,
+            apply_residual_connection_post_layernorm: None,
+            hidden_dropout: 0.0,
+            attention_dropout: 0.0,
+            bos_token_id: Some(1),
+            eos_token_id: Some(2),
+            pad_token_id: Some(3),
+            output_attentions: None,
+            output_hidden_states: None,
+        }
+    }
+}
+
+/// # BLOOM Base model
+/// Base architecture for BLOOM models. Task-specific models will be built from this common base model
+/// It is made of the following blocks:
+/// - `word_embeddings`: Word embeddings
+/// - `word_embeddings_layernorm`: Layer normalization applied to the word embeddings
+/// - `h`: Vector of `BloomBlock` (transformer part of the model)
+/// - `ln_f`: Final layer normalization
+/// - `alibi_slopes`: ALiBi slopes of each attention head, used instead of position embeddings
+pub struct BloomModel {
+    word_embeddings: nn::Embedding,
+    word_embeddings_layernorm: nn::LayerNorm,
+    h: Vec<BloomBlock>,
+    ln_f: nn::LayerNorm,
+    alibi_slopes: Tensor,
+    output_attentions: bool,
+    output_hidden_states: bool,
+}
+
+impl BloomModel {
+    /// Build a new `BloomModel`
+    ///
+    /// # Arguments
+    ///
+    /// * `p` - Variable store path for the root of the BLOOM model
+    /// * `config` - `BloomConfig` object defining the model architecture
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use rust_bert::bloom::{BloomConfig, BloomModel};
+    /// use rust_bert::Config;
+    /// use std::path::Path;
+    /// use tch::{nn, Device};
+    ///
+    /// let config_path = Path::new("path/to/config.json");
+    /// let device = Device::Cpu;
+    /// let p = nn::VarStore::new(device);
+    /// let config = BloomConfig::from_file(config_path);
+    /// let bloom_model = BloomModel::new(&p.root(), &config);
+    /// ```
+    pub fn new<'p, P>(p: P, config: &BloomConfig) -> BloomModel
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let p = p.borrow();
+
+        let layer_norm_config = nn::LayerNormConfig {
+            eps: config.layer_norm_epsilon,
+            ..Default::default()
+        };
+        let word_embeddings = nn::embedding(
+            p / "word_embeddings",
+            config.vocab_size,
+            config.hidden_size,
+            Default::default(),
+        );
+        let word_embeddings_layernorm = nn::layer_norm(
+            p / "word_embeddings_layernorm",
+            vec![config.hidden_size],
+            layer_norm_config,
+        );
+
+        let mut h: Vec<BloomBlock> = Vec::with_capacity(config.n_layer as usize);
+        let p_layers = p / "h";
+        for layer_index in 0..config.n_layer {
+            h.push(BloomBlock::new(&p_layers / layer_index, config));
+        }
+
+        let ln_f = nn::layer_norm(p / "ln_f", vec![config.hidden_size], layer_norm_config);
+        let alibi_slopes = Tensor::of_slice(&get_alibi_slopes(config.n_head))
+            .to_kind(Kind::Float)
+            .to_device(p.device());
+
+        let output_attentions = config.output_attentions.unwrap_or(false);
+        let output_hidden_states = config.output_hidden_states.unwrap_or(false);
+
+        BloomModel {
+            word_embeddings,
+            word_embeddings_layernorm,
+            h,
+            ln_f,
+            alibi_slopes,
+            output_attentions,
+            output_hidden_states,
+        }
+    }
+
+    /// Forward pass through the model
+    ///
+    /// # Arguments
+    ///
+    /// * `input_ids` - Optional input tensor of shape (*batch size*, *sequence_length*). This or `input_embeds` must be provided.
+    /// * `input_embeds` - Optional input tensor of shape (*batch size*, *sequence_length*, *embeddings dimension*). This or `input_ids` must be provided.
+    /// * `layer_states` - Optional Vector `Option<Vec<Option<LayerState>>>` of length *n_layer* containing the past keys and values of each layer.
+    /// * `attention_mask` - Optional attention mask of shape (*batch size*, *past_sequence_length + sequence_length*). Positions with a mask with value 0 will be masked.
+    /// * `train` - boolean flag to turn on/off the dropout layers in the model. Should be set to false for inference.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<BloomModelOutput, RustBertError>` containing:
+    ///   - `hidden_states` - `Tensor` of shape (*batch size*, *sequence_length*, *hidden_size*) representing the activations of the last hidden state
+    ///   - `next_cache` - `Option<Vec<Option<LayerState>>>` of length *n_layer* containing the past content for the the attention layers
+    ///   - `all_hidden_states` - `Option<Vec<Tensor>>` of length *n_layer + 1* with shape (*batch size*, *sequence_length*, *hidden_size*)
+    ///   - `all_attentions` - `Option<Vec<Tensor>>` of length *n_layer* containing the attention weights for each layer
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use tch::{nn, Device, Tensor, no_grad, Kind};
+    /// # use rust_bert::Config;
+    /// # use std::path::Path;
+    /// # use tch::kind::Kind::{Int64, Double};
+    /// use rust_bert::bloom::{BloomConfig, BloomModel};
+    /// # let config_path = Path::new("path/to/config.json");
+    /// # let device = Device::Cpu;
+    /// # let vs = nn::VarStore::new(device);
+    /// # let config = BloomConfig::from_file(config_path);
+    /// # let bloom_model = BloomModel::new(&vs.root(), &config);
+    /// let (batch_size, sequence_length) = (64, 128);
+    /// let input_tensor = Tensor::rand(&[batch_size, sequence_length], (Int64, device));
+    /// let attention_mask = Tensor::ones(&[batch_size, sequence_length], (Int64, device));
+    ///
+    /// let model_output = no_grad(|| {
+    ///     bloom_model.forward_t(
+    ///         Some(&input_tensor),
+    ///         None,
+    ///         None,
+    ///         Some(&attention_mask),
+    ///         false,
+    ///     )
+    /// });
+    /// ```
+    pub fn forward_t(
+        &self,
+        input_ids: Option<&Tensor>,
+        input_embeds: Option<&Tensor>,
+        layer_states: Option<Vec<Option<LayerState>>>,
+        attention_mask: Option<&Tensor>,
+        train: bool,
+    ) -> Result<BloomModelOutput, RustBertError> {
+        let (calc_input_embeddings, input_shape, device) =
+            process_ids_embeddings_pair(input_ids, input_embeds, &self.word_embeddings)?;
+        let input_embeds = input_embeds.unwrap_or_else(|| calc_input_embeddings.as_ref().unwrap());
+
+        let (batch_size, sequence_length) = (input_shape[0], input_shape[1]);
+        let past_length = match &layer_states {
+            Some(layer_states) => match &layer_states[0] {
+                Some(layer_state) => layer_state.prev_key.size()[2],
+                None => 0,
+            },
+            None => 0,
+        };
+        let full_sequence_length = past_length + sequence_length;
+
+        // The ALiBi biases only depend on the key positions, counted from the first non-padded token
+        let calc_attention_mask = if attention_mask.is_none() {
+            Some(Tensor::ones(
+                &[batch_size, full_sequence_length],
+                (Kind::Int64, device),
+            ))
+        } else {
+            None
+        };
+        let full_attention_mask = attention_mask
+            .unwrap_or_else(|| calc_attention_mask.as_ref().unwrap())
+            .to_kind(Kind::Int64);
+        let key_positions =
+            (full_attention_mask.cumsum(-1, Kind::Int64) - 1) * &full_attention_mask;
+        let alibi = (self
+            .alibi_slopes
+            .to_device(key_positions.device())
+            .view([1, -1, 1])
+            * key_positions.unsqueeze(1))
+        .unsqueeze(2);
+
+        let query_positions =
+            Tensor::arange_start(past_length, full_sequence_length, (Kind::Int64, device))
+                .unsqueeze(-1);
+        let mut causal_mask = Tensor::arange(full_sequence_length, (Kind::Int64, device))
+            .unsqueeze(0)
+            .gt_tensor(&query_positions)
+            .view([1, 1, sequence_length, full_sequence_length]);
+        if let Some(attention_mask) = attention_mask {
+            causal_mask =
+                causal_mask.logical_or(&attention_mask.eq(0).view([batch_size, 1, 1, -1]));
+        }
+
+        let mut hidden_state = input_embeds.apply(&self.word_embeddings_layernorm);
+        let mut all_hidden_states: Option<Vec<Tensor>> = if self.output_hidden_states {
+            Some(vec![])
+        } else {
+            None
+        };
+        let mut all_attentions: Option<Vec<Tensor>> = if self.output_attentions {
+            Some(vec![])
+        } else {
+            None
+        };
+        let old_cache = layer_states.unwrap_or_else(|| vec![None; self.h.len()]);
+        let mut next_cache = Vec::with_capacity(self.h.len());
+
+        for (layer, layer_state) in self.h.iter().zip(old_cache) {
+            if let Some(hidden_states) = all_hidden_states.borrow_mut() {
+                hidden_states.push(hidden_state.copy());
+            };
+            let (output, attention_weights, layer_state) = layer.forward_t(
+                &hidden_state,
+                &alibi,
+                Some(&causal_mask),
+                layer_state.as_ref(),
+                train,
+            );
+            hidden_state = output;
+            next_cache.push(Some(layer_state));
+            if let Some(attentions) = all_attentions.borrow_mut() {
+                attentions.push(attention_weights.unwrap());
+            };
+        }
+
+        let hidden_states = hidden_state.apply(&self.ln_f);
+        if let Some(all_hidden_states) = all_hidden_states.borrow_mut() {
+            all_hidden_states.push(hidden_states.copy());
+        };
+
+        Ok(BloomModelOutput {
+            hidden_states,
+            next_cache: Some(next_cache),
+            all_hidden_states,
+            all_attentions,
+        })
+    }
+}
+
+/// # BLOOM Model for causal language modeling
+/// BLOOM model with a vocabulary decoding head tied to the word embeddings.
+/// It is made of the following blocks:
+/// - `transformer`: `BloomModel` Base BLOOM model
+pub struct BloomForCausalLM {
+    transformer: BloomModel,
+}
+
+impl BloomForCausalLM {
+    /// Build a new `BloomForCausalLM`
+    ///
+    /// # Arguments
+    ///
+    /// * `p` - Variable store path for the root of the BLOOM model
+    /// * `config` - `BloomConfig` object defining the model architecture
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use rust_bert::bloom::{BloomConfig, BloomForCausalLM};
+    /// use rust_bert::Config;
+    /// use std::path::Path;
+    /// use tch::{nn, Device};
+    ///
+    /// let config_path = Path::new("path/to/config.json");
+    /// let device = Device::Cpu;
+    /// let p = nn::VarStore::new(device);
+    /// let config = BloomConfig::from_file(config_path);
+    /// let bloom_model = BloomForCausalLM::new(&p.root(), &config);
+    /// ```
+    pub fn new<'p, P>(p: P, config: &BloomConfig) -> BloomForCausalLM
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let transformer = BloomModel::new(p.borrow() / "transformer", config);
+
+        BloomForCausalLM { transformer }
+    }
+
+    /// Forward pass through the model
+    ///
+    /// # Arguments
+    ///
+    /// * `input_ids` - Optional input tensor of shape (*batch size*, *sequence_length*). This or `input_embeds` must be provided.
+    /// * `input_embeds` - Optional input tensor of shape (*batch size*, *sequence_length*, *embeddings dimension*). This or `input_ids` must be provided.
+    /// * `layer_states` - Optional Vector `Option<Vec<Option<LayerState>>>` of length *n_layer* containing the past keys and values of each layer.
+    /// * `attention_mask` - Optional attention mask of shape (*batch size*, *past_sequence_length + sequence_length*). Positions with a mask with value 0 will be masked.
+    /// * `train` - boolean flag to turn on/off the dropout layers in the model. Should be set to false for inference.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<BloomModelLMOutput, RustBertError>` containing:
+    ///   - `lm_logits` - `Tensor` of shape (*batch size*, *sequence_length*, *vocab_size*) representing the logits for each vocab item and position
+    ///   - `next_cache` - `Option<Vec<Option<LayerState>>>` of length *n_layer* containing the past content for the the attention layers
+    ///   - `all_hidden_states` - `Option<Vec<Tensor>>` of length *n_layer + 1* with shape (*batch size*, *sequence_length*, *hidden_size*)
+    ///   - `all_attentions` - `Option<Vec<Tensor>>` of length *n_layer* containing the attention weights for each layer
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use tch::{nn, Device, Tensor, no_grad, Kind};
+    /// # use rust_bert::Config;
+    /// # use std::path::Path;
+    /// # use tch::kind::Kind::{Int64, Double};
+    /// use rust_bert::bloom::{BloomConfig, BloomForCausalLM};
+    /// # let config_path = Path::new("path/to/config.json");
+    /// # let device = Device::Cpu;
+    /// # let vs = nn::VarStore::new(device);
+    /// # let config = BloomConfig::from_file(config_path);
+    /// # let bloom_model = BloomForCausalLM::new(&vs.root(), &config);
+    /// let (batch_size, sequence_length) = (64, 128);
+    /// let input_tensor = Tensor::rand(&[batch_size, sequence_length], (Int64, device));
+    /// let attention_mask = Tensor::ones(&[batch_size, sequence_length], (Int64, device));
+    ///
+    /// let model_output = no_grad(|| {
+    ///     bloom_model.forward_t(
+    ///         Some(&input_tensor),
+    ///         None,
+    ///         None,
+    ///         Some(&attention_mask),
+    ///         false,
+    ///     )
+    /// });
+    /// ```
+    pub fn forward_t(
+        &self,
+        input_ids: Option<&Tensor>,
+        input_embeds: Option<&Tensor>,
+        layer_states: Option<Vec<Option<LayerState>>>,
+        attention_mask: Option<&Tensor>,
+        train: bool,
+    ) -> Result<BloomModelLMOutput, RustBertError> {
+        let base_model_output = self.transformer.forward_t(
+            input_ids,
+            input_embeds,
+            layer_states,
+            attention_mask,
+            train,
+        )?;
+
+        let lm_logits = base_model_output
+            .hidden_states
+            .linear::<Tensor>(&self.transformer.word_embeddings.ws, None);
+
+        Ok(BloomModelLMOutput {
+            lm_logits,
+            next_cache: base_model_output.next_cache,
+            all_hidden_states: base_model_output.all_hidden_states,
+            all_attentions: base_model_output.all_attentions,
+        })
+    }
+}
+
+impl LMHeadModel for BloomForCausalLM {
+    fn forward_t(
+        &self,
+        input_ids: Option<&Tensor>,
+        layer_past: Cache,
+        attention_mask: Option<&Tensor>,
+        _token_type_ids: Option<&Tensor>,
+        _position_ids: Option<&Tensor>,
+        input_embeds: Option<&Tensor>,
+        _encoder_outputs: Option<&Tensor>,
+        _decoder_input_ids: Option<&Tensor>,
+        train: bool,
+    ) -> Result<LMModelOutput, RustBertError> {
+        let base_model_output = match layer_past {
+            Cache::BloomCache(layer_past) => {
+                self.forward_t(input_ids, input_embeds, layer_past, attention_mask, train)
+            }
+            Cache::None => self.forward_t(input_ids, input_embeds, None, attention_mask, train),
+            _ => {
+                return Err(RustBertError::ValueError(
+                    "Cache not compatible with BLOOM Model".into(),
+                ));
+            }
+        }?;
+
+        Ok(LMModelOutput {
+            lm_logits: base_model_output.lm_logits,
+            cache: Cache::BloomCache(base_model_output.next_cache),
+            loss: None,
+        })
+    }
+}
+
+/// Container for the BLOOM model output.
+pub struct BloomModelOutput {
+    /// Last hidden states from the model
+    pub hidden_states: Tensor,
+    /// Cached outputs of the model (attention layers keys and values) if the model is used for generation
+    pub next_cache: Option<Vec<Option<LayerState>>>,
+    /// Hidden states for all intermediate layers
+    pub all_hidden_states: Option<Vec<Tensor>>,
+    /// Attention weights for all intermediate layers
+    pub all_attentions: Option<Vec<Tensor>>,
+}
+
+///Container holding a BLOOM model with LM head output
+pub struct BloomModelLMOutput {
+    /// logits
+    pub lm_logits: Tensor,
+    /// Cached outputs of the model (attention layers keys and values) if the model is used for generation
+    pub next_cache: Option<Vec<Option<LayerState>>>,
+    /// Hidden states for all intermediate layers
+    pub all_hidden_states: Option<Vec<Tensor>>,
+    /// Attention weights for all intermediate layers
+    pub all_attentions: Option<Vec<Tensor>>,
+}
+
+/// # Language generation model based on the BLOOM architecture
+pub struct BloomGenerator {
+    model: BloomForCausalLM,
+    tokenizer: TokenizerOption,
+    var_store: nn::VarStore,
+    generate_config: GenerateConfig,
+    bos_token_id: Option<i64>,
+    eos_token_ids: Option<Vec<i64>>,
+    pad_token_id: Option<i64>,
+    is_encoder_decoder: bool,
+    vocab_size: i64,
+    decoder_start_id: Option<i64>,
+    max_position_embeddings: i64,
+}
+
+impl BloomGenerator {
+    /// Build a new `BloomGenerator`
+    ///
+    /// # Arguments
+    ///
+    /// * `generate_config` - `GenerateConfig` object containing the resource references (model, vocabulary, configuration), generation options and device placement (CPU/GPU). BLOOM uses a byte-level BPE tokenizer and requires a merges resource.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::bloom::BloomGenerator;
+    /// use rust_bert::pipelines::generation_utils::GenerateConfig;
+    /// use rust_bert::resources::LocalResource;
+    /// use std::path::PathBuf;
+    ///
+    /// let generate_config = GenerateConfig {
+    ///     model_resource: Box::new(LocalResource {
+    ///         local_path: PathBuf::from("path/to/rust_model.ot"),
+    ///     }),
+    ///     config_resource: Box::new(LocalResource {
+    ///         local_path: PathBuf::from("path/to/config.json"),
+    ///     }),
+    ///     vocab_resource: Box::new(LocalResource {
+    ///         local_path: PathBuf::from("path/to/vocab.json"),
+    ///     }),
+    ///     merges_resource: Some(Box::new(LocalResource {
+    ///         local_path: PathBuf::from("path/to/merges.txt"),
+    ///     })),
+    ///     max_length: Some(30),
+    ///     do_sample: true,
+    ///     ..Default::default()
+    /// };
+    /// let bloom_generator = BloomGenerator::new(generate_config)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn new(generate_config: GenerateConfig) -> Result<BloomGenerator, RustBertError> {
+        let vocab_path = generate_config.vocab_resource.get_local_path()?;
+        let merges_path = generate_config
+            .merges_resource
+            .as_ref()
+            .ok_or_else(|| {
+                RustBertError::InvalidConfigurationError(
+                    "BLOOM expects a merges resources to be provided".to_string(),
+                )
+            })?
+            .get_local_path()?;
+
+        let tokenizer = TokenizerOption::from_file(
+            ModelType::Bloom,
+            vocab_path.to_str().unwrap(),
+            Some(merges_path.to_str().unwrap()),
+            false,
+            None,
+            None,
+        )?;
+
+        Self::new_with_tokenizer(generate_config, tokenizer)
+    }
+
+    pub fn new_with_tokenizer(
+        generate_config: GenerateConfig,
+        tokenizer: TokenizerOption,
+    ) -> Result<BloomGenerator, RustBertError> {
+        let config_path = generate_config.config_resource.get_local_path()?;
+        let weights_path = generate_config.model_resource.get_local_path()?;
+        let device = generate_config.device;
+
+        generate_config.validate();
+        let mut var_store = nn::VarStore::new(device);
+        let config = BloomConfig::from_file(config_path);
+        let model = BloomForCausalLM::new(var_store.root(), &config);
+        var_store.load(weights_path)?;
+
+        let bos_token_id = config.bos_token_id.or_else(|| tokenizer.get_bos_id());
+        let eos_token_ids = config
+            .eos_token_id
+            .or_else(|| tokenizer.get_eos_id())
+            .map(|id| vec![id]);
+        let pad_token_id = config.pad_token_id.or_else(|| tokenizer.get_pad_id());
+        let is_encoder_decoder = false;
+        let vocab_size = config.vocab_size;
+        let decoder_start_id = None;
+        // ALiBi does not limit the number of positions
+        let max_position_embeddings = i64::MAX;
+
+        Ok(BloomGenerator {
+            model,
+            tokenizer,
+            var_store,
+            generate_config,
+            bos_token_id,
+            eos_token_ids,
+            pad_token_id,
+            is_encoder_decoder,
+            vocab_size,
+            decoder_start_id,
+            max_position_embeddings,
+        })
+    }
+}
+
+impl PrivateLanguageGenerator<BloomForCausalLM, Gpt2Vocab, Gpt2Tokenizer> for BloomGenerator {
+    fn get_model(&self) -> &BloomForCausalLM {
+        &self.model
+    }
+    fn _get_tokenizer(&self) -> &TokenizerOption {
+        &self.tokenizer
+    }
+    fn get_var_store(&self) -> &nn::VarStore {
+        &self.var_store
+    }
+    fn get_var_store_mut(&mut self) -> &mut nn::VarStore {
+        &mut self.var_store
+    }
+    fn get_config(&self) -> &GenerateConfig {
+        &self.generate_config
+    }
+    fn get_bos_id(&self) -> Option<i64> {
+        self.bos_token_id
+    }
+    fn get_eos_ids(&self) -> Option<&Vec<i64>> {
+        self.eos_token_ids.as_ref()
+    }
+    fn get_pad_id(&self) -> Option<i64> {
+        self.pad_token_id
+    }
+    fn is_encoder_decoder(&self) -> bool {
+        self.is_encoder_decoder
+    }
+    fn get_vocab_size(&self) -> i64 {
+        self.vocab_size
+    }
+    fn get_decoder_start_id(&self) -> Option<i64> {
+        self.decoder_start_id
+    }
+    fn get_max_positions_embeddings(&self) -> i64 {
+        self.max_position_embeddings
+    }
+
+    fn prepare_inputs_for_generation<'a>(
+        &self,
+        input_ids: Tensor,
+        _encoder_outputs: Option<&'a Tensor>,
+        past: Cache,
+        attention_mask: Tensor,
+    ) -> PreparedInput<'a> {
+        match past {
+            Cache::BloomCache(past) => {
+                if past.is_some() {
+                    PreparedInput {
+                        prepared_input: Some(input_ids.select(1, -1).unsqueeze(-1)),
+                        prepared_attention_mask: Some(attention_mask),
+                        prepared_encoder_output: None,
+                        prepared_decoder_input: None,
+                        prepared_position_ids: None,
+                        prepared_past: Cache::BloomCache(past),
+                    }
+                } else {
+                    PreparedInput {
+                        prepared_input: Some(input_ids),
+                        prepared_attention_mask: Some(attention_mask),
+                        prepared_encoder_output: None,
+                        prepared_decoder_input: None,
+                        prepared_position_ids: None,
+                        prepared_past: Cache::BloomCache(None),
+                    }
+                }
+            }
+            Cache::None => PreparedInput {
+                prepared_input: Some(input_ids),
+                prepared_attention_mask: Some(attention_mask),
+                prepared_encoder_output: None,
+                prepared_decoder_input: None,
+                prepared_position_ids: None,
+                prepared_past: Cache::BloomCache(None),
+            },
+            _ => panic!("Cache type incompatible with BLOOM"),
+        }
+    }
+
+    fn reorder_cache(
+        &self,
+        past: &mut Cache,
+        _encoder_outputs: Option<Tensor>,
+        beam_indices: &Tensor,
+    ) -> Option<Tensor> {
+        match past {
+            Cache::BloomCache(cached_decoder_state) => match cached_decoder_state {
+                Some(old_cache) => {
+                    for layer_state in old_cache.iter_mut().flatten() {
+                        layer_state.reorder_cache(beam_indices)
+                    }
+                    None
+                }
+                None => None,
+            },
+            Cache::None => None,
+            _ => {
+                panic!("Invalid cache for BLOOM model");
+            }
+        }
+    }
+}
+
+impl LanguageGenerator<BloomForCausalLM, Gpt2Vocab, Gpt2Tokenizer> for BloomGenerator {}
@@ -0,0 +1,69 @@
+//! # BLOOM
+//!
+//! Implementation of the BLOOM language model ([BLOOM: A 176B-Parameter Open-Access Multilingual Language Model](https://arxiv.org/abs/2211.05100) BigScience Workshop, 2022).
+//! The base model is implemented in the `bloom_model::BloomModel` struct. A causal language modeling head is implemented in `bloom_model::BloomForCausalLM`.
+//! The model does not use position embeddings: the attention scores are biased by the distance between tokens (ALiBi),
+//! and the word embeddings are normalized by an additional layer normalization.
+//!
+//! # Model set-up and pre-trained weights loading
+//!
+//! All models expect the following resources:
+//! - Configuration file expected to have a structure following the [Transformers library](https://github.com/huggingface/transformers)
+//! - Model weights are expected to have a structure and parameter names following the [Transformers library](https://github.com/huggingface/transformers). A conversion using the Python utility scripts is required to convert the `.bin` weights to the `.ot` format.
+//! - `GPT2Tokenizer` using a `vocab.json` vocabulary and a `merges.txt` merges file (both can be extracted from the `tokenizer.json` file of the checkpoint).
+//!   The pre-tokenization follows the GPT2 rules, which can split some words differently from the reference tokenizer.
+//!
+//! No pretrained checkpoints are registered as remote resources: the weights have to be converted and loaded from local resources.
+//!
+//! ```no_run
+//! use rust_bert::pipelines::common::ModelType;
+//! use rust_bert::pipelines::text_generation::{TextGenerationConfig, TextGenerationModel};
+//! use rust_bert::resources::LocalResource;
+//! use std::path::PathBuf;
+//! use tch::Device;
+//!
+//! fn main() -> anyhow::Result<()> {
+//!     let config_resource = Box::new(LocalResource {
+//!         local_path: PathBuf::from("path/to/config.json"),
+//!     });
+//!     let vocab_resource = Box::new(LocalResource {
+//!         local_path: PathBuf::from("path/to/vocab.json"),
+//!     });
+//!     let merges_resource = Box::new(LocalResource {
+//!         local_path: PathBuf::from("path/to/merges.txt"),
+//!     });
+//!     let model_resource = Box::new(LocalResource {
+//!         local_path: PathBuf::from("path/to/rust_model.ot"),
+//!     });
+//!
+//!     let text_generation_config = TextGenerationConfig {
+//!         model_type: ModelType::Bloom,
+//!         model_resource,
+//!         config_resource,
+//!         vocab_resource,
+//!         merges_resource: Some(merges_resource),
+//!         max_length: Some(64),
+//!         do_sample: false,
+//!         device: Device::cuda_if_available(),
+//!         ..Default::default()
+//!     };
+//!     let model = TextGenerationModel::new(text_generation_config)?;
+//!
+//!     let output = model.generate(&["Il était une fois"], None);
+//!
+//!     for sentence in output {
+//!         println!("{}", sentence);
+//!     }
+//!
+//!     Ok(())
+//! }
+//! ```
+
+mod attention;
+mod bloom_model;
+mod decoder;
+
+pub use attention::LayerState;
+pub use bloom_model::{
+    BloomConfig, BloomForCausalLM, BloomGenerator, BloomModel, BloomModelLMOutput, BloomModelOutput,
+};
@@ -0,0 +1,624 @@
+// Copyright 2021 The OpenAI Team Authors and The HuggingFace Team. All rights reserved.
+// Copyright 2023 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::clip::encoder::{causal_attention_mask, ClipEncoder, ClipEncoderConfig};
+use crate::common::embeddings::process_ids_embeddings_pair;
+use crate::{Activation, Config, RustBertError};
+use serde::{Deserialize, Serialize};
+use std::borrow::Borrow;
+use tch::nn::{ConvConfig, Init};
+use tch::{nn, Kind, Tensor};
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+/// # CLIP text encoder configuration
+/// Defines the CLIP text transformer architecture (e.g. number of layers, hidden layer size...)
+pub struct ClipTextConfig {
+    pub vocab_size: i64,
+    pub hidden_size: i64,
+    pub intermediate_size: i64,
+    pub num_hidden_layers: i64,
+    pub num_attention_heads: i64,
+    pub max_position_embeddings: i64,
+    pub hidden_act: Activation,
+    pub layer_norm_eps: f64,
+    pub attention_dropout: f64,
+    pub pad_token_id: Option<i64>,
+    pub bos_token_id: Option<i64>,
+    pub eos_token_id: Option<i64>,
+    pub output_attentions: Option<bool>,
+    pub output_hidden_states: Option<bool>,
+}
+
+impl Default for ClipTextConfig {
+    fn default() -> Self {
+        ClipTextConfig {
+            vocab_size: 49408,
+            hidden_size: 512,
+            intermediate_size: 2048,
+            num_hidden_layers: 12,
+            num_attention_heads: 8,
+            max_position_embeddings: 77,
+            hidden_act: Activation::quick_gelu,
+            layer_norm_eps: 1e-5,
+            attention_dropout: 0.0,
+            pad_token_id: Some(1),
+            bos_token_id: Some(49406),
+            eos_token_id: Some(49407),
+            output_attentions: None,
+            output_hidden_states: None,
+        }
+    }
+}
+
+impl From<&ClipTextConfig> for ClipEncoderConfig {
+    fn from(config: &ClipTextConfig) -> Self {
+        ClipEncoderConfig {
+            hidden_size: config.hidden_size,
+            intermediate_size: config.intermediate_size,
+            num_hidden_layers: config.num_hidden_layers,
+            num_attention_heads: config.num_attention_heads,
+            hidden_act: config.hidden_act,
+            layer_norm_eps: config.layer_norm_eps,
+            attention_dropout: config.attention_dropout,
+            output_attentions: config.output_attentions.unwrap_or(false),
+            output_hidden_states: config.output_hidden_states.unwrap_or(false),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+/// # CLIP vision encoder configuration
+/// Defines the CLIP vision transformer (ViT) architecture (e.g. number of layers, hidden layer size, patch size...)
+pub struct ClipVisionConfig {
+    pub hidden_size: i64,
+    pub intermediate_size: i64,
+    pub num_hidden_layers: i64,
+    pub num_attention_heads: i64,
+    pub num_channels: i64,
+    pub image_size: i64,
+    pub patch_size: i64,
+    pub hidden_act: Activation,
+    pub layer_norm_eps: f64,
+    pub attention_dropout: f64,
+    pub output_attentions: Option<bool>,
+    pub output_hidden_states: Option<bool>,
+}
+
+impl Default for ClipVisionConfig {
+    fn default() -> Self {
+        ClipVisionConfig {
+            hidden_size: 768,
+            intermediate_size: 3072,
+            num_hidden_layers: 12,
+            num_attention_heads: 12,
+            num_channels: 3,
+            image_size: 224,
+            patch_size: 32,
+            hidden_act: Activation::quick_gelu,
+            layer_norm_eps: 1e-5,
+            attention_dropout: 0.0,
+            output_attentions: None,
+            output_hidden_states: None,
+        }
+    }
+}
+
+impl From<&ClipVisionConfig> for ClipEncoderConfig {
+    fn from(config: &ClipVisionConfig) -> Self {
+        ClipEncoderConfig {
+            hidden_size: config.hidden_size,
+            intermediate_size: config.intermediate_size,
+            num_hidden_layers: config.num_hidden_layers,
+            num_attention_heads: config.num_attention_heads,
+            hidden_act: config.hidden_act,
+            layer_norm_eps: config.layer_norm_eps,
+            attention_dropout: config.attention_dropout,
+            output_attentions: config.output_attentions.unwrap_or(false),
+            output_hidden_states: config.output_hidden_states.unwrap_or(false),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+/// # CLIP model configuration
+/// Defines the CLIP model architecture: text and vision encoder configurations and dimension of the joint embedding space
+pub struct ClipConfig {
+    #[serde(default)]
+    pub text_config: ClipTextConfig,
+    #[serde(default)]
+    pub vision_config: ClipVisionConfig,
+    pub projection_dim: i64,
+    pub logit_scale_init_value: f64,
+}
+
+impl Config for ClipConfig {}
+
+impl Default for ClipConfig {
+    fn default() -> Self {
+        ClipConfig {
+            text_config: Default::default(),
+            vision_config: Default::default(),
+            projection_dim: 512,
+            logit_scale_init_value: 2.6592,
+        }
+    }
+}
+
+/// # CLIP text transformer
+/// Token and absolute position embeddings followed by a causal transformer encoder. The pooled output is the
+/// final hidden state of the end of text token.
+pub struct ClipTextTransformer {
+    token_embedding: nn::Embedding,
+    position_embedding: nn::Embedding,
+    encoder: ClipEncoder,
+    final_layer_norm: nn::LayerNorm,
+    eos_token_id: i64,
+}
+
+impl ClipTextTransformer {
+    /// Build a new `ClipTextTransformer`
+    ///
+    /// # Arguments
+    ///
+    /// * `p` - Variable store path for the root of the text transformer
+    /// * `config` - `ClipTextConfig` object defining the model architecture
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use rust_bert::clip::{ClipTextConfig, ClipTextTransformer};
+    /// use tch::{nn, Device};
+    ///
+    /// let device = Device::Cpu;
+    /// let p = nn::VarStore::new(device);
+    /// let config = ClipTextConfig::default();
+    /// let text_model = ClipTextTransformer::new(&p.root() / "text_model", &config);
+    /// ```
+    pub fn new<'p, P>(p: P, config: &ClipTextConfig) -> ClipTextTransformer
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let p = p.borrow();
+
+        let p_embeddings = p / "embeddings";
+        let token_embedding = nn::embedding(
+            &p_embeddings / "token_embedding",
+            config.vocab_size,
+            config.hidden_size,
+            Default::default(),
+        );
+        let position_embedding = nn::embedding(
+            &p_embeddings / "position_embedding",
+            config.max_position_embeddings,
+            config.hidden_size,
+            Default::default(),
+        );
+        let encoder = ClipEncoder::new(p / "encoder", &config.into());
+        let final_layer_norm = nn::layer_norm(
+            p / "final_layer_norm",
+            vec![config.hidden_size],
+            nn::LayerNormConfig {
+                eps: config.layer_norm_eps,
+                ..Default::default()
+            },
+        );
+
+        ClipTextTransformer {
+            token_embedding,
+            position_embedding,
+            encoder,
+            final_layer_norm,
+            eos_token_id: config.eos_token_id.unwrap_or(49407),
+        }
+    }
+
+    /// Forward pass through the text transformer
+    ///
+    /// # Arguments
+    ///
+    /// * `input_ids` - Optional input tensor of shape (*batch size*, *sequence_length*). If None, pre-computed embeddings must be provided (see `input_embeds`)
+    /// * `attention_mask` - Optional mask of shape (*batch size*, *sequence_length*). Masked position have value 0, non-masked value 1. If None set to 1
+    /// * `input_embeds` - Optional pre-computed input embeddings of shape (*batch size*, *sequence_length*, *hidden_size*). If None, input ids must be provided (see `input_ids`)
+    /// * `train` - boolean flag to turn on/off the dropout layers in the model. Should be set to false for inference.
+    ///
+    /// # Returns
+    ///
+    /// * `ClipTransformerOutput` containing:
+    ///   - `hidden_state` - `Tensor` of shape (*batch size*, *sequence_length*, *hidden_size*)
+    ///   - `pooled_output` - `Tensor` of shape (*batch size*, *hidden_size*) of the end of text token hidden states
+    ///   - `all_hidden_states` - `Option<Vec<Tensor>>` of length *num_hidden_layers + 1* with shape (*batch size*, *sequence_length*, *hidden_size*)
+    ///   - `all_attentions` - `Option<Vec<Tensor>>` of length *num_hidden_layers* with shape (*batch size*, *num_heads*, *sequence_length*, *sequence_length*)
+    pub fn forward_t(
+        &self,
+        input_ids: Option<&Tensor>,
+        attention_mask: Option<&Tensor>,
+        input_embeds: Option<&Tensor>,
+        train: bool,
+    ) -> Result<ClipTransformerOutput, RustBertError> {
+        let (calc_input_embeddings, input_shape, device) =
+            process_ids_embeddings_pair(input_ids, input_embeds, &self.token_embedding)?;
+        let input_embeddings =
+            input_embeds.unwrap_or_else(|| calc_input_embeddings.as_ref().unwrap());
+
+        let position_ids = Tensor::arange(input_shape[1], (Kind::Int64, device)).unsqueeze(0);
+        let hidden_states = input_embeddings + position_ids.apply(&self.position_embedding);
+
+        let attention_mask = causal_attention_mask(attention_mask, &hidden_states);
+        let encoder_output = self
+            .encoder
+            .forward_t(&hidden_states, Some(&attention_mask), train);
+        let hidden_state = encoder_output.hidden_state.apply(&self.final_layer_norm);
+
+        // The pooled output is taken at the end of text token. Legacy configurations (`eos_token_id` 2)
+        // rely on the end of text token having the largest id in the vocabulary.
+        let eos_positions = match input_ids {
+            Some(input_ids) if self.eos_token_id == 2 => input_ids.argmax(-1, false),
+            Some(input_ids) => input_ids
+                .eq(self.eos_token_id)
+                .to_kind(Kind::Int64)
+                .argmax(-1, false),
+            None => Tensor::full(&[input_shape[0]], input_shape[1] - 1, (Kind::Int64, device)),
+        };
+        let pooled_output = hidden_state
+            .gather(
+                1,
+                &eos_positions
+                    .view([-1, 1, 1])
+                    .expand(&[-1, 1, hidden_state.size()[2]], true),
+                false,
+            )
+            .squeeze_dim(1);
+
+        Ok(ClipTransformerOutput {
+            hidden_state,
+            pooled_output,
+            all_hidden_states: encoder_output.all_hidden_states,
+            all_attentions: encoder_output.all_attentions,
+        })
+    }
+}
+
+/// # CLIP vision transformer
+/// Vision transformer (ViT) encoding non-overlapping image patches, with a learned class embedding prepended to
+/// the patch embeddings. The pooled output is the normalized final hidden state of the class embedding.
+pub struct ClipVisionTransformer {
+    class_embedding: Tensor,
+    patch_embedding: nn::Conv2D,
+    position_embedding: nn::Embedding,
+    pre_layernorm: nn::LayerNorm,
+    encoder: ClipEncoder,
+    post_layernorm: nn::LayerNorm,
+}
+
+impl ClipVisionTransformer {
+    /// Build a new `ClipVisionTransformer`
+    ///
+    /// # Arguments
+    ///
+    /// * `p` - Variable store path for the root of the vision transformer
+    /// * `config` - `ClipVisionConfig` object defining the model architecture
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use rust_bert::clip::{ClipVisionConfig, ClipVisionTransformer};
+    /// use tch::{nn, Device};
+    ///
+    /// let device = Device::Cpu;
+    /// let p = nn::VarStore::new(device);
+    /// let config = ClipVisionConfig::default();
+    /// let vision_model = ClipVisionTransformer::new(&p.root() / "vision_model", &config);
+    /// ```
+    pub fn new<'p, P>(p: P, config: &ClipVisionConfig) -> ClipVisionTransformer
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let p = p.borrow();
+
+        let p_embeddings = p / "embeddings";
+        let class_embedding =
+            p_embeddings.var("class_embedding", &[config.hidden_size], Init::Const(0.));
+        let patch_embedding = nn::conv2d(
+            &p_embeddings / "patch_embedding",
+            config.num_channels,
+            config.hidden_size,
+            config.patch_size,
+            ConvConfig {
+                stride: config.patch_size,
+                bias: false,
+                ..Default::default()
+            },
+        );
+        let num_patches = (config.image_size / config.patch_size).pow(2);
+        let position_embedding = nn::embedding(
+            &p_embeddings / "position_embedding",
+            num_patches + 1,
+            config.hidden_size,
+            Default::default(),
+        );
+
+        let layer_norm_config = nn::LayerNormConfig {
+            eps: config.layer_norm_eps,
+            ..Default::default()
+        };
+        let pre_layernorm = nn::layer_norm(
+            p / "pre_layrnorm",
+            vec![config.hidden_size],
+            layer_norm_config,
+        );
+        let encoder = ClipEncoder::new(p / "encoder", &config.into());
+        let post_layernorm = nn::layer_norm(
+            p / "post_layernorm",
+            vec![config.hidden_size],
+            layer_norm_config,
+        );
+
+        ClipVisionTransformer {
+            class_embedding,
+            patch_embedding,
+            position_embedding,
+            pre_layernorm,
+            encoder,
+            post_layernorm,
+        }
+    }
+
+    /// Forward pass through the vision transformer
+    ///
+    /// # Arguments
+    ///
+    /// * `pixel_values` - Preprocessed images of shape (*batch size*, *num_channels*, *image_size*, *image_size*) (see `ClipImageProcessor`)
+    /// * `train` - boolean flag to turn on/off the dropout layers in the model. Should be set to false for inference.
+    ///
+    /// # Returns
+    ///
+    /// * `ClipTransformerOutput` containing:
+    ///   - `hidden_state` - `Tensor` of shape (*batch size*, *num_patches + 1*, *hidden_size*)
+    ///   - `pooled_output` - `Tensor` of shape (*batch size*, *hidden_size*) of the normalized class embedding hidden states
+    ///   - `all_hidden_states` - `Option<Vec<Tensor>>` of length *num_hidden_layers + 1* with shape (*batch size*, *num_patches + 1*, *hidden_size*)
+    ///   - `all_attentions` - `Option<Vec<Tensor>>` of length *num_hidden_layers* with shape (*batch size*, *num_heads*, *num_patches + 1*, *num_patches + 1*)
+    pub fn forward_t(
+        &self,
+        pixel_values: &Tensor,
+        train: bool,
+    ) -> Result<ClipTransformerOutput, RustBertError> {
+        if pixel_values.dim() != 4 {
+            return Err(RustBertError::ValueError(format!(
+                "Pixel values must have shape (batch size, channels, height, width), got {:?}",
+                pixel_values.size()
+            )));
+        }
+        let batch_size = pixel_values.size()[0];
+
+        let patch_embeddings = pixel_values
+            .to_kind(self.class_embedding.kind())
+            .apply(&self.patch_embedding)
+            .flatten(2, -1)
+            .transpose(1, 2);
+        let class_embeddings = self
+            .class_embedding
+            .view([1, 1, -1])
+            .expand(&[batch_size, 1, -1], true);
+        let embeddings = Tensor::cat(&[class_embeddings, patch_embeddings], 1);
+        let position_ids = Tensor::arange(embeddings.size()[1], (Kind::Int64, embeddings.device()));
+        let hidden_states = (embeddings
+            + position_ids.apply(&self.position_embedding).unsqueeze(0))
+        .apply(&self.pre_layernorm);
+
+        let encoder_output = self.encoder.forward_t(&hidden_states, None, train);
+        let pooled_output = encoder_output
+            .hidden_state
+            .select(1, 0)
+            .apply(&self.post_layernorm);
+
+        Ok(ClipTransformerOutput {
+            hidden_state: encoder_output.hidden_state,
+            pooled_output,
+            all_hidden_states: encoder_output.all_hidden_states,
+            all_attentions: encoder_output.all_attentions,
+        })
+    }
+}
+
+/// # CLIP model
+/// Contrastive language-image model made of a text and a vision transformers, and linear projections of their
+/// pooled outputs into a joint embedding space:
+/// - `text_model`: `ClipTextTransformer` text encoder
+/// - `vision_model`: `ClipVisionTransformer` image encoder
+/// - `text_projection`, `visual_projection`: projections to the joint embedding space
+/// - `logit_scale`: learned temperature scaling the similarity logits
+pub struct ClipModel {
+    text_model: ClipTextTransformer,
+    vision_model: ClipVisionTransformer,
+    text_projection: nn::Linear,
+    visual_projection: nn::Linear,
+    logit_scale: Tensor,
+}
+
+impl ClipModel {
+    /// Build a new `ClipModel`
+    ///
+    /// # Arguments
+    ///
+    /// * `p` - Variable store path for the root of the CLIP model
+    /// * `config` - `ClipConfig` object defining the model architecture
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use rust_bert::clip::{ClipConfig, ClipModel};
+    /// use rust_bert::Config;
+    /// use std::path::Path;
+    /// use tch::{nn, Device};
+    ///
+    /// let config_path = Path::new("path/to/config.json");
+    /// let device = Device::Cpu;
+    /// let p = nn::VarStore::new(device);
+    /// let config = ClipConfig::from_file(config_path);
+    /// let clip_model = ClipModel::new(&p.root(), &config);
+    /// ```
+    pub fn new<'p, P>(p: P, config: &ClipConfig) -> ClipModel
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let p = p.borrow();
+
+        let text_model = ClipTextTransformer::new(p / "text_model", &config.text_config);
+        let vision_model = ClipVisionTransformer::new(p / "vision_model", &config.vision_config);
+        let linear_config = nn::LinearConfig {
+            bias: false,
+            ..Default::default()
+        };
+        let text_projection = nn::linear(
+            p / "text_projection",
+            config.text_config.hidden_size,
+            config.projection_dim,
+            linear_config,
+        );
+        let visual_projection = nn::linear(
+            p / "visual_projection",
+            config.vision_config.hidden_size,
+            config.projection_dim,
+            linear_config,
+        );
+        let logit_scale = p.var(
+            "logit_scale",
+            &[],
+            Init::Const(config.logit_scale_init_value),
+        );
+
+        ClipModel {
+            text_model,
+            vision_model,
+            text_projection,
+            visual_projection,
+            logit_scale,
+        }
+    }
+
+    /// Computes the (unnormalized) text embeddings in the joint embedding space
+    ///
+    /// # Arguments
+    ///
+    /// * `input_ids` - Input tensor of shape (*batch size*, *sequence_length*)
+    /// * `attention_mask` - Optional mask of shape (*batch size*, *sequence_length*). Masked position have value 0, non-masked value 1. If None set to 1
+    /// * `train` - boolean flag to turn on/off the dropout layers in the model. Should be set to false for inference.
+    ///
+    /// # Returns
+    ///
+    /// * `Tensor` of shape (*batch size*, *projection_dim*)
+    pub fn get_text_features(
+        &self,
+        input_ids: &Tensor,
+        attention_mask: Option<&Tensor>,
+        train: bool,
+    ) -> Result<Tensor, RustBertError> {
+        Ok(self
+            .text_model
+            .forward_t(Some(input_ids), attention_mask, None, train)?
+            .pooled_output
+            .apply(&self.text_projection))
+    }
+
+    /// Computes the (unnormalized) image embeddings in the joint embedding space
+    ///
+    /// # Arguments
+    ///
+    /// * `pixel_values` - Preprocessed images of shape (*batch size*, *num_channels*, *image_size*, *image_size*)
+    /// * `train` - boolean flag to turn on/off the dropout layers in the model. Should be set to false for inference.
+    ///
+    /// # Returns
+    ///
+    /// * `Tensor` of shape (*batch size*, *projection_dim*)
+    pub fn get_image_features(
+        &self,
+        pixel_values: &Tensor,
+        train: bool,
+    ) -> Result<Tensor, RustBertError> {
+        Ok(self
+            .vision_model
+            .forward_t(pixel_values, train)?
+            .pooled_output
+            .apply(&self.visual_projection))
+    }
+
+    /// Forward pass through the model, computing the scaled cosine similarities between all images and texts
+    ///
+    /// # Arguments
+    ///
+    /// * `input_ids` - Input tensor of shape (*batch size*, *sequence_length*)
+    /// * `attention_mask` - Optional mask of shape (*batch size*, *sequence_length*). Masked position have value 0, non-masked value 1. If None set to 1
+    /// * `pixel_values` - Preprocessed images of shape (*num_images*, *num_channels*, *image_size*, *image_size*)
+    /// * `train` - boolean flag to turn on/off the dropout layers in the model. Should be set to false for inference.
+    ///
+    /// # Returns
+    ///
+    /// * `ClipModelOutput` containing:
+    ///   - `logits_per_image` - `Tensor` of shape (*num_images*, *batch size*)
+    ///   - `logits_per_text` - `Tensor` of shape (*batch size*, *num_images*)
+    ///   - `text_embeds` - normalized text embeddings of shape (*batch size*, *projection_dim*)
+    ///   - `image_embeds` - normalized image embeddings of shape (*num_images*, *projection_dim*)
+    pub fn forward_t(
+        &self,
+        input_ids: &Tensor,
+        attention_mask: Option<&Tensor>,
+        pixel_values: &Tensor,
+        train: bool,
+    ) -> Result<ClipModelOutput, RustBertError> {
+        let text_embeds = normalize(&self.get_text_features(input_ids, attention_mask, train)?);
+        let image_embeds = normalize(&self.get_image_features(pixel_values, train)?);
+
+        let logits_per_text =
+            text_embeds.matmul(&image_embeds.transpose(0, 1)) * self.logit_scale.exp();
+        let logits_per_image = logits_per_text.transpose(0, 1);
+
+        Ok(ClipModelOutput {
+            logits_per_image,
+            logits_per_text,
+            text_embeds,
+            image_embeds,
+        })
+    }
+}
+
+pub(crate) fn normalize(embeddings: &Tensor) -> Tensor {
+    embeddings
+        / embeddings
+            .norm_scalaropt_dim(2, &[-1], true)
+            .clamp_min(1e-12)
+}
+
+/// Container for the CLIP text or vision transformer output.
+pub struct ClipTransformerOutput {
+    /// Last hidden states of the transformer
+    pub hidden_state: Tensor,
+    /// Pooled output (end of text token for the text transformer, class embedding for the vision transformer)
+    pub pooled_output: Tensor,
+    /// Hidden states for all intermediate layers
+    pub all_hidden_states: Option<Vec<Tensor>>,
+    /// Attention weights for all intermediate layers
+    pub all_attentions: Option<Vec<Tensor>>,
+}
+
+/// Container for the CLIP model output.
+pub struct ClipModelOutput {
+    /// Scaled cosine similarities of each image with each text
+    pub logits_per_image: Tensor,
+    /// Scaled cosine similarities of each text with each image
+    pub logits_per_text: Tensor,
+    /// Normalized text embeddings
+    pub text_embeds: Tensor,
+    /// Normalized image embeddings
+    pub image_embeds: Tensor,
+}
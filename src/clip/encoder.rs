@@ -0,0 +1,302 @@
+// Copyright 2021 The OpenAI Team Authors and The HuggingFace Team. All rights reserved.
+// Copyright 2023 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::common::activations::TensorFunction;
+use crate::common::dropout::Dropout;
+use crate::Activation;
+use std::borrow::{Borrow, BorrowMut};
+use tch::{nn, Kind, Tensor};
+
+/// Architecture parameters shared by the CLIP text and vision encoders
+pub(crate) struct ClipEncoderConfig {
+    pub hidden_size: i64,
+    pub intermediate_size: i64,
+    pub num_hidden_layers: i64,
+    pub num_attention_heads: i64,
+    pub hidden_act: Activation,
+    pub layer_norm_eps: f64,
+    pub attention_dropout: f64,
+    pub output_attentions: bool,
+    pub output_hidden_states: bool,
+}
+
+pub struct ClipAttention {
+    q_proj: nn::Linear,
+    k_proj: nn::Linear,
+    v_proj: nn::Linear,
+    out_proj: nn::Linear,
+    dropout: Dropout,
+    num_heads: i64,
+    head_dim: i64,
+    scaling: f64,
+    output_attentions: bool,
+}
+
+impl ClipAttention {
+    pub(crate) fn new<'p, P>(p: P, config: &ClipEncoderConfig) -> ClipAttention
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let p = p.borrow();
+
+        let embed_dim = config.hidden_size;
+        let q_proj = nn::linear(p / "q_proj", embed_dim, embed_dim, Default::default());
+        let k_proj = nn::linear(p / "k_proj", embed_dim, embed_dim, Default::default());
+        let v_proj = nn::linear(p / "v_proj", embed_dim, embed_dim, Default::default());
+        let out_proj = nn::linear(p / "out_proj", embed_dim, embed_dim, Default::default());
+
+        let head_dim = embed_dim / config.num_attention_heads;
+
+        ClipAttention {
+            q_proj,
+            k_proj,
+            v_proj,
+            out_proj,
+            dropout: Dropout::new(config.attention_dropout),
+            num_heads: config.num_attention_heads,
+            head_dim,
+            scaling: (head_dim as f64).powf(-0.5),
+            output_attentions: config.output_attentions,
+        }
+    }
+
+    fn shape(&self, x: Tensor, bs: i64) -> Tensor {
+        x.view((bs, -1, self.num_heads, self.head_dim))
+            .transpose(1, 2)
+    }
+
+    pub fn forward_t(
+        &self,
+        hidden_states: &Tensor,
+        attention_mask: Option<&Tensor>,
+        train: bool,
+    ) -> (Tensor, Option<Tensor>) {
+        let (bs, seq_length, embed_dim) = hidden_states.size3().unwrap();
+
+        let query = self.shape(hidden_states.apply(&self.q_proj) * self.scaling, bs);
+        let key = self.shape(hidden_states.apply(&self.k_proj), bs);
+        let value = self.shape(hidden_states.apply(&self.v_proj), bs);
+
+        let mut attention_scores = query.matmul(&key.transpose(-1, -2));
+        if let Some(attention_mask) = attention_mask {
+            attention_scores = attention_scores + attention_mask;
+        }
+        let attention_weights = attention_scores.softmax(-1, attention_scores.kind());
+        let attention_probs = attention_weights.apply_t(&self.dropout, train);
+
+        let output = attention_probs
+            .matmul(&value)
+            .transpose(1, 2)
+            .reshape(&[bs, seq_length, embed_dim])
+            .apply(&self.out_proj);
+
+        let attention_weights = if self.output_attentions {
+            Some(attention_weights)
+        } else {
+            None
+        };
+        (output, attention_weights)
+    }
+}
+
+pub struct ClipMLP {
+    fc1: nn::Linear,
+    fc2: nn::Linear,
+    activation: TensorFunction,
+}
+
+impl ClipMLP {
+    pub(crate) fn new<'p, P>(p: P, config: &ClipEncoderConfig) -> ClipMLP
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let p = p.borrow();
+
+        let fc1 = nn::linear(
+            p / "fc1",
+            config.hidden_size,
+            config.intermediate_size,
+            Default::default(),
+        );
+        let fc2 = nn::linear(
+            p / "fc2",
+            config.intermediate_size,
+            config.hidden_size,
+            Default::default(),
+        );
+        let activation = config.hidden_act.get_function();
+
+        ClipMLP {
+            fc1,
+            fc2,
+            activation,
+        }
+    }
+
+    pub fn forward(&self, hidden_states: &Tensor) -> Tensor {
+        self.activation.get_fn()(&hidden_states.apply(&self.fc1)).apply(&self.fc2)
+    }
+}
+
+/// # CLIP encoder layer
+/// Pre-normalization transformer layer shared by the text and vision encoders.
+pub struct ClipEncoderLayer {
+    self_attn: ClipAttention,
+    layer_norm1: nn::LayerNorm,
+    mlp: ClipMLP,
+    layer_norm2: nn::LayerNorm,
+}
+
+impl ClipEncoderLayer {
+    pub(crate) fn new<'p, P>(p: P, config: &ClipEncoderConfig) -> ClipEncoderLayer
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let p = p.borrow();
+
+        let layer_norm_config = nn::LayerNormConfig {
+            eps: config.layer_norm_eps,
+            ..Default::default()
+        };
+        let self_attn = ClipAttention::new(p / "self_attn", config);
+        let layer_norm1 = nn::layer_norm(
+            p / "layer_norm1",
+            vec![config.hidden_size],
+            layer_norm_config,
+        );
+        let mlp = ClipMLP::new(p / "mlp", config);
+        let layer_norm2 = nn::layer_norm(
+            p / "layer_norm2",
+            vec![config.hidden_size],
+            layer_norm_config,
+        );
+
+        ClipEncoderLayer {
+            self_attn,
+            layer_norm1,
+            mlp,
+            layer_norm2,
+        }
+    }
+
+    pub fn forward_t(
+        &self,
+        hidden_states: &Tensor,
+        attention_mask: Option<&Tensor>,
+        train: bool,
+    ) -> (Tensor, Option<Tensor>) {
+        let (attention_output, attention_weights) = self.self_attn.forward_t(
+            &hidden_states.apply(&self.layer_norm1),
+            attention_mask,
+            train,
+        );
+        let hidden_states = hidden_states + attention_output;
+        let output = self.mlp.forward(&hidden_states.apply(&self.layer_norm2)) + hidden_states;
+        (output, attention_weights)
+    }
+}
+
+pub struct ClipEncoder {
+    layers: Vec<ClipEncoderLayer>,
+    output_attentions: bool,
+    output_hidden_states: bool,
+}
+
+impl ClipEncoder {
+    pub(crate) fn new<'p, P>(p: P, config: &ClipEncoderConfig) -> ClipEncoder
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let p_layers = p.borrow() / "layers";
+        let layers = (0..config.num_hidden_layers)
+            .map(|layer_index| ClipEncoderLayer::new(&p_layers / layer_index, config))
+            .collect();
+
+        ClipEncoder {
+            layers,
+            output_attentions: config.output_attentions,
+            output_hidden_states: config.output_hidden_states,
+        }
+    }
+
+    pub fn forward_t(
+        &self,
+        input_embeddings: &Tensor,
+        attention_mask: Option<&Tensor>,
+        train: bool,
+    ) -> ClipEncoderOutput {
+        let mut all_hidden_states: Option<Vec<Tensor>> = if self.output_hidden_states {
+            Some(vec![])
+        } else {
+            None
+        };
+        let mut all_attentions: Option<Vec<Tensor>> = if self.output_attentions {
+            Some(vec![])
+        } else {
+            None
+        };
+
+        let mut hidden_state = input_embeddings.shallow_clone();
+        for layer in &self.layers {
+            if let Some(hidden_states) = all_hidden_states.borrow_mut() {
+                hidden_states.push(hidden_state.shallow_clone());
+            };
+            let (layer_output, attention_weights) =
+                layer.forward_t(&hidden_state, attention_mask, train);
+            hidden_state = layer_output;
+            if let Some(attentions) = all_attentions.borrow_mut() {
+                attentions.push(attention_weights.unwrap());
+            };
+        }
+        if let Some(hidden_states) = all_hidden_states.borrow_mut() {
+            hidden_states.push(hidden_state.shallow_clone());
+        };
+
+        ClipEncoderOutput {
+            hidden_state,
+            all_hidden_states,
+            all_attentions,
+        }
+    }
+}
+
+/// Container for the CLIP encoder output.
+pub struct ClipEncoderOutput {
+    /// Last hidden states from the encoder
+    pub hidden_state: Tensor,
+    /// Hidden states for all intermediate layers
+    pub all_hidden_states: Option<Vec<Tensor>>,
+    /// Attention weights for all intermediate layers
+    pub all_attentions: Option<Vec<Tensor>>,
+}
+
+/// Builds the causal attention mask of the text encoder, combined with the padding mask if provided
+pub(crate) fn causal_attention_mask(
+    attention_mask: Option<&Tensor>,
+    input_embeddings: &Tensor,
+) -> Tensor {
+    let (batch_size, sequence_length, _) = input_embeddings.size3().unwrap();
+    let positions = Tensor::arange(sequence_length, (Kind::Int64, input_embeddings.device()));
+    let mut masked_positions = positions
+        .unsqueeze(0)
+        .gt_tensor(&positions.unsqueeze(1))
+        .view([1, 1, sequence_length, sequence_length]);
+    if let Some(attention_mask) = attention_mask {
+        masked_positions = masked_positions.logical_or(&attention_mask.eq(0).view([
+            batch_size,
+            1,
+            1,
+            sequence_length,
+        ]));
+    }
+    masked_positions.to_kind(input_embeddings.kind()) * -1e4
+}
@@ -0,0 +1,281 @@
+// Copyright 2021 The OpenAI Team Authors and The HuggingFace Team. All rights reserved.
+// Copyright 2023 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::{Config, RustBertError};
+use serde::{Deserialize, Serialize};
+use tch::{Kind, Tensor};
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(untagged)]
+/// # Image size of the preprocessing configuration
+/// Follows the formats of the Transformers library preprocessor configurations
+pub enum ImageSize {
+    /// Single value: length of the shortest edge for resizing, or side of the square for center-cropping
+    Value(i64),
+    /// Length of the shortest edge, the aspect ratio being preserved
+    ShortestEdge { shortest_edge: i64 },
+    /// Exact height and width
+    HeightWidth { height: i64, width: i64 },
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+/// # CLIP image preprocessing configuration
+/// Can be loaded from the `preprocessor_config.json` file of the Transformers library.
+pub struct ClipImageProcessorConfig {
+    /// Resize the images (bicubic interpolation with anti-aliasing)
+    pub do_resize: bool,
+    /// Target size of the resizing
+    pub size: ImageSize,
+    /// Crop the center of the images
+    pub do_center_crop: bool,
+    /// Size of the center crop
+    pub crop_size: ImageSize,
+    /// Rescale the pixel values by `rescale_factor`
+    pub do_rescale: bool,
+    /// Factor applied to the pixel values (in the range [0, 255])
+    pub rescale_factor: f64,
+    /// Normalize the pixel values with `image_mean` and `image_std`
+    pub do_normalize: bool,
+    /// Mean of each channel
+    pub image_mean: Vec<f64>,
+    /// Standard deviation of each channel
+    pub image_std: Vec<f64>,
+}
+
+impl Config for ClipImageProcessorConfig {}
+
+impl Default for ClipImageProcessorConfig {
+    fn default() -> Self {
+        ClipImageProcessorConfig {
+            do_resize: true,
+            size: ImageSize::ShortestEdge { shortest_edge: 224 },
+            do_center_crop: true,
+            crop_size: ImageSize::HeightWidth {
+                height: 224,
+                width: 224,
+            },
+            do_rescale: true,
+            rescale_factor: 1.0 / 255.0,
+            do_normalize: true,
+            image_mean: vec![0.48145466, 0.4578275, 0.40821073],
+            image_std: vec![0.26862954, 0.26130258, 0.27577711],
+        }
+    }
+}
+
+/// # Raw RGB image
+/// Borrowed 8-bit RGB image buffer, with the pixels stored row by row as interleaved `[r, g, b]` values
+/// (e.g. the raw buffer of an `image::RgbImage`).
+#[derive(Debug, Clone, Copy)]
+pub struct RgbImage<'a> {
+    /// Pixel values, of length `3 * width * height`
+    pub pixels: &'a [u8],
+    /// Width of the image
+    pub width: usize,
+    /// Height of the image
+    pub height: usize,
+}
+
+impl<'a> RgbImage<'a> {
+    /// Creates a new `RgbImage`, checking that the buffer length matches the image dimensions
+    pub fn new(pixels: &'a [u8], width: usize, height: usize) -> Result<Self, RustBertError> {
+        if pixels.len() != 3 * width * height || width == 0 || height == 0 {
+            return Err(RustBertError::ValueError(format!(
+                "Invalid RGB buffer of length {} for an image of {width}x{height} pixels",
+                pixels.len()
+            )));
+        }
+        Ok(RgbImage {
+            pixels,
+            width,
+            height,
+        })
+    }
+
+    /// Converts the image to a `Tensor` of shape (3, *height*, *width*) with values in [0, 255]
+    pub fn to_tensor(&self) -> Result<Tensor, RustBertError> {
+        let image = RgbImage::new(self.pixels, self.width, self.height)?;
+        Ok(Tensor::of_slice(image.pixels)
+            .view([image.height as i64, image.width as i64, 3])
+            .permute(&[2, 0, 1]))
+    }
+}
+
+/// # CLIP image processor
+/// Converts images to the pixel values expected by the CLIP vision transformer: the shortest edge is resized,
+/// the center of the image cropped and the pixel values rescaled and normalized.
+/// All operations are performed on `Tensor`, on the device of the input images.
+pub struct ClipImageProcessor {
+    config: ClipImageProcessorConfig,
+}
+
+impl ClipImageProcessor {
+    /// Creates a new `ClipImageProcessor`
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use rust_bert::clip::{ClipImageProcessor, ClipImageProcessorConfig, RgbImage};
+    ///
+    /// let processor = ClipImageProcessor::new(ClipImageProcessorConfig::default());
+    /// let pixels = vec![127u8; 3 * 640 * 480];
+    /// let image = RgbImage::new(&pixels, 640, 480)?;
+    /// let pixel_values = processor.preprocess(&[image])?;
+    /// # Ok::<(), rust_bert::RustBertError>(())
+    /// ```
+    pub fn new(config: ClipImageProcessorConfig) -> ClipImageProcessor {
+        ClipImageProcessor { config }
+    }
+
+    /// Preprocesses a batch of images
+    ///
+    /// # Arguments
+    ///
+    /// * `images` - Raw RGB images
+    ///
+    /// # Returns
+    ///
+    /// * `Tensor` of pixel values with shape (*batch size*, 3, *crop height*, *crop width*)
+    pub fn preprocess(&self, images: &[RgbImage]) -> Result<Tensor, RustBertError> {
+        let pixel_values = images
+            .iter()
+            .map(|image| self.preprocess_tensor(&image.to_tensor()?))
+            .collect::<Result<Vec<Tensor>, RustBertError>>()?;
+        Ok(Tensor::stack(&pixel_values, 0))
+    }
+
+    /// Preprocesses a single image
+    ///
+    /// # Arguments
+    ///
+    /// * `image` - `Tensor` of shape (3, *height*, *width*) with values in [0, 255]
+    ///
+    /// # Returns
+    ///
+    /// * `Tensor` of pixel values with shape (3, *crop height*, *crop width*)
+    pub fn preprocess_tensor(&self, image: &Tensor) -> Result<Tensor, RustBertError> {
+        let size = image.size();
+        if size.len() != 3 || size[0] != 3 {
+            return Err(RustBertError::ValueError(format!(
+                "Images must have shape (3, height, width), got {size:?}"
+            )));
+        }
+        let mut image = image.to_kind(Kind::Float).unsqueeze(0);
+
+        if self.config.do_resize {
+            let (height, width) = (size[1], size[2]);
+            let (new_height, new_width) = match self.config.size {
+                ImageSize::Value(shortest_edge) | ImageSize::ShortestEdge { shortest_edge } => {
+                    if height <= width {
+                        (shortest_edge, shortest_edge * width / height)
+                    } else {
+                        (shortest_edge * height / width, shortest_edge)
+                    }
+                }
+                ImageSize::HeightWidth { height, width } => (height, width),
+            };
+            // The reference implementation resizes 8-bit images: the interpolated values are clamped and rounded
+            image = image
+                .internal_upsample_bicubic2d_aa(&[new_height, new_width], false, None, None)
+                .clamp(0.0, 255.0)
+                .round();
+        }
+
+        if self.config.do_center_crop {
+            let (crop_height, crop_width) = match self.config.crop_size {
+                ImageSize::Value(size)
+                | ImageSize::ShortestEdge {
+                    shortest_edge: size,
+                } => (size, size),
+                ImageSize::HeightWidth { height, width } => (height, width),
+            };
+            image = center_crop(&image, crop_height, crop_width);
+        }
+
+        if self.config.do_rescale {
+            image *= self.config.rescale_factor;
+        }
+
+        if self.config.do_normalize {
+            let mean = Tensor::of_slice(&self.config.image_mean)
+                .to_kind(Kind::Float)
+                .to_device(image.device())
+                .view([1, -1, 1, 1]);
+            let std = Tensor::of_slice(&self.config.image_std)
+                .to_kind(Kind::Float)
+                .to_device(image.device())
+                .view([1, -1, 1, 1]);
+            image = (image - mean) / std;
+        }
+
+        Ok(image.squeeze_dim(0))
+    }
+}
+
+/// Crops the center of a batch of images of shape (*batch size*, *channels*, *height*, *width*),
+/// padding the images with zeros if they are smaller than the crop
+fn center_crop(image: &Tensor, crop_height: i64, crop_width: i64) -> Tensor {
+    let size = image.size();
+    let (height, width) = (size[2], size[3]);
+    let pad_height = (crop_height - height).max(0);
+    let pad_width = (crop_width - width).max(0);
+    let image = if (pad_height > 0) | (pad_width > 0) {
+        image.constant_pad_nd(&[
+            pad_width / 2,
+            pad_width - pad_width / 2,
+            pad_height / 2,
+            pad_height - pad_height / 2,
+        ])
+    } else {
+        image.shallow_clone()
+    };
+    let (height, width) = (height + pad_height, width + pad_width);
+    image
+        .narrow(2, (height - crop_height) / 2, crop_height)
+        .narrow(3, (width - crop_width) / 2, crop_width)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn deserialize_preprocessor_configs() {
+        let legacy: ClipImageProcessorConfig =
+            serde_json::from_str(r#"{"size": 224, "crop_size": 224, "do_normalize": true}"#)
+                .unwrap();
+        assert_eq!(legacy.size, ImageSize::Value(224));
+        assert_eq!(legacy.crop_size, ImageSize::Value(224));
+        assert!(legacy.do_rescale);
+
+        let config: ClipImageProcessorConfig = serde_json::from_str(
+            r#"{"size": {"shortest_edge": 336}, "crop_size": {"height": 336, "width": 336}}"#,
+        )
+        .unwrap();
+        assert_eq!(config.size, ImageSize::ShortestEdge { shortest_edge: 336 });
+        assert_eq!(
+            config.crop_size,
+            ImageSize::HeightWidth {
+                height: 336,
+                width: 336
+            }
+        );
+    }
+
+    #[test]
+    fn rgb_image_buffer_length() {
+        let pixels = vec![0u8; 3 * 4 * 2];
+        assert!(RgbImage::new(&pixels, 4, 2).is_ok());
+        assert!(RgbImage::new(&pixels, 4, 3).is_err());
+    }
+}
@@ -0,0 +1,61 @@
+//! # CLIP (Contrastive Language-Image Pre-training)
+//!
+//! Implementation of the CLIP model ([Learning Transferable Visual Models From Natural Language Supervision](https://arxiv.org/abs/2103.00020) Radford, Kim, Hallacy, Ramesh, Goh, Agarwal, Sastry, Askell, Mishkin, Clark, Krueger, Sutskever, 2021).
+//! The model is made of a causal text transformer (`clip_model::ClipTextTransformer`) and a vision transformer encoding image
+//! patches (`clip_model::ClipVisionTransformer`), whose pooled outputs are projected into a joint embedding space by `clip_model::ClipModel`.
+//!
+//! `ClipEmbeddingsModel` provides an API similar to the sentence embeddings pipeline: `encode_text` and `encode_image` return normalized
+//! embeddings (`Vec<Embedding>`) that can be compared with a dot product and indexed with the `semantic_search` pipeline.
+//! Images are provided as raw 8-bit RGB buffers (`RgbImage`) and preprocessed with `ClipImageProcessor` (resizing of the shortest edge,
+//! center-cropping, rescaling and normalization), on the device of the model.
+//!
+//! # Model set-up and pre-trained weights loading
+//!
+//! All models expect the following resources:
+//! - Configuration file expected to have a structure following the [Transformers library](https://github.com/huggingface/transformers)
+//! - Model weights are expected to have a structure and parameter names following the [Transformers library](https://github.com/huggingface/transformers). A conversion using the Python utility scripts is required to convert the `.bin` weights to the `.ot` format.
+//! - `ClipTokenizer` using a `vocab.json` vocabulary and a `merges.txt` merges file
+//! - Optionally, an image preprocessing configuration (`preprocessor_config.json`)
+//!
+//! No pretrained checkpoints are registered as remote resources: the weights have to be converted and loaded from local resources.
+//!
+//! ```no_run
+//! # fn main() -> anyhow::Result<()> {
+//! use rust_bert::clip::{ClipEmbeddingsConfig, ClipEmbeddingsModel, RgbImage};
+//! use rust_bert::pipelines::semantic_search::{IndexBackend, SemanticSearchIndex};
+//! use rust_bert::resources::LocalResource;
+//! use std::path::PathBuf;
+//!
+//! let config = ClipEmbeddingsConfig::new(
+//!     LocalResource::from(PathBuf::from("path/to/rust_model.ot")),
+//!     LocalResource::from(PathBuf::from("path/to/config.json")),
+//!     LocalResource::from(PathBuf::from("path/to/vocab.json")),
+//!     LocalResource::from(PathBuf::from("path/to/merges.txt")),
+//! );
+//! let model = ClipEmbeddingsModel::new(config)?;
+//!
+//! let pixels = vec![127u8; 3 * 640 * 480];
+//! let images = [RgbImage::new(&pixels, 640, 480)?];
+//! let image_embeddings = model.encode_image(&images)?;
+//!
+//! let mut index = SemanticSearchIndex::new(IndexBackend::Exact);
+//! index.add(&["grey_picture"], &image_embeddings, None)?;
+//! let results = index.search(&model.encode_text(&["a grey picture"])?, 1, None, None)?;
+//! # Ok(())
+//! # }
+//! ```
+
+mod clip_model;
+mod encoder;
+mod image_processing;
+mod pipeline;
+mod tokenizer;
+
+pub use clip_model::{
+    ClipConfig, ClipModel, ClipModelOutput, ClipTextConfig, ClipTextTransformer,
+    ClipTransformerOutput, ClipVisionConfig, ClipVisionTransformer,
+};
+pub use encoder::ClipEncoderOutput;
+pub use image_processing::{ClipImageProcessor, ClipImageProcessorConfig, ImageSize, RgbImage};
+pub use pipeline::{ClipEmbeddingsConfig, ClipEmbeddingsModel};
+pub use tokenizer::ClipTokenizer;
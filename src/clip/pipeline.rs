@@ -0,0 +1,252 @@
+// Copyright 2023 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::clip::clip_model::normalize;
+use crate::clip::{
+    ClipConfig, ClipImageProcessor, ClipImageProcessorConfig, ClipModel, ClipTokenizer, RgbImage,
+};
+use crate::pipelines::sentence_embeddings::Embedding;
+use crate::resources::ResourceProvider;
+use crate::{Config, RustBertError};
+use tch::{nn, Device, Tensor};
+
+/// # Configuration for CLIP embeddings
+/// Contains information regarding the model to load and device to place the model on.
+pub struct ClipEmbeddingsConfig {
+    /// Model weights resource
+    pub model_resource: Box<dyn ResourceProvider + Send>,
+    /// Config resource
+    pub config_resource: Box<dyn ResourceProvider + Send>,
+    /// Vocab resource (`vocab.json`)
+    pub vocab_resource: Box<dyn ResourceProvider + Send>,
+    /// Merges resource (`merges.txt`)
+    pub merges_resource: Box<dyn ResourceProvider + Send>,
+    /// Optional image preprocessing configuration resource (`preprocessor_config.json`). If None, the defaults of the OpenAI CLIP models are used
+    pub preprocessor_config_resource: Option<Box<dyn ResourceProvider + Send>>,
+    /// Device to place the model on (default: CUDA/GPU when available)
+    pub device: Device,
+}
+
+impl ClipEmbeddingsConfig {
+    /// Instantiate a new CLIP embeddings configuration, using the default image preprocessing configuration.
+    ///
+    /// # Arguments
+    ///
+    /// * `model_resource` - The `ResourceProvider` pointing to the model to load (e.g.  model.ot)
+    /// * `config_resource` - The `ResourceProvider` pointing to the model configuration to load (e.g. config.json)
+    /// * `vocab_resource` - The `ResourceProvider` pointing to the tokenizer's vocabulary to load (e.g.  vocab.json)
+    /// * `merges_resource` - The `ResourceProvider` pointing to the tokenizer's merges file to load (e.g.  merges.txt)
+    pub fn new<RM, RC, RV, RMe>(
+        model_resource: RM,
+        config_resource: RC,
+        vocab_resource: RV,
+        merges_resource: RMe,
+    ) -> ClipEmbeddingsConfig
+    where
+        RM: ResourceProvider + Send + 'static,
+        RC: ResourceProvider + Send + 'static,
+        RV: ResourceProvider + Send + 'static,
+        RMe: ResourceProvider + Send + 'static,
+    {
+        ClipEmbeddingsConfig {
+            model_resource: Box::new(model_resource),
+            config_resource: Box::new(config_resource),
+            vocab_resource: Box::new(vocab_resource),
+            merges_resource: Box::new(merges_resource),
+            preprocessor_config_resource: None,
+            device: Device::cuda_if_available(),
+        }
+    }
+}
+
+/// # CLIP embeddings model
+/// Computes normalized text and image embeddings in a joint embedding space: the cosine similarity (dot product)
+/// of a text and an image embeddings measures how well the text describes the image. The embeddings can be
+/// indexed and queried with the `semantic_search` pipeline.
+pub struct ClipEmbeddingsModel {
+    tokenizer: ClipTokenizer,
+    image_processor: ClipImageProcessor,
+    max_length: usize,
+    var_store: nn::VarStore,
+    model: ClipModel,
+}
+
+impl ClipEmbeddingsModel {
+    /// Build a new `ClipEmbeddingsModel`
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - `ClipEmbeddingsConfig` object containing the resource references (model, vocabulary, configuration) and device placement (CPU/GPU)
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::clip::{ClipEmbeddingsConfig, ClipEmbeddingsModel};
+    /// use rust_bert::resources::LocalResource;
+    /// use std::path::PathBuf;
+    ///
+    /// let config = ClipEmbeddingsConfig::new(
+    ///     LocalResource::from(PathBuf::from("path/to/rust_model.ot")),
+    ///     LocalResource::from(PathBuf::from("path/to/config.json")),
+    ///     LocalResource::from(PathBuf::from("path/to/vocab.json")),
+    ///     LocalResource::from(PathBuf::from("path/to/merges.txt")),
+    /// );
+    /// let model = ClipEmbeddingsModel::new(config)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn new(config: ClipEmbeddingsConfig) -> Result<ClipEmbeddingsModel, RustBertError> {
+        let tokenizer = ClipTokenizer::from_file(
+            config.vocab_resource.get_local_path()?,
+            config.merges_resource.get_local_path()?,
+        )?;
+        let image_processor_config = match &config.preprocessor_config_resource {
+            Some(resource) => ClipImageProcessorConfig::from_file(resource.get_local_path()?),
+            None => ClipImageProcessorConfig::default(),
+        };
+        let image_processor = ClipImageProcessor::new(image_processor_config);
+
+        let model_config = ClipConfig::from_file(config.config_resource.get_local_path()?);
+        let mut var_store = nn::VarStore::new(config.device);
+        let model = ClipModel::new(var_store.root(), &model_config);
+        var_store.load(config.model_resource.get_local_path()?)?;
+
+        Ok(ClipEmbeddingsModel {
+            tokenizer,
+            image_processor,
+            max_length: model_config.text_config.max_position_embeddings as usize,
+            var_store,
+            model,
+        })
+    }
+
+    /// Tokenizes the inputs, returning the padded token ids and attention masks of shape (*batch size*, *sequence_length*)
+    pub fn tokenize<S>(&self, inputs: &[S]) -> (Tensor, Tensor)
+    where
+        S: AsRef<str>,
+    {
+        let token_ids = inputs
+            .iter()
+            .map(|input| self.tokenizer.encode(input.as_ref(), self.max_length))
+            .collect::<Vec<Vec<i64>>>();
+        let max_len = token_ids.iter().map(Vec::len).max().unwrap_or(0);
+
+        // The end of text token is used for padding: the attention mask is built from the sequence lengths
+        let pad_token_id = self.tokenizer.get_eos_id();
+        let (input_ids, attention_masks): (Vec<Tensor>, Vec<Tensor>) = token_ids
+            .into_iter()
+            .map(|mut input| {
+                let mut mask = vec![1i64; input.len()];
+                mask.resize(max_len, 0);
+                input.resize(max_len, pad_token_id);
+                (Tensor::of_slice(&input), Tensor::of_slice(&mask))
+            })
+            .unzip();
+
+        (
+            Tensor::stack(&input_ids, 0).to(self.var_store.device()),
+            Tensor::stack(&attention_masks, 0).to(self.var_store.device()),
+        )
+    }
+
+    /// Computes normalized text embeddings, outputs `Tensor` of shape (*batch size*, *projection_dim*).
+    pub fn encode_text_as_tensor<S>(&self, inputs: &[S]) -> Result<Tensor, RustBertError>
+    where
+        S: AsRef<str>,
+    {
+        let (input_ids, attention_mask) = self.tokenize(inputs);
+        let text_features = tch::no_grad(|| {
+            self.model
+                .get_text_features(&input_ids, Some(&attention_mask), false)
+        })?;
+        Ok(normalize(&text_features))
+    }
+
+    /// Computes normalized text embeddings.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// # use rust_bert::clip::{ClipEmbeddingsConfig, ClipEmbeddingsModel};
+    /// # use rust_bert::resources::LocalResource;
+    /// # use std::path::PathBuf;
+    /// # let config = ClipEmbeddingsConfig::new(
+    /// #     LocalResource::from(PathBuf::from("path/to/rust_model.ot")),
+    /// #     LocalResource::from(PathBuf::from("path/to/config.json")),
+    /// #     LocalResource::from(PathBuf::from("path/to/vocab.json")),
+    /// #     LocalResource::from(PathBuf::from("path/to/merges.txt")),
+    /// # );
+    /// let model = ClipEmbeddingsModel::new(config)?;
+    /// let embeddings = model.encode_text(&["a photo of a cat", "a photo of a dog"])?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn encode_text<S>(&self, inputs: &[S]) -> Result<Vec<Embedding>, RustBertError>
+    where
+        S: AsRef<str>,
+    {
+        if inputs.is_empty() {
+            return Ok(Vec::new());
+        }
+        Ok(Vec::from(self.encode_text_as_tensor(inputs)?))
+    }
+
+    /// Preprocesses images into pixel values of shape (*batch size*, 3, *crop height*, *crop width*), on the model device.
+    pub fn preprocess_images(&self, images: &[RgbImage]) -> Result<Tensor, RustBertError> {
+        let pixel_values = images
+            .iter()
+            .map(|image| {
+                self.image_processor
+                    .preprocess_tensor(&image.to_tensor()?.to(self.var_store.device()))
+            })
+            .collect::<Result<Vec<Tensor>, RustBertError>>()?;
+        Ok(Tensor::stack(&pixel_values, 0))
+    }
+
+    /// Computes normalized image embeddings, outputs `Tensor` of shape (*batch size*, *projection_dim*).
+    pub fn encode_image_as_tensor(&self, images: &[RgbImage]) -> Result<Tensor, RustBertError> {
+        let pixel_values = self.preprocess_images(images)?;
+        let image_features = tch::no_grad(|| self.model.get_image_features(&pixel_values, false))?;
+        Ok(normalize(&image_features))
+    }
+
+    /// Computes normalized image embeddings from raw RGB buffers.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// # use rust_bert::clip::{ClipEmbeddingsConfig, ClipEmbeddingsModel, RgbImage};
+    /// # use rust_bert::resources::LocalResource;
+    /// # use std::path::PathBuf;
+    /// # let config = ClipEmbeddingsConfig::new(
+    /// #     LocalResource::from(PathBuf::from("path/to/rust_model.ot")),
+    /// #     LocalResource::from(PathBuf::from("path/to/config.json")),
+    /// #     LocalResource::from(PathBuf::from("path/to/vocab.json")),
+    /// #     LocalResource::from(PathBuf::from("path/to/merges.txt")),
+    /// # );
+    /// let model = ClipEmbeddingsModel::new(config)?;
+    /// let pixels = vec![127u8; 3 * 640 * 480];
+    /// let image = RgbImage::new(&pixels, 640, 480)?;
+    /// let image_embeddings = model.encode_image(&[image])?;
+    /// let text_embeddings = model.encode_text(&["a grey picture"])?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn encode_image(&self, images: &[RgbImage]) -> Result<Vec<Embedding>, RustBertError> {
+        if images.is_empty() {
+            return Ok(Vec::new());
+        }
+        Ok(Vec::from(self.encode_image_as_tensor(images)?))
+    }
+}
@@ -0,0 +1,297 @@
+// Copyright 2021 The OpenAI Team Authors and The HuggingFace Team. All rights reserved.
+// Copyright 2023 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::RustBertError;
+use regex::Regex;
+use rust_tokenizers::vocab::{BpePairRef, BpePairVocab};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+use std::sync::RwLock;
+
+const START_OF_TEXT: &str = "<|startoftext|>";
+const END_OF_TEXT: &str = "<|endoftext|>";
+const END_OF_WORD: &str = "</w>";
+
+/// Mapping of bytes to printable unicode characters used by byte-level BPE vocabularies
+fn bytes_to_unicode() -> HashMap<u8, char> {
+    let mut printable_bytes: Vec<u32> = (u32::from(b'!')..=u32::from(b'~'))
+        .chain(0xA1..=0xAC)
+        .chain(0xAE..=0xFF)
+        .collect();
+    let mut characters = printable_bytes.clone();
+    let mut offset = 0;
+    for byte in 0..=255u32 {
+        if !printable_bytes.contains(&byte) {
+            printable_bytes.push(byte);
+            characters.push(256 + offset);
+            offset += 1;
+        }
+    }
+    printable_bytes
+        .into_iter()
+        .zip(characters)
+        .map(|(byte, character)| (byte as u8, char::from_u32(character).unwrap()))
+        .collect()
+}
+
+/// # CLIP tokenizer
+/// Byte-level BPE tokenizer of the CLIP text encoder. The text is lower-cased and its whitespaces collapsed,
+/// split into words, numbers and punctuation, and each piece is encoded with an end of word marker (`</w>`).
+/// Encoded sequences are wrapped in `<|startoftext|>` and `<|endoftext|>` tokens.
+///
+/// The `ftfy` unicode fixing step of the reference implementation is not applied.
+pub struct ClipTokenizer {
+    encoder: HashMap<String, i64>,
+    decoder: HashMap<i64, String>,
+    bpe_ranks: BpePairVocab,
+    byte_encoder: HashMap<u8, char>,
+    byte_decoder: HashMap<char, u8>,
+    pattern: Regex,
+    cache: RwLock<HashMap<String, Vec<String>>>,
+    bos_token_id: i64,
+    eos_token_id: i64,
+}
+
+impl ClipTokenizer {
+    /// Create a new `ClipTokenizer` from a `vocab.json` vocabulary and a `merges.txt` merges file
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use rust_bert::clip::ClipTokenizer;
+    ///
+    /// let tokenizer = ClipTokenizer::from_file("path/to/vocab.json", "path/to/merges.txt")?;
+    /// let token_ids = tokenizer.encode("a photo of a cat", 77);
+    /// # Ok::<(), rust_bert::RustBertError>(())
+    /// ```
+    pub fn from_file<V: AsRef<Path>, M: AsRef<Path>>(
+        vocab_path: V,
+        merges_path: M,
+    ) -> Result<ClipTokenizer, RustBertError> {
+        let encoder: HashMap<String, i64> =
+            serde_json::from_reader(BufReader::new(File::open(vocab_path)?))
+                .map_err(|error| RustBertError::TokenizerError(error.to_string()))?;
+        let bpe_ranks = BpePairVocab::from_file(merges_path)?;
+        ClipTokenizer::from_existing_vocab_and_merges(encoder, bpe_ranks)
+    }
+
+    /// Create a new `ClipTokenizer` from a vocabulary (mapping of tokens to ids) and BPE merges
+    pub fn from_existing_vocab_and_merges(
+        encoder: HashMap<String, i64>,
+        bpe_ranks: BpePairVocab,
+    ) -> Result<ClipTokenizer, RustBertError> {
+        let special_token_id = |token: &str| {
+            encoder.get(token).copied().ok_or_else(|| {
+                RustBertError::TokenizerError(format!(
+                    "The special token {token} could not be found in the vocabulary"
+                ))
+            })
+        };
+        let bos_token_id = special_token_id(START_OF_TEXT)?;
+        let eos_token_id = special_token_id(END_OF_TEXT)?;
+
+        let decoder = encoder
+            .iter()
+            .map(|(token, &token_id)| (token_id, token.clone()))
+            .collect();
+        let byte_encoder = bytes_to_unicode();
+        let byte_decoder = byte_encoder
+            .iter()
+            .map(|(&byte, &character)| (character, byte))
+            .collect();
+        let pattern = Regex::new(
+            r"<\|startoftext\|>|<\|endoftext\|>|'s|'t|'re|'ve|'m|'ll|'d|\p{L}+|\p{N}|[^\s\p{L}\p{N}]+",
+        )
+        .unwrap();
+
+        Ok(ClipTokenizer {
+            encoder,
+            decoder,
+            bpe_ranks,
+            byte_encoder,
+            byte_decoder,
+            pattern,
+            cache: RwLock::new(HashMap::new()),
+            bos_token_id,
+            eos_token_id,
+        })
+    }
+
+    /// Id of the start of text token
+    pub fn get_bos_id(&self) -> i64 {
+        self.bos_token_id
+    }
+
+    /// Id of the end of text token, also used for padding
+    pub fn get_eos_id(&self) -> i64 {
+        self.eos_token_id
+    }
+
+    fn bpe(&self, token: &str) -> Vec<String> {
+        if let Some(cached) = self.cache.read().unwrap().get(token) {
+            return cached.clone();
+        }
+
+        let mut word: Vec<String> = token.chars().map(String::from).collect();
+        if let Some(last) = word.last_mut() {
+            last.push_str(END_OF_WORD);
+        }
+        while word.len() > 1 {
+            let best_pair = word
+                .windows(2)
+                .filter_map(|pair| {
+                    self.bpe_ranks
+                        .byte_pair_to_id(&BpePairRef {
+                            byte_1: &pair[0],
+                            byte_2: &pair[1],
+                        })
+                        .map(|rank| (*rank, pair[0].clone(), pair[1].clone()))
+                })
+                .min_by_key(|(rank, _, _)| *rank);
+            let (first, second) = match best_pair {
+                Some((_, first, second)) => (first, second),
+                None => break,
+            };
+
+            let mut merged_word = Vec::with_capacity(word.len());
+            let mut index = 0;
+            while index < word.len() {
+                if index + 1 < word.len() && word[index] == first && word[index + 1] == second {
+                    merged_word.push(format!("{first}{second}"));
+                    index += 2;
+                } else {
+                    merged_word.push(word[index].clone());
+                    index += 1;
+                }
+            }
+            word = merged_word;
+        }
+
+        self.cache
+            .write()
+            .unwrap()
+            .insert(token.to_string(), word.clone());
+        word
+    }
+
+    /// Splits a text into BPE tokens (without special tokens)
+    pub fn tokenize(&self, text: &str) -> Vec<String> {
+        let text = text.split_whitespace().collect::<Vec<&str>>().join(" ");
+        let text = text.to_lowercase();
+        self.pattern
+            .find_iter(&text)
+            .flat_map(|piece| {
+                let piece = piece.as_str();
+                if piece == START_OF_TEXT || piece == END_OF_TEXT {
+                    vec![piece.to_string()]
+                } else {
+                    let byte_encoded = piece
+                        .bytes()
+                        .map(|byte| self.byte_encoder[&byte])
+                        .collect::<String>();
+                    self.bpe(&byte_encoded)
+                }
+            })
+            .collect()
+    }
+
+    /// Converts a text into token ids, wrapped in start and end of text tokens and truncated to `max_length` ids
+    pub fn encode(&self, text: &str, max_length: usize) -> Vec<i64> {
+        let content_length = max_length.saturating_sub(2);
+        let mut token_ids = Vec::with_capacity(max_length);
+        token_ids.push(self.bos_token_id);
+        token_ids.extend(
+            self.tokenize(text)
+                .iter()
+                .take(content_length)
+                .map(|token| *self.encoder.get(token).unwrap_or(&self.eos_token_id)),
+        );
+        token_ids.push(self.eos_token_id);
+        token_ids
+    }
+
+    /// Converts token ids back into a text, skipping the start and end of text tokens
+    pub fn decode(&self, token_ids: &[i64]) -> String {
+        let text = token_ids
+            .iter()
+            .filter(|&&token_id| token_id != self.bos_token_id && token_id != self.eos_token_id)
+            .filter_map(|token_id| self.decoder.get(token_id))
+            .flat_map(|token| token.chars())
+            .collect::<String>()
+            .replace(END_OF_WORD, " ");
+        let bytes = text
+            .chars()
+            .map(|character| *self.byte_decoder.get(&character).unwrap_or(&b' '))
+            .collect::<Vec<u8>>();
+        String::from_utf8_lossy(&bytes).trim().to_string()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn test_tokenizer() -> ClipTokenizer {
+        let byte_encoder = bytes_to_unicode();
+        let mut encoder: HashMap<String, i64> = byte_encoder
+            .values()
+            .flat_map(|character| vec![character.to_string(), format!("{character}</w>")])
+            .enumerate()
+            .map(|(index, token)| (token, index as i64))
+            .collect();
+        let merges = [("c", "a"), ("ca", "t</w>"), ("o", "f</w>")];
+        let mut bpe_ranks = HashMap::new();
+        for (rank, (first, second)) in merges.iter().enumerate() {
+            encoder.insert(format!("{first}{second}"), encoder.len() as i64);
+            bpe_ranks.insert((first.to_string(), second.to_string()), rank as i64);
+        }
+        encoder.insert(START_OF_TEXT.to_string(), encoder.len() as i64);
+        encoder.insert(END_OF_TEXT.to_string(), encoder.len() as i64);
+        ClipTokenizer::from_existing_vocab_and_merges(encoder, BpePairVocab { values: bpe_ranks })
+            .unwrap()
+    }
+
+    #[test]
+    fn bytes_to_unicode_is_bijective() {
+        let byte_encoder = bytes_to_unicode();
+        assert_eq!(byte_encoder.len(), 256);
+        assert_eq!(byte_encoder[&b'a'], 'a');
+        assert_eq!(byte_encoder[&b' '], '\u{120}');
+        let mut characters = byte_encoder.values().collect::<Vec<&char>>();
+        characters.sort();
+        characters.dedup();
+        assert_eq!(characters.len(), 256);
+    }
+
+    #[test]
+    fn tokenize_and_decode() {
+        let tokenizer = test_tokenizer();
+        assert_eq!(
+            tokenizer.tokenize("A  photo of\tthe CAT, 42!!"),
+            vec![
+                "a</w>", "p", "h", "o", "t", "o</w>", "of</w>", "t", "h", "e</w>", "cat</w>",
+                ",</w>", "4</w>", "2</w>", "!", "!</w>"
+            ]
+        );
+
+        let token_ids = tokenizer.encode("photo of a cat", 77);
+        assert_eq!(token_ids[0], tokenizer.get_bos_id());
+        assert_eq!(*token_ids.last().unwrap(), tokenizer.get_eos_id());
+        assert_eq!(tokenizer.decode(&token_ids), "photo of a cat");
+
+        let truncated = tokenizer.encode("photo of a cat", 4);
+        assert_eq!(truncated.len(), 4);
+        assert_eq!(tokenizer.decode(&truncated), "ph");
+    }
+}
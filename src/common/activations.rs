@@ -22,6 +22,10 @@ pub fn _gelu_new(x: &Tensor) -> Tensor {
     x * 0.5 * (((x.pow_tensor_scalar(3.0f64) * 0.044715 + x) * ((2f64 / PI).sqrt())).tanh() + 1)
 }
 
+pub fn _quick_gelu(x: &Tensor) -> Tensor {
+    x * (x * 1.702).sigmoid()
+}
+
 pub fn _tanh(x: &Tensor) -> Tensor {
     x.tanh()
 }
@@ -54,12 +58,15 @@ pub enum Activation {
     gelu,
     /// Rectified Linear Unit
     relu,
-    /// Swish ([Ramachandran, 2017](https://arxiv.org/abs/1710.05941))
+    /// Swish ([Ramachandran, 2017](https://arxiv.org/abs/1710.05941)), also known as SiLU
+    #[serde(alias = "silu")]
     swish,
     /// Mish ([Misra, 2019](https://arxiv.org/abs/1908.08681))
     mish,
     /// Gaussian Error Linear Unit (New) ([Hendrycks et al., 2016,](https://arxiv.org/abs/1606.08415))
     gelu_new,
+    /// Sigmoid approximation of the Gaussian Error Linear Unit, used by CLIP
+    quick_gelu,
     /// Tanh
     tanh,
     /// Identity
@@ -73,6 +80,7 @@ impl Activation {
             Activation::relu => _relu,
             Activation::swish => _swish,
             Activation::gelu_new => _gelu_new,
+            Activation::quick_gelu => _quick_gelu,
             Activation::mish => _mish,
             Activation::tanh => _tanh,
             Activation::identity => _identity,
@@ -0,0 +1,163 @@
+// Copyright 2023 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::RustBertError;
+use tch::{Device, Kind, Tensor};
+
+/// Memory-efficient attention: computes the attention context for blocks of at most `chunk_size` queries at a
+/// time and concatenates the contexts along the query dimension (-2).
+///
+/// `attention_fn(query_offset, query_length)` returns the attention context of the queries
+/// `query_offset..query_offset + query_length`, so that only the attention scores of (*batch size*, *num_heads*,
+/// `chunk_size`, *key_length*) are materialized at once instead of the full (*batch size*, *num_heads*,
+/// *query_length*, *key_length*) scores, mask and probabilities.
+///
+/// The fused scaled dot-product attention kernels are not exposed by the version of libtorch used by this crate:
+/// the chunked implementation runs on all devices and returns the same results as the full attention.
+/// For an empty query, the (empty) context is returned by a single `attention_fn(0, 0)` call.
+pub(crate) fn chunked_attention<F>(
+    query_length: i64,
+    chunk_size: i64,
+    mut attention_fn: F,
+) -> Tensor
+where
+    F: FnMut(i64, i64) -> Tensor,
+{
+    try_chunked_attention(query_length, chunk_size, |query_offset, query_length| {
+        Ok(attention_fn(query_offset, query_length))
+    })
+    .unwrap()
+}
+
+/// Fallible version of `chunked_attention`, returning the first error raised by `attention_fn`.
+pub(crate) fn try_chunked_attention<F>(
+    query_length: i64,
+    chunk_size: i64,
+    mut attention_fn: F,
+) -> Result<Tensor, RustBertError>
+where
+    F: FnMut(i64, i64) -> Result<Tensor, RustBertError>,
+{
+    if query_length == 0 {
+        return attention_fn(0, 0);
+    }
+    let chunk_size = chunk_size.max(1);
+    let contexts = (0..query_length)
+        .step_by(chunk_size as usize)
+        .map(|query_offset| attention_fn(query_offset, chunk_size.min(query_length - query_offset)))
+        .collect::<Result<Vec<Tensor>, RustBertError>>()?;
+    Ok(Tensor::cat(&contexts, -2))
+}
+
+/// Selects the rows of an attention mask matching the queries `query_offset..query_offset + query_length`.
+/// Masks broadcast over the query dimension (of size 1 along `query_dim`) are returned unchanged.
+pub(crate) fn narrow_attention_mask(
+    mask: &Tensor,
+    query_dim: i64,
+    query_offset: i64,
+    query_length: i64,
+) -> Tensor {
+    if mask.size()[query_dim as usize] == 1 {
+        mask.shallow_clone()
+    } else {
+        mask.narrow(query_dim, query_offset, query_length)
+    }
+}
+
+/// Pads the dimension `dim` of `x` with zeros to a multiple of `block_len` and splits it into blocks
+/// of `block_len` elements, returning a tensor of shape (..., *num_blocks*, *block_len*, ...).
+pub(crate) fn split_into_blocks(x: &Tensor, block_len: i64, dim: i64) -> Tensor {
+    let mut shape = x.size();
+    let dim = if dim < 0 {
+        shape.len() as i64 + dim
+    } else {
+        dim
+    } as usize;
+    let pad_len = (block_len - shape[dim] % block_len) % block_len;
+    let x = if pad_len > 0 {
+        let mut pad = vec![0; 2 * (shape.len() - dim)];
+        pad[2 * (shape.len() - dim) - 1] = pad_len;
+        x.constant_pad_nd(&pad)
+    } else {
+        x.shallow_clone()
+    };
+    let num_blocks = (shape[dim] + pad_len) / block_len;
+    shape.splice(dim..dim + 1, vec![num_blocks, block_len]);
+    x.reshape(&shape)
+}
+
+/// Concatenates each block with its previous and next blocks (padding the first and last blocks with zeros)
+/// along `sequence_dim`, so that every block can attend to its neighbours.
+pub(crate) fn concatenate_3_blocks(x: &Tensor, block_dim: i64, sequence_dim: i64) -> Tensor {
+    let num_dims = x.dim() as i64;
+    let num_blocks = x.size()[block_dim as usize];
+    let mut pad = vec![0; (2 * (num_dims - block_dim)) as usize];
+    pad[(2 * (num_dims - block_dim) - 2) as usize] = 1;
+    pad[(2 * (num_dims - block_dim) - 1) as usize] = 1;
+    let x = x.constant_pad_nd(&pad);
+    let blocks = (0..3)
+        .map(|offset| x.narrow(block_dim, offset, num_blocks))
+        .collect::<Vec<Tensor>>();
+    Tensor::cat(&blocks, sequence_dim)
+}
+
+/// Relative positions of the 3 concatenated blocks with respect to the central block, of shape (*block_len*, 3 * *block_len*)
+pub(crate) fn make_3block_relative_position_ids(block_len: i64, device: Device) -> Tensor {
+    let position_ids = Tensor::arange(3 * block_len, (Kind::Int64, device));
+    let center_position_ids = position_ids.slice(0, block_len, 2 * block_len, 1);
+    position_ids.unsqueeze(0) - center_position_ids.unsqueeze(1)
+}
+
+/// Builds the local attention mask of shape (*batch size*, 1, *num_blocks*, *block_len*, 3 * *block_len*) from a
+/// padding mask of shape (*batch size*, *sequence_length*), restricting attention to the tokens within `block_len - 1` positions.
+pub(crate) fn get_local_attention_mask(attention_mask: &Tensor, block_len: i64) -> Tensor {
+    let blocked_attention_mask = split_into_blocks(attention_mask, block_len, 1);
+    let three_blocked_attention_mask = concatenate_3_blocks(&blocked_attention_mask, 1, 2);
+    let local_attention_mask = blocked_attention_mask
+        .unsqueeze(-1)
+        .logical_and(&three_blocked_attention_mask.unsqueeze(-2));
+    let locality_mask = make_3block_relative_position_ids(block_len, attention_mask.device())
+        .abs()
+        .lt(block_len)
+        .unsqueeze(0)
+        .unsqueeze(0);
+    local_attention_mask
+        .logical_and(&locality_mask)
+        .unsqueeze(1)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_chunked_attention() {
+        let context = Tensor::arange(2 * 10 * 4, (Kind::Float, Device::Cpu)).view([2, 10, 4]);
+        let mut chunks = vec![];
+        let output = chunked_attention(10, 3, |query_offset, query_length| {
+            chunks.push((query_offset, query_length));
+            context.narrow(1, query_offset, query_length)
+        });
+        assert_eq!(chunks, vec![(0, 3), (3, 3), (6, 3), (9, 1)]);
+        assert!(output.equal(&context));
+
+        // Empty queries return an empty context without concatenating chunks
+        let output = chunked_attention(0, 3, |query_offset, query_length| {
+            context.narrow(1, query_offset, query_length)
+        });
+        assert_eq!(output.size(), vec![2, 0, 4]);
+        let output = try_chunked_attention(0, 3, |query_offset, query_length| {
+            Ok(context.narrow(1, query_offset, query_length))
+        })
+        .unwrap();
+        assert_eq!(output.size(), vec![2, 0, 4]);
+    }
+}
@@ -1,6 +1,6 @@
 use crate::RustBertError;
 use tch::nn::Embedding;
-use tch::{Device, Tensor};
+use tch::{Device, Kind, Tensor};
 
 pub fn process_ids_embeddings_pair(
     input_ids: Option<&Tensor>,
@@ -52,3 +52,84 @@ pub fn get_shape_and_device_from_ids_embeddings_pair(
         }
     })
 }
+
+#[derive(Debug)]
+/// # Rotary position embeddings
+/// Computes the cosine and sine tables used to rotate the queries and keys
+/// ([Su et al., 2021](https://arxiv.org/abs/2104.09864)). The rotation is applied to the first `dim`
+/// dimensions of each head, the remaining dimensions are left unchanged (partial rotary embeddings).
+/// The rotated dimensions are either paired as first half / second half (GPT-NeoX and LLaMA layout)
+/// or as consecutive pairs (`interleaved`, GPT-J layout).
+pub struct RotaryEmbedding {
+    inv_freq: Tensor,
+    interleaved: bool,
+}
+
+impl RotaryEmbedding {
+    /// Creates a new rotary embedding rotating `dim` dimensions of each head, with a frequency base `theta`
+    pub fn new(dim: i64, theta: f64, interleaved: bool, device: Device) -> RotaryEmbedding {
+        let inv_freq = (Tensor::arange_start_step(0, dim, 2, (Kind::Float, device)) / dim as f64
+            * theta.ln())
+        .exp()
+        .reciprocal();
+        RotaryEmbedding {
+            inv_freq,
+            interleaved,
+        }
+    }
+
+    /// Returns the cosine and sine tables of shape (*batch size*, *sequence_length*, *dim*) for the
+    /// position ids of shape (*batch size*, *sequence_length*), in the precision `kind`
+    pub fn forward(&self, position_ids: &Tensor, kind: Kind) -> (Tensor, Tensor) {
+        let freqs = position_ids.to_kind(Kind::Float).unsqueeze(-1)
+            * self.inv_freq.to_device(position_ids.device());
+        let embeddings = if self.interleaved {
+            Tensor::stack(&[&freqs, &freqs], -1).flatten(-2, -1)
+        } else {
+            Tensor::cat(&[&freqs, &freqs], -1)
+        };
+        (
+            embeddings.cos().to_kind(kind),
+            embeddings.sin().to_kind(kind),
+        )
+    }
+
+    /// Rotates an input of shape (*batch size*, *num_heads*, *sequence_length*, *head_dim*) with the cosine
+    /// and sine tables of shape (*batch size*, *sequence_length*, *dim*)
+    pub fn apply(&self, x: &Tensor, cos: &Tensor, sin: &Tensor) -> Tensor {
+        let head_dim = *x.size().last().unwrap();
+        let rotary_dim = *cos.size().last().unwrap();
+        let x_rot = x.narrow(-1, 0, rotary_dim);
+        let rotated = if self.interleaved {
+            rotate_every_two(&x_rot)
+        } else {
+            rotate_half(&x_rot)
+        };
+        let x_rot = x_rot * cos.unsqueeze(1) + rotated * sin.unsqueeze(1);
+        if rotary_dim < head_dim {
+            Tensor::cat(
+                &[&x_rot, &x.narrow(-1, rotary_dim, head_dim - rotary_dim)],
+                -1,
+            )
+        } else {
+            x_rot
+        }
+    }
+}
+
+fn rotate_half(x: &Tensor) -> Tensor {
+    let half_dim = x.size().last().unwrap() / 2;
+    let x1 = x.narrow(-1, 0, half_dim);
+    let x2 = x.narrow(-1, half_dim, half_dim);
+    Tensor::cat(&[&x2.neg(), &x1], -1)
+}
+
+fn rotate_every_two(x: &Tensor) -> Tensor {
+    let mut pairs_shape = x.size();
+    let dim = pairs_shape.pop().unwrap();
+    pairs_shape.extend_from_slice(&[dim / 2, 2]);
+    let pairs = x.reshape(pairs_shape.as_slice());
+    let x1 = pairs.select(-1, 0);
+    let x2 = pairs.select(-1, 1);
+    Tensor::stack(&[&x2.neg(), &x1], -1).flatten(-2, -1)
+}
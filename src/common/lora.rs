@@ -0,0 +1,705 @@
+// Copyright 2023 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! # Low-Rank Adaptation (LoRA) of the attention projections
+//! Implementation of LoRA adapters ([LoRA: Low-Rank Adaptation of Large Language Models](https://arxiv.org/abs/2106.09685),
+//! Hu, Shen, Wallis, Allen-Zhu, Li, Wang, Wang and Chen, 2021), available for the attention projections of
+//! BERT, RoBERTa, DistilBERT, DeBERTa, DeBERTa-v2, GPT2 and BART.
+//!
+//! The adapters are enabled by setting the `lora` field of the model configuration before creating the model.
+//! The targeted layers compute `W x + (alpha / r) B A x`, where the low-rank matrices `A` and `B` are stored
+//! in the model `VarStore` next to the frozen base weights (`<layer>.lora_A.weight` and `<layer>.lora_B.weight`).
+//! A `LoraAdapter` holds a trained set of adapter weights that can be:
+//! - saved and loaded as a small standalone weights file, independently of the base model weights
+//! - activated on a model created with LoRA layers, replacing the active adapter (hot-swapping)
+//! - merged into (and unmerged from) the base weights of any model, removing the inference overhead
+//!
+//! ```no_run
+//! use rust_bert::bert::{BertConfig, BertForSequenceClassification};
+//! use rust_bert::lora::{mark_only_lora_as_trainable, LoraAdapter, LoraConfig};
+//! use rust_bert::Config;
+//! use tch::{nn, Device};
+//! # fn main() -> anyhow::Result<()> {
+//! let mut config = BertConfig::from_file("path/to/config.json");
+//! let mut lora_config = LoraConfig::new(8, 16.0, &["query", "value"]);
+//! // The classification head is trained and saved with the adapter
+//! lora_config.modules_to_save = vec!["classifier".to_string()];
+//! config.lora = Some(lora_config.clone());
+//!
+//! let mut var_store = nn::VarStore::new(Device::Cpu);
+//! let model = BertForSequenceClassification::new(var_store.root(), &config)?;
+//! var_store.load_partial("path/to/rust_model.ot")?;
+//! mark_only_lora_as_trainable(&var_store, &lora_config);
+//! // ... fine-tune the model, then save the adapter weights only
+//! LoraAdapter::from_var_store(&var_store, lora_config.clone()).save("path/to/adapter_a")?;
+//!
+//! // Swap to another adapter on the same base model
+//! let adapter_b = LoraAdapter::load("path/to/adapter_b", Device::Cpu)?;
+//! adapter_b.activate(&var_store, &lora_config)?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::common::dropout::Dropout;
+use crate::common::error::RustBertError;
+use serde::{Deserialize, Serialize};
+use std::borrow::Borrow;
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{BufReader, BufWriter};
+use std::path::Path;
+use tch::nn::init::DEFAULT_KAIMING_UNIFORM;
+use tch::nn::{Init, Module, ModuleT, VarStore};
+use tch::{nn, no_grad, Device, Tensor};
+
+const LORA_A_SUFFIX: &str = ".lora_A.weight";
+const LORA_B_SUFFIX: &str = ".lora_B.weight";
+const LORA_FAN_IN_FAN_OUT_SUFFIX: &str = ".lora_fan_in_fan_out";
+const ADAPTER_CONFIG_FILE: &str = "adapter_config.json";
+const ADAPTER_WEIGHTS_FILE: &str = "adapter_model.ot";
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+/// # LoRA configuration
+/// Field names follow the `adapter_config.json` files of the Python PEFT library.
+pub struct LoraConfig {
+    /// Rank of the low-rank update matrices
+    pub r: i64,
+    /// Scaling numerator: the update is scaled by `lora_alpha / r`
+    pub lora_alpha: f64,
+    /// Dropout probability applied to the inputs of the adapters
+    #[serde(default)]
+    pub lora_dropout: f64,
+    /// Names of the layers receiving an adapter (e.g. `query`, `key`, `value` and `dense` for BERT, `q_lin`,
+    /// `k_lin`, `v_lin` and `out_lin` for DistilBERT, `in_proj` and `dense` for DeBERTa, `query_proj`,
+    /// `key_proj`, `value_proj` and `dense` for DeBERTa-v2, `c_attn` and `c_proj` for GPT2, `q_proj`, `k_proj`,
+    /// `v_proj` and `out_proj` for BART)
+    pub target_modules: Vec<String>,
+    /// Set to true if the base weights of all the targeted layers are stored as (*in_features*, *out_features*),
+    /// as for the GPT2 `Conv1D` layers. Used when merging adapters that do not record the layout of their layers
+    /// (e.g. converted from PEFT): the adapters extracted with `LoraAdapter::from_var_store` record the layout of
+    /// each layer from the type of the wrapped projection.
+    #[serde(default)]
+    pub fan_in_fan_out: bool,
+    /// Names of the modules trained and saved with the adapter in addition to the LoRA matrices (e.g.
+    /// `classifier` for the BERT sequence classification head)
+    #[serde(default)]
+    pub modules_to_save: Vec<String>,
+}
+
+impl LoraConfig {
+    /// Creates a new LoRA configuration without dropout
+    ///
+    /// # Arguments
+    ///
+    /// * `r` - Rank of the low-rank update matrices
+    /// * `lora_alpha` - Scaling numerator of the update
+    /// * `target_modules` - Names of the layers receiving an adapter
+    pub fn new<S: AsRef<str>>(r: i64, lora_alpha: f64, target_modules: &[S]) -> LoraConfig {
+        LoraConfig {
+            r,
+            lora_alpha,
+            lora_dropout: 0.0,
+            target_modules: target_modules
+                .iter()
+                .map(|module| module.as_ref().to_string())
+                .collect(),
+            fan_in_fan_out: false,
+            modules_to_save: vec![],
+        }
+    }
+
+    /// Scaling factor applied to the low-rank update
+    pub fn scaling(&self) -> f64 {
+        self.lora_alpha / self.r as f64
+    }
+
+    fn targets(&self, module_name: &str) -> bool {
+        self.target_modules
+            .iter()
+            .any(|target| target.as_str() == module_name)
+    }
+
+    fn saves(&self, variable_name: &str) -> bool {
+        variable_name.split('.').any(|component| {
+            self.modules_to_save
+                .iter()
+                .any(|module| module.as_str() == component)
+        })
+    }
+}
+
+/// # Base projection of a LoRA layer
+/// Implemented by the projections that can be wrapped by a `LoraLinear`, providing the layout of their weights.
+pub trait LoraBaseLayer: Module {
+    /// True if the weights are stored as (*in_features*, *out_features*)
+    const FAN_IN_FAN_OUT: bool;
+}
+
+impl LoraBaseLayer for nn::Linear {
+    const FAN_IN_FAN_OUT: bool = false;
+}
+
+fn is_lora_weight(name: &str) -> bool {
+    name.ends_with(LORA_A_SUFFIX) || name.ends_with(LORA_B_SUFFIX)
+}
+
+#[derive(Debug)]
+/// # Low-rank update of a linear layer
+pub struct LoraLayer {
+    lora_a: Tensor,
+    lora_b: Tensor,
+    dropout: Dropout,
+    scaling: f64,
+}
+
+impl LoraLayer {
+    /// Creates the low-rank update matrices of a layer. `B` is initialized to zero so that the adapted
+    /// layer is initially equivalent to the base layer.
+    pub fn new<'p, P>(p: P, in_dim: i64, out_dim: i64, config: &LoraConfig) -> LoraLayer
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let p = p.borrow();
+        let lora_a = (p / "lora_A").var("weight", &[config.r, in_dim], DEFAULT_KAIMING_UNIFORM);
+        let lora_b = (p / "lora_B").var("weight", &[out_dim, config.r], Init::Const(0.));
+        LoraLayer {
+            lora_a,
+            lora_b,
+            dropout: Dropout::new(config.lora_dropout),
+            scaling: config.scaling(),
+        }
+    }
+
+    /// Weight update `(alpha / r) B A` of shape (*out_features*, *in_features*)
+    pub fn delta_weight(&self) -> Tensor {
+        self.lora_b.matmul(&self.lora_a) * self.scaling
+    }
+}
+
+impl ModuleT for LoraLayer {
+    fn forward_t(&self, xs: &Tensor, train: bool) -> Tensor {
+        xs.apply_t(&self.dropout, train)
+            .matmul(&self.lora_a.tr())
+            .matmul(&self.lora_b.tr())
+            * self.scaling
+    }
+}
+
+#[derive(Debug)]
+/// # Linear layer with an optional LoRA adapter
+/// Wraps a base projection (`nn::Linear` or equivalent module). The adapter is only created if the name of
+/// the layer is part of the `target_modules` of the LoRA configuration. The adapters of projections storing
+/// their weights as (*in_features*, *out_features*) are marked with a non-trainable `<layer>.lora_fan_in_fan_out`
+/// variable, used to merge them into the base weights.
+pub struct LoraLinear<M = nn::Linear> {
+    /// Base projection
+    pub base: M,
+    /// Low-rank update, if the layer is targeted
+    pub lora: Option<LoraLayer>,
+}
+
+impl<M: LoraBaseLayer> LoraLinear<M> {
+    /// Wraps a base projection registered at path `p`, adding an adapter if the last component of the path is
+    /// one of the target modules of the LoRA configuration.
+    ///
+    /// # Arguments
+    ///
+    /// * `p` - Variable store path of the base projection
+    /// * `base` - Base projection
+    /// * `in_dim` - Input dimension of the projection
+    /// * `out_dim` - Output dimension of the projection
+    /// * `config` - Optional LoRA configuration
+    pub fn new<'p, P>(
+        p: P,
+        base: M,
+        in_dim: i64,
+        out_dim: i64,
+        config: Option<&LoraConfig>,
+    ) -> LoraLinear<M>
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let p = p.borrow();
+        let lora = config
+            .filter(|config| config.targets(p.components().last().unwrap_or_default()))
+            .map(|config| {
+                if M::FAN_IN_FAN_OUT {
+                    let _ = p.ones_no_train(&LORA_FAN_IN_FAN_OUT_SUFFIX[1..], &[1]);
+                }
+                LoraLayer::new(p, in_dim, out_dim, config)
+            });
+        LoraLinear { base, lora }
+    }
+}
+
+impl LoraLinear<nn::Linear> {
+    /// Base weight of the layer with the low-rank update added, of shape (*out_features*, *in_features*)
+    pub fn merged_weight(&self) -> Tensor {
+        match &self.lora {
+            Some(lora) => &self.base.ws + lora.delta_weight(),
+            None => self.base.ws.shallow_clone(),
+        }
+    }
+}
+
+impl<M: Module> ModuleT for LoraLinear<M> {
+    fn forward_t(&self, xs: &Tensor, train: bool) -> Tensor {
+        let output = xs.apply(&self.base);
+        match &self.lora {
+            Some(lora) => output + xs.apply_t(lora, train),
+            None => output,
+        }
+    }
+}
+
+/// Creates a linear layer at path `p` with an optional LoRA adapter
+pub(crate) fn lora_linear<'p, P>(
+    p: P,
+    in_dim: i64,
+    out_dim: i64,
+    linear_config: nn::LinearConfig,
+    lora_config: Option<&LoraConfig>,
+) -> LoraLinear
+where
+    P: Borrow<nn::Path<'p>>,
+{
+    let p = p.borrow();
+    LoraLinear::new(
+        p,
+        nn::linear(p, in_dim, out_dim, linear_config),
+        in_dim,
+        out_dim,
+        lora_config,
+    )
+}
+
+/// Freezes all the variables of a `VarStore` except for the LoRA adapter weights and the `modules_to_save` of
+/// the LoRA configuration, so that only these are updated by an optimizer.
+///
+/// # Arguments
+///
+/// * `var_store` - `VarStore` of a model created with a LoRA configuration
+/// * `config` - LoRA configuration of the model
+pub fn mark_only_lora_as_trainable(var_store: &VarStore, config: &LoraConfig) {
+    for (name, variable) in var_store.variables() {
+        if !(is_lora_weight(&name) || config.saves(&name)) {
+            let _ = variable.set_requires_grad(false);
+        }
+    }
+}
+
+/// Disables the active LoRA adapter of a model by setting its `B` matrices to zero. The model then
+/// behaves as the base model.
+///
+/// # Arguments
+///
+/// * `var_store` - `VarStore` of a model created with a LoRA configuration
+pub fn deactivate_lora(var_store: &VarStore) {
+    no_grad(|| {
+        for (name, variable) in var_store.variables() {
+            if name.ends_with(LORA_B_SUFFIX) {
+                let _ = variable.shallow_clone().zero_();
+            }
+        }
+    });
+}
+
+/// # Set of trained LoRA adapter weights
+pub struct LoraAdapter {
+    /// Configuration the adapter was trained with
+    pub config: LoraConfig,
+    weights: HashMap<String, Tensor>,
+}
+
+impl LoraAdapter {
+    /// Extracts a copy of the adapter weights of a model created with a LoRA configuration, including the
+    /// layout markers of the adapted layers and the weights of the `modules_to_save`
+    ///
+    /// # Arguments
+    ///
+    /// * `var_store` - `VarStore` of the model
+    /// * `config` - LoRA configuration of the model
+    pub fn from_var_store(var_store: &VarStore, config: LoraConfig) -> LoraAdapter {
+        let weights = var_store
+            .variables()
+            .into_iter()
+            .filter(|(name, _)| {
+                is_lora_weight(name)
+                    || name.ends_with(LORA_FAN_IN_FAN_OUT_SUFFIX)
+                    || config.saves(name)
+            })
+            .map(|(name, variable)| (name, variable.detach().copy()))
+            .collect::<HashMap<String, Tensor>>();
+        LoraAdapter { config, weights }
+    }
+
+    /// Loads an adapter saved with `LoraAdapter::save` from a directory containing `adapter_config.json`
+    /// and `adapter_model.ot`
+    ///
+    /// # Arguments
+    ///
+    /// * `directory` - Directory containing the adapter files
+    /// * `device` - `Device` on which the adapter weights are loaded
+    pub fn load<P: AsRef<Path>>(
+        directory: P,
+        device: Device,
+    ) -> Result<LoraAdapter, RustBertError> {
+        let directory = directory.as_ref();
+        let config_file = BufReader::new(File::open(directory.join(ADAPTER_CONFIG_FILE))?);
+        let config = serde_json::from_reader(config_file).map_err(|error| {
+            RustBertError::InvalidConfigurationError(format!(
+                "Could not parse the adapter configuration: {error}"
+            ))
+        })?;
+        let weights = Tensor::load_multi_with_device(directory.join(ADAPTER_WEIGHTS_FILE), device)?
+            .into_iter()
+            .collect();
+        Ok(LoraAdapter { config, weights })
+    }
+
+    /// Saves the adapter configuration (`adapter_config.json`) and weights (`adapter_model.ot`) to a directory
+    ///
+    /// # Arguments
+    ///
+    /// * `directory` - Directory in which the adapter is saved (created if it does not exist)
+    pub fn save<P: AsRef<Path>>(&self, directory: P) -> Result<(), RustBertError> {
+        let directory = directory.as_ref();
+        fs::create_dir_all(directory)?;
+        let config_file = BufWriter::new(File::create(directory.join(ADAPTER_CONFIG_FILE))?);
+        serde_json::to_writer_pretty(config_file, &self.config)
+            .map_err(|error| RustBertError::IOError(error.to_string()))?;
+        let weights = self.weights.iter().collect::<Vec<(&String, &Tensor)>>();
+        Tensor::save_multi(&weights, directory.join(ADAPTER_WEIGHTS_FILE))?;
+        Ok(())
+    }
+
+    /// Activates the adapter on a model created with LoRA layers, replacing the adapter currently active and
+    /// the weights of the `modules_to_save`. The adapter must have the same rank as the model LoRA layers and
+    /// only target layers with an adapter.
+    ///
+    /// # Arguments
+    ///
+    /// * `var_store` - `VarStore` of the model
+    /// * `model_config` - LoRA configuration the model was created with
+    pub fn activate(
+        &self,
+        var_store: &VarStore,
+        model_config: &LoraConfig,
+    ) -> Result<(), RustBertError> {
+        let variables = var_store.variables();
+        for name in self.weights.keys() {
+            if !variables.contains_key(name) {
+                return Err(RustBertError::ValueError(format!(
+                    "The model has no variable for the adapter weight {name}"
+                )));
+            }
+        }
+        let rescaling = self.config.scaling() / model_config.scaling();
+        deactivate_lora(var_store);
+        no_grad(|| {
+            for (name, weight) in &self.weights {
+                let weight = if name.ends_with(LORA_B_SUFFIX) {
+                    weight * rescaling
+                } else {
+                    weight.shallow_clone()
+                };
+                variables[name].shallow_clone().f_copy_(&weight)?;
+            }
+            Ok(())
+        })
+    }
+
+    /// Merges the adapter into the base weights of a model (the model does not need LoRA layers). The merged
+    /// model has the same inference cost as the base model.
+    ///
+    /// # Arguments
+    ///
+    /// * `var_store` - `VarStore` of the model
+    pub fn merge(&self, var_store: &VarStore) -> Result<(), RustBertError> {
+        self.update_base_weights(var_store, 1.0)
+    }
+
+    /// Removes the adapter from base weights it was previously merged into with `LoraAdapter::merge`
+    ///
+    /// # Arguments
+    ///
+    /// * `var_store` - `VarStore` of the model
+    pub fn unmerge(&self, var_store: &VarStore) -> Result<(), RustBertError> {
+        self.update_base_weights(var_store, -1.0)
+    }
+
+    fn update_base_weights(&self, var_store: &VarStore, sign: f64) -> Result<(), RustBertError> {
+        let variables = var_store.variables();
+        let scaling = sign * self.config.scaling();
+        no_grad(|| {
+            for (name, lora_a) in &self.weights {
+                if let Some(layer_name) = name.strip_suffix(LORA_A_SUFFIX) {
+                    let lora_b = self
+                        .weights
+                        .get(&format!("{layer_name}{LORA_B_SUFFIX}"))
+                        .ok_or_else(|| {
+                            RustBertError::ValueError(format!(
+                                "Missing LoRA B matrix for layer {layer_name}"
+                            ))
+                        })?;
+                    let base_weight =
+                        variables
+                            .get(&format!("{layer_name}.weight"))
+                            .ok_or_else(|| {
+                                RustBertError::ValueError(format!(
+                                    "The model has no weight for the adapted layer {layer_name}"
+                                ))
+                            })?;
+                    let mut delta_weight = lora_b.matmul(lora_a) * scaling;
+                    if self.config.fan_in_fan_out
+                        || self
+                            .weights
+                            .contains_key(&format!("{layer_name}{LORA_FAN_IN_FAN_OUT_SUFFIX}"))
+                    {
+                        delta_weight = delta_weight.tr();
+                    }
+                    if delta_weight.size() != base_weight.size() {
+                        return Err(RustBertError::ValueError(format!(
+                            "The adapter update for layer {layer_name} does not match the base weight shape"
+                        )));
+                    }
+                    let _ = base_weight
+                        .shallow_clone()
+                        .f_add_(&delta_weight.to_device(base_weight.device()))?;
+                }
+            }
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::gpt2::{Gpt2Config, Gpt2Model};
+    use tch::Kind;
+
+    #[test]
+    fn test_lora_merge() {
+        let var_store = VarStore::new(Device::Cpu);
+        let config = LoraConfig::new(2, 4.0, &["query"]);
+        let layer = lora_linear(
+            var_store.root() / "query",
+            3,
+            4,
+            Default::default(),
+            Some(&config),
+        );
+        let untargeted = lora_linear(
+            var_store.root() / "key",
+            3,
+            4,
+            Default::default(),
+            Some(&config),
+        );
+        assert!(layer.lora.is_some());
+        assert!(untargeted.lora.is_none());
+
+        no_grad(|| {
+            let _ = layer
+                .lora
+                .as_ref()
+                .unwrap()
+                .lora_b
+                .shallow_clone()
+                .fill_(0.5);
+        });
+        let input = Tensor::rand(&[2, 3], (Kind::Float, Device::Cpu));
+        let adapted_output = no_grad(|| input.apply_t(&layer, false));
+
+        let adapter = LoraAdapter::from_var_store(&var_store, config);
+        deactivate_lora(&var_store);
+        adapter.merge(&var_store).unwrap();
+        let merged_output = no_grad(|| input.apply_t(&layer, false));
+        assert!(adapted_output.allclose(&merged_output, 1e-5, 1e-5, false));
+    }
+
+    #[test]
+    fn test_lora_merge_gpt2() {
+        let var_store = VarStore::new(Device::Cpu);
+        let lora_config = LoraConfig::new(2, 4.0, &["c_attn", "c_proj"]);
+        let config = Gpt2Config {
+            n_ctx: 16,
+            n_embd: 8,
+            n_head: 2,
+            n_layer: 1,
+            n_positions: 16,
+            vocab_size: 16,
+            lora: Some(lora_config.clone()),
+            ..Default::default()
+        };
+        let model = Gpt2Model::new(var_store.root(), &config);
+        no_grad(|| {
+            for (name, variable) in var_store.variables() {
+                if name.ends_with(LORA_B_SUFFIX) {
+                    let _ = variable.shallow_clone().normal_(0.0, 0.5);
+                }
+            }
+        });
+        let input_ids = Tensor::of_slice(&[3i64, 1, 4, 1, 5]).unsqueeze(0);
+        let forward = || {
+            no_grad(|| model.forward_t(Some(&input_ids), None, None, None, None, None, false))
+                .unwrap()
+                .output
+        };
+        let adapted_output = forward();
+
+        // The GPT2 Conv1D layers store their weights as (in_features, out_features), which cannot be
+        // detected from the shape of the square `c_proj` layers: the layout is recorded for each layer
+        let adapter = LoraAdapter::from_var_store(&var_store, lora_config);
+        assert!(!adapter.config.fan_in_fan_out);
+        assert!(adapter
+            .weights
+            .contains_key("h.0.attn.c_proj.lora_fan_in_fan_out"));
+        deactivate_lora(&var_store);
+        let base_output = forward();
+        assert!(!base_output.allclose(&adapted_output, 1e-5, 1e-5, false));
+
+        adapter.merge(&var_store).unwrap();
+        assert!(forward().allclose(&adapted_output, 1e-5, 1e-5, false));
+        adapter.unmerge(&var_store).unwrap();
+        assert!(forward().allclose(&base_output, 1e-5, 1e-5, false));
+    }
+
+    fn randomize_adapter(var_store: &VarStore) {
+        no_grad(|| {
+            for (name, variable) in var_store.variables() {
+                if name.ends_with(LORA_B_SUFFIX) || name.starts_with("classifier") {
+                    let _ = variable.shallow_clone().normal_(0.0, 0.5);
+                }
+            }
+        });
+    }
+
+    #[test]
+    fn test_lora_activate() {
+        let var_store = VarStore::new(Device::Cpu);
+        let mut config = LoraConfig::new(2, 4.0, &["query"]);
+        config.modules_to_save = vec!["classifier".to_string()];
+        let layer = lora_linear(
+            var_store.root() / "query",
+            3,
+            4,
+            Default::default(),
+            Some(&config),
+        );
+        let classifier = nn::linear(var_store.root() / "classifier", 4, 2, Default::default());
+        let _dense = nn::linear(var_store.root() / "dense", 4, 4, Default::default());
+
+        mark_only_lora_as_trainable(&var_store, &config);
+        let variables = var_store.variables();
+        assert!(variables["query.lora_A.weight"].requires_grad());
+        assert!(variables["query.lora_B.weight"].requires_grad());
+        assert!(variables["classifier.weight"].requires_grad());
+        assert!(!variables["query.weight"].requires_grad());
+        assert!(!variables["dense.weight"].requires_grad());
+
+        let input = Tensor::rand(&[2, 3], (Kind::Float, Device::Cpu));
+        let forward = || no_grad(|| input.apply_t(&layer, false));
+        let forward_classifier = || forward().apply(&classifier);
+        let base_output = forward();
+
+        randomize_adapter(&var_store);
+        let output_a = forward_classifier();
+        let adapter_a = LoraAdapter::from_var_store(&var_store, config.clone());
+        randomize_adapter(&var_store);
+        let output_b = forward_classifier();
+        let adapter_b = LoraAdapter::from_var_store(&var_store, config.clone());
+        assert!(!output_a.allclose(&output_b, 1e-5, 1e-5, false));
+
+        // Hot-swapping replaces both the LoRA matrices and the classification head
+        adapter_a.activate(&var_store, &config).unwrap();
+        assert!(forward_classifier().allclose(&output_a, 1e-5, 1e-5, false));
+        adapter_b.activate(&var_store, &config).unwrap();
+        assert!(forward_classifier().allclose(&output_b, 1e-5, 1e-5, false));
+
+        // An adapter trained with a different scaling is rescaled to the scaling of the model
+        let adapter_b_update = forward() - &base_output;
+        let rescaled_adapter = LoraAdapter {
+            config: LoraConfig {
+                lora_alpha: 8.0,
+                ..config.clone()
+            },
+            weights: adapter_b
+                .weights
+                .iter()
+                .map(|(name, weight)| (name.clone(), weight.shallow_clone()))
+                .collect(),
+        };
+        rescaled_adapter.activate(&var_store, &config).unwrap();
+        assert!((forward() - &base_output).allclose(&(adapter_b_update * 2), 1e-5, 1e-5, false));
+
+        deactivate_lora(&var_store);
+        assert!(forward().allclose(&base_output, 1e-5, 1e-5, false));
+
+        let untargeted_config = LoraConfig::new(2, 4.0, &["key"]);
+        let untargeted_var_store = VarStore::new(Device::Cpu);
+        let _key = lora_linear(
+            untargeted_var_store.root() / "key",
+            3,
+            4,
+            Default::default(),
+            Some(&untargeted_config),
+        );
+        assert!(adapter_a
+            .activate(&untargeted_var_store, &untargeted_config)
+            .is_err());
+    }
+
+    #[test]
+    fn test_lora_save_load() {
+        let var_store = VarStore::new(Device::Cpu);
+        let lora_config = LoraConfig::new(2, 4.0, &["c_attn", "c_proj"]);
+        let config = Gpt2Config {
+            n_ctx: 16,
+            n_embd: 8,
+            n_head: 2,
+            n_layer: 1,
+            n_positions: 16,
+            vocab_size: 16,
+            lora: Some(lora_config.clone()),
+            ..Default::default()
+        };
+        let model = Gpt2Model::new(var_store.root(), &config);
+        randomize_adapter(&var_store);
+        let input_ids = Tensor::of_slice(&[3i64, 1, 4, 1, 5]).unsqueeze(0);
+        let forward = || {
+            no_grad(|| model.forward_t(Some(&input_ids), None, None, None, None, None, false))
+                .unwrap()
+                .output
+        };
+        let adapted_output = forward();
+
+        let directory = tempfile::tempdir().unwrap();
+        LoraAdapter::from_var_store(&var_store, lora_config.clone())
+            .save(directory.path())
+            .unwrap();
+        let adapter = LoraAdapter::load(directory.path(), Device::Cpu).unwrap();
+        assert_eq!(adapter.config, lora_config);
+        // LoRA matrices of `c_attn` and `c_proj`, and their layout markers
+        assert_eq!(adapter.weights.len(), 6);
+
+        deactivate_lora(&var_store);
+        assert!(!forward().allclose(&adapted_output, 1e-5, 1e-5, false));
+        adapter.activate(&var_store, &lora_config).unwrap();
+        assert!(forward().allclose(&adapted_output, 1e-5, 1e-5, false));
+
+        // The layout markers are preserved, so that the loaded adapter can be merged
+        deactivate_lora(&var_store);
+        adapter.merge(&var_store).unwrap();
+        assert!(forward().allclose(&adapted_output, 1e-5, 1e-5, false));
+    }
+}
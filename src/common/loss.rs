@@ -0,0 +1,194 @@
+// Copyright 2018-present, the HuggingFace Inc. team
+// Copyright 2023 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! # Loss functions for the model heads
+//! Losses returned by the model heads when labels are provided, following the conventions of the
+//! Python Transformers library so that models can be fine-tuned with a `tch::nn::Optimizer`.
+
+use serde::{Deserialize, Serialize};
+use tch::{Kind, Reduction, Tensor};
+
+/// Label value ignored by the cross-entropy losses (e.g. padding or non-masked tokens)
+pub const IGNORE_INDEX: i64 = -100;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+/// # Type of sequence classification problem, defining how the model outputs are interpreted
+/// Follows the `problem_type` field of Transformers configuration files.
+pub enum ProblemType {
+    /// Single raw output (e.g. semantic textual similarity)
+    Regression,
+    /// Mutually exclusive labels, with probabilities given by a softmax
+    SingleLabelClassification,
+    /// Independent labels, with probabilities given by a sigmoid
+    MultiLabelClassification,
+}
+
+/// Mean cross-entropy loss between logits of shape (*..., num_labels*) and class indices of shape (*...*).
+/// Positions with a label equal to `IGNORE_INDEX` (-100) do not contribute to the loss.
+///
+/// # Arguments
+///
+/// * `logits` - `Tensor` of shape (*..., num_labels*)
+/// * `labels` - `Tensor` of class indices with the leading dimensions of the logits
+///
+/// # Returns
+///
+/// * `Tensor` scalar loss
+pub fn cross_entropy_loss(logits: &Tensor, labels: &Tensor) -> Tensor {
+    let num_labels = *logits.size().last().unwrap();
+    logits.view([-1, num_labels]).cross_entropy_loss::<Tensor>(
+        &labels.view([-1]).to_kind(Kind::Int64),
+        None,
+        Reduction::Mean,
+        IGNORE_INDEX,
+        0.0,
+    )
+}
+
+/// Loss for sequence classification heads, depending on the problem type:
+/// - regression: mean squared error
+/// - single-label classification: cross-entropy with integer labels of shape (*batch size*)
+/// - multi-label classification: binary cross-entropy with logits and multi-hot labels of shape (*batch size*, *num_labels*)
+///
+/// If the problem type of the model configuration is not set, it is inferred as in the Transformers library:
+/// regression if the model has a single label, single-label classification for integer labels and multi-label
+/// classification otherwise.
+///
+/// # Arguments
+///
+/// * `logits` - `Tensor` of shape (*batch size*, *num_labels*)
+/// * `labels` - `Tensor` of class indices, regression targets or multi-hot targets
+/// * `problem_type` - Optional `ProblemType` of the model configuration
+///
+/// # Returns
+///
+/// * `Tensor` scalar loss
+pub fn sequence_classification_loss(
+    logits: &Tensor,
+    labels: &Tensor,
+    problem_type: Option<ProblemType>,
+) -> Tensor {
+    let num_labels = *logits.size().last().unwrap();
+    let problem_type = problem_type.unwrap_or(if num_labels == 1 {
+        ProblemType::Regression
+    } else if labels.is_floating_point() {
+        ProblemType::MultiLabelClassification
+    } else {
+        ProblemType::SingleLabelClassification
+    });
+    match problem_type {
+        ProblemType::Regression => logits
+            .view([-1])
+            .mse_loss(&labels.view([-1]).to_kind(logits.kind()), Reduction::Mean),
+        ProblemType::SingleLabelClassification => cross_entropy_loss(logits, labels),
+        ProblemType::MultiLabelClassification => logits.binary_cross_entropy_with_logits::<Tensor>(
+            &labels.to_kind(logits.kind()),
+            None,
+            None,
+            Reduction::Mean,
+        ),
+    }
+}
+
+/// Loss for extractive question answering heads: average of the cross-entropy losses of the start and end
+/// positions. Positions outside of the sequence are clamped to the sequence length and ignored.
+///
+/// # Arguments
+///
+/// * `start_logits` - `Tensor` of shape (*batch size*, *sequence_length*)
+/// * `end_logits` - `Tensor` of shape (*batch size*, *sequence_length*)
+/// * `start_positions` - `Tensor` of shape (*batch size*) with the answer start positions
+/// * `end_positions` - `Tensor` of shape (*batch size*) with the answer end positions
+///
+/// # Returns
+///
+/// * `Tensor` scalar loss
+pub fn question_answering_loss(
+    start_logits: &Tensor,
+    end_logits: &Tensor,
+    start_positions: &Tensor,
+    end_positions: &Tensor,
+) -> Tensor {
+    let ignored_index = *start_logits.size().last().unwrap();
+    let position_loss = |logits: &Tensor, positions: &Tensor| {
+        logits.cross_entropy_loss::<Tensor>(
+            &positions
+                .view([-1])
+                .to_kind(Kind::Int64)
+                .clamp(0, ignored_index),
+            None,
+            Reduction::Mean,
+            ignored_index,
+            0.0,
+        )
+    };
+    (position_loss(start_logits, start_positions) + position_loss(end_logits, end_positions)) / 2
+}
+
+/// Loss for causal language models: cross-entropy between the logits at each position and the label of the
+/// next position. Labels equal to `IGNORE_INDEX` (-100) do not contribute to the loss.
+///
+/// # Arguments
+///
+/// * `lm_logits` - `Tensor` of shape (*batch size*, *sequence_length*, *vocab_size*)
+/// * `labels` - `Tensor` of shape (*batch size*, *sequence_length*), usually equal to the input ids
+///
+/// # Returns
+///
+/// * `Tensor` scalar loss
+pub fn causal_lm_loss(lm_logits: &Tensor, labels: &Tensor) -> Tensor {
+    let sequence_length = lm_logits.size()[1];
+    cross_entropy_loss(
+        &lm_logits.narrow(1, 0, sequence_length - 1),
+        &labels.narrow(1, 1, sequence_length - 1),
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use tch::Device;
+
+    #[test]
+    fn test_cross_entropy_ignore_index() {
+        let logits = Tensor::of_slice(&[2.0f32, 0.0, 0.0, 5.0, 1.0, 1.0]).view([1, 3, 2]);
+        let labels = Tensor::of_slice(&[0i64, IGNORE_INDEX, 0]).view([1, 3]);
+        let loss = f64::from(cross_entropy_loss(&logits, &labels));
+        let expected = ((1.0 + (-2.0f64).exp()).ln() + 2.0f64.ln()) / 2.0;
+        assert!((loss - expected).abs() < 1e-5);
+
+        let start_logits = Tensor::zeros(&[2, 4], (Kind::Float, Device::Cpu));
+        let positions = Tensor::of_slice(&[1i64, 10]);
+        let loss = f64::from(question_answering_loss(
+            &start_logits,
+            &start_logits,
+            &positions,
+            &positions,
+        ));
+        assert!((loss - 4.0f64.ln()).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_sequence_classification_problem_type() {
+        let logits = Tensor::zeros(&[1, 1], (Kind::Float, Device::Cpu));
+        let labels = Tensor::ones(&[1, 1], (Kind::Float, Device::Cpu));
+        let loss = f64::from(sequence_classification_loss(&logits, &labels, None));
+        assert!((loss - 1.0).abs() < 1e-5);
+        let loss = f64::from(sequence_classification_loss(
+            &logits,
+            &labels,
+            Some(ProblemType::MultiLabelClassification),
+        ));
+        assert!((loss - 2.0f64.ln()).abs() < 1e-5);
+    }
+}
@@ -1,10 +1,13 @@
 pub(crate) mod activations;
+pub(crate) mod attention;
 pub mod config;
 pub(crate) mod dropout;
 pub(crate) mod embeddings;
 pub mod error;
 pub(crate) mod kind;
 pub(crate) mod linear;
+pub mod lora;
+pub mod loss;
 pub mod resources;
 pub(crate) mod summary;
 
@@ -10,7 +10,9 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use crate::common::attention::{narrow_attention_mask, try_chunked_attention};
 use crate::common::dropout::XDropout;
+use crate::common::lora::{lora_linear, LoraLinear};
 use crate::deberta::deberta_model::{x_softmax, PositionAttentionType, PositionAttentionTypes};
 use crate::deberta::{BaseDebertaLayerNorm, DebertaConfig};
 use crate::RustBertError;
@@ -42,7 +44,7 @@ pub fn build_relative_position(query_size: i64, key_size: i64, device: Device) -
 }
 
 pub struct DebertaDisentangledSelfAttention {
-    in_proj: nn::Linear,
+    in_proj: LoraLinear,
     q_bias: Tensor,
     v_bias: Tensor,
     num_attention_heads: i64,
@@ -55,6 +57,7 @@ pub struct DebertaDisentangledSelfAttention {
     pos_dropout: Option<XDropout>,
     dropout: XDropout,
     output_attentions: bool,
+    attention_chunk_size: Option<i64>,
 }
 
 impl DebertaDisentangledSelfAttention {
@@ -135,6 +138,7 @@ impl DebertaDisentangledSelfAttention {
         relative_pos: Option<&Tensor>,
         relative_embeddings: &Tensor,
         scale_factor: f64,
+        is_query_block: bool,
     ) -> Result<Tensor, RustBertError> {
         let mut key_layer_size = key_layer.size();
         key_layer_size.reverse();
@@ -202,33 +206,111 @@ impl DebertaDisentangledSelfAttention {
             let pos_query_layer = self.transpose_for_scores(&relative_embeddings.apply(pos_q_proj));
             let pos_query_layer = &pos_query_layer
                 / (*pos_query_layer.size().last().unwrap() as f64 * scale_factor).sqrt();
-            let r_pos = if query_layer_size[1] != key_layer_size[1] {
-                build_relative_position(key_layer_size[1], key_layer_size[1], query_layer.device())
+            let p2c_att = if is_query_block {
+                // Block of queries of a self-attention layer: the positions of the queries relative to the keys
+                // are given by the transposed relative positions of the block
+                let p2c_pos = (relative_pos.transpose(-1, -2) + attention_span)
+                    .clamp(0, attention_span * 2 - 1);
+                let query_layer_size = query_layer.size();
+                key_layer
+                    .matmul(&pos_query_layer.transpose(-1, -2))
+                    .gather(
+                        -1,
+                        &p2c_pos.expand(
+                            &[
+                                query_layer_size[0],
+                                query_layer_size[1],
+                                key_layer_size[1],
+                                query_layer_size[2],
+                            ],
+                            true,
+                        ),
+                        true,
+                    )
+                    .transpose(-1, -2)
             } else {
-                relative_pos.copy()
+                let r_pos = if query_layer_size[1] != key_layer_size[1] {
+                    build_relative_position(
+                        key_layer_size[1],
+                        key_layer_size[1],
+                        query_layer.device(),
+                    )
+                } else {
+                    relative_pos.copy()
+                };
+                let p2c_pos = (-r_pos + attention_span).clamp(0, attention_span * 2 - 1);
+                let mut p2c_att = key_layer
+                    .matmul(&pos_query_layer.transpose(-1, -2))
+                    .gather(
+                        -1,
+                        &self.p2c_dynamic_expand(&p2c_pos, query_layer, key_layer),
+                        true,
+                    )
+                    .transpose(-1, -2);
+                if query_layer_size[1] != key_layer_size[1] {
+                    let pos_index = relative_pos.select(3, 0).unsqueeze(-1);
+                    p2c_att = p2c_att.gather(
+                        -2,
+                        &self.pos_dynamic_expand(&pos_index, &p2c_att, key_layer),
+                        true,
+                    );
+                }
+                p2c_att
             };
-            let p2c_pos = (-r_pos + attention_span).clamp(0, attention_span * 2 - 1);
-            let mut p2c_att = key_layer
-                .matmul(&pos_query_layer.transpose(-1, -2))
-                .gather(
-                    -1,
-                    &self.p2c_dynamic_expand(&p2c_pos, query_layer, key_layer),
-                    true,
-                )
-                .transpose(-1, -2);
-            if query_layer_size[1] != key_layer_size[1] {
-                let pos_index = relative_pos.select(3, 0).unsqueeze(-1);
-                p2c_att = p2c_att.gather(
-                    -2,
-                    &self.pos_dynamic_expand(&pos_index, &p2c_att, key_layer),
-                    true,
-                );
-            }
             score = score + p2c_att;
         }
 
         Ok(score)
     }
+
+    /// Attention of the (scaled) queries to the keys, returning the context (before merging the heads) and attention probabilities.
+    /// If `is_query_block` is true, the queries are a block of the queries of a self-attention layer and `relative_pos`
+    /// contains the relative positions of this block.
+    fn attention(
+        &self,
+        query_layer: &Tensor,
+        key_layer: &Tensor,
+        value_layer: &Tensor,
+        attention_mask: &Tensor,
+        relative_pos: Option<&Tensor>,
+        relative_embeddings: Option<&Tensor>,
+        scale_factor: f64,
+        is_query_block: bool,
+        train: bool,
+    ) -> Result<(Tensor, Tensor), RustBertError> {
+        let mut attention_scores = query_layer.matmul(&key_layer.transpose(-1, -2));
+
+        if let Some(relative_embeddings) = relative_embeddings {
+            let relative_attention = self.disentangled_att_bias(
+                query_layer,
+                key_layer,
+                relative_pos,
+                relative_embeddings,
+                scale_factor,
+                is_query_block,
+            )?;
+            attention_scores = attention_scores + relative_attention;
+        }
+
+        if let Some(head_logits_proj) = &self.head_logits_proj {
+            attention_scores = attention_scores
+                .permute(&[0, 2, 3, 1])
+                .apply(head_logits_proj)
+                .permute(&[0, 3, 1, 2]);
+        }
+
+        let mut attention_probs =
+            x_softmax(&attention_scores, attention_mask, -1).apply_t(&self.dropout, train);
+
+        if let Some(head_weights_proj) = &self.head_weights_proj {
+            attention_probs = attention_probs
+                .permute(&[0, 2, 3, 1])
+                .apply(head_weights_proj)
+                .permute(&[0, 3, 1, 2]);
+        }
+
+        Ok((attention_probs.matmul(value_layer), attention_probs))
+    }
 }
 
 impl DisentangledSelfAttention for DebertaDisentangledSelfAttention {
@@ -247,11 +329,12 @@ impl DisentangledSelfAttention for DebertaDisentangledSelfAttention {
             ..Default::default()
         };
 
-        let in_proj = nn::linear(
+        let in_proj = lora_linear(
             p / "in_proj",
             config.hidden_size,
             all_head_size * 3,
             linear_no_bias_config,
+            config.lora.as_ref(),
         );
         let q_bias = p.var("q_bias", &[all_head_size], Init::Const(0.0));
         let v_bias = p.var("v_bias", &[all_head_size], Init::Const(0.0));
@@ -333,6 +416,7 @@ impl DisentangledSelfAttention for DebertaDisentangledSelfAttention {
             pos_dropout,
             dropout,
             output_attentions,
+            attention_chunk_size: config.attention_chunk_size,
         }
     }
 
@@ -346,7 +430,10 @@ impl DisentangledSelfAttention for DebertaDisentangledSelfAttention {
         train: bool,
     ) -> Result<(Tensor, Option<Tensor>), RustBertError> {
         let (query_layer, key_layer, value_layer) = if let Some(query_states) = query_states {
-            let ws = self.in_proj.ws.chunk(self.num_attention_heads * 3, 0);
+            let ws = self
+                .in_proj
+                .merged_weight()
+                .chunk(self.num_attention_heads * 3, 0);
             let query_key_value_weights = (0..3)
                 .map(|k| {
                     Tensor::cat(
@@ -377,7 +464,7 @@ impl DisentangledSelfAttention for DebertaDisentangledSelfAttention {
             ));
             (query_layer, key_layer, value_layer)
         } else {
-            let qp = hidden_states.apply(&self.in_proj);
+            let qp = hidden_states.apply_t(&self.in_proj, train);
             let mut layers = self.transpose_for_scores(&qp).chunk(3, -1);
             let value_layer = layers.pop().unwrap();
             let key_layer = layers.pop().unwrap();
@@ -393,42 +480,61 @@ impl DisentangledSelfAttention for DebertaDisentangledSelfAttention {
         let scale_factor = 1.0 + self.pos_att_type.len() as f64;
         let scale = (*query_layer.size().last().unwrap() as f64 * scale_factor).sqrt();
         let query_layer = query_layer / scale;
-        let mut attention_scores = query_layer.matmul(&key_layer.transpose(-1, -2));
-
-        if let Some(relative_embeddings) = relative_embeddings {
-            let relative_embeddings =
-                relative_embeddings.apply_t(self.pos_dropout.as_ref().unwrap(), train);
-            let relative_attention = self.disentangled_att_bias(
-                &query_layer,
-                &key_layer,
-                relative_pos,
-                &relative_embeddings,
-                scale_factor,
-            )?;
-            attention_scores = attention_scores + relative_attention;
-        }
-
-        if let Some(head_logits_proj) = &self.head_logits_proj {
-            attention_scores = attention_scores
-                .permute(&[0, 2, 3, 1])
-                .apply(head_logits_proj)
-                .permute(&[0, 3, 1, 2]);
-        }
-
-        let mut attention_probs =
-            x_softmax(&attention_scores, attention_mask, -1).apply_t(&self.dropout, train);
-
-        if let Some(head_weights_proj) = &self.head_weights_proj {
-            attention_probs = attention_probs
-                .permute(&[0, 2, 3, 1])
-                .apply(head_weights_proj)
-                .permute(&[0, 3, 1, 2]);
-        }
+        let relative_embeddings = relative_embeddings.map(|relative_embeddings| {
+            relative_embeddings.apply_t(self.pos_dropout.as_ref().unwrap(), train)
+        });
+
+        let (context_layer, attention_probs) = match self.attention_chunk_size {
+            Some(attention_chunk_size) if !self.output_attentions && query_states.is_none() => {
+                let query_length = query_layer.size()[2];
+                let relative_pos = relative_embeddings.as_ref().map(|_| match relative_pos {
+                    Some(relative_pos) => relative_pos.shallow_clone(),
+                    None => build_relative_position(
+                        query_length,
+                        key_layer.size()[2],
+                        query_layer.device(),
+                    ),
+                });
+                let context_layer = try_chunked_attention(
+                    query_length,
+                    attention_chunk_size,
+                    |query_offset, chunk_length| {
+                        let relative_pos = relative_pos.as_ref().map(|relative_pos| {
+                            relative_pos.narrow(-2, query_offset, chunk_length)
+                        });
+                        self.attention(
+                            &query_layer.narrow(2, query_offset, chunk_length),
+                            &key_layer,
+                            &value_layer,
+                            &narrow_attention_mask(attention_mask, 2, query_offset, chunk_length),
+                            relative_pos.as_ref(),
+                            relative_embeddings.as_ref(),
+                            scale_factor,
+                            true,
+                            train,
+                        )
+                        .map(|(context_layer, _)| context_layer)
+                    },
+                )?;
+                (context_layer, None)
+            }
+            _ => {
+                let (context_layer, attention_probs) = self.attention(
+                    &query_layer,
+                    &key_layer,
+                    &value_layer,
+                    attention_mask,
+                    relative_pos,
+                    relative_embeddings.as_ref(),
+                    scale_factor,
+                    false,
+                    train,
+                )?;
+                (context_layer, Some(attention_probs))
+            }
+        };
 
-        let context_layer = attention_probs
-            .matmul(&value_layer)
-            .permute(&[0, 2, 1, 3])
-            .contiguous();
+        let context_layer = context_layer.permute(&[0, 2, 1, 3]).contiguous();
 
         let mut new_context_layer_shape = context_layer.size();
         let _ = new_context_layer_shape.pop();
@@ -437,7 +543,7 @@ impl DisentangledSelfAttention for DebertaDisentangledSelfAttention {
         let context_layer = context_layer.view(new_context_layer_shape.as_slice());
 
         let attention_probs = if self.output_attentions {
-            Some(attention_probs)
+            attention_probs
         } else {
             None
         };
@@ -447,7 +553,7 @@ impl DisentangledSelfAttention for DebertaDisentangledSelfAttention {
 }
 
 pub struct DebertaSelfOutput<LN: BaseDebertaLayerNorm + Module> {
-    dense: nn::Linear,
+    dense: LoraLinear,
     layer_norm: LN,
     dropout: XDropout,
 }
@@ -458,11 +564,12 @@ impl<LN: BaseDebertaLayerNorm + Module> DebertaSelfOutput<LN> {
         P: Borrow<nn::Path<'p>>,
     {
         let p = p.borrow();
-        let dense = nn::linear(
+        let dense = lora_linear(
             p / "dense",
             config.hidden_size,
             config.hidden_size,
             Default::default(),
+            config.lora.as_ref(),
         );
         let layer_norm = LN::new(
             p / "LayerNorm",
@@ -480,7 +587,7 @@ impl<LN: BaseDebertaLayerNorm + Module> DebertaSelfOutput<LN> {
     pub fn forward_t(&self, hidden_states: &Tensor, input_tensor: &Tensor, train: bool) -> Tensor {
         self.layer_norm.forward(
             &(hidden_states
-                .apply(&self.dense)
+                .apply_t(&self.dense, train)
                 .apply_t(&self.dropout, train)
                 + input_tensor),
         )
@@ -17,6 +17,10 @@ use crate::common::activations::TensorFunction;
 use crate::common::dropout::{Dropout, XDropout};
 use crate::common::embeddings::get_shape_and_device_from_ids_embeddings_pair;
 use crate::common::kind::get_negative_infinity;
+use crate::common::lora::LoraConfig;
+use crate::common::loss::{
+    cross_entropy_loss, question_answering_loss, sequence_classification_loss, ProblemType,
+};
 use crate::deberta::embeddings::DebertaEmbeddings;
 use crate::deberta::encoder::{DebertaEncoder, DebertaEncoderOutput};
 use crate::{Activation, Config, RustBertError};
@@ -148,7 +152,7 @@ impl PositionAttentionTypes {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 /// # DeBERTa model configuration
 /// Defines the DeBERTa model architecture (e.g. number of layers, hidden layer size, label mapping...)
 pub struct DebertaConfig {
@@ -181,8 +185,13 @@ pub struct DebertaConfig {
     pub is_decoder: Option<bool>,
     pub id2label: Option<HashMap<i64, String>>,
     pub label2id: Option<HashMap<String, i64>>,
+    /// Type of problem defining the loss of the sequence classification head (default: inferred from the labels)
+    pub problem_type: Option<ProblemType>,
     pub share_att_key: Option<bool>,
     pub position_buckets: Option<i64>,
+    pub lora: Option<LoraConfig>,
+    /// If set, the attention is computed for blocks of `attention_chunk_size` queries at a time
+    pub attention_chunk_size: Option<i64>,
 }
 
 impl Default for DebertaConfig {
@@ -216,8 +225,11 @@ impl Default for DebertaConfig {
             is_decoder: None,
             id2label: None,
             label2id: None,
+            problem_type: None,
             share_att_key: None,
             position_buckets: None,
+            lora: None,
+            attention_chunk_size: None,
         }
     }
 }
@@ -585,12 +597,14 @@ impl DebertaForMaskedLM {
     /// * `token_type_ids` -Optional segment id of shape (*batch size*, *sequence_length*). Convention is value of 0 for the first sentence (incl. *SEP*) and 1 for the second sentence. If None set to 0.
     /// * `position_ids` - Optional position ids of shape (*batch size*, *sequence_length*). If None, will be incremented from 0.
     /// * `input_embeds` - Optional pre-computed input embeddings of shape (*batch size*, *sequence_length*, *hidden_size*). If None, input ids must be provided (see *input_ids*)
+    /// * `labels` - Optional target token ids of shape (*batch size*, *sequence_length*) used to compute the masked language modeling loss. Positions with a label of -100 (e.g. non-masked tokens) are ignored.
     /// * `train` - boolean flag to turn on/off the dropout layers in the model. Should be set to false for inference.
     ///
     /// # Returns
     ///
     /// * `DebertaMaskedLMOutput` containing:
     ///   - `prediction_scores` - `Tensor` of shape (*batch size*, *sequence_length*, *vocab_size*)
+    ///   - `loss` - `Option<Tensor>` scalar loss, computed if labels are provided
     ///   - `all_hidden_states` - `Option<Vec<Tensor>>` of length *num_hidden_layers* with shape (*batch size*, *sequence_length*, *hidden_size*)
     ///   - `all_attentions` - `Option<Vec<Tensor>>` of length *num_hidden_layers* with shape (*batch size*, *sequence_length*, *hidden_size*)
     ///
@@ -620,6 +634,7 @@ impl DebertaForMaskedLM {
     ///         Some(&token_type_ids),
     ///         Some(&position_ids),
     ///         None,
+    ///         None,
     ///         false,
     ///     )
     /// });
@@ -631,6 +646,7 @@ impl DebertaForMaskedLM {
         token_type_ids: Option<&Tensor>,
         position_ids: Option<&Tensor>,
         input_embeds: Option<&Tensor>,
+        labels: Option<&Tensor>,
         train: bool,
     ) -> Result<DebertaMaskedLMOutput, RustBertError> {
         let model_outputs = self.deberta.forward_t(
@@ -643,7 +659,9 @@ impl DebertaForMaskedLM {
         )?;
 
         let logits = model_outputs.hidden_state.apply(&self.cls);
+        let loss = labels.map(|labels| cross_entropy_loss(&logits, labels));
         Ok(DebertaMaskedLMOutput {
+            loss,
             logits,
             all_hidden_states: model_outputs.all_hidden_states,
             all_attentions: model_outputs.all_attentions,
@@ -709,6 +727,7 @@ pub struct DebertaForSequenceClassification {
     pooler: ContextPooler,
     classifier: nn::Linear,
     dropout: XDropout,
+    problem_type: Option<ProblemType>,
 }
 
 impl DebertaForSequenceClassification {
@@ -772,6 +791,7 @@ impl DebertaForSequenceClassification {
             pooler,
             classifier,
             dropout,
+            problem_type: config.problem_type,
         })
     }
 
@@ -784,12 +804,14 @@ impl DebertaForSequenceClassification {
     /// * `token_type_ids` -Optional segment id of shape (*batch size*, *sequence_length*). Convention is value of 0 for the first sentence (incl. *SEP*) and 1 for the second sentence. If None set to 0.
     /// * `position_ids` - Optional position ids of shape (*batch size*, *sequence_length*). If None, will be incremented from 0.
     /// * `input_embeds` - Optional pre-computed input embeddings of shape (*batch size*, *sequence_length*, *hidden_size*). If None, input ids must be provided (see `input_ids`)
+    /// * `labels` - Optional labels of shape (*batch size*) used to compute the loss: class indices for single-label classification or target values for regression. Multi-label classification uses floating point targets of shape (*batch size*, *num_labels*). The loss follows the `problem_type` of the configuration, inferred from the labels if not set.
     /// * `train` - boolean flag to turn on/off the dropout layers in the model. Should be set to false for inference.
     ///
     /// # Returns
     ///
     /// * `DebertaSequenceClassificationOutput` containing:
     ///   - `logits` - `Tensor` of shape (*batch size*, *num_labels*)
+    ///   - `loss` - `Option<Tensor>` scalar loss, computed if labels are provided
     ///   - `all_hidden_states` - `Option<Vec<Tensor>>` of length *num_hidden_layers* with shape (*batch size*, *sequence_length*, *hidden_size*)
     ///   - `all_attentions` - `Option<Vec<Tensor>>` of length *num_hidden_layers* with shape (*batch size*, *sequence_length*, *hidden_size*)
     ///
@@ -819,6 +841,7 @@ impl DebertaForSequenceClassification {
     ///         Some(&token_type_ids),
     ///         Some(&position_ids),
     ///         None,
+    ///         None,
     ///         false,
     ///     )
     /// });
@@ -830,6 +853,7 @@ impl DebertaForSequenceClassification {
         token_type_ids: Option<&Tensor>,
         position_ids: Option<&Tensor>,
         input_embeds: Option<&Tensor>,
+        labels: Option<&Tensor>,
         train: bool,
     ) -> Result<DebertaSequenceClassificationOutput, RustBertError> {
         let base_model_output = self.deberta.forward_t(
@@ -847,7 +871,10 @@ impl DebertaForSequenceClassification {
             .apply_t(&self.dropout, train)
             .apply(&self.classifier);
 
+        let loss =
+            labels.map(|labels| sequence_classification_loss(&logits, labels, self.problem_type));
         Ok(DebertaSequenceClassificationOutput {
+            loss,
             logits,
             all_hidden_states: base_model_output.all_hidden_states,
             all_attentions: base_model_output.all_attentions,
@@ -933,12 +960,14 @@ impl DebertaForTokenClassification {
     /// * `token_type_ids` -Optional segment id of shape (*batch size*, *sequence_length*). Convention is value of 0 for the first sentence (incl. *SEP*) and 1 for the second sentence. If None set to 0.
     /// * `position_ids` - Optional position ids of shape (*batch size*, *sequence_length*). If None, will be incremented from 0.
     /// * `input_embeds` - Optional pre-computed input embeddings of shape (*batch size*, *sequence_length*, *hidden_size*). If None, input ids must be provided (see `input_ids`)
+    /// * `labels` - Optional labels of shape (*batch size*, *sequence_length*) used to compute the cross-entropy loss. Positions with a label of -100 are ignored.
     /// * `train` - boolean flag to turn on/off the dropout layers in the model. Should be set to false for inference.
     ///
     /// # Returns
     ///
     /// * `DebertaTokenClassificationOutput` containing:
     ///   - `logits` - `Tensor` of shape (*batch size*, *sequence_length*, *num_labels*)
+    ///   - `loss` - `Option<Tensor>` scalar loss, computed if labels are provided
     ///   - `all_hidden_states` - `Option<Vec<Tensor>>` of length *num_hidden_layers* with shape (*batch size*, *sequence_length*, *hidden_size*)
     ///   - `all_attentions` - `Option<Vec<Tensor>>` of length *num_hidden_layers* with shape (*batch size*, *sequence_length*, *hidden_size*)
     ///
@@ -968,6 +997,7 @@ impl DebertaForTokenClassification {
     ///         Some(&token_type_ids),
     ///         Some(&position_ids),
     ///         None,
+    ///         None,
     ///         false,
     ///     )
     /// });
@@ -979,6 +1009,7 @@ impl DebertaForTokenClassification {
         token_type_ids: Option<&Tensor>,
         position_ids: Option<&Tensor>,
         input_embeds: Option<&Tensor>,
+        labels: Option<&Tensor>,
         train: bool,
     ) -> Result<DebertaTokenClassificationOutput, RustBertError> {
         let base_model_output = self.deberta.forward_t(
@@ -995,7 +1026,9 @@ impl DebertaForTokenClassification {
             .apply_t(&self.dropout, train)
             .apply(&self.classifier);
 
+        let loss = labels.map(|labels| cross_entropy_loss(&logits, labels));
         Ok(DebertaTokenClassificationOutput {
+            loss,
             logits,
             all_hidden_states: base_model_output.all_hidden_states,
             all_attentions: base_model_output.all_attentions,
@@ -1067,6 +1100,8 @@ impl DebertaForQuestionAnswering {
     /// * `token_type_ids` -Optional segment id of shape (*batch size*, *sequence_length*). Convention is value of 0 for the first sentence (incl. *SEP*) and 1 for the second sentence. If None set to 0.
     /// * `position_ids` - Optional position ids of shape (*batch size*, *sequence_length*). If None, will be incremented from 0.
     /// * `input_embeds` - Optional pre-computed input embeddings of shape (*batch size*, *sequence_length*, *hidden_size*). If None, input ids must be provided (see `input_ids`)
+    /// * `start_positions` - Optional answer start positions of shape (*batch size*) used to compute the loss. Positions outside of the sequence are ignored.
+    /// * `end_positions` - Optional answer end positions of shape (*batch size*) used to compute the loss. Positions outside of the sequence are ignored.
     /// * `train` - boolean flag to turn on/off the dropout layers in the model. Should be set to false for inference.
     ///
     /// # Returns
@@ -1074,6 +1109,7 @@ impl DebertaForQuestionAnswering {
     /// * `DebertaQuestionAnsweringOutput` containing:
     ///   - `start_logits` - `Tensor` of shape (*batch size*, *sequence_length*) containing the logits for start of the answer
     ///   - `end_logits` - `Tensor` of shape (*batch size*, *sequence_length*) containing the logits for end of the answer
+    ///   - `loss` - `Option<Tensor>` scalar loss, computed if the start and end positions are provided
     ///   - `all_hidden_states` - `Option<Vec<Tensor>>` of length *num_hidden_layers* with shape (*batch size*, *sequence_length*, *hidden_size*)
     ///   - `all_attentions` - `Option<Vec<Vec<Tensor>>>` of length *num_hidden_layers* with shape (*batch size*, *sequence_length*, *hidden_size*)
     ///
@@ -1104,6 +1140,8 @@ impl DebertaForQuestionAnswering {
     ///         Some(&token_type_ids),
     ///         Some(&position_ids),
     ///         None,
+    ///         None,
+    ///         None,
     ///         false,
     ///     )
     /// });
@@ -1115,6 +1153,8 @@ impl DebertaForQuestionAnswering {
         token_type_ids: Option<&Tensor>,
         position_ids: Option<&Tensor>,
         input_embeds: Option<&Tensor>,
+        start_positions: Option<&Tensor>,
+        end_positions: Option<&Tensor>,
         train: bool,
     ) -> Result<DebertaQuestionAnsweringOutput, RustBertError> {
         let base_model_output = self.deberta.forward_t(
@@ -1132,7 +1172,13 @@ impl DebertaForQuestionAnswering {
         let start_logits = start_logits.squeeze_dim(-1);
         let end_logits = end_logits.squeeze_dim(-1);
 
+        let loss = start_positions
+            .zip(end_positions)
+            .map(|(start_positions, end_positions)| {
+                question_answering_loss(&start_logits, &end_logits, start_positions, end_positions)
+            });
         Ok(DebertaQuestionAnsweringOutput {
+            loss,
             start_logits,
             end_logits,
             all_hidden_states: base_model_output.all_hidden_states,
@@ -1141,6 +1187,10 @@ impl DebertaForQuestionAnswering {
     }
 }
 
+/// # DeBERTa for sentence embeddings
+/// Transformer usable in [`SentenceEmbeddingsModel`](crate::pipelines::sentence_embeddings::SentenceEmbeddingsModel).
+pub type DebertaForSentenceEmbeddings = DebertaModel;
+
 /// Container for the DeBERTa model output.
 pub type DebertaModelOutput = DebertaEncoderOutput;
 
@@ -1152,6 +1202,8 @@ pub struct DebertaMaskedLMOutput {
     pub all_hidden_states: Option<Vec<Tensor>>,
     /// Attention weights for all intermediate layers
     pub all_attentions: Option<Vec<Tensor>>,
+    /// Loss computed from the labels, if provided
+    pub loss: Option<Tensor>,
 }
 
 /// Container for the DeBERTa sequence classification model output.
@@ -60,9 +60,10 @@ mod encoder;
 
 pub use deberta_model::{
     DebertaConfig, DebertaConfigResources, DebertaForMaskedLM, DebertaForQuestionAnswering,
-    DebertaForSequenceClassification, DebertaForTokenClassification, DebertaMaskedLMOutput,
-    DebertaMergesResources, DebertaModel, DebertaModelResources, DebertaQuestionAnsweringOutput,
-    DebertaSequenceClassificationOutput, DebertaTokenClassificationOutput, DebertaVocabResources,
+    DebertaForSentenceEmbeddings, DebertaForSequenceClassification, DebertaForTokenClassification,
+    DebertaMaskedLMOutput, DebertaMergesResources, DebertaModel, DebertaModelResources,
+    DebertaQuestionAnsweringOutput, DebertaSequenceClassificationOutput,
+    DebertaTokenClassificationOutput, DebertaVocabResources,
 };
 
 pub(crate) use deberta_model::{
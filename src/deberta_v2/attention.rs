@@ -10,7 +10,9 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use crate::common::attention::{narrow_attention_mask, try_chunked_attention};
 use crate::common::dropout::XDropout;
+use crate::common::lora::{lora_linear, LoraLinear};
 use crate::deberta::{
     x_softmax, DebertaConfig, DisentangledSelfAttention, PositionAttentionType,
     PositionAttentionTypes,
@@ -59,11 +61,11 @@ pub fn build_relative_position(
 }
 
 pub struct DebertaV2DisentangledSelfAttention {
-    query_proj: nn::Linear,
-    key_proj: nn::Linear,
-    value_proj: nn::Linear,
-    pos_key_proj: Option<nn::Linear>,
-    pos_query_proj: Option<nn::Linear>,
+    query_proj: LoraLinear,
+    key_proj: LoraLinear,
+    value_proj: LoraLinear,
+    pos_key_proj: Option<LoraLinear>,
+    pos_query_proj: Option<LoraLinear>,
     position_buckets: Option<i64>,
     pos_embed_size: Option<i64>,
     dropout: XDropout,
@@ -72,6 +74,7 @@ pub struct DebertaV2DisentangledSelfAttention {
     max_relative_positions: Option<i64>,
     pos_dropout: Option<XDropout>,
     output_attentions: bool,
+    attention_chunk_size: Option<i64>,
 }
 
 impl DebertaV2DisentangledSelfAttention {
@@ -92,6 +95,8 @@ impl DebertaV2DisentangledSelfAttention {
         relative_pos: Option<&Tensor>,
         relative_embeddings: &Tensor,
         scale_factor: f64,
+        is_query_block: bool,
+        train: bool,
     ) -> Result<Tensor, RustBertError> {
         let mut key_layer_size = key_layer.size();
         key_layer_size.reverse();
@@ -132,10 +137,10 @@ impl DebertaV2DisentangledSelfAttention {
         let query_proj = self.pos_query_proj.as_ref().unwrap_or(&self.query_proj);
 
         let pos_query_layer = self
-            .transpose_for_scores(&relative_embeddings.apply(query_proj))
+            .transpose_for_scores(&relative_embeddings.apply_t(query_proj, train))
             .repeat(&[query_layer.size()[0] / self.num_attention_heads, 1, 1]);
         let pos_key_layer = self
-            .transpose_for_scores(&relative_embeddings.apply(key_proj))
+            .transpose_for_scores(&relative_embeddings.apply_t(key_proj, train))
             .repeat(&[query_layer.size()[0] / self.num_attention_heads, 1, 1]);
 
         let mut score = Tensor::zeros(&[1], (query_layer.kind(), query_layer.device()));
@@ -166,32 +171,54 @@ impl DebertaV2DisentangledSelfAttention {
 
         if self.pos_att_type.has_type(PositionAttentionType::p2c) {
             let scale = *pos_query_layer.size().last().unwrap() as f64 * scale_factor;
-            let r_pos = if key_layer_size[1] != query_layer_size[1] {
-                build_relative_position(
-                    key_layer_size[1],
-                    key_layer_size[1],
-                    self.position_buckets.unwrap_or(-1),
-                    self.max_relative_positions.unwrap_or(-1),
-                    query_layer.device(),
-                )
-                .unsqueeze(0)
+            let p2c_att = if is_query_block {
+                // Block of queries of a self-attention layer: the positions of the queries relative to the keys
+                // are given by the transposed relative positions of the block
+                let p2c_pos =
+                    (relative_pos.transpose(-1, -2) + att_span).clamp(0, 2 * att_span - 1);
+                key_layer
+                    .bmm(&pos_query_layer.transpose(-1, -2))
+                    .gather(
+                        -1,
+                        &p2c_pos.squeeze_dim(0).expand(
+                            &[
+                                query_layer.size()[0],
+                                key_layer_size[1],
+                                query_layer_size[1],
+                            ],
+                            true,
+                        ),
+                        true,
+                    )
+                    .transpose(-1, -2)
             } else {
-                relative_pos.shallow_clone()
-            };
+                let r_pos = if key_layer_size[1] != query_layer_size[1] {
+                    build_relative_position(
+                        key_layer_size[1],
+                        key_layer_size[1],
+                        self.position_buckets.unwrap_or(-1),
+                        self.max_relative_positions.unwrap_or(-1),
+                        query_layer.device(),
+                    )
+                    .unsqueeze(0)
+                } else {
+                    relative_pos.shallow_clone()
+                };
 
-            let p2c_pos = (-r_pos + att_span).clamp(0, 2 * att_span - 1);
+                let p2c_pos = (-r_pos + att_span).clamp(0, 2 * att_span - 1);
 
-            let p2c_att = key_layer
-                .bmm(&pos_query_layer.transpose(-1, -2))
-                .gather(
-                    -1,
-                    &p2c_pos.squeeze_dim(0).expand(
-                        &[query_layer.size()[0], key_layer_size[1], key_layer_size[1]],
+                key_layer
+                    .bmm(&pos_query_layer.transpose(-1, -2))
+                    .gather(
+                        -1,
+                        &p2c_pos.squeeze_dim(0).expand(
+                            &[query_layer.size()[0], key_layer_size[1], key_layer_size[1]],
+                            true,
+                        ),
                         true,
-                    ),
-                    true,
-                )
-                .transpose(-1, -2);
+                    )
+                    .transpose(-1, -2)
+            };
             score = score + p2c_att / scale;
         }
 
@@ -218,6 +245,61 @@ impl DebertaV2DisentangledSelfAttention {
 
         Ok(score)
     }
+
+    /// Attention of the queries to the keys, returning the context (before merging the heads) and attention probabilities.
+    /// If `is_query_block` is true, the queries are a block of the queries of a self-attention layer and `relative_pos`
+    /// contains the relative positions of this block.
+    fn attention(
+        &self,
+        query_layer: &Tensor,
+        key_layer: &Tensor,
+        value_layer: &Tensor,
+        attention_mask: &Tensor,
+        relative_pos: Option<&Tensor>,
+        relative_embeddings: Option<&Tensor>,
+        scale_factor: i64,
+        is_query_block: bool,
+        train: bool,
+    ) -> Result<(Tensor, Tensor), RustBertError> {
+        let scale = ((query_layer.size().last().unwrap() * scale_factor) as f64).sqrt();
+        let mut attention_scores = query_layer.bmm(&key_layer.transpose(-1, -2)) / scale;
+
+        if let Some(relative_embeddings) = relative_embeddings {
+            let rel_att = self.disentangled_att_bias(
+                query_layer,
+                key_layer,
+                relative_pos,
+                relative_embeddings,
+                scale_factor as f64,
+                is_query_block,
+                train,
+            )?;
+            attention_scores = attention_scores + rel_att;
+        }
+        let mut reverse_attention_scores_size = attention_scores.size();
+        reverse_attention_scores_size.reverse();
+        attention_scores = attention_scores.view([
+            -1,
+            self.num_attention_heads,
+            reverse_attention_scores_size[1],
+            reverse_attention_scores_size[0],
+        ]);
+
+        let attention_probs =
+            x_softmax(&attention_scores, attention_mask, -1).apply_t(&self.dropout, train);
+
+        let mut reverse_attention_probs_size = attention_probs.size();
+        reverse_attention_probs_size.reverse();
+        let context_layer = attention_probs
+            .view([
+                -1,
+                reverse_attention_probs_size[1],
+                reverse_attention_probs_size[0],
+            ])
+            .bmm(value_layer);
+
+        Ok((context_layer, attention_probs))
+    }
 }
 
 impl DisentangledSelfAttention for DebertaV2DisentangledSelfAttention {
@@ -228,23 +310,26 @@ impl DisentangledSelfAttention for DebertaV2DisentangledSelfAttention {
         let p = p.borrow();
 
         let num_attention_heads = config.num_attention_heads;
-        let query_proj = nn::linear(
+        let query_proj = lora_linear(
             p / "query_proj",
             config.hidden_size,
             config.hidden_size,
             Default::default(),
+            config.lora.as_ref(),
         );
-        let key_proj = nn::linear(
+        let key_proj = lora_linear(
             p / "key_proj",
             config.hidden_size,
             config.hidden_size,
             Default::default(),
+            config.lora.as_ref(),
         );
-        let value_proj = nn::linear(
+        let value_proj = lora_linear(
             p / "value_proj",
             config.hidden_size,
             config.hidden_size,
             Default::default(),
+            config.lora.as_ref(),
         );
         let share_attention_key = config.share_att_key.unwrap_or(false);
         let pos_att_type = config.pos_att_type.clone().unwrap_or_default();
@@ -274,11 +359,12 @@ impl DisentangledSelfAttention for DebertaV2DisentangledSelfAttention {
                 let pos_key_proj = if pos_att_type.has_type(PositionAttentionType::c2p)
                     | pos_att_type.has_type(PositionAttentionType::p2p)
                 {
-                    Some(nn::linear(
+                    Some(lora_linear(
                         p / "pos_key_proj",
                         config.hidden_size,
                         config.hidden_size,
                         Default::default(),
+                        config.lora.as_ref(),
                     ))
                 } else {
                     None
@@ -286,11 +372,12 @@ impl DisentangledSelfAttention for DebertaV2DisentangledSelfAttention {
                 let pos_query_proj = if pos_att_type.has_type(PositionAttentionType::p2c)
                     | pos_att_type.has_type(PositionAttentionType::p2p)
                 {
-                    Some(nn::linear(
+                    Some(lora_linear(
                         p / "pos_query_proj",
                         config.hidden_size,
                         config.hidden_size,
                         Default::default(),
+                        config.lora.as_ref(),
                     ))
                 } else {
                     None
@@ -329,6 +416,7 @@ impl DisentangledSelfAttention for DebertaV2DisentangledSelfAttention {
             pos_query_proj,
             position_buckets,
             pos_embed_size,
+            attention_chunk_size: config.attention_chunk_size,
         }
     }
     fn forward_t(
@@ -340,11 +428,12 @@ impl DisentangledSelfAttention for DebertaV2DisentangledSelfAttention {
         relative_embeddings: Option<&Tensor>,
         train: bool,
     ) -> Result<(Tensor, Option<Tensor>), RustBertError> {
+        let is_self_attention = query_states.is_none();
         let query_states = query_states.unwrap_or(hidden_states);
 
-        let query_layer = self.transpose_for_scores(&query_states.apply(&self.query_proj));
-        let key_layer = self.transpose_for_scores(&query_states.apply(&self.key_proj));
-        let value_layer = self.transpose_for_scores(&query_states.apply(&self.value_proj));
+        let query_layer = self.transpose_for_scores(&query_states.apply_t(&self.query_proj, train));
+        let key_layer = self.transpose_for_scores(&query_states.apply_t(&self.key_proj, train));
+        let value_layer = self.transpose_for_scores(&query_states.apply_t(&self.value_proj, train));
 
         let mut scale_factor = 1;
         if self.pos_att_type.has_type(PositionAttentionType::c2p) {
@@ -356,42 +445,64 @@ impl DisentangledSelfAttention for DebertaV2DisentangledSelfAttention {
         if self.pos_att_type.has_type(PositionAttentionType::p2p) {
             scale_factor += 1;
         }
-        let scale = ((query_layer.size().last().unwrap() * scale_factor) as f64).sqrt();
-        let mut attention_scores = query_layer.bmm(&key_layer.transpose(-1, -2)) / scale;
-
-        if let (Some(pos_dropout), Some(rel_embeddings)) = (&self.pos_dropout, relative_embeddings)
-        {
-            let rel_embeddings = rel_embeddings.apply_t(pos_dropout, train);
-            let rel_att = self.disentangled_att_bias(
-                &query_layer,
-                &key_layer,
-                relative_pos,
-                &rel_embeddings,
-                scale_factor as f64,
-            )?;
-            attention_scores = attention_scores + rel_att;
-        }
-        let mut reverse_attention_scores_size = attention_scores.size();
-        reverse_attention_scores_size.reverse();
-        attention_scores = attention_scores.view([
-            -1,
-            self.num_attention_heads,
-            reverse_attention_scores_size[1],
-            reverse_attention_scores_size[0],
-        ]);
-
-        let attention_probs =
-            x_softmax(&attention_scores, attention_mask, -1).apply_t(&self.dropout, train);
+        let relative_embeddings = match (&self.pos_dropout, relative_embeddings) {
+            (Some(pos_dropout), Some(relative_embeddings)) => {
+                Some(relative_embeddings.apply_t(pos_dropout, train))
+            }
+            _ => None,
+        };
 
-        let mut reverse_attention_probs_size = attention_probs.size();
-        reverse_attention_probs_size.reverse();
-        let context_layer = attention_probs
-            .view([
-                -1,
-                reverse_attention_probs_size[1],
-                reverse_attention_probs_size[0],
-            ])
-            .bmm(&value_layer);
+        let (context_layer, attention_probs) = match self.attention_chunk_size {
+            Some(attention_chunk_size) if !self.output_attentions && is_self_attention => {
+                let query_length = query_layer.size()[1];
+                let relative_pos = relative_embeddings.as_ref().map(|_| match relative_pos {
+                    Some(relative_pos) => relative_pos.shallow_clone(),
+                    None => build_relative_position(
+                        query_length,
+                        key_layer.size()[1],
+                        self.position_buckets.unwrap_or(-1),
+                        self.max_relative_positions.unwrap_or(-1),
+                        query_layer.device(),
+                    ),
+                });
+                let context_layer = try_chunked_attention(
+                    query_length,
+                    attention_chunk_size,
+                    |query_offset, chunk_length| {
+                        let relative_pos = relative_pos.as_ref().map(|relative_pos| {
+                            relative_pos.narrow(-2, query_offset, chunk_length)
+                        });
+                        self.attention(
+                            &query_layer.narrow(1, query_offset, chunk_length),
+                            &key_layer,
+                            &value_layer,
+                            &narrow_attention_mask(attention_mask, 2, query_offset, chunk_length),
+                            relative_pos.as_ref(),
+                            relative_embeddings.as_ref(),
+                            scale_factor,
+                            true,
+                            train,
+                        )
+                        .map(|(context_layer, _)| context_layer)
+                    },
+                )?;
+                (context_layer, None)
+            }
+            _ => {
+                let (context_layer, attention_probs) = self.attention(
+                    &query_layer,
+                    &key_layer,
+                    &value_layer,
+                    attention_mask,
+                    relative_pos,
+                    relative_embeddings.as_ref(),
+                    scale_factor,
+                    false,
+                    train,
+                )?;
+                (context_layer, Some(attention_probs))
+            }
+        };
 
         let mut reverse_context_layer_size = context_layer.size();
         reverse_context_layer_size.reverse();
@@ -413,7 +524,7 @@ impl DisentangledSelfAttention for DebertaV2DisentangledSelfAttention {
         let context_layer = context_layer.view(new_context_layer_shape.as_slice());
 
         let attention_probs = if self.output_attentions {
-            Some(attention_probs)
+            attention_probs
         } else {
             None
         };
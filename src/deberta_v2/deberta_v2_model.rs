@@ -12,6 +12,10 @@
 
 use crate::common::dropout::{Dropout, XDropout};
 use crate::common::embeddings::get_shape_and_device_from_ids_embeddings_pair;
+use crate::common::lora::LoraConfig;
+use crate::common::loss::{
+    cross_entropy_loss, question_answering_loss, sequence_classification_loss, ProblemType,
+};
 use crate::deberta::{
     deserialize_attention_type, ContextPooler, DebertaConfig, DebertaLMPredictionHead,
     DebertaMaskedLMOutput, DebertaModelOutput, DebertaQuestionAnsweringOutput,
@@ -61,7 +65,7 @@ impl DebertaV2VocabResources {
     );
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 /// # DeBERTa (v2) model configuration
 /// Defines the DeBERTa (v2) model architecture (e.g. number of layers, hidden layer size, label mapping...)
 pub struct DebertaV2Config {
@@ -102,6 +106,11 @@ pub struct DebertaV2Config {
     pub is_decoder: Option<bool>,
     pub id2label: Option<HashMap<i64, String>>,
     pub label2id: Option<HashMap<String, i64>>,
+    /// Type of problem defining the loss of the sequence classification head (default: inferred from the labels)
+    pub problem_type: Option<ProblemType>,
+    pub lora: Option<LoraConfig>,
+    /// If set, the attention is computed for blocks of `attention_chunk_size` queries at a time
+    pub attention_chunk_size: Option<i64>,
 }
 
 #[allow(non_camel_case_types)]
@@ -228,6 +237,9 @@ impl Default for DebertaV2Config {
             is_decoder: None,
             id2label: None,
             label2id: None,
+            problem_type: None,
+            lora: None,
+            attention_chunk_size: None,
         }
     }
 }
@@ -263,8 +275,11 @@ impl From<DebertaV2Config> for DebertaConfig {
             is_decoder: v2_config.is_decoder,
             id2label: v2_config.id2label,
             label2id: v2_config.label2id,
+            problem_type: v2_config.problem_type,
             share_att_key: v2_config.share_att_key,
             position_buckets: v2_config.position_buckets,
+            lora: v2_config.lora,
+            attention_chunk_size: v2_config.attention_chunk_size,
         }
     }
 }
@@ -300,8 +315,11 @@ impl From<&DebertaV2Config> for DebertaConfig {
             is_decoder: v2_config.is_decoder,
             id2label: v2_config.id2label.clone(),
             label2id: v2_config.label2id.clone(),
+            problem_type: v2_config.problem_type,
             share_att_key: v2_config.share_att_key,
             position_buckets: v2_config.position_buckets,
+            lora: v2_config.lora.clone(),
+            attention_chunk_size: v2_config.attention_chunk_size,
         }
     }
 }
@@ -495,12 +513,14 @@ impl DebertaV2ForMaskedLM {
     /// * `token_type_ids` -Optional segment id of shape (*batch size*, *sequence_length*). Convention is value of 0 for the first sentence (incl. *SEP*) and 1 for the second sentence. If None set to 0.
     /// * `position_ids` - Optional position ids of shape (*batch size*, *sequence_length*). If None, will be incremented from 0.
     /// * `input_embeds` - Optional pre-computed input embeddings of shape (*batch size*, *sequence_length*, *hidden_size*). If None, input ids must be provided (see *input_ids*)
+    /// * `labels` - Optional target token ids of shape (*batch size*, *sequence_length*) used to compute the masked language modeling loss. Positions with a label of -100 (e.g. non-masked tokens) are ignored.
     /// * `train` - boolean flag to turn on/off the dropout layers in the model. Should be set to false for inference.
     ///
     /// # Returns
     ///
     /// * `DebertaMaskedLMOutput` containing:
     ///   - `prediction_scores` - `Tensor` of shape (*batch size*, *sequence_length*, *vocab_size*)
+    ///   - `loss` - `Option<Tensor>` scalar loss, computed if labels are provided
     ///   - `all_hidden_states` - `Option<Vec<Tensor>>` of length *num_hidden_layers* with shape (*batch size*, *sequence_length*, *hidden_size*)
     ///   - `all_attentions` - `Option<Vec<Tensor>>` of length *num_hidden_layers* with shape (*batch size*, *sequence_length*, *hidden_size*)
     ///
@@ -530,6 +550,7 @@ impl DebertaV2ForMaskedLM {
     ///         Some(&token_type_ids),
     ///         Some(&position_ids),
     ///         None,
+    ///         None,
     ///         false,
     ///     )
     /// });
@@ -541,6 +562,7 @@ impl DebertaV2ForMaskedLM {
         token_type_ids: Option<&Tensor>,
         position_ids: Option<&Tensor>,
         input_embeds: Option<&Tensor>,
+        labels: Option<&Tensor>,
         train: bool,
     ) -> Result<DebertaV2MaskedLMOutput, RustBertError> {
         let model_outputs = self.deberta.forward_t(
@@ -553,7 +575,9 @@ impl DebertaV2ForMaskedLM {
         )?;
 
         let logits = model_outputs.hidden_state.apply(&self.cls);
+        let loss = labels.map(|labels| cross_entropy_loss(&logits, labels));
         Ok(DebertaV2MaskedLMOutput {
+            loss,
             logits,
             all_hidden_states: model_outputs.all_hidden_states,
             all_attentions: model_outputs.all_attentions,
@@ -571,6 +595,7 @@ pub struct DebertaV2ForSequenceClassification {
     pooler: ContextPooler,
     classifier: nn::Linear,
     dropout: XDropout,
+    problem_type: Option<ProblemType>,
 }
 
 impl DebertaV2ForSequenceClassification {
@@ -634,6 +659,7 @@ impl DebertaV2ForSequenceClassification {
             pooler,
             classifier,
             dropout,
+            problem_type: config.problem_type,
         })
     }
 
@@ -646,12 +672,14 @@ impl DebertaV2ForSequenceClassification {
     /// * `token_type_ids` -Optional segment id of shape (*batch size*, *sequence_length*). Convention is value of 0 for the first sentence (incl. *SEP*) and 1 for the second sentence. If None set to 0.
     /// * `position_ids` - Optional position ids of shape (*batch size*, *sequence_length*). If None, will be incremented from 0.
     /// * `input_embeds` - Optional pre-computed input embeddings of shape (*batch size*, *sequence_length*, *hidden_size*). If None, input ids must be provided (see `input_ids`)
+    /// * `labels` - Optional labels of shape (*batch size*) used to compute the loss: class indices for single-label classification or target values for regression. Multi-label classification uses floating point targets of shape (*batch size*, *num_labels*). The loss follows the `problem_type` of the configuration, inferred from the labels if not set.
     /// * `train` - boolean flag to turn on/off the dropout layers in the model. Should be set to false for inference.
     ///
     /// # Returns
     ///
     /// * `DebertaV2SequenceClassificationOutput` containing:
     ///   - `logits` - `Tensor` of shape (*batch size*, *num_labels*)
+    ///   - `loss` - `Option<Tensor>` scalar loss, computed if labels are provided
     ///   - `all_hidden_states` - `Option<Vec<Tensor>>` of length *num_hidden_layers* with shape (*batch size*, *sequence_length*, *hidden_size*)
     ///   - `all_attentions` - `Option<Vec<Tensor>>` of length *num_hidden_layers* with shape (*batch size*, *sequence_length*, *hidden_size*)
     ///
@@ -681,6 +709,7 @@ impl DebertaV2ForSequenceClassification {
     ///         Some(&token_type_ids),
     ///         Some(&position_ids),
     ///         None,
+    ///         None,
     ///         false,
     ///     )
     /// });
@@ -692,6 +721,7 @@ impl DebertaV2ForSequenceClassification {
         token_type_ids: Option<&Tensor>,
         position_ids: Option<&Tensor>,
         input_embeds: Option<&Tensor>,
+        labels: Option<&Tensor>,
         train: bool,
     ) -> Result<DebertaV2SequenceClassificationOutput, RustBertError> {
         let base_model_output = self.deberta.forward_t(
@@ -709,7 +739,10 @@ impl DebertaV2ForSequenceClassification {
             .apply_t(&self.dropout, train)
             .apply(&self.classifier);
 
+        let loss =
+            labels.map(|labels| sequence_classification_loss(&logits, labels, self.problem_type));
         Ok(DebertaV2SequenceClassificationOutput {
+            loss,
             logits,
             all_hidden_states: base_model_output.all_hidden_states,
             all_attentions: base_model_output.all_attentions,
@@ -795,12 +828,14 @@ impl DebertaV2ForTokenClassification {
     /// * `token_type_ids` -Optional segment id of shape (*batch size*, *sequence_length*). Convention is value of 0 for the first sentence (incl. *SEP*) and 1 for the second sentence. If None set to 0.
     /// * `position_ids` - Optional position ids of shape (*batch size*, *sequence_length*). If None, will be incremented from 0.
     /// * `input_embeds` - Optional pre-computed input embeddings of shape (*batch size*, *sequence_length*, *hidden_size*). If None, input ids must be provided (see `input_ids`)
+    /// * `labels` - Optional labels of shape (*batch size*, *sequence_length*) used to compute the cross-entropy loss. Positions with a label of -100 are ignored.
     /// * `train` - boolean flag to turn on/off the dropout layers in the model. Should be set to false for inference.
     ///
     /// # Returns
     ///
     /// * `DebertaV2TokenClassificationOutput` containing:
     ///   - `logits` - `Tensor` of shape (*batch size*, *sequence_length*, *num_labels*)
+    ///   - `loss` - `Option<Tensor>` scalar loss, computed if labels are provided
     ///   - `all_hidden_states` - `Option<Vec<Tensor>>` of length *num_hidden_layers* with shape (*batch size*, *sequence_length*, *hidden_size*)
     ///   - `all_attentions` - `Option<Vec<Tensor>>` of length *num_hidden_layers* with shape (*batch size*, *sequence_length*, *hidden_size*)
     ///
@@ -830,6 +865,7 @@ impl DebertaV2ForTokenClassification {
     ///         Some(&token_type_ids),
     ///         Some(&position_ids),
     ///         None,
+    ///         None,
     ///         false,
     ///     )
     /// });
@@ -841,6 +877,7 @@ impl DebertaV2ForTokenClassification {
         token_type_ids: Option<&Tensor>,
         position_ids: Option<&Tensor>,
         input_embeds: Option<&Tensor>,
+        labels: Option<&Tensor>,
         train: bool,
     ) -> Result<DebertaV2TokenClassificationOutput, RustBertError> {
         let base_model_output = self.deberta.forward_t(
@@ -857,7 +894,9 @@ impl DebertaV2ForTokenClassification {
             .apply_t(&self.dropout, train)
             .apply(&self.classifier);
 
+        let loss = labels.map(|labels| cross_entropy_loss(&logits, labels));
         Ok(DebertaV2TokenClassificationOutput {
+            loss,
             logits,
             all_hidden_states: base_model_output.all_hidden_states,
             all_attentions: base_model_output.all_attentions,
@@ -929,6 +968,8 @@ impl DebertaV2ForQuestionAnswering {
     /// * `token_type_ids` -Optional segment id of shape (*batch size*, *sequence_length*). Convention is value of 0 for the first sentence (incl. *SEP*) and 1 for the second sentence. If None set to 0.
     /// * `position_ids` - Optional position ids of shape (*batch size*, *sequence_length*). If None, will be incremented from 0.
     /// * `input_embeds` - Optional pre-computed input embeddings of shape (*batch size*, *sequence_length*, *hidden_size*). If None, input ids must be provided (see `input_ids`)
+    /// * `start_positions` - Optional answer start positions of shape (*batch size*) used to compute the loss. Positions outside of the sequence are ignored.
+    /// * `end_positions` - Optional answer end positions of shape (*batch size*) used to compute the loss. Positions outside of the sequence are ignored.
     /// * `train` - boolean flag to turn on/off the dropout layers in the model. Should be set to false for inference.
     ///
     /// # Returns
@@ -936,6 +977,7 @@ impl DebertaV2ForQuestionAnswering {
     /// * `DebertaQuestionAnsweringOutput` containing:
     ///   - `start_logits` - `Tensor` of shape (*batch size*, *sequence_length*) containing the logits for start of the answer
     ///   - `end_logits` - `Tensor` of shape (*batch size*, *sequence_length*) containing the logits for end of the answer
+    ///   - `loss` - `Option<Tensor>` scalar loss, computed if the start and end positions are provided
     ///   - `all_hidden_states` - `Option<Vec<Tensor>>` of length *num_hidden_layers* with shape (*batch size*, *sequence_length*, *hidden_size*)
     ///   - `all_attentions` - `Option<Vec<Vec<Tensor>>>` of length *num_hidden_layers* with shape (*batch size*, *sequence_length*, *hidden_size*)
     ///
@@ -966,6 +1008,8 @@ impl DebertaV2ForQuestionAnswering {
     ///         Some(&token_type_ids),
     ///         Some(&position_ids),
     ///         None,
+    ///         None,
+    ///         None,
     ///         false,
     ///     )
     /// });
@@ -977,6 +1021,8 @@ impl DebertaV2ForQuestionAnswering {
         token_type_ids: Option<&Tensor>,
         position_ids: Option<&Tensor>,
         input_embeds: Option<&Tensor>,
+        start_positions: Option<&Tensor>,
+        end_positions: Option<&Tensor>,
         train: bool,
     ) -> Result<DebertaV2QuestionAnsweringOutput, RustBertError> {
         let base_model_output = self.deberta.forward_t(
@@ -994,7 +1040,13 @@ impl DebertaV2ForQuestionAnswering {
         let start_logits = start_logits.squeeze_dim(-1);
         let end_logits = end_logits.squeeze_dim(-1);
 
+        let loss = start_positions
+            .zip(end_positions)
+            .map(|(start_positions, end_positions)| {
+                question_answering_loss(&start_logits, &end_logits, start_positions, end_positions)
+            });
         Ok(DebertaV2QuestionAnsweringOutput {
+            loss,
             start_logits,
             end_logits,
             all_hidden_states: base_model_output.all_hidden_states,
@@ -1003,6 +1055,10 @@ impl DebertaV2ForQuestionAnswering {
     }
 }
 
+/// # DeBERTa (v2) for sentence embeddings
+/// Transformer usable in [`SentenceEmbeddingsModel`](crate::pipelines::sentence_embeddings::SentenceEmbeddingsModel).
+pub type DebertaV2ForSentenceEmbeddings = DebertaV2Model;
+
 /// Container for the DeBERTa V2 model output.
 pub type DebertaV2ModelOutput = DebertaModelOutput;
 
@@ -54,8 +54,8 @@ mod encoder;
 
 pub use deberta_v2_model::{
     DebertaV2Config, DebertaV2ConfigResources, DebertaV2ForMaskedLM, DebertaV2ForQuestionAnswering,
-    DebertaV2ForSequenceClassification, DebertaV2ForTokenClassification, DebertaV2Model,
-    DebertaV2ModelResources, DebertaV2QuestionAnsweringOutput,
-    DebertaV2SequenceClassificationOutput, DebertaV2TokenClassificationOutput,
-    DebertaV2VocabResources,
+    DebertaV2ForSentenceEmbeddings, DebertaV2ForSequenceClassification,
+    DebertaV2ForTokenClassification, DebertaV2Model, DebertaV2ModelResources,
+    DebertaV2QuestionAnsweringOutput, DebertaV2SequenceClassificationOutput,
+    DebertaV2TokenClassificationOutput, DebertaV2VocabResources,
 };
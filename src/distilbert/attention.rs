@@ -10,7 +10,9 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use crate::common::attention::chunked_attention;
 use crate::common::dropout::Dropout;
+use crate::common::lora::{lora_linear, LoraLinear};
 use crate::distilbert::distilbert_model::DistilBertConfig;
 use std::borrow::Borrow;
 use tch::{nn, Tensor};
@@ -21,10 +23,11 @@ pub struct MultiHeadSelfAttention {
     dim_per_head: i64,
     dropout: Dropout,
     output_attentions: bool,
-    q_lin: nn::Linear,
-    k_lin: nn::Linear,
-    v_lin: nn::Linear,
-    out_lin: nn::Linear,
+    q_lin: LoraLinear,
+    k_lin: LoraLinear,
+    v_lin: LoraLinear,
+    out_lin: LoraLinear,
+    attention_chunk_size: Option<i64>,
 }
 
 impl MultiHeadSelfAttention {
@@ -33,10 +36,34 @@ impl MultiHeadSelfAttention {
         P: Borrow<nn::Path<'p>>,
     {
         let p = p.borrow();
-        let q_lin = nn::linear(p / "q_lin", config.dim, config.dim, Default::default());
-        let k_lin = nn::linear(p / "k_lin", config.dim, config.dim, Default::default());
-        let v_lin = nn::linear(p / "v_lin", config.dim, config.dim, Default::default());
-        let out_lin = nn::linear(p / "out_lin", config.dim, config.dim, Default::default());
+        let q_lin = lora_linear(
+            p / "q_lin",
+            config.dim,
+            config.dim,
+            Default::default(),
+            config.lora.as_ref(),
+        );
+        let k_lin = lora_linear(
+            p / "k_lin",
+            config.dim,
+            config.dim,
+            Default::default(),
+            config.lora.as_ref(),
+        );
+        let v_lin = lora_linear(
+            p / "v_lin",
+            config.dim,
+            config.dim,
+            Default::default(),
+            config.lora.as_ref(),
+        );
+        let out_lin = lora_linear(
+            p / "out_lin",
+            config.dim,
+            config.dim,
+            Default::default(),
+            config.lora.as_ref(),
+        );
 
         let dropout = Dropout::new(config.attention_dropout);
         let output_attentions = config.output_attentions.unwrap_or(false);
@@ -50,6 +77,7 @@ impl MultiHeadSelfAttention {
             k_lin,
             v_lin,
             out_lin,
+            attention_chunk_size: config.attention_chunk_size,
         }
     }
 
@@ -63,22 +91,16 @@ impl MultiHeadSelfAttention {
             .view((bs, -1, self.n_heads * dim_per_head))
     }
 
-    pub fn forward_t(
+    /// Attention of the (scaled) queries `q` to the keys `k`, returning the context and attention weights
+    fn attention(
         &self,
-        query: &Tensor,
-        key: &Tensor,
-        value: &Tensor,
+        q: &Tensor,
+        k: &Tensor,
+        v: &Tensor,
         mask: Option<&Tensor>,
         train: bool,
-    ) -> (Tensor, Option<Tensor>) {
-        let bs = query.size()[0];
-        let k_length = key.size()[1];
-
-        let q = self.split_heads(query.apply(&self.q_lin), bs, self.dim_per_head);
-        let k = self.split_heads(key.apply(&self.k_lin), bs, self.dim_per_head);
-        let v = self.split_heads(value.apply(&self.v_lin), bs, self.dim_per_head);
-        let q: Tensor = q / (self.dim_per_head as f64).sqrt();
-
+    ) -> (Tensor, Tensor) {
+        let (bs, k_length) = (k.size()[0], k.size()[2]);
         let scores = if let Some(mask) = mask {
             let unmasked_scores = q.matmul(&k.transpose(2, 3));
             let mask = mask
@@ -93,14 +115,55 @@ impl MultiHeadSelfAttention {
         let weights = scores
             .softmax(-1, scores.kind())
             .apply_t(&self.dropout, train);
+        (weights.matmul(v), weights)
+    }
+
+    pub fn forward_t(
+        &self,
+        query: &Tensor,
+        key: &Tensor,
+        value: &Tensor,
+        mask: Option<&Tensor>,
+        train: bool,
+    ) -> (Tensor, Option<Tensor>) {
+        let bs = query.size()[0];
+
+        let q = self.split_heads(query.apply_t(&self.q_lin, train), bs, self.dim_per_head);
+        let k = self.split_heads(key.apply_t(&self.k_lin, train), bs, self.dim_per_head);
+        let v = self.split_heads(value.apply_t(&self.v_lin, train), bs, self.dim_per_head);
+        let q: Tensor = q / (self.dim_per_head as f64).sqrt();
+
+        let (context, weights) = match self.attention_chunk_size {
+            Some(attention_chunk_size) if !self.output_attentions => {
+                let context = chunked_attention(
+                    q.size()[2],
+                    attention_chunk_size,
+                    |query_offset, chunk_length| {
+                        self.attention(
+                            &q.narrow(2, query_offset, chunk_length),
+                            &k,
+                            &v,
+                            mask,
+                            train,
+                        )
+                        .0
+                    },
+                );
+                (context, None)
+            }
+            _ => {
+                let (context, weights) = self.attention(&q, &k, &v, mask, train);
+                (context, Some(weights))
+            }
+        };
         let context = self
-            .flatten(weights.matmul(&v), bs, self.dim_per_head)
-            .apply(&self.out_lin);
+            .flatten(context, bs, self.dim_per_head)
+            .apply_t(&self.out_lin, train);
 
         if !self.output_attentions {
             (context, None)
         } else {
-            (context, Some(weights))
+            (context, weights)
         }
     }
 }
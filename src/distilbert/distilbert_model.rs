@@ -15,6 +15,10 @@ extern crate tch;
 use self::tch::{nn, Tensor};
 use crate::common::activations::Activation;
 use crate::common::dropout::Dropout;
+use crate::common::lora::LoraConfig;
+use crate::common::loss::{
+    cross_entropy_loss, question_answering_loss, sequence_classification_loss, ProblemType,
+};
 use crate::distilbert::embeddings::DistilBertEmbedding;
 use crate::distilbert::transformer::{DistilBertTransformerOutput, Transformer};
 use crate::{Config, RustBertError};
@@ -112,6 +116,8 @@ pub struct DistilBertConfig {
     pub initializer_range: f32,
     pub is_decoder: Option<bool>,
     pub label2id: Option<HashMap<String, i64>>,
+    /// Type of problem defining the loss of the sequence classification head (default: inferred from the labels)
+    pub problem_type: Option<ProblemType>,
     pub max_position_embeddings: i64,
     pub n_heads: i64,
     pub n_layers: i64,
@@ -123,6 +129,10 @@ pub struct DistilBertConfig {
     pub sinusoidal_pos_embds: bool,
     pub tie_weights_: bool,
     pub vocab_size: i64,
+    pub lora: Option<LoraConfig>,
+    /// If set, the attention is computed for blocks of `attention_chunk_size` queries at a time, limiting the
+    /// memory used by the attention scores (not applied when the attention weights are returned)
+    pub attention_chunk_size: Option<i64>,
 }
 
 impl Config for DistilBertConfig {}
@@ -139,6 +149,7 @@ impl Default for DistilBertConfig {
             initializer_range: 0.02,
             is_decoder: None,
             label2id: None,
+            problem_type: None,
             max_position_embeddings: 512,
             n_heads: 12,
             n_layers: 6,
@@ -150,6 +161,8 @@ impl Default for DistilBertConfig {
             sinusoidal_pos_embds: false,
             tie_weights_: false,
             vocab_size: 30522,
+            lora: None,
+            attention_chunk_size: None,
         }
     }
 }
@@ -264,6 +277,7 @@ pub struct DistilBertModelClassifier {
     pre_classifier: nn::Linear,
     classifier: nn::Linear,
     dropout: Dropout,
+    problem_type: Option<ProblemType>,
 }
 
 impl DistilBertModelClassifier {
@@ -324,6 +338,7 @@ impl DistilBertModelClassifier {
             pre_classifier,
             classifier,
             dropout,
+            problem_type: config.problem_type,
         })
     }
 
@@ -334,12 +349,14 @@ impl DistilBertModelClassifier {
     /// * `input_ids` - Optional input tensor of shape (*batch size*, *sequence_length*). If None, pre-computed embeddings must be provided (see `input_embeds`)
     /// * `mask` - Optional mask of shape (*batch size*, *sequence_length*). Masked position have value 0, non-masked value 1. If None set to 1
     /// * `input_embeds` - Optional pre-computed input embeddings of shape (*batch size*, *sequence_length*, *hidden_size*). If None, input ids must be provided (see `input_ids`)
+    /// * `labels` - Optional labels of shape (*batch size*) used to compute the loss: class indices for single-label classification or target values for regression. Multi-label classification uses floating point targets of shape (*batch size*, *num_labels*). The loss follows the `problem_type` of the configuration, inferred from the labels if not set.
     /// * `train` - boolean flag to turn on/off the dropout layers in the model. Should be set to false for inference.
     ///
     /// # Returns
     ///
     /// * `DistilBertSequenceClassificationOutput` containing:
     ///   - `logits` - `Tensor` of shape (*batch size*, *num_labels*)
+    ///   - `loss` - `Option<Tensor>` scalar loss, computed if labels are provided
     ///   - `all_hidden_states` - `Option<Vec<Tensor>>` of length *num_hidden_layers* with shape (*batch size*, *sequence_length*, *hidden_size*)
     ///   - `all_attentions` - `Option<Vec<Tensor>>` of length *num_hidden_layers* with shape (*batch size*, *sequence_length*, *hidden_size*)
     ///
@@ -366,6 +383,7 @@ impl DistilBertModelClassifier {
     ///         .forward_t(Some(&input_tensor),
     ///                    Some(&mask),
     ///                    None,
+    ///                    None,
     ///                    false).unwrap()
     ///    });
     /// ```
@@ -374,6 +392,7 @@ impl DistilBertModelClassifier {
         input: Option<&Tensor>,
         mask: Option<&Tensor>,
         input_embeds: Option<&Tensor>,
+        labels: Option<&Tensor>,
         train: bool,
     ) -> Result<DistilBertSequenceClassificationOutput, RustBertError> {
         let base_model_output =
@@ -388,7 +407,10 @@ impl DistilBertModelClassifier {
             .apply_t(&self.dropout, train)
             .apply(&self.classifier);
 
+        let loss =
+            labels.map(|labels| sequence_classification_loss(&logits, labels, self.problem_type));
         Ok(DistilBertSequenceClassificationOutput {
+            loss,
             logits,
             all_hidden_states: base_model_output.all_hidden_states,
             all_attentions: base_model_output.all_attentions,
@@ -473,12 +495,14 @@ impl DistilBertModelMaskedLM {
     /// * `input_ids` - Optional input tensor of shape (*batch size*, *sequence_length*). If None, pre-computed embeddings must be provided (see `input_embeds`)
     /// * `mask` - Optional mask of shape (*batch size*, *sequence_length*). Masked position have value 0, non-masked value 1. If None set to 1
     /// * `input_embeds` - Optional pre-computed input embeddings of shape (*batch size*, *sequence_length*, *hidden_size*). If None, input ids must be provided (see `input_ids`)
+    /// * `labels` - Optional target token ids of shape (*batch size*, *sequence_length*) used to compute the masked language modeling loss. Positions with a label of -100 (e.g. non-masked tokens) are ignored.
     /// * `train` - boolean flag to turn on/off the dropout layers in the model. Should be set to false for inference.
     ///
     /// # Returns
     ///
     /// * `DistilBertMaskedLMOutput` containing:
     ///   - `prediction_scores` - `Tensor` of shape (*batch size*, *sequence_length*, *vocab_size*)
+    ///   - `loss` - `Option<Tensor>` scalar loss, computed if labels are provided
     ///   - `all_hidden_states` - `Option<Vec<Tensor>>` of length *num_hidden_layers* with shape (*batch size*, *sequence_length*, *hidden_size*)
     ///   - `all_attentions` - `Option<Vec<Tensor>>` of length *num_hidden_layers* with shape (*batch size*, *sequence_length*, *hidden_size*)
     ///
@@ -502,7 +526,7 @@ impl DistilBertModelMaskedLM {
     ///
     /// let model_output = no_grad(|| {
     ///     distilbert_model
-    ///         .forward_t(Some(&input_tensor), Some(&mask), None, false)
+    ///         .forward_t(Some(&input_tensor), Some(&mask), None, None, false)
     ///         .unwrap()
     /// });
     /// ```
@@ -511,6 +535,7 @@ impl DistilBertModelMaskedLM {
         input: Option<&Tensor>,
         mask: Option<&Tensor>,
         input_embeds: Option<&Tensor>,
+        labels: Option<&Tensor>,
         train: bool,
     ) -> Result<DistilBertMaskedLMOutput, RustBertError> {
         let base_model_output =
@@ -524,7 +549,9 @@ impl DistilBertModelMaskedLM {
             .apply(&self.vocab_layer_norm)
             .apply(&self.vocab_projector);
 
+        let loss = labels.map(|labels| cross_entropy_loss(&prediction_scores, labels));
         Ok(DistilBertMaskedLMOutput {
+            loss,
             prediction_scores,
             all_hidden_states: base_model_output.all_hidden_states,
             all_attentions: base_model_output.all_attentions,
@@ -591,6 +618,8 @@ impl DistilBertForQuestionAnswering {
     /// * `input_ids` - Optional input tensor of shape (*batch size*, *sequence_length*). If None, pre-computed embeddings must be provided (see `input_embeds`)
     /// * `mask` - Optional mask of shape (*batch size*, *sequence_length*). Masked position have value 0, non-masked value 1. If None set to 1
     /// * `input_embeds` - Optional pre-computed input embeddings of shape (*batch size*, *sequence_length*, *hidden_size*). If None, input ids must be provided (see `input_ids`)
+    /// * `start_positions` - Optional answer start positions of shape (*batch size*) used to compute the loss. Positions outside of the sequence are ignored.
+    /// * `end_positions` - Optional answer end positions of shape (*batch size*) used to compute the loss. Positions outside of the sequence are ignored.
     /// * `train` - boolean flag to turn on/off the dropout layers in the model. Should be set to false for inference.
     ///
     /// # Returns
@@ -598,6 +627,7 @@ impl DistilBertForQuestionAnswering {
     /// * `DistilBertQuestionAnsweringOutput` containing:
     ///   - `start_logits` - `Tensor` of shape (*batch size*, *sequence_length*) containing the logits for start of the answer
     ///   - `end_logits` - `Tensor` of shape (*batch size*, *sequence_length*) containing the logits for end of the answer
+    ///   - `loss` - `Option<Tensor>` scalar loss, computed if the start and end positions are provided
     ///   - `all_hidden_states` - `Option<Vec<Tensor>>` of length *num_hidden_layers* with shape (*batch size*, *sequence_length*, *hidden_size*)
     ///   - `all_attentions` - `Option<Vec<Vec<Tensor>>>` of length *num_hidden_layers* with shape (*batch size*, *sequence_length*, *hidden_size*)
     ///
@@ -621,7 +651,7 @@ impl DistilBertForQuestionAnswering {
     ///
     /// let model_output = no_grad(|| {
     ///     distilbert_model
-    ///         .forward_t(Some(&input_tensor), Some(&mask), None, false)
+    ///         .forward_t(Some(&input_tensor), Some(&mask), None, None, None, false)
     ///         .unwrap()
     /// });
     /// ```
@@ -630,6 +660,8 @@ impl DistilBertForQuestionAnswering {
         input: Option<&Tensor>,
         mask: Option<&Tensor>,
         input_embeds: Option<&Tensor>,
+        start_positions: Option<&Tensor>,
+        end_positions: Option<&Tensor>,
         train: bool,
     ) -> Result<DistilBertQuestionAnsweringOutput, RustBertError> {
         let base_model_output =
@@ -646,7 +678,13 @@ impl DistilBertForQuestionAnswering {
         let start_logits = start_logits.squeeze_dim(-1);
         let end_logits = end_logits.squeeze_dim(-1);
 
+        let loss = start_positions
+            .zip(end_positions)
+            .map(|(start_positions, end_positions)| {
+                question_answering_loss(&start_logits, &end_logits, start_positions, end_positions)
+            });
         Ok(DistilBertQuestionAnsweringOutput {
+            loss,
             start_logits,
             end_logits,
             all_hidden_states: base_model_output.all_hidden_states,
@@ -728,12 +766,14 @@ impl DistilBertForTokenClassification {
     /// * `input_ids` - Optional input tensor of shape (*batch size*, *sequence_length*). If None, pre-computed embeddings must be provided (see `input_embeds`)
     /// * `mask` - Optional mask of shape (*batch size*, *sequence_length*). Masked position have value 0, non-masked value 1. If None set to 1
     /// * `input_embeds` - Optional pre-computed input embeddings of shape (*batch size*, *sequence_length*, *hidden_size*). If None, input ids must be provided (see `input_ids`)
+    /// * `labels` - Optional labels of shape (*batch size*, *sequence_length*) used to compute the cross-entropy loss. Positions with a label of -100 are ignored.
     /// * `train` - boolean flag to turn on/off the dropout layers in the model. Should be set to false for inference.
     ///
     /// # Returns
     ///
     /// * `DistilBertTokenClassificationOutput` containing:
     ///   - `logits` - `Tensor` of shape (*batch size*, *sequence_length*, *num_labels*) containing the logits for each of the input tokens and classes
+    ///   - `loss` - `Option<Tensor>` scalar loss, computed if labels are provided
     ///   - `all_hidden_states` - `Option<Vec<Tensor>>` of length *num_hidden_layers* with shape (*batch size*, *sequence_length*, *hidden_size*)
     ///   - `all_attentions` - `Option<Vec<Tensor>>` of length *num_hidden_layers* with shape (*batch size*, *sequence_length*, *hidden_size*)
     ///
@@ -757,7 +797,7 @@ impl DistilBertForTokenClassification {
     ///
     /// let model_output = no_grad(|| {
     ///     distilbert_model
-    ///         .forward_t(Some(&input_tensor), Some(&mask), None, false)
+    ///         .forward_t(Some(&input_tensor), Some(&mask), None, None, false)
     ///         .unwrap()
     /// });
     /// ```
@@ -766,6 +806,7 @@ impl DistilBertForTokenClassification {
         input: Option<&Tensor>,
         mask: Option<&Tensor>,
         input_embeds: Option<&Tensor>,
+        labels: Option<&Tensor>,
         train: bool,
     ) -> Result<DistilBertTokenClassificationOutput, RustBertError> {
         let base_model_output =
@@ -777,7 +818,9 @@ impl DistilBertForTokenClassification {
             .apply_t(&self.dropout, train)
             .apply(&self.classifier);
 
+        let loss = labels.map(|labels| cross_entropy_loss(&logits, labels));
         Ok(DistilBertTokenClassificationOutput {
+            loss,
             logits,
             all_hidden_states: base_model_output.all_hidden_states,
             all_attentions: base_model_output.all_attentions,
@@ -797,6 +840,8 @@ pub struct DistilBertMaskedLMOutput {
     pub all_hidden_states: Option<Vec<Tensor>>,
     /// Attention weights for all intermediate layers
     pub all_attentions: Option<Vec<Tensor>>,
+    /// Loss computed from the labels, if provided
+    pub loss: Option<Tensor>,
 }
 
 /// Container for the DistilBERT sequence classification model output
@@ -807,6 +852,8 @@ pub struct DistilBertSequenceClassificationOutput {
     pub all_hidden_states: Option<Vec<Tensor>>,
     /// Attention weights for all intermediate layers
     pub all_attentions: Option<Vec<Tensor>>,
+    /// Loss computed from the labels, if provided
+    pub loss: Option<Tensor>,
 }
 
 /// Container for the DistilBERT token classification model output
@@ -817,6 +864,8 @@ pub struct DistilBertTokenClassificationOutput {
     pub all_hidden_states: Option<Vec<Tensor>>,
     /// Attention weights for all intermediate layers
     pub all_attentions: Option<Vec<Tensor>>,
+    /// Loss computed from the labels, if provided
+    pub loss: Option<Tensor>,
 }
 
 /// Container for the DistilBERT question answering model output
@@ -829,4 +878,6 @@ pub struct DistilBertQuestionAnsweringOutput {
     pub all_hidden_states: Option<Vec<Tensor>>,
     /// Attention weights for all intermediate layers
     pub all_attentions: Option<Vec<Tensor>>,
+    /// Loss computed from the labels, if provided
+    pub loss: Option<Tensor>,
 }
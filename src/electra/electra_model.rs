@@ -16,6 +16,7 @@ use crate::bert::BertConfig;
 use crate::common::activations::Activation;
 use crate::common::dropout::Dropout;
 use crate::common::embeddings::get_shape_and_device_from_ids_embeddings_pair;
+use crate::common::loss::cross_entropy_loss;
 use crate::electra::embeddings::ElectraEmbeddings;
 use crate::{bert::encoder::BertEncoder, common::activations::TensorFunction};
 use crate::{Config, RustBertError};
@@ -94,6 +95,9 @@ pub struct ElectraConfig {
     pub output_hidden_states: Option<bool>,
     pub id2label: Option<HashMap<i64, String>>,
     pub label2id: Option<HashMap<String, i64>>,
+    /// If set, the attention is computed for blocks of `attention_chunk_size` queries at a time, limiting the
+    /// memory used by the attention scores (not applied when the attention weights are returned)
+    pub attention_chunk_size: Option<i64>,
 }
 
 impl Config for ElectraConfig {}
@@ -120,6 +124,7 @@ impl Default for ElectraConfig {
             output_hidden_states: None,
             id2label: None,
             label2id: None,
+            attention_chunk_size: None,
         }
     }
 }
@@ -193,6 +198,13 @@ impl ElectraModel {
             is_decoder: None,
             id2label: config.id2label.clone(),
             label2id: config.label2id.clone(),
+            problem_type: None,
+            lora: None,
+            position_embedding_type: None,
+            rotary_embedding_base: None,
+            local_attention_window: None,
+            global_attention_every_n_layers: None,
+            attention_chunk_size: config.attention_chunk_size,
         };
         let encoder = BertEncoder::new(p / "encoder", &bert_config);
         ElectraModel {
@@ -575,12 +587,14 @@ impl ElectraForMaskedLM {
     /// * `token_type_ids` - Optional segment id of shape (*batch size*, *sequence_length*). Convention is value of 0 for the first sentence (incl. *SEP*) and 1 for the second sentence. If None set to 0.
     /// * `position_ids` - Optional position ids of shape (*batch size*, *sequence_length*). If None, will be incremented from 0.
     /// * `input_embeds` - Optional pre-computed input embeddings of shape (*batch size*, *sequence_length*, *hidden_size*). If None, input ids must be provided (see `input_ids`)
+    /// * `labels` - Optional target token ids of shape (*batch size*, *sequence_length*) used to compute the masked language modeling loss. Positions with a label of -100 (e.g. non-masked tokens) are ignored.
     /// * `train` - boolean flag to turn on/off the dropout layers in the model. Should be set to false for inference.
     ///
     /// # Returns
     ///
     /// * `ElectraMaskedLMOutput` containing:
     ///   - `prediction_scores` - `Tensor` of shape (*batch size*, *sequence_length*, *vocab_size*)
+    ///   - `loss` - `Option<Tensor>` scalar loss, computed if labels are provided
     ///   - `all_hidden_states` - `Option<Vec<Tensor>>` of length *num_hidden_layers* with shape (*batch size*, *sequence_length*, *hidden_size*)
     ///   - `all_attentions` - `Option<Vec<Tensor>>` of length *num_hidden_layers* with shape (*batch size*, *sequence_length*, *hidden_size*)
     ///
@@ -611,6 +625,7 @@ impl ElectraForMaskedLM {
     ///         Some(&token_type_ids),
     ///         Some(&position_ids),
     ///         None,
+    ///         None,
     ///         false,
     ///     )
     /// });
@@ -622,6 +637,7 @@ impl ElectraForMaskedLM {
         token_type_ids: Option<&Tensor>,
         position_ids: Option<&Tensor>,
         input_embeds: Option<&Tensor>,
+        labels: Option<&Tensor>,
         train: bool,
     ) -> ElectraMaskedLMOutput {
         let base_model_output = self
@@ -637,7 +653,9 @@ impl ElectraForMaskedLM {
             .unwrap();
         let hidden_states = self.generator_head.forward(&base_model_output.hidden_state);
         let prediction_scores = hidden_states.apply(&self.lm_head);
+        let loss = labels.map(|labels| cross_entropy_loss(&prediction_scores, labels));
         ElectraMaskedLMOutput {
+            loss,
             prediction_scores,
             all_hidden_states: base_model_output.all_hidden_states,
             all_attentions: base_model_output.all_attentions,
@@ -851,12 +869,14 @@ impl ElectraForTokenClassification {
     /// * `token_type_ids` - Optional segment id of shape (*batch size*, *sequence_length*). Convention is value of 0 for the first sentence (incl. *SEP*) and 1 for the second sentence. If None set to 0.
     /// * `position_ids` - Optional position ids of shape (*batch size*, *sequence_length*). If None, will be incremented from 0.
     /// * `input_embeds` - Optional pre-computed input embeddings of shape (*batch size*, *sequence_length*, *hidden_size*). If None, input ids must be provided (see `input_ids`)
+    /// * `labels` - Optional labels of shape (*batch size*, *sequence_length*) used to compute the cross-entropy loss. Positions with a label of -100 are ignored.
     /// * `train` - boolean flag to turn on/off the dropout layers in the model. Should be set to false for inference.
     ///
     /// # Returns
     ///
     /// * `ElectraTokenClassificationOutput` containing:
     ///   - `logits` - `Tensor` of shape (*batch size*, *sequence_length*, *num_labels*) containing the logits for each of the input tokens and classes
+    ///   - `loss` - `Option<Tensor>` scalar loss, computed if labels are provided
     ///   - `all_hidden_states` - `Option<Vec<Tensor>>` of length *num_hidden_layers* with shape (*batch size*, *sequence_length*, *hidden_size*)
     ///   - `all_attentions` - `Option<Vec<Tensor>>` of length *num_hidden_layers* with shape (*batch size*, *sequence_length*, *hidden_size*)
     ///
@@ -886,6 +906,7 @@ impl ElectraForTokenClassification {
     ///                    Some(&token_type_ids),
     ///                    Some(&position_ids),
     ///                    None,
+    ///                    None,
     ///                    false)
     ///    });
     /// ```
@@ -896,6 +917,7 @@ impl ElectraForTokenClassification {
         token_type_ids: Option<&Tensor>,
         position_ids: Option<&Tensor>,
         input_embeds: Option<&Tensor>,
+        labels: Option<&Tensor>,
         train: bool,
     ) -> ElectraTokenClassificationOutput {
         let base_model_output = self
@@ -913,7 +935,9 @@ impl ElectraForTokenClassification {
             .hidden_state
             .apply_t(&self.dropout, train)
             .apply(&self.classifier);
+        let loss = labels.map(|labels| cross_entropy_loss(&logits, labels));
         ElectraTokenClassificationOutput {
+            loss,
             logits,
             all_hidden_states: base_model_output.all_hidden_states,
             all_attentions: base_model_output.all_attentions,
@@ -921,6 +945,10 @@ impl ElectraForTokenClassification {
     }
 }
 
+/// # Electra for sentence embeddings
+/// Transformer usable in [`SentenceEmbeddingsModel`](crate::pipelines::sentence_embeddings::SentenceEmbeddingsModel).
+pub type ElectraForSentenceEmbeddings = ElectraModel;
+
 /// Container for the Electra model output.
 pub struct ElectraModelOutput {
     /// Last hidden states from the model
@@ -949,6 +977,8 @@ pub struct ElectraMaskedLMOutput {
     pub all_hidden_states: Option<Vec<Tensor>>,
     /// Attention weights for all intermediate layers
     pub all_attentions: Option<Vec<Tensor>>,
+    /// Loss computed from the labels, if provided
+    pub loss: Option<Tensor>,
 }
 
 /// Container for the Electra token classification model output.
@@ -959,4 +989,6 @@ pub struct ElectraTokenClassificationOutput {
     pub all_hidden_states: Option<Vec<Tensor>>,
     /// Attention weights for all intermediate layers
     pub all_attentions: Option<Vec<Tensor>>,
+    /// Loss computed from the labels, if provided
+    pub loss: Option<Tensor>,
 }
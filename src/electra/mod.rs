@@ -60,7 +60,8 @@ mod embeddings;
 
 pub use electra_model::{
     ElectraConfig, ElectraConfigResources, ElectraDiscriminator, ElectraDiscriminatorHead,
-    ElectraDiscriminatorOutput, ElectraForMaskedLM, ElectraForTokenClassification,
-    ElectraGeneratorHead, ElectraMaskedLMOutput, ElectraModel, ElectraModelOutput,
-    ElectraModelResources, ElectraTokenClassificationOutput, ElectraVocabResources,
+    ElectraDiscriminatorOutput, ElectraForMaskedLM, ElectraForSentenceEmbeddings,
+    ElectraForTokenClassification, ElectraGeneratorHead, ElectraMaskedLMOutput, ElectraModel,
+    ElectraModelOutput, ElectraModelResources, ElectraTokenClassificationOutput,
+    ElectraVocabResources,
 };
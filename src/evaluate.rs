@@ -0,0 +1,345 @@
+// Copyright 2023 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+extern crate tch;
+
+use rust_bert::evaluation::classification::{classification_metrics, glue_processor};
+use rust_bert::evaluation::sequence_labeling::{
+    conll_processor, predict_word_tags, sequence_labeling_report,
+};
+use rust_bert::evaluation::squad::{squad_evaluate, squad_examples_processor};
+use rust_bert::evaluation::summarization::rouge;
+use rust_bert::evaluation::translation::{corpus_bleu, corpus_chrf, parallel_corpus_processor};
+use rust_bert::pipelines::common::ModelType;
+use rust_bert::pipelines::question_answering::{
+    QaInput, QuestionAnsweringConfig, QuestionAnsweringModel,
+};
+use rust_bert::pipelines::sequence_classification::{
+    SequenceClassificationConfig, SequenceClassificationModel,
+};
+use rust_bert::pipelines::summarization::SummarizationModel;
+use rust_bert::pipelines::token_classification::{
+    LabelAggregationOption, TokenClassificationConfig, TokenClassificationModel,
+};
+use rust_bert::pipelines::translation::{Language, TranslationModelBuilder};
+use rust_bert::resources::LocalResource;
+use rust_bert::RustBertError;
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+const USAGE: &str =
+    "usage: evaluate <squad|conll|glue|translation|summarization> <dataset_path> [options]
+
+options:
+    --limit <n>                 evaluate only the first n examples
+    --batch-size <n>            batch size for pipelines supporting it (default: 32)
+    --text-column <name>        GLUE text column (default: sentence)
+    --label-column <name>       GLUE label column (default: label)
+    --source-language <name>    translation source language (e.g. English)
+    --target-language <name>    translation target language (e.g. French)
+    --model-type <type>         model type of a local model (e.g. Bert, DistilBert)
+    --model <path>              local model weights (.ot), requires --config and --vocab
+    --config <path>             local model configuration (config.json)
+    --vocab <path>              local vocabulary file
+    --merges <path>             local merges file (BPE tokenizers)
+    --lower-case                lower case inputs of the local model";
+
+struct LocalModel {
+    model_type: ModelType,
+    model: LocalResource,
+    config: LocalResource,
+    vocab: LocalResource,
+    merges: Option<LocalResource>,
+    lower_case: bool,
+}
+
+struct Arguments {
+    task: String,
+    dataset: PathBuf,
+    options: HashMap<String, String>,
+    lower_case: bool,
+}
+
+impl Arguments {
+    fn parse() -> Result<Self, RustBertError> {
+        let mut args = std::env::args().skip(1);
+        let (task, dataset) = match (args.next(), args.next()) {
+            (Some(task), Some(dataset)) => (task, PathBuf::from(dataset)),
+            _ => return Err(RustBertError::ValueError(USAGE.to_string())),
+        };
+        let mut options = HashMap::new();
+        let mut lower_case = false;
+        while let Some(flag) = args.next() {
+            match flag.strip_prefix("--") {
+                Some("lower-case") => lower_case = true,
+                Some(name) => {
+                    let value = args.next().ok_or_else(|| {
+                        RustBertError::ValueError(format!("Missing value for --{name}\n{USAGE}"))
+                    })?;
+                    options.insert(name.to_string(), value);
+                }
+                None => {
+                    return Err(RustBertError::ValueError(format!(
+                        "Unexpected argument {flag}\n{USAGE}"
+                    )))
+                }
+            }
+        }
+        Ok(Arguments {
+            task,
+            dataset,
+            options,
+            lower_case,
+        })
+    }
+
+    fn get_usize(&self, name: &str) -> Result<Option<usize>, RustBertError> {
+        self.options
+            .get(name)
+            .map(|value| {
+                value.parse::<usize>().map_err(|_| {
+                    RustBertError::ValueError(format!("--{name} expects an integer, got {value}"))
+                })
+            })
+            .transpose()
+    }
+
+    fn get_language(&self, name: &str) -> Result<Option<Language>, RustBertError> {
+        self.options
+            .get(name)
+            .map(|value| {
+                serde_json::from_value(serde_json::Value::String(value.clone())).map_err(|_| {
+                    RustBertError::ValueError(format!("--{name}: unknown language {value}"))
+                })
+            })
+            .transpose()
+    }
+
+    fn local_model(&self) -> Result<Option<LocalModel>, RustBertError> {
+        let model = match self.options.get("model") {
+            Some(model) => model,
+            None => return Ok(None),
+        };
+        let get_required = |name: &str| {
+            self.options.get(name).ok_or_else(|| {
+                RustBertError::InvalidConfigurationError(format!(
+                    "--{name} must be provided for a local model"
+                ))
+            })
+        };
+        let model_type = serde_json::from_value(serde_json::Value::String(
+            get_required("model-type")?.clone(),
+        ))
+        .map_err(|e| RustBertError::InvalidConfigurationError(e.to_string()))?;
+        Ok(Some(LocalModel {
+            model_type,
+            model: LocalResource::from(PathBuf::from(model)),
+            config: LocalResource::from(PathBuf::from(get_required("config")?)),
+            vocab: LocalResource::from(PathBuf::from(get_required("vocab")?)),
+            merges: self
+                .options
+                .get("merges")
+                .map(|path| LocalResource::from(PathBuf::from(path))),
+            lower_case: self.lower_case,
+        }))
+    }
+}
+
+fn truncate<T>(mut examples: Vec<T>, limit: Option<usize>) -> Vec<T> {
+    if let Some(limit) = limit {
+        examples.truncate(limit);
+    }
+    examples
+}
+
+fn evaluate_squad(arguments: &Arguments) -> Result<(), RustBertError> {
+    let examples = truncate(
+        squad_examples_processor(&arguments.dataset)?,
+        arguments.get_usize("limit")?,
+    );
+    let config = match arguments.local_model()? {
+        Some(local) => QuestionAnsweringConfig::new(
+            local.model_type,
+            local.model,
+            local.config,
+            local.vocab,
+            local.merges,
+            local.lower_case,
+            None,
+            None,
+        ),
+        None => QuestionAnsweringConfig::default(),
+    };
+    let model = QuestionAnsweringModel::new(config)?;
+    let inputs = examples.iter().map(QaInput::from).collect::<Vec<QaInput>>();
+    let predictions = model
+        .predict(&inputs, 1, arguments.get_usize("batch-size")?.unwrap_or(32))
+        .into_iter()
+        .map(|answers| {
+            answers
+                .into_iter()
+                .next()
+                .map(|answer| answer.answer)
+                .unwrap_or_default()
+        })
+        .collect::<Vec<String>>();
+    let references = examples
+        .into_iter()
+        .map(|example| example.answers)
+        .collect::<Vec<Vec<String>>>();
+    println!("{}", squad_evaluate(&predictions, &references)?);
+    Ok(())
+}
+
+fn evaluate_conll(arguments: &Arguments) -> Result<(), RustBertError> {
+    let sentences = truncate(
+        conll_processor(&arguments.dataset)?,
+        arguments.get_usize("limit")?,
+    );
+    let mut config = match arguments.local_model()? {
+        Some(local) => TokenClassificationConfig::new(
+            local.model_type,
+            local.model,
+            local.config,
+            local.vocab,
+            local.merges,
+            local.lower_case,
+            None,
+            None,
+            LabelAggregationOption::First,
+        ),
+        None => TokenClassificationConfig::default(),
+    };
+    if let Some(batch_size) = arguments.get_usize("batch-size")? {
+        config.batch_size = batch_size;
+    }
+    let model = TokenClassificationModel::new(config)?;
+    let (words, references): (Vec<Vec<String>>, Vec<Vec<String>>) = sentences.into_iter().unzip();
+    let predictions = predict_word_tags(&model, &words);
+    println!("{}", sequence_labeling_report(&references, &predictions)?);
+    Ok(())
+}
+
+fn evaluate_glue(arguments: &Arguments) -> Result<(), RustBertError> {
+    let text_column = arguments
+        .options
+        .get("text-column")
+        .map(String::as_str)
+        .unwrap_or("sentence");
+    let label_column = arguments
+        .options
+        .get("label-column")
+        .map(String::as_str)
+        .unwrap_or("label");
+    let examples = truncate(
+        glue_processor(&arguments.dataset, text_column, label_column)?,
+        arguments.get_usize("limit")?,
+    );
+    let config = match arguments.local_model()? {
+        Some(local) => SequenceClassificationConfig::new(
+            local.model_type,
+            local.model,
+            local.config,
+            local.vocab,
+            local.merges,
+            local.lower_case,
+            None,
+            None,
+        ),
+        None => SequenceClassificationConfig::default(),
+    };
+    let model = SequenceClassificationModel::new(config)?;
+    let batch_size = arguments.get_usize("batch-size")?.unwrap_or(32);
+    let mut predictions = Vec::with_capacity(examples.len());
+    for batch in examples.chunks(batch_size) {
+        let texts = batch
+            .iter()
+            .map(|(text, _)| text.as_str())
+            .collect::<Vec<&str>>();
+        predictions.extend(model.predict(texts).into_iter().map(|label| label.id));
+    }
+    let references = examples
+        .iter()
+        .map(|(_, label)| *label)
+        .collect::<Vec<i64>>();
+    println!("{}", classification_metrics(&references, &predictions)?);
+    Ok(())
+}
+
+fn evaluate_translation(arguments: &Arguments) -> Result<(), RustBertError> {
+    let pairs = truncate(
+        parallel_corpus_processor(&arguments.dataset)?,
+        arguments.get_usize("limit")?,
+    );
+    let source_language = arguments.get_language("source-language")?;
+    let target_language = arguments.get_language("target-language")?;
+    let mut builder = TranslationModelBuilder::new();
+    if let Some(source_language) = source_language {
+        builder.with_source_languages(vec![source_language]);
+    }
+    if let Some(target_language) = target_language {
+        builder.with_target_languages(vec![target_language]);
+    }
+    let model = builder.create_model()?;
+    let batch_size = arguments.get_usize("batch-size")?.unwrap_or(32);
+    let mut hypotheses = Vec::with_capacity(pairs.len());
+    for batch in pairs.chunks(batch_size) {
+        let sources = batch
+            .iter()
+            .map(|(source, _)| source.as_str())
+            .collect::<Vec<&str>>();
+        hypotheses.extend(model.translate(&sources, source_language, target_language)?);
+    }
+    let references = pairs
+        .into_iter()
+        .map(|(_, reference)| vec![reference])
+        .collect::<Vec<Vec<String>>>();
+    println!("{}", corpus_bleu(&hypotheses, &references)?);
+    println!("{}", corpus_chrf(&hypotheses, &references, false)?);
+    Ok(())
+}
+
+fn evaluate_summarization(arguments: &Arguments) -> Result<(), RustBertError> {
+    let pairs = truncate(
+        parallel_corpus_processor(&arguments.dataset)?,
+        arguments.get_usize("limit")?,
+    );
+    let model = SummarizationModel::new(Default::default())?;
+    let batch_size = arguments.get_usize("batch-size")?.unwrap_or(8);
+    let mut hypotheses = Vec::with_capacity(pairs.len());
+    for batch in pairs.chunks(batch_size) {
+        let documents = batch
+            .iter()
+            .map(|(document, _)| document.as_str())
+            .collect::<Vec<&str>>();
+        hypotheses.extend(model.summarize(&documents));
+    }
+    let references = pairs
+        .into_iter()
+        .map(|(_, reference)| vec![reference])
+        .collect::<Vec<Vec<String>>>();
+    print!("{}", rouge(&hypotheses, &references)?);
+    Ok(())
+}
+
+pub fn main() -> Result<(), RustBertError> {
+    let arguments = Arguments::parse()?;
+    match arguments.task.as_str() {
+        "squad" => evaluate_squad(&arguments),
+        "conll" => evaluate_conll(&arguments),
+        "glue" => evaluate_glue(&arguments),
+        "translation" => evaluate_translation(&arguments),
+        "summarization" => evaluate_summarization(&arguments),
+        task => Err(RustBertError::ValueError(format!(
+            "Unknown task {task}\n{USAGE}"
+        ))),
+    }
+}
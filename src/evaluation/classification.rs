@@ -0,0 +1,235 @@
+// Copyright 2023 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! # Sequence classification metrics
+//! Accuracy, F1 score and Matthews correlation coefficient (as reported on the GLUE benchmark)
+//! for single-label classification.
+
+use crate::common::error::RustBertError;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+/// # Classification evaluation report
+pub struct ClassificationMetrics {
+    /// Accuracy
+    pub accuracy: f64,
+    /// Macro-averaged F1 score over all labels
+    pub macro_f1: f64,
+    /// Matthews correlation coefficient
+    pub mcc: f64,
+    /// Number of examples evaluated
+    pub total: usize,
+}
+
+impl fmt::Display for ClassificationMetrics {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "accuracy: {:.4}", self.accuracy)?;
+        writeln!(f, "macro_f1: {:.4}", self.macro_f1)?;
+        writeln!(f, "mcc: {:.4}", self.mcc)?;
+        write!(f, "total: {}", self.total)
+    }
+}
+
+fn check_lengths(references: &[i64], predictions: &[i64]) -> Result<(), RustBertError> {
+    if references.len() != predictions.len() {
+        return Err(RustBertError::ValueError(format!(
+            "Number of references ({}) and predictions ({}) differ",
+            references.len(),
+            predictions.len()
+        )));
+    }
+    Ok(())
+}
+
+/// Fraction of predictions matching the reference labels.
+pub fn accuracy(references: &[i64], predictions: &[i64]) -> Result<f64, RustBertError> {
+    check_lengths(references, predictions)?;
+    if references.is_empty() {
+        return Ok(0.0);
+    }
+    let correct = references
+        .iter()
+        .zip(predictions.iter())
+        .filter(|(reference, prediction)| reference == prediction)
+        .count();
+    Ok(correct as f64 / references.len() as f64)
+}
+
+/// F1 score for a given positive label (binary F1, e.g. for MRPC or QQP).
+pub fn f1_score(
+    references: &[i64],
+    predictions: &[i64],
+    positive_label: i64,
+) -> Result<f64, RustBertError> {
+    check_lengths(references, predictions)?;
+    let (mut true_positives, mut false_positives, mut false_negatives) = (0usize, 0usize, 0usize);
+    for (&reference, &prediction) in references.iter().zip(predictions.iter()) {
+        match (reference == positive_label, prediction == positive_label) {
+            (true, true) => true_positives += 1,
+            (false, true) => false_positives += 1,
+            (true, false) => false_negatives += 1,
+            (false, false) => {}
+        }
+    }
+    let denominator = 2 * true_positives + false_positives + false_negatives;
+    Ok(if denominator > 0 {
+        2.0 * true_positives as f64 / denominator as f64
+    } else {
+        0.0
+    })
+}
+
+/// F1 score averaged over all labels present in the references or predictions.
+pub fn macro_f1_score(references: &[i64], predictions: &[i64]) -> Result<f64, RustBertError> {
+    check_lengths(references, predictions)?;
+    let labels = references
+        .iter()
+        .chain(predictions.iter())
+        .copied()
+        .collect::<BTreeSet<i64>>();
+    if labels.is_empty() {
+        return Ok(0.0);
+    }
+    let mut total = 0.0;
+    for label in labels.iter() {
+        total += f1_score(references, predictions, *label)?;
+    }
+    Ok(total / labels.len() as f64)
+}
+
+/// Matthews correlation coefficient (multi-class generalization, as used for CoLA).
+pub fn matthews_corrcoef(references: &[i64], predictions: &[i64]) -> Result<f64, RustBertError> {
+    check_lengths(references, predictions)?;
+    let mut reference_counts: HashMap<i64, f64> = HashMap::new();
+    let mut prediction_counts: HashMap<i64, f64> = HashMap::new();
+    let mut correct = 0.0;
+    for (&reference, &prediction) in references.iter().zip(predictions.iter()) {
+        *reference_counts.entry(reference).or_insert(0.0) += 1.0;
+        *prediction_counts.entry(prediction).or_insert(0.0) += 1.0;
+        if reference == prediction {
+            correct += 1.0;
+        }
+    }
+    let samples = references.len() as f64;
+    let labels = reference_counts
+        .keys()
+        .chain(prediction_counts.keys())
+        .copied()
+        .collect::<BTreeSet<i64>>();
+    let (mut covariance_pred_ref, mut sum_pred_squared, mut sum_ref_squared) = (0.0, 0.0, 0.0);
+    for label in labels {
+        let reference_count = reference_counts.get(&label).copied().unwrap_or(0.0);
+        let prediction_count = prediction_counts.get(&label).copied().unwrap_or(0.0);
+        covariance_pred_ref += prediction_count * reference_count;
+        sum_pred_squared += prediction_count * prediction_count;
+        sum_ref_squared += reference_count * reference_count;
+    }
+    let numerator = correct * samples - covariance_pred_ref;
+    let denominator =
+        ((samples * samples - sum_pred_squared) * (samples * samples - sum_ref_squared)).sqrt();
+    Ok(if denominator > 0.0 {
+        numerator / denominator
+    } else {
+        0.0
+    })
+}
+
+/// Computes accuracy, macro-F1 and MCC for a set of predictions.
+///
+/// # Example
+///
+/// ```
+/// use rust_bert::evaluation::classification::classification_metrics;
+///
+/// let metrics = classification_metrics(&[0, 1, 1, 0], &[0, 1, 0, 0]).unwrap();
+/// assert_eq!(metrics.accuracy, 0.75);
+/// ```
+pub fn classification_metrics(
+    references: &[i64],
+    predictions: &[i64],
+) -> Result<ClassificationMetrics, RustBertError> {
+    Ok(ClassificationMetrics {
+        accuracy: accuracy(references, predictions)?,
+        macro_f1: macro_f1_score(references, predictions)?,
+        mcc: matthews_corrcoef(references, predictions)?,
+        total: references.len(),
+    })
+}
+
+/// Reads a GLUE-style tab-separated file with a header row.
+///
+/// # Arguments
+///
+/// * `file_path` - Path to the TSV file (e.g. `SST-2/dev.tsv`)
+/// * `text_column` - Name of the column holding the input text (e.g. `sentence`)
+/// * `label_column` - Name of the column holding the integer label (e.g. `label`)
+///
+/// # Returns
+///
+/// * `Vec<(String, i64)>` input texts and their reference labels
+pub fn glue_processor<P: AsRef<Path>>(
+    file_path: P,
+    text_column: &str,
+    label_column: &str,
+) -> Result<Vec<(String, i64)>, RustBertError> {
+    let content = fs::read_to_string(file_path)?;
+    let mut lines = content.lines();
+    let header = lines
+        .next()
+        .ok_or_else(|| RustBertError::ValueError("Empty GLUE file".to_string()))?
+        .split('\t')
+        .collect::<Vec<&str>>();
+    let column_index = |name: &str| {
+        header
+            .iter()
+            .position(|column| *column == name)
+            .ok_or_else(|| RustBertError::ValueError(format!("Column {name} not found in header")))
+    };
+    let text_index = column_index(text_column)?;
+    let label_index = column_index(label_column)?;
+
+    let mut examples = Vec::new();
+    for line in lines.filter(|line| !line.trim().is_empty()) {
+        let columns = line.split('\t').collect::<Vec<&str>>();
+        let (text, label) = match (columns.get(text_index), columns.get(label_index)) {
+            (Some(text), Some(label)) => (text, label),
+            _ => {
+                return Err(RustBertError::ValueError(format!(
+                    "Missing columns in GLUE line: {line}"
+                )))
+            }
+        };
+        let label = label.trim().parse::<i64>().map_err(|_| {
+            RustBertError::ValueError(format!("Invalid integer label {label} in line: {line}"))
+        })?;
+        examples.push((text.to_string(), label));
+    }
+    Ok(examples)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_classification_metrics() {
+        let references = [1, 1, 0, 0, 1, 0];
+        let predictions = [1, 0, 0, 0, 1, 1];
+        assert!((accuracy(&references, &predictions).unwrap() - 4.0 / 6.0).abs() < 1e-9);
+        assert!((f1_score(&references, &predictions, 1).unwrap() - 2.0 / 3.0).abs() < 1e-9);
+        assert!((matthews_corrcoef(&references, &predictions).unwrap() - 1.0 / 3.0).abs() < 1e-9);
+        assert_eq!(matthews_corrcoef(&[0, 1], &[0, 1]).unwrap(), 1.0);
+    }
+}
@@ -0,0 +1,41 @@
+//! # Evaluation metrics for pipelines
+//!
+//! Accuracy metrics allowing to validate the predictions of pipelines against reference datasets,
+//! for example after converting or quantizing a model:
+//! - [`squad`]: exact match and F1 score for extractive question answering (SQuAD v1.1/v2.0)
+//! - [`sequence_labeling`]: entity-level precision, recall and F1 (seqeval conventions) for token classification (CoNLL)
+//! - [`classification`]: accuracy, F1 score and Matthews correlation coefficient for sequence classification (GLUE)
+//! - [`translation`]: corpus-level BLEU and chrF scores
+//! - [`summarization`]: ROUGE-1, ROUGE-2 and ROUGE-L scores
+//!
+//! Each module also provides a reader for the corresponding local dataset format. The `evaluate`
+//! binary runs the default pipeline for a task over such a dataset and prints a report:
+//! `cargo run --release --bin evaluate -- squad path/to/dev-v2.0.json`
+//!
+//! ```no_run
+//! use rust_bert::evaluation::squad::{squad_evaluate, squad_examples_processor};
+//! use rust_bert::pipelines::question_answering::{QaInput, QuestionAnsweringModel};
+//! # fn main() -> anyhow::Result<()> {
+//! let qa_model = QuestionAnsweringModel::new(Default::default())?;
+//! let examples = squad_examples_processor("path/to/dev-v1.1.json")?;
+//! let inputs = examples.iter().map(QaInput::from).collect::<Vec<QaInput>>();
+//! let predictions = qa_model
+//!     .predict(&inputs, 1, 32)
+//!     .into_iter()
+//!     .map(|answers| answers.into_iter().next().map(|answer| answer.answer).unwrap_or_default())
+//!     .collect::<Vec<String>>();
+//! let references = examples
+//!     .into_iter()
+//!     .map(|example| example.answers)
+//!     .collect::<Vec<Vec<String>>>();
+//! let metrics = squad_evaluate(&predictions, &references)?;
+//! println!("{metrics}");
+//! # Ok(())
+//! # }
+//! ```
+
+pub mod classification;
+pub mod sequence_labeling;
+pub mod squad;
+pub mod summarization;
+pub mod translation;
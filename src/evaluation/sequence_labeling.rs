@@ -0,0 +1,412 @@
+// Copyright (c) 2018 chakki (https://github.com/chakki-works/seqeval/blob/master/seqeval/metrics/sequence_labeling.py)
+// Copyright 2023 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! # Sequence labeling metrics
+//! Entity-level precision, recall and F1 scores for token classification (e.g. CoNLL-2003 NER),
+//! following the chunking conventions of the [seqeval library](https://github.com/chakki-works/seqeval).
+//! An entity is considered correctly predicted only if both its label and its boundaries match the reference.
+
+use crate::common::error::RustBertError;
+use crate::pipelines::token_classification::TokenClassificationModel;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+/// # Entity chunk extracted from a sequence of tags
+pub struct EntitySpan {
+    /// Entity type (e.g. PER, LOC)
+    pub label: String,
+    /// Index of the first word of the entity
+    pub start: usize,
+    /// Index of the last word of the entity (inclusive)
+    pub end: usize,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+/// # Precision, recall and F1 score for a set of entities
+pub struct EntityMetrics {
+    /// Precision
+    pub precision: f64,
+    /// Recall
+    pub recall: f64,
+    /// F1 score
+    pub f1: f64,
+    /// Number of reference entities
+    pub support: usize,
+}
+
+impl EntityMetrics {
+    fn from_counts(true_positives: usize, num_predicted: usize, num_reference: usize) -> Self {
+        let precision = if num_predicted > 0 {
+            true_positives as f64 / num_predicted as f64
+        } else {
+            0.0
+        };
+        let recall = if num_reference > 0 {
+            true_positives as f64 / num_reference as f64
+        } else {
+            0.0
+        };
+        let f1 = if precision + recall > 0.0 {
+            2.0 * precision * recall / (precision + recall)
+        } else {
+            0.0
+        };
+        EntityMetrics {
+            precision,
+            recall,
+            f1,
+            support: num_reference,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// # Entity-level classification report
+pub struct SequenceLabelingReport {
+    /// Metrics for each entity type
+    pub per_label: BTreeMap<String, EntityMetrics>,
+    /// Micro-averaged metrics over all entity types
+    pub micro: EntityMetrics,
+    /// Token-level accuracy
+    pub accuracy: f64,
+}
+
+impl fmt::Display for SequenceLabelingReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "{:>12} {:>10} {:>10} {:>10} {:>10}",
+            "", "precision", "recall", "f1", "support"
+        )?;
+        for (label, metrics) in self.per_label.iter() {
+            writeln!(
+                f,
+                "{:>12} {:>10.4} {:>10.4} {:>10.4} {:>10}",
+                label, metrics.precision, metrics.recall, metrics.f1, metrics.support
+            )?;
+        }
+        writeln!(
+            f,
+            "{:>12} {:>10.4} {:>10.4} {:>10.4} {:>10}",
+            "micro avg", self.micro.precision, self.micro.recall, self.micro.f1, self.micro.support
+        )?;
+        write!(f, "accuracy: {:.4}", self.accuracy)
+    }
+}
+
+fn split_tag(tag: &str) -> (&str, &str) {
+    match tag.split_once(['-', '_']) {
+        Some((prefix, label)) => (prefix, label),
+        None if tag == "O" => ("O", ""),
+        None => ("I", tag),
+    }
+}
+
+fn end_of_chunk(previous_prefix: &str, prefix: &str, previous_label: &str, label: &str) -> bool {
+    matches!(
+        (previous_prefix, prefix),
+        ("E", _)
+            | ("S", _)
+            | ("B", "B")
+            | ("B", "S")
+            | ("B", "O")
+            | ("I", "B")
+            | ("I", "S")
+            | ("I", "O")
+    ) || (previous_prefix != "O" && previous_label != label)
+}
+
+fn start_of_chunk(previous_prefix: &str, prefix: &str, previous_label: &str, label: &str) -> bool {
+    matches!(
+        (previous_prefix, prefix),
+        (_, "B")
+            | (_, "S")
+            | ("E", "E")
+            | ("E", "I")
+            | ("S", "E")
+            | ("S", "I")
+            | ("O", "E")
+            | ("O", "I")
+    ) || (prefix != "O" && previous_label != label)
+}
+
+/// Extracts entity chunks from a sequence of IOB1/IOB2/IOE/IOBES tags.
+///
+/// # Arguments
+///
+/// * `tags` - Sequence of tags, e.g. `["B-PER", "I-PER", "O"]`
+///
+/// # Example
+///
+/// ```
+/// use rust_bert::evaluation::sequence_labeling::get_entities;
+///
+/// let entities = get_entities(&["B-PER", "I-PER", "O", "B-LOC"]);
+/// assert_eq!(entities.len(), 2);
+/// assert_eq!((entities[0].start, entities[0].end), (0, 1));
+/// ```
+pub fn get_entities<S: AsRef<str>>(tags: &[S]) -> Vec<EntitySpan> {
+    let mut entities = Vec::new();
+    let mut previous_prefix = "O";
+    let mut previous_label = "";
+    let mut begin_offset = 0usize;
+
+    for (position, tag) in tags.iter().map(AsRef::as_ref).chain(["O"]).enumerate() {
+        let (prefix, label) = split_tag(tag);
+        if end_of_chunk(previous_prefix, prefix, previous_label, label) {
+            entities.push(EntitySpan {
+                label: previous_label.to_string(),
+                start: begin_offset,
+                end: position - 1,
+            });
+        }
+        if start_of_chunk(previous_prefix, prefix, previous_label, label) {
+            begin_offset = position;
+        }
+        previous_prefix = prefix;
+        previous_label = label;
+    }
+    entities
+}
+
+/// Computes the entity-level classification report for a set of tagged sequences.
+///
+/// # Arguments
+///
+/// * `references` - Reference tags for each sequence
+/// * `predictions` - Predicted tags for each sequence (must have the same shape as the references)
+///
+/// # Returns
+///
+/// * `SequenceLabelingReport` with per-label and micro-averaged metrics
+pub fn sequence_labeling_report<R, P>(
+    references: &[Vec<R>],
+    predictions: &[Vec<P>],
+) -> Result<SequenceLabelingReport, RustBertError>
+where
+    R: AsRef<str>,
+    P: AsRef<str>,
+{
+    if references.len() != predictions.len() {
+        return Err(RustBertError::ValueError(format!(
+            "Number of reference sequences ({}) and predicted sequences ({}) differ",
+            references.len(),
+            predictions.len()
+        )));
+    }
+
+    let mut reference_entities = HashSet::new();
+    let mut predicted_entities = HashSet::new();
+    let mut correct_tokens = 0usize;
+    let mut total_tokens = 0usize;
+    for (sequence_index, (reference, prediction)) in
+        references.iter().zip(predictions.iter()).enumerate()
+    {
+        if reference.len() != prediction.len() {
+            return Err(RustBertError::ValueError(format!(
+                "Sequence {} has {} reference tags but {} predicted tags",
+                sequence_index,
+                reference.len(),
+                prediction.len()
+            )));
+        }
+        correct_tokens += reference
+            .iter()
+            .zip(prediction.iter())
+            .filter(|(reference_tag, predicted_tag)| {
+                reference_tag.as_ref() == predicted_tag.as_ref()
+            })
+            .count();
+        total_tokens += reference.len();
+        reference_entities.extend(
+            get_entities(reference)
+                .into_iter()
+                .map(|entity| (sequence_index, entity)),
+        );
+        predicted_entities.extend(
+            get_entities(prediction)
+                .into_iter()
+                .map(|entity| (sequence_index, entity)),
+        );
+    }
+
+    let labels = reference_entities
+        .iter()
+        .chain(predicted_entities.iter())
+        .map(|(_, entity)| entity.label.as_str())
+        .collect::<HashSet<&str>>();
+
+    let mut per_label = BTreeMap::new();
+    for label in labels {
+        let num_reference = reference_entities
+            .iter()
+            .filter(|(_, entity)| entity.label == label)
+            .count();
+        let num_predicted = predicted_entities
+            .iter()
+            .filter(|(_, entity)| entity.label == label)
+            .count();
+        let true_positives = reference_entities
+            .intersection(&predicted_entities)
+            .filter(|(_, entity)| entity.label == label)
+            .count();
+        per_label.insert(
+            label.to_string(),
+            EntityMetrics::from_counts(true_positives, num_predicted, num_reference),
+        );
+    }
+
+    let true_positives = reference_entities.intersection(&predicted_entities).count();
+    let micro = EntityMetrics::from_counts(
+        true_positives,
+        predicted_entities.len(),
+        reference_entities.len(),
+    );
+    let accuracy = if total_tokens > 0 {
+        correct_tokens as f64 / total_tokens as f64
+    } else {
+        0.0
+    };
+
+    Ok(SequenceLabelingReport {
+        per_label,
+        micro,
+        accuracy,
+    })
+}
+
+/// Words and tags of a sentence
+pub type TaggedSentence = (Vec<String>, Vec<String>);
+
+/// Reads a CoNLL-formatted file: one word per line with the tag in the last column, sentences
+/// separated by blank lines. `-DOCSTART-` lines are skipped.
+///
+/// # Returns
+///
+/// * `Vec<TaggedSentence>` words and tags for each sentence
+pub fn conll_processor<P: AsRef<Path>>(file_path: P) -> Result<Vec<TaggedSentence>, RustBertError> {
+    let content = fs::read_to_string(file_path)?;
+    let mut sentences = Vec::new();
+    let mut words = Vec::new();
+    let mut tags = Vec::new();
+    for line in content.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with("-DOCSTART-") {
+            if !words.is_empty() {
+                sentences.push((std::mem::take(&mut words), std::mem::take(&mut tags)));
+            }
+            continue;
+        }
+        let columns = line.split_whitespace().collect::<Vec<&str>>();
+        if columns.len() < 2 {
+            return Err(RustBertError::ValueError(format!(
+                "Invalid CoNLL line (expected at least a word and a tag): {line}"
+            )));
+        }
+        words.push(columns[0].to_string());
+        tags.push(columns[columns.len() - 1].to_string());
+    }
+    if !words.is_empty() {
+        sentences.push((words, tags));
+    }
+    Ok(sentences)
+}
+
+/// Predicts one tag per pre-tokenized word with a `TokenClassificationModel`.
+/// Words are joined with a single space and the tag of the first (sub-)token starting within each
+/// word is assigned to the word. Words without any predicted token are tagged `O`.
+pub fn predict_word_tags<S: AsRef<str>>(
+    model: &TokenClassificationModel,
+    sentences: &[Vec<S>],
+) -> Vec<Vec<String>> {
+    let mut inputs = Vec::with_capacity(sentences.len());
+    let mut word_spans = Vec::with_capacity(sentences.len());
+    for words in sentences {
+        let mut text = String::new();
+        let mut spans = Vec::with_capacity(words.len());
+        let mut position = 0u32;
+        for word in words {
+            if !text.is_empty() {
+                text.push(' ');
+                position += 1;
+            }
+            let length = word.as_ref().chars().count() as u32;
+            spans.push((position, position + length));
+            text.push_str(word.as_ref());
+            position += length;
+        }
+        inputs.push(text);
+        word_spans.push(spans);
+    }
+
+    model
+        .predict(&inputs, false, false)
+        .into_iter()
+        .zip(word_spans)
+        .map(|(tokens, spans)| {
+            spans
+                .iter()
+                .map(|(begin, end)| {
+                    tokens
+                        .iter()
+                        .find(|token| {
+                            token
+                                .offset
+                                .map(|offset| offset.begin >= *begin && offset.begin < *end)
+                                .unwrap_or(false)
+                        })
+                        .map(|token| token.label.clone())
+                        .unwrap_or_else(|| "O".to_string())
+                })
+                .collect()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_get_entities() {
+        let tags = ["B-PER", "I-PER", "O", "B-LOC", "I-MISC", "S-ORG", "I-LOC"];
+        let entities = get_entities(&tags)
+            .into_iter()
+            .map(|entity| (entity.label, entity.start, entity.end))
+            .collect::<Vec<(String, usize, usize)>>();
+        assert_eq!(
+            entities,
+            vec![
+                ("PER".to_string(), 0, 1),
+                ("LOC".to_string(), 3, 3),
+                ("MISC".to_string(), 4, 4),
+                ("ORG".to_string(), 5, 5),
+                ("LOC".to_string(), 6, 6),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_sequence_labeling_report() {
+        let references = vec![vec!["B-PER", "I-PER", "O", "B-LOC"]];
+        let predictions = vec![vec!["B-PER", "I-PER", "O", "B-ORG"]];
+        let report = sequence_labeling_report(&references, &predictions).unwrap();
+        assert!((report.micro.f1 - 0.5).abs() < 1e-9);
+        assert_eq!(report.per_label["PER"].f1, 1.0);
+        assert_eq!(report.per_label["LOC"].recall, 0.0);
+        assert_eq!(report.accuracy, 0.75);
+    }
+}
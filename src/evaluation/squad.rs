@@ -0,0 +1,280 @@
+// Copyright 2016 The Stanford NLP Group (https://rajpurkar.github.io/SQuAD-explorer/)
+// Copyright 2023 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! # SQuAD metrics
+//! Exact match and token-level F1 score following the official SQuAD v1.1/v2.0 evaluation script.
+//! Unanswerable questions (SQuAD v2.0) are represented by an empty list of reference answers and
+//! are counted as correct if the predicted answer is empty.
+
+use crate::common::error::RustBertError;
+use crate::pipelines::question_answering::QaInput;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// # SQuAD example with reference answers
+pub struct SquadExample {
+    /// Question identifier
+    pub id: String,
+    /// Question string
+    pub question: String,
+    /// Context containing the answer
+    pub context: String,
+    /// Reference answers (empty for unanswerable questions)
+    pub answers: Vec<String>,
+}
+
+impl From<&SquadExample> for QaInput {
+    fn from(example: &SquadExample) -> Self {
+        QaInput {
+            question: example.question.clone(),
+            context: example.context.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+/// # SQuAD evaluation report
+pub struct SquadMetrics {
+    /// Exact match score (percentage)
+    pub exact_match: f64,
+    /// Token-level F1 score (percentage)
+    pub f1: f64,
+    /// Number of examples evaluated
+    pub total: usize,
+}
+
+impl fmt::Display for SquadMetrics {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "exact_match: {:.2}", self.exact_match)?;
+        writeln!(f, "f1: {:.2}", self.f1)?;
+        write!(f, "total: {}", self.total)
+    }
+}
+
+/// Normalizes an answer: lower case, removal of punctuation, articles and extra whitespace.
+pub fn normalize_answer(answer: &str) -> String {
+    answer
+        .to_lowercase()
+        .chars()
+        .filter(|character| !character.is_ascii_punctuation())
+        .collect::<String>()
+        .split_whitespace()
+        .filter(|word| !matches!(*word, "a" | "an" | "the"))
+        .collect::<Vec<&str>>()
+        .join(" ")
+}
+
+fn exact_match_single(prediction: &str, reference: &str) -> f64 {
+    (normalize_answer(prediction) == normalize_answer(reference)) as u8 as f64
+}
+
+fn f1_single(prediction: &str, reference: &str) -> f64 {
+    let prediction = normalize_answer(prediction);
+    let reference = normalize_answer(reference);
+    let prediction_tokens = prediction.split_whitespace().collect::<Vec<&str>>();
+    let reference_tokens = reference.split_whitespace().collect::<Vec<&str>>();
+    if prediction_tokens.is_empty() || reference_tokens.is_empty() {
+        return (prediction_tokens == reference_tokens) as u8 as f64;
+    }
+
+    let mut reference_counts: HashMap<&str, usize> = HashMap::new();
+    for token in reference_tokens.iter() {
+        *reference_counts.entry(token).or_insert(0) += 1;
+    }
+    let mut num_same = 0usize;
+    for token in prediction_tokens.iter() {
+        if let Some(count) = reference_counts.get_mut(token) {
+            if *count > 0 {
+                *count -= 1;
+                num_same += 1;
+            }
+        }
+    }
+    if num_same == 0 {
+        return 0.0;
+    }
+    let precision = num_same as f64 / prediction_tokens.len() as f64;
+    let recall = num_same as f64 / reference_tokens.len() as f64;
+    2.0 * precision * recall / (precision + recall)
+}
+
+/// Exact match between a prediction and a set of reference answers (maximum over references).
+/// An empty set of references represents an unanswerable question.
+pub fn exact_match<S: AsRef<str>>(prediction: &str, references: &[S]) -> f64 {
+    if references.is_empty() {
+        return exact_match_single(prediction, "");
+    }
+    references
+        .iter()
+        .map(|reference| exact_match_single(prediction, reference.as_ref()))
+        .fold(0.0, f64::max)
+}
+
+/// Token-level F1 score between a prediction and a set of reference answers (maximum over references).
+/// An empty set of references represents an unanswerable question.
+pub fn f1_score<S: AsRef<str>>(prediction: &str, references: &[S]) -> f64 {
+    if references.is_empty() {
+        return f1_single(prediction, "");
+    }
+    references
+        .iter()
+        .map(|reference| f1_single(prediction, reference.as_ref()))
+        .fold(0.0, f64::max)
+}
+
+fn check_lengths<P, R>(predictions: &[P], references: &[Vec<R>]) -> Result<(), RustBertError> {
+    if predictions.len() != references.len() {
+        return Err(RustBertError::ValueError(format!(
+            "Number of predictions ({}) and references ({}) differ",
+            predictions.len(),
+            references.len()
+        )));
+    }
+    Ok(())
+}
+
+/// Computes the SQuAD exact match and F1 scores for a set of predictions.
+///
+/// # Arguments
+///
+/// * `predictions` - Predicted answers (an empty string indicates no answer)
+/// * `references` - Reference answers for each prediction
+///
+/// # Example
+///
+/// ```
+/// # fn main() -> anyhow::Result<()> {
+/// use rust_bert::evaluation::squad::squad_evaluate;
+///
+/// let predictions = ["Amsterdam", "Paris"];
+/// let references = [vec!["Amsterdam"], vec!["the city of Paris"]];
+/// let metrics = squad_evaluate(&predictions, &references)?;
+/// assert_eq!(metrics.exact_match, 50.0);
+/// # Ok(())
+/// # }
+/// ```
+pub fn squad_evaluate<P, R>(
+    predictions: &[P],
+    references: &[Vec<R>],
+) -> Result<SquadMetrics, RustBertError>
+where
+    P: AsRef<str>,
+    R: AsRef<str>,
+{
+    check_lengths(predictions, references)?;
+    let total = predictions.len();
+    let (exact_match_sum, f1_sum) = predictions.iter().zip(references.iter()).fold(
+        (0.0, 0.0),
+        |(exact_match_sum, f1_sum), (prediction, reference)| {
+            (
+                exact_match_sum + exact_match(prediction.as_ref(), reference),
+                f1_sum + f1_score(prediction.as_ref(), reference),
+            )
+        },
+    );
+    let scale = if total > 0 { 100.0 / total as f64 } else { 0.0 };
+    Ok(SquadMetrics {
+        exact_match: exact_match_sum * scale,
+        f1: f1_sum * scale,
+        total,
+    })
+}
+
+/// Reads a SQuAD-formatted JSON file (v1.1 or v2.0) including the reference answers.
+pub fn squad_examples_processor<P: AsRef<Path>>(
+    file_path: P,
+) -> Result<Vec<SquadExample>, RustBertError> {
+    #[derive(Deserialize)]
+    struct SquadFile {
+        data: Vec<SquadArticle>,
+    }
+    #[derive(Deserialize)]
+    struct SquadArticle {
+        paragraphs: Vec<SquadParagraph>,
+    }
+    #[derive(Deserialize)]
+    struct SquadParagraph {
+        context: String,
+        qas: Vec<SquadQuestion>,
+    }
+    #[derive(Deserialize)]
+    struct SquadQuestion {
+        id: String,
+        question: String,
+        #[serde(default)]
+        answers: Vec<SquadAnswer>,
+        #[serde(default)]
+        is_impossible: bool,
+    }
+    #[derive(Deserialize)]
+    struct SquadAnswer {
+        text: String,
+    }
+
+    let file = fs::File::open(file_path)?;
+    let squad_file: SquadFile = serde_json::from_reader(file)
+        .map_err(|e| RustBertError::IOError(format!("Invalid SQuAD file: {e}")))?;
+
+    let mut examples = Vec::new();
+    for article in squad_file.data {
+        for paragraph in article.paragraphs {
+            for qa in paragraph.qas {
+                let answers = if qa.is_impossible {
+                    vec![]
+                } else {
+                    qa.answers.into_iter().map(|answer| answer.text).collect()
+                };
+                examples.push(SquadExample {
+                    id: qa.id,
+                    question: qa.question,
+                    context: paragraph.context.clone(),
+                    answers,
+                });
+            }
+        }
+    }
+    Ok(examples)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_normalize_answer() {
+        assert_eq!(normalize_answer("The  Eiffel Tower!"), "eiffel tower");
+        assert_eq!(normalize_answer("an apple, a pear"), "apple pear");
+    }
+
+    #[test]
+    fn test_squad_scores() {
+        assert_eq!(exact_match("the Eiffel Tower", &["Eiffel tower"]), 1.0);
+        assert_eq!(exact_match("", &[] as &[&str]), 1.0);
+        assert_eq!(f1_score("Paris", &[] as &[&str]), 0.0);
+        let f1 = f1_score("in the city of Paris", &["Paris"]);
+        assert!((f1 - 0.4).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_squad_evaluate() {
+        let predictions = ["Amsterdam", ""];
+        let references = [vec!["Amsterdam"], vec![]];
+        let metrics = squad_evaluate(&predictions, &references).unwrap();
+        assert_eq!(metrics.exact_match, 100.0);
+        assert_eq!(metrics.total, 2);
+        assert!(squad_evaluate(&predictions, &references[..1]).is_err());
+    }
+}
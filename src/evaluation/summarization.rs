@@ -0,0 +1,242 @@
+// Copyright 2022 The Google Research Authors (https://github.com/google-research/google-research/tree/master/rouge)
+// Copyright 2023 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! # Summarization metrics
+//! ROUGE-1, ROUGE-2 and ROUGE-L scores, using the default tokenization of the
+//! [rouge-score](https://github.com/google-research/google-research/tree/master/rouge) package
+//! (lower-casing and splitting on non-alphanumeric characters, no stemming).
+
+use crate::common::error::RustBertError;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+/// # ROUGE precision, recall and F-measure
+pub struct RougeScore {
+    /// Precision
+    pub precision: f64,
+    /// Recall
+    pub recall: f64,
+    /// F-measure
+    pub f1: f64,
+}
+
+impl RougeScore {
+    fn from_counts(matches: usize, hypothesis_count: usize, reference_count: usize) -> Self {
+        let precision = if hypothesis_count > 0 {
+            matches as f64 / hypothesis_count as f64
+        } else {
+            0.0
+        };
+        let recall = if reference_count > 0 {
+            matches as f64 / reference_count as f64
+        } else {
+            0.0
+        };
+        let f1 = if precision + recall > 0.0 {
+            2.0 * precision * recall / (precision + recall)
+        } else {
+            0.0
+        };
+        RougeScore {
+            precision,
+            recall,
+            f1,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+/// # ROUGE scores averaged over a set of summaries
+pub struct RougeScores {
+    /// Unigram overlap
+    pub rouge1: RougeScore,
+    /// Bigram overlap
+    pub rouge2: RougeScore,
+    /// Longest common subsequence
+    pub rouge_l: RougeScore,
+}
+
+impl fmt::Display for RougeScores {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (name, score) in [
+            ("rouge1", self.rouge1),
+            ("rouge2", self.rouge2),
+            ("rougeL", self.rouge_l),
+        ] {
+            writeln!(
+                f,
+                "{}: P = {:.4} R = {:.4} F = {:.4}",
+                name, score.precision, score.recall, score.f1
+            )?;
+        }
+        Ok(())
+    }
+}
+
+fn tokenize(text: &str) -> Vec<String> {
+    text.to_lowercase()
+        .split(|character: char| !character.is_alphanumeric())
+        .filter(|token| !token.is_empty())
+        .map(|token| token.to_string())
+        .collect()
+}
+
+fn rouge_n(hypothesis: &[String], reference: &[String], order: usize) -> RougeScore {
+    let ngrams = |tokens: &[String]| {
+        let mut counts: HashMap<Vec<String>, usize> = HashMap::new();
+        if tokens.len() >= order {
+            for ngram in tokens.windows(order) {
+                *counts.entry(ngram.to_vec()).or_insert(0) += 1;
+            }
+        }
+        counts
+    };
+    let hypothesis_ngrams = ngrams(hypothesis);
+    let reference_ngrams = ngrams(reference);
+    let matches = hypothesis_ngrams
+        .iter()
+        .map(|(ngram, count)| (*count).min(reference_ngrams.get(ngram).copied().unwrap_or(0)))
+        .sum();
+    RougeScore::from_counts(
+        matches,
+        hypothesis_ngrams.values().sum(),
+        reference_ngrams.values().sum(),
+    )
+}
+
+fn longest_common_subsequence(hypothesis: &[String], reference: &[String]) -> usize {
+    let mut previous_row = vec![0usize; reference.len() + 1];
+    let mut current_row = vec![0usize; reference.len() + 1];
+    for hypothesis_token in hypothesis {
+        for (position, reference_token) in reference.iter().enumerate() {
+            current_row[position + 1] = if hypothesis_token == reference_token {
+                previous_row[position] + 1
+            } else {
+                previous_row[position + 1].max(current_row[position])
+            };
+        }
+        std::mem::swap(&mut previous_row, &mut current_row);
+    }
+    previous_row[reference.len()]
+}
+
+fn rouge_l(hypothesis: &[String], reference: &[String]) -> RougeScore {
+    RougeScore::from_counts(
+        longest_common_subsequence(hypothesis, reference),
+        hypothesis.len(),
+        reference.len(),
+    )
+}
+
+/// Computes ROUGE-1, ROUGE-2 and ROUGE-L averaged over a set of summaries. When multiple references
+/// are available for a summary, the reference with the highest F-measure is used for each metric.
+///
+/// # Arguments
+///
+/// * `hypotheses` - Generated summaries
+/// * `references` - One or more reference summaries for each generated summary
+///
+/// # Example
+///
+/// ```
+/// use rust_bert::evaluation::summarization::rouge;
+///
+/// let scores = rouge(&["the cat was found under the bed"], &[vec!["the cat was under the bed"]]).unwrap();
+/// assert!((scores.rouge1.recall - 1.0).abs() < 1e-6);
+/// ```
+pub fn rouge<H, R>(hypotheses: &[H], references: &[Vec<R>]) -> Result<RougeScores, RustBertError>
+where
+    H: AsRef<str>,
+    R: AsRef<str>,
+{
+    if hypotheses.len() != references.len() {
+        return Err(RustBertError::ValueError(format!(
+            "Number of hypotheses ({}) and references ({}) differ",
+            hypotheses.len(),
+            references.len()
+        )));
+    }
+    if hypotheses.is_empty() {
+        return Ok(RougeScores::default());
+    }
+
+    let best = |scores: Vec<RougeScore>| {
+        scores
+            .into_iter()
+            .max_by(|a, b| a.f1.total_cmp(&b.f1))
+            .unwrap_or_default()
+    };
+    let mut totals = [RougeScore::default(); 3];
+    for (hypothesis, sentence_references) in hypotheses.iter().zip(references.iter()) {
+        let hypothesis = tokenize(hypothesis.as_ref());
+        let sentence_references = sentence_references
+            .iter()
+            .map(|reference| tokenize(reference.as_ref()))
+            .collect::<Vec<Vec<String>>>();
+        let scores = [
+            best(
+                sentence_references
+                    .iter()
+                    .map(|reference| rouge_n(&hypothesis, reference, 1))
+                    .collect(),
+            ),
+            best(
+                sentence_references
+                    .iter()
+                    .map(|reference| rouge_n(&hypothesis, reference, 2))
+                    .collect(),
+            ),
+            best(
+                sentence_references
+                    .iter()
+                    .map(|reference| rouge_l(&hypothesis, reference))
+                    .collect(),
+            ),
+        ];
+        for (total, score) in totals.iter_mut().zip(scores.iter()) {
+            total.precision += score.precision;
+            total.recall += score.recall;
+            total.f1 += score.f1;
+        }
+    }
+
+    let count = hypotheses.len() as f64;
+    let average = |total: RougeScore| RougeScore {
+        precision: total.precision / count,
+        recall: total.recall / count,
+        f1: total.f1 / count,
+    };
+    Ok(RougeScores {
+        rouge1: average(totals[0]),
+        rouge2: average(totals[1]),
+        rouge_l: average(totals[2]),
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_rouge() {
+        let scores = rouge(
+            &["the cat was found under the bed"],
+            &[vec!["the cat was under the bed"]],
+        )
+        .unwrap();
+        assert!((scores.rouge1.precision - 6.0 / 7.0).abs() < 1e-9);
+        assert!((scores.rouge2.recall - 4.0 / 5.0).abs() < 1e-9);
+        assert!((scores.rouge_l.recall - 1.0).abs() < 1e-9);
+    }
+}
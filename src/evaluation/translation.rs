@@ -0,0 +1,436 @@
+// Copyright 2017--2018 Amazon.com, Inc. or its affiliates (https://github.com/mjpost/sacrebleu)
+// Copyright 2023 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! # Translation metrics
+//! Corpus-level BLEU (with the `13a` tokenization and exponential smoothing used by default in
+//! [sacreBLEU](https://github.com/mjpost/sacrebleu)) and character n-gram F-score (chrF).
+
+use crate::common::error::RustBertError;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::hash::Hash;
+use std::path::Path;
+
+const BLEU_MAX_ORDER: usize = 4;
+const CHRF_MAX_ORDER: usize = 6;
+const CHRF_BETA: f64 = 2.0;
+const CHRF_EPSILON: f64 = 1e-16;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+/// # Corpus-level BLEU score
+pub struct BleuScore {
+    /// BLEU score (0-100)
+    pub score: f64,
+    /// Modified n-gram precisions for orders 1 to 4 (0-100)
+    pub precisions: [f64; BLEU_MAX_ORDER],
+    /// Brevity penalty
+    pub brevity_penalty: f64,
+    /// Total number of hypothesis tokens
+    pub hypothesis_length: usize,
+    /// Total number of (closest) reference tokens
+    pub reference_length: usize,
+}
+
+impl fmt::Display for BleuScore {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "BLEU = {:.2} {:.1}/{:.1}/{:.1}/{:.1} (BP = {:.3} hyp_len = {} ref_len = {})",
+            self.score,
+            self.precisions[0],
+            self.precisions[1],
+            self.precisions[2],
+            self.precisions[3],
+            self.brevity_penalty,
+            self.hypothesis_length,
+            self.reference_length
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+/// # Corpus-level chrF score
+pub struct ChrfScore {
+    /// chrF score (0-100)
+    pub score: f64,
+    /// Average character n-gram precision (0-100)
+    pub precision: f64,
+    /// Average character n-gram recall (0-100)
+    pub recall: f64,
+}
+
+impl fmt::Display for ChrfScore {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "chrF2 = {:.2} (P = {:.2} R = {:.2})",
+            self.score, self.precision, self.recall
+        )
+    }
+}
+
+struct Tokenizer13a {
+    rules: Vec<(Regex, &'static str)>,
+}
+
+impl Tokenizer13a {
+    fn new() -> Self {
+        let rules = [
+            (r"([\{-~\[-` -&\(-\+:-@/])", " $1 "),
+            (r"([^0-9])([\.,])", "$1 $2 "),
+            (r"([\.,])([^0-9])", " $1 $2"),
+            (r"([0-9])(-)", "$1 $2 "),
+        ]
+        .iter()
+        .map(|(pattern, replacement)| (Regex::new(pattern).unwrap(), *replacement))
+        .collect();
+        Tokenizer13a { rules }
+    }
+
+    fn tokenize(&self, text: &str) -> Vec<String> {
+        let mut text = text
+            .replace("<skipped>", "")
+            .replace("-\n", "")
+            .replace('\n', " ")
+            .replace("&quot;", "\"")
+            .replace("&amp;", "&")
+            .replace("&lt;", "<")
+            .replace("&gt;", ">");
+        text = format!(" {text} ");
+        for (pattern, replacement) in self.rules.iter() {
+            text = pattern.replace_all(&text, *replacement).into_owned();
+        }
+        text.split_whitespace()
+            .map(|token| token.to_string())
+            .collect()
+    }
+}
+
+fn ngram_counts<T: Eq + Hash + Clone>(tokens: &[T], order: usize) -> HashMap<&[T], usize> {
+    let mut counts = HashMap::new();
+    if tokens.len() >= order {
+        for ngram in tokens.windows(order) {
+            *counts.entry(ngram).or_insert(0) += 1;
+        }
+    }
+    counts
+}
+
+fn check_lengths<H, R>(hypotheses: &[H], references: &[Vec<R>]) -> Result<(), RustBertError> {
+    if hypotheses.len() != references.len() {
+        return Err(RustBertError::ValueError(format!(
+            "Number of hypotheses ({}) and references ({}) differ",
+            hypotheses.len(),
+            references.len()
+        )));
+    }
+    if references.iter().any(|reference| reference.is_empty()) {
+        return Err(RustBertError::ValueError(
+            "At least one reference must be provided for each hypothesis".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Computes the corpus-level BLEU score.
+///
+/// # Arguments
+///
+/// * `hypotheses` - Translated texts
+/// * `references` - One or more reference translations for each hypothesis
+///
+/// # Example
+///
+/// ```
+/// use rust_bert::evaluation::translation::corpus_bleu;
+///
+/// let hypotheses = ["The cat sat on the mat."];
+/// let references = [vec!["The cat sat on the mat."]];
+/// let bleu = corpus_bleu(&hypotheses, &references).unwrap();
+/// assert!((bleu.score - 100.0).abs() < 1e-6);
+/// ```
+pub fn corpus_bleu<H, R>(
+    hypotheses: &[H],
+    references: &[Vec<R>],
+) -> Result<BleuScore, RustBertError>
+where
+    H: AsRef<str>,
+    R: AsRef<str>,
+{
+    check_lengths(hypotheses, references)?;
+    let tokenizer = Tokenizer13a::new();
+    let mut correct = [0usize; BLEU_MAX_ORDER];
+    let mut total = [0usize; BLEU_MAX_ORDER];
+    let mut hypothesis_length = 0usize;
+    let mut reference_length = 0usize;
+
+    for (hypothesis, sentence_references) in hypotheses.iter().zip(references.iter()) {
+        let hypothesis = tokenizer.tokenize(hypothesis.as_ref());
+        let sentence_references = sentence_references
+            .iter()
+            .map(|reference| tokenizer.tokenize(reference.as_ref()))
+            .collect::<Vec<Vec<String>>>();
+
+        hypothesis_length += hypothesis.len();
+        reference_length += sentence_references
+            .iter()
+            .map(|reference| reference.len())
+            .min_by_key(|&length| {
+                (
+                    (length as i64 - hypothesis.len() as i64).abs(),
+                    length as i64,
+                )
+            })
+            .unwrap_or(0);
+
+        for order in 1..=BLEU_MAX_ORDER {
+            let hypothesis_counts = ngram_counts(&hypothesis, order);
+            let mut max_reference_counts: HashMap<&[String], usize> = HashMap::new();
+            for reference in sentence_references.iter() {
+                for (ngram, count) in ngram_counts(reference, order) {
+                    let max_count = max_reference_counts.entry(ngram).or_insert(0);
+                    *max_count = (*max_count).max(count);
+                }
+            }
+            for (ngram, count) in hypothesis_counts.iter() {
+                correct[order - 1] +=
+                    (*count).min(max_reference_counts.get(ngram).copied().unwrap_or(0));
+                total[order - 1] += count;
+            }
+        }
+    }
+
+    let mut precisions = [0f64; BLEU_MAX_ORDER];
+    let mut smoothing_factor = 1.0;
+    for (precision, (&correct, &total)) in
+        precisions.iter_mut().zip(correct.iter().zip(total.iter()))
+    {
+        *precision = if total == 0 {
+            0.0
+        } else if correct == 0 {
+            smoothing_factor *= 2.0;
+            100.0 / (smoothing_factor * total as f64)
+        } else {
+            100.0 * correct as f64 / total as f64
+        };
+    }
+
+    let brevity_penalty = if hypothesis_length == 0 {
+        0.0
+    } else if hypothesis_length < reference_length {
+        (1.0 - reference_length as f64 / hypothesis_length as f64).exp()
+    } else {
+        1.0
+    };
+
+    let score = if precisions.contains(&0.0) {
+        0.0
+    } else {
+        brevity_penalty
+            * (precisions
+                .iter()
+                .map(|precision| (precision / 100.0).ln())
+                .sum::<f64>()
+                / BLEU_MAX_ORDER as f64)
+                .exp()
+            * 100.0
+    };
+
+    Ok(BleuScore {
+        score,
+        precisions,
+        brevity_penalty,
+        hypothesis_length,
+        reference_length,
+    })
+}
+
+fn chrf_statistics(hypothesis: &[char], reference: &[char]) -> [(usize, usize, usize); 6] {
+    let mut statistics = [(0usize, 0usize, 0usize); CHRF_MAX_ORDER];
+    for (order, statistic) in statistics.iter_mut().enumerate() {
+        let hypothesis_counts = ngram_counts(hypothesis, order + 1);
+        let reference_counts = ngram_counts(reference, order + 1);
+        let matches = hypothesis_counts
+            .iter()
+            .map(|(ngram, count)| (*count).min(reference_counts.get(ngram).copied().unwrap_or(0)))
+            .sum();
+        *statistic = (
+            hypothesis_counts.values().sum(),
+            reference_counts.values().sum(),
+            matches,
+        );
+    }
+    statistics
+}
+
+fn chrf_from_statistics(statistics: &[(usize, usize, usize); 6], eps_smoothing: bool) -> ChrfScore {
+    let beta_squared = CHRF_BETA * CHRF_BETA;
+    let mut smoothed_score = 0.0;
+    let mut precision = 0.0;
+    let mut recall = 0.0;
+    let mut effective_order = 0usize;
+    for &(hypothesis_count, reference_count, matches) in statistics.iter() {
+        let order_precision = if hypothesis_count > 0 {
+            matches as f64 / hypothesis_count as f64
+        } else {
+            CHRF_EPSILON
+        };
+        let order_recall = if reference_count > 0 {
+            matches as f64 / reference_count as f64
+        } else {
+            CHRF_EPSILON
+        };
+        let denominator = beta_squared * order_precision + order_recall;
+        smoothed_score += if denominator > 0.0 {
+            (1.0 + beta_squared) * order_precision * order_recall / denominator
+        } else {
+            CHRF_EPSILON
+        };
+        if hypothesis_count > 0 && reference_count > 0 {
+            effective_order += 1;
+        }
+        precision += order_precision;
+        recall += order_recall;
+    }
+    if effective_order == 0 {
+        precision = 0.0;
+        recall = 0.0;
+    } else {
+        precision /= effective_order as f64;
+        recall /= effective_order as f64;
+    }
+    let score = if eps_smoothing {
+        smoothed_score / CHRF_MAX_ORDER as f64
+    } else if precision + recall > 0.0 {
+        (1.0 + beta_squared) * precision * recall / (beta_squared * precision + recall)
+    } else {
+        0.0
+    };
+    ChrfScore {
+        score: 100.0 * score,
+        precision: 100.0 * precision,
+        recall: 100.0 * recall,
+    }
+}
+
+/// Computes the corpus-level chrF score (character n-grams up to order 6, beta = 2) as sacreBLEU.
+/// Whitespace is ignored. For each hypothesis, the reference yielding the highest sentence-level
+/// score contributes to the corpus statistics.
+///
+/// By default the F-score is computed from the precision and recall averaged over the n-gram orders
+/// present in both the hypotheses and references. Setting `eps_smoothing` averages the F-scores of
+/// each n-gram order instead, missing orders contributing a score close to 0 (sacreBLEU
+/// `CHRF(eps_smoothing=True)`, the original chrF++ implementation).
+pub fn corpus_chrf<H, R>(
+    hypotheses: &[H],
+    references: &[Vec<R>],
+    eps_smoothing: bool,
+) -> Result<ChrfScore, RustBertError>
+where
+    H: AsRef<str>,
+    R: AsRef<str>,
+{
+    check_lengths(hypotheses, references)?;
+    let remove_whitespace = |text: &str| -> Vec<char> {
+        text.chars()
+            .filter(|character| !character.is_whitespace())
+            .collect()
+    };
+    let mut corpus_statistics = [(0usize, 0usize, 0usize); CHRF_MAX_ORDER];
+    for (hypothesis, sentence_references) in hypotheses.iter().zip(references.iter()) {
+        let hypothesis = remove_whitespace(hypothesis.as_ref());
+        let best_statistics = sentence_references
+            .iter()
+            .map(|reference| chrf_statistics(&hypothesis, &remove_whitespace(reference.as_ref())))
+            .max_by(|a, b| {
+                chrf_from_statistics(a, eps_smoothing)
+                    .score
+                    .total_cmp(&chrf_from_statistics(b, eps_smoothing).score)
+            })
+            .unwrap();
+        for (corpus_statistic, sentence_statistic) in
+            corpus_statistics.iter_mut().zip(best_statistics.iter())
+        {
+            corpus_statistic.0 += sentence_statistic.0;
+            corpus_statistic.1 += sentence_statistic.1;
+            corpus_statistic.2 += sentence_statistic.2;
+        }
+    }
+    Ok(chrf_from_statistics(&corpus_statistics, eps_smoothing))
+}
+
+/// Reads a tab-separated file of `source<TAB>reference` pairs (one pair per line, no header).
+/// Also suitable for summarization datasets (`document<TAB>summary`).
+pub fn parallel_corpus_processor<P: AsRef<Path>>(
+    file_path: P,
+) -> Result<Vec<(String, String)>, RustBertError> {
+    let content = fs::read_to_string(file_path)?;
+    content
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| match line.split_once('\t') {
+            Some((source, reference)) => Ok((source.to_string(), reference.to_string())),
+            None => Err(RustBertError::ValueError(format!(
+                "Expected a tab-separated source/reference pair, got: {line}"
+            ))),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_tokenizer_13a() {
+        let tokenizer = Tokenizer13a::new();
+        assert_eq!(
+            tokenizer.tokenize("Hello, world! It costs 3.5 dollars."),
+            vec!["Hello", ",", "world", "!", "It", "costs", "3.5", "dollars", "."]
+        );
+    }
+
+    #[test]
+    fn test_corpus_bleu() {
+        let hypotheses = ["the cat is on the mat"];
+        let references = [vec!["the cat sat on the mat", "there is a cat on the mat"]];
+        let bleu = corpus_bleu(&hypotheses, &references).unwrap();
+        assert!((bleu.precisions[0] - 100.0).abs() < 1e-6);
+        assert!((bleu.precisions[1] - 60.0).abs() < 1e-6);
+        assert_eq!(bleu.brevity_penalty, 1.0);
+    }
+
+    #[test]
+    fn test_corpus_chrf() {
+        let identical = corpus_chrf(&["a small test"], &[vec!["a small test"]], false).unwrap();
+        assert!((identical.score - 100.0).abs() < 1e-6);
+        let disjoint = corpus_chrf(&["xyz"], &[vec!["abc"]], false).unwrap();
+        assert_eq!(disjoint.score, 0.0);
+
+        // Reference values of sacreBLEU `CHRF()` and `CHRF(eps_smoothing=True)`
+        let hypotheses = ["the cat is on the mat"];
+        let references = [vec!["the cat sat on the mat", "there is a cat on the mat"]];
+        let chrf = corpus_chrf(&hypotheses, &references, false).unwrap();
+        assert!((chrf.score - 61.9252).abs() < 1e-4);
+        let chrf = corpus_chrf(&hypotheses, &references, true).unwrap();
+        assert!((chrf.score - 61.9245).abs() < 1e-4);
+
+        // Only the orders 1 and 2 are present: smoothing averages over all 6 orders
+        let chrf = corpus_chrf(&["ab"], &[vec!["abc"]], false).unwrap();
+        assert!((chrf.score - 63.6364).abs() < 1e-4);
+        let chrf = corpus_chrf(&["ab"], &[vec!["abc"]], true).unwrap();
+        assert!((chrf.score - 21.1640).abs() < 1e-4);
+    }
+}
@@ -11,9 +11,12 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use crate::common::activations::{TensorFunction, _tanh};
+use crate::common::activations::{_tanh, TensorFunction};
 use crate::common::dropout::Dropout;
 use crate::common::embeddings::get_shape_and_device_from_ids_embeddings_pair;
+use crate::common::loss::{
+    cross_entropy_loss, question_answering_loss, sequence_classification_loss, ProblemType,
+};
 use crate::fnet::embeddings::FNetEmbeddings;
 use crate::fnet::encoder::FNetEncoder;
 use crate::{Activation, Config, RustBertError};
@@ -71,7 +74,7 @@ impl FNetVocabResources {
     );
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 /// # FNet model configuration
 /// Defines the FNet model architecture (e.g. number of layers, hidden layer size, label mapping...)
 pub struct FNetConfig {
@@ -90,6 +93,8 @@ pub struct FNetConfig {
     pub eos_token_id: Option<i64>,
     pub id2label: Option<HashMap<i64, String>>,
     pub label2id: Option<HashMap<String, i64>>,
+    /// Type of problem defining the loss of the sequence classification head (default: inferred from the labels)
+    pub problem_type: Option<ProblemType>,
     pub output_attentions: Option<bool>,
     pub output_hidden_states: Option<bool>,
 }
@@ -114,6 +119,7 @@ impl Default for FNetConfig {
             eos_token_id: Some(2),
             id2label: None,
             label2id: None,
+            problem_type: None,
             output_attentions: None,
             output_hidden_states: None,
         }
@@ -403,12 +409,14 @@ impl FNetForMaskedLM {
     /// * `token_type_ids` - Optional segment id of shape (*batch size*, *sequence_length*). Convention is value of 0 for the first sentence (incl. *SEP*) and 1 for the second sentence. If None set to 0.
     /// * `position_ids` - Optional position ids of shape (*batch size*, *sequence_length*). If None, will be incremented from 0.
     /// * `input_embeds` - Optional pre-computed input embeddings of shape (*batch size*, *sequence_length*, *hidden_size*). If None, input ids must be provided (see `input_ids`)
+    /// * `labels` - Optional target token ids of shape (*batch size*, *sequence_length*) used to compute the masked language modeling loss. Positions with a label of -100 (e.g. non-masked tokens) are ignored.
     /// * `train` - boolean flag to turn on/off the dropout layers in the model. Should be set to false for inference.
     ///
     /// # Returns
     ///
     /// * `FNetMaskedLMOutput` containing:
     ///   - `prediction_scores` - `Tensor` of shape (*batch size*, *sequence_length*, *vocab_size*)
+    ///   - `loss` - `Option<Tensor>` scalar loss, computed if labels are provided
     ///   - `all_hidden_states` - `Option<Vec<Tensor>>` of length *num_hidden_layers* with shape (*batch size*, *sequence_length*, *hidden_size*)
     ///
     /// # Example
@@ -437,6 +445,7 @@ impl FNetForMaskedLM {
     ///             Some(&token_type_ids),
     ///             Some(&position_ids),
     ///             None,
+    ///             None,
     ///             false,
     ///         )
     ///         .unwrap()
@@ -448,6 +457,7 @@ impl FNetForMaskedLM {
         token_type_ids: Option<&Tensor>,
         position_ids: Option<&Tensor>,
         input_embeddings: Option<&Tensor>,
+        labels: Option<&Tensor>,
         train: bool,
     ) -> Result<FNetMaskedLMOutput, RustBertError> {
         let model_output = self.fnet.forward_t(
@@ -460,7 +470,9 @@ impl FNetForMaskedLM {
 
         let prediction_scores = self.lm_head.forward(&model_output.hidden_states);
 
+        let loss = labels.map(|labels| cross_entropy_loss(&prediction_scores, labels));
         Ok(FNetMaskedLMOutput {
+            loss,
             prediction_scores,
             all_hidden_states: model_output.all_hidden_states,
         })
@@ -477,6 +489,7 @@ pub struct FNetForSequenceClassification {
     fnet: FNetModel,
     dropout: Dropout,
     classifier: nn::Linear,
+    problem_type: Option<ProblemType>,
 }
 
 impl FNetForSequenceClassification {
@@ -532,6 +545,7 @@ impl FNetForSequenceClassification {
             fnet,
             dropout,
             classifier,
+            problem_type: config.problem_type,
         })
     }
 
@@ -543,12 +557,14 @@ impl FNetForSequenceClassification {
     /// * `token_type_ids` - Optional segment id of shape (*batch size*, *sequence_length*). Convention is value of 0 for the first sentence (incl. *SEP*) and 1 for the second sentence. If None set to 0.
     /// * `position_ids` - Optional position ids of shape (*batch size*, *sequence_length*). If None, will be incremented from 0.
     /// * `input_embeds` - Optional pre-computed input embeddings of shape (*batch size*, *sequence_length*, *hidden_size*). If None, input ids must be provided (see `input_ids`)
+    /// * `labels` - Optional labels of shape (*batch size*) used to compute the loss: class indices for single-label classification or target values for regression. Multi-label classification uses floating point targets of shape (*batch size*, *num_labels*). The loss follows the `problem_type` of the configuration, inferred from the labels if not set.
     /// * `train` - boolean flag to turn on/off the dropout layers in the model. Should be set to false for inference.
     ///
     /// # Returns
     ///
     /// * `FNetSequenceClassificationOutput` containing:
     ///   - `logits` - `Tensor` of shape (*batch size*, *num_classes*)
+    ///   - `loss` - `Option<Tensor>` scalar loss, computed if labels are provided
     ///   - `all_hidden_states` - `Option<Vec<Tensor>>` of length *num_hidden_layers* with shape (*batch size*, *sequence_length*, *hidden_size*)
     ///
     /// # Example
@@ -577,6 +593,7 @@ impl FNetForSequenceClassification {
     ///             Some(&token_type_ids),
     ///             Some(&position_ids),
     ///             None,
+    ///             None,
     ///             false,
     ///         )
     ///         .unwrap()
@@ -588,6 +605,7 @@ impl FNetForSequenceClassification {
         token_type_ids: Option<&Tensor>,
         position_ids: Option<&Tensor>,
         input_embeddings: Option<&Tensor>,
+        labels: Option<&Tensor>,
         train: bool,
     ) -> Result<FNetSequenceClassificationOutput, RustBertError> {
         let base_model_output = self.fnet.forward_t(
@@ -604,7 +622,10 @@ impl FNetForSequenceClassification {
             .apply_t(&self.dropout, train)
             .apply(&self.classifier);
 
+        let loss =
+            labels.map(|labels| sequence_classification_loss(&logits, labels, self.problem_type));
         Ok(FNetSequenceClassificationOutput {
+            loss,
             logits,
             all_hidden_states: base_model_output.all_hidden_states,
         })
@@ -747,6 +768,7 @@ impl FNetForMultipleChoice {
             .view((-1, num_choices));
 
         Ok(FNetSequenceClassificationOutput {
+            loss: None,
             logits,
             all_hidden_states: base_model_output.all_hidden_states,
         })
@@ -830,12 +852,14 @@ impl FNetForTokenClassification {
     /// * `token_type_ids` - Optional segment id of shape (*batch size*, *sequence_length*). Convention is value of 0 for the first sentence (incl. *SEP*) and 1 for the second sentence. If None set to 0.
     /// * `position_ids` - Optional position ids of shape (*batch size*, *sequence_length*). If None, will be incremented from 0.
     /// * `input_embeds` - Optional pre-computed input embeddings of shape (*batch size*, *sequence_length*, *hidden_size*). If None, input ids must be provided (see `input_ids`)
+    /// * `labels` - Optional labels of shape (*batch size*, *sequence_length*) used to compute the cross-entropy loss. Positions with a label of -100 are ignored.
     /// * `train` - boolean flag to turn on/off the dropout layers in the model. Should be set to false for inference.
     ///
     /// # Returns
     ///
     /// * `FNetTokenClassificationOutput` containing:
     ///   - `logits` - `Tensor` of shape (*batch size*, *sequence_length*, *num_labels*) containing the logits for each of the input tokens and classes
+    ///   - `loss` - `Option<Tensor>` scalar loss, computed if labels are provided
     ///   - `all_hidden_states` - `Option<Vec<Tensor>>` of length *num_hidden_layers* with shape (*batch size*, *sequence_length*, *hidden_size*)
     ///
     /// # Example
@@ -864,6 +888,7 @@ impl FNetForTokenClassification {
     ///             Some(&token_type_ids),
     ///             Some(&position_ids),
     ///             None,
+    ///             None,
     ///             false,
     ///         )
     ///         .unwrap()
@@ -875,6 +900,7 @@ impl FNetForTokenClassification {
         token_type_ids: Option<&Tensor>,
         position_ids: Option<&Tensor>,
         input_embeddings: Option<&Tensor>,
+        labels: Option<&Tensor>,
         train: bool,
     ) -> Result<FNetTokenClassificationOutput, RustBertError> {
         let base_model_output = self.fnet.forward_t(
@@ -890,7 +916,9 @@ impl FNetForTokenClassification {
             .apply_t(&self.dropout, train)
             .apply(&self.classifier);
 
+        let loss = labels.map(|labels| cross_entropy_loss(&logits, labels));
         Ok(FNetTokenClassificationOutput {
+            loss,
             logits,
             all_hidden_states: base_model_output.all_hidden_states,
         })
@@ -951,6 +979,8 @@ impl FNetForQuestionAnswering {
     /// * `token_type_ids` - Optional segment id of shape (*batch size*, *sequence_length*). Convention is value of 0 for the first sentence (incl. *SEP*) and 1 for the second sentence. If None set to 0.
     /// * `position_ids` - Optional position ids of shape (*batch size*, *sequence_length*). If None, will be incremented from 0.
     /// * `input_embeds` - Optional pre-computed input embeddings of shape (*batch size*, *sequence_length*, *hidden_size*). If None, input ids must be provided (see `input_ids`)
+    /// * `start_positions` - Optional answer start positions of shape (*batch size*) used to compute the loss. Positions outside of the sequence are ignored.
+    /// * `end_positions` - Optional answer end positions of shape (*batch size*) used to compute the loss. Positions outside of the sequence are ignored.
     /// * `train` - boolean flag to turn on/off the dropout layers in the model. Should be set to false for inference.
     ///
     /// # Returns
@@ -958,6 +988,7 @@ impl FNetForQuestionAnswering {
     /// * `FNetQuestionAnsweringOutput` containing:
     ///   - `start_logits` - `Tensor` of shape (*batch size*, *sequence_length*) containing the logits for start of the answer
     ///   - `end_logits` - `Tensor` of shape (*batch size*, *sequence_length*) containing the logits for end of the answer
+    ///   - `loss` - `Option<Tensor>` scalar loss, computed if the start and end positions are provided
     ///   - `all_hidden_states` - `Option<Vec<Tensor>>` of length *num_hidden_layers* with shape (*batch size*, *sequence_length*, *hidden_size*)
     ///
     /// # Example
@@ -986,6 +1017,8 @@ impl FNetForQuestionAnswering {
     ///             Some(&token_type_ids),
     ///             Some(&position_ids),
     ///             None,
+    ///             None,
+    ///             None,
     ///             false,
     ///         )
     ///         .unwrap()
@@ -997,6 +1030,8 @@ impl FNetForQuestionAnswering {
         token_type_ids: Option<&Tensor>,
         position_ids: Option<&Tensor>,
         input_embeddings: Option<&Tensor>,
+        start_positions: Option<&Tensor>,
+        end_positions: Option<&Tensor>,
         train: bool,
     ) -> Result<FNetQuestionAnsweringOutput, RustBertError> {
         let base_model_output = self.fnet.forward_t(
@@ -1015,7 +1050,13 @@ impl FNetForQuestionAnswering {
         let start_logits = start_logits.squeeze_dim(-1);
         let end_logits = end_logits.squeeze_dim(-1);
 
+        let loss = start_positions
+            .zip(end_positions)
+            .map(|(start_positions, end_positions)| {
+                question_answering_loss(&start_logits, &end_logits, start_positions, end_positions)
+            });
         Ok(FNetQuestionAnsweringOutput {
+            loss,
             start_logits,
             end_logits,
             all_hidden_states: base_model_output.all_hidden_states,
@@ -1023,6 +1064,10 @@ impl FNetForQuestionAnswering {
     }
 }
 
+/// # FNet for sentence embeddings
+/// Transformer usable in [`SentenceEmbeddingsModel`](crate::pipelines::sentence_embeddings::SentenceEmbeddingsModel).
+pub type FNetForSentenceEmbeddings = FNetModel;
+
 /// Container for the FNet model output.
 pub struct FNetModelOutput {
     /// Last hidden states from the model
@@ -1039,6 +1084,8 @@ pub struct FNetMaskedLMOutput {
     pub prediction_scores: Tensor,
     /// Hidden states for all intermediate layers
     pub all_hidden_states: Option<Vec<Tensor>>,
+    /// Loss computed from the labels, if provided
+    pub loss: Option<Tensor>,
 }
 
 /// Container for the FNet sequence classification model output.
@@ -1047,6 +1094,8 @@ pub struct FNetSequenceClassificationOutput {
     pub logits: Tensor,
     /// Hidden states for all intermediate layers
     pub all_hidden_states: Option<Vec<Tensor>>,
+    /// Loss computed from the labels, if provided
+    pub loss: Option<Tensor>,
 }
 
 /// Container for the FNet token classification model output.
@@ -1060,6 +1109,8 @@ pub struct FNetQuestionAnsweringOutput {
     pub end_logits: Tensor,
     /// Hidden states for all intermediate layers
     pub all_hidden_states: Option<Vec<Tensor>>,
+    /// Loss computed from the labels, if provided
+    pub loss: Option<Tensor>,
 }
 
 #[cfg(test)]
@@ -57,8 +57,8 @@ mod fnet_model;
 
 pub use fnet_model::{
     FNetConfig, FNetConfigResources, FNetForMaskedLM, FNetForMultipleChoice,
-    FNetForQuestionAnswering, FNetForSequenceClassification, FNetForTokenClassification,
-    FNetMaskedLMOutput, FNetModel, FNetModelOutput, FNetModelResources,
+    FNetForQuestionAnswering, FNetForSentenceEmbeddings, FNetForSequenceClassification,
+    FNetForTokenClassification, FNetMaskedLMOutput, FNetModel, FNetModelOutput, FNetModelResources,
     FNetQuestionAnsweringOutput, FNetSequenceClassificationOutput, FNetTokenClassificationOutput,
     FNetVocabResources,
 };
@@ -13,6 +13,7 @@
 // limitations under the License.
 
 use crate::common::dropout::Dropout;
+use crate::common::lora::{LoraBaseLayer, LoraLinear};
 use crate::gpt2::gpt2_model::Gpt2Config;
 use std::borrow::Borrow;
 use tch::kind::Kind::Float;
@@ -51,10 +52,14 @@ impl Module for GPTConv1D {
     }
 }
 
+impl LoraBaseLayer for GPTConv1D {
+    const FAN_IN_FAN_OUT: bool = true;
+}
+
 pub struct Attention {
     bias: Tensor,
-    c_attn: GPTConv1D,
-    c_proj: GPTConv1D,
+    c_attn: LoraLinear<GPTConv1D>,
+    c_proj: LoraLinear<GPTConv1D>,
     attn_dropout: Dropout,
     resid_dropout: Dropout,
     output_attentions: bool,
@@ -77,8 +82,20 @@ impl Attention {
 
         let bias = p.var_copy("bias", &bias);
 
-        let c_attn = GPTConv1D::new(p / "c_attn", config.n_embd * 3, config.n_embd);
-        let c_proj = GPTConv1D::new(p / "c_proj", config.n_embd, config.n_embd);
+        let c_attn = LoraLinear::new(
+            p / "c_attn",
+            GPTConv1D::new(p / "c_attn", config.n_embd * 3, config.n_embd),
+            config.n_embd,
+            config.n_embd * 3,
+            config.lora.as_ref(),
+        );
+        let c_proj = LoraLinear::new(
+            p / "c_proj",
+            GPTConv1D::new(p / "c_proj", config.n_embd, config.n_embd),
+            config.n_embd,
+            config.n_embd,
+            config.lora.as_ref(),
+        );
 
         let attn_pdrop = config.attn_pdrop.unwrap_or(0.1);
         let resid_pdrop = config.resid_pdrop.unwrap_or(0.1);
@@ -160,7 +177,7 @@ impl Attention {
         attention_mask: Option<&Tensor>,
         train: bool,
     ) -> (Tensor, Tensor, Option<Tensor>) {
-        let x = x.apply(&self.c_attn).split(self.n_state, 2);
+        let x = x.apply_t(&self.c_attn, train).split(self.n_state, 2);
 
         let (query, key, value) = (
             self.split_heads(&x[0], false),
@@ -180,7 +197,7 @@ impl Attention {
 
         let a = self
             .flatten(a)
-            .apply(&self.c_proj)
+            .apply_t(&self.c_proj, train)
             .apply_t(&self.resid_dropout, train);
 
         (a, present, attentions)
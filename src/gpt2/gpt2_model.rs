@@ -15,6 +15,7 @@
 use crate::common::activations::Activation;
 use crate::common::dropout::Dropout;
 use crate::common::embeddings::process_ids_embeddings_pair;
+use crate::common::lora::LoraConfig;
 use crate::gpt2::transformer::Block;
 use crate::pipelines::common::{ModelType, TokenizerOption};
 use crate::pipelines::generation_utils::private_generation_utils::{
@@ -198,6 +199,7 @@ pub struct Gpt2Config {
     pub output_hidden_states: Option<bool>,
     pub resid_pdrop: Option<f64>,
     pub vocab_size: i64,
+    pub lora: Option<LoraConfig>,
 }
 
 impl Config for Gpt2Config {}
@@ -222,6 +224,7 @@ impl Default for Gpt2Config {
             output_hidden_states: None,
             resid_pdrop: Some(0.1),
             vocab_size: 50257,
+            lora: None,
         }
     }
 }
@@ -648,6 +651,7 @@ impl LMHeadModel for GPT2LMHeadModel {
         Ok(LMModelOutput {
             lm_logits,
             cache: Cache::GPT2Cache(base_model_output.cache),
+            loss: None,
         })
     }
 }
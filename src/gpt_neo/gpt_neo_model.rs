@@ -612,6 +612,7 @@ impl LMHeadModel for GptNeoForCausalLM {
         Ok(LMModelOutput {
             lm_logits: base_model_output.lm_logits,
             cache: Cache::GPTNeoCache(base_model_output.next_cache),
+            loss: None,
         })
     }
 }
@@ -0,0 +1,157 @@
+// Copyright 2022 EleutherAI The HuggingFace Inc. team. All rights reserved.
+// Copyright 2023 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::common::activations::TensorFunction;
+use crate::common::dropout::Dropout;
+use crate::common::embeddings::RotaryEmbedding;
+use crate::gpt_neox::attention::{GptNeoXAttention, LayerState};
+use crate::gpt_neox::GptNeoXConfig;
+use std::borrow::Borrow;
+use tch::nn::Module;
+use tch::{nn, Tensor};
+
+#[derive(Debug)]
+pub struct GptNeoXMLP {
+    dense_h_to_4h: nn::Linear,
+    dense_4h_to_h: nn::Linear,
+    activation_function: TensorFunction,
+}
+
+impl GptNeoXMLP {
+    pub fn new<'p, P>(p: P, config: &GptNeoXConfig) -> GptNeoXMLP
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let p = p.borrow();
+
+        let dense_h_to_4h = nn::linear(
+            p / "dense_h_to_4h",
+            config.hidden_size,
+            config.intermediate_size,
+            Default::default(),
+        );
+        let dense_4h_to_h = nn::linear(
+            p / "dense_4h_to_h",
+            config.intermediate_size,
+            config.hidden_size,
+            Default::default(),
+        );
+        let activation_function = config.hidden_act.get_function();
+
+        GptNeoXMLP {
+            dense_h_to_4h,
+            dense_4h_to_h,
+            activation_function,
+        }
+    }
+}
+
+impl Module for GptNeoXMLP {
+    fn forward(&self, hidden_states: &Tensor) -> Tensor {
+        let hidden_states = hidden_states.apply(&self.dense_h_to_4h);
+        self.activation_function.get_fn()(&hidden_states).apply(&self.dense_4h_to_h)
+    }
+}
+
+/// # GPT-NeoX layer
+/// Pre-normalization layer made of a self-attention and a feed-forward block. If `use_parallel_residual` is set,
+/// both blocks are computed from the layer input (`x + attn(ln1(x)) + mlp(ln2(x))`), otherwise they are applied
+/// sequentially.
+pub struct GptNeoXLayer {
+    input_layernorm: nn::LayerNorm,
+    post_attention_layernorm: nn::LayerNorm,
+    attention: GptNeoXAttention,
+    mlp: GptNeoXMLP,
+    post_attention_dropout: Dropout,
+    post_mlp_dropout: Dropout,
+    use_parallel_residual: bool,
+}
+
+impl GptNeoXLayer {
+    pub fn new<'p, P>(p: P, config: &GptNeoXConfig) -> GptNeoXLayer
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let p = p.borrow();
+
+        let layer_norm_config = nn::LayerNormConfig {
+            eps: config.layer_norm_eps,
+            ..Default::default()
+        };
+        let input_layernorm = nn::layer_norm(
+            p / "input_layernorm",
+            vec![config.hidden_size],
+            layer_norm_config,
+        );
+        let post_attention_layernorm = nn::layer_norm(
+            p / "post_attention_layernorm",
+            vec![config.hidden_size],
+            layer_norm_config,
+        );
+        let attention = GptNeoXAttention::new(p / "attention", config);
+        let mlp = GptNeoXMLP::new(p / "mlp", config);
+
+        let hidden_dropout = config.hidden_dropout.unwrap_or(0.0);
+        let post_attention_dropout = Dropout::new(hidden_dropout);
+        let post_mlp_dropout = Dropout::new(hidden_dropout);
+        let use_parallel_residual = config.use_parallel_residual.unwrap_or(true);
+
+        GptNeoXLayer {
+            input_layernorm,
+            post_attention_layernorm,
+            attention,
+            mlp,
+            post_attention_dropout,
+            post_mlp_dropout,
+            use_parallel_residual,
+        }
+    }
+
+    pub fn forward_t(
+        &self,
+        hidden_states: &Tensor,
+        rotary_embedding: &RotaryEmbedding,
+        cos: &Tensor,
+        sin: &Tensor,
+        attention_mask: Option<&Tensor>,
+        layer_state: Option<&LayerState>,
+        train: bool,
+    ) -> (Tensor, Option<Tensor>, LayerState) {
+        let (attention_output, attention_weights, layer_state) = self.attention.forward_t(
+            &hidden_states.apply(&self.input_layernorm),
+            rotary_embedding,
+            cos,
+            sin,
+            attention_mask,
+            layer_state,
+            train,
+        );
+        let attention_output = attention_output.apply_t(&self.post_attention_dropout, train);
+
+        let output = if self.use_parallel_residual {
+            let mlp_output = hidden_states
+                .apply(&self.post_attention_layernorm)
+                .apply(&self.mlp)
+                .apply_t(&self.post_mlp_dropout, train);
+            mlp_output + attention_output + hidden_states
+        } else {
+            let hidden_states = attention_output + hidden_states;
+            let mlp_output = hidden_states
+                .apply(&self.post_attention_layernorm)
+                .apply(&self.mlp)
+                .apply_t(&self.post_mlp_dropout, train);
+            mlp_output + hidden_states
+        };
+
+        (output, attention_weights, layer_state)
+    }
+}
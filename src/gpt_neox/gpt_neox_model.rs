@@ -0,0 +1,763 @@
+// Copyright 2022 EleutherAI The HuggingFace Inc. team. All rights reserved.
+// Copyright 2023 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::common::dropout::Dropout;
+use crate::common::embeddings::{process_ids_embeddings_pair, RotaryEmbedding};
+use crate::gpt_neox::decoder::GptNeoXLayer;
+use crate::gpt_neox::LayerState;
+use crate::pipelines::common::{ModelType, TokenizerOption};
+use crate::pipelines::generation_utils::private_generation_utils::{
+    PreparedInput, PrivateLanguageGenerator,
+};
+use crate::pipelines::generation_utils::{
+    Cache, GenerateConfig, LMHeadModel, LMModelOutput, LanguageGenerator,
+};
+use crate::{Activation, Config, RustBertError};
+use rust_tokenizers::tokenizer::Gpt2Tokenizer;
+use rust_tokenizers::vocab::Gpt2Vocab;
+use serde::{Deserialize, Serialize};
+use std::borrow::{Borrow, BorrowMut};
+use tch::{nn, Kind, Tensor};
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+/// # GPT-NeoX model configuration
+/// Defines the GPT-NeoX model architecture (e.g. number of layers, hidden layer size, rotary embeddings fraction...).
+/// The field names follow the configuration files of the [Transformers library](https://github.com/huggingface/transformers).
+pub struct GptNeoXConfig {
+    pub vocab_size: i64,
+    pub hidden_size: i64,
+    pub intermediate_size: i64,
+    pub num_hidden_layers: i64,
+    pub num_attention_heads: i64,
+    pub hidden_act: Activation,
+    /// Fraction of each attention head dimension rotated by the rotary embeddings
+    pub rotary_pct: f64,
+    pub rotary_emb_base: f64,
+    pub max_position_embeddings: i64,
+    pub initializer_range: f64,
+    pub layer_norm_eps: f64,
+    /// Compute the attention and feed-forward layers in parallel (defaults to `true`)
+    pub use_parallel_residual: Option<bool>,
+    pub attention_bias: Option<bool>,
+    pub attention_dropout: Option<f64>,
+    pub hidden_dropout: Option<f64>,
+    pub tie_word_embeddings: Option<bool>,
+    pub bos_token_id: Option<i64>,
+    pub eos_token_id: Option<i64>,
+    pub pad_token_id: Option<i64>,
+    pub output_attentions: Option<bool>,
+    pub output_hidden_states: Option<bool>,
+}
+
+impl Config for GptNeoXConfig {}
+
+impl Default for GptNeoXConfig {
+    fn default() -> Self {
+        GptNeoXConfig {
+            vocab_size: 50432,
+            hidden_size: 6144,
+            intermediate_size: 24576,
+            num_hidden_layers: 44,
+            num_attention_heads: 64,
+            hidden_act: Activation::gelu,
+            rotary_pct: 0.25,
+            rotary_emb_base: 10000.0,
+            max_position_embeddings: 2048,
+            initializer_range: 0.02,
+            layer_norm_eps: 1e-5,
+            use_parallel_residual: None,
+            attention_bias: None,
+            attention_dropout: None,
+            hidden_dropout: None,
+            tie_word_embeddings: None,
+            bos_token_id: Some(0),
+            eos_token_id: Some(2),
+            pad_token_id: None,
+            output_attentions: None,
+            output_hidden_states: None,
+        }
+    }
+}
+
+/// # GPT-NeoX Base model
+/// Base architecture for GPT-NeoX models. Task-specific models will be built from this common base model
+/// It is made of the following blocks:
+/// - `embed_in`: Word embeddings
+/// - `layers`: Vector of `GptNeoXLayer` (transformer part of the model)
+/// - `final_layer_norm`: Final layer normalization
+/// - `rotary_embedding`: Rotary position embeddings applied to the first `rotary_pct` fraction of the dimensions of the queries and keys
+pub struct GptNeoXModel {
+    embed_in: nn::Embedding,
+    emb_dropout: Dropout,
+    layers: Vec<GptNeoXLayer>,
+    final_layer_norm: nn::LayerNorm,
+    rotary_embedding: RotaryEmbedding,
+    output_attentions: bool,
+    output_hidden_states: bool,
+}
+
+impl GptNeoXModel {
+    /// Build a new `GptNeoXModel`
+    ///
+    /// # Arguments
+    ///
+    /// * `p` - Variable store path for the root of the GPT-NeoX model
+    /// * `config` - `GptNeoXConfig` object defining the model architecture
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use rust_bert::gpt_neox::{GptNeoXConfig, GptNeoXModel};
+    /// use rust_bert::Config;
+    /// use std::path::Path;
+    /// use tch::{nn, Device};
+    ///
+    /// let config_path = Path::new("path/to/config.json");
+    /// let device = Device::Cpu;
+    /// let p = nn::VarStore::new(device);
+    /// let config = GptNeoXConfig::from_file(config_path);
+    /// let gpt_neox_model = GptNeoXModel::new(&p.root(), &config);
+    /// ```
+    pub fn new<'p, P>(p: P, config: &GptNeoXConfig) -> GptNeoXModel
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let p = p.borrow();
+
+        let embed_in = nn::embedding(
+            p / "embed_in",
+            config.vocab_size,
+            config.hidden_size,
+            Default::default(),
+        );
+        let emb_dropout = Dropout::new(config.hidden_dropout.unwrap_or(0.0));
+
+        let mut layers: Vec<GptNeoXLayer> = Vec::with_capacity(config.num_hidden_layers as usize);
+        let p_layers = p / "layers";
+        for layer_index in 0..config.num_hidden_layers {
+            layers.push(GptNeoXLayer::new(&p_layers / layer_index, config));
+        }
+
+        let layer_norm_config = nn::LayerNormConfig {
+            eps: config.layer_norm_eps,
+            ..Default::default()
+        };
+        let final_layer_norm = nn::layer_norm(
+            p / "final_layer_norm",
+            vec![config.hidden_size],
+            layer_norm_config,
+        );
+
+        let head_dim = config.hidden_size / config.num_attention_heads;
+        let rotary_embedding = RotaryEmbedding::new(
+            (head_dim as f64 * config.rotary_pct) as i64,
+            config.rotary_emb_base,
+            false,
+            p.device(),
+        );
+
+        let output_attentions = config.output_attentions.unwrap_or(false);
+        let output_hidden_states = config.output_hidden_states.unwrap_or(false);
+
+        GptNeoXModel {
+            embed_in,
+            emb_dropout,
+            layers,
+            final_layer_norm,
+            rotary_embedding,
+            output_attentions,
+            output_hidden_states,
+        }
+    }
+
+    /// Forward pass through the model
+    ///
+    /// # Arguments
+    ///
+    /// * `input_ids` - Optional input tensor of shape (*batch size*, *sequence_length*). This or `input_embeds` must be provided.
+    /// * `input_embeds` - Optional input tensor of shape (*batch size*, *sequence_length*, *embeddings dimension*). This or `input_ids` must be provided.
+    /// * `position_ids` - Optional position ids of shape (*batch size*, *sequence_length*). If None, will be incremented starting from the length of the past input.
+    /// * `layer_states` - Optional Vector `Option<Vec<Option<LayerState>>>` of length *n_layer* containing the past keys and values of each layer.
+    /// * `attention_mask` - Optional attention mask of shape (*batch size*, *past_sequence_length + sequence_length*). Positions with a mask with value 0 will be masked.
+    /// * `train` - boolean flag to turn on/off the dropout layers in the model. Should be set to false for inference.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<GptNeoXModelOutput, RustBertError>` containing:
+    ///   - `hidden_states` - `Tensor` of shape (*batch size*, *sequence_length*, *hidden_size*) representing the activations of the last hidden state
+    ///   - `next_cache` - `Option<Vec<Option<LayerState>>>` of length *n_layer* containing the past content for the the attention layers
+    ///   - `all_hidden_states` - `Option<Vec<Tensor>>` of length *n_layer + 1* with shape (*batch size*, *sequence_length*, *hidden_size*)
+    ///   - `all_attentions` - `Option<Vec<Tensor>>` of length *n_layer* containing the attention weights for each layer
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use tch::{nn, Device, Tensor, no_grad, Kind};
+    /// # use rust_bert::Config;
+    /// # use std::path::Path;
+    /// # use tch::kind::Kind::{Int64, Double};
+    /// use rust_bert::gpt_neox::{GptNeoXConfig, GptNeoXModel};
+    /// # let config_path = Path::new("path/to/config.json");
+    /// # let device = Device::Cpu;
+    /// # let vs = nn::VarStore::new(device);
+    /// # let config = GptNeoXConfig::from_file(config_path);
+    /// # let gpt_neox_model = GptNeoXModel::new(&vs.root(), &config);
+    /// let (batch_size, sequence_length) = (64, 128);
+    /// let input_tensor = Tensor::rand(&[batch_size, sequence_length], (Int64, device));
+    /// let attention_mask = Tensor::ones(&[batch_size, sequence_length], (Int64, device));
+    ///
+    /// let model_output = no_grad(|| {
+    ///     gpt_neox_model.forward_t(
+    ///         Some(&input_tensor),
+    ///         None,
+    ///         None,
+    ///         None,
+    ///         Some(&attention_mask),
+    ///         false,
+    ///     )
+    /// });
+    /// ```
+    pub fn forward_t(
+        &self,
+        input_ids: Option<&Tensor>,
+        input_embeds: Option<&Tensor>,
+        position_ids: Option<&Tensor>,
+        layer_states: Option<Vec<Option<LayerState>>>,
+        attention_mask: Option<&Tensor>,
+        train: bool,
+    ) -> Result<GptNeoXModelOutput, RustBertError> {
+        let (calc_input_embeddings, input_shape, device) =
+            process_ids_embeddings_pair(input_ids, input_embeds, &self.embed_in)?;
+        let input_embeds = input_embeds.unwrap_or_else(|| calc_input_embeddings.as_ref().unwrap());
+
+        let (batch_size, sequence_length) = (input_shape[0], input_shape[1]);
+        let past_length = match &layer_states {
+            Some(layer_states) => match &layer_states[0] {
+                Some(layer_state) => layer_state.prev_key.size()[2],
+                None => 0,
+            },
+            None => 0,
+        };
+        let full_sequence_length = past_length + sequence_length;
+
+        let calc_position_ids = if position_ids.is_none() {
+            Some(
+                Tensor::arange_start(past_length, full_sequence_length, (Kind::Int64, device))
+                    .unsqueeze(0)
+                    .expand(&[batch_size, sequence_length], true),
+            )
+        } else {
+            None
+        };
+        let position_ids = position_ids.unwrap_or_else(|| calc_position_ids.as_ref().unwrap());
+        let (cos, sin) = self
+            .rotary_embedding
+            .forward(position_ids, input_embeds.kind());
+
+        let query_positions =
+            Tensor::arange_start(past_length, full_sequence_length, (Kind::Int64, device))
+                .unsqueeze(-1);
+        let key_positions =
+            Tensor::arange(full_sequence_length, (Kind::Int64, device)).unsqueeze(0);
+        let mut causal_mask = key_positions.gt_tensor(&query_positions).view([
+            1,
+            1,
+            sequence_length,
+            full_sequence_length,
+        ]);
+        if let Some(attention_mask) = attention_mask {
+            causal_mask =
+                causal_mask.logical_or(&attention_mask.eq(0).view([batch_size, 1, 1, -1]));
+        }
+
+        let mut hidden_state = input_embeds.apply_t(&self.emb_dropout, train);
+        let mut all_hidden_states: Option<Vec<Tensor>> = if self.output_hidden_states {
+            Some(vec![])
+        } else {
+            None
+        };
+        let mut all_attentions: Option<Vec<Tensor>> = if self.output_attentions {
+            Some(vec![])
+        } else {
+            None
+        };
+        let old_cache = layer_states.unwrap_or_else(|| vec![None; self.layers.len()]);
+        let mut next_cache = Vec::with_capacity(self.layers.len());
+
+        for (layer, layer_state) in self.layers.iter().zip(old_cache) {
+            if let Some(hidden_states) = all_hidden_states.borrow_mut() {
+                hidden_states.push(hidden_state.copy());
+            };
+            let (output, attention_weights, layer_state) = layer.forward_t(
+                &hidden_state,
+                &self.rotary_embedding,
+                &cos,
+                &sin,
+                Some(&causal_mask),
+                layer_state.as_ref(),
+                train,
+            );
+            hidden_state = output;
+            next_cache.push(Some(layer_state));
+            if let Some(attentions) = all_attentions.borrow_mut() {
+                attentions.push(attention_weights.unwrap());
+            };
+        }
+
+        let hidden_states = hidden_state.apply(&self.final_layer_norm);
+        if let Some(all_hidden_states) = all_hidden_states.borrow_mut() {
+            all_hidden_states.push(hidden_states.copy());
+        };
+
+        Ok(GptNeoXModelOutput {
+            hidden_states,
+            next_cache: Some(next_cache),
+            all_hidden_states,
+            all_attentions,
+        })
+    }
+}
+
+/// # GPT-NeoX Model for causal language modeling
+/// GPT-NeoX model with a vocabulary decoding head. The decoding head is tied to the word embeddings if
+/// `tie_word_embeddings` is set in the configuration.
+/// It is made of the following blocks:
+/// - `gpt_neox`: `GptNeoXModel` Base GPT-NeoX model
+/// - `embed_out`: Linear layer projecting the hidden states to the vocabulary logits
+pub struct GptNeoXForCausalLM {
+    gpt_neox: GptNeoXModel,
+    embed_out: Option<nn::Linear>,
+}
+
+impl GptNeoXForCausalLM {
+    /// Build a new `GptNeoXForCausalLM`
+    ///
+    /// # Arguments
+    ///
+    /// * `p` - Variable store path for the root of the GPT-NeoX model
+    /// * `config` - `GptNeoXConfig` object defining the model architecture
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use rust_bert::gpt_neox::{GptNeoXConfig, GptNeoXForCausalLM};
+    /// use rust_bert::Config;
+    /// use std::path::Path;
+    /// use tch::{nn, Device};
+    ///
+    /// let config_path = Path::new("path/to/config.json");
+    /// let device = Device::Cpu;
+    /// let p = nn::VarStore::new(device);
+    /// let config = GptNeoXConfig::from_file(config_path);
+    /// let gpt_neox_model = GptNeoXForCausalLM::new(&p.root(), &config);
+    /// ```
+    pub fn new<'p, P>(p: P, config: &GptNeoXConfig) -> GptNeoXForCausalLM
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let p = p.borrow();
+
+        let gpt_neox = GptNeoXModel::new(p / "gpt_neox", config);
+        let embed_out = if config.tie_word_embeddings.unwrap_or(false) {
+            None
+        } else {
+            Some(nn::linear(
+                p / "embed_out",
+                config.hidden_size,
+                config.vocab_size,
+                nn::LinearConfig {
+                    bias: false,
+                    ..Default::default()
+                },
+            ))
+        };
+
+        GptNeoXForCausalLM {
+            gpt_neox,
+            embed_out,
+        }
+    }
+
+    /// Forward pass through the model
+    ///
+    /// # Arguments
+    ///
+    /// * `input_ids` - Optional input tensor of shape (*batch size*, *sequence_length*). This or `input_embeds` must be provided.
+    /// * `input_embeds` - Optional input tensor of shape (*batch size*, *sequence_length*, *embeddings dimension*). This or `input_ids` must be provided.
+    /// * `position_ids` - Optional position ids of shape (*batch size*, *sequence_length*). If None, will be incremented starting from the length of the past input.
+    /// * `layer_states` - Optional Vector `Option<Vec<Option<LayerState>>>` of length *n_layer* containing the past keys and values of each layer.
+    /// * `attention_mask` - Optional attention mask of shape (*batch size*, *past_sequence_length + sequence_length*). Positions with a mask with value 0 will be masked.
+    /// * `train` - boolean flag to turn on/off the dropout layers in the model. Should be set to false for inference.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<GptNeoXModelLMOutput, RustBertError>` containing:
+    ///   - `lm_logits` - `Tensor` of shape (*batch size*, *sequence_length*, *vocab_size*) representing the logits for each vocab item and position
+    ///   - `next_cache` - `Option<Vec<Option<LayerState>>>` of length *n_layer* containing the past content for the the attention layers
+    ///   - `all_hidden_states` - `Option<Vec<Tensor>>` of length *n_layer + 1* with shape (*batch size*, *sequence_length*, *hidden_size*)
+    ///   - `all_attentions` - `Option<Vec<Tensor>>` of length *n_layer* containing the attention weights for each layer
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use tch::{nn, Device, Tensor, no_grad, Kind};
+    /// # use rust_bert::Config;
+    /// # use std::path::Path;
+    /// # use tch::kind::Kind::{Int64, Double};
+    /// use rust_bert::gpt_neox::{GptNeoXConfig, GptNeoXForCausalLM};
+    /// # let config_path = Path::new("path/to/config.json");
+    /// # let device = Device::Cpu;
+    /// # let vs = nn::VarStore::new(device);
+    /// # let config = GptNeoXConfig::from_file(config_path);
+    /// # let gpt_neox_model = GptNeoXForCausalLM::new(&vs.root(), &config);
+    /// let (batch_size, sequence_length) = (64, 128);
+    /// let input_tensor = Tensor::rand(&[batch_size, sequence_length], (Int64, device));
+    /// let attention_mask = Tensor::ones(&[batch_size, sequence_length], (Int64, device));
+    ///
+    /// let model_output = no_grad(|| {
+    ///     gpt_neox_model.forward_t(
+    ///         Some(&input_tensor),
+    ///         None,
+    ///         None,
+    ///         None,
+    ///         Some(&attention_mask),
+    ///         false,
+    ///     )
+    /// });
+    /// ```
+    pub fn forward_t(
+        &self,
+        input_ids: Option<&Tensor>,
+        input_embeds: Option<&Tensor>,
+        position_ids: Option<&Tensor>,
+        layer_states: Option<Vec<Option<LayerState>>>,
+        attention_mask: Option<&Tensor>,
+        train: bool,
+    ) -> Result<GptNeoXModelLMOutput, RustBertError> {
+        let base_model_output = self.gpt_neox.forward_t(
+            input_ids,
+            input_embeds,
+            position_ids,
+            layer_states,
+            attention_mask,
+            train,
+        )?;
+
+        let lm_logits = match &self.embed_out {
+            Some(embed_out) => base_model_output.hidden_states.apply(embed_out),
+            None => base_model_output
+                .hidden_states
+                .linear::<Tensor>(&self.gpt_neox.embed_in.ws, None),
+        };
+
+        Ok(GptNeoXModelLMOutput {
+            lm_logits,
+            next_cache: base_model_output.next_cache,
+            all_hidden_states: base_model_output.all_hidden_states,
+            all_attentions: base_model_output.all_attentions,
+        })
+    }
+}
+
+impl LMHeadModel for GptNeoXForCausalLM {
+    fn forward_t(
+        &self,
+        input_ids: Option<&Tensor>,
+        layer_past: Cache,
+        attention_mask: Option<&Tensor>,
+        _token_type_ids: Option<&Tensor>,
+        position_ids: Option<&Tensor>,
+        input_embeds: Option<&Tensor>,
+        _encoder_outputs: Option<&Tensor>,
+        _decoder_input_ids: Option<&Tensor>,
+        train: bool,
+    ) -> Result<LMModelOutput, RustBertError> {
+        let base_model_output = match layer_past {
+            Cache::GPTNeoXCache(layer_past) => self.forward_t(
+                input_ids,
+                input_embeds,
+                position_ids,
+                layer_past,
+                attention_mask,
+                train,
+            ),
+            Cache::None => self.forward_t(
+                input_ids,
+                input_embeds,
+                position_ids,
+                None,
+                attention_mask,
+                train,
+            ),
+            _ => {
+                return Err(RustBertError::ValueError(
+                    "Cache not compatible with GPT-NeoX Model".into(),
+                ));
+            }
+        }?;
+
+        Ok(LMModelOutput {
+            lm_logits: base_model_output.lm_logits,
+            cache: Cache::GPTNeoXCache(base_model_output.next_cache),
+            loss: None,
+        })
+    }
+}
+
+/// Container for the GPT-NeoX model output.
+pub struct GptNeoXModelOutput {
+    /// Last hidden states from the model
+    pub hidden_states: Tensor,
+    /// Cached outputs of the model (attention layers keys and values) if the model is used for generation
+    pub next_cache: Option<Vec<Option<LayerState>>>,
+    /// Hidden states for all intermediate layers
+    pub all_hidden_states: Option<Vec<Tensor>>,
+    /// Attention weights for all intermediate layers
+    pub all_attentions: Option<Vec<Tensor>>,
+}
+
+///Container holding a GPT-NeoX model with LM head output
+pub struct GptNeoXModelLMOutput {
+    /// logits
+    pub lm_logits: Tensor,
+    /// Cached outputs of the model (attention layers keys and values) if the model is used for generation
+    pub next_cache: Option<Vec<Option<LayerState>>>,
+    /// Hidden states for all intermediate layers
+    pub all_hidden_states: Option<Vec<Tensor>>,
+    /// Attention weights for all intermediate layers
+    pub all_attentions: Option<Vec<Tensor>>,
+}
+
+/// # Language generation model based on the GPT-NeoX architecture
+pub struct GptNeoXGenerator {
+    model: GptNeoXForCausalLM,
+    tokenizer: TokenizerOption,
+    var_store: nn::VarStore,
+    generate_config: GenerateConfig,
+    bos_token_id: Option<i64>,
+    eos_token_ids: Option<Vec<i64>>,
+    pad_token_id: Option<i64>,
+    is_encoder_decoder: bool,
+    vocab_size: i64,
+    decoder_start_id: Option<i64>,
+    max_position_embeddings: i64,
+}
+
+impl GptNeoXGenerator {
+    /// Build a new `GptNeoXGenerator`
+    ///
+    /// # Arguments
+    ///
+    /// * `generate_config` - `GenerateConfig` object containing the resource references (model, vocabulary, configuration), generation options and device placement (CPU/GPU). GPT-NeoX uses a GPT2-style byte-level BPE tokenizer and requires a merges resource.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::gpt_neox::GptNeoXGenerator;
+    /// use rust_bert::pipelines::generation_utils::GenerateConfig;
+    /// use rust_bert::resources::LocalResource;
+    /// use std::path::PathBuf;
+    ///
+    /// let generate_config = GenerateConfig {
+    ///     model_resource: Box::new(LocalResource {
+    ///         local_path: PathBuf::from("path/to/rust_model.ot"),
+    ///     }),
+    ///     config_resource: Box::new(LocalResource {
+    ///         local_path: PathBuf::from("path/to/config.json"),
+    ///     }),
+    ///     vocab_resource: Box::new(LocalResource {
+    ///         local_path: PathBuf::from("path/to/vocab.json"),
+    ///     }),
+    ///     merges_resource: Some(Box::new(LocalResource {
+    ///         local_path: PathBuf::from("path/to/merges.txt"),
+    ///     })),
+    ///     max_length: Some(30),
+    ///     do_sample: true,
+    ///     ..Default::default()
+    /// };
+    /// let gpt_neox_generator = GptNeoXGenerator::new(generate_config)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn new(generate_config: GenerateConfig) -> Result<GptNeoXGenerator, RustBertError> {
+        let vocab_path = generate_config.vocab_resource.get_local_path()?;
+        let merges_path = generate_config
+            .merges_resource
+            .as_ref()
+            .ok_or_else(|| {
+                RustBertError::InvalidConfigurationError(
+                    "GPT-NeoX expects a merges resources to be provided".to_string(),
+                )
+            })?
+            .get_local_path()?;
+
+        let tokenizer = TokenizerOption::from_file(
+            ModelType::GPTNeoX,
+            vocab_path.to_str().unwrap(),
+            Some(merges_path.to_str().unwrap()),
+            false,
+            None,
+            None,
+        )?;
+
+        Self::new_with_tokenizer(generate_config, tokenizer)
+    }
+
+    pub fn new_with_tokenizer(
+        generate_config: GenerateConfig,
+        tokenizer: TokenizerOption,
+    ) -> Result<GptNeoXGenerator, RustBertError> {
+        let config_path = generate_config.config_resource.get_local_path()?;
+        let weights_path = generate_config.model_resource.get_local_path()?;
+        let device = generate_config.device;
+
+        generate_config.validate();
+        let mut var_store = nn::VarStore::new(device);
+        let config = GptNeoXConfig::from_file(config_path);
+        let model = GptNeoXForCausalLM::new(var_store.root(), &config);
+        var_store.load(weights_path)?;
+
+        let bos_token_id = config.bos_token_id.or_else(|| tokenizer.get_bos_id());
+        let eos_token_ids = config
+            .eos_token_id
+            .or_else(|| tokenizer.get_eos_id())
+            .map(|id| vec![id]);
+        let pad_token_id = config.pad_token_id.or_else(|| tokenizer.get_pad_id());
+        let is_encoder_decoder = false;
+        let vocab_size = config.vocab_size;
+        let decoder_start_id = None;
+        let max_position_embeddings = config.max_position_embeddings;
+
+        Ok(GptNeoXGenerator {
+            model,
+            tokenizer,
+            var_store,
+            generate_config,
+            bos_token_id,
+            eos_token_ids,
+            pad_token_id,
+            is_encoder_decoder,
+            vocab_size,
+            decoder_start_id,
+            max_position_embeddings,
+        })
+    }
+}
+
+impl PrivateLanguageGenerator<GptNeoXForCausalLM, Gpt2Vocab, Gpt2Tokenizer> for GptNeoXGenerator {
+    fn get_model(&self) -> &GptNeoXForCausalLM {
+        &self.model
+    }
+    fn _get_tokenizer(&self) -> &TokenizerOption {
+        &self.tokenizer
+    }
+    fn get_var_store(&self) -> &nn::VarStore {
+        &self.var_store
+    }
+    fn get_var_store_mut(&mut self) -> &mut nn::VarStore {
+        &mut self.var_store
+    }
+    fn get_config(&self) -> &GenerateConfig {
+        &self.generate_config
+    }
+    fn get_bos_id(&self) -> Option<i64> {
+        self.bos_token_id
+    }
+    fn get_eos_ids(&self) -> Option<&Vec<i64>> {
+        self.eos_token_ids.as_ref()
+    }
+    fn get_pad_id(&self) -> Option<i64> {
+        self.pad_token_id
+    }
+    fn is_encoder_decoder(&self) -> bool {
+        self.is_encoder_decoder
+    }
+    fn get_vocab_size(&self) -> i64 {
+        self.vocab_size
+    }
+    fn get_decoder_start_id(&self) -> Option<i64> {
+        self.decoder_start_id
+    }
+    fn get_max_positions_embeddings(&self) -> i64 {
+        self.max_position_embeddings
+    }
+
+    fn prepare_inputs_for_generation<'a>(
+        &self,
+        input_ids: Tensor,
+        _encoder_outputs: Option<&'a Tensor>,
+        past: Cache,
+        attention_mask: Tensor,
+    ) -> PreparedInput<'a> {
+        let position_ids = (attention_mask.totype(Kind::Int64).cumsum(-1, Kind::Int64) - 1)
+            .masked_fill(&attention_mask.eq(0), 1);
+
+        match past {
+            Cache::GPTNeoXCache(past) => {
+                if past.is_some() {
+                    PreparedInput {
+                        prepared_input: Some(input_ids.select(1, -1).unsqueeze(-1)),
+                        prepared_attention_mask: Some(attention_mask),
+                        prepared_encoder_output: None,
+                        prepared_decoder_input: None,
+                        prepared_position_ids: Some(position_ids.select(1, -1).unsqueeze(-1)),
+                        prepared_past: Cache::GPTNeoXCache(past),
+                    }
+                } else {
+                    PreparedInput {
+                        prepared_input: Some(input_ids),
+                        prepared_attention_mask: Some(attention_mask),
+                        prepared_encoder_output: None,
+                        prepared_decoder_input: None,
+                        prepared_position_ids: Some(position_ids),
+                        prepared_past: Cache::GPTNeoXCache(None),
+                    }
+                }
+            }
+            Cache::None => PreparedInput {
+                prepared_input: Some(input_ids),
+                prepared_attention_mask: Some(attention_mask),
+                prepared_encoder_output: None,
+                prepared_decoder_input: None,
+                prepared_position_ids: Some(position_ids),
+                prepared_past: Cache::GPTNeoXCache(None),
+            },
+            _ => panic!("Cache type incompatible with GPT-NeoX"),
+        }
+    }
+
+    fn reorder_cache(
+        &self,
+        past: &mut Cache,
+        _encoder_outputs: Option<Tensor>,
+        beam_indices: &Tensor,
+    ) -> Option<Tensor> {
+        match past {
+            Cache::GPTNeoXCache(cached_decoder_state) => match cached_decoder_state {
+                Some(old_cache) => {
+                    for layer_state in old_cache.iter_mut().flatten() {
+                        layer_state.reorder_cache(beam_indices)
+                    }
+                    None
+                }
+                None => None,
+            },
+            Cache::None => None,
+            _ => {
+                panic!("Invalid cache for GPT-NeoX model");
+            }
+        }
+    }
+}
+
+impl LanguageGenerator<GptNeoXForCausalLM, Gpt2Vocab, Gpt2Tokenizer> for GptNeoXGenerator {}
@@ -0,0 +1,69 @@
+//! # GPT-NeoX
+//!
+//! Implementation of the GPT-NeoX language model ([GPT-NeoX-20B: An Open-Source Autoregressive Language Model](https://arxiv.org/abs/2204.06745) Black, Biderman, Hallahan, Anthony, Gao, Golding, He, Leahy, McDonell, Phang, Pieler, Prashanth, Purohit, Reynolds, Tow, Wang, Weinbach, 2022).
+//! The base model is implemented in the `gpt_neox_model::GptNeoXModel` struct. A causal language modeling head is implemented in `gpt_neox_model::GptNeoXForCausalLM`.
+//! The rotary position embeddings are applied to a fraction (`rotary_pct`) of each attention head, and the attention and
+//! feed-forward layers are computed in parallel when `use_parallel_residual` is set (e.g. for the Pythia checkpoints).
+//!
+//! # Model set-up and pre-trained weights loading
+//!
+//! All models expect the following resources:
+//! - Configuration file expected to have a structure following the [Transformers library](https://github.com/huggingface/transformers)
+//! - Model weights are expected to have a structure and parameter names following the [Transformers library](https://github.com/huggingface/transformers). A conversion using the Python utility scripts is required to convert the `.bin` weights to the `.ot` format.
+//! - `GPT2Tokenizer` using a `vocab.json` vocabulary and a `merges.txt` merges file (both can be extracted from the `tokenizer.json` file of the checkpoint)
+//!
+//! No pretrained checkpoints are registered as remote resources: the weights have to be converted and loaded from local resources.
+//!
+//! ```no_run
+//! use rust_bert::pipelines::common::ModelType;
+//! use rust_bert::pipelines::text_generation::{TextGenerationConfig, TextGenerationModel};
+//! use rust_bert::resources::LocalResource;
+//! use std::path::PathBuf;
+//! use tch::Device;
+//!
+//! fn main() -> anyhow::Result<()> {
+//!     let config_resource = Box::new(LocalResource {
+//!         local_path: PathBuf::from("path/to/config.json"),
+//!     });
+//!     let vocab_resource = Box::new(LocalResource {
+//!         local_path: PathBuf::from("path/to/vocab.json"),
+//!     });
+//!     let merges_resource = Box::new(LocalResource {
+//!         local_path: PathBuf::from("path/to/merges.txt"),
+//!     });
+//!     let model_resource = Box::new(LocalResource {
+//!         local_path: PathBuf::from("path/to/rust_model.ot"),
+//!     });
+//!
+//!     let text_generation_config = TextGenerationConfig {
+//!         model_type: ModelType::GPTNeoX,
+//!         model_resource,
+//!         config_resource,
+//!         vocab_resource,
+//!         merges_resource: Some(merges_resource),
+//!         max_length: Some(64),
+//!         do_sample: false,
+//!         device: Device::cuda_if_available(),
+//!         ..Default::default()
+//!     };
+//!     let model = TextGenerationModel::new(text_generation_config)?;
+//!
+//!     let output = model.generate(&["It was a very nice and sunny"], None);
+//!
+//!     for sentence in output {
+//!         println!("{}", sentence);
+//!     }
+//!
+//!     Ok(())
+//! }
+//! ```
+
+mod attention;
+mod decoder;
+mod gpt_neox_model;
+
+pub use attention::LayerState;
+pub use gpt_neox_model::{
+    GptNeoXConfig, GptNeoXForCausalLM, GptNeoXGenerator, GptNeoXModel, GptNeoXModelLMOutput,
+    GptNeoXModelOutput,
+};
@@ -0,0 +1,165 @@
+// Copyright 2021 The EleutherAI and HuggingFace Teams. All rights reserved.
+// Copyright 2023 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::common::dropout::Dropout;
+use crate::common::embeddings::RotaryEmbedding;
+use crate::gptj::GptJConfig;
+use std::borrow::Borrow;
+use tch::{nn, Kind, Tensor};
+
+#[derive(Debug)]
+/// # Cache for GPT-J attention layers
+/// Stores the cached value of key (after the rotary embeddings) and value
+pub struct LayerState {
+    /// Cached keys
+    pub prev_key: Tensor,
+    /// Cached values
+    pub prev_value: Tensor,
+}
+
+impl Clone for LayerState {
+    fn clone(&self) -> Self {
+        LayerState {
+            prev_key: self.prev_key.copy(),
+            prev_value: self.prev_value.copy(),
+        }
+    }
+}
+
+impl LayerState {
+    pub(crate) fn reorder_cache(&mut self, new_indices: &Tensor) {
+        self.prev_key = self.prev_key.index_select(0, new_indices);
+        self.prev_value = self.prev_value.index_select(0, new_indices);
+    }
+}
+
+pub struct GptJAttention {
+    q_proj: nn::Linear,
+    k_proj: nn::Linear,
+    v_proj: nn::Linear,
+    out_proj: nn::Linear,
+    attention_dropout: Dropout,
+    resid_dropout: Dropout,
+    num_heads: i64,
+    head_dim: i64,
+    output_attentions: bool,
+}
+
+impl GptJAttention {
+    pub fn new<'p, P>(p: P, config: &GptJConfig) -> GptJAttention
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let p = p.borrow();
+
+        let num_heads = config.n_head;
+        let head_dim = config.n_embd / config.n_head;
+
+        let linear_config = nn::LinearConfig {
+            bias: false,
+            ..Default::default()
+        };
+        let q_proj = nn::linear(p / "q_proj", config.n_embd, config.n_embd, linear_config);
+        let k_proj = nn::linear(p / "k_proj", config.n_embd, config.n_embd, linear_config);
+        let v_proj = nn::linear(p / "v_proj", config.n_embd, config.n_embd, linear_config);
+        let out_proj = nn::linear(p / "out_proj", config.n_embd, config.n_embd, linear_config);
+
+        let attention_dropout = Dropout::new(config.attn_pdrop);
+        let resid_dropout = Dropout::new(config.resid_pdrop);
+        let output_attentions = config.output_attentions.unwrap_or(false);
+
+        GptJAttention {
+            q_proj,
+            k_proj,
+            v_proj,
+            out_proj,
+            attention_dropout,
+            resid_dropout,
+            num_heads,
+            head_dim,
+            output_attentions,
+        }
+    }
+
+    fn split_heads(&self, x: &Tensor) -> Tensor {
+        let size = x.size();
+        x.view([size[0], size[1], self.num_heads, self.head_dim])
+            .transpose(1, 2)
+    }
+
+    /// Forward pass through the attention layer
+    ///
+    /// # Arguments
+    ///
+    /// * `hidden_states` - input tensor of shape (*batch size*, *sequence_length*, *n_embd*)
+    /// * `rotary_embedding` - `RotaryEmbedding` applied to the queries and keys
+    /// * `cos`, `sin` - rotary embedding tables of shape (*batch size*, *sequence_length*, *rotary_dim*)
+    /// * `attention_mask` - Optional boolean mask of shape (*batch size*, 1, *sequence_length*, *total_sequence_length*), with `true` for the positions that cannot be attended to
+    /// * `layer_state` - Optional cached keys and values of the previous positions
+    /// * `train` - boolean flag to turn on/off the dropout layers in the model
+    pub fn forward_t(
+        &self,
+        hidden_states: &Tensor,
+        rotary_embedding: &RotaryEmbedding,
+        cos: &Tensor,
+        sin: &Tensor,
+        attention_mask: Option<&Tensor>,
+        layer_state: Option<&LayerState>,
+        train: bool,
+    ) -> (Tensor, Option<Tensor>, LayerState) {
+        let size = hidden_states.size();
+        let (batch_size, sequence_length) = (size[0], size[1]);
+
+        let query = self.split_heads(&hidden_states.apply(&self.q_proj));
+        let key = self.split_heads(&hidden_states.apply(&self.k_proj));
+        let mut value = self.split_heads(&hidden_states.apply(&self.v_proj));
+
+        let query = rotary_embedding.apply(&query, cos, sin);
+        let mut key = rotary_embedding.apply(&key, cos, sin);
+        if let Some(layer_state) = layer_state {
+            key = Tensor::cat(&[&layer_state.prev_key, &key], 2);
+            value = Tensor::cat(&[&layer_state.prev_value, &value], 2);
+        }
+        let new_layer_state = LayerState {
+            prev_key: key.copy(),
+            prev_value: value.copy(),
+        };
+
+        let mut attention_scores = query
+            .to_kind(Kind::Float)
+            .matmul(&key.to_kind(Kind::Float).transpose(-1, -2))
+            / (self.head_dim as f64).sqrt();
+        if let Some(attention_mask) = attention_mask {
+            attention_scores = attention_scores.masked_fill(attention_mask, f32::MIN as f64);
+        }
+        let attention_weights = attention_scores
+            .softmax(-1, Kind::Float)
+            .to_kind(value.kind())
+            .apply_t(&self.attention_dropout, train);
+
+        let attention_output = attention_weights
+            .matmul(&value)
+            .transpose(1, 2)
+            .contiguous()
+            .view([batch_size, sequence_length, self.num_heads * self.head_dim])
+            .apply(&self.out_proj)
+            .apply_t(&self.resid_dropout, train);
+
+        let attention_weights = if self.output_attentions {
+            Some(attention_weights)
+        } else {
+            None
+        };
+
+        (attention_output, attention_weights, new_layer_state)
+    }
+}
@@ -0,0 +1,119 @@
+// Copyright 2021 The EleutherAI and HuggingFace Teams. All rights reserved.
+// Copyright 2023 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::common::activations::TensorFunction;
+use crate::common::dropout::Dropout;
+use crate::common::embeddings::RotaryEmbedding;
+use crate::gptj::attention::{GptJAttention, LayerState};
+use crate::gptj::GptJConfig;
+use std::borrow::Borrow;
+use tch::nn::ModuleT;
+use tch::{nn, Tensor};
+
+#[derive(Debug)]
+pub struct GptJMLP {
+    fc_in: nn::Linear,
+    fc_out: nn::Linear,
+    activation_function: TensorFunction,
+    dropout: Dropout,
+}
+
+impl GptJMLP {
+    pub fn new<'p, P>(p: P, config: &GptJConfig) -> GptJMLP
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let p = p.borrow();
+
+        let inner_dim = config.n_inner.unwrap_or(4 * config.n_embd);
+        let fc_in = nn::linear(p / "fc_in", config.n_embd, inner_dim, Default::default());
+        let fc_out = nn::linear(p / "fc_out", inner_dim, config.n_embd, Default::default());
+
+        let activation_function = config.activation_function.get_function();
+        let dropout = Dropout::new(config.resid_pdrop);
+
+        GptJMLP {
+            fc_in,
+            fc_out,
+            activation_function,
+            dropout,
+        }
+    }
+}
+
+impl ModuleT for GptJMLP {
+    fn forward_t(&self, hidden_states: &Tensor, train: bool) -> Tensor {
+        let hidden_states = hidden_states.apply(&self.fc_in);
+        let hidden_states = self.activation_function.get_fn()(&hidden_states);
+        hidden_states
+            .apply(&self.fc_out)
+            .apply_t(&self.dropout, train)
+    }
+}
+
+/// # GPT-J block
+/// The attention and feed-forward layers are computed in parallel from the same normalized input, and
+/// added to the residual stream.
+pub struct GptJBlock {
+    ln_1: nn::LayerNorm,
+    attention: GptJAttention,
+    mlp: GptJMLP,
+}
+
+impl GptJBlock {
+    pub fn new<'p, P>(p: P, config: &GptJConfig) -> GptJBlock
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let p = p.borrow();
+
+        let layer_norm_config = nn::LayerNormConfig {
+            eps: config.layer_norm_epsilon,
+            ..Default::default()
+        };
+        let ln_1 = nn::layer_norm(p / "ln_1", vec![config.n_embd], layer_norm_config);
+        let attention = GptJAttention::new(p / "attn", config);
+        let mlp = GptJMLP::new(p / "mlp", config);
+
+        GptJBlock {
+            ln_1,
+            attention,
+            mlp,
+        }
+    }
+
+    pub fn forward_t(
+        &self,
+        hidden_states: &Tensor,
+        rotary_embedding: &RotaryEmbedding,
+        cos: &Tensor,
+        sin: &Tensor,
+        attention_mask: Option<&Tensor>,
+        layer_state: Option<&LayerState>,
+        train: bool,
+    ) -> (Tensor, Option<Tensor>, LayerState) {
+        let intermediate = hidden_states.apply(&self.ln_1);
+        let (attention_output, attention_weights, layer_state) = self.attention.forward_t(
+            &intermediate,
+            rotary_embedding,
+            cos,
+            sin,
+            attention_mask,
+            layer_state,
+            train,
+        );
+        let feed_forward_output = intermediate.apply_t(&self.mlp, train);
+        let output = attention_output + feed_forward_output + hidden_states;
+
+        (output, attention_weights, layer_state)
+    }
+}
@@ -0,0 +1,68 @@
+//! # GPT-J
+//!
+//! Implementation of the GPT-J language model ([GPT-J-6B: A 6 Billion Parameter Autoregressive Language Model](https://github.com/kingoflolz/mesh-transformer-jax) Wang, Komatsuzaki, 2021).
+//! The base model is implemented in the `gptj_model::GptJModel` struct. A causal language modeling head is implemented in `gptj_model::GptJForCausalLM`.
+//! Each block computes its attention and feed-forward layers in parallel from the same normalized input, and the
+//! rotary position embeddings are only applied to the first `rotary_dim` dimensions of each attention head.
+//!
+//! # Model set-up and pre-trained weights loading
+//!
+//! All models expect the following resources:
+//! - Configuration file expected to have a structure following the [Transformers library](https://github.com/huggingface/transformers)
+//! - Model weights are expected to have a structure and parameter names following the [Transformers library](https://github.com/huggingface/transformers). A conversion using the Python utility scripts is required to convert the `.bin` weights to the `.ot` format.
+//! - `GPT2Tokenizer` using a `vocab.json` vocabulary and a `merges.txt` merges file
+//!
+//! No pretrained checkpoints are registered as remote resources: the weights have to be converted and loaded from local resources.
+//!
+//! ```no_run
+//! use rust_bert::pipelines::common::ModelType;
+//! use rust_bert::pipelines::text_generation::{TextGenerationConfig, TextGenerationModel};
+//! use rust_bert::resources::LocalResource;
+//! use std::path::PathBuf;
+//! use tch::Device;
+//!
+//! fn main() -> anyhow::Result<()> {
+//!     let config_resource = Box::new(LocalResource {
+//!         local_path: PathBuf::from("path/to/config.json"),
+//!     });
+//!     let vocab_resource = Box::new(LocalResource {
+//!         local_path: PathBuf::from("path/to/vocab.json"),
+//!     });
+//!     let merges_resource = Box::new(LocalResource {
+//!         local_path: PathBuf::from("path/to/merges.txt"),
+//!     });
+//!     let model_resource = Box::new(LocalResource {
+//!         local_path: PathBuf::from("path/to/rust_model.ot"),
+//!     });
+//!
+//!     let text_generation_config = TextGenerationConfig {
+//!         model_type: ModelType::GPTJ,
+//!         model_resource,
+//!         config_resource,
+//!         vocab_resource,
+//!         merges_resource: Some(merges_resource),
+//!         max_length: Some(64),
+//!         do_sample: false,
+//!         device: Device::cuda_if_available(),
+//!         ..Default::default()
+//!     };
+//!     let model = TextGenerationModel::new(text_generation_config)?;
+//!
+//!     let output = model.generate(&["It was a very nice and sunny"], None);
+//!
+//!     for sentence in output {
+//!         println!("{}", sentence);
+//!     }
+//!
+//!     Ok(())
+//! }
+//! ```
+
+mod attention;
+mod decoder;
+mod gptj_model;
+
+pub use attention::LayerState;
+pub use gptj_model::{
+    GptJConfig, GptJForCausalLM, GptJGenerator, GptJModel, GptJModelLMOutput, GptJModelOutput,
+};
@@ -61,6 +61,11 @@
 //!GPT| | | |✅ | | | |  |
 //!GPT2| | | |✅ | | | |  |
 //!GPT-Neo| | | |✅ | | | | |
+//!GPT-J| | | |✅ | | | | |
+//!GPT-NeoX| | | |✅ | | | | |
+//!LLaMA| | | |✅ | | | | |
+//!OPT| | | |✅ | | | | |
+//!BLOOM| | | |✅ | | | | |
 //!BART|✅| | |✅ |✅| | | |
 //!Marian| | | |  | |✅| |  |
 //!MBart|✅| | |✅ | | | |  |
@@ -73,6 +78,7 @@
 //!ProphetNet| | | |✅ |✅ | | |  |
 //!Longformer|✅|✅|✅| | | |✅|  |
 //!Pegasus| | | | |✅| | |  |
+//!CLIP| | | | | | | | ✅ |
 //! </details>
 //!
 //! # Getting started
@@ -433,14 +439,17 @@
 //!     Sentiment {
 //!         polarity: Positive,
 //!         score: 0.998,
+//!         stars: None,
 //!     },
 //!     Sentiment {
 //!         polarity: Negative,
 //!         score: 0.992,
+//!         stars: None,
 //!     },
 //!     Sentiment {
 //!         polarity: Positive,
 //!         score: 0.999,
+//!         stars: None,
 //!     },
 //! ]
 //! # ;
@@ -698,28 +707,38 @@
 pub mod albert;
 pub mod bart;
 pub mod bert;
+pub mod bloom;
+pub mod clip;
 mod common;
 pub mod deberta;
 pub mod deberta_v2;
 pub mod distilbert;
 pub mod electra;
+pub mod evaluation;
 pub mod fnet;
 pub mod gpt2;
 pub mod gpt_neo;
+pub mod gpt_neox;
+pub mod gptj;
+pub mod llama;
 pub mod longformer;
 pub mod m2m_100;
 pub mod marian;
 pub mod mbart;
 pub mod mobilebert;
 pub mod openai_gpt;
+pub mod opt;
 pub mod pegasus;
 pub mod pipelines;
 pub mod prophetnet;
 pub mod reformer;
 pub mod roberta;
 pub mod t5;
+pub mod training;
 pub mod xlnet;
 
 pub use common::error::RustBertError;
+pub use common::lora;
+pub use common::loss;
 pub use common::resources;
 pub use common::{Activation, Config};
@@ -0,0 +1,211 @@
+// Copyright 2022 EleutherAI and the HuggingFace Inc. team. All rights reserved.
+// Copyright 2023 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::common::dropout::Dropout;
+use crate::common::embeddings::RotaryEmbedding;
+use crate::llama::LlamaConfig;
+use std::borrow::Borrow;
+use tch::{nn, Kind, Tensor};
+
+#[derive(Debug)]
+/// # Cache for LLaMA attention layers
+/// Stores the cached value of key and value (after the rotary embeddings, before the repetition of the
+/// key/value heads for grouped-query attention)
+pub struct LayerState {
+    /// Cached keys
+    pub prev_key: Tensor,
+    /// Cached values
+    pub prev_value: Tensor,
+}
+
+impl Clone for LayerState {
+    fn clone(&self) -> Self {
+        LayerState {
+            prev_key: self.prev_key.copy(),
+            prev_value: self.prev_value.copy(),
+        }
+    }
+}
+
+impl LayerState {
+    pub(crate) fn reorder_cache(&mut self, new_indices: &Tensor) {
+        self.prev_key = self.prev_key.index_select(0, new_indices);
+        self.prev_value = self.prev_value.index_select(0, new_indices);
+    }
+}
+
+/// # LLaMA self-attention with grouped-query attention
+/// Queries use `num_attention_heads` heads, while keys and values use `num_key_value_heads` heads shared by
+/// groups of query heads (multi-head attention if both are equal, multi-query attention for a single
+/// key/value head).
+pub struct LlamaAttention {
+    q_proj: nn::Linear,
+    k_proj: nn::Linear,
+    v_proj: nn::Linear,
+    o_proj: nn::Linear,
+    dropout: Dropout,
+    num_heads: i64,
+    num_key_value_heads: i64,
+    head_dim: i64,
+    output_attentions: bool,
+}
+
+impl LlamaAttention {
+    pub fn new<'p, P>(p: P, config: &LlamaConfig) -> LlamaAttention
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let p = p.borrow();
+
+        let num_heads = config.num_attention_heads;
+        let num_key_value_heads = config.num_key_value_heads.unwrap_or(num_heads);
+        let head_dim = config.hidden_size / num_heads;
+
+        let linear_config = nn::LinearConfig {
+            bias: config.attention_bias.unwrap_or(false),
+            ..Default::default()
+        };
+        let q_proj = nn::linear(
+            p / "q_proj",
+            config.hidden_size,
+            num_heads * head_dim,
+            linear_config,
+        );
+        let k_proj = nn::linear(
+            p / "k_proj",
+            config.hidden_size,
+            num_key_value_heads * head_dim,
+            linear_config,
+        );
+        let v_proj = nn::linear(
+            p / "v_proj",
+            config.hidden_size,
+            num_key_value_heads * head_dim,
+            linear_config,
+        );
+        let o_proj = nn::linear(
+            p / "o_proj",
+            num_heads * head_dim,
+            config.hidden_size,
+            linear_config,
+        );
+
+        let dropout = Dropout::new(config.attention_dropout.unwrap_or(0.0));
+        let output_attentions = config.output_attentions.unwrap_or(false);
+
+        LlamaAttention {
+            q_proj,
+            k_proj,
+            v_proj,
+            o_proj,
+            dropout,
+            num_heads,
+            num_key_value_heads,
+            head_dim,
+            output_attentions,
+        }
+    }
+
+    fn split_heads(&self, x: &Tensor, num_heads: i64) -> Tensor {
+        let size = x.size();
+        x.view([size[0], size[1], num_heads, self.head_dim])
+            .transpose(1, 2)
+    }
+
+    fn repeat_key_value(&self, x: &Tensor) -> Tensor {
+        let num_groups = self.num_heads / self.num_key_value_heads;
+        if num_groups == 1 {
+            return x.shallow_clone();
+        }
+        let size = x.size();
+        x.unsqueeze(2)
+            .expand(
+                &[
+                    size[0],
+                    self.num_key_value_heads,
+                    num_groups,
+                    size[2],
+                    self.head_dim,
+                ],
+                false,
+            )
+            .reshape(&[size[0], self.num_heads, size[2], self.head_dim])
+    }
+
+    /// Forward pass through the attention layer
+    ///
+    /// # Arguments
+    ///
+    /// * `hidden_states` - input tensor of shape (*batch size*, *sequence_length*, *hidden_size*)
+    /// * `rotary_embedding` - `RotaryEmbedding` applied to the queries and keys
+    /// * `cos`, `sin` - rotary embedding tables of shape (*batch size*, *sequence_length*, *head_dim*)
+    /// * `attention_mask` - Optional boolean mask of shape (*batch size*, 1, *sequence_length*, *total_sequence_length*), with `true` for the positions that cannot be attended to
+    /// * `layer_state` - Optional cached keys and values of the previous positions
+    /// * `train` - boolean flag to turn on/off the dropout layers in the model
+    pub fn forward_t(
+        &self,
+        hidden_states: &Tensor,
+        rotary_embedding: &RotaryEmbedding,
+        cos: &Tensor,
+        sin: &Tensor,
+        attention_mask: Option<&Tensor>,
+        layer_state: Option<&LayerState>,
+        train: bool,
+    ) -> (Tensor, Option<Tensor>, LayerState) {
+        let size = hidden_states.size();
+        let (batch_size, sequence_length) = (size[0], size[1]);
+
+        let query = self.split_heads(&hidden_states.apply(&self.q_proj), self.num_heads);
+        let key = self.split_heads(&hidden_states.apply(&self.k_proj), self.num_key_value_heads);
+        let value = self.split_heads(&hidden_states.apply(&self.v_proj), self.num_key_value_heads);
+
+        let query = rotary_embedding.apply(&query, cos, sin);
+        let mut key = rotary_embedding.apply(&key, cos, sin);
+        let mut value = value;
+        if let Some(layer_state) = layer_state {
+            key = Tensor::cat(&[&layer_state.prev_key, &key], 2);
+            value = Tensor::cat(&[&layer_state.prev_value, &value], 2);
+        }
+        let new_layer_state = LayerState {
+            prev_key: key.copy(),
+            prev_value: value.copy(),
+        };
+
+        let key = self.repeat_key_value(&key);
+        let value = self.repeat_key_value(&value);
+
+        let mut attention_scores = query.matmul(&key.transpose(-1, -2)).to_kind(Kind::Float)
+            / (self.head_dim as f64).sqrt();
+        if let Some(attention_mask) = attention_mask {
+            attention_scores = attention_scores.masked_fill(attention_mask, f32::MIN as f64);
+        }
+        let attention_weights = attention_scores
+            .softmax(-1, Kind::Float)
+            .to_kind(value.kind())
+            .apply_t(&self.dropout, train);
+
+        let attention_output = attention_weights
+            .matmul(&value)
+            .transpose(1, 2)
+            .contiguous()
+            .view([batch_size, sequence_length, self.num_heads * self.head_dim])
+            .apply(&self.o_proj);
+
+        let attention_weights = if self.output_attentions {
+            Some(attention_weights)
+        } else {
+            None
+        };
+
+        (attention_output, attention_weights, new_layer_state)
+    }
+}
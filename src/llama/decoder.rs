@@ -0,0 +1,172 @@
+// Copyright 2022 EleutherAI and the HuggingFace Inc. team. All rights reserved.
+// Copyright 2023 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::common::activations::TensorFunction;
+use crate::common::embeddings::RotaryEmbedding;
+use crate::llama::attention::{LayerState, LlamaAttention};
+use crate::llama::LlamaConfig;
+use std::borrow::Borrow;
+use tch::nn::{Init, Module};
+use tch::{nn, Kind, Tensor};
+
+#[derive(Debug)]
+/// # Root mean square layer normalization
+/// Layer normalization without re-centering and without bias ([Zhang and Sennrich, 2019](https://arxiv.org/abs/1910.07467)).
+pub struct RmsNorm {
+    weight: Tensor,
+    epsilon: f64,
+}
+
+impl RmsNorm {
+    pub fn new<'p, P>(p: P, hidden_size: i64, epsilon: f64) -> RmsNorm
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let weight = p.borrow().var("weight", &[hidden_size], Init::Const(1.0));
+        RmsNorm { weight, epsilon }
+    }
+}
+
+impl Module for RmsNorm {
+    fn forward(&self, x: &Tensor) -> Tensor {
+        let input_type = x.kind();
+        let x = x.to_kind(Kind::Float);
+        let variance = x
+            .pow_tensor_scalar(2.0_f64)
+            .mean_dim([-1].as_slice(), true, Kind::Float);
+        let x = x * (variance + self.epsilon).rsqrt();
+        &self.weight * x.to_kind(input_type)
+    }
+}
+
+#[derive(Debug)]
+/// # Gated feed-forward layer (SwiGLU)
+/// Computes `down_proj(activation(gate_proj(x)) * up_proj(x))` ([Shazeer, 2020](https://arxiv.org/abs/2002.05202)).
+pub struct LlamaMLP {
+    gate_proj: nn::Linear,
+    up_proj: nn::Linear,
+    down_proj: nn::Linear,
+    activation_function: TensorFunction,
+}
+
+impl LlamaMLP {
+    pub fn new<'p, P>(p: P, config: &LlamaConfig) -> LlamaMLP
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let p = p.borrow();
+
+        let linear_config = nn::LinearConfig {
+            bias: false,
+            ..Default::default()
+        };
+        let gate_proj = nn::linear(
+            p / "gate_proj",
+            config.hidden_size,
+            config.intermediate_size,
+            linear_config,
+        );
+        let up_proj = nn::linear(
+            p / "up_proj",
+            config.hidden_size,
+            config.intermediate_size,
+            linear_config,
+        );
+        let down_proj = nn::linear(
+            p / "down_proj",
+            config.intermediate_size,
+            config.hidden_size,
+            linear_config,
+        );
+        let activation_function = config.hidden_act.get_function();
+
+        LlamaMLP {
+            gate_proj,
+            up_proj,
+            down_proj,
+            activation_function,
+        }
+    }
+}
+
+impl Module for LlamaMLP {
+    fn forward(&self, hidden_states: &Tensor) -> Tensor {
+        let gate = self.activation_function.get_fn()(&hidden_states.apply(&self.gate_proj));
+        (gate * hidden_states.apply(&self.up_proj)).apply(&self.down_proj)
+    }
+}
+
+/// # LLaMA decoder layer
+/// Pre-normalization decoder layer made of a self-attention and a gated feed-forward block.
+pub struct LlamaDecoderLayer {
+    self_attn: LlamaAttention,
+    mlp: LlamaMLP,
+    input_layernorm: RmsNorm,
+    post_attention_layernorm: RmsNorm,
+}
+
+impl LlamaDecoderLayer {
+    pub fn new<'p, P>(p: P, config: &LlamaConfig) -> LlamaDecoderLayer
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let p = p.borrow();
+
+        let self_attn = LlamaAttention::new(p / "self_attn", config);
+        let mlp = LlamaMLP::new(p / "mlp", config);
+        let input_layernorm = RmsNorm::new(
+            p / "input_layernorm",
+            config.hidden_size,
+            config.rms_norm_eps,
+        );
+        let post_attention_layernorm = RmsNorm::new(
+            p / "post_attention_layernorm",
+            config.hidden_size,
+            config.rms_norm_eps,
+        );
+
+        LlamaDecoderLayer {
+            self_attn,
+            mlp,
+            input_layernorm,
+            post_attention_layernorm,
+        }
+    }
+
+    pub fn forward_t(
+        &self,
+        hidden_states: &Tensor,
+        rotary_embedding: &RotaryEmbedding,
+        cos: &Tensor,
+        sin: &Tensor,
+        attention_mask: Option<&Tensor>,
+        layer_state: Option<&LayerState>,
+        train: bool,
+    ) -> (Tensor, Option<Tensor>, LayerState) {
+        let (attention_output, attention_weights, layer_state) = self.self_attn.forward_t(
+            &hidden_states.apply(&self.input_layernorm),
+            rotary_embedding,
+            cos,
+            sin,
+            attention_mask,
+            layer_state,
+            train,
+        );
+        let hidden_states = hidden_states + attention_output;
+        let output = &hidden_states
+            + hidden_states
+                .apply(&self.post_attention_layernorm)
+                .apply(&self.mlp);
+
+        (output, attention_weights, layer_state)
+    }
+}
@@ -0,0 +1,776 @@
+// Copyright 2022 EleutherAI and the HuggingFace Inc. team. All rights reserved.
+// Copyright 2023 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::common::embeddings::{process_ids_embeddings_pair, RotaryEmbedding};
+use crate::llama::decoder::{LlamaDecoderLayer, RmsNorm};
+use crate::llama::{LayerState, LlamaTokenizer};
+use crate::pipelines::common::{ModelType, TokenizerOption};
+use crate::pipelines::generation_utils::private_generation_utils::{
+    PreparedInput, PrivateLanguageGenerator,
+};
+use crate::pipelines::generation_utils::{
+    Cache, GenerateConfig, LMHeadModel, LMModelOutput, LanguageGenerator,
+};
+use crate::{Activation, Config, RustBertError};
+use rust_tokenizers::tokenizer::TruncationStrategy;
+use rust_tokenizers::vocab::SentencePieceVocab;
+use serde::{Deserialize, Serialize};
+use std::borrow::{Borrow, BorrowMut};
+use tch::{nn, Kind, Tensor};
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+/// # LLaMA model configuration
+/// Defines the LLaMA model architecture (e.g. number of layers, hidden layer size, number of key/value heads...).
+/// The field names follow the configuration files of the [Transformers library](https://github.com/huggingface/transformers).
+pub struct LlamaConfig {
+    pub vocab_size: i64,
+    pub hidden_size: i64,
+    pub intermediate_size: i64,
+    pub num_hidden_layers: i64,
+    pub num_attention_heads: i64,
+    /// Number of key/value heads for grouped-query attention (defaults to `num_attention_heads`)
+    pub num_key_value_heads: Option<i64>,
+    pub hidden_act: Activation,
+    pub max_position_embeddings: i64,
+    pub initializer_range: f64,
+    pub rms_norm_eps: f64,
+    /// Base of the rotary embedding frequencies (defaults to 10000)
+    pub rope_theta: Option<f64>,
+    pub attention_bias: Option<bool>,
+    pub attention_dropout: Option<f64>,
+    pub tie_word_embeddings: Option<bool>,
+    pub bos_token_id: Option<i64>,
+    pub eos_token_id: Option<i64>,
+    pub pad_token_id: Option<i64>,
+    pub output_attentions: Option<bool>,
+    pub output_hidden_states: Option<bool>,
+}
+
+impl Config for LlamaConfig {}
+
+impl Default for LlamaConfig {
+    fn default() -> Self {
+        LlamaConfig {
+            vocab_size: 32000,
+            hidden_size: 4096,
+            intermediate_size: 11008,
+            num_hidden_layers: 32,
+            num_attention_heads: 32,
+            num_key_value_heads: None,
+            hidden_act: Activation::swish,
+            max_position_embeddings: 2048,
+            initializer_range: 0.02,
+            rms_norm_eps: 1e-6,
+            rope_theta: None,
+            attention_bias: None,
+            attention_dropout: None,
+            tie_word_embeddings: None,
+            bos_token_id: Some(1),
+            eos_token_id: Some(2),
+            pad_token_id: None,
+            output_attentions: None,
+            output_hidden_states: None,
+        }
+    }
+}
+
+/// # LLaMA Base model
+/// Base architecture for LLaMA models. Task-specific models will be built from this common base model
+/// It is made of the following blocks:
+/// - `embed_tokens`: Word embeddings
+/// - `layers`: Vector of `LlamaDecoderLayer` (transformer part of the model)
+/// - `norm`: Final RMS normalization
+/// - `rotary_embedding`: Rotary position embeddings shared by all layers
+pub struct LlamaModel {
+    embed_tokens: nn::Embedding,
+    layers: Vec<LlamaDecoderLayer>,
+    norm: RmsNorm,
+    rotary_embedding: RotaryEmbedding,
+    output_attentions: bool,
+    output_hidden_states: bool,
+}
+
+impl LlamaModel {
+    /// Build a new `LlamaModel`
+    ///
+    /// # Arguments
+    ///
+    /// * `p` - Variable store path for the root of the LLaMA model
+    /// * `config` - `LlamaConfig` object defining the model architecture
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use rust_bert::llama::{LlamaConfig, LlamaModel};
+    /// use rust_bert::Config;
+    /// use std::path::Path;
+    /// use tch::{nn, Device};
+    ///
+    /// let config_path = Path::new("path/to/config.json");
+    /// let device = Device::Cpu;
+    /// let p = nn::VarStore::new(device);
+    /// let config = LlamaConfig::from_file(config_path);
+    /// let llama_model = LlamaModel::new(&p.root(), &config);
+    /// ```
+    pub fn new<'p, P>(p: P, config: &LlamaConfig) -> LlamaModel
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let p = p.borrow();
+
+        let embed_tokens = nn::embedding(
+            p / "embed_tokens",
+            config.vocab_size,
+            config.hidden_size,
+            Default::default(),
+        );
+
+        let mut layers: Vec<LlamaDecoderLayer> =
+            Vec::with_capacity(config.num_hidden_layers as usize);
+        let p_layers = p / "layers";
+        for layer_index in 0..config.num_hidden_layers {
+            layers.push(LlamaDecoderLayer::new(&p_layers / layer_index, config));
+        }
+
+        let norm = RmsNorm::new(p / "norm", config.hidden_size, config.rms_norm_eps);
+        let rotary_embedding = RotaryEmbedding::new(
+            config.hidden_size / config.num_attention_heads,
+            config.rope_theta.unwrap_or(10000.0),
+            false,
+            p.device(),
+        );
+
+        let output_attentions = config.output_attentions.unwrap_or(false);
+        let output_hidden_states = config.output_hidden_states.unwrap_or(false);
+
+        LlamaModel {
+            embed_tokens,
+            layers,
+            norm,
+            rotary_embedding,
+            output_attentions,
+            output_hidden_states,
+        }
+    }
+
+    /// Forward pass through the model
+    ///
+    /// # Arguments
+    ///
+    /// * `input_ids` - Optional input tensor of shape (*batch size*, *sequence_length*). This or `input_embeds` must be provided.
+    /// * `input_embeds` - Optional input tensor of shape (*batch size*, *sequence_length*, *embeddings dimension*). This or `input_ids` must be provided.
+    /// * `position_ids` - Optional position ids of shape (*batch size*, *sequence_length*). If None, will be incremented starting from the length of the past input.
+    /// * `layer_states` - Optional Vector `Option<Vec<Option<LayerState>>>` of length *n_layer* containing the past keys and values of each layer.
+    /// * `attention_mask` - Optional attention mask of shape (*batch size*, *past_sequence_length + sequence_length*). Positions with a mask with value 0 will be masked.
+    /// * `train` - boolean flag to turn on/off the dropout layers in the model. Should be set to false for inference.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<LlamaModelOutput, RustBertError>` containing:
+    ///   - `hidden_states` - `Tensor` of shape (*batch size*, *sequence_length*, *hidden_size*) representing the activations of the last hidden state
+    ///   - `next_cache` - `Option<Vec<Option<LayerState>>>` of length *n_layer* containing the past content for the the attention layers
+    ///   - `all_hidden_states` - `Option<Vec<Tensor>>` of length *n_layer + 1* with shape (*batch size*, *sequence_length*, *hidden_size*)
+    ///   - `all_attentions` - `Option<Vec<Tensor>>` of length *n_layer* containing the attention weights for each layer
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use tch::{nn, Device, Tensor, no_grad, Kind};
+    /// # use rust_bert::Config;
+    /// # use std::path::Path;
+    /// # use tch::kind::Kind::{Int64, Double};
+    /// use rust_bert::llama::{LlamaConfig, LlamaModel};
+    /// # let config_path = Path::new("path/to/config.json");
+    /// # let device = Device::Cpu;
+    /// # let vs = nn::VarStore::new(device);
+    /// # let config = LlamaConfig::from_file(config_path);
+    /// # let llama_model = LlamaModel::new(&vs.root(), &config);
+    /// let (batch_size, sequence_length) = (64, 128);
+    /// let input_tensor = Tensor::rand(&[batch_size, sequence_length], (Int64, device));
+    /// let attention_mask = Tensor::ones(&[batch_size, sequence_length], (Int64, device));
+    ///
+    /// let model_output = no_grad(|| {
+    ///     llama_model.forward_t(
+    ///         Some(&input_tensor),
+    ///         None,
+    ///         None,
+    ///         None,
+    ///         Some(&attention_mask),
+    ///         false,
+    ///     )
+    /// });
+    /// ```
+    pub fn forward_t(
+        &self,
+        input_ids: Option<&Tensor>,
+        input_embeds: Option<&Tensor>,
+        position_ids: Option<&Tensor>,
+        layer_states: Option<Vec<Option<LayerState>>>,
+        attention_mask: Option<&Tensor>,
+        train: bool,
+    ) -> Result<LlamaModelOutput, RustBertError> {
+        let (calc_input_embeddings, input_shape, device) =
+            process_ids_embeddings_pair(input_ids, input_embeds, &self.embed_tokens)?;
+        let input_embeds = input_embeds.unwrap_or_else(|| calc_input_embeddings.as_ref().unwrap());
+
+        let (batch_size, sequence_length) = (input_shape[0], input_shape[1]);
+        let past_length = match &layer_states {
+            Some(layer_states) => match &layer_states[0] {
+                Some(layer_state) => layer_state.prev_key.size()[2],
+                None => 0,
+            },
+            None => 0,
+        };
+        let full_sequence_length = past_length + sequence_length;
+
+        let calc_position_ids = if position_ids.is_none() {
+            Some(
+                Tensor::arange_start(past_length, full_sequence_length, (Kind::Int64, device))
+                    .unsqueeze(0)
+                    .expand(&[batch_size, sequence_length], true),
+            )
+        } else {
+            None
+        };
+        let position_ids = position_ids.unwrap_or_else(|| calc_position_ids.as_ref().unwrap());
+        let (cos, sin) = self
+            .rotary_embedding
+            .forward(position_ids, input_embeds.kind());
+
+        let query_positions =
+            Tensor::arange_start(past_length, full_sequence_length, (Kind::Int64, device))
+                .unsqueeze(-1);
+        let key_positions =
+            Tensor::arange(full_sequence_length, (Kind::Int64, device)).unsqueeze(0);
+        let mut causal_mask = key_positions.gt_tensor(&query_positions).view([
+            1,
+            1,
+            sequence_length,
+            full_sequence_length,
+        ]);
+        if let Some(attention_mask) = attention_mask {
+            causal_mask =
+                causal_mask.logical_or(&attention_mask.eq(0).view([batch_size, 1, 1, -1]));
+        }
+
+        let mut hidden_state = input_embeds.shallow_clone();
+        let mut all_hidden_states: Option<Vec<Tensor>> = if self.output_hidden_states {
+            Some(vec![])
+        } else {
+            None
+        };
+        let mut all_attentions: Option<Vec<Tensor>> = if self.output_attentions {
+            Some(vec![])
+        } else {
+            None
+        };
+        let old_cache = layer_states.unwrap_or_else(|| vec![None; self.layers.len()]);
+        let mut next_cache = Vec::with_capacity(self.layers.len());
+
+        for (layer, layer_state) in self.layers.iter().zip(old_cache) {
+            if let Some(hidden_states) = all_hidden_states.borrow_mut() {
+                hidden_states.push(hidden_state.copy());
+            };
+            let (output, attention_weights, layer_state) = layer.forward_t(
+                &hidden_state,
+                &self.rotary_embedding,
+                &cos,
+                &sin,
+                Some(&causal_mask),
+                layer_state.as_ref(),
+                train,
+            );
+            hidden_state = output;
+            next_cache.push(Some(layer_state));
+            if let Some(attentions) = all_attentions.borrow_mut() {
+                attentions.push(attention_weights.unwrap());
+            };
+        }
+
+        let hidden_states = hidden_state.apply(&self.norm);
+        if let Some(all_hidden_states) = all_hidden_states.borrow_mut() {
+            all_hidden_states.push(hidden_states.copy());
+        };
+
+        Ok(LlamaModelOutput {
+            hidden_states,
+            next_cache: Some(next_cache),
+            all_hidden_states,
+            all_attentions,
+        })
+    }
+}
+
+/// # LLaMA Model for causal language modeling
+/// LLaMA model with a vocabulary decoding head. The decoding head is tied to the word embeddings if
+/// `tie_word_embeddings` is set in the configuration.
+/// It is made of the following blocks:
+/// - `model`: `LlamaModel` Base LLaMA model
+/// - `lm_head`: Linear layer projecting the hidden states to the vocabulary logits
+pub struct LlamaForCausalLM {
+    model: LlamaModel,
+    lm_head: Option<nn::Linear>,
+}
+
+impl LlamaForCausalLM {
+    /// Build a new `LlamaForCausalLM`
+    ///
+    /// # Arguments
+    ///
+    /// * `p` - Variable store path for the root of the LLaMA model
+    /// * `config` - `LlamaConfig` object defining the model architecture
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use rust_bert::llama::{LlamaConfig, LlamaForCausalLM};
+    /// use rust_bert::Config;
+    /// use std::path::Path;
+    /// use tch::{nn, Device};
+    ///
+    /// let config_path = Path::new("path/to/config.json");
+    /// let device = Device::Cpu;
+    /// let p = nn::VarStore::new(device);
+    /// let config = LlamaConfig::from_file(config_path);
+    /// let llama_model = LlamaForCausalLM::new(&p.root(), &config);
+    /// ```
+    pub fn new<'p, P>(p: P, config: &LlamaConfig) -> LlamaForCausalLM
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let p = p.borrow();
+
+        let model = LlamaModel::new(p / "model", config);
+        let lm_head = if config.tie_word_embeddings.unwrap_or(false) {
+            None
+        } else {
+            Some(nn::linear(
+                p / "lm_head",
+                config.hidden_size,
+                config.vocab_size,
+                nn::LinearConfig {
+                    bias: false,
+                    ..Default::default()
+                },
+            ))
+        };
+
+        LlamaForCausalLM { model, lm_head }
+    }
+
+    /// Forward pass through the model
+    ///
+    /// # Arguments
+    ///
+    /// * `input_ids` - Optional input tensor of shape (*batch size*, *sequence_length*). This or `input_embeds` must be provided.
+    /// * `input_embeds` - Optional input tensor of shape (*batch size*, *sequence_length*, *embeddings dimension*). This or `input_ids` must be provided.
+    /// * `position_ids` - Optional position ids of shape (*batch size*, *sequence_length*). If None, will be incremented starting from the length of the past input.
+    /// * `layer_states` - Optional Vector `Option<Vec<Option<LayerState>>>` of length *n_layer* containing the past keys and values of each layer.
+    /// * `attention_mask` - Optional attention mask of shape (*batch size*, *past_sequence_length + sequence_length*). Positions with a mask with value 0 will be masked.
+    /// * `train` - boolean flag to turn on/off the dropout layers in the model. Should be set to false for inference.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<LlamaModelLMOutput, RustBertError>` containing:
+    ///   - `lm_logits` - `Tensor` of shape (*batch size*, *sequence_length*, *vocab_size*) representing the logits for each vocab item and position
+    ///   - `next_cache` - `Option<Vec<Option<LayerState>>>` of length *n_layer* containing the past content for the the attention layers
+    ///   - `all_hidden_states` - `Option<Vec<Tensor>>` of length *n_layer + 1* with shape (*batch size*, *sequence_length*, *hidden_size*)
+    ///   - `all_attentions` - `Option<Vec<Tensor>>` of length *n_layer* containing the attention weights for each layer
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use tch::{nn, Device, Tensor, no_grad, Kind};
+    /// # use rust_bert::Config;
+    /// # use std::path::Path;
+    /// # use tch::kind::Kind::{Int64, Double};
+    /// use rust_bert::llama::{LlamaConfig, LlamaForCausalLM};
+    /// # let config_path = Path::new("path/to/config.json");
+    /// # let device = Device::Cpu;
+    /// # let vs = nn::VarStore::new(device);
+    /// # let config = LlamaConfig::from_file(config_path);
+    /// # let llama_model = LlamaForCausalLM::new(&vs.root(), &config);
+    /// let (batch_size, sequence_length) = (64, 128);
+    /// let input_tensor = Tensor::rand(&[batch_size, sequence_length], (Int64, device));
+    /// let attention_mask = Tensor::ones(&[batch_size, sequence_length], (Int64, device));
+    ///
+    /// let model_output = no_grad(|| {
+    ///     llama_model.forward_t(
+    ///         Some(&input_tensor),
+    ///         None,
+    ///         None,
+    ///         None,
+    ///         Some(&attention_mask),
+    ///         false,
+    ///     )
+    /// });
+    /// ```
+    pub fn forward_t(
+        &self,
+        input_ids: Option<&Tensor>,
+        input_embeds: Option<&Tensor>,
+        position_ids: Option<&Tensor>,
+        layer_states: Option<Vec<Option<LayerState>>>,
+        attention_mask: Option<&Tensor>,
+        train: bool,
+    ) -> Result<LlamaModelLMOutput, RustBertError> {
+        let base_model_output = self.model.forward_t(
+            input_ids,
+            input_embeds,
+            position_ids,
+            layer_states,
+            attention_mask,
+            train,
+        )?;
+
+        let lm_logits = match &self.lm_head {
+            Some(lm_head) => base_model_output.hidden_states.apply(lm_head),
+            None => base_model_output
+                .hidden_states
+                .linear::<Tensor>(&self.model.embed_tokens.ws, None),
+        };
+
+        Ok(LlamaModelLMOutput {
+            lm_logits,
+            next_cache: base_model_output.next_cache,
+            all_hidden_states: base_model_output.all_hidden_states,
+            all_attentions: base_model_output.all_attentions,
+        })
+    }
+}
+
+impl LMHeadModel for LlamaForCausalLM {
+    fn forward_t(
+        &self,
+        input_ids: Option<&Tensor>,
+        layer_past: Cache,
+        attention_mask: Option<&Tensor>,
+        _token_type_ids: Option<&Tensor>,
+        position_ids: Option<&Tensor>,
+        input_embeds: Option<&Tensor>,
+        _encoder_outputs: Option<&Tensor>,
+        _decoder_input_ids: Option<&Tensor>,
+        train: bool,
+    ) -> Result<LMModelOutput, RustBertError> {
+        let base_model_output = match layer_past {
+            Cache::LlamaCache(layer_past) => self.forward_t(
+                input_ids,
+                input_embeds,
+                position_ids,
+                layer_past,
+                attention_mask,
+                train,
+            ),
+            Cache::None => self.forward_t(
+                input_ids,
+                input_embeds,
+                position_ids,
+                None,
+                attention_mask,
+                train,
+            ),
+            _ => {
+                return Err(RustBertError::ValueError(
+                    "Cache not compatible with LLaMA Model".into(),
+                ));
+            }
+        }?;
+
+        Ok(LMModelOutput {
+            lm_logits: base_model_output.lm_logits,
+            cache: Cache::LlamaCache(base_model_output.next_cache),
+            loss: None,
+        })
+    }
+}
+
+/// Container for the LLaMA model output.
+pub struct LlamaModelOutput {
+    /// Last hidden states from the model
+    pub hidden_states: Tensor,
+    /// Cached outputs of the model (attention layers keys and values) if the model is used for generation
+    pub next_cache: Option<Vec<Option<LayerState>>>,
+    /// Hidden states for all intermediate layers
+    pub all_hidden_states: Option<Vec<Tensor>>,
+    /// Attention weights for all intermediate layers
+    pub all_attentions: Option<Vec<Tensor>>,
+}
+
+///Container holding a LLaMA model with LM head output
+pub struct LlamaModelLMOutput {
+    /// logits
+    pub lm_logits: Tensor,
+    /// Cached outputs of the model (attention layers keys and values) if the model is used for generation
+    pub next_cache: Option<Vec<Option<LayerState>>>,
+    /// Hidden states for all intermediate layers
+    pub all_hidden_states: Option<Vec<Tensor>>,
+    /// Attention weights for all intermediate layers
+    pub all_attentions: Option<Vec<Tensor>>,
+}
+
+/// # Language generation model based on the LLaMA architecture
+pub struct LlamaGenerator {
+    model: LlamaForCausalLM,
+    tokenizer: TokenizerOption,
+    var_store: nn::VarStore,
+    generate_config: GenerateConfig,
+    bos_token_id: Option<i64>,
+    eos_token_ids: Option<Vec<i64>>,
+    pad_token_id: Option<i64>,
+    is_encoder_decoder: bool,
+    vocab_size: i64,
+    decoder_start_id: Option<i64>,
+    max_position_embeddings: i64,
+}
+
+impl LlamaGenerator {
+    /// Build a new `LlamaGenerator`
+    ///
+    /// # Arguments
+    ///
+    /// * `generate_config` - `GenerateConfig` object containing the resource references (model, vocabulary, configuration), generation options and device placement (CPU/GPU). The vocabulary resource is the SentencePiece model (`tokenizer.model`) of the checkpoint.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::llama::LlamaGenerator;
+    /// use rust_bert::pipelines::generation_utils::GenerateConfig;
+    /// use rust_bert::resources::LocalResource;
+    /// use std::path::PathBuf;
+    ///
+    /// let generate_config = GenerateConfig {
+    ///     model_resource: Box::new(LocalResource {
+    ///         local_path: PathBuf::from("path/to/rust_model.ot"),
+    ///     }),
+    ///     config_resource: Box::new(LocalResource {
+    ///         local_path: PathBuf::from("path/to/config.json"),
+    ///     }),
+    ///     vocab_resource: Box::new(LocalResource {
+    ///         local_path: PathBuf::from("path/to/tokenizer.model"),
+    ///     }),
+    ///     merges_resource: None,
+    ///     max_length: Some(30),
+    ///     do_sample: true,
+    ///     ..Default::default()
+    /// };
+    /// let llama_generator = LlamaGenerator::new(generate_config)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn new(generate_config: GenerateConfig) -> Result<LlamaGenerator, RustBertError> {
+        let vocab_path = generate_config.vocab_resource.get_local_path()?;
+
+        let tokenizer = TokenizerOption::from_file(
+            ModelType::Llama,
+            vocab_path.to_str().unwrap(),
+            None,
+            false,
+            None,
+            None,
+        )?;
+
+        Self::new_with_tokenizer(generate_config, tokenizer)
+    }
+
+    pub fn new_with_tokenizer(
+        generate_config: GenerateConfig,
+        tokenizer: TokenizerOption,
+    ) -> Result<LlamaGenerator, RustBertError> {
+        let config_path = generate_config.config_resource.get_local_path()?;
+        let weights_path = generate_config.model_resource.get_local_path()?;
+        let device = generate_config.device;
+
+        generate_config.validate();
+        let mut var_store = nn::VarStore::new(device);
+        let config = LlamaConfig::from_file(config_path);
+        let model = LlamaForCausalLM::new(var_store.root(), &config);
+        var_store.load(weights_path)?;
+
+        let bos_token_id = config.bos_token_id.or_else(|| tokenizer.get_bos_id());
+        let eos_token_ids = config
+            .eos_token_id
+            .or_else(|| tokenizer.get_eos_id())
+            .map(|id| vec![id]);
+        let pad_token_id = config.pad_token_id.or_else(|| tokenizer.get_pad_id());
+        let is_encoder_decoder = false;
+        let vocab_size = config.vocab_size;
+        let decoder_start_id = None;
+        let max_position_embeddings = config.max_position_embeddings;
+
+        Ok(LlamaGenerator {
+            model,
+            tokenizer,
+            var_store,
+            generate_config,
+            bos_token_id,
+            eos_token_ids,
+            pad_token_id,
+            is_encoder_decoder,
+            vocab_size,
+            decoder_start_id,
+            max_position_embeddings,
+        })
+    }
+}
+
+impl PrivateLanguageGenerator<LlamaForCausalLM, SentencePieceVocab, LlamaTokenizer>
+    for LlamaGenerator
+{
+    fn get_model(&self) -> &LlamaForCausalLM {
+        &self.model
+    }
+    fn _get_tokenizer(&self) -> &TokenizerOption {
+        &self.tokenizer
+    }
+    fn get_var_store(&self) -> &nn::VarStore {
+        &self.var_store
+    }
+    fn get_var_store_mut(&mut self) -> &mut nn::VarStore {
+        &mut self.var_store
+    }
+    fn get_config(&self) -> &GenerateConfig {
+        &self.generate_config
+    }
+    fn get_bos_id(&self) -> Option<i64> {
+        self.bos_token_id
+    }
+    fn get_eos_ids(&self) -> Option<&Vec<i64>> {
+        self.eos_token_ids.as_ref()
+    }
+    fn get_pad_id(&self) -> Option<i64> {
+        self.pad_token_id
+    }
+    fn is_encoder_decoder(&self) -> bool {
+        self.is_encoder_decoder
+    }
+    fn get_vocab_size(&self) -> i64 {
+        self.vocab_size
+    }
+    fn get_decoder_start_id(&self) -> Option<i64> {
+        self.decoder_start_id
+    }
+    fn get_max_positions_embeddings(&self) -> i64 {
+        self.max_position_embeddings
+    }
+
+    fn prepare_inputs_for_generation<'a>(
+        &self,
+        input_ids: Tensor,
+        _encoder_outputs: Option<&'a Tensor>,
+        past: Cache,
+        attention_mask: Tensor,
+    ) -> PreparedInput<'a> {
+        let position_ids = (attention_mask.totype(Kind::Int64).cumsum(-1, Kind::Int64) - 1)
+            .masked_fill(&attention_mask.eq(0), 1);
+
+        match past {
+            Cache::LlamaCache(past) => {
+                if past.is_some() {
+                    PreparedInput {
+                        prepared_input: Some(input_ids.select(1, -1).unsqueeze(-1)),
+                        prepared_attention_mask: Some(attention_mask),
+                        prepared_encoder_output: None,
+                        prepared_decoder_input: None,
+                        prepared_position_ids: Some(position_ids.select(1, -1).unsqueeze(-1)),
+                        prepared_past: Cache::LlamaCache(past),
+                    }
+                } else {
+                    PreparedInput {
+                        prepared_input: Some(input_ids),
+                        prepared_attention_mask: Some(attention_mask),
+                        prepared_encoder_output: None,
+                        prepared_decoder_input: None,
+                        prepared_position_ids: Some(position_ids),
+                        prepared_past: Cache::LlamaCache(None),
+                    }
+                }
+            }
+            Cache::None => PreparedInput {
+                prepared_input: Some(input_ids),
+                prepared_attention_mask: Some(attention_mask),
+                prepared_encoder_output: None,
+                prepared_decoder_input: None,
+                prepared_position_ids: Some(position_ids),
+                prepared_past: Cache::LlamaCache(None),
+            },
+            _ => panic!("Cache type incompatible with LLaMA"),
+        }
+    }
+
+    fn encode_prompt_text<S>(
+        &self,
+        prompt_text: &[S],
+        max_len: Option<i64>,
+        pad_token_id: Option<i64>,
+    ) -> Tensor
+    where
+        S: AsRef<str> + Sync,
+    {
+        // Encoding through the tokenizer prefixes the prompts with the beginning of sequence token
+        let tokens = self._get_tokenizer().encode_list(
+            prompt_text,
+            max_len
+                .map(|max_len| max_len as usize)
+                .unwrap_or(usize::MAX),
+            &TruncationStrategy::LongestFirst,
+            0,
+        );
+        let token_ids = tokens
+            .into_iter()
+            .map(|tokenized_input| tokenized_input.token_ids)
+            .collect::<Vec<Vec<i64>>>();
+
+        let max_len = token_ids.iter().map(|input| input.len()).max().unwrap();
+
+        let pad_token = match pad_token_id {
+            Some(value) => value,
+            None => self._get_tokenizer().get_unk_id(),
+        };
+
+        let token_ids = token_ids
+            .into_iter()
+            .map(|input| {
+                let mut temp = vec![pad_token; max_len - input.len()];
+                temp.extend(input);
+                temp
+            })
+            .map(|tokens| Tensor::of_slice(&tokens).to(self.get_var_store().device()))
+            .collect::<Vec<Tensor>>();
+        Tensor::stack(&token_ids, 0)
+    }
+
+    fn reorder_cache(
+        &self,
+        past: &mut Cache,
+        _encoder_outputs: Option<Tensor>,
+        beam_indices: &Tensor,
+    ) -> Option<Tensor> {
+        match past {
+            Cache::LlamaCache(cached_decoder_state) => match cached_decoder_state {
+                Some(old_cache) => {
+                    for layer_state in old_cache.iter_mut().flatten() {
+                        layer_state.reorder_cache(beam_indices)
+                    }
+                    None
+                }
+                None => None,
+            },
+            Cache::None => None,
+            _ => {
+                panic!("Invalid cache for LLaMA model");
+            }
+        }
+    }
+}
+
+impl LanguageGenerator<LlamaForCausalLM, SentencePieceVocab, LlamaTokenizer> for LlamaGenerator {}
@@ -0,0 +1,302 @@
+// Copyright 2023 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::RustBertError;
+use rust_tokenizers::tokenizer::{MultiThreadedTokenizer, Tokenizer};
+use rust_tokenizers::vocab::{SentencePieceBpeModel, SentencePieceVocab, Vocab};
+use rust_tokenizers::{Mask, Token, TokenIdsWithOffsets, TokenIdsWithSpecialTokens, TokenRef};
+use std::path::Path;
+
+pub(crate) const LLAMA_BOS_TOKEN: &str = "<s>";
+pub(crate) const LLAMA_EOS_TOKEN: &str = "</s>";
+const SPIECE_UNDERLINE: char = '\u{2581}';
+
+/// # LLaMA tokenizer
+/// SentencePiece BPE tokenizer following the conventions of the LLaMA `tokenizer.model`:
+/// - every sequence is prefixed with the beginning of sequence token (`<s>`)
+/// - the input text is not normalized, characters missing from the vocabulary (for example new lines)
+///   are decomposed into their UTF-8 bytes (`<0x0A>`) instead of being mapped to the unknown token
+/// - byte tokens are merged back into UTF-8 text when decoding
+pub struct LlamaTokenizer {
+    model: SentencePieceBpeModel,
+    vocab: SentencePieceVocab,
+    lower_case: bool,
+}
+
+impl LlamaTokenizer {
+    /// Create a new instance of a `LlamaTokenizer` from a SentencePiece BPE model file (`tokenizer.model`).
+    ///
+    /// # Parameters
+    /// - path (`&Path`): path to the SentencePiece model file
+    /// - lower_case (`bool`): flag indicating if the text should be lower-cased as part of the tokenization
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use rust_bert::llama::LlamaTokenizer;
+    /// let tokenizer = LlamaTokenizer::from_file("path/to/tokenizer.model", false).unwrap();
+    /// ```
+    pub fn from_file<P: AsRef<Path>>(
+        path: P,
+        lower_case: bool,
+    ) -> Result<LlamaTokenizer, RustBertError> {
+        let model = SentencePieceBpeModel::from_file(&path)?;
+        let vocab = SentencePieceVocab::from_file(path)?;
+        Ok(LlamaTokenizer::from_existing_vocab_and_model(
+            vocab, model, lower_case,
+        ))
+    }
+
+    /// Create a new instance of a `LlamaTokenizer` from an existing vocabulary and model
+    ///
+    /// # Parameters
+    /// - vocab (`SentencePieceVocab`): vocabulary
+    /// - model (`SentencePieceBpeModel`): SentencePiece BPE model
+    /// - lower_case (`bool`): flag indicating if the text should be lower-cased as part of the tokenization
+    pub fn from_existing_vocab_and_model(
+        mut vocab: SentencePieceVocab,
+        model: SentencePieceBpeModel,
+        lower_case: bool,
+    ) -> LlamaTokenizer {
+        // The SentencePiece model does not flag the beginning and end of sequence tokens as special:
+        // register them so that they are skipped when decoding.
+        for special_token in [LLAMA_BOS_TOKEN, LLAMA_EOS_TOKEN].iter() {
+            if let Some(&token_id) = vocab.values().get(*special_token) {
+                vocab
+                    .special_values_mut()
+                    .insert(special_token.to_string(), token_id);
+                vocab
+                    .special_indices_mut()
+                    .insert(token_id, special_token.to_string());
+            }
+        }
+        LlamaTokenizer {
+            model,
+            vocab,
+            lower_case,
+        }
+    }
+
+    /// Splits a token missing from the vocabulary into its UTF-8 byte tokens (`<0xNN>`).
+    /// The token is returned unchanged (and will be mapped to the unknown token) if the vocabulary
+    /// does not contain the byte tokens.
+    fn byte_fallback(&self, token: Token) -> Vec<Token> {
+        if token.mask == Mask::Special || self.vocab.values().contains_key(&token.text) {
+            return vec![token];
+        }
+        let byte_tokens = token
+            .text
+            .bytes()
+            .map(byte_to_token)
+            .collect::<Vec<String>>();
+        if !byte_tokens
+            .iter()
+            .all(|byte_token| self.vocab.values().contains_key(byte_token))
+        {
+            return vec![token];
+        }
+        byte_tokens
+            .into_iter()
+            .map(|text| Token {
+                text,
+                offset: token.offset,
+                reference_offsets: token.reference_offsets.clone(),
+                mask: token.mask,
+            })
+            .collect()
+    }
+}
+
+fn byte_to_token(byte: u8) -> String {
+    format!("<0x{byte:02X}>")
+}
+
+fn token_to_byte(token: &str) -> Option<u8> {
+    if token.len() == 6 && token.starts_with("<0x") && token.ends_with('>') {
+        u8::from_str_radix(&token[3..5], 16).ok()
+    } else {
+        None
+    }
+}
+
+impl Tokenizer<SentencePieceVocab> for LlamaTokenizer {
+    fn vocab(&self) -> &SentencePieceVocab {
+        &self.vocab
+    }
+
+    fn vocab_mut(&mut self) -> &mut SentencePieceVocab {
+        &mut self.vocab
+    }
+
+    fn tokenize_to_tokens(&self, text: TokenRef) -> Vec<Token> {
+        let mut token = text.to_owned();
+        if self.lower_case {
+            let mut text = String::with_capacity(token.text.len());
+            let mut reference_offsets = Vec::with_capacity(token.reference_offsets.len());
+            for (character, position) in token.text.chars().zip(token.reference_offsets.iter()) {
+                for lower_cased in character.to_lowercase() {
+                    text.push(lower_cased);
+                    reference_offsets.push(*position);
+                }
+            }
+            token.text = text;
+            token.reference_offsets = reference_offsets;
+        }
+        token.text = token.text.replace(' ', &SPIECE_UNDERLINE.to_string());
+        token.text.insert(0, SPIECE_UNDERLINE);
+        let first_offset = token.reference_offsets.first().copied().unwrap_or(0);
+        token.reference_offsets.insert(0, first_offset);
+
+        self.model
+            .tokenize_to_tokens(token.as_ref())
+            .into_iter()
+            .flat_map(|sub_token| self.byte_fallback(sub_token))
+            .collect()
+    }
+
+    fn convert_tokens_to_string(&self, tokens: Vec<String>) -> String {
+        let mut output = String::new();
+        let mut bytes = Vec::new();
+        for token in tokens {
+            match token_to_byte(&token) {
+                Some(byte) => bytes.push(byte),
+                None => {
+                    output.push_str(&String::from_utf8_lossy(&bytes));
+                    bytes.clear();
+                    output.push_str(&token.replace(SPIECE_UNDERLINE, " "));
+                }
+            }
+        }
+        output.push_str(&String::from_utf8_lossy(&bytes));
+        // Remove the whitespace prefix added to the input text
+        match output.strip_prefix(' ') {
+            Some(stripped) => stripped.to_string(),
+            None => output,
+        }
+    }
+
+    fn build_input_with_special_tokens(
+        &self,
+        tokens_ids_with_offsets_1: TokenIdsWithOffsets,
+        tokens_ids_with_offsets_2: Option<TokenIdsWithOffsets>,
+    ) -> TokenIdsWithSpecialTokens {
+        let bos_token_id = self.vocab.token_to_id(LLAMA_BOS_TOKEN);
+        let mut output = TokenIdsWithSpecialTokens {
+            token_ids: vec![],
+            segment_ids: vec![],
+            special_tokens_mask: vec![],
+            token_offsets: vec![],
+            reference_offsets: vec![],
+            mask: vec![],
+        };
+        for (segment_id, tokens_ids_with_offsets) in std::iter::once(tokens_ids_with_offsets_1)
+            .chain(tokens_ids_with_offsets_2)
+            .enumerate()
+        {
+            let length = tokens_ids_with_offsets.ids.len();
+            output.token_ids.push(bos_token_id);
+            output.token_ids.extend(tokens_ids_with_offsets.ids);
+            output
+                .segment_ids
+                .extend(vec![segment_id as i8; length + 1]);
+            output.special_tokens_mask.push(1);
+            output.special_tokens_mask.extend(vec![0; length]);
+            output.token_offsets.push(None);
+            output.token_offsets.extend(tokens_ids_with_offsets.offsets);
+            output.reference_offsets.push(vec![]);
+            output
+                .reference_offsets
+                .extend(tokens_ids_with_offsets.reference_offsets);
+            output.mask.push(Mask::Special);
+            output.mask.extend(tokens_ids_with_offsets.masks);
+        }
+        output
+    }
+}
+
+impl MultiThreadedTokenizer<SentencePieceVocab> for LlamaTokenizer {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rust_tokenizers::tokenizer::TruncationStrategy;
+    use rust_tokenizers::Offset;
+    use std::io::Write;
+
+    /// Serializes a SentencePiece model protobuf holding the provided pieces (ranked by position).
+    fn sentence_piece_model(pieces: &[&str]) -> Vec<u8> {
+        let mut model = Vec::new();
+        for piece in pieces {
+            let mut sentence_piece = vec![0x0A, piece.len() as u8];
+            sentence_piece.extend(piece.as_bytes());
+            model.push(0x0A);
+            model.push(sentence_piece.len() as u8);
+            model.extend(sentence_piece);
+        }
+        model
+    }
+
+    fn tiny_llama_tokenizer() -> anyhow::Result<LlamaTokenizer> {
+        let mut pieces = vec!["<unk>", "<s>", "</s>"];
+        let byte_tokens = (0..=255u8).map(byte_to_token).collect::<Vec<String>>();
+        pieces.extend(byte_tokens.iter().map(String::as_str));
+        pieces.extend(["▁H", "▁Hi", "i", "H", "▁"].iter());
+        let mut model_file = tempfile::NamedTempFile::new()?;
+        model_file.write_all(&sentence_piece_model(&pieces))?;
+        Ok(LlamaTokenizer::from_file(model_file.path(), false)?)
+    }
+
+    #[test]
+    fn test_bos_token_prepended() -> anyhow::Result<()> {
+        let tokenizer = tiny_llama_tokenizer()?;
+        let bos_token_id = Tokenizer::vocab(&tokenizer).token_to_id(LLAMA_BOS_TOKEN);
+        let hi_token_id = Tokenizer::vocab(&tokenizer).token_to_id("▁Hi");
+
+        let encoded = tokenizer.encode("Hi", None, 128, &TruncationStrategy::LongestFirst, 0);
+        assert_eq!(encoded.token_ids, vec![bos_token_id, hi_token_id]);
+        assert_eq!(encoded.special_tokens_mask, vec![1, 0]);
+        assert_eq!(encoded.token_offsets[0], None);
+        assert_eq!(encoded.token_offsets[1], Some(Offset::new(0, 2)));
+
+        let encoded = tokenizer.encode("Hi", Some("Hi"), 128, &TruncationStrategy::LongestFirst, 0);
+        assert_eq!(
+            encoded.token_ids,
+            vec![bos_token_id, hi_token_id, bos_token_id, hi_token_id]
+        );
+        assert_eq!(encoded.segment_ids, vec![0, 0, 1, 1]);
+
+        let truncated = tokenizer.encode("Hi Hi", None, 2, &TruncationStrategy::LongestFirst, 0);
+        assert_eq!(truncated.token_ids, vec![bos_token_id, hi_token_id]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_byte_fallback() -> anyhow::Result<()> {
+        let tokenizer = tiny_llama_tokenizer()?;
+        assert_eq!(
+            tokenizer.tokenize("Hi\nHi"),
+            vec!["▁Hi", "<0x0A>", "H", "i"]
+        );
+        assert_eq!(tokenizer.tokenize("Hé"), vec!["▁H", "<0xC3>", "<0xA9>"]);
+
+        let token_ids = tokenizer.convert_tokens_to_ids(&tokenizer.tokenize("Hi\nHé"));
+        assert!(!token_ids.contains(&Tokenizer::vocab(&tokenizer).token_to_id("<unk>")));
+        assert_eq!(tokenizer.decode(&token_ids, true, false), "Hi\nHé");
+
+        let encoded = tokenizer.encode("Hi\nHi", None, 128, &TruncationStrategy::LongestFirst, 0);
+        assert_eq!(tokenizer.decode(&encoded.token_ids, true, false), "Hi\nHi");
+        assert_eq!(
+            tokenizer.decode(&encoded.token_ids, false, false),
+            "<s> Hi\nHi"
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,70 @@
+//! # LLaMA
+//!
+//! Implementation of the LLaMA language model ([LLaMA: Open and Efficient Foundation Language Models](https://arxiv.org/abs/2302.13971) Touvron, Lavril, Izacard, Martinet, Lachaux, Lacroix, Rozière, Goyal, Hambro, Azhar, Rodriguez, Joulin, Grave, Lample, 2023).
+//! The base model is implemented in the `llama_model::LlamaModel` struct. A causal language modeling head is implemented in `llama_model::LlamaForCausalLM`.
+//! The architecture uses rotary position embeddings, RMS normalization, SwiGLU feed-forward layers and
+//! grouped-query attention (`num_key_value_heads`), and is shared by most recent open decoder models following the LLaMA design.
+//!
+//! # Model set-up and pre-trained weights loading
+//!
+//! All models expect the following resources:
+//! - Configuration file expected to have a structure following the [Transformers library](https://github.com/huggingface/transformers)
+//! - Model weights are expected to have a structure and parameter names following the [Transformers library](https://github.com/huggingface/transformers). A conversion using the Python utility scripts is required to convert the `.bin` weights to the `.ot` format.
+//! - `LlamaTokenizer` using a SentencePiece BPE model file (`tokenizer.model`)
+//!
+//! No pretrained checkpoints are registered as remote resources: the weights of the model you want to use have to be
+//! converted and loaded from local resources. Prompts are prefixed with the beginning of sequence token (`<s>`)
+//! and characters missing from the vocabulary fall back to their UTF-8 byte tokens (e.g. `<0x0A>` for new lines).
+//!
+//! ```no_run
+//! use rust_bert::pipelines::common::ModelType;
+//! use rust_bert::pipelines::text_generation::{TextGenerationConfig, TextGenerationModel};
+//! use rust_bert::resources::LocalResource;
+//! use std::path::PathBuf;
+//! use tch::Device;
+//!
+//! fn main() -> anyhow::Result<()> {
+//!     let config_resource = Box::new(LocalResource {
+//!         local_path: PathBuf::from("path/to/config.json"),
+//!     });
+//!     let vocab_resource = Box::new(LocalResource {
+//!         local_path: PathBuf::from("path/to/tokenizer.model"),
+//!     });
+//!     let model_resource = Box::new(LocalResource {
+//!         local_path: PathBuf::from("path/to/rust_model.ot"),
+//!     });
+//!
+//!     let text_generation_config = TextGenerationConfig {
+//!         model_type: ModelType::Llama,
+//!         model_resource,
+//!         config_resource,
+//!         vocab_resource,
+//!         merges_resource: None,
+//!         max_length: Some(64),
+//!         do_sample: false,
+//!         device: Device::cuda_if_available(),
+//!         ..Default::default()
+//!     };
+//!     let model = TextGenerationModel::new(text_generation_config)?;
+//!
+//!     let output = model.generate(&["<s>The capital of France is"], None);
+//!
+//!     for sentence in output {
+//!         println!("{}", sentence);
+//!     }
+//!
+//!     Ok(())
+//! }
+//! ```
+
+mod attention;
+mod decoder;
+mod llama_model;
+mod llama_tokenizer;
+
+pub use attention::LayerState;
+pub use llama_model::{
+    LlamaConfig, LlamaForCausalLM, LlamaGenerator, LlamaModel, LlamaModelLMOutput, LlamaModelOutput,
+};
+pub use llama_tokenizer::LlamaTokenizer;
+pub(crate) use llama_tokenizer::{LLAMA_BOS_TOKEN, LLAMA_EOS_TOKEN};
@@ -10,9 +10,12 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use crate::common::activations::{TensorFunction, _tanh};
+use crate::common::activations::{_tanh, TensorFunction};
 use crate::common::dropout::Dropout;
 use crate::common::embeddings::get_shape_and_device_from_ids_embeddings_pair;
+use crate::common::loss::{
+    cross_entropy_loss, question_answering_loss, sequence_classification_loss, ProblemType,
+};
 use crate::longformer::embeddings::LongformerEmbeddings;
 use crate::longformer::encoder::LongformerEncoder;
 use crate::{Activation, Config, RustBertError};
@@ -119,6 +122,8 @@ pub struct LongformerConfig {
     pub is_decoder: Option<bool>,
     pub id2label: Option<HashMap<i64, String>>,
     pub label2id: Option<HashMap<String, i64>>,
+    /// Type of problem defining the loss of the sequence classification head (default: inferred from the labels)
+    pub problem_type: Option<ProblemType>,
 }
 
 impl Config for LongformerConfig {}
@@ -147,6 +152,7 @@ impl Default for LongformerConfig {
             is_decoder: None,
             id2label: None,
             label2id: None,
+            problem_type: None,
         }
     }
 }
@@ -707,12 +713,14 @@ impl LongformerForMaskedLM {
     /// * `token_type_ids` - Optional segment id of shape (*batch size*, *sequence_length*). Convention is value of 0 for the first sentence (incl. *SEP*) and 1 for the second sentence. If None set to 0.
     /// * `position_ids` - Optional position ids of shape (*batch size*, *sequence_length*). If None, will be incremented from 0.
     /// * `input_embeds` - Optional pre-computed input embeddings of shape (*batch size*, *sequence_length*, *hidden_size*). If None, input ids must be provided (see `input_ids`)
+    /// * `labels` - Optional target token ids of shape (*batch size*, *sequence_length*) used to compute the masked language modeling loss. Positions with a label of -100 (e.g. non-masked tokens) are ignored.
     /// * `train` - boolean flag to turn on/off the dropout layers in the model. Should be set to false for inference.
     ///
     /// # Returns
     ///
     /// * `LongformerMaskedLMOutput` containing:
     ///   - `prediction_scores` - `Tensor` of shape (*batch size*, *sequence_length*, *vocab_size*)
+    ///   - `loss` - `Option<Tensor>` scalar loss, computed if labels are provided
     ///   - `all_hidden_states` - `Option<Vec<Tensor>>` of length *num_hidden_layers* with shape (*batch size*, *sequence_length*, *hidden_size*)
     ///   - `all_attentions` - `Option<Vec<Tensor>>` of length *num_hidden_layers* with shape (*batch size*, *num_heads*, *sequence_length*, * attention_window_size*, *x + attention_window_size + 1*) where x is the number of tokens with global attention
     ///   - `all_global_attentions` - `Option<Vec<Tensor>>` of length *num_hidden_layers* with shape (*batch size*, *num_heads*, *sequence_length*, *attention_window_size*, *x*)  where x is the number of tokens with global attention
@@ -746,6 +754,7 @@ impl LongformerForMaskedLM {
     ///             None,
     ///             None,
     ///             None,
+    ///             None,
     ///             false,
     ///         )
     ///         .unwrap()
@@ -759,6 +768,7 @@ impl LongformerForMaskedLM {
         token_type_ids: Option<&Tensor>,
         position_ids: Option<&Tensor>,
         input_embeds: Option<&Tensor>,
+        labels: Option<&Tensor>,
         train: bool,
     ) -> Result<LongformerMaskedLMOutput, RustBertError> {
         let longformer_outputs = self.longformer.forward_t(
@@ -775,7 +785,9 @@ impl LongformerForMaskedLM {
             .lm_head
             .forward_t(&longformer_outputs.hidden_state, train);
 
+        let loss = labels.map(|labels| cross_entropy_loss(&prediction_scores, labels));
         Ok(LongformerMaskedLMOutput {
+            loss,
             prediction_scores,
             all_hidden_states: longformer_outputs.all_hidden_states,
             all_attentions: longformer_outputs.all_attentions,
@@ -850,6 +862,7 @@ impl LongformerClassificationHead {
 pub struct LongformerForSequenceClassification {
     longformer: LongformerModel,
     classifier: LongformerClassificationHead,
+    problem_type: Option<ProblemType>,
 }
 
 impl LongformerForSequenceClassification {
@@ -889,6 +902,7 @@ impl LongformerForSequenceClassification {
         Ok(LongformerForSequenceClassification {
             longformer,
             classifier,
+            problem_type: config.problem_type,
         })
     }
 
@@ -902,12 +916,14 @@ impl LongformerForSequenceClassification {
     /// * `token_type_ids` - Optional segment id of shape (*batch size*, *sequence_length*). Convention is value of 0 for the first sentence (incl. *SEP*) and 1 for the second sentence. If None set to 0.
     /// * `position_ids` - Optional position ids of shape (*batch size*, *sequence_length*). If None, will be incremented from 0.
     /// * `input_embeds` - Optional pre-computed input embeddings of shape (*batch size*, *sequence_length*, *hidden_size*). If None, input ids must be provided (see `input_ids`)
+    /// * `labels` - Optional labels of shape (*batch size*) used to compute the loss: class indices for single-label classification or target values for regression. Multi-label classification uses floating point targets of shape (*batch size*, *num_labels*). The loss follows the `problem_type` of the configuration, inferred from the labels if not set.
     /// * `train` - boolean flag to turn on/off the dropout layers in the model. Should be set to false for inference.
     ///
     /// # Returns
     ///
     /// * `LongformerSequenceClassificationOutput` containing:
     ///   - `logits` - `Tensor` of shape (*batch size*, *sequence_length*, *num_classes*)
+    ///   - `loss` - `Option<Tensor>` scalar loss, computed if labels are provided
     ///   - `all_hidden_states` - `Option<Vec<Tensor>>` of length *num_hidden_layers* with shape (*batch size*, *sequence_length*, *hidden_size*)
     ///   - `all_attentions` - `Option<Vec<Tensor>>` of length *num_hidden_layers* with shape (*batch size*, *num_heads*, *sequence_length*, * attention_window_size*, *x + attention_window_size + 1*) where x is the number of tokens with global attention
     ///   - `all_global_attentions` - `Option<Vec<Tensor>>` of length *num_hidden_layers* with shape (*batch size*, *num_heads*, *sequence_length*, *attention_window_size*, *x*)  where x is the number of tokens with global attention
@@ -941,6 +957,7 @@ impl LongformerForSequenceClassification {
     ///             None,
     ///             None,
     ///             None,
+    ///             None,
     ///             false,
     ///         )
     ///         .unwrap()
@@ -954,6 +971,7 @@ impl LongformerForSequenceClassification {
         token_type_ids: Option<&Tensor>,
         position_ids: Option<&Tensor>,
         input_embeds: Option<&Tensor>,
+        labels: Option<&Tensor>,
         train: bool,
     ) -> Result<LongformerSequenceClassificationOutput, RustBertError> {
         let calc_global_attention_mask = if global_attention_mask.is_none() {
@@ -1001,7 +1019,10 @@ impl LongformerForSequenceClassification {
         let logits = self
             .classifier
             .forward_t(&base_model_output.hidden_state, train);
+        let loss =
+            labels.map(|labels| sequence_classification_loss(&logits, labels, self.problem_type));
         Ok(LongformerSequenceClassificationOutput {
+            loss,
             logits,
             all_hidden_states: base_model_output.all_hidden_states,
             all_attentions: base_model_output.all_attentions,
@@ -1072,6 +1093,8 @@ impl LongformerForQuestionAnswering {
     /// * `token_type_ids` - Optional segment id of shape (*batch size*, *sequence_length*). Convention is value of 0 for the first sentence (incl. *SEP*) and 1 for the second sentence. If None set to 0.
     /// * `position_ids` - Optional position ids of shape (*batch size*, *sequence_length*). If None, will be incremented from 0.
     /// * `input_embeds` - Optional pre-computed input embeddings of shape (*batch size*, *sequence_length*, *hidden_size*). If None, input ids must be provided (see `input_ids`)
+    /// * `start_positions` - Optional answer start positions of shape (*batch size*) used to compute the loss. Positions outside of the sequence are ignored.
+    /// * `end_positions` - Optional answer end positions of shape (*batch size*) used to compute the loss. Positions outside of the sequence are ignored.
     /// * `train` - boolean flag to turn on/off the dropout layers in the model. Should be set to false for inference.
     ///
     /// # Returns
@@ -1079,6 +1102,7 @@ impl LongformerForQuestionAnswering {
     /// * `LongformerForQuestionAnsweringOutput` containing:
     ///   - `start_logits` - `Tensor` of shape (*batch size*, *sequence_length*) containing the logits for start of the answer
     ///   - `end_logits` - `Tensor` of shape (*batch size*, *sequence_length*) containing the logits for end of the answer
+    ///   - `loss` - `Option<Tensor>` scalar loss, computed if the start and end positions are provided
     ///   - `all_hidden_states` - `Option<Vec<Tensor>>` of length *num_hidden_layers* with shape (*batch size*, *sequence_length*, *hidden_size*)
     ///   - `all_attentions` - `Option<Vec<Tensor>>` of length *num_hidden_layers* with shape (*batch size*, *num_heads*, *sequence_length*, * attention_window_size*, *x + attention_window_size + 1*) where x is the number of tokens with global attention
     ///   - `all_global_attentions` - `Option<Vec<Tensor>>` of length *num_hidden_layers* with shape (*batch size*, *num_heads*, *sequence_length*, *attention_window_size*, *x*)  where x is the number of tokens with global attention
@@ -1112,6 +1136,8 @@ impl LongformerForQuestionAnswering {
     ///             None,
     ///             None,
     ///             None,
+    ///             None,
+    ///             None,
     ///             false,
     ///         )
     ///         .unwrap()
@@ -1125,6 +1151,8 @@ impl LongformerForQuestionAnswering {
         token_type_ids: Option<&Tensor>,
         position_ids: Option<&Tensor>,
         input_embeds: Option<&Tensor>,
+        start_positions: Option<&Tensor>,
+        end_positions: Option<&Tensor>,
         train: bool,
     ) -> Result<LongformerQuestionAnsweringOutput, RustBertError> {
         let calc_global_attention_mask = if global_attention_mask.is_none() {
@@ -1165,7 +1193,13 @@ impl LongformerForQuestionAnswering {
         let start_logits = start_logits.squeeze_dim(-1);
         let end_logits = end_logits.squeeze_dim(-1);
 
+        let loss = start_positions
+            .zip(end_positions)
+            .map(|(start_positions, end_positions)| {
+                question_answering_loss(&start_logits, &end_logits, start_positions, end_positions)
+            });
         Ok(LongformerQuestionAnsweringOutput {
+            loss,
             start_logits,
             end_logits,
             all_hidden_states: base_model_output.all_hidden_states,
@@ -1254,12 +1288,14 @@ impl LongformerForTokenClassification {
     /// * `token_type_ids` - Optional segment id of shape (*batch size*, *sequence_length*). Convention is value of 0 for the first sentence (incl. *SEP*) and 1 for the second sentence. If None set to 0.
     /// * `position_ids` - Optional position ids of shape (*batch size*, *sequence_length*). If None, will be incremented from 0.
     /// * `input_embeds` - Optional pre-computed input embeddings of shape (*batch size*, *sequence_length*, *hidden_size*). If None, input ids must be provided (see `input_ids`)
+    /// * `labels` - Optional labels of shape (*batch size*, *sequence_length*) used to compute the cross-entropy loss. Positions with a label of -100 are ignored.
     /// * `train` - boolean flag to turn on/off the dropout layers in the model. Should be set to false for inference.
     ///
     /// # Returns
     ///
     /// * `LongformerTokenClassificationOutput` containing:
     ///   - `logits` - `Tensor` of shape (*batch size*, *sequence_length*, *num_labels*) containing the logits for each of the input tokens and classes
+    ///   - `loss` - `Option<Tensor>` scalar loss, computed if labels are provided
     ///   - `all_hidden_states` - `Option<Vec<Tensor>>` of length *num_hidden_layers* with shape (*batch size*, *sequence_length*, *hidden_size*)
     ///   - `all_attentions` - `Option<Vec<Tensor>>` of length *num_hidden_layers* with shape (*batch size*, *num_heads*, *sequence_length*, * attention_window_size*, *x + attention_window_size + 1*) where x is the number of tokens with global attention
     ///   - `all_global_attentions` - `Option<Vec<Tensor>>` of length *num_hidden_layers* with shape (*batch size*, *num_heads*, *sequence_length*, *attention_window_size*, *x*)  where x is the number of tokens with global attention
@@ -1293,6 +1329,7 @@ impl LongformerForTokenClassification {
     ///             None,
     ///             None,
     ///             None,
+    ///             None,
     ///             false,
     ///         )
     ///         .unwrap()
@@ -1306,6 +1343,7 @@ impl LongformerForTokenClassification {
         token_type_ids: Option<&Tensor>,
         position_ids: Option<&Tensor>,
         input_embeds: Option<&Tensor>,
+        labels: Option<&Tensor>,
         train: bool,
     ) -> Result<LongformerTokenClassificationOutput, RustBertError> {
         let base_model_output = self.longformer.forward_t(
@@ -1323,7 +1361,9 @@ impl LongformerForTokenClassification {
             .apply_t(&self.dropout, train)
             .apply(&self.classifier);
 
+        let loss = labels.map(|labels| cross_entropy_loss(&logits, labels));
         Ok(LongformerTokenClassificationOutput {
+            loss,
             logits,
             all_hidden_states: base_model_output.all_hidden_states,
             all_attentions: base_model_output.all_attentions,
@@ -1523,6 +1563,7 @@ impl LongformerForMultipleChoice {
             .view((-1, num_choices));
 
         Ok(LongformerSequenceClassificationOutput {
+            loss: None,
             logits,
             all_hidden_states: base_model_output.all_hidden_states,
             all_attentions: base_model_output.all_attentions,
@@ -1531,6 +1572,10 @@ impl LongformerForMultipleChoice {
     }
 }
 
+/// # Longformer for sentence embeddings
+/// Transformer usable in [`SentenceEmbeddingsModel`](crate::pipelines::sentence_embeddings::SentenceEmbeddingsModel).
+pub type LongformerForSentenceEmbeddings = LongformerModel;
+
 /// Container for the Longformer model output.
 pub struct LongformerModelOutput {
     /// Last hidden states from the model
@@ -1555,6 +1600,8 @@ pub struct LongformerMaskedLMOutput {
     pub all_attentions: Option<Vec<Tensor>>,
     /// Global attention weights for all intermediate layers
     pub all_global_attentions: Option<Vec<Tensor>>,
+    /// Loss computed from the labels, if provided
+    pub loss: Option<Tensor>,
 }
 
 /// Container for the Longformer sequence classification model output.
@@ -1567,6 +1614,8 @@ pub struct LongformerSequenceClassificationOutput {
     pub all_attentions: Option<Vec<Tensor>>,
     /// Global attention weights for all intermediate layers
     pub all_global_attentions: Option<Vec<Tensor>>,
+    /// Loss computed from the labels, if provided
+    pub loss: Option<Tensor>,
 }
 
 /// Container for the Longformer token classification model output.
@@ -1579,6 +1628,8 @@ pub struct LongformerTokenClassificationOutput {
     pub all_attentions: Option<Vec<Tensor>>,
     /// Global attention weights for all intermediate layers
     pub all_global_attentions: Option<Vec<Tensor>>,
+    /// Loss computed from the labels, if provided
+    pub loss: Option<Tensor>,
 }
 
 /// Container for the Longformer question answering model output.
@@ -1593,4 +1644,6 @@ pub struct LongformerQuestionAnsweringOutput {
     pub all_attentions: Option<Vec<Tensor>>,
     /// Global attention weights for all intermediate layers
     pub all_global_attentions: Option<Vec<Tensor>>,
+    /// Loss computed from the labels, if provided
+    pub loss: Option<Tensor>,
 }
@@ -80,7 +80,7 @@ mod longformer_model;
 
 pub use longformer_model::{
     LongformerConfig, LongformerConfigResources, LongformerForMaskedLM,
-    LongformerForMultipleChoice, LongformerForQuestionAnswering,
+    LongformerForMultipleChoice, LongformerForQuestionAnswering, LongformerForSentenceEmbeddings,
     LongformerForSequenceClassification, LongformerForTokenClassification,
     LongformerMergesResources, LongformerModel, LongformerModelResources,
     LongformerTokenClassificationOutput, LongformerVocabResources,
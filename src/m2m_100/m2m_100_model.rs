@@ -308,6 +308,7 @@ impl M2M100Model {
         );
 
         M2M100ModelOutput {
+            loss: None,
             decoder_output: decoder_output.hidden_state,
             encoder_hidden_state: calc_hidden_states,
             cache: decoder_output.next_decoder_cache,
@@ -557,8 +558,13 @@ impl LMHeadModel for M2M100ForConditionalGeneration {
         Ok(LMModelOutput {
             lm_logits,
             cache: Cache::BARTCache(base_model_output.cache),
+            loss: None,
         })
     }
+
+    fn predicts_next_token(&self) -> bool {
+        false
+    }
 }
 
 /// # Language generation model based on the M2M100 architecture
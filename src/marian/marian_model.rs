@@ -777,8 +777,13 @@ impl LMHeadModel for MarianForConditionalGeneration {
         Ok(LMModelOutput {
             lm_logits,
             cache: Cache::BARTCache(base_model_output.cache),
+            loss: None,
         })
     }
+
+    fn predicts_next_token(&self) -> bool {
+        false
+    }
 }
 
 /// # Language generation model based on the Marian architecture for machine translation
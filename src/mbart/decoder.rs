@@ -53,6 +53,7 @@ impl MBartDecoderLayer {
             false,
             true,
             output_attention,
+            None,
         );
         let encoder_attention = MBartAttention::new(
             p / "encoder_attn",
@@ -62,6 +63,7 @@ impl MBartDecoderLayer {
             true,
             true,
             output_attention,
+            None,
         );
         let self_attention_layer_norm = nn::layer_norm(
             p / "self_attn_layer_norm",
@@ -51,6 +51,7 @@ impl MBartEncoderLayer {
             false,
             false,
             output_attention,
+            None,
         );
         let self_attention_layer_norm = nn::layer_norm(
             p / "self_attn_layer_norm",
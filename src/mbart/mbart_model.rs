@@ -12,6 +12,7 @@
 
 use crate::bart::BartModelOutput;
 use crate::common::dropout::Dropout;
+use crate::common::loss::{sequence_classification_loss, ProblemType};
 use crate::mbart::decoder::MBartDecoder;
 use crate::mbart::encoder::MBartEncoder;
 use crate::mbart::LayerState;
@@ -106,6 +107,8 @@ pub struct MBartConfig {
     pub decoder_start_token_id: Option<i64>,
     pub id2label: Option<HashMap<i64, String>>,
     pub label2id: Option<HashMap<String, i64>>,
+    /// Type of problem defining the loss of the sequence classification head (default: inferred from the labels)
+    pub problem_type: Option<ProblemType>,
     pub init_std: f64,
     pub min_length: Option<i64>,
     pub no_repeat_ngram_size: Option<i64>,
@@ -145,6 +148,7 @@ impl Default for MBartConfig {
             decoder_start_token_id: None,
             id2label: None,
             label2id: None,
+            problem_type: None,
             init_std: 0.02,
             min_length: None,
             no_repeat_ngram_size: None,
@@ -402,6 +406,7 @@ impl MBartModel {
         );
 
         MBartModelOutput {
+            loss: None,
             decoder_output: decoder_output.hidden_state,
             encoder_hidden_state: calc_hidden_states,
             cache: decoder_output.next_decoder_cache,
@@ -572,6 +577,7 @@ pub struct MBartForSequenceClassification {
     base_model: MBartModel,
     classification_head: MBartClassificationHead,
     eos_token_id: i64,
+    problem_type: Option<ProblemType>,
 }
 
 impl MBartForSequenceClassification {
@@ -613,6 +619,7 @@ impl MBartForSequenceClassification {
             base_model,
             classification_head,
             eos_token_id,
+            problem_type: config.problem_type,
         })
     }
 
@@ -626,12 +633,14 @@ impl MBartForSequenceClassification {
     /// These correspond to the encoder last hidden state and optional hidden states/attention weights for encoder layers. When provided, the encoder hidden state will not be recalculated. Useful for generation tasks.
     /// * `decoder_input_ids` - Optional input tensor of shape (*batch size*, *target_sequence_length*). Must be provided when running in generation mode (e.g. initialized with a BOS token)
     /// * `decoder_attention_mask` - Optional attention mask of shape (*batch size*, *target_sequence_length*) for the decoder positions. Positions with a mask with value 0 will be masked.
+    /// * `labels` - Optional labels of shape (*batch size*) used to compute the loss: class indices for single-label classification or target values for regression. Multi-label classification uses floating point targets of shape (*batch size*, *num_labels*). The loss follows the `problem_type` of the configuration, inferred from the labels if not set.
     /// * `train` - boolean flag to turn on/off the dropout layers in the model. Should be set to false for inference.
     ///
     /// # Returns
     ///
     /// * `MBartModelOutput` containing:
     ///   - `decoder_output` - `Tensor` of shape (*batch size*, *num_classes*) representing the activations for each class and batch item
+    ///   - `loss` - `Option<Tensor>` scalar loss, computed if labels are provided
     ///   - `encoder_hidden_states` - `Option<Tensor>` of shape (*batch size*, *source_sequence_length*, *hidden_size*) representing the activations of the last encoder hidden state if it was not provided, otherwise None.
     ///   - `cache` - `(Option<Tensor>, Option<Vec<&LayerState, &LayerState>>)` of length *n_layer* containing the encoder padding mask and past keys and values for both the self attention and the encoder cross attention of each layer of the decoder.
     ///   - `all_encoder_hidden_states` - `Option<Vec<Tensor>>` of length *num_encoder_layers* with shape (*batch size*, *source_sequence_length*, *hidden_size*)
@@ -666,6 +675,7 @@ impl MBartForSequenceClassification {
     ///                    None,
     ///                    Some(&target_tensor),
     ///                    Some(&decoder_attention_mask),
+    ///                    None,
     ///                    false)
     ///    });
     /// ```
@@ -676,6 +686,7 @@ impl MBartForSequenceClassification {
         encoder_output: Option<&Tensor>,
         decoder_input_ids: Option<&Tensor>,
         decoder_attention_mask: Option<&Tensor>,
+        labels: Option<&Tensor>,
         train: bool,
     ) -> MBartModelOutput {
         let base_model_output = self.base_model.forward_t(
@@ -705,7 +716,10 @@ impl MBartForSequenceClassification {
         let logits = self
             .classification_head
             .forward_t(&sentence_representation, train);
+        let loss =
+            labels.map(|labels| sequence_classification_loss(&logits, labels, self.problem_type));
         MBartModelOutput {
+            loss,
             decoder_output: logits,
             encoder_hidden_state: base_model_output.encoder_hidden_state,
             cache: None,
@@ -818,8 +832,13 @@ impl LMHeadModel for MBartForConditionalGeneration {
         Ok(LMModelOutput {
             lm_logits,
             cache: Cache::BARTCache(base_model_output.cache),
+            loss: None,
         })
     }
+
+    fn predicts_next_token(&self) -> bool {
+        false
+    }
 }
 
 /// Container holding a MBART model output
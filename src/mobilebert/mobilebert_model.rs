@@ -13,6 +13,9 @@
 use crate::common::activations::{Activation, TensorFunction};
 use crate::common::dropout::Dropout;
 use crate::common::embeddings::get_shape_and_device_from_ids_embeddings_pair;
+use crate::common::loss::{
+    cross_entropy_loss, question_answering_loss, sequence_classification_loss, ProblemType,
+};
 use crate::mobilebert::embeddings::MobileBertEmbeddings;
 use crate::mobilebert::encoder::{MobileBertEncoder, MobileBertPooler};
 use crate::{Config, RustBertError};
@@ -163,7 +166,7 @@ impl NormalizationLayer {
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 /// # MobileBERT model configuration
 /// Defines the MobileBERT model architecture (e.g. number of layers, hidden layer size, label mapping...)
 pub struct MobileBertConfig {
@@ -194,6 +197,8 @@ pub struct MobileBertConfig {
     pub is_decoder: Option<bool>,
     pub id2label: Option<HashMap<i64, String>>,
     pub label2id: Option<HashMap<String, i64>>,
+    /// Type of problem defining the loss of the sequence classification head (default: inferred from the labels)
+    pub problem_type: Option<ProblemType>,
 }
 
 impl Config for MobileBertConfig {}
@@ -228,6 +233,7 @@ impl Default for MobileBertConfig {
             is_decoder: None,
             id2label: None,
             label2id: None,
+            problem_type: None,
         }
     }
 }
@@ -585,12 +591,14 @@ impl MobileBertForMaskedLM {
     /// * `position_ids` - Optional position ids of shape (*batch size*, *sequence_length*). If None, will be incremented from 0.
     /// * `input_embeds` - Optional pre-computed input embeddings of shape (*batch size*, *sequence_length*, *hidden_size*). If None, input ids must be provided (see `input_ids`)
     /// * `attention_mask` - Optional mask of shape (*batch size*, *sequence_length*). Masked position have value 0, non-masked value 1. If None set to 1
+    /// * `labels` - Optional target token ids of shape (*batch size*, *sequence_length*) used to compute the masked language modeling loss. Positions with a label of -100 (e.g. non-masked tokens) are ignored.
     /// * `train` - boolean flag to turn on/off the dropout layers in the model. Should be set to false for inference.
     ///
     /// # Returns
     ///
     /// * `MobileBertMaskedLMOutput` containing:
     ///   - `logits` - `Tensor` of shape (*batch size*, *sequence_length*, *vocab_size*)
+    ///   - `loss` - `Option<Tensor>` scalar loss, computed if labels are provided
     ///   - `all_hidden_states` - `Option<Vec<Tensor>>` of length *num_hidden_layers* with shape (*batch size*, *sequence_length*, *hidden_size*)
     ///   - `all_attentions` - `Option<Vec<Tensor>>` of length *num_hidden_layers* with shape (*batch size*, *sequence_length*, *hidden_size*)
     ///
@@ -622,6 +630,7 @@ impl MobileBertForMaskedLM {
     ///             Some(&position_ids),
     ///             None,
     ///             Some(&attention_mask),
+    ///             None,
     ///             false,
     ///         )
     ///         .unwrap()
@@ -634,6 +643,7 @@ impl MobileBertForMaskedLM {
         position_ids: Option<&Tensor>,
         input_embeds: Option<&Tensor>,
         attention_mask: Option<&Tensor>,
+        labels: Option<&Tensor>,
         train: bool,
     ) -> Result<MobileBertMaskedLMOutput, RustBertError> {
         let mobilebert_output = self.mobilebert.forward_t(
@@ -650,7 +660,9 @@ impl MobileBertForMaskedLM {
             self.mobilebert.get_embeddings(),
         );
 
+        let loss = labels.map(|labels| cross_entropy_loss(&logits, labels));
         Ok(MobileBertMaskedLMOutput {
+            loss,
             logits,
             all_hidden_states: mobilebert_output.all_hidden_states,
             all_attentions: mobilebert_output.all_attentions,
@@ -668,6 +680,7 @@ pub struct MobileBertForSequenceClassification {
     mobilebert: MobileBertModel,
     dropout: Dropout,
     classifier: nn::Linear,
+    problem_type: Option<ProblemType>,
 }
 
 impl MobileBertForSequenceClassification {
@@ -722,6 +735,7 @@ impl MobileBertForSequenceClassification {
             mobilebert,
             dropout,
             classifier,
+            problem_type: config.problem_type,
         })
     }
 
@@ -734,12 +748,14 @@ impl MobileBertForSequenceClassification {
     /// * `position_ids` - Optional position ids of shape (*batch size*, *sequence_length*). If None, will be incremented from 0.
     /// * `input_embeds` - Optional pre-computed input embeddings of shape (*batch size*, *sequence_length*, *hidden_size*). If None, input ids must be provided (see `input_ids`)
     /// * `attention_mask` - Optional mask of shape (*batch size*, *sequence_length*). Masked position have value 0, non-masked value 1. If None set to 1
+    /// * `labels` - Optional labels of shape (*batch size*) used to compute the loss: class indices for single-label classification or target values for regression. Multi-label classification uses floating point targets of shape (*batch size*, *num_labels*). The loss follows the `problem_type` of the configuration, inferred from the labels if not set.
     /// * `train` - boolean flag to turn on/off the dropout layers in the model. Should be set to false for inference.
     ///
     /// # Returns
     ///
     /// * `MobileBertSequenceClassificationOutput` containing:
     ///   - `logits` - `Tensor` of shape (*batch size*, *num_classes*)
+    ///   - `loss` - `Option<Tensor>` scalar loss, computed if labels are provided
     ///   - `all_hidden_states` - `Option<Vec<Tensor>>` of length *num_hidden_layers* with shape (*batch size*, *sequence_length*, *hidden_size*)
     ///   - `all_attentions` - `Option<Vec<Tensor>>` of length *num_hidden_layers* with shape (*batch size*, *sequence_length*, *hidden_size*)
     ///
@@ -771,6 +787,7 @@ impl MobileBertForSequenceClassification {
     ///             Some(&position_ids),
     ///             None,
     ///             Some(&attention_mask),
+    ///             None,
     ///             false,
     ///         )
     ///         .unwrap()
@@ -783,6 +800,7 @@ impl MobileBertForSequenceClassification {
         position_ids: Option<&Tensor>,
         input_embeds: Option<&Tensor>,
         attention_mask: Option<&Tensor>,
+        labels: Option<&Tensor>,
         train: bool,
     ) -> Result<MobileBertSequenceClassificationOutput, RustBertError> {
         let mobilebert_output = self.mobilebert.forward_t(
@@ -800,7 +818,10 @@ impl MobileBertForSequenceClassification {
             .apply_t(&self.dropout, train)
             .apply(&self.classifier);
 
+        let loss =
+            labels.map(|labels| sequence_classification_loss(&logits, labels, self.problem_type));
         Ok(MobileBertSequenceClassificationOutput {
+            loss,
             logits,
             all_hidden_states: mobilebert_output.all_hidden_states,
             all_attentions: mobilebert_output.all_attentions,
@@ -866,6 +887,8 @@ impl MobileBertForQuestionAnswering {
     /// * `position_ids` - Optional position ids of shape (*batch size*, *sequence_length*). If None, will be incremented from 0.
     /// * `input_embeds` - Optional pre-computed input embeddings of shape (*batch size*, *sequence_length*, *hidden_size*). If None, input ids must be provided (see `input_ids`)
     /// * `attention_mask` - Optional mask of shape (*batch size*, *sequence_length*). Masked position have value 0, non-masked value 1. If None set to 1
+    /// * `start_positions` - Optional answer start positions of shape (*batch size*) used to compute the loss. Positions outside of the sequence are ignored.
+    /// * `end_positions` - Optional answer end positions of shape (*batch size*) used to compute the loss. Positions outside of the sequence are ignored.
     /// * `train` - boolean flag to turn on/off the dropout layers in the model. Should be set to false for inference.
     ///
     /// # Returns
@@ -873,6 +896,7 @@ impl MobileBertForQuestionAnswering {
     /// * `MobileBertQuestionAnsweringOutput` containing:
     ///   - `start_logits` - `Tensor` of shape (*batch size*, *sequence_length*) containing the logits for start of the answer
     ///   - `end_logits` - `Tensor` of shape (*batch size*, *sequence_length*) containing the logits for end of the answer
+    ///   - `loss` - `Option<Tensor>` scalar loss, computed if the start and end positions are provided
     ///   - `all_hidden_states` - `Option<Vec<Tensor>>` of length *num_hidden_layers* with shape (*batch size*, *sequence_length*, *hidden_size*)
     ///   - `all_attentions` - `Option<Vec<Vec<Tensor>>>` of length *num_hidden_layers* with shape (*batch size*, *sequence_length*, *hidden_size*)
     ///
@@ -904,6 +928,8 @@ impl MobileBertForQuestionAnswering {
     ///             Some(&position_ids),
     ///             None,
     ///             Some(&attention_mask),
+    ///             None,
+    ///             None,
     ///             false,
     ///         )
     ///         .unwrap()
@@ -916,6 +942,8 @@ impl MobileBertForQuestionAnswering {
         position_ids: Option<&Tensor>,
         input_embeds: Option<&Tensor>,
         attention_mask: Option<&Tensor>,
+        start_positions: Option<&Tensor>,
+        end_positions: Option<&Tensor>,
         train: bool,
     ) -> Result<MobileBertQuestionAnsweringOutput, RustBertError> {
         let mobilebert_output = self.mobilebert.forward_t(
@@ -933,7 +961,13 @@ impl MobileBertForQuestionAnswering {
         let start_logits = start_logits.squeeze_dim(-1);
         let end_logits = end_logits.squeeze_dim(-1);
 
+        let loss = start_positions
+            .zip(end_positions)
+            .map(|(start_positions, end_positions)| {
+                question_answering_loss(&start_logits, &end_logits, start_positions, end_positions)
+            });
         Ok(MobileBertQuestionAnsweringOutput {
+            loss,
             start_logits,
             end_logits,
             all_hidden_states: mobilebert_output.all_hidden_states,
@@ -1094,6 +1128,7 @@ impl MobileBertForMultipleChoice {
             .view([-1, num_choices]);
 
         Ok(MobileBertSequenceClassificationOutput {
+            loss: None,
             logits,
             all_hidden_states: mobilebert_output.all_hidden_states,
             all_attentions: mobilebert_output.all_attentions,
@@ -1179,12 +1214,14 @@ impl MobileBertForTokenClassification {
     /// * `position_ids` - Optional position ids of shape (*batch size*, *sequence_length*). If None, will be incremented from 0.
     /// * `input_embeds` - Optional pre-computed input embeddings of shape (*batch size*, *sequence_length*, *hidden_size*). If None, input ids must be provided (see `input_ids`)
     /// * `attention_mask` - Optional mask of shape (*batch size*, *sequence_length*). Masked position have value 0, non-masked value 1. If None set to 1
+    /// * `labels` - Optional labels of shape (*batch size*, *sequence_length*) used to compute the cross-entropy loss. Positions with a label of -100 are ignored.
     /// * `train` - boolean flag to turn on/off the dropout layers in the model. Should be set to false for inference.
     ///
     /// # Returns
     ///
     /// * `MobileBertTokenClassificationOutput` containing:
     ///   - `logits` - `Tensor` of shape (*batch size*, *sequence_length*, *num_labels*) containing the logits for each of the input tokens and classes
+    ///   - `loss` - `Option<Tensor>` scalar loss, computed if labels are provided
     ///   - `all_hidden_states` - `Option<Vec<Tensor>>` of length *num_hidden_layers* with shape (*batch size*, *sequence_length*, *hidden_size*)
     ///   - `all_attentions` - `Option<Vec<Tensor>>` of length *num_hidden_layers* with shape (*batch size*, *sequence_length*, *hidden_size*)
     ///
@@ -1216,6 +1253,7 @@ impl MobileBertForTokenClassification {
     ///             Some(&position_ids),
     ///             None,
     ///             Some(&attention_mask),
+    ///             None,
     ///             false,
     ///         )
     ///         .unwrap()
@@ -1228,6 +1266,7 @@ impl MobileBertForTokenClassification {
         position_ids: Option<&Tensor>,
         input_embeds: Option<&Tensor>,
         attention_mask: Option<&Tensor>,
+        labels: Option<&Tensor>,
         train: bool,
     ) -> Result<MobileBertTokenClassificationOutput, RustBertError> {
         let mobilebert_output = self.mobilebert.forward_t(
@@ -1244,7 +1283,9 @@ impl MobileBertForTokenClassification {
             .apply_t(&self.dropout, train)
             .apply(&self.classifier);
 
+        let loss = labels.map(|labels| cross_entropy_loss(&logits, labels));
         Ok(MobileBertTokenClassificationOutput {
+            loss,
             logits,
             all_hidden_states: mobilebert_output.all_hidden_states,
             all_attentions: mobilebert_output.all_attentions,
@@ -1252,6 +1293,10 @@ impl MobileBertForTokenClassification {
     }
 }
 
+/// # MobileBERT for sentence embeddings
+/// Transformer usable in [`SentenceEmbeddingsModel`](crate::pipelines::sentence_embeddings::SentenceEmbeddingsModel).
+pub type MobileBertForSentenceEmbeddings = MobileBertModel;
+
 /// Container for the MobileBert output.
 pub struct MobileBertOutput {
     /// Last hidden states from the model
@@ -1272,6 +1317,8 @@ pub struct MobileBertMaskedLMOutput {
     pub all_hidden_states: Option<Vec<Tensor>>,
     /// Attention weights for all intermediate layers
     pub all_attentions: Option<Vec<Tensor>>,
+    /// Loss computed from the labels, if provided
+    pub loss: Option<Tensor>,
 }
 
 /// Container for the MobileBert sequence classification model output.
@@ -1282,6 +1329,8 @@ pub struct MobileBertSequenceClassificationOutput {
     pub all_hidden_states: Option<Vec<Tensor>>,
     /// Attention weights for all intermediate layers
     pub all_attentions: Option<Vec<Tensor>>,
+    /// Loss computed from the labels, if provided
+    pub loss: Option<Tensor>,
 }
 
 /// Container for the MobileBert token classification model output.
@@ -1292,6 +1341,8 @@ pub struct MobileBertTokenClassificationOutput {
     pub all_hidden_states: Option<Vec<Tensor>>,
     /// Attention weights for all intermediate layers
     pub all_attentions: Option<Vec<Tensor>>,
+    /// Loss computed from the labels, if provided
+    pub loss: Option<Tensor>,
 }
 
 /// Container for the MobileBert question answering model output.
@@ -1304,4 +1355,6 @@ pub struct MobileBertQuestionAnsweringOutput {
     pub all_hidden_states: Option<Vec<Tensor>>,
     /// Attention weights for all intermediate layers
     pub all_attentions: Option<Vec<Tensor>>,
+    /// Loss computed from the labels, if provided
+    pub loss: Option<Tensor>,
 }
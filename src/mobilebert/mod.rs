@@ -56,7 +56,7 @@ mod mobilebert_model;
 
 pub use mobilebert_model::{
     MobileBertConfig, MobileBertConfigResources, MobileBertForMaskedLM,
-    MobileBertForMultipleChoice, MobileBertForQuestionAnswering,
+    MobileBertForMultipleChoice, MobileBertForQuestionAnswering, MobileBertForSentenceEmbeddings,
     MobileBertForSequenceClassification, MobileBertForTokenClassification, MobileBertModel,
     MobileBertModelResources, MobileBertVocabResources, NoNorm, NormalizationType,
 };
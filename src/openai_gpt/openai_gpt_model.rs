@@ -413,6 +413,7 @@ impl LMHeadModel for OpenAIGPTLMHeadModel {
         Ok(LMModelOutput {
             lm_logits,
             cache: Cache::None,
+            loss: None,
         })
     }
 }
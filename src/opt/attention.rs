@@ -0,0 +1,175 @@
+// Copyright 2022 The Fairseq Authors and The HuggingFace Inc. team. All rights reserved.
+// Copyright 2023 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::common::dropout::Dropout;
+use crate::opt::OptConfig;
+use std::borrow::Borrow;
+use tch::{nn, Kind, Tensor};
+
+#[derive(Debug)]
+/// # Cache for OPT attention layers
+/// Stores the cached value of key and value
+pub struct LayerState {
+    /// Cached keys
+    pub prev_key: Tensor,
+    /// Cached values
+    pub prev_value: Tensor,
+}
+
+impl Clone for LayerState {
+    fn clone(&self) -> Self {
+        LayerState {
+            prev_key: self.prev_key.copy(),
+            prev_value: self.prev_value.copy(),
+        }
+    }
+}
+
+impl LayerState {
+    pub(crate) fn reorder_cache(&mut self, new_indices: &Tensor) {
+        self.prev_key = self.prev_key.index_select(0, new_indices);
+        self.prev_value = self.prev_value.index_select(0, new_indices);
+    }
+}
+
+pub struct OptAttention {
+    q_proj: nn::Linear,
+    k_proj: nn::Linear,
+    v_proj: nn::Linear,
+    out_proj: nn::Linear,
+    dropout: Dropout,
+    num_heads: i64,
+    head_dim: i64,
+    scaling: f64,
+    output_attentions: bool,
+}
+
+impl OptAttention {
+    pub fn new<'p, P>(p: P, config: &OptConfig) -> OptAttention
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let p = p.borrow();
+
+        let num_heads = config.num_attention_heads;
+        let head_dim = config.hidden_size / config.num_attention_heads;
+        let scaling = (head_dim as f64).powf(-0.5);
+
+        let linear_config = nn::LinearConfig {
+            bias: config.enable_bias.unwrap_or(true),
+            ..Default::default()
+        };
+        let q_proj = nn::linear(
+            p / "q_proj",
+            config.hidden_size,
+            config.hidden_size,
+            linear_config,
+        );
+        let k_proj = nn::linear(
+            p / "k_proj",
+            config.hidden_size,
+            config.hidden_size,
+            linear_config,
+        );
+        let v_proj = nn::linear(
+            p / "v_proj",
+            config.hidden_size,
+            config.hidden_size,
+            linear_config,
+        );
+        let out_proj = nn::linear(
+            p / "out_proj",
+            config.hidden_size,
+            config.hidden_size,
+            linear_config,
+        );
+
+        let dropout = Dropout::new(config.attention_dropout);
+        let output_attentions = config.output_attentions.unwrap_or(false);
+
+        OptAttention {
+            q_proj,
+            k_proj,
+            v_proj,
+            out_proj,
+            dropout,
+            num_heads,
+            head_dim,
+            scaling,
+            output_attentions,
+        }
+    }
+
+    fn split_heads(&self, x: &Tensor) -> Tensor {
+        let size = x.size();
+        x.view([size[0], size[1], self.num_heads, self.head_dim])
+            .transpose(1, 2)
+    }
+
+    /// Forward pass through the attention layer
+    ///
+    /// # Arguments
+    ///
+    /// * `hidden_states` - input tensor of shape (*batch size*, *sequence_length*, *hidden_size*)
+    /// * `attention_mask` - Optional boolean mask of shape (*batch size*, 1, *sequence_length*, *total_sequence_length*), with `true` for the positions that cannot be attended to
+    /// * `layer_state` - Optional cached keys and values of the previous positions
+    /// * `train` - boolean flag to turn on/off the dropout layers in the model
+    pub fn forward_t(
+        &self,
+        hidden_states: &Tensor,
+        attention_mask: Option<&Tensor>,
+        layer_state: Option<&LayerState>,
+        train: bool,
+    ) -> (Tensor, Option<Tensor>, LayerState) {
+        let size = hidden_states.size();
+        let (batch_size, sequence_length) = (size[0], size[1]);
+
+        let query = self.split_heads(&(hidden_states.apply(&self.q_proj) * self.scaling));
+        let mut key = self.split_heads(&hidden_states.apply(&self.k_proj));
+        let mut value = self.split_heads(&hidden_states.apply(&self.v_proj));
+
+        if let Some(layer_state) = layer_state {
+            key = Tensor::cat(&[&layer_state.prev_key, &key], 2);
+            value = Tensor::cat(&[&layer_state.prev_value, &value], 2);
+        }
+        let new_layer_state = LayerState {
+            prev_key: key.copy(),
+            prev_value: value.copy(),
+        };
+
+        let mut attention_scores = query
+            .to_kind(Kind::Float)
+            .matmul(&key.to_kind(Kind::Float).transpose(-1, -2));
+        if let Some(attention_mask) = attention_mask {
+            attention_scores = attention_scores.masked_fill(attention_mask, f32::MIN as f64);
+        }
+        let attention_weights = attention_scores
+            .softmax(-1, Kind::Float)
+            .to_kind(value.kind())
+            .apply_t(&self.dropout, train);
+
+        let attention_output = attention_weights
+            .matmul(&value)
+            .transpose(1, 2)
+            .contiguous()
+            .view([batch_size, sequence_length, self.num_heads * self.head_dim])
+            .apply(&self.out_proj);
+
+        let attention_weights = if self.output_attentions {
+            Some(attention_weights)
+        } else {
+            None
+        };
+
+        (attention_output, attention_weights, new_layer_state)
+    }
+}
@@ -0,0 +1,117 @@
+// Copyright 2022 The Fairseq Authors and The HuggingFace Inc. team. All rights reserved.
+// Copyright 2023 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::common::activations::TensorFunction;
+use crate::common::dropout::Dropout;
+use crate::opt::attention::{LayerState, OptAttention};
+use crate::opt::OptConfig;
+use std::borrow::Borrow;
+use tch::{nn, Tensor};
+
+/// # OPT decoder layer
+/// Decoder layer made of a self-attention and a feed-forward block. The layer normalization is applied
+/// before each block if `do_layer_norm_before` is set (all checkpoints except the 350M one), or after the
+/// residual connections otherwise.
+pub struct OptDecoderLayer {
+    self_attn: OptAttention,
+    self_attn_layer_norm: nn::LayerNorm,
+    fc1: nn::Linear,
+    fc2: nn::Linear,
+    final_layer_norm: nn::LayerNorm,
+    activation_function: TensorFunction,
+    dropout: Dropout,
+    do_layer_norm_before: bool,
+}
+
+impl OptDecoderLayer {
+    pub fn new<'p, P>(p: P, config: &OptConfig) -> OptDecoderLayer
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let p = p.borrow();
+
+        let layer_norm_config = nn::LayerNormConfig {
+            elementwise_affine: config.layer_norm_elementwise_affine.unwrap_or(true),
+            ..Default::default()
+        };
+        let linear_config = nn::LinearConfig {
+            bias: config.enable_bias.unwrap_or(true),
+            ..Default::default()
+        };
+
+        let self_attn = OptAttention::new(p / "self_attn", config);
+        let self_attn_layer_norm = nn::layer_norm(
+            p / "self_attn_layer_norm",
+            vec![config.hidden_size],
+            layer_norm_config,
+        );
+        let fc1 = nn::linear(p / "fc1", config.hidden_size, config.ffn_dim, linear_config);
+        let fc2 = nn::linear(p / "fc2", config.ffn_dim, config.hidden_size, linear_config);
+        let final_layer_norm = nn::layer_norm(
+            p / "final_layer_norm",
+            vec![config.hidden_size],
+            layer_norm_config,
+        );
+
+        let activation_function = config.activation_function.get_function();
+        let dropout = Dropout::new(config.dropout);
+        let do_layer_norm_before = config.do_layer_norm_before.unwrap_or(true);
+
+        OptDecoderLayer {
+            self_attn,
+            self_attn_layer_norm,
+            fc1,
+            fc2,
+            final_layer_norm,
+            activation_function,
+            dropout,
+            do_layer_norm_before,
+        }
+    }
+
+    pub fn forward_t(
+        &self,
+        hidden_states: &Tensor,
+        attention_mask: Option<&Tensor>,
+        layer_state: Option<&LayerState>,
+        train: bool,
+    ) -> (Tensor, Option<Tensor>, LayerState) {
+        let attention_input = if self.do_layer_norm_before {
+            hidden_states.apply(&self.self_attn_layer_norm)
+        } else {
+            hidden_states.shallow_clone()
+        };
+        let (attention_output, attention_weights, layer_state) =
+            self.self_attn
+                .forward_t(&attention_input, attention_mask, layer_state, train);
+        let mut hidden_states = hidden_states + attention_output.apply_t(&self.dropout, train);
+        if !self.do_layer_norm_before {
+            hidden_states = hidden_states.apply(&self.self_attn_layer_norm);
+        }
+
+        let feed_forward_input = if self.do_layer_norm_before {
+            hidden_states.apply(&self.final_layer_norm)
+        } else {
+            hidden_states.shallow_clone()
+        };
+        let feed_forward_output =
+            self.activation_function.get_fn()(&feed_forward_input.apply(&self.fc1))
+                .apply(&self.fc2)
+                .apply_t(&self.dropout, train);
+        let mut output = hidden_states + feed_forward_output;
+        if !self.do_layer_norm_before {
+            output = output.apply(&self.final_layer_norm);
+        }
+
+        (output, attention_weights, layer_state)
+    }
+}
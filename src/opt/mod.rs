@@ -0,0 +1,69 @@
+//! # OPT
+//!
+//! Implementation of the OPT language model ([OPT: Open Pre-trained Transformer Language Models](https://arxiv.org/abs/2205.01068) Zhang, Roller, Goyal, Artetxe, Chen, Chen, Dewan, Diab, Li, Lin, Mihaylov, Ott, Shleifer, Shuster, Simig, Koura, Sridhar, Wang, Zettlemoyer, 2022).
+//! The base model is implemented in the `opt_model::OptModel` struct. A causal language modeling head is implemented in `opt_model::OptForCausalLM`.
+//! The learned position embeddings are offset by 2 positions, and the layer normalization is applied before (`do_layer_norm_before`, default)
+//! or after each block depending on the checkpoint.
+//!
+//! # Model set-up and pre-trained weights loading
+//!
+//! All models expect the following resources:
+//! - Configuration file expected to have a structure following the [Transformers library](https://github.com/huggingface/transformers)
+//! - Model weights are expected to have a structure and parameter names following the [Transformers library](https://github.com/huggingface/transformers). A conversion using the Python utility scripts is required to convert the `.bin` weights to the `.ot` format.
+//! - `GPT2Tokenizer` using a `vocab.json` vocabulary and a `merges.txt` merges file
+//!
+//! No pretrained checkpoints are registered as remote resources: the weights have to be converted and loaded from local resources.
+//! Prompts are not prefixed with the beginning of sequence token (`</s>`) automatically, it can be added to the input text.
+//!
+//! ```no_run
+//! use rust_bert::pipelines::common::ModelType;
+//! use rust_bert::pipelines::text_generation::{TextGenerationConfig, TextGenerationModel};
+//! use rust_bert::resources::LocalResource;
+//! use std::path::PathBuf;
+//! use tch::Device;
+//!
+//! fn main() -> anyhow::Result<()> {
+//!     let config_resource = Box::new(LocalResource {
+//!         local_path: PathBuf::from("path/to/config.json"),
+//!     });
+//!     let vocab_resource = Box::new(LocalResource {
+//!         local_path: PathBuf::from("path/to/vocab.json"),
+//!     });
+//!     let merges_resource = Box::new(LocalResource {
+//!         local_path: PathBuf::from("path/to/merges.txt"),
+//!     });
+//!     let model_resource = Box::new(LocalResource {
+//!         local_path: PathBuf::from("path/to/rust_model.ot"),
+//!     });
+//!
+//!     let text_generation_config = TextGenerationConfig {
+//!         model_type: ModelType::OPT,
+//!         model_resource,
+//!         config_resource,
+//!         vocab_resource,
+//!         merges_resource: Some(merges_resource),
+//!         max_length: Some(64),
+//!         do_sample: false,
+//!         device: Device::cuda_if_available(),
+//!         ..Default::default()
+//!     };
+//!     let model = TextGenerationModel::new(text_generation_config)?;
+//!
+//!     let output = model.generate(&["</s>It was a very nice and sunny"], None);
+//!
+//!     for sentence in output {
+//!         println!("{}", sentence);
+//!     }
+//!
+//!     Ok(())
+//! }
+//! ```
+
+mod attention;
+mod decoder;
+mod opt_model;
+
+pub use attention::LayerState;
+pub use opt_model::{
+    OptConfig, OptForCausalLM, OptGenerator, OptModel, OptModelLMOutput, OptModelOutput,
+};
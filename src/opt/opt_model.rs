@@ -0,0 +1,808 @@
+// Copyright 2022 The Fairseq Authors and The HuggingFace Inc. team. All rights reserved.
+// Copyright 2023 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::common::embeddings::process_ids_embeddings_pair;
+use crate::opt::decoder::OptDecoderLayer;
+use crate::opt::LayerState;
+use crate::pipelines::common::{ModelType, TokenizerOption};
+use crate::pipelines::generation_utils::private_generation_utils::{
+    PreparedInput, PrivateLanguageGenerator,
+};
+use crate::pipelines::generation_utils::{
+    Cache, GenerateConfig, LMHeadModel, LMModelOutput, LanguageGenerator,
+};
+use crate::{Activation, Config, RustBertError};
+use rust_tokenizers::tokenizer::Gpt2Tokenizer;
+use rust_tokenizers::vocab::Gpt2Vocab;
+use serde::{Deserialize, Serialize};
+use std::borrow::{Borrow, BorrowMut};
+use tch::{nn, Kind, Tensor};
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+/// # OPT model configuration
+/// Defines the OPT model architecture (e.g. number of layers, hidden layer size, layer normalization placement...).
+/// The field names follow the configuration files of the [Transformers library](https://github.com/huggingface/transformers).
+pub struct OptConfig {
+    pub vocab_size: i64,
+    pub hidden_size: i64,
+    pub num_hidden_layers: i64,
+    pub ffn_dim: i64,
+    pub max_position_embeddings: i64,
+    /// Apply the layer normalization before the attention and feed-forward blocks (defaults to `true`)
+    pub do_layer_norm_before: Option<bool>,
+    #[serde(rename = "_remove_final_layer_norm")]
+    pub remove_final_layer_norm: Option<bool>,
+    /// Dimension of the word embeddings, projected to `hidden_size` if different (defaults to `hidden_size`)
+    pub word_embed_proj_dim: Option<i64>,
+    pub dropout: f64,
+    pub attention_dropout: f64,
+    pub num_attention_heads: i64,
+    pub activation_function: Activation,
+    pub init_std: f64,
+    pub enable_bias: Option<bool>,
+    pub layer_norm_elementwise_affine: Option<bool>,
+    pub tie_word_embeddings: Option<bool>,
+    pub bos_token_id: Option<i64>,
+    pub eos_token_id: Option<i64>,
+    pub pad_token_id: Option<i64>,
+    pub output_attentions: Option<bool>,
+    pub output_hidden_states: Option<bool>,
+}
+
+impl Config for OptConfig {}
+
+impl Default for OptConfig {
+    fn default() -> Self {
+        OptConfig {
+            vocab_size: 50272,
+            hidden_size: 768,
+            num_hidden_layers: 12,
+            ffn_dim: 3072,
+            max_position_embeddings: 2048,
+            do_layer_norm_before: None,
+            remove_final_layer_norm: None,
+            word_embed_proj_dim: None,
+            dropout: 0.1,
+            attention_dropout: 0.0,
+            num_attention_heads: 12,
+            activation_function: Activation::relu,
+            init_std: 0.02,
+            enable_bias: None,
+            layer_norm_elementwise_affine: None,
+            tie_word_embeddings: None,
+            bos_token_id: Some(2),
+            eos_token_id: Some(2),
+            pad_token_id: Some(1),
+            output_attentions: None,
+            output_hidden_states: None,
+        }
+    }
+}
+
+/// Offset of the learned position embeddings: the first two positions of the embedding matrix are not used
+const POSITION_OFFSET: i64 = 2;
+
+/// # OPT Base model
+/// Base architecture for OPT models. Task-specific models will be built from this common base model
+/// It is made of the following blocks:
+/// - `embed_tokens`: Word embeddings
+/// - `embed_positions`: Learned position embeddings, offset by 2 positions
+/// - `project_in`, `project_out`: Optional projections between the word embeddings and hidden dimensions
+/// - `layers`: Vector of `OptDecoderLayer` (transformer part of the model)
+/// - `final_layer_norm`: Optional final layer normalization (only used when the layer normalization is applied before each block)
+pub struct OptModel {
+    embed_tokens: nn::Embedding,
+    embed_positions: nn::Embedding,
+    project_in: Option<nn::Linear>,
+    project_out: Option<nn::Linear>,
+    layers: Vec<OptDecoderLayer>,
+    final_layer_norm: Option<nn::LayerNorm>,
+    output_attentions: bool,
+    output_hidden_states: bool,
+}
+
+impl OptModel {
+    /// Build a new `OptModel`
+    ///
+    /// # Arguments
+    ///
+    /// * `p` - Variable store path for the root of the OPT model
+    /// * `config` - `OptConfig` object defining the model architecture
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use rust_bert::opt::{OptConfig, OptModel};
+    /// use rust_bert::Config;
+    /// use std::path::Path;
+    /// use tch::{nn, Device};
+    ///
+    /// let config_path = Path::new("path/to/config.json");
+    /// let device = Device::Cpu;
+    /// let p = nn::VarStore::new(device);
+    /// let config = OptConfig::from_file(config_path);
+    /// let opt_model = OptModel::new(&p.root(), &config);
+    /// ```
+    pub fn new<'p, P>(p: P, config: &OptConfig) -> OptModel
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let p = p.borrow();
+
+        let word_embed_proj_dim = config.word_embed_proj_dim.unwrap_or(config.hidden_size);
+        let embed_tokens = nn::embedding(
+            p / "embed_tokens",
+            config.vocab_size,
+            word_embed_proj_dim,
+            Default::default(),
+        );
+        let embed_positions = nn::embedding(
+            p / "embed_positions",
+            config.max_position_embeddings + POSITION_OFFSET,
+            config.hidden_size,
+            Default::default(),
+        );
+
+        let (project_in, project_out) = if word_embed_proj_dim != config.hidden_size {
+            let linear_config = nn::LinearConfig {
+                bias: false,
+                ..Default::default()
+            };
+            (
+                Some(nn::linear(
+                    p / "project_in",
+                    word_embed_proj_dim,
+                    config.hidden_size,
+                    linear_config,
+                )),
+                Some(nn::linear(
+                    p / "project_out",
+                    config.hidden_size,
+                    word_embed_proj_dim,
+                    linear_config,
+                )),
+            )
+        } else {
+            (None, None)
+        };
+
+        let mut layers: Vec<OptDecoderLayer> =
+            Vec::with_capacity(config.num_hidden_layers as usize);
+        let p_layers = p / "layers";
+        for layer_index in 0..config.num_hidden_layers {
+            layers.push(OptDecoderLayer::new(&p_layers / layer_index, config));
+        }
+
+        let final_layer_norm = if config.do_layer_norm_before.unwrap_or(true)
+            && !config.remove_final_layer_norm.unwrap_or(false)
+        {
+            Some(nn::layer_norm(
+                p / "final_layer_norm",
+                vec![config.hidden_size],
+                nn::LayerNormConfig {
+                    elementwise_affine: config.layer_norm_elementwise_affine.unwrap_or(true),
+                    ..Default::default()
+                },
+            ))
+        } else {
+            None
+        };
+
+        let output_attentions = config.output_attentions.unwrap_or(false);
+        let output_hidden_states = config.output_hidden_states.unwrap_or(false);
+
+        OptModel {
+            embed_tokens,
+            embed_positions,
+            project_in,
+            project_out,
+            layers,
+            final_layer_norm,
+            output_attentions,
+            output_hidden_states,
+        }
+    }
+
+    /// Forward pass through the model
+    ///
+    /// # Arguments
+    ///
+    /// * `input_ids` - Optional input tensor of shape (*batch size*, *sequence_length*). This or `input_embeds` must be provided.
+    /// * `input_embeds` - Optional input tensor of shape (*batch size*, *sequence_length*, *embeddings dimension*). This or `input_ids` must be provided.
+    /// * `position_ids` - Optional position ids of shape (*batch size*, *sequence_length*), without the offset of the learned position embeddings. If None, will be computed from the attention mask (or incremented starting from the length of the past input).
+    /// * `layer_states` - Optional Vector `Option<Vec<Option<LayerState>>>` of length *n_layer* containing the past keys and values of each layer.
+    /// * `attention_mask` - Optional attention mask of shape (*batch size*, *past_sequence_length + sequence_length*). Positions with a mask with value 0 will be masked.
+    /// * `train` - boolean flag to turn on/off the dropout layers in the model. Should be set to false for inference.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<OptModelOutput, RustBertError>` containing:
+    ///   - `hidden_states` - `Tensor` of shape (*batch size*, *sequence_length*, *word_embed_proj_dim*) representing the activations of the last hidden state
+    ///   - `next_cache` - `Option<Vec<Option<LayerState>>>` of length *n_layer* containing the past content for the the attention layers
+    ///   - `all_hidden_states` - `Option<Vec<Tensor>>` of length *n_layer + 1* with shape (*batch size*, *sequence_length*, *hidden_size*)
+    ///   - `all_attentions` - `Option<Vec<Tensor>>` of length *n_layer* containing the attention weights for each layer
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use tch::{nn, Device, Tensor, no_grad, Kind};
+    /// # use rust_bert::Config;
+    /// # use std::path::Path;
+    /// # use tch::kind::Kind::{Int64, Double};
+    /// use rust_bert::opt::{OptConfig, OptModel};
+    /// # let config_path = Path::new("path/to/config.json");
+    /// # let device = Device::Cpu;
+    /// # let vs = nn::VarStore::new(device);
+    /// # let config = OptConfig::from_file(config_path);
+    /// # let opt_model = OptModel::new(&vs.root(), &config);
+    /// let (batch_size, sequence_length) = (64, 128);
+    /// let input_tensor = Tensor::rand(&[batch_size, sequence_length], (Int64, device));
+    /// let attention_mask = Tensor::ones(&[batch_size, sequence_length], (Int64, device));
+    ///
+    /// let model_output = no_grad(|| {
+    ///     opt_model.forward_t(
+    ///         Some(&input_tensor),
+    ///         None,
+    ///         None,
+    ///         None,
+    ///         Some(&attention_mask),
+    ///         false,
+    ///     )
+    /// });
+    /// ```
+    pub fn forward_t(
+        &self,
+        input_ids: Option<&Tensor>,
+        input_embeds: Option<&Tensor>,
+        position_ids: Option<&Tensor>,
+        layer_states: Option<Vec<Option<LayerState>>>,
+        attention_mask: Option<&Tensor>,
+        train: bool,
+    ) -> Result<OptModelOutput, RustBertError> {
+        let (calc_input_embeddings, input_shape, device) =
+            process_ids_embeddings_pair(input_ids, input_embeds, &self.embed_tokens)?;
+        let input_embeds = input_embeds.unwrap_or_else(|| calc_input_embeddings.as_ref().unwrap());
+
+        let (batch_size, sequence_length) = (input_shape[0], input_shape[1]);
+        let past_length = match &layer_states {
+            Some(layer_states) => match &layer_states[0] {
+                Some(layer_state) => layer_state.prev_key.size()[2],
+                None => 0,
+            },
+            None => 0,
+        };
+        let full_sequence_length = past_length + sequence_length;
+
+        let calc_position_ids = if position_ids.is_none() {
+            Some(match attention_mask {
+                Some(attention_mask) => {
+                    let attention_mask = attention_mask.to_kind(Kind::Int64);
+                    (attention_mask.cumsum(-1, Kind::Int64) * &attention_mask - 1).narrow(
+                        1,
+                        past_length,
+                        sequence_length,
+                    )
+                }
+                None => {
+                    Tensor::arange_start(past_length, full_sequence_length, (Kind::Int64, device))
+                        .unsqueeze(0)
+                        .expand(&[batch_size, sequence_length], true)
+                }
+            })
+        } else {
+            None
+        };
+        let position_ids = position_ids.unwrap_or_else(|| calc_position_ids.as_ref().unwrap());
+
+        let mut input_embeds = match &self.project_in {
+            Some(project_in) => input_embeds.apply(project_in),
+            None => input_embeds.shallow_clone(),
+        };
+        input_embeds += (position_ids + POSITION_OFFSET).apply(&self.embed_positions);
+
+        let query_positions =
+            Tensor::arange_start(past_length, full_sequence_length, (Kind::Int64, device))
+                .unsqueeze(-1);
+        let key_positions =
+            Tensor::arange(full_sequence_length, (Kind::Int64, device)).unsqueeze(0);
+        let mut causal_mask = key_positions.gt_tensor(&query_positions).view([
+            1,
+            1,
+            sequence_length,
+            full_sequence_length,
+        ]);
+        if let Some(attention_mask) = attention_mask {
+            causal_mask =
+                causal_mask.logical_or(&attention_mask.eq(0).view([batch_size, 1, 1, -1]));
+        }
+
+        let mut hidden_state = input_embeds;
+        let mut all_hidden_states: Option<Vec<Tensor>> = if self.output_hidden_states {
+            Some(vec![])
+        } else {
+            None
+        };
+        let mut all_attentions: Option<Vec<Tensor>> = if self.output_attentions {
+            Some(vec![])
+        } else {
+            None
+        };
+        let old_cache = layer_states.unwrap_or_else(|| vec![None; self.layers.len()]);
+        let mut next_cache = Vec::with_capacity(self.layers.len());
+
+        for (layer, layer_state) in self.layers.iter().zip(old_cache) {
+            if let Some(hidden_states) = all_hidden_states.borrow_mut() {
+                hidden_states.push(hidden_state.copy());
+            };
+            let (output, attention_weights, layer_state) = layer.forward_t(
+                &hidden_state,
+                Some(&causal_mask),
+                layer_state.as_ref(),
+                train,
+            );
+            hidden_state = output;
+            next_cache.push(Some(layer_state));
+            if let Some(attentions) = all_attentions.borrow_mut() {
+                attentions.push(attention_weights.unwrap());
+            };
+        }
+
+        if let Some(final_layer_norm) = &self.final_layer_norm {
+            hidden_state = hidden_state.apply(final_layer_norm);
+        }
+        if let Some(project_out) = &self.project_out {
+            hidden_state = hidden_state.apply(project_out);
+        }
+        if let Some(all_hidden_states) = all_hidden_states.borrow_mut() {
+            all_hidden_states.push(hidden_state.copy());
+        };
+
+        Ok(OptModelOutput {
+            hidden_states: hidden_state,
+            next_cache: Some(next_cache),
+            all_hidden_states,
+            all_attentions,
+        })
+    }
+}
+
+/// # OPT Model for causal language modeling
+/// OPT model with a vocabulary decoding head. The decoding head is tied to the word embeddings if
+/// `tie_word_embeddings` is set in the configuration (default).
+/// It is made of the following blocks:
+/// - `model`: `OptModel` Base OPT model (stored under `model.decoder`)
+/// - `lm_head`: Linear layer projecting the hidden states to the vocabulary logits
+pub struct OptForCausalLM {
+    model: OptModel,
+    lm_head: Option<nn::Linear>,
+}
+
+impl OptForCausalLM {
+    /// Build a new `OptForCausalLM`
+    ///
+    /// # Arguments
+    ///
+    /// * `p` - Variable store path for the root of the OPT model
+    /// * `config` - `OptConfig` object defining the model architecture
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use rust_bert::opt::{OptConfig, OptForCausalLM};
+    /// use rust_bert::Config;
+    /// use std::path::Path;
+    /// use tch::{nn, Device};
+    ///
+    /// let config_path = Path::new("path/to/config.json");
+    /// let device = Device::Cpu;
+    /// let p = nn::VarStore::new(device);
+    /// let config = OptConfig::from_file(config_path);
+    /// let opt_model = OptForCausalLM::new(&p.root(), &config);
+    /// ```
+    pub fn new<'p, P>(p: P, config: &OptConfig) -> OptForCausalLM
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let p = p.borrow();
+
+        let model = OptModel::new(p / "model" / "decoder", config);
+        let lm_head = if config.tie_word_embeddings.unwrap_or(true) {
+            None
+        } else {
+            Some(nn::linear(
+                p / "lm_head",
+                config.word_embed_proj_dim.unwrap_or(config.hidden_size),
+                config.vocab_size,
+                nn::LinearConfig {
+                    bias: false,
+                    ..Default::default()
+                },
+            ))
+        };
+
+        OptForCausalLM { model, lm_head }
+    }
+
+    /// Forward pass through the model
+    ///
+    /// # Arguments
+    ///
+    /// * `input_ids` - Optional input tensor of shape (*batch size*, *sequence_length*). This or `input_embeds` must be provided.
+    /// * `input_embeds` - Optional input tensor of shape (*batch size*, *sequence_length*, *embeddings dimension*). This or `input_ids` must be provided.
+    /// * `position_ids` - Optional position ids of shape (*batch size*, *sequence_length*), without the offset of the learned position embeddings. If None, will be computed from the attention mask (or incremented starting from the length of the past input).
+    /// * `layer_states` - Optional Vector `Option<Vec<Option<LayerState>>>` of length *n_layer* containing the past keys and values of each layer.
+    /// * `attention_mask` - Optional attention mask of shape (*batch size*, *past_sequence_length + sequence_length*). Positions with a mask with value 0 will be masked.
+    /// * `train` - boolean flag to turn on/off the dropout layers in the model. Should be set to false for inference.
+    ///
+    /// # Returns
+    ///
+    /// * `Result<OptModelLMOutput, RustBertError>` containing:
+    ///   - `lm_logits` - `Tensor` of shape (*batch size*, *sequence_length*, *vocab_size*) representing the logits for each vocab item and position
+    ///   - `next_cache` - `Option<Vec<Option<LayerState>>>` of length *n_layer* containing the past content for the the attention layers
+    ///   - `all_hidden_states` - `Option<Vec<Tensor>>` of length *n_layer + 1* with shape (*batch size*, *sequence_length*, *hidden_size*)
+    ///   - `all_attentions` - `Option<Vec<Tensor>>` of length *n_layer* containing the attention weights for each layer
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use tch::{nn, Device, Tensor, no_grad, Kind};
+    /// # use rust_bert::Config;
+    /// # use std::path::Path;
+    /// # use tch::kind::Kind::{Int64, Double};
+    /// use rust_bert::opt::{OptConfig, OptForCausalLM};
+    /// # let config_path = Path::new("path/to/config.json");
+    /// # let device = Device::Cpu;
+    /// # let vs = nn::VarStore::new(device);
+    /// # let config = OptConfig::from_file(config_path);
+    /// # let opt_model = OptForCausalLM::new(&vs.root(), &config);
+    /// let (batch_size, sequence_length) = (64, 128);
+    /// let input_tensor = Tensor::rand(&[batch_size, sequence_length], (Int64, device));
+    /// let attention_mask = Tensor::ones(&[batch_size, sequence_length], (Int64, device));
+    ///
+    /// let model_output = no_grad(|| {
+    ///     opt_model.forward_t(
+    ///         Some(&input_tensor),
+    ///         None,
+    ///         None,
+    ///         None,
+    ///         Some(&attention_mask),
+    ///         false,
+    ///     )
+    /// });
+    /// ```
+    pub fn forward_t(
+        &self,
+        input_ids: Option<&Tensor>,
+        input_embeds: Option<&Tensor>,
+        position_ids: Option<&Tensor>,
+        layer_states: Option<Vec<Option<LayerState>>>,
+        attention_mask: Option<&Tensor>,
+        train: bool,
+    ) -> Result<OptModelLMOutput, RustBertError> {
+        let base_model_output = self.model.forward_t(
+            input_ids,
+            input_embeds,
+            position_ids,
+            layer_states,
+            attention_mask,
+            train,
+        )?;
+
+        let lm_logits = match &self.lm_head {
+            Some(lm_head) => base_model_output.hidden_states.apply(lm_head),
+            None => base_model_output
+                .hidden_states
+                .linear::<Tensor>(&self.model.embed_tokens.ws, None),
+        };
+
+        Ok(OptModelLMOutput {
+            lm_logits,
+            next_cache: base_model_output.next_cache,
+            all_hidden_states: base_model_output.all_hidden_states,
+            all_attentions: base_model_output.all_attentions,
+        })
+    }
+}
+
+impl LMHeadModel for OptForCausalLM {
+    fn forward_t(
+        &self,
+        input_ids: Option<&Tensor>,
+        layer_past: Cache,
+        attention_mask: Option<&Tensor>,
+        _token_type_ids: Option<&Tensor>,
+        position_ids: Option<&Tensor>,
+        input_embeds: Option<&Tensor>,
+        _encoder_outputs: Option<&Tensor>,
+        _decoder_input_ids: Option<&Tensor>,
+        train: bool,
+    ) -> Result<LMModelOutput, RustBertError> {
+        let base_model_output = match layer_past {
+            Cache::OPTCache(layer_past) => self.forward_t(
+                input_ids,
+                input_embeds,
+                position_ids,
+                layer_past,
+                attention_mask,
+                train,
+            ),
+            Cache::None => self.forward_t(
+                input_ids,
+                input_embeds,
+                position_ids,
+                None,
+                attention_mask,
+                train,
+            ),
+            _ => {
+                return Err(RustBertError::ValueError(
+                    "Cache not compatible with OPT Model".into(),
+                ));
+            }
+        }?;
+
+        Ok(LMModelOutput {
+            lm_logits: base_model_output.lm_logits,
+            cache: Cache::OPTCache(base_model_output.next_cache),
+            loss: None,
+        })
+    }
+}
+
+/// Container for the OPT model output.
+pub struct OptModelOutput {
+    /// Last hidden states from the model
+    pub hidden_states: Tensor,
+    /// Cached outputs of the model (attention layers keys and values) if the model is used for generation
+    pub next_cache: Option<Vec<Option<LayerState>>>,
+    /// Hidden states for all intermediate layers
+    pub all_hidden_states: Option<Vec<Tensor>>,
+    /// Attention weights for all intermediate layers
+    pub all_attentions: Option<Vec<Tensor>>,
+}
+
+///Container holding a OPT model with LM head output
+pub struct OptModelLMOutput {
+    /// logits
+    pub lm_logits: Tensor,
+    /// Cached outputs of the model (attention layers keys and values) if the model is used for generation
+    pub next_cache: Option<Vec<Option<LayerState>>>,
+    /// Hidden states for all intermediate layers
+    pub all_hidden_states: Option<Vec<Tensor>>,
+    /// Attention weights for all intermediate layers
+    pub all_attentions: Option<Vec<Tensor>>,
+}
+
+/// # Language generation model based on the OPT architecture
+pub struct OptGenerator {
+    model: OptForCausalLM,
+    tokenizer: TokenizerOption,
+    var_store: nn::VarStore,
+    generate_config: GenerateConfig,
+    bos_token_id: Option<i64>,
+    eos_token_ids: Option<Vec<i64>>,
+    pad_token_id: Option<i64>,
+    is_encoder_decoder: bool,
+    vocab_size: i64,
+    decoder_start_id: Option<i64>,
+    max_position_embeddings: i64,
+}
+
+impl OptGenerator {
+    /// Build a new `OptGenerator`
+    ///
+    /// # Arguments
+    ///
+    /// * `generate_config` - `GenerateConfig` object containing the resource references (model, vocabulary, configuration), generation options and device placement (CPU/GPU). OPT uses a byte-level BPE tokenizer and requires a merges resource.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::opt::OptGenerator;
+    /// use rust_bert::pipelines::generation_utils::GenerateConfig;
+    /// use rust_bert::resources::LocalResource;
+    /// use std::path::PathBuf;
+    ///
+    /// let generate_config = GenerateConfig {
+    ///     model_resource: Box::new(LocalResource {
+    ///         local_path: PathBuf::from("path/to/rust_model.ot"),
+    ///     }),
+    ///     config_resource: Box::new(LocalResource {
+    ///         local_path: PathBuf::from("path/to/config.json"),
+    ///     }),
+    ///     vocab_resource: Box::new(LocalResource {
+    ///         local_path: PathBuf::from("path/to/vocab.json"),
+    ///     }),
+    ///     merges_resource: Some(Box::new(LocalResource {
+    ///         local_path: PathBuf::from("path/to/merges.txt"),
+    ///     })),
+    ///     max_length: Some(30),
+    ///     do_sample: true,
+    ///     ..Default::default()
+    /// };
+    /// let opt_generator = OptGenerator::new(generate_config)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn new(generate_config: GenerateConfig) -> Result<OptGenerator, RustBertError> {
+        let vocab_path = generate_config.vocab_resource.get_local_path()?;
+        let merges_path = generate_config
+            .merges_resource
+            .as_ref()
+            .ok_or_else(|| {
+                RustBertError::InvalidConfigurationError(
+                    "OPT expects a merges resources to be provided".to_string(),
+                )
+            })?
+            .get_local_path()?;
+
+        let tokenizer = TokenizerOption::from_file(
+            ModelType::OPT,
+            vocab_path.to_str().unwrap(),
+            Some(merges_path.to_str().unwrap()),
+            false,
+            None,
+            None,
+        )?;
+
+        Self::new_with_tokenizer(generate_config, tokenizer)
+    }
+
+    pub fn new_with_tokenizer(
+        generate_config: GenerateConfig,
+        tokenizer: TokenizerOption,
+    ) -> Result<OptGenerator, RustBertError> {
+        let config_path = generate_config.config_resource.get_local_path()?;
+        let weights_path = generate_config.model_resource.get_local_path()?;
+        let device = generate_config.device;
+
+        generate_config.validate();
+        let mut var_store = nn::VarStore::new(device);
+        let config = OptConfig::from_file(config_path);
+        let model = OptForCausalLM::new(var_store.root(), &config);
+        var_store.load(weights_path)?;
+
+        let bos_token_id = config.bos_token_id.or_else(|| tokenizer.get_bos_id());
+        let eos_token_ids = config
+            .eos_token_id
+            .or_else(|| tokenizer.get_eos_id())
+            .map(|id| vec![id]);
+        let pad_token_id = config.pad_token_id.or_else(|| tokenizer.get_pad_id());
+        let is_encoder_decoder = false;
+        let vocab_size = config.vocab_size;
+        let decoder_start_id = None;
+        let max_position_embeddings = config.max_position_embeddings;
+
+        Ok(OptGenerator {
+            model,
+            tokenizer,
+            var_store,
+            generate_config,
+            bos_token_id,
+            eos_token_ids,
+            pad_token_id,
+            is_encoder_decoder,
+            vocab_size,
+            decoder_start_id,
+            max_position_embeddings,
+        })
+    }
+}
+
+impl PrivateLanguageGenerator<OptForCausalLM, Gpt2Vocab, Gpt2Tokenizer> for OptGenerator {
+    fn get_model(&self) -> &OptForCausalLM {
+        &self.model
+    }
+    fn _get_tokenizer(&self) -> &TokenizerOption {
+        &self.tokenizer
+    }
+    fn get_var_store(&self) -> &nn::VarStore {
+        &self.var_store
+    }
+    fn get_var_store_mut(&mut self) -> &mut nn::VarStore {
+        &mut self.var_store
+    }
+    fn get_config(&self) -> &GenerateConfig {
+        &self.generate_config
+    }
+    fn get_bos_id(&self) -> Option<i64> {
+        self.bos_token_id
+    }
+    fn get_eos_ids(&self) -> Option<&Vec<i64>> {
+        self.eos_token_ids.as_ref()
+    }
+    fn get_pad_id(&self) -> Option<i64> {
+        self.pad_token_id
+    }
+    fn is_encoder_decoder(&self) -> bool {
+        self.is_encoder_decoder
+    }
+    fn get_vocab_size(&self) -> i64 {
+        self.vocab_size
+    }
+    fn get_decoder_start_id(&self) -> Option<i64> {
+        self.decoder_start_id
+    }
+    fn get_max_positions_embeddings(&self) -> i64 {
+        self.max_position_embeddings
+    }
+
+    fn prepare_inputs_for_generation<'a>(
+        &self,
+        input_ids: Tensor,
+        _encoder_outputs: Option<&'a Tensor>,
+        past: Cache,
+        attention_mask: Tensor,
+    ) -> PreparedInput<'a> {
+        let position_ids = (attention_mask.totype(Kind::Int64).cumsum(-1, Kind::Int64) - 1)
+            .masked_fill(&attention_mask.eq(0), 1);
+
+        match past {
+            Cache::OPTCache(past) => {
+                if past.is_some() {
+                    PreparedInput {
+                        prepared_input: Some(input_ids.select(1, -1).unsqueeze(-1)),
+                        prepared_attention_mask: Some(attention_mask),
+                        prepared_encoder_output: None,
+                        prepared_decoder_input: None,
+                        prepared_position_ids: Some(position_ids.select(1, -1).unsqueeze(-1)),
+                        prepared_past: Cache::OPTCache(past),
+                    }
+                } else {
+                    PreparedInput {
+                        prepared_input: Some(input_ids),
+                        prepared_attention_mask: Some(attention_mask),
+                        prepared_encoder_output: None,
+                        prepared_decoder_input: None,
+                        prepared_position_ids: Some(position_ids),
+                        prepared_past: Cache::OPTCache(None),
+                    }
+                }
+            }
+            Cache::None => PreparedInput {
+                prepared_input: Some(input_ids),
+                prepared_attention_mask: Some(attention_mask),
+                prepared_encoder_output: None,
+                prepared_decoder_input: None,
+                prepared_position_ids: Some(position_ids),
+                prepared_past: Cache::OPTCache(None),
+            },
+            _ => panic!("Cache type incompatible with OPT"),
+        }
+    }
+
+    fn reorder_cache(
+        &self,
+        past: &mut Cache,
+        _encoder_outputs: Option<Tensor>,
+        beam_indices: &Tensor,
+    ) -> Option<Tensor> {
+        match past {
+            Cache::OPTCache(cached_decoder_state) => match cached_decoder_state {
+                Some(old_cache) => {
+                    for layer_state in old_cache.iter_mut().flatten() {
+                        layer_state.reorder_cache(beam_indices)
+                    }
+                    None
+                }
+                None => None,
+            },
+            Cache::None => None,
+            _ => {
+                panic!("Invalid cache for OPT model");
+            }
+        }
+    }
+}
+
+impl LanguageGenerator<OptForCausalLM, Gpt2Vocab, Gpt2Tokenizer> for OptGenerator {}
@@ -250,6 +250,7 @@ impl PegasusModel {
             train,
         );
         PegasusModelOutput {
+            loss: None,
             decoder_output: decoder_output.hidden_state,
             encoder_hidden_state: calc_hidden_states,
             cache: decoder_output.next_decoder_cache,
@@ -540,8 +541,13 @@ impl LMHeadModel for PegasusForConditionalGeneration {
         Ok(LMModelOutput {
             lm_logits,
             cache: Cache::BARTCache(base_model_output.cache),
+            loss: None,
         })
     }
+
+    fn predicts_next_token(&self) -> bool {
+        false
+    }
 }
 
 /// # Language generation model based on the Pegasus architecture
@@ -0,0 +1,945 @@
+// Copyright 2023 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! # Chat pipeline for instruction-tuned causal language models
+//! Multi-turn chat over any causal language model supported by the text generation pipeline. Conversations are
+//! rendered to a prompt with a `ChatTemplate` defining the system prompt, role markers and turn separators.
+//!
+//! The history is truncated from its oldest turns to fit in a token budget, and the past keys and values computed
+//! for the previous turns are kept in the `ChatSession` and reused for the next turn (for GPT2, GPT-Neo, GPT-J,
+//! GPT-NeoX, LLaMA, OPT and BLOOM models), so that only the new tokens of the prompt are processed by the model.
+//!
+//! ```no_run
+//! # fn main() -> anyhow::Result<()> {
+//! use rust_bert::pipelines::chat::{ChatConfig, ChatModel, ChatTemplate};
+//! use rust_bert::pipelines::text_generation::TextGenerationConfig;
+//!
+//! let mut config = ChatConfig::new(TextGenerationConfig::default(), ChatTemplate::chatml());
+//! config.system_prompt = Some("You are a helpful assistant.".to_string());
+//! let chat_model = ChatModel::new(config)?;
+//!
+//! let mut session = chat_model.new_session();
+//! let response = chat_model.chat(&mut session, "What is the capital of France?")?;
+//! let follow_up = chat_model.chat(&mut session, "And of Germany?")?;
+//! # Ok(())
+//! # }
+//! ```
+use crate::common::error::RustBertError;
+use crate::pipelines::common::TokenizerOption;
+use crate::pipelines::generation_utils::private_generation_utils::PrivateLanguageGenerator;
+use crate::pipelines::generation_utils::{Cache, LMHeadModel, LMModelOutput};
+use crate::pipelines::text_generation::{TextGenerationConfig, TextGenerationOption};
+use rust_tokenizers::tokenizer::Tokenizer;
+use rust_tokenizers::vocab::Vocab;
+use serde::{Deserialize, Serialize};
+use std::mem;
+use tch::{Device, Kind, Tensor};
+
+/// # Role of the author of a chat message
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChatRole {
+    /// System instructions
+    System,
+    /// User message
+    User,
+    /// Model response
+    Assistant,
+}
+
+/// # Chat message
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ChatMessage {
+    /// Role of the author of the message
+    pub role: ChatRole,
+    /// Text content of the message
+    pub content: String,
+}
+
+impl ChatMessage {
+    pub fn new<S: Into<String>>(role: ChatRole, content: S) -> ChatMessage {
+        ChatMessage {
+            role,
+            content: content.into(),
+        }
+    }
+}
+
+/// # Chat template
+/// Defines how a conversation is rendered to a prompt. Each message is rendered as the marker of its role,
+/// followed by its content and the turn separator. The prompt starts with the `prefix` and ends with the
+/// `assistant_marker` for the model to generate the next response.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChatTemplate {
+    /// Text inserted at the start of the prompt (e.g. a beginning of sequence marker)
+    pub prefix: String,
+    /// Marker preceding the system prompt
+    pub system_marker: String,
+    /// Marker preceding the user messages
+    pub user_marker: String,
+    /// Marker preceding the model responses, also used to prompt the model for a response
+    pub assistant_marker: String,
+    /// Separator appended after each message. Generation stops when the model produces this separator.
+    pub turn_separator: String,
+}
+
+impl ChatTemplate {
+    /// ChatML template used by many instruction-tuned models
+    /// (`<|im_start|>user\n...<|im_end|>\n`)
+    pub fn chatml() -> ChatTemplate {
+        ChatTemplate {
+            prefix: String::new(),
+            system_marker: "<|im_start|>system\n".to_string(),
+            user_marker: "<|im_start|>user\n".to_string(),
+            assistant_marker: "<|im_start|>assistant\n".to_string(),
+            turn_separator: "<|im_end|>\n".to_string(),
+        }
+    }
+
+    /// Plain text template (`User: ...\nAssistant: ...\n`), suitable for base models without chat fine-tuning
+    pub fn plain() -> ChatTemplate {
+        ChatTemplate {
+            prefix: String::new(),
+            system_marker: String::new(),
+            user_marker: "User: ".to_string(),
+            assistant_marker: "Assistant: ".to_string(),
+            turn_separator: "\n".to_string(),
+        }
+    }
+
+    fn marker(&self, role: ChatRole) -> &str {
+        match role {
+            ChatRole::System => &self.system_marker,
+            ChatRole::User => &self.user_marker,
+            ChatRole::Assistant => &self.assistant_marker,
+        }
+    }
+
+    /// Renders a single message with its role marker and turn separator
+    pub fn render_message(&self, message: &ChatMessage) -> String {
+        format!(
+            "{}{}{}",
+            self.marker(message.role),
+            message.content,
+            self.turn_separator
+        )
+    }
+
+    /// Renders a list of messages to a prompt
+    ///
+    /// # Arguments
+    ///
+    /// * `messages` - messages of the conversation
+    /// * `add_generation_prompt` - if true, the prompt ends with the assistant marker
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use rust_bert::pipelines::chat::{ChatMessage, ChatRole, ChatTemplate};
+    ///
+    /// let template = ChatTemplate::plain();
+    /// let prompt = template.render(&[ChatMessage::new(ChatRole::User, "Hello!")], true);
+    /// assert_eq!(prompt, "User: Hello!\nAssistant: ");
+    /// ```
+    pub fn render(&self, messages: &[ChatMessage], add_generation_prompt: bool) -> String {
+        let mut prompt = self.prefix.clone();
+        for message in messages {
+            prompt.push_str(&self.render_message(message));
+        }
+        if add_generation_prompt {
+            prompt.push_str(&self.assistant_marker);
+        }
+        prompt
+    }
+}
+
+impl Default for ChatTemplate {
+    fn default() -> Self {
+        Self::chatml()
+    }
+}
+
+/// # Configuration for the chat pipeline
+pub struct ChatConfig {
+    /// Configuration of the underlying text generation model. The sampling options (`do_sample`,
+    /// `temperature`, `top_k`, `top_p`) are used for the responses generation.
+    pub generation_config: TextGenerationConfig,
+    /// Template used to render the conversations
+    pub template: ChatTemplate,
+    /// Optional system prompt for new sessions
+    pub system_prompt: Option<String>,
+    /// Maximum number of tokens generated for a response (default: 128)
+    pub max_new_tokens: usize,
+    /// Optional token budget for the prompt and the response. The oldest turns of the conversation
+    /// are dropped from the prompt to fit in this budget. Defaults to the maximum number of positions of the model.
+    pub max_context_tokens: Option<usize>,
+}
+
+impl ChatConfig {
+    /// Instantiate a new chat configuration
+    ///
+    /// # Arguments
+    ///
+    /// * `generation_config` - `TextGenerationConfig` of the causal language model to use
+    /// * `template` - `ChatTemplate` used to render the conversations
+    pub fn new(generation_config: TextGenerationConfig, template: ChatTemplate) -> ChatConfig {
+        ChatConfig {
+            generation_config,
+            template,
+            system_prompt: None,
+            max_new_tokens: 128,
+            max_context_tokens: None,
+        }
+    }
+}
+
+#[cfg(feature = "remote")]
+impl Default for ChatConfig {
+    fn default() -> ChatConfig {
+        ChatConfig::new(TextGenerationConfig::default(), ChatTemplate::default())
+    }
+}
+
+/// # Chat session
+/// Holds the messages of a conversation and the cached past keys and values of the tokens already
+/// processed by the model.
+pub struct ChatSession {
+    system_prompt: Option<String>,
+    messages: Vec<ChatMessage>,
+    cache: Cache,
+    cached_ids: Vec<i64>,
+}
+
+impl ChatSession {
+    /// Build a new `ChatSession` with an optional system prompt
+    pub fn new(system_prompt: Option<&str>) -> ChatSession {
+        ChatSession {
+            system_prompt: system_prompt.map(str::to_string),
+            messages: vec![],
+            cache: Cache::None,
+            cached_ids: vec![],
+        }
+    }
+
+    /// Returns the user and assistant messages of the session
+    pub fn messages(&self) -> &[ChatMessage] {
+        &self.messages
+    }
+
+    /// Appends a message to the session, for example to restore a previous conversation
+    pub fn add_message(&mut self, message: ChatMessage) {
+        self.messages.push(message);
+    }
+
+    /// Removes all messages and cached states from the session, keeping the system prompt
+    pub fn clear(&mut self) {
+        self.messages.clear();
+        self.cache = Cache::None;
+        self.cached_ids.clear();
+    }
+
+    /// Returns the number of tokens with cached past keys and values
+    pub fn cached_tokens(&self) -> usize {
+        self.cached_ids.len()
+    }
+}
+
+/// # Chat model
+/// Generates responses for `ChatSession`s with a causal language model.
+pub struct ChatModel {
+    model: TextGenerationOption,
+    template: ChatTemplate,
+    system_prompt: Option<String>,
+    max_new_tokens: usize,
+    max_context_tokens: usize,
+    do_sample: bool,
+    temperature: f64,
+    top_k: i64,
+    top_p: f64,
+    eos_token_ids: Vec<i64>,
+    device: Device,
+}
+
+impl ChatModel {
+    /// Build a new `ChatModel`
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - `ChatConfig` object containing the text generation model configuration and chat template
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::chat::ChatModel;
+    ///
+    /// let chat_model = ChatModel::new(Default::default())?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn new(config: ChatConfig) -> Result<ChatModel, RustBertError> {
+        let do_sample = config.generation_config.do_sample;
+        let temperature = config.generation_config.temperature;
+        let top_k = config.generation_config.top_k;
+        let top_p = config.generation_config.top_p;
+        let device = config.generation_config.device;
+        let model = TextGenerationOption::new(config.generation_config)?;
+
+        let (eos_token_ids, max_positions) = match &model {
+            TextGenerationOption::GPT2(generator) => generation_limits(generator),
+            TextGenerationOption::GPT(generator) => generation_limits(generator),
+            TextGenerationOption::GPTNeo(generator) => generation_limits(generator),
+            TextGenerationOption::GPTJ(generator) => generation_limits(generator),
+            TextGenerationOption::GPTNeoX(generator) => generation_limits(generator),
+            TextGenerationOption::Llama(generator) => generation_limits(generator),
+            TextGenerationOption::OPT(generator) => generation_limits(generator),
+            TextGenerationOption::Bloom(generator) => generation_limits(generator),
+            TextGenerationOption::XLNet(generator) => generation_limits(generator),
+            TextGenerationOption::Reformer(generator) => generation_limits(generator),
+        };
+        let max_context_tokens = config.max_context_tokens.unwrap_or(max_positions);
+        if config.max_new_tokens >= max_context_tokens {
+            return Err(RustBertError::InvalidConfigurationError(format!(
+                "max_new_tokens ({}) must be lower than the context budget ({max_context_tokens})",
+                config.max_new_tokens
+            )));
+        }
+
+        Ok(ChatModel {
+            model,
+            template: config.template,
+            system_prompt: config.system_prompt,
+            max_new_tokens: config.max_new_tokens,
+            max_context_tokens,
+            do_sample,
+            temperature,
+            top_k,
+            top_p,
+            eos_token_ids,
+            device,
+        })
+    }
+
+    /// Creates a new session using the system prompt of the configuration
+    pub fn new_session(&self) -> ChatSession {
+        ChatSession::new(self.system_prompt.as_deref())
+    }
+
+    /// Generates a response to a user message and adds both to the session.
+    ///
+    /// # Arguments
+    ///
+    /// * `session` - `ChatSession` holding the conversation history
+    /// * `user_input` - new user message
+    ///
+    /// # Returns
+    ///
+    /// * `Result<String, RustBertError>` generated response
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::chat::ChatModel;
+    ///
+    /// let chat_model = ChatModel::new(Default::default())?;
+    /// let mut session = chat_model.new_session();
+    /// let response = chat_model.chat(&mut session, "Hello, how are you?")?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn chat(
+        &self,
+        session: &mut ChatSession,
+        user_input: &str,
+    ) -> Result<String, RustBertError> {
+        let user_message = ChatMessage::new(ChatRole::User, user_input);
+        let prompt_ids = self.build_prompt(session, &user_message)?;
+        let (response_ids, cache, cached_length) =
+            tch::no_grad(|| self.generate_response(session, &prompt_ids))?;
+
+        let mut response = self.model.get_tokenizer().decode(&response_ids, true, true);
+        let stop_sequence = self.template.turn_separator.trim();
+        if !stop_sequence.is_empty() {
+            if let Some(stop_position) = response.find(stop_sequence) {
+                response.truncate(stop_position);
+            }
+        }
+        let response = response.trim().to_string();
+
+        let mut processed_ids = prompt_ids;
+        processed_ids.extend(response_ids);
+        processed_ids.truncate(cached_length);
+        session.cache = cache;
+        session.cached_ids = processed_ids;
+        session.messages.push(user_message);
+        session
+            .messages
+            .push(ChatMessage::new(ChatRole::Assistant, response.as_str()));
+        Ok(response)
+    }
+
+    fn encode(&self, text: &str) -> Vec<i64> {
+        let tokenizer: &TokenizerOption = self.model.get_tokenizer();
+        tokenizer.convert_tokens_to_ids(&tokenizer.tokenize(text))
+    }
+
+    /// Builds the prompt token ids, dropping the oldest turns that do not fit in the token budget.
+    /// Messages are encoded separately so that the ids of past turns are stable across turns.
+    fn build_prompt(
+        &self,
+        session: &ChatSession,
+        user_message: &ChatMessage,
+    ) -> Result<Vec<i64>, RustBertError> {
+        let mut head = self.template.prefix.clone();
+        if let Some(system_prompt) = &session.system_prompt {
+            head.push_str(
+                &self
+                    .template
+                    .render_message(&ChatMessage::new(ChatRole::System, system_prompt.as_str())),
+            );
+        }
+        let head_ids = self.encode(&head);
+        let generation_prompt_ids = self.encode(&self.template.assistant_marker);
+        let message_ids = session
+            .messages
+            .iter()
+            .chain(std::iter::once(user_message))
+            .map(|message| self.encode(&self.template.render_message(message)))
+            .collect::<Vec<Vec<i64>>>();
+
+        let budget = self.max_context_tokens as isize
+            - (self.max_new_tokens + head_ids.len() + generation_prompt_ids.len()) as isize;
+        let message_roles = session
+            .messages
+            .iter()
+            .chain(std::iter::once(user_message))
+            .map(|message| message.role)
+            .collect::<Vec<ChatRole>>();
+        let message_lengths = message_ids.iter().map(Vec::len).collect::<Vec<usize>>();
+        let first_message = first_message_in_budget(&message_roles, &message_lengths, budget)
+            .ok_or_else(|| {
+                RustBertError::ValueError(format!(
+                    "The system prompt and user message do not fit in the context budget of {} tokens",
+                    self.max_context_tokens
+                ))
+            })?;
+
+        let mut prompt_ids = head_ids;
+        for ids in &message_ids[first_message..] {
+            prompt_ids.extend_from_slice(ids);
+        }
+        prompt_ids.extend(generation_prompt_ids);
+        Ok(prompt_ids)
+    }
+
+    /// Generates the response token ids, returning the cache and the number of tokens it covers
+    fn generate_response(
+        &self,
+        session: &mut ChatSession,
+        prompt_ids: &[i64],
+    ) -> Result<(Vec<i64>, Cache, usize), RustBertError> {
+        if prompt_ids.is_empty() {
+            return Err(RustBertError::ValueError(
+                "The chat prompt is empty, at least one token is required to generate a response"
+                    .to_string(),
+            ));
+        }
+        let supports_cache_reuse = matches!(
+            self.model,
+            TextGenerationOption::GPT2(_)
+                | TextGenerationOption::GPTNeo(_)
+                | TextGenerationOption::GPTJ(_)
+                | TextGenerationOption::GPTNeoX(_)
+                | TextGenerationOption::Llama(_)
+                | TextGenerationOption::OPT(_)
+                | TextGenerationOption::Bloom(_)
+        );
+        let common_prefix_length = session
+            .cached_ids
+            .iter()
+            .zip(prompt_ids.iter())
+            .take_while(|(cached, prompt)| cached == prompt)
+            .count()
+            .min(prompt_ids.len() - 1);
+        let past = mem::replace(&mut session.cache, Cache::None);
+
+        let mut all_ids = prompt_ids.to_vec();
+        let mut output = match truncate_cache(past, common_prefix_length) {
+            Some(past) if supports_cache_reuse && common_prefix_length > 0 => {
+                let input_ids = Tensor::of_slice(&prompt_ids[common_prefix_length..])
+                    .unsqueeze(0)
+                    .to(self.device);
+                let position_ids = Tensor::arange_start(
+                    common_prefix_length as i64,
+                    prompt_ids.len() as i64,
+                    (Kind::Int64, self.device),
+                )
+                .unsqueeze(0);
+                let attention_mask =
+                    Tensor::ones(&[1, prompt_ids.len() as i64], (Kind::Int64, self.device));
+                self.forward_with_past(&input_ids, past, &attention_mask, &position_ids)?
+            }
+            _ => self.forward_step(&all_ids, Cache::None)?,
+        };
+        let mut cached_length = all_ids.len();
+
+        let mut response_ids = Vec::with_capacity(self.max_new_tokens);
+        let stop_sequence = self.template.turn_separator.trim();
+        for _ in 0..self.max_new_tokens {
+            let next_token = self.select_next_token(output.lm_logits.select(1, -1));
+            if self.eos_token_ids.contains(&next_token) {
+                break;
+            }
+            response_ids.push(next_token);
+            all_ids.push(next_token);
+            if !stop_sequence.is_empty()
+                && self
+                    .model
+                    .get_tokenizer()
+                    .decode(&response_ids, true, true)
+                    .contains(stop_sequence)
+            {
+                break;
+            }
+            if all_ids.len() >= self.max_context_tokens {
+                break;
+            }
+            output = self.forward_step(&all_ids, output.cache)?;
+            cached_length = all_ids.len();
+        }
+
+        let cache = if supports_cache_reuse {
+            output.cache
+        } else {
+            cached_length = 0;
+            Cache::None
+        };
+        Ok((response_ids, cache, cached_length))
+    }
+
+    fn forward_step(&self, input_ids: &[i64], past: Cache) -> Result<LMModelOutput, RustBertError> {
+        let input_ids = Tensor::of_slice(input_ids).unsqueeze(0).to(self.device);
+        let attention_mask = input_ids.ones_like();
+        match &self.model {
+            TextGenerationOption::GPT2(generator) => {
+                prepared_forward(generator, &input_ids, past, &attention_mask)
+            }
+            TextGenerationOption::GPT(generator) => {
+                prepared_forward(generator, &input_ids, past, &attention_mask)
+            }
+            TextGenerationOption::GPTNeo(generator) => {
+                prepared_forward(generator, &input_ids, past, &attention_mask)
+            }
+            TextGenerationOption::GPTJ(generator) => {
+                prepared_forward(generator, &input_ids, past, &attention_mask)
+            }
+            TextGenerationOption::GPTNeoX(generator) => {
+                prepared_forward(generator, &input_ids, past, &attention_mask)
+            }
+            TextGenerationOption::Llama(generator) => {
+                prepared_forward(generator, &input_ids, past, &attention_mask)
+            }
+            TextGenerationOption::OPT(generator) => {
+                prepared_forward(generator, &input_ids, past, &attention_mask)
+            }
+            TextGenerationOption::Bloom(generator) => {
+                prepared_forward(generator, &input_ids, past, &attention_mask)
+            }
+            TextGenerationOption::XLNet(generator) => {
+                prepared_forward(generator, &input_ids, past, &attention_mask)
+            }
+            TextGenerationOption::Reformer(generator) => {
+                prepared_forward(generator, &input_ids, past, &attention_mask)
+            }
+        }
+    }
+
+    fn forward_with_past(
+        &self,
+        input_ids: &Tensor,
+        past: Cache,
+        attention_mask: &Tensor,
+        position_ids: &Tensor,
+    ) -> Result<LMModelOutput, RustBertError> {
+        let model: &dyn LMHeadModel = match &self.model {
+            TextGenerationOption::GPT2(generator) => generator.get_model(),
+            TextGenerationOption::GPTNeo(generator) => generator.get_model(),
+            TextGenerationOption::GPTJ(generator) => generator.get_model(),
+            TextGenerationOption::GPTNeoX(generator) => generator.get_model(),
+            TextGenerationOption::Llama(generator) => generator.get_model(),
+            TextGenerationOption::OPT(generator) => generator.get_model(),
+            TextGenerationOption::Bloom(generator) => generator.get_model(),
+            _ => {
+                return Err(RustBertError::InvalidConfigurationError(format!(
+                    "Cache reuse is not supported for {:?}",
+                    self.model.model_type()
+                )))
+            }
+        };
+        model.forward_t(
+            Some(input_ids),
+            past,
+            Some(attention_mask),
+            None,
+            Some(position_ids),
+            None,
+            None,
+            None,
+            false,
+        )
+    }
+
+    fn select_next_token(&self, mut next_token_logits: Tensor) -> i64 {
+        if self.do_sample {
+            if self.temperature != 1f64 {
+                next_token_logits /= self.temperature;
+            }
+            match &self.model {
+                TextGenerationOption::GPT2(generator) => generator.top_k_top_p_filtering(
+                    &mut next_token_logits,
+                    self.top_k,
+                    self.top_p,
+                    1,
+                ),
+                TextGenerationOption::GPT(generator) => generator.top_k_top_p_filtering(
+                    &mut next_token_logits,
+                    self.top_k,
+                    self.top_p,
+                    1,
+                ),
+                TextGenerationOption::GPTNeo(generator) => generator.top_k_top_p_filtering(
+                    &mut next_token_logits,
+                    self.top_k,
+                    self.top_p,
+                    1,
+                ),
+                TextGenerationOption::GPTJ(generator) => generator.top_k_top_p_filtering(
+                    &mut next_token_logits,
+                    self.top_k,
+                    self.top_p,
+                    1,
+                ),
+                TextGenerationOption::GPTNeoX(generator) => generator.top_k_top_p_filtering(
+                    &mut next_token_logits,
+                    self.top_k,
+                    self.top_p,
+                    1,
+                ),
+                TextGenerationOption::Llama(generator) => generator.top_k_top_p_filtering(
+                    &mut next_token_logits,
+                    self.top_k,
+                    self.top_p,
+                    1,
+                ),
+                TextGenerationOption::OPT(generator) => generator.top_k_top_p_filtering(
+                    &mut next_token_logits,
+                    self.top_k,
+                    self.top_p,
+                    1,
+                ),
+                TextGenerationOption::Bloom(generator) => generator.top_k_top_p_filtering(
+                    &mut next_token_logits,
+                    self.top_k,
+                    self.top_p,
+                    1,
+                ),
+                TextGenerationOption::XLNet(generator) => generator.top_k_top_p_filtering(
+                    &mut next_token_logits,
+                    self.top_k,
+                    self.top_p,
+                    1,
+                ),
+                TextGenerationOption::Reformer(generator) => generator.top_k_top_p_filtering(
+                    &mut next_token_logits,
+                    self.top_k,
+                    self.top_p,
+                    1,
+                ),
+            }
+            next_token_logits
+                .softmax(-1, Kind::Float)
+                .multinomial(1, false)
+                .int64_value(&[0, 0])
+        } else {
+            next_token_logits.argmax(-1, false).int64_value(&[0])
+        }
+    }
+}
+
+fn generation_limits<G, T, V, U>(generator: &G) -> (Vec<i64>, usize)
+where
+    G: PrivateLanguageGenerator<T, V, U>,
+    T: LMHeadModel,
+    V: Vocab,
+    U: Tokenizer<V>,
+{
+    (
+        generator.get_eos_ids().cloned().unwrap_or_default(),
+        generator.get_max_positions_embeddings() as usize,
+    )
+}
+
+fn prepared_forward<G, T, V, U>(
+    generator: &G,
+    input_ids: &Tensor,
+    past: Cache,
+    attention_mask: &Tensor,
+) -> Result<LMModelOutput, RustBertError>
+where
+    G: PrivateLanguageGenerator<T, V, U>,
+    T: LMHeadModel,
+    V: Vocab,
+    U: Tokenizer<V>,
+{
+    let prepared_input = generator.prepare_inputs_for_generation(
+        input_ids.copy(),
+        None,
+        past,
+        attention_mask.copy(),
+    );
+    generator.get_model().forward_t(
+        prepared_input.prepared_input.as_ref(),
+        prepared_input.prepared_past,
+        prepared_input.prepared_attention_mask.as_ref(),
+        None,
+        prepared_input.prepared_position_ids.as_ref(),
+        None,
+        prepared_input.prepared_encoder_output,
+        prepared_input.prepared_decoder_input.as_ref(),
+        false,
+    )
+}
+
+/// Truncates the past keys and values to the first `length` positions, returning `None` for
+/// caches that cannot be truncated.
+fn truncate_cache(past: Cache, length: usize) -> Option<Cache> {
+    let length = length as i64;
+    match past {
+        Cache::GPT2Cache(Some(layer_states)) => Some(Cache::GPT2Cache(Some(
+            layer_states
+                .into_iter()
+                .map(|layer_state| layer_state.narrow(-2, 0, length))
+                .collect(),
+        ))),
+        Cache::GPTNeoCache(Some(layer_states)) => Some(Cache::GPTNeoCache(Some(
+            layer_states
+                .into_iter()
+                .map(|layer_state| {
+                    layer_state.map(|mut layer_state| {
+                        layer_state.prev_key = layer_state.prev_key.narrow(-2, 0, length);
+                        layer_state.prev_value = layer_state
+                            .prev_value
+                            .map(|value| value.narrow(-2, 0, length));
+                        layer_state
+                    })
+                })
+                .collect(),
+        ))),
+        Cache::LlamaCache(Some(layer_states)) => Some(Cache::LlamaCache(Some(
+            layer_states
+                .into_iter()
+                .map(|layer_state| {
+                    layer_state.map(|mut layer_state| {
+                        layer_state.prev_key = layer_state.prev_key.narrow(-2, 0, length);
+                        layer_state.prev_value = layer_state.prev_value.narrow(-2, 0, length);
+                        layer_state
+                    })
+                })
+                .collect(),
+        ))),
+        Cache::OPTCache(Some(layer_states)) => Some(Cache::OPTCache(Some(
+            layer_states
+                .into_iter()
+                .map(|layer_state| {
+                    layer_state.map(|mut layer_state| {
+                        layer_state.prev_key = layer_state.prev_key.narrow(-2, 0, length);
+                        layer_state.prev_value = layer_state.prev_value.narrow(-2, 0, length);
+                        layer_state
+                    })
+                })
+                .collect(),
+        ))),
+        Cache::BloomCache(Some(layer_states)) => Some(Cache::BloomCache(Some(
+            layer_states
+                .into_iter()
+                .map(|layer_state| {
+                    layer_state.map(|mut layer_state| {
+                        layer_state.prev_key = layer_state.prev_key.narrow(-2, 0, length);
+                        layer_state.prev_value = layer_state.prev_value.narrow(-2, 0, length);
+                        layer_state
+                    })
+                })
+                .collect(),
+        ))),
+        Cache::GPTJCache(Some(layer_states)) => Some(Cache::GPTJCache(Some(
+            layer_states
+                .into_iter()
+                .map(|layer_state| {
+                    layer_state.map(|mut layer_state| {
+                        layer_state.prev_key = layer_state.prev_key.narrow(-2, 0, length);
+                        layer_state.prev_value = layer_state.prev_value.narrow(-2, 0, length);
+                        layer_state
+                    })
+                })
+                .collect(),
+        ))),
+        Cache::GPTNeoXCache(Some(layer_states)) => Some(Cache::GPTNeoXCache(Some(
+            layer_states
+                .into_iter()
+                .map(|layer_state| {
+                    layer_state.map(|mut layer_state| {
+                        layer_state.prev_key = layer_state.prev_key.narrow(-2, 0, length);
+                        layer_state.prev_value = layer_state.prev_value.narrow(-2, 0, length);
+                        layer_state
+                    })
+                })
+                .collect(),
+        ))),
+        _ => None,
+    }
+}
+
+/// Returns the index of the first message to keep so that the messages fit in the token budget.
+/// Turns are dropped from the oldest, and the kept history always starts with a user message.
+/// Returns `None` if the last message alone does not fit in the budget.
+fn first_message_in_budget(roles: &[ChatRole], lengths: &[usize], budget: isize) -> Option<usize> {
+    let mut total_length = lengths.iter().sum::<usize>() as isize;
+    let mut first_message = 0;
+    while total_length > budget
+        || (first_message < roles.len() - 1 && roles[first_message] != ChatRole::User)
+    {
+        if first_message == roles.len() - 1 {
+            return None;
+        }
+        total_length -= lengths[first_message] as isize;
+        first_message += 1;
+    }
+    Some(first_message)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::gpt2::{GPT2LMHeadModel, Gpt2Config};
+    use crate::llama::{LlamaConfig, LlamaForCausalLM};
+    use tch::nn::VarStore;
+    use tch::no_grad;
+
+    #[test]
+    #[ignore] // no need to run, compilation is enough to verify it is Send
+    fn test() {
+        let config = ChatConfig::default();
+        let _: Box<dyn Send> = Box::new(ChatModel::new(config));
+    }
+
+    #[test]
+    fn test_chat_template() {
+        let messages = [
+            ChatMessage::new(ChatRole::System, "Be concise."),
+            ChatMessage::new(ChatRole::User, "Hi"),
+            ChatMessage::new(ChatRole::Assistant, "Hello!"),
+            ChatMessage::new(ChatRole::User, "How are you?"),
+        ];
+        assert_eq!(
+            ChatTemplate::chatml().render(&messages, true),
+            "<|im_start|>system\nBe concise.<|im_end|>\n<|im_start|>user\nHi<|im_end|>\n\
+             <|im_start|>assistant\nHello!<|im_end|>\n<|im_start|>user\nHow are you?<|im_end|>\n\
+             <|im_start|>assistant\n"
+        );
+    }
+
+    fn assert_cache_reuse_matches_recompute(model: &dyn LMHeadModel) {
+        let forward = |ids: &[i64], past: Cache, position_offset: i64| {
+            let total_length = position_offset + ids.len() as i64;
+            let input_ids = Tensor::of_slice(ids).unsqueeze(0);
+            let attention_mask = Tensor::ones(&[1, total_length], (Kind::Int64, Device::Cpu));
+            let position_ids =
+                Tensor::arange_start(position_offset, total_length, (Kind::Int64, Device::Cpu))
+                    .unsqueeze(0);
+            no_grad(|| {
+                model.forward_t(
+                    Some(&input_ids),
+                    past,
+                    Some(&attention_mask),
+                    None,
+                    Some(&position_ids),
+                    None,
+                    None,
+                    None,
+                    false,
+                )
+            })
+            .unwrap()
+        };
+        let prompt_ids = [5i64, 3, 8, 1, 9, 4, 7];
+        let full_output = forward(&prompt_ids, Cache::None, 0);
+
+        // Cache of a previous turn sharing its first 4 tokens with the new prompt
+        let previous_output = forward(&[5, 3, 8, 1, 2, 2], Cache::None, 0);
+        let past = truncate_cache(previous_output.cache, 4).unwrap();
+        let reused_output = forward(&prompt_ids[4..], past, 4);
+
+        assert!(reused_output.lm_logits.select(1, -1).allclose(
+            &full_output.lm_logits.select(1, -1),
+            1e-5,
+            1e-5,
+            false
+        ));
+    }
+
+    #[test]
+    fn test_cache_reuse() {
+        let var_store = VarStore::new(Device::Cpu);
+        let config = Gpt2Config {
+            n_ctx: 16,
+            n_embd: 8,
+            n_head: 2,
+            n_layer: 2,
+            n_positions: 16,
+            vocab_size: 16,
+            ..Default::default()
+        };
+        let model = GPT2LMHeadModel::new(var_store.root(), &config);
+        assert_cache_reuse_matches_recompute(&model);
+
+        let var_store = VarStore::new(Device::Cpu);
+        let config = LlamaConfig {
+            vocab_size: 16,
+            hidden_size: 16,
+            intermediate_size: 24,
+            num_hidden_layers: 2,
+            num_attention_heads: 4,
+            num_key_value_heads: Some(2),
+            max_position_embeddings: 16,
+            ..Default::default()
+        };
+        let model = LlamaForCausalLM::new(var_store.root(), &config);
+        assert_cache_reuse_matches_recompute(&model);
+    }
+
+    #[test]
+    fn test_history_truncation() {
+        let roles = [
+            ChatRole::User,
+            ChatRole::Assistant,
+            ChatRole::User,
+            ChatRole::Assistant,
+            ChatRole::User,
+        ];
+        let lengths = [10, 20, 5, 5, 10];
+        assert_eq!(first_message_in_budget(&roles, &lengths, 50), Some(0));
+        assert_eq!(first_message_in_budget(&roles, &lengths, 49), Some(2));
+        // The history never starts with an assistant response
+        assert_eq!(first_message_in_budget(&roles, &lengths, 19), Some(4));
+        assert_eq!(first_message_in_budget(&roles, &lengths, 9), None);
+    }
+}
@@ -19,7 +19,9 @@
 use crate::albert::AlbertConfig;
 use crate::bart::BartConfig;
 use crate::bert::BertConfig;
+use crate::bloom::BloomConfig;
 use crate::common::error::RustBertError;
+use crate::common::loss::ProblemType;
 use crate::deberta::DebertaConfig;
 use crate::deberta_v2::DebertaV2Config;
 use crate::distilbert::DistilBertConfig;
@@ -27,12 +29,16 @@ use crate::electra::ElectraConfig;
 use crate::fnet::FNetConfig;
 use crate::gpt2::Gpt2Config;
 use crate::gpt_neo::GptNeoConfig;
+use crate::gpt_neox::GptNeoXConfig;
+use crate::gptj::GptJConfig;
+use crate::llama::{LlamaConfig, LlamaTokenizer, LLAMA_BOS_TOKEN, LLAMA_EOS_TOKEN};
 use crate::longformer::LongformerConfig;
 use crate::m2m_100::M2M100Config;
 use crate::marian::MarianConfig;
 use crate::mbart::MBartConfig;
 use crate::mobilebert::MobileBertConfig;
 use crate::openai_gpt::OpenAiGptConfig;
+use crate::opt::OptConfig;
 use crate::pegasus::PegasusConfig;
 use crate::prophetnet::ProphetNetConfig;
 use crate::reformer::ReformerConfig;
@@ -46,11 +52,13 @@ use rust_tokenizers::tokenizer::{
     OpenAiGptTokenizer, PegasusTokenizer, ProphetNetTokenizer, ReformerTokenizer, RobertaTokenizer,
     T5Tokenizer, Tokenizer, TruncationStrategy, XLMRobertaTokenizer, XLNetTokenizer,
 };
-use rust_tokenizers::vocab::Vocab;
+use rust_tokenizers::vocab::{BpePairVocab, Gpt2Vocab, Vocab};
 use rust_tokenizers::{TokenIdsWithOffsets, TokenizedInput, TokensWithOffsets};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::convert::TryFrom;
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
 use std::path::Path;
 
 #[derive(Clone, Copy, Serialize, Deserialize, Debug, PartialEq, Eq)]
@@ -61,15 +69,20 @@ pub enum ModelType {
     Bert,
     #[serde(alias = "distilbert")]
     DistilBert,
+    #[serde(alias = "deberta")]
     Deberta,
+    #[serde(alias = "deberta-v2")]
     DebertaV2,
     #[serde(alias = "roberta")]
     Roberta,
+    #[serde(alias = "xlm-roberta")]
     XLMRoberta,
+    #[serde(alias = "electra")]
     Electra,
     Marian,
+    #[serde(alias = "mobilebert")]
     MobileBert,
-    #[serde(alias = "t5")]
+    #[serde(alias = "t5", alias = "longt5")]
     T5,
     #[serde(alias = "albert")]
     Albert,
@@ -78,12 +91,24 @@ pub enum ModelType {
     OpenAiGpt,
     Reformer,
     ProphetNet,
+    #[serde(alias = "longformer")]
     Longformer,
     Pegasus,
     GPTNeo,
+    #[serde(alias = "gptj")]
+    GPTJ,
+    #[serde(alias = "gpt_neox")]
+    GPTNeoX,
     MBart,
     M2M100,
+    #[serde(alias = "fnet")]
     FNet,
+    #[serde(alias = "llama")]
+    Llama,
+    #[serde(alias = "opt")]
+    OPT,
+    #[serde(alias = "bloom")]
+    Bloom,
 }
 
 /// # Abstraction that holds a model configuration, can be of any of the supported models
@@ -126,12 +151,77 @@ pub enum ConfigOption {
     Pegasus(PegasusConfig),
     /// GPT-Neo configuration
     GPTNeo(GptNeoConfig),
+    /// GPT-J configuration
+    GPTJ(GptJConfig),
+    /// GPT-NeoX configuration
+    GPTNeoX(GptNeoXConfig),
     /// MBart configuration
     MBart(MBartConfig),
     /// M2M100 configuration
     M2M100(M2M100Config),
     /// FNet configuration
     FNet(FNetConfig),
+    /// LLaMA configuration
+    Llama(LlamaConfig),
+    /// OPT configuration
+    OPT(OptConfig),
+    /// BLOOM configuration
+    Bloom(BloomConfig),
+}
+
+const OPT_EOS_TOKEN: &str = "</s>";
+const OPT_PAD_TOKEN: &str = "<pad>";
+const BLOOM_UNK_TOKEN: &str = "<unk>";
+const BLOOM_BOS_TOKEN: &str = "<s>";
+const BLOOM_EOS_TOKEN: &str = "</s>";
+const BLOOM_PAD_TOKEN: &str = "<pad>";
+
+/// Builds a byte-level BPE tokenizer from a `vocab.json` vocabulary that does not contain the GPT2
+/// special token (`<|endoftext|>`), registering the given unknown, beginning/end of sequence and padding tokens.
+fn gpt2_tokenizer_with_special_tokens(
+    vocab_path: &str,
+    merges_path: &str,
+    lower_case: bool,
+    unk_token: &str,
+    bos_token: &str,
+    eos_token: &str,
+    pad_token: &str,
+) -> Result<Gpt2Tokenizer, RustBertError> {
+    let values: HashMap<String, i64> =
+        serde_json::from_reader(BufReader::new(File::open(vocab_path)?))
+            .map_err(|error| RustBertError::TokenizerError(error.to_string()))?;
+    let indices = values
+        .iter()
+        .map(|(token, &token_id)| (token_id, token.clone()))
+        .collect();
+    let mut vocab = Gpt2Vocab {
+        values,
+        indices,
+        special_token_map: Default::default(),
+        special_values: HashMap::new(),
+        special_indices: HashMap::new(),
+    };
+    vocab.special_token_map.unk_token = unk_token.to_string();
+    vocab.special_token_map.bos_token = Some(bos_token.to_string());
+    vocab.special_token_map.eos_token = Some(eos_token.to_string());
+    vocab.special_token_map.pad_token = Some(pad_token.to_string());
+    for special_token in [unk_token, bos_token, eos_token, pad_token].iter() {
+        let token_id = *vocab.values.get(*special_token).ok_or_else(|| {
+            RustBertError::TokenizerError(format!(
+                "The special token {special_token} could not be found in the vocabulary"
+            ))
+        })?;
+        vocab
+            .special_values
+            .insert(special_token.to_string(), token_id);
+        vocab
+            .special_indices
+            .insert(token_id, special_token.to_string());
+    }
+    let merges = BpePairVocab::from_file(merges_path)?;
+    Ok(Gpt2Tokenizer::from_existing_vocab_and_merges(
+        vocab, merges, lower_case,
+    ))
 }
 
 /// # Abstraction that holds a particular tokenizer, can be of any of the supported models
@@ -172,6 +262,12 @@ pub enum TokenizerOption {
     FNet(FNetTokenizer),
     /// Bart Tokenizer
     Bart(RobertaTokenizer),
+    /// LLaMA Tokenizer
+    Llama(LlamaTokenizer),
+    /// OPT Tokenizer (GPT2 tokenizer with the OPT special tokens)
+    OPT(Gpt2Tokenizer),
+    /// BLOOM Tokenizer (GPT2 tokenizer with the BLOOM special tokens)
+    Bloom(Gpt2Tokenizer),
 }
 
 impl ConfigOption {
@@ -191,6 +287,8 @@ impl ConfigOption {
             ModelType::XLNet => ConfigOption::XLNet(XLNetConfig::from_file(path)),
             ModelType::GPT2 => ConfigOption::GPT2(Gpt2Config::from_file(path)),
             ModelType::GPTNeo => ConfigOption::GPTNeo(GptNeoConfig::from_file(path)),
+            ModelType::GPTJ => ConfigOption::GPTJ(GptJConfig::from_file(path)),
+            ModelType::GPTNeoX => ConfigOption::GPTNeoX(GptNeoXConfig::from_file(path)),
             ModelType::OpenAiGpt => ConfigOption::OpenAiGpt(OpenAiGptConfig::from_file(path)),
             ModelType::Reformer => ConfigOption::Reformer(ReformerConfig::from_file(path)),
             ModelType::ProphetNet => ConfigOption::ProphetNet(ProphetNetConfig::from_file(path)),
@@ -202,6 +300,9 @@ impl ConfigOption {
             ModelType::MBart => ConfigOption::MBart(MBartConfig::from_file(path)),
             ModelType::M2M100 => ConfigOption::M2M100(M2M100Config::from_file(path)),
             ModelType::FNet => ConfigOption::FNet(FNetConfig::from_file(path)),
+            ModelType::Llama => ConfigOption::Llama(LlamaConfig::from_file(path)),
+            ModelType::OPT => ConfigOption::OPT(OptConfig::from_file(path)),
+            ModelType::Bloom => ConfigOption::Bloom(BloomConfig::from_file(path)),
         }
     }
 
@@ -279,14 +380,234 @@ impl ConfigOption {
             Self::OpenAiGpt(_) => panic!("OpenAI GPT does not use a label mapping"),
             Self::GPT2(_) => panic!("GPT2 does not use a label mapping"),
             Self::GPTNeo(_) => panic!("GPT-Neo does not use a label mapping"),
+            Self::GPTJ(_) => panic!("GPT-J does not use a label mapping"),
+            Self::GPTNeoX(_) => panic!("GPT-NeoX does not use a label mapping"),
+            Self::Llama(_) => panic!("LLaMA does not use a label mapping"),
+            Self::OPT(_) => panic!("OPT does not use a label mapping"),
+            Self::Bloom(_) => panic!("BLOOM does not use a label mapping"),
             Self::Pegasus(_) => panic!("Pegasus does not use a label mapping"),
         }
     }
 
+    /// Sets the label mapping (`id2label` and `label2id`) of the configuration, for example to fine-tune
+    /// a classification head on a new set of labels.
+    ///
+    /// # Arguments
+    ///
+    /// * `id2label` - `HashMap<i64, String>` mapping the class indices to the label names
+    pub fn set_label_mapping(
+        &mut self,
+        id2label: HashMap<i64, String>,
+    ) -> Result<(), RustBertError> {
+        let label2id = id2label
+            .iter()
+            .map(|(id, label)| (label.clone(), *id))
+            .collect::<HashMap<String, i64>>();
+        match self {
+            Self::Bart(config) => {
+                config.num_labels = Some(id2label.len() as i64);
+                config.label2id = Some(label2id);
+                config.id2label = Some(id2label);
+            }
+            Self::Bert(config) => {
+                config.label2id = Some(label2id);
+                config.id2label = Some(id2label);
+            }
+            Self::Deberta(config) => {
+                config.label2id = Some(label2id);
+                config.id2label = Some(id2label);
+            }
+            Self::DebertaV2(config) => {
+                config.label2id = Some(label2id);
+                config.id2label = Some(id2label);
+            }
+            Self::DistilBert(config) => {
+                config.label2id = Some(label2id);
+                config.id2label = Some(id2label);
+            }
+            Self::Electra(config) => {
+                config.label2id = Some(label2id);
+                config.id2label = Some(id2label);
+            }
+            Self::Marian(config) => {
+                config.num_labels = Some(id2label.len() as i64);
+                config.label2id = Some(label2id);
+                config.id2label = Some(id2label);
+            }
+            Self::MobileBert(config) => {
+                config.label2id = Some(label2id);
+                config.id2label = Some(id2label);
+            }
+            Self::Albert(config) => {
+                config.label2id = Some(label2id);
+                config.id2label = Some(id2label);
+            }
+            Self::XLNet(config) => {
+                config.label2id = Some(label2id);
+                config.id2label = Some(id2label);
+            }
+            Self::Reformer(config) => {
+                config.label2id = Some(label2id);
+                config.id2label = Some(id2label);
+            }
+            Self::ProphetNet(config) => {
+                config.label2id = Some(label2id);
+                config.id2label = Some(id2label);
+            }
+            Self::Longformer(config) => {
+                config.label2id = Some(label2id);
+                config.id2label = Some(id2label);
+            }
+            Self::MBart(config) => {
+                config.label2id = Some(label2id);
+                config.id2label = Some(id2label);
+            }
+            Self::M2M100(config) => {
+                config.label2id = Some(label2id);
+                config.id2label = Some(id2label);
+            }
+            Self::FNet(config) => {
+                config.label2id = Some(label2id);
+                config.id2label = Some(id2label);
+            }
+            Self::Roberta(config) => {
+                config.label2id = Some(label2id);
+                config.id2label = Some(id2label);
+            }
+            Self::T5(_)
+            | Self::OpenAiGpt(_)
+            | Self::GPT2(_)
+            | Self::GPTNeo(_)
+            | Self::GPTJ(_)
+            | Self::GPTNeoX(_)
+            | Self::Llama(_)
+            | Self::OPT(_)
+            | Self::Bloom(_)
+            | Self::Pegasus(_) => {
+                return Err(RustBertError::InvalidConfigurationError(
+                    "This model configuration does not support a label mapping".to_string(),
+                ))
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the problem type of the sequence classification heads (`problem_type` field of the
+    /// configuration file), if set.
+    pub fn get_problem_type(&self) -> Option<ProblemType> {
+        match self {
+            Self::Bart(config) | Self::Marian(config) => config.problem_type,
+            Self::Bert(config) | Self::Roberta(config) => config.problem_type,
+            Self::MBart(config) | Self::M2M100(config) | Self::Pegasus(config) => {
+                config.problem_type
+            }
+            Self::Deberta(config) => config.problem_type,
+            Self::DebertaV2(config) => config.problem_type,
+            Self::DistilBert(config) => config.problem_type,
+            Self::MobileBert(config) => config.problem_type,
+            Self::Albert(config) => config.problem_type,
+            Self::XLNet(config) => config.problem_type,
+            Self::Reformer(config) => config.problem_type,
+            Self::Longformer(config) => config.problem_type,
+            Self::FNet(config) => config.problem_type,
+            Self::Electra(_)
+            | Self::ProphetNet(_)
+            | Self::T5(_)
+            | Self::OpenAiGpt(_)
+            | Self::GPT2(_)
+            | Self::GPTNeo(_)
+            | Self::GPTJ(_)
+            | Self::GPTNeoX(_)
+            | Self::Llama(_)
+            | Self::OPT(_)
+            | Self::Bloom(_) => None,
+        }
+    }
+
+    /// Sets the problem type of the configuration, defining the loss of the sequence classification heads
+    /// built from it.
+    ///
+    /// # Arguments
+    ///
+    /// * `problem_type` - `ProblemType` of the sequence classification task
+    pub fn set_problem_type(&mut self, problem_type: ProblemType) -> Result<(), RustBertError> {
+        let field = match self {
+            Self::Bart(config) | Self::Marian(config) => &mut config.problem_type,
+            Self::Bert(config) | Self::Roberta(config) => &mut config.problem_type,
+            Self::MBart(config) | Self::M2M100(config) | Self::Pegasus(config) => {
+                &mut config.problem_type
+            }
+            Self::Deberta(config) => &mut config.problem_type,
+            Self::DebertaV2(config) => &mut config.problem_type,
+            Self::DistilBert(config) => &mut config.problem_type,
+            Self::MobileBert(config) => &mut config.problem_type,
+            Self::Albert(config) => &mut config.problem_type,
+            Self::XLNet(config) => &mut config.problem_type,
+            Self::Reformer(config) => &mut config.problem_type,
+            Self::Longformer(config) => &mut config.problem_type,
+            Self::FNet(config) => &mut config.problem_type,
+            Self::Electra(_)
+            | Self::ProphetNet(_)
+            | Self::T5(_)
+            | Self::OpenAiGpt(_)
+            | Self::GPT2(_)
+            | Self::GPTNeo(_)
+            | Self::GPTJ(_)
+            | Self::GPTNeoX(_)
+            | Self::Llama(_)
+            | Self::OPT(_)
+            | Self::Bloom(_) => return Err(RustBertError::InvalidConfigurationError(
+                "This model configuration does not support a sequence classification problem type"
+                    .to_string(),
+            )),
+        };
+        *field = Some(problem_type);
+        Ok(())
+    }
+
+    /// Saves the configuration to a JSON file, in a format that can be read back with `ConfigOption::from_file`.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - `Path` of the configuration JSON file to write.
+    pub fn to_file<P: AsRef<Path>>(&self, path: P) -> Result<(), RustBertError> {
+        let writer = BufWriter::new(File::create(path)?);
+        match self {
+            Self::Bart(config) => serde_json::to_writer_pretty(writer, config),
+            Self::Bert(config) => serde_json::to_writer_pretty(writer, config),
+            Self::DistilBert(config) => serde_json::to_writer_pretty(writer, config),
+            Self::Deberta(config) => serde_json::to_writer_pretty(writer, config),
+            Self::DebertaV2(config) => serde_json::to_writer_pretty(writer, config),
+            Self::Electra(config) => serde_json::to_writer_pretty(writer, config),
+            Self::Marian(config) => serde_json::to_writer_pretty(writer, config),
+            Self::MobileBert(config) => serde_json::to_writer_pretty(writer, config),
+            Self::OpenAiGpt(config) => serde_json::to_writer_pretty(writer, config),
+            Self::T5(config) => serde_json::to_writer_pretty(writer, config),
+            Self::Albert(config) => serde_json::to_writer_pretty(writer, config),
+            Self::XLNet(config) => serde_json::to_writer_pretty(writer, config),
+            Self::GPT2(config) => serde_json::to_writer_pretty(writer, config),
+            Self::Reformer(config) => serde_json::to_writer_pretty(writer, config),
+            Self::Roberta(config) => serde_json::to_writer_pretty(writer, config),
+            Self::ProphetNet(config) => serde_json::to_writer_pretty(writer, config),
+            Self::Longformer(config) => serde_json::to_writer_pretty(writer, config),
+            Self::Pegasus(config) => serde_json::to_writer_pretty(writer, config),
+            Self::GPTNeo(config) => serde_json::to_writer_pretty(writer, config),
+            Self::GPTJ(config) => serde_json::to_writer_pretty(writer, config),
+            Self::GPTNeoX(config) => serde_json::to_writer_pretty(writer, config),
+            Self::MBart(config) => serde_json::to_writer_pretty(writer, config),
+            Self::M2M100(config) => serde_json::to_writer_pretty(writer, config),
+            Self::FNet(config) => serde_json::to_writer_pretty(writer, config),
+            Self::Llama(config) => serde_json::to_writer_pretty(writer, config),
+            Self::OPT(config) => serde_json::to_writer_pretty(writer, config),
+            Self::Bloom(config) => serde_json::to_writer_pretty(writer, config),
+        }
+        .map_err(|error| RustBertError::IOError(error.to_string()))
+    }
+
     pub fn get_max_len(&self) -> Option<i64> {
         match self {
             Self::Bart(config) => Some(config.max_position_embeddings),
-            Self::Bert(config) => Some(config.max_position_embeddings),
+            Self::Bert(config) | Self::Roberta(config) => config.max_absolute_position(),
             Self::Deberta(config) => Some(config.max_position_embeddings),
             Self::DebertaV2(config) => Some(config.max_position_embeddings),
             Self::DistilBert(config) => Some(config.max_position_embeddings),
@@ -303,10 +624,14 @@ impl ConfigOption {
             Self::Pegasus(config) => Some(config.max_position_embeddings),
             Self::OpenAiGpt(config) => Some(config.n_positions),
             Self::GPTNeo(config) => Some(config.max_position_embeddings),
+            Self::GPTJ(config) => Some(config.n_positions),
+            Self::GPTNeoX(config) => Some(config.max_position_embeddings),
             Self::MBart(config) => Some(config.max_position_embeddings),
             Self::M2M100(config) => Some(config.max_position_embeddings),
             Self::FNet(config) => Some(config.max_position_embeddings),
-            Self::Roberta(config) => Some(config.max_position_embeddings),
+            Self::Llama(config) => Some(config.max_position_embeddings),
+            Self::OPT(config) => Some(config.max_position_embeddings),
+            Self::Bloom(_) => None,
         }
     }
 }
@@ -353,6 +678,48 @@ impl TryFrom<&ConfigOption> for AlbertConfig {
     }
 }
 
+impl TryFrom<&ConfigOption> for DebertaConfig {
+    type Error = RustBertError;
+
+    fn try_from(config: &ConfigOption) -> Result<Self, Self::Error> {
+        if let ConfigOption::Deberta(config) = config {
+            Ok(config.clone())
+        } else {
+            Err(RustBertError::InvalidConfigurationError(
+                "You can only supply a DebertaConfig for DeBERTa!".to_string(),
+            ))
+        }
+    }
+}
+
+impl TryFrom<&ConfigOption> for DebertaV2Config {
+    type Error = RustBertError;
+
+    fn try_from(config: &ConfigOption) -> Result<Self, Self::Error> {
+        if let ConfigOption::DebertaV2(config) = config {
+            Ok(config.clone())
+        } else {
+            Err(RustBertError::InvalidConfigurationError(
+                "You can only supply a DebertaV2Config for DeBERTa V2!".to_string(),
+            ))
+        }
+    }
+}
+
+impl TryFrom<&ConfigOption> for ElectraConfig {
+    type Error = RustBertError;
+
+    fn try_from(config: &ConfigOption) -> Result<Self, Self::Error> {
+        if let ConfigOption::Electra(config) = config {
+            Ok(config.clone())
+        } else {
+            Err(RustBertError::InvalidConfigurationError(
+                "You can only supply an ElectraConfig for Electra!".to_string(),
+            ))
+        }
+    }
+}
+
 impl TryFrom<&ConfigOption> for T5Config {
     type Error = RustBertError;
 
@@ -367,6 +734,48 @@ impl TryFrom<&ConfigOption> for T5Config {
     }
 }
 
+impl TryFrom<&ConfigOption> for LongformerConfig {
+    type Error = RustBertError;
+
+    fn try_from(config: &ConfigOption) -> Result<Self, Self::Error> {
+        if let ConfigOption::Longformer(config) = config {
+            Ok(config.clone())
+        } else {
+            Err(RustBertError::InvalidConfigurationError(
+                "You can only supply a LongformerConfig for Longformer!".to_string(),
+            ))
+        }
+    }
+}
+
+impl TryFrom<&ConfigOption> for MobileBertConfig {
+    type Error = RustBertError;
+
+    fn try_from(config: &ConfigOption) -> Result<Self, Self::Error> {
+        if let ConfigOption::MobileBert(config) = config {
+            Ok(config.clone())
+        } else {
+            Err(RustBertError::InvalidConfigurationError(
+                "You can only supply a MobileBertConfig for MobileBert!".to_string(),
+            ))
+        }
+    }
+}
+
+impl TryFrom<&ConfigOption> for FNetConfig {
+    type Error = RustBertError;
+
+    fn try_from(config: &ConfigOption) -> Result<Self, Self::Error> {
+        if let ConfigOption::FNet(config) = config {
+            Ok(config.clone())
+        } else {
+            Err(RustBertError::InvalidConfigurationError(
+                "You can only supply an FNetConfig for FNet!".to_string(),
+            ))
+        }
+    }
+}
+
 impl TokenizerOption {
     /// Interface method to load a tokenizer from file
     pub fn from_file(
@@ -547,11 +956,13 @@ impl TokenizerOption {
                 }
                 TokenizerOption::Reformer(ReformerTokenizer::from_file(vocab_path, lower_case)?)
             }
-            ModelType::GPT2 | ModelType::GPTNeo => TokenizerOption::GPT2(Gpt2Tokenizer::from_file(
-                vocab_path,
-                merges_path.expect("No merges specified!"),
-                lower_case,
-            )?),
+            ModelType::GPT2 | ModelType::GPTNeo | ModelType::GPTJ | ModelType::GPTNeoX => {
+                TokenizerOption::GPT2(Gpt2Tokenizer::from_file(
+                    vocab_path,
+                    merges_path.expect("No merges specified!"),
+                    lower_case,
+                )?)
+            }
             ModelType::OpenAiGpt => TokenizerOption::OpenAiGpt(OpenAiGptTokenizer::from_file(
                 vocab_path,
                 merges_path.expect("No merges specified!"),
@@ -627,6 +1038,58 @@ impl TokenizerOption {
                 lower_case,
                 strip_accents.unwrap_or(false),
             )?),
+            ModelType::Llama => {
+                if let Some(add_prefix_space) = add_prefix_space {
+                    return Err(RustBertError::InvalidConfigurationError(format!(
+                        "Optional input `add_prefix_space` set to value {} but cannot be used by {:?}",
+                        add_prefix_space, model_type
+                    )));
+                }
+                if let Some(strip_accents) = strip_accents {
+                    return Err(RustBertError::InvalidConfigurationError(format!(
+                        "Optional input `strip_accents` set to value {} but cannot be used by {:?}",
+                        strip_accents, model_type
+                    )));
+                }
+                TokenizerOption::Llama(LlamaTokenizer::from_file(vocab_path, lower_case)?)
+            }
+            ModelType::OPT | ModelType::Bloom => {
+                if let Some(add_prefix_space) = add_prefix_space {
+                    return Err(RustBertError::InvalidConfigurationError(format!(
+                        "Optional input `add_prefix_space` set to value {} but cannot be used by {:?}",
+                        add_prefix_space, model_type
+                    )));
+                }
+                if let Some(strip_accents) = strip_accents {
+                    return Err(RustBertError::InvalidConfigurationError(format!(
+                        "Optional input `strip_accents` set to value {} but cannot be used by {:?}",
+                        strip_accents, model_type
+                    )));
+                }
+                let merges_path = merges_path.expect("No merges specified!");
+                if model_type == ModelType::OPT {
+                    // OPT uses the end of sequence token as beginning of sequence and unknown token
+                    TokenizerOption::OPT(gpt2_tokenizer_with_special_tokens(
+                        vocab_path,
+                        merges_path,
+                        lower_case,
+                        OPT_EOS_TOKEN,
+                        OPT_EOS_TOKEN,
+                        OPT_EOS_TOKEN,
+                        OPT_PAD_TOKEN,
+                    )?)
+                } else {
+                    TokenizerOption::Bloom(gpt2_tokenizer_with_special_tokens(
+                        vocab_path,
+                        merges_path,
+                        lower_case,
+                        BLOOM_UNK_TOKEN,
+                        BLOOM_BOS_TOKEN,
+                        BLOOM_EOS_TOKEN,
+                        BLOOM_PAD_TOKEN,
+                    )?)
+                }
+            }
         };
         Ok(tokenizer)
     }
@@ -652,6 +1115,9 @@ impl TokenizerOption {
             Self::MBart50(_) => ModelType::MBart,
             Self::M2M100(_) => ModelType::M2M100,
             Self::FNet(_) => ModelType::FNet,
+            Self::Llama(_) => ModelType::Llama,
+            Self::OPT(_) => ModelType::OPT,
+            Self::Bloom(_) => ModelType::Bloom,
         }
     }
 
@@ -737,13 +1203,15 @@ impl TokenizerOption {
                 truncation_strategy,
                 stride,
             ),
-            Self::GPT2(ref tokenizer) => MultiThreadedTokenizer::encode_list(
-                tokenizer,
-                text_list,
-                max_len,
-                truncation_strategy,
-                stride,
-            ),
+            Self::GPT2(ref tokenizer) | Self::OPT(ref tokenizer) | Self::Bloom(ref tokenizer) => {
+                MultiThreadedTokenizer::encode_list(
+                    tokenizer,
+                    text_list,
+                    max_len,
+                    truncation_strategy,
+                    stride,
+                )
+            }
             Self::OpenAiGpt(ref tokenizer) => MultiThreadedTokenizer::encode_list(
                 tokenizer,
                 text_list,
@@ -793,6 +1261,13 @@ impl TokenizerOption {
                 truncation_strategy,
                 stride,
             ),
+            Self::Llama(ref tokenizer) => MultiThreadedTokenizer::encode_list(
+                tokenizer,
+                text_list,
+                max_len,
+                truncation_strategy,
+                stride,
+            ),
         }
     }
 
@@ -875,13 +1350,15 @@ impl TokenizerOption {
                 truncation_strategy,
                 stride,
             ),
-            Self::GPT2(ref tokenizer) => MultiThreadedTokenizer::encode_pair_list(
-                tokenizer,
-                text_pair_list,
-                max_len,
-                truncation_strategy,
-                stride,
-            ),
+            Self::GPT2(ref tokenizer) | Self::OPT(ref tokenizer) | Self::Bloom(ref tokenizer) => {
+                MultiThreadedTokenizer::encode_pair_list(
+                    tokenizer,
+                    text_pair_list,
+                    max_len,
+                    truncation_strategy,
+                    stride,
+                )
+            }
             Self::OpenAiGpt(ref tokenizer) => MultiThreadedTokenizer::encode_pair_list(
                 tokenizer,
                 text_pair_list,
@@ -931,6 +1408,13 @@ impl TokenizerOption {
                 truncation_strategy,
                 stride,
             ),
+            Self::Llama(ref tokenizer) => MultiThreadedTokenizer::encode_pair_list(
+                tokenizer,
+                text_pair_list,
+                max_len,
+                truncation_strategy,
+                stride,
+            ),
         }
     }
 
@@ -974,7 +1458,7 @@ impl TokenizerOption {
             Self::XLNet(ref tokenizer) => {
                 tokenizer.encode(text_1, text_2, max_len, truncation_strategy, stride)
             }
-            Self::GPT2(ref tokenizer) => {
+            Self::GPT2(ref tokenizer) | Self::OPT(ref tokenizer) | Self::Bloom(ref tokenizer) => {
                 tokenizer.encode(text_1, text_2, max_len, truncation_strategy, stride)
             }
             Self::OpenAiGpt(ref tokenizer) => {
@@ -998,6 +1482,9 @@ impl TokenizerOption {
             Self::FNet(ref tokenizer) => {
                 tokenizer.encode(text_1, text_2, max_len, truncation_strategy, stride)
             }
+            Self::Llama(ref tokenizer) => {
+                tokenizer.encode(text_1, text_2, max_len, truncation_strategy, stride)
+            }
         }
     }
 
@@ -1014,7 +1501,9 @@ impl TokenizerOption {
             Self::XLMRoberta(ref tokenizer) => tokenizer.tokenize(text),
             Self::Albert(ref tokenizer) => tokenizer.tokenize(text),
             Self::XLNet(ref tokenizer) => tokenizer.tokenize(text),
-            Self::GPT2(ref tokenizer) => tokenizer.tokenize(text),
+            Self::GPT2(ref tokenizer) | Self::OPT(ref tokenizer) | Self::Bloom(ref tokenizer) => {
+                tokenizer.tokenize(text)
+            }
             Self::OpenAiGpt(ref tokenizer) => tokenizer.tokenize(text),
             Self::Reformer(ref tokenizer) => tokenizer.tokenize(text),
             Self::ProphetNet(ref tokenizer) => tokenizer.tokenize(text),
@@ -1022,6 +1511,7 @@ impl TokenizerOption {
             Self::MBart50(ref tokenizer) => tokenizer.tokenize(text),
             Self::M2M100(ref tokenizer) => tokenizer.tokenize(text),
             Self::FNet(ref tokenizer) => tokenizer.tokenize(text),
+            Self::Llama(ref tokenizer) => tokenizer.tokenize(text),
         }
     }
 
@@ -1038,7 +1528,9 @@ impl TokenizerOption {
             Self::XLMRoberta(ref tokenizer) => tokenizer.tokenize_with_offsets(text),
             Self::Albert(ref tokenizer) => tokenizer.tokenize_with_offsets(text),
             Self::XLNet(ref tokenizer) => tokenizer.tokenize_with_offsets(text),
-            Self::GPT2(ref tokenizer) => tokenizer.tokenize_with_offsets(text),
+            Self::GPT2(ref tokenizer) | Self::OPT(ref tokenizer) | Self::Bloom(ref tokenizer) => {
+                tokenizer.tokenize_with_offsets(text)
+            }
             Self::OpenAiGpt(ref tokenizer) => tokenizer.tokenize_with_offsets(text),
             Self::Reformer(ref tokenizer) => tokenizer.tokenize_with_offsets(text),
             Self::ProphetNet(ref tokenizer) => tokenizer.tokenize_with_offsets(text),
@@ -1046,6 +1538,7 @@ impl TokenizerOption {
             Self::MBart50(ref tokenizer) => tokenizer.tokenize_with_offsets(text),
             Self::M2M100(ref tokenizer) => tokenizer.tokenize_with_offsets(text),
             Self::FNet(ref tokenizer) => tokenizer.tokenize_with_offsets(text),
+            Self::Llama(ref tokenizer) => tokenizer.tokenize_with_offsets(text),
         }
     }
 
@@ -1069,7 +1562,9 @@ impl TokenizerOption {
             }
             Self::Albert(ref tokenizer) => MultiThreadedTokenizer::tokenize_list(tokenizer, text),
             Self::XLNet(ref tokenizer) => MultiThreadedTokenizer::tokenize_list(tokenizer, text),
-            Self::GPT2(ref tokenizer) => MultiThreadedTokenizer::tokenize_list(tokenizer, text),
+            Self::GPT2(ref tokenizer) | Self::OPT(ref tokenizer) | Self::Bloom(ref tokenizer) => {
+                MultiThreadedTokenizer::tokenize_list(tokenizer, text)
+            }
             Self::OpenAiGpt(ref tokenizer) => {
                 MultiThreadedTokenizer::tokenize_list(tokenizer, text)
             }
@@ -1081,6 +1576,7 @@ impl TokenizerOption {
             Self::MBart50(ref tokenizer) => MultiThreadedTokenizer::tokenize_list(tokenizer, text),
             Self::M2M100(ref tokenizer) => MultiThreadedTokenizer::tokenize_list(tokenizer, text),
             Self::FNet(ref tokenizer) => MultiThreadedTokenizer::tokenize_list(tokenizer, text),
+            Self::Llama(ref tokenizer) => MultiThreadedTokenizer::tokenize_list(tokenizer, text),
         }
     }
 
@@ -1122,7 +1618,7 @@ impl TokenizerOption {
             Self::XLNet(ref tokenizer) => {
                 tokenizer.decode(token_ids, skip_special_tokens, clean_up_tokenization_spaces)
             }
-            Self::GPT2(ref tokenizer) => {
+            Self::GPT2(ref tokenizer) | Self::OPT(ref tokenizer) | Self::Bloom(ref tokenizer) => {
                 tokenizer.decode(token_ids, skip_special_tokens, clean_up_tokenization_spaces)
             }
             Self::OpenAiGpt(ref tokenizer) => {
@@ -1146,6 +1642,9 @@ impl TokenizerOption {
             Self::FNet(ref tokenizer) => {
                 tokenizer.decode(token_ids, skip_special_tokens, clean_up_tokenization_spaces)
             }
+            Self::Llama(ref tokenizer) => {
+                tokenizer.decode(token_ids, skip_special_tokens, clean_up_tokenization_spaces)
+            }
         }
     }
 
@@ -1196,10 +1695,12 @@ impl TokenizerOption {
                 token_ids_with_offsets_1,
                 token_ids_with_offsets_2,
             ),
-            Self::GPT2(ref tokenizer) => tokenizer.build_input_with_special_tokens(
-                token_ids_with_offsets_1,
-                token_ids_with_offsets_2,
-            ),
+            Self::GPT2(ref tokenizer) | Self::OPT(ref tokenizer) | Self::Bloom(ref tokenizer) => {
+                tokenizer.build_input_with_special_tokens(
+                    token_ids_with_offsets_1,
+                    token_ids_with_offsets_2,
+                )
+            }
             Self::OpenAiGpt(ref tokenizer) => tokenizer.build_input_with_special_tokens(
                 token_ids_with_offsets_1,
                 token_ids_with_offsets_2,
@@ -1228,6 +1729,10 @@ impl TokenizerOption {
                 token_ids_with_offsets_1,
                 token_ids_with_offsets_2,
             ),
+            Self::Llama(ref tokenizer) => tokenizer.build_input_with_special_tokens(
+                token_ids_with_offsets_1,
+                token_ids_with_offsets_2,
+            ),
         };
         TokenizedInput {
             token_ids: token_ids_with_special_tokens.token_ids,
@@ -1257,7 +1762,9 @@ impl TokenizerOption {
             Self::XLMRoberta(ref tokenizer) => tokenizer.convert_tokens_to_ids(tokens),
             Self::Albert(ref tokenizer) => tokenizer.convert_tokens_to_ids(tokens),
             Self::XLNet(ref tokenizer) => tokenizer.convert_tokens_to_ids(tokens),
-            Self::GPT2(ref tokenizer) => tokenizer.convert_tokens_to_ids(tokens),
+            Self::GPT2(ref tokenizer) | Self::OPT(ref tokenizer) | Self::Bloom(ref tokenizer) => {
+                tokenizer.convert_tokens_to_ids(tokens)
+            }
             Self::OpenAiGpt(ref tokenizer) => tokenizer.convert_tokens_to_ids(tokens),
             Self::Reformer(ref tokenizer) => tokenizer.convert_tokens_to_ids(tokens),
             Self::ProphetNet(ref tokenizer) => tokenizer.convert_tokens_to_ids(tokens),
@@ -1265,6 +1772,7 @@ impl TokenizerOption {
             Self::MBart50(ref tokenizer) => tokenizer.convert_tokens_to_ids(tokens),
             Self::M2M100(ref tokenizer) => tokenizer.convert_tokens_to_ids(tokens),
             Self::FNet(ref tokenizer) => tokenizer.convert_tokens_to_ids(tokens),
+            Self::Llama(ref tokenizer) => tokenizer.convert_tokens_to_ids(tokens),
         }
     }
 
@@ -1311,7 +1819,7 @@ impl TokenizerOption {
                 let vocab = MultiThreadedTokenizer::vocab(tokenizer);
                 vocab.token_to_id(vocab.get_unknown_value())
             }
-            Self::GPT2(ref tokenizer) => {
+            Self::GPT2(ref tokenizer) | Self::OPT(ref tokenizer) | Self::Bloom(ref tokenizer) => {
                 let vocab = MultiThreadedTokenizer::vocab(tokenizer);
                 vocab.token_to_id(vocab.get_unknown_value())
             }
@@ -1343,6 +1851,10 @@ impl TokenizerOption {
                 let vocab = MultiThreadedTokenizer::vocab(tokenizer);
                 vocab.token_to_id(vocab.get_unknown_value())
             }
+            Self::Llama(ref tokenizer) => {
+                let vocab = MultiThreadedTokenizer::vocab(tokenizer);
+                vocab.token_to_id(vocab.get_unknown_value())
+            }
         }
     }
 
@@ -1409,8 +1921,17 @@ impl TokenizerOption {
                 let vocab = MultiThreadedTokenizer::vocab(tokenizer);
                 Some(vocab.token_to_id(vocab.get_pad_value()))
             }
+            Self::Llama(_) => None,
             Self::Reformer(_) => None,
             Self::GPT2(_) => None,
+            Self::OPT(ref tokenizer) | Self::Bloom(ref tokenizer) => {
+                let vocab = MultiThreadedTokenizer::vocab(tokenizer);
+                vocab
+                    .special_token_map
+                    .pad_token
+                    .as_ref()
+                    .map(|pad_token| vocab.token_to_id(pad_token))
+            }
             Self::OpenAiGpt(_) => None,
         }
     }
@@ -1466,9 +1987,10 @@ impl TokenizerOption {
                 let vocab = MultiThreadedTokenizer::vocab(tokenizer);
                 Some(vocab.token_to_id(vocab.get_sep_value()))
             }
+            Self::Llama(_) => None,
             Self::Marian(_) => None,
             Self::T5(_) => None,
-            Self::GPT2(_) => None,
+            Self::GPT2(_) | Self::OPT(_) | Self::Bloom(_) => None,
             Self::OpenAiGpt(_) => None,
             Self::Reformer(_) => None,
             Self::Pegasus(_) => None,
@@ -1522,6 +2044,7 @@ impl TokenizerOption {
                 let vocab = MultiThreadedTokenizer::vocab(tokenizer);
                 Some(vocab.token_to_id(vocab.get_mask_value()))
             }
+            Self::Llama(_) => None,
             Self::Pegasus(ref tokenizer) => {
                 let vocab = MultiThreadedTokenizer::vocab(tokenizer);
                 Some(vocab.token_to_id(vocab.get_mask_value()))
@@ -1529,7 +2052,7 @@ impl TokenizerOption {
             Self::Marian(_) => None,
             Self::M2M100(_) => None,
             Self::T5(_) => None,
-            Self::GPT2(_) => None,
+            Self::GPT2(_) | Self::OPT(_) | Self::Bloom(_) => None,
             Self::OpenAiGpt(_) => None,
             Self::Reformer(_) => None,
         }
@@ -1571,13 +2094,14 @@ impl TokenizerOption {
             Self::FNet(ref tokenizer) => {
                 Some(MultiThreadedTokenizer::vocab(tokenizer).get_mask_value())
             }
+            Self::Llama(_) => None,
             Self::Pegasus(ref tokenizer) => {
                 Some(MultiThreadedTokenizer::vocab(tokenizer).get_mask_value())
             }
             Self::M2M100(_) => None,
             Self::Marian(_) => None,
             Self::T5(_) => None,
-            Self::GPT2(_) => None,
+            Self::GPT2(_) | Self::OPT(_) | Self::Bloom(_) => None,
             Self::OpenAiGpt(_) => None,
             Self::Reformer(_) => None,
         }
@@ -1614,7 +2138,7 @@ impl TokenizerOption {
                 let vocab = MultiThreadedTokenizer::vocab(tokenizer);
                 Some(vocab.token_to_id(vocab.get_bos_value()))
             }
-            Self::GPT2(ref tokenizer) => {
+            Self::GPT2(ref tokenizer) | Self::OPT(ref tokenizer) | Self::Bloom(ref tokenizer) => {
                 let vocab = MultiThreadedTokenizer::vocab(tokenizer);
                 Some(vocab.token_to_id(vocab.get_bos_value()))
             }
@@ -1623,6 +2147,9 @@ impl TokenizerOption {
                 Some(vocab.token_to_id(vocab.get_bos_value()))
             }
             Self::MBart50(_) => Some(0),
+            Self::Llama(ref tokenizer) => {
+                Some(MultiThreadedTokenizer::vocab(tokenizer).token_to_id(LLAMA_BOS_TOKEN))
+            }
             Self::FNet(_) => None,
             Self::Bert(_) => None,
             Self::Marian(_) => Some(0),
@@ -1669,7 +2196,7 @@ impl TokenizerOption {
                 let vocab = MultiThreadedTokenizer::vocab(tokenizer);
                 Some(vocab.token_to_id(vocab.get_eos_value()))
             }
-            Self::GPT2(ref tokenizer) => {
+            Self::GPT2(ref tokenizer) | Self::OPT(ref tokenizer) | Self::Bloom(ref tokenizer) => {
                 let vocab = MultiThreadedTokenizer::vocab(tokenizer);
                 Some(vocab.token_to_id(vocab.get_eos_value()))
             }
@@ -1693,6 +2220,9 @@ impl TokenizerOption {
                 let vocab = MultiThreadedTokenizer::vocab(tokenizer);
                 Some(vocab.token_to_id(vocab.get_eos_value()))
             }
+            Self::Llama(ref tokenizer) => {
+                Some(MultiThreadedTokenizer::vocab(tokenizer).token_to_id(LLAMA_EOS_TOKEN))
+            }
             Self::FNet(_) => None,
             Self::Bert(_) => None,
             Self::ProphetNet(_) => None,
@@ -57,9 +57,14 @@ use crate::common::error::RustBertError;
 use crate::gpt2::GPT2Generator;
 use crate::pipelines::common::{ModelType, TokenizerOption};
 use crate::pipelines::generation_utils::private_generation_utils::PrivateLanguageGenerator;
-use crate::pipelines::generation_utils::{GenerateConfig, LanguageGenerator};
+use crate::pipelines::generation_utils::{GenerateConfig, GenerateOptions, LanguageGenerator};
+use crate::pipelines::summarization::SummarizationModel;
 use crate::resources::ResourceProvider;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::fs;
+use std::mem;
+use std::path::PathBuf;
 use tch::{Device, Kind, Tensor};
 use uuid::Uuid;
 
@@ -89,6 +94,8 @@ pub struct ConversationConfig {
     pub max_length: Option<i64>,
     /// Minimum free length available for generated responses (default: 32)
     pub min_length_for_response: i64,
+    /// Strategy used to truncate the conversation history to the context window (default: `HistoryTruncationStrategy::Left`)
+    pub truncation_strategy: HistoryTruncationStrategy,
     /// Sampling flag. If true, will perform top-k and/or nucleus sampling on generated tokens, otherwise greedy (deterministic) decoding (default: true)
     pub do_sample: bool,
     /// Early stopping flag indicating if the beam search should stop as soon as `num_beam` hypotheses have been generated (default: false)
@@ -137,6 +144,7 @@ impl Default for ConversationConfig {
             min_length: 0,
             max_length: Some(1000),
             min_length_for_response: 64,
+            truncation_strategy: HistoryTruncationStrategy::Left,
             do_sample: true,
             early_stopping: false,
             num_beams: 1,
@@ -180,7 +188,83 @@ impl From<ConversationConfig> for GenerateConfig {
     }
 }
 
-#[derive(Debug, Clone)]
+/// # Truncation strategy for conversation histories
+/// Defines how the history of a conversation is shortened when the history and the new user input
+/// do not fit in the context window (`max_length - min_length_for_response`).
+#[derive(Default)]
+pub enum HistoryTruncationStrategy {
+    /// Drops the oldest history segments (user inputs or responses) until the history fits. If the latest
+    /// segment does not fit on its own, it is truncated from the left
+    #[default]
+    Left,
+    /// Drops the oldest turns (user input and response) as a whole
+    DropOldestTurns,
+    /// Keeps the given number of first turns and the latest turns that fit, dropping the turns in between
+    KeepFirstTurns(usize),
+    /// Replaces the oldest turns by a summary generated by the summarizer
+    Summarize(Box<dyn ConversationSummarizer + Send>),
+}
+
+/// # Summarizer of conversation turns
+/// Hook used by `HistoryTruncationStrategy::Summarize` to condense the oldest turns of a conversation.
+/// It is implemented for the `SummarizationModel` pipeline.
+pub trait ConversationSummarizer {
+    /// Summarizes a sequence of turns, given as pairs of user input and generated response
+    fn summarize_turns(&self, turns: &[(&str, &str)]) -> String;
+}
+
+impl ConversationSummarizer for SummarizationModel {
+    fn summarize_turns(&self, turns: &[(&str, &str)]) -> String {
+        let text = turns
+            .iter()
+            .map(|(user_input, response)| format!("{user_input}\n{response}"))
+            .collect::<Vec<String>>()
+            .join("\n");
+        self.summarize(&[text]).pop().unwrap_or_default()
+    }
+}
+
+/// # Summary of the oldest turns of a conversation
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConversationSummary {
+    /// Summary text, used as context in place of the summarized turns
+    pub text: String,
+    /// Number of turns (from the start of the conversation) covered by the summary
+    pub summarized_turns: usize,
+}
+
+/// # Generation settings for a single conversation
+/// Options left as `None` default to the values of the `ConversationConfig` used to create the model.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct GenerationOverrides {
+    /// Sampling flag
+    pub do_sample: Option<bool>,
+    /// Temperature setting
+    pub temperature: Option<f64>,
+    /// Top_k value for sampling tokens
+    pub top_k: Option<i64>,
+    /// Top_p value for nucleus sampling
+    pub top_p: Option<f64>,
+    /// Maximum number of tokens of the generated response. The context window available for the history
+    /// is reduced accordingly, and this value must therefore be lower than the model `max_length`
+    /// (checked by `ConversationModel::set_generation_overrides`).
+    pub max_response_length: Option<i64>,
+}
+
+impl From<GenerationOverrides> for GenerateOptions<'_> {
+    fn from(overrides: GenerationOverrides) -> Self {
+        GenerateOptions {
+            do_sample: overrides.do_sample,
+            temperature: overrides.temperature,
+            top_k: overrides.top_k,
+            top_p: overrides.top_p,
+            max_new_tokens: overrides.max_response_length,
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 /// Data structure keeping track of a conversation in the system. It contains past user inputs and
 /// generated answers, a history of the tokens generated and a placeholder for new user inputs to be
 /// processed by the system if submitted for prediction
@@ -193,6 +277,29 @@ pub struct Conversation {
     pub new_user_input: Option<String>,
     ///  History of the tokens passed as an input and generated so far used as context for next turn generation
     pub history: Vec<Vec<i64>>,
+    /// Signature of the tokenizer that encoded the `history`. The history is re-encoded from the past
+    /// inputs and responses if it does not match the signature of the model generating the next response.
+    #[serde(default)]
+    pub history_signature: Option<HistorySignature>,
+    /// Summary of the oldest turns, maintained by the `HistoryTruncationStrategy::Summarize` strategy
+    #[serde(default)]
+    pub summary: Option<ConversationSummary>,
+    /// Generation settings overriding the model configuration for this conversation
+    #[serde(default)]
+    pub generation_overrides: Option<GenerationOverrides>,
+}
+
+/// # Signature of the tokenizer used to encode a conversation history
+/// Identifies the tokenizer that produced the token history of a conversation, allowing to detect
+/// that a conversation saved with a different tokenizer or model needs to be re-encoded.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HistorySignature {
+    /// Type of the model that generated the history
+    pub model_type: ModelType,
+    /// End of sequence token id separating the turns of the conversation
+    pub eos_token_id: i64,
+    /// Token ids for a reference text, identifying the tokenizer vocabulary
+    pub reference_ids: Vec<i64>,
 }
 
 impl Conversation {
@@ -215,6 +322,9 @@ impl Conversation {
             generated_responses: vec![],
             new_user_input: Some(text.to_string()),
             history: vec![],
+            history_signature: None,
+            summary: None,
+            generation_overrides: None,
         }
     }
 
@@ -233,6 +343,9 @@ impl Conversation {
             generated_responses: vec![],
             new_user_input: None,
             history: vec![],
+            history_signature: None,
+            summary: None,
+            generation_overrides: None,
         }
     }
 
@@ -459,7 +572,8 @@ impl Conversation {
 
 /// Data structure allowing the management of conversations and main input to the dialogue model.
 /// It contains a `HashMap` of conversations with `UUID` keys
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(transparent)]
 pub struct ConversationManager {
     conversations: HashMap<Uuid, Conversation>,
 }
@@ -658,6 +772,96 @@ impl ConversationManager {
         self.conversations.remove(uuid)
     }
 
+    /// Inserts a conversation with a given UUID in the conversation manager, for example when
+    /// restoring a conversation from a `ConversationStore`
+    ///
+    /// # Arguments
+    ///
+    /// * `uuid` - `Uuid` of the conversation
+    /// * `conversation` - `Conversation` to be added to the conversation manager
+    ///
+    /// # Returns
+    ///
+    /// * `Option<Conversation>` conversation previously registered with the same UUID, if any
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use rust_bert::pipelines::conversation::{Conversation, ConversationManager};
+    /// use uuid::Uuid;
+    ///
+    /// let mut conversation_manager = ConversationManager::new();
+    ///
+    /// let conversation = Conversation::new("Hi there!");
+    /// conversation_manager.insert(Uuid::new_v4(), conversation);
+    /// ```
+    pub fn insert(&mut self, uuid: Uuid, conversation: Conversation) -> Option<Conversation> {
+        self.conversations.insert(uuid, conversation)
+    }
+
+    /// Saves all conversations of the conversation manager to a `ConversationStore`
+    ///
+    /// # Arguments
+    ///
+    /// * `store` - `ConversationStore` to persist the conversations to
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::conversation::{ConversationManager, FileConversationStore};
+    ///
+    /// let mut store = FileConversationStore::new("path/to/conversations")?;
+    /// let mut conversation_manager = ConversationManager::new();
+    /// conversation_manager.create("Hi there!");
+    ///
+    /// conversation_manager.save_to_store(&mut store)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn save_to_store<T: ConversationStore + ?Sized>(
+        &self,
+        store: &mut T,
+    ) -> Result<(), RustBertError> {
+        for (uuid, conversation) in self.conversations.iter() {
+            store.save(uuid, conversation)?;
+        }
+        Ok(())
+    }
+
+    /// Builds a conversation manager with all conversations persisted in a `ConversationStore`
+    ///
+    /// # Arguments
+    ///
+    /// * `store` - `ConversationStore` to restore the conversations from
+    ///
+    /// # Returns
+    ///
+    /// * `ConversationManager` containing the stored conversations, referenced by their original UUID
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::conversation::{ConversationManager, FileConversationStore};
+    ///
+    /// let store = FileConversationStore::new("path/to/conversations")?;
+    /// let conversation_manager = ConversationManager::from_store(&store)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn from_store<T: ConversationStore + ?Sized>(
+        store: &T,
+    ) -> Result<ConversationManager, RustBertError> {
+        let mut conversations = HashMap::new();
+        for uuid in store.list()? {
+            if let Some(conversation) = store.load(&uuid)? {
+                conversations.insert(uuid, conversation);
+            }
+        }
+        Ok(ConversationManager { conversations })
+    }
+
     /// Clear all conversations from the conversation manager, and returns the conversations and their
     /// former UUID.
     ///
@@ -691,6 +895,103 @@ impl Default for ConversationManager {
     }
 }
 
+/// # Persistence backend for conversations
+/// Stores conversations by UUID, allowing to restore a `ConversationManager` state after a restart.
+pub trait ConversationStore {
+    /// Saves a conversation, overwriting any existing conversation with the same UUID
+    fn save(&mut self, uuid: &Uuid, conversation: &Conversation) -> Result<(), RustBertError>;
+
+    /// Loads the conversation with the provided UUID, returning `None` if it does not exist
+    fn load(&self, uuid: &Uuid) -> Result<Option<Conversation>, RustBertError>;
+
+    /// Deletes the conversation with the provided UUID, if it exists
+    fn delete(&mut self, uuid: &Uuid) -> Result<(), RustBertError>;
+
+    /// Lists the UUIDs of all stored conversations
+    fn list(&self) -> Result<Vec<Uuid>, RustBertError>;
+}
+
+/// # File-backed conversation store
+/// Stores each conversation as a JSON file named after its UUID in a directory.
+pub struct FileConversationStore {
+    directory: PathBuf,
+}
+
+impl FileConversationStore {
+    /// Build a new `FileConversationStore`, creating the directory if it does not exist
+    ///
+    /// # Arguments
+    ///
+    /// * `directory` - path to the directory storing the conversations
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::conversation::FileConversationStore;
+    ///
+    /// let store = FileConversationStore::new("path/to/conversations")?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn new<P: Into<PathBuf>>(directory: P) -> Result<FileConversationStore, RustBertError> {
+        let directory = directory.into();
+        fs::create_dir_all(&directory)?;
+        Ok(FileConversationStore { directory })
+    }
+
+    fn conversation_path(&self, uuid: &Uuid) -> PathBuf {
+        self.directory.join(format!("{uuid}.json"))
+    }
+}
+
+impl ConversationStore for FileConversationStore {
+    fn save(&mut self, uuid: &Uuid, conversation: &Conversation) -> Result<(), RustBertError> {
+        let serialized = serde_json::to_vec(conversation)
+            .map_err(|error| RustBertError::IOError(error.to_string()))?;
+        // Written to a temporary file first so that an interrupted save does not corrupt a stored conversation
+        let temporary_path = self.directory.join(format!("{uuid}.json.tmp"));
+        fs::write(&temporary_path, serialized)?;
+        fs::rename(&temporary_path, self.conversation_path(uuid))?;
+        Ok(())
+    }
+
+    fn load(&self, uuid: &Uuid) -> Result<Option<Conversation>, RustBertError> {
+        let path = self.conversation_path(uuid);
+        if !path.is_file() {
+            return Ok(None);
+        }
+        let conversation = serde_json::from_slice(&fs::read(path)?)
+            .map_err(|error| RustBertError::IOError(error.to_string()))?;
+        Ok(Some(conversation))
+    }
+
+    fn delete(&mut self, uuid: &Uuid) -> Result<(), RustBertError> {
+        let path = self.conversation_path(uuid);
+        if path.is_file() {
+            fs::remove_file(path)?;
+        }
+        Ok(())
+    }
+
+    fn list(&self) -> Result<Vec<Uuid>, RustBertError> {
+        let mut uuids = Vec::new();
+        for entry in fs::read_dir(&self.directory)? {
+            let path = entry?.path();
+            if path.extension().and_then(|extension| extension.to_str()) == Some("json") {
+                if let Some(uuid) = path
+                    .file_stem()
+                    .and_then(|stem| stem.to_str())
+                    .and_then(|stem| Uuid::parse_str(stem).ok())
+                {
+                    uuids.push(uuid);
+                }
+            }
+        }
+        Ok(uuids)
+    }
+}
+
 /// # Abstraction that holds one particular conversation model, for any of the supported models
 pub enum ConversationOption {
     /// Conversation based on GPT2 model
@@ -734,10 +1035,11 @@ impl ConversationOption {
         &self,
         input_ids: Tensor,
         attention_mask: Option<Tensor>,
+        generate_options: Option<GenerateOptions>,
     ) -> Vec<Vec<i64>> {
         match *self {
             Self::GPT2(ref model) => model
-                .generate_from_ids_and_past(input_ids, attention_mask, None)
+                .generate_from_ids_and_past(input_ids, attention_mask, generate_options)
                 .into_iter()
                 .map(|output| output.indices)
                 .collect(),
@@ -745,12 +1047,19 @@ impl ConversationOption {
     }
 }
 
+/// Reference text encoded to identify the tokenizer used for conversation histories
+const HISTORY_SIGNATURE_REFERENCE_TEXT: &str =
+    "The quick brown fox jumps over the lazy dog! 0123456789 (Ünïcödé, 東京)";
+
 /// # Conversation model
 /// Processes a ConversationManager and generate system responses for active conversations.
 pub struct ConversationModel {
     model: ConversationOption,
     eos_token_id: i64,
     max_allowed_context_length: Option<i64>,
+    max_length: Option<i64>,
+    truncation_strategy: HistoryTruncationStrategy,
+    history_signature: HistorySignature,
     device: Device,
 }
 
@@ -772,22 +1081,185 @@ impl ConversationModel {
     /// # }
     /// ```
     pub fn new(
-        conversation_config: ConversationConfig,
+        mut conversation_config: ConversationConfig,
     ) -> Result<ConversationModel, RustBertError> {
+        let max_length = conversation_config.max_length;
         let max_allowed_length = conversation_config
             .max_length
             .map(|max_length| max_length - conversation_config.min_length_for_response);
+        let truncation_strategy = mem::take(&mut conversation_config.truncation_strategy);
         let device = conversation_config.device;
         let model = ConversationOption::new(conversation_config)?;
         let eos_token_id = model.get_eos_id()?;
+        let history_signature = HistorySignature {
+            model_type: model.model_type(),
+            eos_token_id,
+            reference_ids: model.get_tokenizer().convert_tokens_to_ids(
+                &model
+                    .get_tokenizer()
+                    .tokenize(HISTORY_SIGNATURE_REFERENCE_TEXT),
+            ),
+        };
         Ok(ConversationModel {
             model,
             eos_token_id,
             max_allowed_context_length: max_allowed_length,
+            max_length,
+            truncation_strategy,
+            history_signature,
             device,
         })
     }
 
+    /// Sets the generation settings of a conversation, overriding the model configuration for this
+    /// conversation. This method returns an error if the maximum response length does not leave room
+    /// for a context within the model maximum length.
+    ///
+    /// # Arguments
+    ///
+    /// * `conversation` - `&mut Conversation` to update
+    /// * `overrides` - `GenerationOverrides` to use for the next responses of this conversation
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::conversation::{
+    ///     Conversation, ConversationModel, GenerationOverrides,
+    /// };
+    /// let model = ConversationModel::new(Default::default())?;
+    ///
+    /// let mut conversation = Conversation::new("Hi there!");
+    /// model.set_generation_overrides(
+    ///     &mut conversation,
+    ///     GenerationOverrides {
+    ///         max_response_length: Some(64),
+    ///         ..Default::default()
+    ///     },
+    /// )?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn set_generation_overrides(
+        &self,
+        conversation: &mut Conversation,
+        overrides: GenerationOverrides,
+    ) -> Result<(), RustBertError> {
+        if let Some(max_response_length) = overrides.max_response_length {
+            if max_response_length <= 0 {
+                return Err(RustBertError::ValueError(format!(
+                    "Maximum response length must be positive, got {max_response_length}"
+                )));
+            }
+            if let Some(max_length) = self.max_length {
+                if max_response_length >= max_length {
+                    return Err(RustBertError::ValueError(format!(
+                        "Maximum response length ({max_response_length}) must be lower than the maximum length ({max_length})"
+                    )));
+                }
+            }
+        }
+        conversation.generation_overrides = Some(overrides);
+        Ok(())
+    }
+
+    /// Returns the signature of the tokenizer used by the model to encode conversation histories
+    pub fn history_signature(&self) -> &HistorySignature {
+        &self.history_signature
+    }
+
+    /// Re-encodes the history of a conversation from its past user inputs and generated responses if it
+    /// was not encoded by the tokenizer of this model (for example a conversation saved with a different
+    /// model or created with `load_from_history`). This is performed automatically for active conversations
+    /// by `generate_responses`.
+    ///
+    /// # Arguments
+    ///
+    /// * `conversation` - `&mut Conversation` to validate
+    ///
+    /// # Returns
+    ///
+    /// * `bool` flag indicating if the history was re-encoded
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::conversation::{
+    ///     ConversationManager, ConversationModel, FileConversationStore,
+    /// };
+    /// let model = ConversationModel::new(Default::default())?;
+    ///
+    /// let store = FileConversationStore::new("path/to/conversations")?;
+    /// let mut conversation_manager = ConversationManager::from_store(&store)?;
+    /// let conversation_id = conversation_manager.create("Hi there!");
+    /// let was_reencoded = model.revalidate_history(conversation_manager.get(&conversation_id).unwrap());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn revalidate_history(&self, conversation: &mut Conversation) -> bool {
+        if conversation.history_signature.as_ref() == Some(&self.history_signature) {
+            return false;
+        }
+        let past_user_inputs = conversation
+            .past_user_inputs
+            .iter()
+            .map(String::as_str)
+            .collect::<Vec<&str>>();
+        let generated_responses = conversation
+            .generated_responses
+            .iter()
+            .map(String::as_str)
+            .collect::<Vec<&str>>();
+        let encoded_inputs = self.encode_prompts(&past_user_inputs);
+        let mut encoded_responses = self.encode_prompts(&generated_responses).into_iter();
+
+        conversation.history.clear();
+        for encoded_input in encoded_inputs {
+            conversation.history.push(encoded_input);
+            if let Some(encoded_response) = encoded_responses.next() {
+                conversation.history.push(encoded_response);
+            }
+        }
+        conversation.history_signature = Some(self.history_signature.clone());
+        true
+    }
+
+    /// Re-encodes the history of all conversations of a conversation manager that were not encoded by
+    /// the tokenizer of this model.
+    ///
+    /// # Arguments
+    ///
+    /// * `conversation_manager` - `&mut ConversationManager` containing the conversations to validate
+    ///
+    /// # Returns
+    ///
+    /// * `usize` number of conversations with a re-encoded history
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::conversation::{
+    ///     ConversationManager, ConversationModel, FileConversationStore,
+    /// };
+    /// let model = ConversationModel::new(Default::default())?;
+    ///
+    /// let store = FileConversationStore::new("path/to/conversations")?;
+    /// let mut conversation_manager = ConversationManager::from_store(&store)?;
+    /// let num_reencoded = model.revalidate_histories(&mut conversation_manager);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn revalidate_histories(&self, conversation_manager: &mut ConversationManager) -> usize {
+        conversation_manager
+            .conversations
+            .values_mut()
+            .map(|conversation| self.revalidate_history(conversation))
+            .filter(|reencoded| *reencoded)
+            .count()
+    }
+
     /// Perform a multi-turn conversation based on user input
     ///
     /// # Arguments
@@ -816,48 +1288,87 @@ impl ConversationModel {
         &self,
         conversation_manager: &'a mut ConversationManager,
     ) -> HashMap<&'a Uuid, &'a str> {
-        let (active_uuid, active_conversations) = conversation_manager.get_active_conversations();
+        let (active_uuid, mut active_conversations) =
+            conversation_manager.get_active_conversations();
+        for conversation in active_conversations.iter_mut() {
+            self.revalidate_history(conversation);
+        }
         if !active_uuid.is_empty() {
             let texts = active_conversations
                 .iter()
                 .map(|c| c.new_user_input.as_ref().unwrap().as_str())
                 .collect::<Vec<&str>>();
 
+            let prompt_ids = self.encode_prompts(texts.as_ref());
             let history = active_conversations
-                .iter()
-                .map(|c| c.history.iter().flatten().copied().collect())
+                .iter_mut()
+                .zip(prompt_ids.iter())
+                .map(|(conversation, prompt)| self.truncate_history(conversation, prompt.len()))
                 .collect::<Vec<Vec<i64>>>();
 
-            let prompt_ids = self.encode_prompts(texts.as_ref());
-            let (input_tensor, attention_mask) =
-                self.concat_input_history(prompt_ids.as_ref(), history);
-            let input_length = *input_tensor.size().last().unwrap() as usize;
-            let mut generated = self
-                .model
-                .generate_from_ids_and_past(input_tensor, Some(attention_mask));
-            let removed_padding_quantities = self.clean_padding_indices(&mut generated);
+            // Conversations sharing the same generation overrides are processed as a batch
+            let mut batches: Vec<(Option<GenerationOverrides>, Vec<usize>)> = vec![];
+            for (index, conversation) in active_conversations.iter().enumerate() {
+                match batches
+                    .iter_mut()
+                    .find(|(overrides, _)| *overrides == conversation.generation_overrides)
+                {
+                    Some((_, indices)) => indices.push(index),
+                    None => batches.push((conversation.generation_overrides, vec![index])),
+                }
+            }
+
+            let mut generated_responses = vec![vec![]; active_uuid.len()];
+            for (overrides, indices) in batches {
+                let batch_prompt_ids = indices
+                    .iter()
+                    .map(|&index| prompt_ids[index].clone())
+                    .collect::<Vec<Vec<i64>>>();
+                let batch_history = indices
+                    .iter()
+                    .map(|&index| history[index].clone())
+                    .collect::<Vec<Vec<i64>>>();
+                let (input_tensor, attention_mask) = self.concat_input_history(
+                    batch_prompt_ids.as_ref(),
+                    batch_history,
+                    self.context_length(overrides.as_ref()),
+                );
+                let input_length = *input_tensor.size().last().unwrap() as usize;
+                let mut generated = self.model.generate_from_ids_and_past(
+                    input_tensor,
+                    Some(attention_mask),
+                    overrides.map(GenerateOptions::from),
+                );
+                let removed_padding_quantities = self.clean_padding_indices(&mut generated);
+                for ((index, generated_sequence), removed_padding) in indices
+                    .into_iter()
+                    .zip(generated)
+                    .zip(removed_padding_quantities)
+                {
+                    generated_responses[index] =
+                        generated_sequence[input_length - removed_padding.0..].to_vec();
+                }
+            }
 
             let mut output = HashMap::with_capacity(active_uuid.len());
 
-            for (
-                ((conversation, (generated_sequence, conversation_promp_ids)), uuid),
-                removed_padding,
-            ) in active_conversations
-                .into_iter()
-                .zip(generated.into_iter().zip(prompt_ids.into_iter()))
-                .zip(active_uuid.into_iter())
-                .zip(removed_padding_quantities.into_iter())
+            for (((conversation, generated_response), conversation_promp_ids), uuid) in
+                active_conversations
+                    .into_iter()
+                    .zip(generated_responses)
+                    .zip(prompt_ids)
+                    .zip(active_uuid)
             {
-                let generated_response = &generated_sequence[input_length - removed_padding.0..];
                 conversation
                     .generated_responses
                     .push(
                         self.model
                             .get_tokenizer()
-                            .decode(generated_response, true, true),
+                            .decode(&generated_response, true, true),
                     );
                 conversation.history.push(conversation_promp_ids);
-                conversation.history.push(generated_response.to_vec());
+                conversation.history.push(generated_response);
+                conversation.history_signature = Some(self.history_signature.clone());
                 conversation.mark_processed();
                 output.insert(uuid, conversation.get_last_response().unwrap());
             }
@@ -867,6 +1378,71 @@ impl ConversationModel {
         }
     }
 
+    /// Returns the maximum length of the context (history and user input) for a conversation,
+    /// leaving room for the response length requested by the generation overrides if provided.
+    fn context_length(&self, overrides: Option<&GenerationOverrides>) -> Option<i64> {
+        match overrides.and_then(|overrides| overrides.max_response_length) {
+            Some(max_response_length) => self
+                .max_length
+                .map(|max_length| (max_length - max_response_length).max(1)),
+            None => self.max_allowed_context_length,
+        }
+    }
+
+    /// Selects the history tokens used as context for the next response according to the truncation strategy.
+    /// The concatenation of the history and user input is further truncated from the left if it still
+    /// exceeds the context length.
+    fn truncate_history(&self, conversation: &mut Conversation, prompt_length: usize) -> Vec<i64> {
+        let max_context_length =
+            match self.context_length(conversation.generation_overrides.as_ref()) {
+                Some(max_context_length) => max_context_length.max(0) as usize,
+                None => return flatten_segments(&conversation.history),
+            };
+        let budget = max_context_length.saturating_sub(prompt_length);
+        let segment_lengths = conversation
+            .history
+            .iter()
+            .map(Vec::len)
+            .collect::<Vec<usize>>();
+        let num_turns = segment_lengths.chunks(2).len();
+
+        match &self.truncation_strategy {
+            HistoryTruncationStrategy::Left => flatten_segments(
+                &conversation.history[left_truncation_index(&segment_lengths, budget)..],
+            ),
+            HistoryTruncationStrategy::DropOldestTurns => {
+                let dropped_turns = turns_to_drop(&segment_lengths, 0, budget).unwrap_or(num_turns);
+                flatten_segments(
+                    &conversation.history[segment_index(&conversation.history, dropped_turns)..],
+                )
+            }
+            HistoryTruncationStrategy::KeepFirstTurns(first_turns) => {
+                let (kept_turns, dropped_turns) = (0..=(*first_turns).min(num_turns))
+                    .rev()
+                    .find_map(|kept_turns| {
+                        turns_to_drop(&segment_lengths, kept_turns, budget)
+                            .map(|dropped_turns| (kept_turns, dropped_turns))
+                    })
+                    .unwrap_or((0, num_turns));
+                let mut history = flatten_segments(
+                    &conversation.history[..segment_index(&conversation.history, kept_turns)],
+                );
+                history.extend(flatten_segments(
+                    &conversation.history
+                        [segment_index(&conversation.history, kept_turns + dropped_turns)..],
+                ));
+                history
+            }
+            HistoryTruncationStrategy::Summarize(summarizer) => summarize_history(
+                conversation,
+                summarizer.as_ref(),
+                &segment_lengths,
+                budget,
+                |text| self.encode_prompts(&[text]).pop().unwrap(),
+            ),
+        }
+    }
+
     fn clean_padding_indices(&self, model_output: &mut Vec<Vec<i64>>) -> Vec<(usize, usize)> {
         // In case inputs are sent as batch, this cleans the padding indices in the history for shorter outputs
         let pad_token = self
@@ -898,6 +1474,7 @@ impl ConversationModel {
         &self,
         inputs: &[Vec<i64>],
         history: Vec<Vec<i64>>,
+        max_allowed_context_length: Option<i64>,
     ) -> (Tensor, Tensor) {
         // Concatenates the history token indices with new user input
         let pad_token = self
@@ -922,7 +1499,7 @@ impl ConversationModel {
 
         let truncated_concatenated_inputs = concatenated_inputs
             .iter()
-            .map(|input| match self.max_allowed_context_length {
+            .map(|input| match max_allowed_context_length {
                 Some(max_allowed_context_length)
                     if input.len() > max_allowed_context_length as usize =>
                 {
@@ -1030,9 +1607,123 @@ impl ConversationModel {
     }
 }
 
+fn flatten_segments(segments: &[Vec<i64>]) -> Vec<i64> {
+    segments.iter().flatten().copied().collect()
+}
+
+/// Returns the index of the first history segment after the given number of turns
+fn segment_index(history: &[Vec<i64>], turns: usize) -> usize {
+    (2 * turns).min(history.len())
+}
+
+/// Returns the number of turns to drop after the first `keep_first` turns for the history to fit in
+/// `budget` tokens, or `None` if the history does not fit even after dropping all these turns.
+/// A turn is made of a user input segment followed by a response segment.
+fn turns_to_drop(segment_lengths: &[usize], keep_first: usize, budget: usize) -> Option<usize> {
+    let turn_lengths = segment_lengths
+        .chunks(2)
+        .map(|turn| turn.iter().sum())
+        .collect::<Vec<usize>>();
+    let mut total_length = turn_lengths.iter().sum::<usize>();
+    let mut dropped_turns = 0;
+    for turn_length in turn_lengths.iter().skip(keep_first) {
+        if total_length <= budget {
+            break;
+        }
+        total_length -= turn_length;
+        dropped_turns += 1;
+    }
+    if total_length <= budget {
+        Some(dropped_turns)
+    } else {
+        None
+    }
+}
+
+/// Replaces the oldest turns of the history by a summary so that the history fits in the budget.
+/// The summary is stored in the conversation and only regenerated when more turns need to be summarized.
+/// `encode_summary` converts the summary text to the tokens prepended to the remaining history.
+fn summarize_history<F>(
+    conversation: &mut Conversation,
+    summarizer: &dyn ConversationSummarizer,
+    segment_lengths: &[usize],
+    budget: usize,
+    encode_summary: F,
+) -> Vec<i64>
+where
+    F: Fn(&str) -> Vec<i64>,
+{
+    let num_turns = segment_lengths.chunks(2).len();
+    let mut summarized_turns = match turns_to_drop(segment_lengths, 0, budget) {
+        Some(0) => return flatten_segments(&conversation.history),
+        Some(dropped_turns) => dropped_turns,
+        None => num_turns,
+    };
+    loop {
+        let summary_text = match &conversation.summary {
+            Some(summary) if summary.summarized_turns == summarized_turns => summary.text.clone(),
+            _ => {
+                let turns = (0..summarized_turns)
+                    .map(|turn| {
+                        (
+                            conversation
+                                .past_user_inputs
+                                .get(turn)
+                                .map_or("", String::as_str),
+                            conversation
+                                .generated_responses
+                                .get(turn)
+                                .map_or("", String::as_str),
+                        )
+                    })
+                    .collect::<Vec<(&str, &str)>>();
+                let text = summarizer.summarize_turns(&turns);
+                conversation.summary = Some(ConversationSummary {
+                    text: text.clone(),
+                    summarized_turns,
+                });
+                text
+            }
+        };
+        let mut history = encode_summary(&summary_text);
+        let required_turns =
+            turns_to_drop(segment_lengths, 0, budget.saturating_sub(history.len()))
+                .unwrap_or(num_turns);
+        if required_turns <= summarized_turns || summarized_turns == num_turns {
+            history.extend(flatten_segments(
+                &conversation.history[segment_index(&conversation.history, summarized_turns)..],
+            ));
+            return history;
+        }
+        summarized_turns = required_turns;
+    }
+}
+
+/// Returns the index of the first history segment kept by the left truncation: the oldest segments
+/// are dropped until the remaining segments fit in `budget` tokens. The latest segment is always kept
+/// and truncated from the left when concatenated with the user input if it does not fit on its own.
+fn left_truncation_index(segment_lengths: &[usize], budget: usize) -> usize {
+    let mut total_length = segment_lengths.iter().sum::<usize>();
+    let mut index = 0;
+    for segment_length in segment_lengths {
+        if total_length <= budget {
+            break;
+        }
+        total_length -= segment_length;
+        index += 1;
+    }
+    index.min(segment_lengths.len().saturating_sub(1))
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
+    use crate::gpt2::{GPT2LMHeadModel, Gpt2Config};
+    use crate::pipelines::common::ConfigOption;
+    use crate::resources::LocalResource;
+    use std::cell::RefCell;
+    use std::path::Path;
+    use tch::nn;
 
     #[test]
     #[ignore] // no need to run, compilation is enough to verify it is Send
@@ -1040,4 +1731,316 @@ mod test {
         let config = ConversationConfig::default();
         let _: Box<dyn Send> = Box::new(ConversationModel::new(config));
     }
+
+    #[test]
+    fn test_file_conversation_store() -> Result<(), RustBertError> {
+        let directory = tempfile::tempdir()?;
+        let mut store = FileConversationStore::new(directory.path())?;
+
+        let mut conversation = Conversation::new_empty();
+        conversation.past_user_inputs.push("Hello".to_string());
+        conversation
+            .generated_responses
+            .push("Hi there!".to_string());
+        conversation.history = vec![vec![1, 0], vec![2, 3, 0]];
+
+        let mut conversation_manager = ConversationManager::new();
+        let conversation_id = conversation_manager.add(conversation);
+        let new_conversation_id = conversation_manager.create("How are you?");
+        conversation_manager.save_to_store(&mut store)?;
+
+        let mut restored_manager = ConversationManager::from_store(&store)?;
+        assert_eq!(restored_manager.get_all().len(), 2);
+        let restored_conversation = restored_manager.get(&conversation_id).unwrap();
+        assert_eq!(restored_conversation.past_user_inputs, vec!["Hello"]);
+        assert_eq!(restored_conversation.generated_responses, vec!["Hi there!"]);
+        assert_eq!(
+            restored_conversation.history,
+            vec![vec![1, 0], vec![2, 3, 0]]
+        );
+        assert_eq!(
+            restored_manager
+                .get(&new_conversation_id)
+                .unwrap()
+                .new_user_input
+                .as_deref(),
+            Some("How are you?")
+        );
+
+        store.delete(&conversation_id)?;
+        assert!(store.load(&conversation_id)?.is_none());
+        assert_eq!(store.list()?, vec![new_conversation_id]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_turns_to_drop() {
+        let segment_lengths = [3, 2, 4, 4, 2, 1];
+        assert_eq!(turns_to_drop(&segment_lengths, 0, 16), Some(0));
+        assert_eq!(turns_to_drop(&segment_lengths, 0, 15), Some(1));
+        assert_eq!(turns_to_drop(&segment_lengths, 0, 10), Some(2));
+        assert_eq!(turns_to_drop(&segment_lengths, 0, 2), Some(3));
+        assert_eq!(turns_to_drop(&segment_lengths, 1, 8), Some(1));
+        assert_eq!(turns_to_drop(&segment_lengths, 1, 4), None);
+        // Incomplete last turn (history loaded without a final response)
+        assert_eq!(turns_to_drop(&[3, 2, 4], 0, 4), Some(1));
+    }
+
+    #[test]
+    fn test_left_truncation_index() {
+        let segment_lengths = [3, 2, 4, 4, 2, 1];
+        assert_eq!(left_truncation_index(&segment_lengths, 16), 0);
+        assert_eq!(left_truncation_index(&segment_lengths, 15), 1);
+        assert_eq!(left_truncation_index(&segment_lengths, 11), 2);
+        assert_eq!(left_truncation_index(&segment_lengths, 7), 3);
+        assert_eq!(left_truncation_index(&segment_lengths, 3), 4);
+        // The latest segment is kept even if it does not fit
+        assert_eq!(left_truncation_index(&segment_lengths, 0), 5);
+        assert_eq!(left_truncation_index(&[], 0), 0);
+    }
+
+    /// Summarizer recording the turns it is called with
+    struct MockSummarizer {
+        calls: RefCell<Vec<Vec<(String, String)>>>,
+    }
+
+    impl ConversationSummarizer for MockSummarizer {
+        fn summarize_turns(&self, turns: &[(&str, &str)]) -> String {
+            self.calls.borrow_mut().push(
+                turns
+                    .iter()
+                    .map(|(user_input, response)| (user_input.to_string(), response.to_string()))
+                    .collect(),
+            );
+            format!("summary of {} turns", turns.len())
+        }
+    }
+
+    fn three_turns_conversation() -> Conversation {
+        let mut conversation = Conversation::new_empty();
+        for turn in 1..=3 {
+            conversation.past_user_inputs.push(format!("input {turn}"));
+            conversation
+                .generated_responses
+                .push(format!("response {turn}"));
+            conversation.history.push(vec![turn, turn, 0]);
+            conversation.history.push(vec![10 * turn, 10 * turn, 0]);
+        }
+        conversation
+    }
+
+    #[test]
+    fn test_summarize_history() {
+        let summarizer = MockSummarizer {
+            calls: RefCell::new(vec![]),
+        };
+        let segment_lengths = [3; 6];
+        let encode_summary = |text: &str| {
+            assert_eq!(text, "summary of 2 turns");
+            vec![100, 0]
+        };
+
+        // The history fits: no summary is generated
+        let mut conversation = three_turns_conversation();
+        let history = summarize_history(
+            &mut conversation,
+            &summarizer,
+            &segment_lengths,
+            18,
+            encode_summary,
+        );
+        assert_eq!(history, flatten_segments(&conversation.history));
+        assert!(summarizer.calls.borrow().is_empty());
+        assert!(conversation.summary.is_none());
+
+        // The first two turns are replaced by their summary
+        let history = summarize_history(
+            &mut conversation,
+            &summarizer,
+            &segment_lengths,
+            9,
+            encode_summary,
+        );
+        assert_eq!(history, vec![100, 0, 3, 3, 0, 30, 30, 0]);
+        assert_eq!(
+            summarizer.calls.borrow().as_slice(),
+            &[vec![
+                ("input 1".to_string(), "response 1".to_string()),
+                ("input 2".to_string(), "response 2".to_string()),
+            ]]
+        );
+        assert_eq!(
+            conversation.summary,
+            Some(ConversationSummary {
+                text: "summary of 2 turns".to_string(),
+                summarized_turns: 2,
+            })
+        );
+
+        // The stored summary is re-used
+        let cached_history = summarize_history(
+            &mut conversation,
+            &summarizer,
+            &segment_lengths,
+            9,
+            encode_summary,
+        );
+        assert_eq!(cached_history, history);
+        assert_eq!(summarizer.calls.borrow().len(), 1);
+
+        // A longer summary requires summarizing an additional turn
+        let summarizer = MockSummarizer {
+            calls: RefCell::new(vec![]),
+        };
+        let mut conversation = three_turns_conversation();
+        let history = summarize_history(
+            &mut conversation,
+            &summarizer,
+            &segment_lengths,
+            9,
+            |text: &str| vec![100; text.len() / 4],
+        );
+        assert_eq!(history, vec![100; 4]);
+        assert_eq!(summarizer.calls.borrow().len(), 2);
+        assert_eq!(summarizer.calls.borrow()[1].len(), 3);
+        assert_eq!(conversation.summary.unwrap().summarized_turns, 3);
+    }
+
+    /// Saves a randomly initialized GPT2 model with a byte-level vocabulary of printable ASCII characters
+    fn save_tiny_gpt2(directory: &Path) -> Result<(), RustBertError> {
+        let mut vocab = HashMap::new();
+        vocab.insert("<|endoftext|>".to_string(), 0);
+        for (index, character) in ('!'..='~').chain(Some('Ġ')).enumerate() {
+            vocab.insert(character.to_string(), index as i64 + 1);
+        }
+        fs::write(
+            directory.join("vocab.json"),
+            serde_json::to_string(&vocab).unwrap(),
+        )?;
+        fs::write(directory.join("merges.txt"), "#version: 0.2\n")?;
+
+        let config = Gpt2Config {
+            n_ctx: 32,
+            n_embd: 8,
+            n_head: 2,
+            n_layer: 2,
+            n_positions: 32,
+            vocab_size: vocab.len() as i64,
+            ..Default::default()
+        };
+        let var_store = nn::VarStore::new(Device::Cpu);
+        let _ = GPT2LMHeadModel::new(var_store.root(), &config);
+        var_store.save(directory.join("rust_model.ot"))?;
+        ConfigOption::GPT2(config).to_file(directory.join("config.json"))
+    }
+
+    /// Tiny GPT2 conversation model with a context window of 16 tokens (`max_length` of 20)
+    fn tiny_conversation_model(directory: &Path) -> Result<ConversationModel, RustBertError> {
+        save_tiny_gpt2(directory)?;
+        ConversationModel::new(ConversationConfig {
+            model_type: ModelType::GPT2,
+            model_resource: Box::new(LocalResource::from(directory.join("rust_model.ot"))),
+            config_resource: Box::new(LocalResource::from(directory.join("config.json"))),
+            vocab_resource: Box::new(LocalResource::from(directory.join("vocab.json"))),
+            merges_resource: Some(Box::new(LocalResource::from(directory.join("merges.txt")))),
+            max_length: Some(20),
+            min_length_for_response: 4,
+            do_sample: false,
+            device: Device::Cpu,
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn test_truncate_history_turns() -> Result<(), RustBertError> {
+        let directory = tempfile::tempdir()?;
+        let mut model = tiny_conversation_model(directory.path())?;
+        let mut conversation = three_turns_conversation();
+
+        model.truncation_strategy = HistoryTruncationStrategy::DropOldestTurns;
+        assert_eq!(
+            model.truncate_history(&mut conversation, 4),
+            vec![2, 2, 0, 20, 20, 0, 3, 3, 0, 30, 30, 0]
+        );
+        assert!(model.truncate_history(&mut conversation, 11).is_empty());
+
+        model.truncation_strategy = HistoryTruncationStrategy::KeepFirstTurns(1);
+        assert_eq!(
+            model.truncate_history(&mut conversation, 4),
+            vec![1, 1, 0, 10, 10, 0, 3, 3, 0, 30, 30, 0]
+        );
+        // The first turn is dropped as well if it does not fit on its own
+        assert!(model.truncate_history(&mut conversation, 11).is_empty());
+
+        model.truncation_strategy = HistoryTruncationStrategy::KeepFirstTurns(2);
+        assert_eq!(
+            model.truncate_history(&mut conversation, 4),
+            vec![1, 1, 0, 10, 10, 0, 2, 2, 0, 20, 20, 0]
+        );
+
+        // The maximum response length of the overrides reduces the context window to 10 tokens
+        model.set_generation_overrides(
+            &mut conversation,
+            GenerationOverrides {
+                max_response_length: Some(10),
+                ..Default::default()
+            },
+        )?;
+        model.truncation_strategy = HistoryTruncationStrategy::DropOldestTurns;
+        assert_eq!(
+            model.truncate_history(&mut conversation, 4),
+            vec![3, 3, 0, 30, 30, 0]
+        );
+        assert_eq!(conversation.history.len(), 6);
+        Ok(())
+    }
+
+    #[test]
+    fn test_generation_overrides_batches() -> Result<(), RustBertError> {
+        let directory = tempfile::tempdir()?;
+        let model = tiny_conversation_model(directory.path())?;
+        let overrides = GenerationOverrides {
+            do_sample: Some(false),
+            max_response_length: Some(2),
+            ..Default::default()
+        };
+
+        let mut conversation_manager = ConversationManager::new();
+        let mut uuids = vec![];
+        for with_overrides in [true, false, true] {
+            let mut conversation = Conversation::new("Hello!");
+            if with_overrides {
+                model.set_generation_overrides(&mut conversation, overrides)?;
+            }
+            uuids.push(conversation_manager.add(conversation));
+        }
+        let output = model.generate_responses(&mut conversation_manager);
+        assert_eq!(output.len(), 3);
+
+        let responses = uuids
+            .iter()
+            .map(|uuid| {
+                conversation_manager
+                    .get(uuid)
+                    .unwrap()
+                    .history
+                    .last()
+                    .unwrap()
+                    .clone()
+            })
+            .collect::<Vec<Vec<i64>>>();
+        // Conversations sharing the same overrides are generated in the same batch
+        assert_eq!(responses[0], responses[2]);
+        assert!(responses[0].len() <= 2);
+        // Greedy decoding from the same prompt: the shorter response is a prefix of the default one
+        assert!(responses[1].starts_with(&responses[0]));
+        for uuid in uuids.iter() {
+            assert!(conversation_manager
+                .get(uuid)
+                .unwrap()
+                .new_user_input
+                .is_none());
+        }
+        Ok(())
+    }
 }
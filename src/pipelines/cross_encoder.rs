@@ -0,0 +1,373 @@
+// Copyright 2023 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! # Cross-encoder pipeline (e.g. passage re-ranking)
+//! Scores pairs of texts (for example a query and a passage) with a sequence classification model
+//! processing both texts jointly, such as the ms-marco MiniLM/BERT cross-encoders trained with a single relevance logit.
+//! Cross-encoders are more accurate than comparing sentence embeddings, but require a forward pass per pair:
+//! they are typically used to re-rank the candidates retrieved by a semantic search index.
+//!
+//! Models from the [Hugging Face Hub](https://huggingface.co/cross-encoder) need to be converted with `utils/convert_model.py` beforehand.
+//!
+//! ```no_run
+//! use rust_bert::pipelines::common::ModelType;
+//! use rust_bert::pipelines::cross_encoder::{
+//!     CrossEncoderActivation, CrossEncoderConfig, CrossEncoderModel,
+//! };
+//! use rust_bert::resources::LocalResource;
+//! use std::path::PathBuf;
+//! # fn main() -> anyhow::Result<()> {
+//!
+//! let mut config = CrossEncoderConfig::new(
+//!     ModelType::Bert,
+//!     LocalResource::from(PathBuf::from("path/to/ms-marco-MiniLM-L-6-v2/rust_model.ot")),
+//!     LocalResource::from(PathBuf::from("path/to/ms-marco-MiniLM-L-6-v2/config.json")),
+//!     LocalResource::from(PathBuf::from("path/to/ms-marco-MiniLM-L-6-v2/vocab.txt")),
+//!     None,
+//!     true,
+//!     None,
+//!     None,
+//! );
+//! config.activation = CrossEncoderActivation::Sigmoid;
+//! let cross_encoder = CrossEncoderModel::new(config)?;
+//!
+//! let query = "How many people live in Berlin?";
+//! let documents = [
+//!     "Berlin has a population of 3,520,031 registered inhabitants in an area of 891.82 square kilometers.",
+//!     "New York City is famous for the Metropolitan Museum of Art.",
+//! ];
+//! let ranked_documents = cross_encoder.rerank(query, &documents, 1)?;
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::common::error::RustBertError;
+use crate::pipelines::common::{ConfigOption, ModelType, TokenizerOption};
+use crate::pipelines::sequence_classification::{
+    pad_tokenized_input, SequenceClassificationOption,
+};
+use crate::resources::ResourceProvider;
+use rust_tokenizers::tokenizer::TruncationStrategy;
+use rust_tokenizers::TokenizedInput;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use tch::nn::VarStore;
+use tch::{no_grad, Device, Kind, Tensor};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+/// # Activation applied to the cross-encoder logits
+pub enum CrossEncoderActivation {
+    /// Raw logits
+    Identity,
+    /// Element-wise sigmoid, mapping single-logit relevance scores to (0, 1)
+    Sigmoid,
+    /// Softmax over the outputs of each pair (only meaningful for models with several outputs)
+    Softmax,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// # Document ranked by a `CrossEncoderModel`
+pub struct RankedDocument {
+    /// Position of the document in the input documents
+    pub index: usize,
+    /// Relevance score of the document for the query
+    pub score: f64,
+    /// Document text
+    pub text: String,
+}
+
+/// # Configuration for CrossEncoderModel
+/// Contains information regarding the model to load and device to place the model on.
+pub struct CrossEncoderConfig {
+    /// Model type
+    pub model_type: ModelType,
+    /// Model weights resource
+    pub model_resource: Box<dyn ResourceProvider + Send>,
+    /// Config resource
+    pub config_resource: Box<dyn ResourceProvider + Send>,
+    /// Vocab resource
+    pub vocab_resource: Box<dyn ResourceProvider + Send>,
+    /// Merges resource (default: None)
+    pub merges_resource: Option<Box<dyn ResourceProvider + Send>>,
+    /// Automatically lower case all input upon tokenization (assumes a lower-cased model)
+    pub lower_case: bool,
+    /// Flag indicating if the tokenizer should strip accents (normalization). Only used for BERT / ALBERT models
+    pub strip_accents: Option<bool>,
+    /// Flag indicating if the tokenizer should add a white space before each tokenized input (needed for some Roberta models)
+    pub add_prefix_space: Option<bool>,
+    /// Device to place the model on (default: CUDA/GPU when available)
+    pub device: Device,
+    /// Activation applied to the model logits (default: Identity)
+    pub activation: CrossEncoderActivation,
+    /// Maximum length of the tokenized pairs, truncated longest first (default: maximum length supported by the model)
+    pub max_length: Option<usize>,
+    /// Number of pairs processed in a single forward pass (default: 32)
+    pub batch_size: usize,
+}
+
+impl CrossEncoderConfig {
+    /// Instantiate a new cross-encoder configuration of the supplied type.
+    ///
+    /// # Arguments
+    ///
+    /// * `model_type` - `ModelType` indicating the model type to load (must match with the actual data to be loaded!)
+    /// * model - The `ResourceProvider` pointing to the model to load (e.g.  model.ot)
+    /// * config - The `ResourceProvider` pointing to the model configuration to load (e.g. config.json)
+    /// * vocab - The `ResourceProvider` pointing to the tokenizer's vocabulary to load (e.g.  vocab.txt/vocab.json)
+    /// * merges - An optional `ResourceProvider` pointing to the tokenizer's merge file to load (e.g.  merges.txt), needed only for Roberta.
+    /// * lower_case - A `bool` indicating whether the tokenizer should lower case all input (in case of a lower-cased model)
+    /// * strip_accents - An optional `bool` indicating whether the tokenizer should strip accents
+    /// * add_prefix_space - An optional `bool` indicating whether the tokenizer should add a leading space
+    pub fn new<RM, RC, RV>(
+        model_type: ModelType,
+        model_resource: RM,
+        config_resource: RC,
+        vocab_resource: RV,
+        merges_resource: Option<RV>,
+        lower_case: bool,
+        strip_accents: impl Into<Option<bool>>,
+        add_prefix_space: impl Into<Option<bool>>,
+    ) -> CrossEncoderConfig
+    where
+        RM: ResourceProvider + Send + 'static,
+        RC: ResourceProvider + Send + 'static,
+        RV: ResourceProvider + Send + 'static,
+    {
+        CrossEncoderConfig {
+            model_type,
+            model_resource: Box::new(model_resource),
+            config_resource: Box::new(config_resource),
+            vocab_resource: Box::new(vocab_resource),
+            merges_resource: merges_resource.map(|r| Box::new(r) as Box<_>),
+            lower_case,
+            strip_accents: strip_accents.into(),
+            add_prefix_space: add_prefix_space.into(),
+            device: Device::cuda_if_available(),
+            activation: CrossEncoderActivation::Identity,
+            max_length: None,
+            batch_size: 32,
+        }
+    }
+}
+
+/// # CrossEncoderModel to score text pairs (e.g. query/passage relevance)
+pub struct CrossEncoderModel {
+    tokenizer: TokenizerOption,
+    sequence_classifier: SequenceClassificationOption,
+    var_store: VarStore,
+    activation: CrossEncoderActivation,
+    max_length: usize,
+    batch_size: usize,
+}
+
+impl CrossEncoderModel {
+    /// Build a new `CrossEncoderModel`
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - `CrossEncoderConfig` object containing the resource references (model, vocabulary, configuration), scoring options and device placement (CPU/GPU)
+    pub fn new(config: CrossEncoderConfig) -> Result<CrossEncoderModel, RustBertError> {
+        if config.batch_size == 0 {
+            return Err(RustBertError::InvalidConfigurationError(
+                "The batch size of the cross-encoder must be positive".to_string(),
+            ));
+        }
+        let config_path = config.config_resource.get_local_path()?;
+        let vocab_path = config.vocab_resource.get_local_path()?;
+        let weights_path = config.model_resource.get_local_path()?;
+        let merges_path = if let Some(merges_resource) = &config.merges_resource {
+            Some(merges_resource.get_local_path()?)
+        } else {
+            None
+        };
+        let device = config.device;
+
+        let tokenizer = TokenizerOption::from_file(
+            config.model_type,
+            vocab_path.to_str().unwrap(),
+            merges_path.as_deref().map(|path| path.to_str().unwrap()),
+            config.lower_case,
+            config.strip_accents,
+            config.add_prefix_space,
+        )?;
+        let mut var_store = VarStore::new(device);
+        let model_config = ConfigOption::from_file(config.model_type, config_path);
+        let model_max_length = model_config
+            .get_max_len()
+            .map(|v| v as usize)
+            .unwrap_or(usize::MAX);
+        let max_length = match config.max_length {
+            Some(max_length) if max_length > model_max_length => {
+                return Err(RustBertError::InvalidConfigurationError(format!(
+                    "Maximum length {max_length} exceeds the maximum length supported by the model ({model_max_length})"
+                )));
+            }
+            Some(max_length) => max_length,
+            None => model_max_length,
+        };
+        let sequence_classifier =
+            SequenceClassificationOption::new(config.model_type, var_store.root(), &model_config)?;
+        var_store.load(weights_path)?;
+        Ok(CrossEncoderModel {
+            tokenizer,
+            sequence_classifier,
+            var_store,
+            activation: config.activation,
+            max_length,
+            batch_size: config.batch_size,
+        })
+    }
+
+    /// Runs the model on a batch of pairs, returning the activated scores as a (batch, num_labels) tensor on the CPU
+    fn forward_batch(&self, pairs: &[(&str, &str)]) -> Tensor {
+        let tokenized_input: Vec<TokenizedInput> = self.tokenizer.encode_pair_list(
+            pairs,
+            self.max_length,
+            &TruncationStrategy::LongestFirst,
+            0,
+        );
+        let pad_id = self
+            .tokenizer
+            .get_pad_id()
+            .expect("The Tokenizer used for cross-encoding should contain a PAD id");
+        let (input_tensor, token_type_ids, mask) = pad_tokenized_input(
+            &tokenized_input,
+            pad_id,
+            self.tokenizer.model_type(),
+            self.var_store.device(),
+        );
+
+        no_grad(|| {
+            let logits = self.sequence_classifier.forward_t(
+                Some(&input_tensor),
+                Some(&mask),
+                Some(&token_type_ids),
+                None,
+                None,
+                false,
+            );
+            let scores = match self.activation {
+                CrossEncoderActivation::Identity => logits,
+                CrossEncoderActivation::Sigmoid => logits.sigmoid(),
+                CrossEncoderActivation::Softmax => logits.softmax(-1, Kind::Float),
+            };
+            scores.to_kind(Kind::Double).detach().to(Device::Cpu)
+        })
+    }
+
+    /// Scores text pairs, returning all model outputs for each pair
+    ///
+    /// # Arguments
+    ///
+    /// * `pairs` - `&[(&str, &str)]` Array of text pairs (e.g. query and passage) to score.
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<Vec<f64>>` containing the (activated) model outputs for each pair
+    pub fn predict_scores(&self, pairs: &[(&str, &str)]) -> Result<Vec<Vec<f64>>, RustBertError> {
+        let mut scores = Vec::with_capacity(pairs.len());
+        for batch in pairs.chunks(self.batch_size) {
+            scores.extend(Vec::<Vec<f64>>::from(self.forward_batch(batch)));
+        }
+        Ok(scores)
+    }
+
+    /// Scores text pairs, returning a single relevance score per pair
+    ///
+    /// The relevance score is the model output for single-logit models, and the output of the last
+    /// label (positive class) for models with several outputs.
+    ///
+    /// # Arguments
+    ///
+    /// * `pairs` - `&[(&str, &str)]` Array of text pairs (e.g. query and passage) to score.
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<f64>` containing the relevance score for each pair
+    pub fn predict(&self, pairs: &[(&str, &str)]) -> Result<Vec<f64>, RustBertError> {
+        self.predict_scores(pairs)?
+            .into_iter()
+            .map(|scores| {
+                scores.last().copied().ok_or_else(|| {
+                    RustBertError::ValueError("The cross-encoder returned no output".to_string())
+                })
+            })
+            .collect()
+    }
+
+    /// Ranks documents by relevance for a query
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - Query text
+    /// * `documents` - Documents to rank
+    /// * `top_k` - Maximum number of documents returned
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<RankedDocument>` the `top_k` most relevant documents, by decreasing score
+    pub fn rerank<S>(
+        &self,
+        query: &str,
+        documents: &[S],
+        top_k: usize,
+    ) -> Result<Vec<RankedDocument>, RustBertError>
+    where
+        S: AsRef<str>,
+    {
+        let pairs = documents
+            .iter()
+            .map(|document| (query, document.as_ref()))
+            .collect::<Vec<(&str, &str)>>();
+        let scores = self.predict(&pairs)?;
+        let mut ranked_documents = documents
+            .iter()
+            .zip(scores)
+            .enumerate()
+            .map(|(index, (document, score))| RankedDocument {
+                index,
+                score,
+                text: document.as_ref().to_string(),
+            })
+            .collect::<Vec<RankedDocument>>();
+        ranked_documents.sort_by(|document_1, document_2| {
+            document_2
+                .score
+                .partial_cmp(&document_1.score)
+                .unwrap_or(Ordering::Equal)
+        });
+        ranked_documents.truncate(top_k);
+        Ok(ranked_documents)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::resources::LocalResource;
+    use std::path::PathBuf;
+
+    #[test]
+    #[ignore] // no need to run, compilation is enough to verify it is Send
+    fn test() {
+        let config = CrossEncoderConfig::new(
+            ModelType::Bert,
+            LocalResource::from(PathBuf::from("rust_model.ot")),
+            LocalResource::from(PathBuf::from("config.json")),
+            LocalResource::from(PathBuf::from("vocab.txt")),
+            None,
+            true,
+            None,
+            None,
+        );
+        let _: Box<dyn Send> = Box::new(CrossEncoderModel::new(config));
+    }
+}
@@ -72,9 +72,15 @@ use tch::kind::Kind::Int64;
 use tch::{no_grad, Device, Tensor};
 
 use crate::bart::LayerState as BartLayerState;
+use crate::bloom::LayerState as BloomLayerState;
 use crate::common::error::RustBertError;
+use crate::common::loss::{causal_lm_loss, cross_entropy_loss};
 use crate::common::resources::ResourceProvider;
 use crate::gpt_neo::LayerState as GPTNeoLayerState;
+use crate::gpt_neox::LayerState as GPTNeoXLayerState;
+use crate::gptj::LayerState as GPTJLayerState;
+use crate::llama::LayerState as LlamaLayerState;
+use crate::opt::LayerState as OPTLayerState;
 use crate::pipelines::generation_utils::private_generation_utils::{
     InternalGenerateOptions, PrivateLanguageGenerator,
 };
@@ -223,6 +229,11 @@ pub enum Cache {
     ReformerCache(Option<Vec<Option<ReformerLayerState>>>),
     ProphetNetCache(Option<Vec<(Option<ProphetNetLayerState>, Option<ProphetNetLayerState>)>>),
     GPTNeoCache(Option<Vec<Option<GPTNeoLayerState>>>),
+    GPTJCache(Option<Vec<Option<GPTJLayerState>>>),
+    GPTNeoXCache(Option<Vec<Option<GPTNeoXLayerState>>>),
+    LlamaCache(Option<Vec<Option<LlamaLayerState>>>),
+    OPTCache(Option<Vec<Option<OPTLayerState>>>),
+    BloomCache(Option<Vec<Option<BloomLayerState>>>),
     None,
 }
 
@@ -2337,6 +2348,59 @@ pub trait LMHeadModel {
         decoder_input_ids: Option<&Tensor>,
         train: bool,
     ) -> Result<LMModelOutput, RustBertError>;
+
+    /// Indicates if the logits at a given position predict the token at the next position (causal language
+    /// models) or the token at the same position (encoder-decoder models scoring the decoder input ids).
+    /// This is used to align the logits and labels when computing the loss.
+    fn predicts_next_token(&self) -> bool {
+        true
+    }
+
+    /// Forward pass through the model, computing the language modeling loss for the labels provided.
+    /// For causal language models the labels are usually the input ids, and are shifted internally. For
+    /// encoder-decoder models the labels are the target ids and the decoder input ids should be the
+    /// labels shifted to the right. Labels with a value of -100 do not contribute to the loss.
+    ///
+    /// # Arguments
+    ///
+    /// Arguments of `forward_t`, with the addition of:
+    /// * `labels` - `Tensor` of shape (*batch size*, *sequence_length*) with the target token ids
+    ///
+    /// # Returns
+    ///
+    /// * `LMModelOutput` with the `loss` field populated
+    #[allow(clippy::too_many_arguments)]
+    fn forward_t_with_labels(
+        &self,
+        input_ids: Option<&Tensor>,
+        layer_past: Cache,
+        attention_mask: Option<&Tensor>,
+        token_type_ids: Option<&Tensor>,
+        position_ids: Option<&Tensor>,
+        input_embeds: Option<&Tensor>,
+        encoder_outputs: Option<&Tensor>,
+        decoder_input_ids: Option<&Tensor>,
+        labels: &Tensor,
+        train: bool,
+    ) -> Result<LMModelOutput, RustBertError> {
+        let mut output = self.forward_t(
+            input_ids,
+            layer_past,
+            attention_mask,
+            token_type_ids,
+            position_ids,
+            input_embeds,
+            encoder_outputs,
+            decoder_input_ids,
+            train,
+        )?;
+        output.loss = Some(if self.predicts_next_token() {
+            causal_lm_loss(&output.lm_logits, labels)
+        } else {
+            cross_entropy_loss(&output.lm_logits, labels)
+        });
+        Ok(output)
+    }
 }
 
 /// Container holding a language model output for generation tasks
@@ -2345,4 +2409,6 @@ pub struct LMModelOutput {
     pub lm_logits: Tensor,
     /// cached state for improved efficiency during decoding
     pub cache: Cache,
+    /// Language modeling loss, computed if labels are provided (see `LMHeadModel::forward_t_with_labels`)
+    pub loss: Option<Tensor>,
 }
@@ -0,0 +1,172 @@
+use crate::pipelines::keywords_extraction::KeywordDeduplication;
+use crate::pipelines::translation::Language;
+use rust_tokenizers::Offset;
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+impl KeywordDeduplication {
+    /// Deduplication strategy applicable to documents in `language` (English if not set): the
+    /// rule-based lemmatizer only handles English plurals, other languages fall back to `Lowercase`.
+    pub(crate) fn for_language(self, language: Option<Language>) -> Self {
+        match (self, language) {
+            (KeywordDeduplication::Lemma, Some(language)) if language != Language::English => {
+                KeywordDeduplication::Lowercase
+            }
+            (deduplication, _) => deduplication,
+        }
+    }
+
+    /// Merges the keyword candidates sharing the same normalized form. The most frequent
+    /// surface form of each group is kept as the candidate text, and the offsets are merged.
+    pub(crate) fn deduplicate<'b>(
+        &self,
+        candidates: HashMap<Cow<'b, str>, Vec<Offset>>,
+    ) -> HashMap<Cow<'b, str>, Vec<Offset>> {
+        if let KeywordDeduplication::None = self {
+            return candidates;
+        }
+
+        let mut groups: HashMap<String, Vec<_>> = HashMap::new();
+        for (candidate, offsets) in candidates {
+            groups
+                .entry(self.normalize(&candidate))
+                .or_default()
+                .push((candidate, offsets));
+        }
+
+        groups
+            .into_values()
+            .map(|mut group| {
+                group.sort_by(|(text_a, offsets_a), (text_b, offsets_b)| {
+                    offsets_b
+                        .len()
+                        .cmp(&offsets_a.len())
+                        .then_with(|| text_a.cmp(text_b))
+                });
+                let mut group = group.into_iter();
+                let (text, mut offsets) = group.next().unwrap();
+                for (_, other_offsets) in group {
+                    offsets.extend(other_offsets);
+                }
+                offsets.sort_by_key(|offset| (offset.begin, offset.end));
+                (text, offsets)
+            })
+            .collect()
+    }
+
+    fn normalize(&self, candidate: &str) -> String {
+        match self {
+            KeywordDeduplication::None => candidate.to_string(),
+            KeywordDeduplication::Lowercase => candidate.to_lowercase(),
+            KeywordDeduplication::Lemma => candidate
+                .to_lowercase()
+                .split_whitespace()
+                .map(lemmatize)
+                .collect::<Vec<&str>>()
+                .join(" "),
+        }
+    }
+}
+
+/// Rule-based lemmatization of English plural nouns, returning the word stem. The `-ies` plurals
+/// and the singular forms ending with a consonant followed by `-y` are reduced to the same stem
+/// without the suffix (e.g. `libraries` and `library` to `librar`).
+fn lemmatize(word: &str) -> &str {
+    if word.chars().count() <= 3 {
+        return word;
+    }
+    if let Some(stem) = word.strip_suffix("ies") {
+        return stem;
+    }
+    if let Some(stem) = word.strip_suffix('y') {
+        if !stem.ends_with(|c: char| "aeiou".contains(c)) {
+            return stem;
+        }
+    }
+    for suffix in ["sses", "xes", "ches", "shes", "zzes"] {
+        if word.ends_with(suffix) {
+            return &word[..word.len() - 2];
+        }
+    }
+    for suffix in ["ss", "us", "is"] {
+        if word.ends_with(suffix) {
+            return word;
+        }
+    }
+    word.strip_suffix('s').unwrap_or(word)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_deduplication() {
+        let candidates: HashMap<Cow<str>, Vec<Offset>> = vec![
+            ("Memory safety", vec![Offset::new(0, 13)]),
+            (
+                "memory safety",
+                vec![Offset::new(20, 33), Offset::new(40, 53)],
+            ),
+            ("libraries", vec![Offset::new(60, 69)]),
+            ("library", vec![Offset::new(70, 77)]),
+            ("boxes", vec![Offset::new(80, 85)]),
+            ("box", vec![Offset::new(90, 93)]),
+            ("class", vec![Offset::new(100, 105)]),
+        ]
+        .into_iter()
+        .map(|(text, offsets)| (Cow::from(text), offsets))
+        .collect();
+
+        let lowercase = KeywordDeduplication::Lowercase.deduplicate(candidates.clone());
+        assert_eq!(lowercase.len(), 6);
+        assert_eq!(
+            lowercase.get("memory safety").unwrap(),
+            &vec![Offset::new(0, 13), Offset::new(20, 33), Offset::new(40, 53)]
+        );
+
+        let lemma = KeywordDeduplication::Lemma.deduplicate(candidates);
+        assert_eq!(lemma.len(), 4);
+        assert_eq!(
+            lemma.get("libraries").unwrap(),
+            &vec![Offset::new(60, 69), Offset::new(70, 77)]
+        );
+        assert_eq!(
+            lemma.get("box").unwrap(),
+            &vec![Offset::new(80, 85), Offset::new(90, 93)]
+        );
+        assert!(lemma.contains_key("class"));
+    }
+
+    #[test]
+    fn test_lemmatize() {
+        assert_eq!(lemmatize("libraries"), "librar");
+        assert_eq!(lemmatize("library"), "librar");
+        assert_eq!(lemmatize("days"), "day");
+        assert_eq!(lemmatize("boxes"), "box");
+        assert_eq!(lemmatize("glasses"), "glass");
+        assert_eq!(lemmatize("analysis"), "analysis");
+        assert_eq!(lemmatize("bus"), "bus");
+    }
+
+    #[test]
+    fn test_deduplication_language() {
+        let lemma = || KeywordDeduplication::Lemma;
+        assert!(matches!(
+            lemma().for_language(None),
+            KeywordDeduplication::Lemma
+        ));
+        assert!(matches!(
+            lemma().for_language(Some(Language::English)),
+            KeywordDeduplication::Lemma
+        ));
+        assert!(matches!(
+            lemma().for_language(Some(Language::German)),
+            KeywordDeduplication::Lowercase
+        ));
+        assert!(matches!(
+            KeywordDeduplication::None.for_language(Some(Language::German)),
+            KeywordDeduplication::None
+        ));
+    }
+}
@@ -1,6 +1,10 @@
+mod deduplication;
 mod pipeline;
 mod scorer;
 mod stopwords;
 mod tokenizer;
 
-pub use pipeline::{Keyword, KeywordExtractionConfig, KeywordExtractionModel, KeywordScorerType};
+pub use pipeline::{
+    Keyword, KeywordDeduplication, KeywordExtractionConfig, KeywordExtractionModel,
+    KeywordScorerType,
+};
@@ -20,13 +20,16 @@
 /// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
 /// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 /// SOFTWARE.
+use crate::pipelines::keywords_extraction::stopwords::language_stopwords;
 use crate::pipelines::keywords_extraction::tokenizer::StopWordsTokenizer;
+use crate::pipelines::pos_tagging::{POSConfig, POSModel};
 #[cfg(feature = "remote")]
 use crate::pipelines::sentence_embeddings::SentenceEmbeddingsModelType;
 use crate::pipelines::sentence_embeddings::{
     SentenceEmbeddingsConfig, SentenceEmbeddingsModel, SentenceEmbeddingsSentenceBertConfig,
     SentenceEmbeddingsTokenizerConfig,
 };
+use crate::pipelines::translation::Language;
 use crate::{Config, RustBertError};
 use regex::Regex;
 use rust_tokenizers::Offset;
@@ -64,12 +67,30 @@ pub enum KeywordScorerType {
     MaxSum,
 }
 
+/// # Deduplication strategies for keyword candidates
+///
+/// Candidates sharing the same normalized form are merged into a single candidate, keeping its
+/// most frequent surface form and the offsets of all variants.
+pub enum KeywordDeduplication {
+    /// Candidates are not deduplicated
+    None,
+    /// Candidates are deduplicated by their lowercase form
+    Lowercase,
+    /// Candidates are deduplicated by their lowercase lemmatized form (plural English nouns are
+    /// reduced to their singular form with a rule-based lemmatizer). Falls back to `Lowercase`
+    /// if the `language` of the configuration is not English.
+    Lemma,
+}
+
 /// # Configuration for Keyword extraction
 pub struct KeywordExtractionConfig<'a> {
     /// `SentenceEmbeddingsConfig` defining the sentence embeddings model to use
     pub sentence_embeddings_config: SentenceEmbeddingsConfig,
-    /// Optional list of tokenizer stopwords to exclude from the keywords candidate list. Default to a list of English stopwords.
+    /// Optional list of tokenizer stopwords to exclude from the keywords candidate list. Default to the stopwords of the `language`.
     pub tokenizer_stopwords: Option<HashSet<&'a str>>,
+    /// Optional language of the input documents, used to select a bundled list of stopwords if `tokenizer_stopwords` is not provided.
+    /// Stopwords are available for English, French, German, Spanish, Italian, Portuguese, Dutch and Russian. Defaults to English.
+    pub language: Option<Language>,
     /// Optional tokenization regex pattern. Defaults to sequence of word characters.
     pub tokenizer_pattern: Option<Regex>,
     /// `KeywordScorerType` used to rank keywords.
@@ -86,6 +107,12 @@ pub struct KeywordExtractionConfig<'a> {
     /// identify a global optimum for the ranker criterion, but are more likely to include sets that are less relevant to the
     /// input document. Larger values also have a higher computational and memory cost (N<sup>2</sup> scale)
     pub max_sum_candidates: Option<usize>,
+    /// Optional part of speech tagging model configuration. If provided, keyword candidates are the noun phrases
+    /// (sequences of adjectives followed by one or more nouns) identified by the tagger instead of the n-grams
+    /// of the input, and the `ngram_range` and `tokenizer_pattern` are not used.
+    pub pos_config: Option<POSConfig>,
+    /// `KeywordDeduplication` strategy used to merge keyword candidates.
+    pub deduplication: KeywordDeduplication,
 }
 
 #[cfg(feature = "remote")]
@@ -97,12 +124,15 @@ impl Default for KeywordExtractionConfig<'_> {
         Self {
             sentence_embeddings_config,
             tokenizer_stopwords: None,
+            language: None,
             tokenizer_pattern: None,
             scorer_type: KeywordScorerType::CosineSimilarity,
             ngram_range: (1, 1),
             num_keywords: 5,
             diversity: None,
             max_sum_candidates: None,
+            pos_config: None,
+            deduplication: KeywordDeduplication::None,
         }
     }
 }
@@ -113,9 +143,12 @@ impl Default for KeywordExtractionConfig<'_> {
 /// a tokenizer to define a keyword candidates list and a scorer to rank these keywords.
 /// - `sentence_embeddings_model`: Sentence embeddings model
 /// - `tokenizer`: tokenizer used to generate the list of candidates (differs from the transformer tokenizer)
+/// - `pos_model`: optional part of speech model used to generate noun phrase candidates
 pub struct KeywordExtractionModel<'a> {
     pub sentence_embeddings_model: SentenceEmbeddingsModel,
     pub tokenizer: StopWordsTokenizer<'a>,
+    pub pos_model: Option<POSModel>,
+    deduplication: KeywordDeduplication,
     scorer_type: KeywordScorerType,
     ngram_range: (usize, usize),
     num_keywords: usize,
@@ -162,14 +195,29 @@ impl<'a> KeywordExtractionModel<'a> {
             .do_lower_case
             .unwrap_or(sentence_bert_config.do_lower_case);
 
-        let tokenizer = StopWordsTokenizer::new(
-            config.tokenizer_stopwords,
-            config.tokenizer_pattern,
-            do_lower_case,
-        );
+        let tokenizer_stopwords = match (config.tokenizer_stopwords, config.language) {
+            (Some(tokenizer_stopwords), _) => Some(tokenizer_stopwords),
+            (None, Some(language)) => Some(
+                language_stopwords(language)
+                    .ok_or_else(|| {
+                        RustBertError::InvalidConfigurationError(format!(
+                            "No stopwords available for {language:?}, please provide a list of tokenizer_stopwords"
+                        ))
+                    })?
+                    .iter()
+                    .copied()
+                    .collect(),
+            ),
+            (None, None) => None,
+        };
+        let tokenizer =
+            StopWordsTokenizer::new(tokenizer_stopwords, config.tokenizer_pattern, do_lower_case);
+        let pos_model = config.pos_config.map(POSModel::new).transpose()?;
         Ok(Self {
             sentence_embeddings_model,
             tokenizer,
+            pos_model,
+            deduplication: config.deduplication.for_language(config.language),
             scorer_type: config.scorer_type,
             ngram_range: config.ngram_range,
             num_keywords: config.num_keywords,
@@ -207,7 +255,17 @@ impl<'a> KeywordExtractionModel<'a> {
     where
         S: AsRef<str> + Sync,
     {
-        let words = self.tokenizer.tokenize_list(inputs, self.ngram_range);
+        let words = match &self.pos_model {
+            Some(pos_model) => inputs
+                .iter()
+                .zip(pos_model.predict_tokens(inputs))
+                .map(|(input, pos_tags)| self.tokenizer.noun_phrases(input.as_ref(), &pos_tags))
+                .collect(),
+            None => self.tokenizer.tokenize_list(inputs, self.ngram_range),
+        }
+        .into_iter()
+        .map(|candidates| self.deduplication.deduplicate(candidates))
+        .collect::<Vec<HashMap<Cow<str>, Vec<Offset>>>>();
         let (flat_word_list, document_boundaries) =
             KeywordExtractionModel::flatten_word_list(&words);
 
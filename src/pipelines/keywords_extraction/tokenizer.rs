@@ -1,4 +1,5 @@
 use crate::pipelines::keywords_extraction::stopwords::ENGLISH_STOPWORDS;
+use crate::pipelines::token_classification::Token;
 use regex::Regex;
 use rust_tokenizers::{Offset, OffsetSize};
 use std::borrow::Cow;
@@ -79,6 +80,93 @@ impl<'a> StopWordsTokenizer<'a> {
         tokenized_text
     }
 
+    /// Extracts noun phrases (a sequence of adjectives followed by one or more nouns) from a text
+    /// tagged by a part of speech model. Leading stopwords are removed from the phrases.
+    pub fn noun_phrases<'b>(
+        &self,
+        text: &'b str,
+        pos_tags: &[Token],
+    ) -> HashMap<Cow<'b, str>, Vec<Offset>> {
+        let mut noun_phrases = HashMap::new();
+
+        // The tagger returns character offsets, converted to byte offsets for consistency
+        // with the n-gram candidates
+        let mut byte_positions = text
+            .char_indices()
+            .map(|(byte_position, _)| byte_position)
+            .collect::<Vec<usize>>();
+        byte_positions.push(text.len());
+
+        let mut phrase: Vec<Offset> = Vec::new();
+        let mut phrase_has_noun = false;
+        for pos_tag in pos_tags.iter().map(Some).chain(std::iter::once(None)) {
+            let tag_type = pos_tag.and_then(|pos_tag| {
+                pos_tag.offset.and_then(|offset| {
+                    let offset = Offset {
+                        begin: byte_positions[offset.begin as usize] as OffsetSize,
+                        end: byte_positions[offset.end as usize] as OffsetSize,
+                    };
+                    if is_noun(&pos_tag.label) {
+                        Some((offset, true))
+                    } else if is_adjective(&pos_tag.label) {
+                        Some((offset, false))
+                    } else {
+                        None
+                    }
+                })
+            });
+            match tag_type {
+                Some((offset, true)) => {
+                    phrase.push(offset);
+                    phrase_has_noun = true;
+                    continue;
+                }
+                Some((offset, false)) if !phrase_has_noun => {
+                    phrase.push(offset);
+                    continue;
+                }
+                _ => {}
+            }
+            if phrase_has_noun {
+                let first_word = phrase
+                    .iter()
+                    .position(|offset| {
+                        !self.is_stopword(&text[offset.begin as usize..offset.end as usize])
+                    })
+                    .unwrap_or(phrase.len());
+                if let (Some(first), Some(last)) = (phrase.get(first_word), phrase.last()) {
+                    let pos = Offset {
+                        begin: first.begin,
+                        end: last.end,
+                    };
+                    let mut phrase_text = Cow::from(&text[pos.begin as usize..pos.end as usize]);
+                    if self.do_lower_case {
+                        phrase_text = Cow::from(phrase_text.to_lowercase());
+                    }
+                    noun_phrases
+                        .entry(phrase_text)
+                        .and_modify(|pos_vec: &mut Vec<Offset>| pos_vec.push(pos))
+                        .or_insert_with(|| vec![pos]);
+                }
+            }
+            phrase.clear();
+            phrase_has_noun = false;
+            // An adjective following a noun starts a new phrase
+            if let Some((offset, false)) = tag_type {
+                phrase.push(offset);
+            }
+        }
+        noun_phrases
+    }
+
+    fn is_stopword(&self, word: &str) -> bool {
+        if self.do_lower_case {
+            self.stopwords.contains(word.to_lowercase().as_str())
+        } else {
+            self.stopwords.contains(word)
+        }
+    }
+
     pub fn tokenize_list<'b, S>(
         &self,
         texts: &'b [S],
@@ -93,3 +181,61 @@ impl<'a> StopWordsTokenizer<'a> {
             .collect()
     }
 }
+
+fn is_noun(label: &str) -> bool {
+    label.starts_with("NN") || label == "NOUN" || label == "PROPN"
+}
+
+fn is_adjective(label: &str) -> bool {
+    label.starts_with("JJ") || label == "ADJ"
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use rust_tokenizers::Mask;
+
+    fn pos_tag(text: &str, label: &str, begin: OffsetSize, end: OffsetSize) -> Token {
+        Token {
+            text: text.to_string(),
+            score: 1.0,
+            label: label.to_string(),
+            label_index: 0,
+            sentence: 0,
+            index: 0,
+            word_index: 0,
+            offset: Some(Offset { begin, end }),
+            mask: Mask::None,
+        }
+    }
+
+    #[test]
+    fn test_noun_phrases() {
+        let tokenizer = StopWordsTokenizer::new(None, None, true);
+        let text = "The other fast cars beat élégant Rust programs quickly.";
+        let pos_tags = [
+            pos_tag("The", "DT", 0, 3),
+            pos_tag("other", "JJ", 4, 9),
+            pos_tag("fast", "JJ", 10, 14),
+            pos_tag("cars", "NNS", 15, 19),
+            pos_tag("beat", "VBD", 20, 24),
+            pos_tag("élégant", "JJ", 25, 32),
+            pos_tag("Rust", "NNP", 33, 37),
+            pos_tag("programs", "NNS", 38, 46),
+            pos_tag("quickly", "RB", 47, 54),
+            pos_tag(".", ".", 54, 55),
+        ];
+
+        let noun_phrases = tokenizer.noun_phrases(text, &pos_tags);
+
+        assert_eq!(noun_phrases.len(), 2);
+        assert_eq!(
+            noun_phrases.get("fast cars").unwrap(),
+            &vec![Offset { begin: 10, end: 19 }]
+        );
+        assert_eq!(
+            noun_phrases.get("élégant rust programs").unwrap(),
+            &vec![Offset { begin: 25, end: 48 }]
+        );
+    }
+}
@@ -45,6 +45,12 @@
 //! }
 //! ```
 //!
+//! Beyond the most likely token per mask, the pipeline can return the top-k candidates for each mask
+//! (`predict_top_k`) or score a list of target words that may span several sub-tokens (`predict_targets`).
+//! Sequences with several masks can be decoded jointly (all masks predicted in a single forward pass)
+//! or iteratively (masks filled left to right, each prediction conditioning the following masks),
+//! see `MaskDecodingStrategy`.
+//!
 use crate::bert::BertForMaskedLM;
 use crate::common::error::RustBertError;
 use crate::deberta::DebertaForMaskedLM;
@@ -61,8 +67,9 @@ use crate::{
 use rust_tokenizers::tokenizer::TruncationStrategy;
 use rust_tokenizers::TokenizedInput;
 use std::borrow::Borrow;
+use std::cmp::min;
 use tch::nn::VarStore;
-use tch::{nn, no_grad, Device, Tensor};
+use tch::{nn, no_grad, Device, Kind, Tensor};
 
 #[derive(Debug, Clone)]
 /// Output container for masked language model pipeline.
@@ -75,6 +82,16 @@ pub struct MaskedToken {
     pub score: f64,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// # Decoding strategy for sequences containing multiple masks
+pub enum MaskDecodingStrategy {
+    /// All masks are predicted independently in a single forward pass
+    Joint,
+    /// Masks are filled one at a time from left to right with their most likely prediction,
+    /// each prediction conditioning the following masks
+    Iterative,
+}
+
 /// # Configuration for MaskedLanguageModel
 /// Contains information regarding the model to load and device to place the model on.
 pub struct MaskedLanguageConfig {
@@ -98,6 +115,8 @@ pub struct MaskedLanguageConfig {
     pub mask_token: Option<String>,
     /// Device to place the model on (default: CUDA/GPU when available)
     pub device: Device,
+    /// Number of sequences processed in a single forward pass when scoring targets (default: 32)
+    pub batch_size: usize,
 }
 
 impl MaskedLanguageConfig {
@@ -139,6 +158,7 @@ impl MaskedLanguageConfig {
             add_prefix_space: add_prefix_space.into(),
             mask_token: mask_token.into(),
             device: Device::cuda_if_available(),
+            batch_size: 32,
         }
     }
 }
@@ -297,6 +317,7 @@ impl MaskedLanguageOption {
                         input_embeds,
                         encoder_hidden_states,
                         encoder_mask,
+                        None,
                         train,
                     )
                     .prediction_scores
@@ -310,6 +331,7 @@ impl MaskedLanguageOption {
                         token_type_ids,
                         position_ids,
                         input_embeds,
+                        None,
                         train,
                     )
                     .expect("Error in Deberta forward_t")
@@ -323,6 +345,7 @@ impl MaskedLanguageOption {
                         token_type_ids,
                         position_ids,
                         input_embeds,
+                        None,
                         train,
                     )
                     .expect("Error in Deberta V2 forward_t")
@@ -339,13 +362,21 @@ impl MaskedLanguageOption {
                         input_embeds,
                         encoder_hidden_states,
                         encoder_mask,
+                        None,
                         train,
                     )
                     .prediction_scores
             }
             Self::FNet(ref model) => {
                 model
-                    .forward_t(input_ids, token_type_ids, position_ids, input_embeds, train)
+                    .forward_t(
+                        input_ids,
+                        token_type_ids,
+                        position_ids,
+                        input_embeds,
+                        None,
+                        train,
+                    )
                     .expect("Error in FNet forward pass.")
                     .prediction_scores
             }
@@ -360,6 +391,7 @@ pub struct MaskedLanguageModel {
     mask_token: Option<String>,
     var_store: VarStore,
     max_length: usize,
+    batch_size: usize,
 }
 
 impl MaskedLanguageModel {
@@ -380,6 +412,11 @@ impl MaskedLanguageModel {
     /// # }
     /// ```
     pub fn new(config: MaskedLanguageConfig) -> Result<MaskedLanguageModel, RustBertError> {
+        if config.batch_size == 0 {
+            return Err(RustBertError::InvalidConfigurationError(
+                "The batch size of the masked language model must be positive".to_string(),
+            ));
+        }
         let config_path = config.config_resource.get_local_path()?;
         let vocab_path = config.vocab_resource.get_local_path()?;
         let weights_path = config.model_resource.get_local_path()?;
@@ -415,6 +452,7 @@ impl MaskedLanguageModel {
             mask_token,
             var_store,
             max_length,
+            batch_size: config.batch_size,
         })
     }
 
@@ -551,7 +589,370 @@ impl MaskedLanguageModel {
         }
         Ok(output_tokens)
     }
+
+    fn get_mask_id(&self) -> Result<i64, RustBertError> {
+        self.tokenizer.get_mask_id().ok_or_else(|| {
+            RustBertError::InvalidConfigurationError(
+                "Tokenizer does not have a mask token id, Please use a tokenizer/model with a mask token.".into(),
+            )
+        })
+    }
+
+    fn encode_for_model<'a, S>(&self, input: S) -> Result<Vec<Vec<i64>>, RustBertError>
+    where
+        S: AsRef<[&'a str]>,
+    {
+        let tokenized_input = if let Some(mask_token) = &self.mask_token {
+            let input_with_replaced_mask = self.replace_mask_token(input.as_ref(), mask_token)?;
+            self.tokenizer.encode_list(
+                &input_with_replaced_mask,
+                self.max_length,
+                &TruncationStrategy::LongestFirst,
+                0,
+            )
+        } else {
+            self.tokenizer.encode_list(
+                input.as_ref(),
+                self.max_length,
+                &TruncationStrategy::LongestFirst,
+                0,
+            )
+        };
+        Ok(tokenized_input
+            .into_iter()
+            .map(|input| input.token_ids)
+            .collect())
+    }
+
+    /// Returns the log-probabilities over the vocabulary with shape (batch, sequence, vocabulary)
+    fn forward_log_probabilities(&self, token_ids: &[Vec<i64>]) -> Tensor {
+        self.forward_logits(token_ids).log_softmax(-1, Kind::Float)
+    }
+
+    /// Returns for each sequence the sum of the log-probabilities of the `(position, token id)` targets,
+    /// without keeping the log-probabilities over the full vocabulary
+    fn target_log_probabilities(
+        &self,
+        token_ids: &[Vec<i64>],
+        targets: &[Vec<(usize, i64)>],
+    ) -> Result<Vec<f64>, RustBertError> {
+        let max_len = token_ids.iter().map(|ids| ids.len()).max().unwrap_or(0);
+        let (target_indices, target_ids): (Vec<i64>, Vec<i64>) = targets
+            .iter()
+            .enumerate()
+            .flat_map(|(sequence_index, sequence_targets)| {
+                sequence_targets
+                    .iter()
+                    .map(move |(position, id)| ((sequence_index * max_len + position) as i64, *id))
+            })
+            .unzip();
+        let mut log_scores = vec![0f64; targets.len()];
+        if target_ids.is_empty() {
+            return Ok(log_scores);
+        }
+
+        let device = self.var_store.device();
+        let logits = self.forward_logits(token_ids);
+        let vocab_size = *logits.size().last().unwrap();
+        let target_log_probabilities = no_grad(|| {
+            logits
+                .reshape(&[-1, vocab_size])
+                .index_select(0, &Tensor::of_slice(&target_indices).to(device))
+                .log_softmax(-1, Kind::Float)
+                .gather(
+                    -1,
+                    &Tensor::of_slice(&target_ids).to(device).unsqueeze(-1),
+                    false,
+                )
+                .squeeze_dim(-1)
+        });
+        let mut target_log_probabilities = target_log_probabilities.iter::<f64>()?;
+        for (log_score, sequence_targets) in log_scores.iter_mut().zip(targets) {
+            *log_score = target_log_probabilities
+                .by_ref()
+                .take(sequence_targets.len())
+                .sum();
+        }
+        Ok(log_scores)
+    }
+
+    /// Returns the logits over the vocabulary with shape (batch, sequence, vocabulary)
+    fn forward_logits(&self, token_ids: &[Vec<i64>]) -> Tensor {
+        let max_len = token_ids.iter().map(|ids| ids.len()).max().unwrap_or(0);
+        let pad_id = self.tokenizer.get_pad_id().unwrap_or(0);
+        let mut padded_ids = Vec::with_capacity(token_ids.len());
+        let mut attention_masks = Vec::with_capacity(token_ids.len());
+        for ids in token_ids {
+            let mut padded = ids.clone();
+            padded.resize(max_len, pad_id);
+            padded_ids.push(Tensor::of_slice(&padded));
+            let mut attention_mask = vec![1i64; ids.len()];
+            attention_mask.resize(max_len, 0);
+            attention_masks.push(Tensor::of_slice(&attention_mask));
+        }
+        let input_tensor = Tensor::stack(&padded_ids, 0).to(self.var_store.device());
+        let attention_mask = Tensor::stack(&attention_masks, 0).to(self.var_store.device());
+        no_grad(|| {
+            self.language_encode.forward_t(
+                Some(&input_tensor),
+                Some(&attention_mask),
+                None,
+                None,
+                None,
+                None,
+                None,
+                false,
+            )
+        })
+    }
+
+    /// Fill masks with the top-k most likely tokens
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - `&[&str]` Array of texts to mask.
+    /// * `top_k` - `usize` number of candidates to return for each mask (at most the vocabulary size)
+    /// * `decoding_strategy` - `MaskDecodingStrategy` joint or iterative decoding of sequences with multiple masks.
+    ///   With the iterative strategy, each mask is filled with its most likely candidate before predicting the following ones.
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<Vec<Vec<MaskedToken>>>` containing for each input and each mask the `top_k` candidates with their probability, sorted by decreasing score
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::masked_language::{MaskDecodingStrategy, MaskedLanguageModel};
+    /// let mask_language_model = MaskedLanguageModel::new(Default::default())?;
+    ///
+    /// let input = ["The capital of [MASK] [MASK] is Washington."];
+    /// let output = mask_language_model.predict_top_k(&input, 5, MaskDecodingStrategy::Iterative)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn predict_top_k<'a, S>(
+        &self,
+        input: S,
+        top_k: usize,
+        decoding_strategy: MaskDecodingStrategy,
+    ) -> Result<Vec<Vec<Vec<MaskedToken>>>, RustBertError>
+    where
+        S: AsRef<[&'a str]>,
+    {
+        let mask_token_id = self.get_mask_id()?;
+        let mut token_ids = self.encode_for_model(input.as_ref())?;
+        let mask_positions = token_ids
+            .iter()
+            .map(|ids| get_mask_positions(ids, mask_token_id))
+            .collect::<Vec<Vec<usize>>>();
+        let mut output_tokens = mask_positions
+            .iter()
+            .map(|positions| vec![vec![]; positions.len()])
+            .collect::<Vec<Vec<Vec<MaskedToken>>>>();
+        if token_ids.is_empty() {
+            return Ok(output_tokens);
+        }
+
+        let num_steps = match decoding_strategy {
+            MaskDecodingStrategy::Joint => 1,
+            MaskDecodingStrategy::Iterative => mask_positions
+                .iter()
+                .map(|positions| positions.len())
+                .max()
+                .unwrap_or(0),
+        };
+        for step in 0..num_steps {
+            let log_probabilities = self.forward_log_probabilities(&token_ids);
+            let top_k = min(top_k as i64, *log_probabilities.size().last().unwrap());
+            for (input_id, positions) in mask_positions.iter().enumerate() {
+                let mask_indices = match decoding_strategy {
+                    MaskDecodingStrategy::Joint => 0..positions.len(),
+                    MaskDecodingStrategy::Iterative => step..min(step + 1, positions.len()),
+                };
+                for mask_index in mask_indices {
+                    let position = positions[mask_index];
+                    let (top_scores, top_ids) = log_probabilities
+                        .get(input_id as i64)
+                        .get(position as i64)
+                        .exp()
+                        .topk(top_k, -1, true, true);
+                    let candidates = top_ids
+                        .iter::<i64>()?
+                        .zip(top_scores.iter::<f64>()?)
+                        .map(|(id, score)| MaskedToken {
+                            text: self.tokenizer.decode(&[id], false, true),
+                            id,
+                            score,
+                        })
+                        .collect::<Vec<MaskedToken>>();
+                    if decoding_strategy == MaskDecodingStrategy::Iterative {
+                        if let Some(best_candidate) = candidates.first() {
+                            token_ids[input_id][position] = best_candidate.id;
+                        }
+                    }
+                    output_tokens[input_id][mask_index] = candidates;
+                }
+            }
+        }
+        Ok(output_tokens)
+    }
+
+    /// Score target words for each mask. Targets spanning several sub-tokens are scored by expanding
+    /// the mask into as many masks as the target has sub-tokens, the score being the product of the
+    /// sub-tokens probabilities. The other masks of the sequence are left masked.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - `&[&str]` Array of texts to mask.
+    /// * `targets` - `&[&str]` candidate words to score for each mask
+    /// * `decoding_strategy` - `MaskDecodingStrategy` for targets spanning several sub-tokens: joint (single forward pass)
+    ///   or iterative (sub-tokens scored left to right, conditioning on the previous sub-tokens of the target)
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<Vec<Vec<MaskedToken>>>` containing for each input and each mask the targets with their score, sorted by decreasing score.
+    ///   The `id` of a target spanning several sub-tokens is the id of its first sub-token.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::masked_language::{MaskDecodingStrategy, MaskedLanguageModel};
+    /// let mask_language_model = MaskedLanguageModel::new(Default::default())?;
+    ///
+    /// let input = ["Paris is the [MASK] of France."];
+    /// let targets = ["capital", "heart", "metropolis"];
+    /// let output =
+    ///     mask_language_model.predict_targets(&input, &targets, MaskDecodingStrategy::Joint)?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn predict_targets<'a, S>(
+        &self,
+        input: S,
+        targets: &[&str],
+        decoding_strategy: MaskDecodingStrategy,
+    ) -> Result<Vec<Vec<Vec<MaskedToken>>>, RustBertError>
+    where
+        S: AsRef<[&'a str]>,
+    {
+        let mask_token_id = self.get_mask_id()?;
+        let target_ids = targets
+            .iter()
+            .map(|target| {
+                let ids = self
+                    .tokenizer
+                    .convert_tokens_to_ids(&self.tokenizer.tokenize(&format!(" {target}")));
+                if ids.is_empty() {
+                    Err(RustBertError::ValueError(format!(
+                        "Target {target} could not be tokenized"
+                    )))
+                } else {
+                    Ok(ids)
+                }
+            })
+            .collect::<Result<Vec<Vec<i64>>, RustBertError>>()?;
+
+        let mut output_tokens = Vec::with_capacity(input.as_ref().len());
+        for token_ids in self.encode_for_model(input.as_ref())? {
+            let mask_positions = get_mask_positions(&token_ids, mask_token_id);
+            let mut sequence_tokens = Vec::with_capacity(mask_positions.len());
+            if mask_positions.is_empty() || targets.is_empty() {
+                output_tokens.push(sequence_tokens);
+                continue;
+            }
+            // One sequence per (mask, target) with the mask expanded to the target length
+            let mut expanded_ids = Vec::with_capacity(mask_positions.len() * targets.len());
+            let mut sequence_targets = Vec::with_capacity(expanded_ids.capacity());
+            for &position in mask_positions.iter() {
+                for (target, ids) in targets.iter().zip(target_ids.iter()) {
+                    if token_ids.len() + ids.len() - 1 > self.max_length {
+                        return Err(RustBertError::ValueError(format!(
+                            "Expanding the mask to the {} sub-tokens of target {target} exceeds the maximum sequence length ({})",
+                            ids.len(),
+                            self.max_length
+                        )));
+                    }
+                    let mut expanded = token_ids[..position].to_vec();
+                    expanded.extend(vec![mask_token_id; ids.len()]);
+                    expanded.extend_from_slice(&token_ids[position + 1..]);
+                    expanded_ids.push(expanded);
+                    sequence_targets.push((position, ids));
+                }
+            }
+            let mut log_scores = vec![0f64; expanded_ids.len()];
+            let num_steps = match decoding_strategy {
+                MaskDecodingStrategy::Joint => 1,
+                MaskDecodingStrategy::Iterative => {
+                    target_ids.iter().map(|ids| ids.len()).max().unwrap_or(0)
+                }
+            };
+            for step in 0..num_steps {
+                let step_targets = sequence_targets
+                    .iter()
+                    .map(|(position, ids)| {
+                        let sub_token_indices = match decoding_strategy {
+                            MaskDecodingStrategy::Joint => 0..ids.len(),
+                            MaskDecodingStrategy::Iterative => step..min(step + 1, ids.len()),
+                        };
+                        sub_token_indices
+                            .map(|sub_token_index| {
+                                (position + sub_token_index, ids[sub_token_index])
+                            })
+                            .collect::<Vec<(usize, i64)>>()
+                    })
+                    .collect::<Vec<Vec<(usize, i64)>>>();
+                for (batch_index, (batch_ids, batch_targets)) in expanded_ids
+                    .chunks(self.batch_size)
+                    .zip(step_targets.chunks(self.batch_size))
+                    .enumerate()
+                {
+                    let batch_log_scores =
+                        self.target_log_probabilities(batch_ids, batch_targets)?;
+                    for (log_score, batch_log_score) in log_scores[batch_index * self.batch_size..]
+                        .iter_mut()
+                        .zip(batch_log_scores)
+                    {
+                        *log_score += batch_log_score;
+                    }
+                }
+                // Condition the following steps on the scored sub-tokens
+                for (ids, step_targets) in expanded_ids.iter_mut().zip(step_targets) {
+                    for (position, id) in step_targets {
+                        ids[position] = id;
+                    }
+                }
+            }
+
+            for (mask_index, mask_log_scores) in log_scores.chunks(targets.len()).enumerate() {
+                let mut mask_tokens = mask_log_scores
+                    .iter()
+                    .enumerate()
+                    .map(|(target_index, log_score)| MaskedToken {
+                        text: targets[target_index].to_string(),
+                        id: sequence_targets[mask_index * targets.len() + target_index].1[0],
+                        score: log_score.exp(),
+                    })
+                    .collect::<Vec<MaskedToken>>();
+                mask_tokens.sort_by(|a, b| b.score.total_cmp(&a.score));
+                sequence_tokens.push(mask_tokens);
+            }
+            output_tokens.push(sequence_tokens);
+        }
+        Ok(output_tokens)
+    }
+}
+
+fn get_mask_positions(token_ids: &[i64], mask_token_id: i64) -> Vec<usize> {
+    token_ids
+        .iter()
+        .enumerate()
+        .filter(|(_, id)| **id == mask_token_id)
+        .map(|(position, _)| position)
+        .collect()
 }
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -562,4 +963,13 @@ mod test {
         let config = MaskedLanguageConfig::default();
         let _: Box<dyn Send> = Box::new(MaskedLanguageModel::new(config));
     }
+
+    #[test]
+    fn test_mask_positions() {
+        assert_eq!(
+            get_mask_positions(&[101, 103, 103, 7, 103, 102], 103),
+            [1, 2, 4]
+        );
+        assert!(get_mask_positions(&[101, 102], 103).is_empty());
+    }
 }
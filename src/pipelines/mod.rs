@@ -304,14 +304,17 @@
 //!     Sentiment {
 //!         polarity: Positive,
 //!         score: 0.998,
+//!         stars: None,
 //!     },
 //!     Sentiment {
 //!         polarity: Negative,
 //!         score: 0.992,
+//!         stars: None,
 //!     },
 //!     Sentiment {
 //!         polarity: Positive,
 //!         score: 0.999,
+//!         stars: None,
 //!     },
 //! ]
 //! # ;
@@ -474,14 +477,17 @@
 //! # ;
 //! ```
 
+pub mod chat;
 pub mod common;
 pub mod conversation;
+pub mod cross_encoder;
 pub mod generation_utils;
 pub mod keywords_extraction;
 pub mod masked_language;
 pub mod ner;
 pub mod pos_tagging;
 pub mod question_answering;
+pub mod semantic_search;
 pub mod sentence_embeddings;
 pub mod sentiment;
 pub mod sequence_classification;
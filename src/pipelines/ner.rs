@@ -130,8 +130,9 @@ use crate::common::error::RustBertError;
 use crate::pipelines::token_classification::{
     Token, TokenClassificationConfig, TokenClassificationModel,
 };
-use rust_tokenizers::Offset;
+use rust_tokenizers::{Mask, Offset};
 use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 /// # Entity generated by a `NERModel`
@@ -149,6 +150,47 @@ pub struct Entity {
 //type alias for some backward compatibility
 type NERConfig = TokenClassificationConfig;
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+/// # Strategy used to aggregate sub-token predictions into entities
+pub enum AggregationStrategy {
+    /// Entities are grouped from the sub-token predictions, words may be split across entities
+    Simple,
+    /// Each word is assigned the label predicted for its first sub-token
+    First,
+    /// Each word is assigned the label with the highest probability averaged over its sub-tokens
+    Average,
+    /// Each word is assigned the label of its sub-token with the highest score
+    Max,
+}
+
+#[derive(Debug, Clone)]
+/// # Configuration for the entity aggregation of a `NERModel`
+pub struct EntityAggregationConfig {
+    /// Strategy used to aggregate sub-tokens predictions (default: `AggregationStrategy::Simple`)
+    pub strategy: AggregationStrategy,
+    /// Minimum entity score, entities with a lower score are dropped (default: 0.0)
+    pub min_score: f64,
+    /// Optional set of entity labels (without tag prefix, e.g. `PER`) to return, all labels are returned if `None`
+    pub allowed_labels: Option<HashSet<String>>,
+}
+
+impl EntityAggregationConfig {
+    /// Creates a new entity aggregation configuration for the strategy provided, keeping all entities
+    pub fn new(strategy: AggregationStrategy) -> EntityAggregationConfig {
+        EntityAggregationConfig {
+            strategy,
+            min_score: 0.0,
+            allowed_labels: None,
+        }
+    }
+}
+
+impl Default for EntityAggregationConfig {
+    fn default() -> Self {
+        EntityAggregationConfig::new(AggregationStrategy::Simple)
+    }
+}
+
 /// # NERModel to extract named entities
 pub struct NERModel {
     token_classification_model: TokenClassificationModel,
@@ -287,21 +329,208 @@ impl NERModel {
         entities
     }
 
+    /// Extract full entities from a text, aggregating sub-token predictions at the word level before
+    /// performing entity chunking. The aggregation strategies follow the ones of the
+    /// [Hugging Face Transformers](https://github.com/huggingface/transformers) token classification pipeline.
+    /// Supports BIO, BIOES/BILOU and prefix-less (e.g. `PER`) labelling schemes. The entity score is
+    /// the average of the scores of the words (or sub-tokens for the simple strategy) it spans.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - `&[&str]` Array of texts to extract entities from.
+    /// * `aggregation_config` - `&EntityAggregationConfig` aggregation strategy and entity filters
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<Vec<Entity>>` containing consolidated extracted entities
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// # use rust_bert::pipelines::ner::{AggregationStrategy, EntityAggregationConfig, NERModel};
+    ///
+    /// let ner_model = NERModel::new(Default::default())?;
+    /// let input = ["Asked John Smith about Acme Corp"];
+    /// let aggregation_config = EntityAggregationConfig {
+    ///     min_score: 0.5,
+    ///     allowed_labels: Some(["PER".to_string()].into_iter().collect()),
+    ///     ..EntityAggregationConfig::new(AggregationStrategy::Average)
+    /// };
+    /// let output = ner_model.predict_aggregated_entities(&input, &aggregation_config);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn predict_aggregated_entities<S>(
+        &self,
+        input: &[S],
+        aggregation_config: &EntityAggregationConfig,
+    ) -> Vec<Vec<Entity>>
+    where
+        S: AsRef<str>,
+    {
+        let label_mapping = self.token_classification_model.get_label_mapping();
+        self.token_classification_model
+            .predict_label_scores(input, false)
+            .into_iter()
+            .zip(input.iter())
+            .map(|(scored_tokens, text)| {
+                let tokens = match aggregation_config.strategy {
+                    AggregationStrategy::Simple => scored_tokens
+                        .into_iter()
+                        .map(|(token, _)| token)
+                        .collect::<Vec<Token>>(),
+                    strategy => Self::aggregate_words(&scored_tokens, strategy, label_mapping),
+                };
+                let text_chars = text.as_ref().chars().collect::<Vec<char>>();
+                Self::chunk_entities(&tokens)
+                    .into_iter()
+                    .filter_map(|(start, end, label)| {
+                        let entity_tokens = &tokens[start..end];
+                        let offset = Offset {
+                            begin: entity_tokens.first()?.offset?.begin,
+                            end: entity_tokens.last()?.offset?.end,
+                        };
+                        let score = entity_tokens.iter().map(|token| token.score).sum::<f64>()
+                            / entity_tokens.len() as f64;
+                        let end_char = (offset.end as usize).min(text_chars.len());
+                        let begin_char = (offset.begin as usize).min(end_char);
+                        Some(Entity {
+                            word: text_chars[begin_char..end_char].iter().collect(),
+                            score,
+                            label: label.to_string(),
+                            offset,
+                        })
+                    })
+                    .filter(|entity| {
+                        let allowed_label = match &aggregation_config.allowed_labels {
+                            Some(labels) => labels.contains(&entity.label),
+                            None => true,
+                        };
+                        allowed_label & (entity.score >= aggregation_config.min_score)
+                    })
+                    .collect::<Vec<Entity>>()
+            })
+            .collect::<Vec<Vec<Entity>>>()
+    }
+
+    fn aggregate_words(
+        scored_tokens: &[(Token, Vec<f64>)],
+        strategy: AggregationStrategy,
+        label_mapping: &HashMap<i64, String>,
+    ) -> Vec<Token> {
+        let mut words: Vec<&[(Token, Vec<f64>)]> = Vec::new();
+        let mut word_start = 0;
+        for position in 1..=scored_tokens.len() {
+            if (position == scored_tokens.len())
+                || (scored_tokens[position].0.mask != Mask::Continuation)
+            {
+                if position > word_start {
+                    words.push(&scored_tokens[word_start..position]);
+                }
+                word_start = position;
+            }
+        }
+
+        words
+            .into_iter()
+            .map(|sub_tokens| {
+                let (label_index, score) = match strategy {
+                    AggregationStrategy::Simple | AggregationStrategy::First => {
+                        let first_token = &sub_tokens[0].0;
+                        (first_token.label_index, first_token.score)
+                    }
+                    AggregationStrategy::Max => {
+                        let max_token = sub_tokens
+                            .iter()
+                            .map(|(token, _)| token)
+                            .max_by(|a, b| a.score.total_cmp(&b.score))
+                            .unwrap();
+                        (max_token.label_index, max_token.score)
+                    }
+                    AggregationStrategy::Average => {
+                        let mut average_scores = vec![0f64; sub_tokens[0].1.len()];
+                        for (_, label_scores) in sub_tokens {
+                            for (average, score) in average_scores.iter_mut().zip(label_scores) {
+                                *average += score / sub_tokens.len() as f64;
+                            }
+                        }
+                        average_scores
+                            .into_iter()
+                            .enumerate()
+                            .max_by(|a, b| a.1.total_cmp(&b.1))
+                            .map(|(label_index, score)| (label_index as i64, score))
+                            .unwrap()
+                    }
+                };
+                let first_token = &sub_tokens[0].0;
+                let offset = match (
+                    first_token.offset,
+                    sub_tokens.last().and_then(|(token, _)| token.offset),
+                ) {
+                    (Some(first_offset), Some(last_offset)) => {
+                        Some(Offset::new(first_offset.begin, last_offset.end))
+                    }
+                    _ => None,
+                };
+                Token {
+                    text: sub_tokens
+                        .iter()
+                        .map(|(token, _)| token.text.as_str())
+                        .collect(),
+                    score,
+                    label: label_mapping
+                        .get(&label_index)
+                        .cloned()
+                        .unwrap_or_else(|| first_token.label.clone()),
+                    label_index,
+                    sentence: first_token.sentence,
+                    index: first_token.index,
+                    word_index: first_token.word_index,
+                    offset,
+                    mask: first_token.mask,
+                }
+            })
+            .collect()
+    }
+
     fn consolidate_entities(tokens: &[Token]) -> Vec<Entity> {
-        let mut entities: Vec<Entity> = Vec::new();
+        Self::chunk_entities(tokens)
+            .into_iter()
+            .filter_map(|(start, end, label)| {
+                let entity_tokens = &tokens[start..end];
+                Some(Entity {
+                    word: entity_tokens
+                        .iter()
+                        .map(|token| token.text.as_str())
+                        .collect::<Vec<&str>>()
+                        .join(" "),
+                    score: entity_tokens.iter().map(|token| token.score).product(),
+                    label: label.to_string(),
+                    offset: Offset {
+                        begin: entity_tokens.first()?.offset?.begin,
+                        end: entity_tokens.last()?.offset?.end,
+                    },
+                })
+            })
+            .collect()
+    }
+
+    fn chunk_entities(tokens: &[Token]) -> Vec<(usize, usize, &str)> {
+        let mut chunks = Vec::new();
 
         let mut entity_builder = EntityBuilder::new();
         for (position, token) in tokens.iter().enumerate() {
             let tag = token.get_tag();
             let label = token.get_label();
-            if let Some(entity) = entity_builder.handle_current_tag(tag, label, position, tokens) {
-                entities.push(entity)
+            if let Some(chunk) = entity_builder.handle_current_tag(tag, label, position) {
+                chunks.push(chunk)
             }
         }
-        if let Some(entity) = entity_builder.flush_and_reset(tokens.len(), tokens) {
-            entities.push(entity);
+        if let Some(chunk) = entity_builder.flush_and_reset(tokens.len()) {
+            chunks.push(chunk);
         }
-        entities
+        chunks
     }
 }
 
@@ -321,14 +550,13 @@ impl<'a> EntityBuilder<'a> {
         tag: Tag,
         label: &'a str,
         position: usize,
-        tokens: &[Token],
-    ) -> Option<Entity> {
+    ) -> Option<(usize, usize, &'a str)> {
         match tag {
-            Tag::Outside => self.flush_and_reset(position, tokens),
+            Tag::Outside => self.flush_and_reset(position),
             Tag::Begin | Tag::Single => {
-                let entity = self.flush_and_reset(position, tokens);
+                let chunk = self.flush_and_reset(position);
                 self.start_new(position, tag, label);
-                entity
+                chunk
             }
             Tag::Inside | Tag::End => {
                 if let Some((_, previous_tag, previous_label)) = self.previous_node {
@@ -336,9 +564,9 @@ impl<'a> EntityBuilder<'a> {
                         | (previous_tag == Tag::Single)
                         | (previous_label != label)
                     {
-                        let entity = self.flush_and_reset(position, tokens);
+                        let chunk = self.flush_and_reset(position);
                         self.start_new(position, tag, label);
-                        entity
+                        chunk
                     } else {
                         None
                     }
@@ -350,27 +578,12 @@ impl<'a> EntityBuilder<'a> {
         }
     }
 
-    fn flush_and_reset(&mut self, position: usize, tokens: &[Token]) -> Option<Entity> {
-        let entity = if let Some((start, _, label)) = self.previous_node {
-            let entity_tokens = &tokens[start..position];
-            Some(Entity {
-                word: entity_tokens
-                    .iter()
-                    .map(|token| token.text.as_str())
-                    .collect::<Vec<&str>>()
-                    .join(" "),
-                score: entity_tokens.iter().map(|token| token.score).product(),
-                label: label.to_string(),
-                offset: Offset {
-                    begin: entity_tokens.first()?.offset?.begin,
-                    end: entity_tokens.last()?.offset?.end,
-                },
-            })
-        } else {
-            None
-        };
+    fn flush_and_reset(&mut self, position: usize) -> Option<(usize, usize, &'a str)> {
+        let chunk = self
+            .previous_node
+            .map(|(start, _, label)| (start, position, label));
         self.previous_node = None;
-        entity
+        chunk
     }
 
     fn start_new(&mut self, position: usize, tag: Tag, label: &'a str) {
@@ -388,23 +601,24 @@ enum Tag {
 }
 
 impl Token {
+    /// Tag prefixes follow the BIO, BIOES and BILOU schemes. Labels without a recognized prefix
+    /// (e.g. `PER`) are treated as inside tags of an entity spanning consecutive identical labels.
     fn get_tag(&self) -> Tag {
-        match self.label.split('-').collect::<Vec<&str>>()[0] {
-            "B" => Tag::Begin,
-            "I" => Tag::Inside,
-            "O" => Tag::Outside,
-            "E" => Tag::End,
-            "S" => Tag::Single,
-            _ => panic!("Invalid tag encountered for token {:?}", self),
+        match self.label.split_once('-') {
+            Some(("B", _)) => Tag::Begin,
+            Some(("I", _)) => Tag::Inside,
+            Some(("E", _)) | Some(("L", _)) => Tag::End,
+            Some(("S", _)) | Some(("U", _)) => Tag::Single,
+            _ if self.label == "O" => Tag::Outside,
+            _ => Tag::Inside,
         }
     }
 
     fn get_label(&self) -> &str {
-        let split_label = self.label.split('-').collect::<Vec<&str>>();
-        if split_label.len() > 1 {
-            split_label[1]
-        } else {
-            ""
+        match self.label.split_once('-') {
+            Some(("B" | "I" | "E" | "L" | "S" | "U", label)) => label,
+            _ if self.label == "O" => "",
+            _ => self.label.as_str(),
         }
     }
 }
@@ -419,4 +633,83 @@ mod test {
         let config = NERConfig::default();
         let _: Box<dyn Send> = Box::new(NERModel::new(config));
     }
+
+    fn build_token(label: &str, label_index: i64, score: f64, begin: u32, mask: Mask) -> Token {
+        Token {
+            text: String::new(),
+            score,
+            label: label.to_string(),
+            label_index,
+            sentence: 0,
+            index: 0,
+            word_index: 0,
+            offset: Some(Offset::new(begin, begin + 1)),
+            mask,
+        }
+    }
+
+    fn chunk_labels(labels: &[&str]) -> Vec<(usize, usize, String)> {
+        let tokens = labels
+            .iter()
+            .enumerate()
+            .map(|(position, label)| build_token(label, 0, 1.0, position as u32, Mask::None))
+            .collect::<Vec<Token>>();
+        NERModel::chunk_entities(&tokens)
+            .into_iter()
+            .map(|(start, end, label)| (start, end, label.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_chunk_tagging_schemes() {
+        let expected = vec![(0, 2, "PER".to_string()), (2, 3, "LOC".to_string())];
+        assert_eq!(chunk_labels(&["B-PER", "I-PER", "B-LOC", "O"]), expected);
+        assert_eq!(chunk_labels(&["B-PER", "E-PER", "S-LOC", "O"]), expected);
+        assert_eq!(chunk_labels(&["B-PER", "L-PER", "U-LOC", "O"]), expected);
+        assert_eq!(chunk_labels(&["PER", "PER", "LOC", "O"]), expected);
+        assert_eq!(
+            chunk_labels(&["S-PER", "S-PER"]),
+            vec![(0, 1, "PER".to_string()), (1, 2, "PER".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_word_aggregation_strategies() {
+        let label_mapping: HashMap<i64, String> = [(0, "O"), (1, "B-PER"), (2, "B-ORG")]
+            .iter()
+            .map(|(id, label)| (*id, label.to_string()))
+            .collect();
+        let scored_tokens = vec![
+            (
+                build_token("B-PER", 1, 0.5, 0, Mask::None),
+                vec![0.1, 0.5, 0.4],
+            ),
+            (
+                build_token("B-ORG", 2, 0.9, 1, Mask::Continuation),
+                vec![0.0, 0.1, 0.9],
+            ),
+            (
+                build_token("B-ORG", 2, 0.3, 2, Mask::Continuation),
+                vec![0.1, 0.6, 0.3],
+            ),
+            (build_token("O", 0, 0.8, 4, Mask::None), vec![0.8, 0.1, 0.1]),
+        ];
+
+        let first =
+            NERModel::aggregate_words(&scored_tokens, AggregationStrategy::First, &label_mapping);
+        assert_eq!(first.len(), 2);
+        assert_eq!(first[0].label, "B-PER");
+        assert_eq!(first[0].offset, Some(Offset::new(0, 3)));
+        assert_eq!(first[1].label, "O");
+
+        let max =
+            NERModel::aggregate_words(&scored_tokens, AggregationStrategy::Max, &label_mapping);
+        assert_eq!(max[0].label, "B-ORG");
+        assert_eq!(max[0].score, 0.9);
+
+        let average =
+            NERModel::aggregate_words(&scored_tokens, AggregationStrategy::Average, &label_mapping);
+        assert_eq!(average[0].label, "B-ORG");
+        assert!((average[0].score - 1.6 / 3.0).abs() < 1e-9);
+    }
 }
@@ -82,7 +82,9 @@
 //! To run the pipeline for another language, change the POSModel configuration from its default (see the NER pipeline for an illustration).
 
 use crate::common::error::RustBertError;
-use crate::pipelines::token_classification::{TokenClassificationConfig, TokenClassificationModel};
+use crate::pipelines::token_classification::{
+    Token, TokenClassificationConfig, TokenClassificationModel,
+};
 use serde::{Deserialize, Serialize};
 
 #[cfg(feature = "remote")]
@@ -91,7 +93,10 @@ use {
         mobilebert::{
             MobileBertConfigResources, MobileBertModelResources, MobileBertVocabResources,
         },
-        pipelines::{common::ModelType, token_classification::LabelAggregationOption},
+        pipelines::{
+            common::ModelType,
+            token_classification::{LabelAggregationOption, OverlapResolution},
+        },
         resources::RemoteResource,
     },
     tch::Device,
@@ -136,6 +141,9 @@ impl Default for POSConfig {
                 device: Device::cuda_if_available(),
                 label_aggregation_function: LabelAggregationOption::First,
                 batch_size: 64,
+                max_length: None,
+                stride: None,
+                overlap_resolution: OverlapResolution::CenterMost,
             },
         }
     }
@@ -147,6 +155,14 @@ impl From<POSConfig> for TokenClassificationConfig {
     }
 }
 
+impl From<TokenClassificationConfig> for POSConfig {
+    fn from(token_classification_config: TokenClassificationConfig) -> Self {
+        POSConfig {
+            token_classification_config,
+        }
+    }
+}
+
 /// # POSModel to extract Part of Speech tags
 pub struct POSModel {
     token_classification_model: TokenClassificationModel,
@@ -202,6 +218,26 @@ impl POSModel {
     /// # }
     /// ```
     pub fn predict<S>(&self, input: &[S]) -> Vec<Vec<POSTag>>
+    where
+        S: AsRef<str>,
+    {
+        self.predict_tokens(input)
+            .into_iter()
+            .map(|sequence_tokens| {
+                sequence_tokens
+                    .into_iter()
+                    .map(|token| POSTag {
+                        word: token.text,
+                        score: token.score,
+                        label: token.label,
+                    })
+                    .collect::<Vec<POSTag>>()
+            })
+            .collect::<Vec<Vec<POSTag>>>()
+    }
+
+    /// Tags the input texts, keeping the full token information (including the token offsets)
+    pub(crate) fn predict_tokens<S>(&self, input: &[S]) -> Vec<Vec<Token>>
     where
         S: AsRef<str>,
     {
@@ -220,14 +256,9 @@ impl POSModel {
                         };
                         token
                     })
-                    .map(|token| POSTag {
-                        word: token.text,
-                        score: token.score,
-                        label: token.label,
-                    })
-                    .collect::<Vec<POSTag>>()
+                    .collect::<Vec<Token>>()
             })
-            .collect::<Vec<Vec<POSTag>>>()
+            .collect::<Vec<Vec<Token>>>()
     }
 
     fn is_punctuation(string: &str) -> bool {
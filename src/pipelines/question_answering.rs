@@ -489,61 +489,74 @@ impl QuestionAnsweringOption {
     ) -> (Tensor, Tensor) {
         match *self {
             Self::Bert(ref model) => {
-                let outputs = model.forward_t(input_ids, mask, None, None, input_embeds, train);
+                let outputs =
+                    model.forward_t(input_ids, mask, None, None, input_embeds, None, None, train);
                 (outputs.start_logits, outputs.end_logits)
             }
             Self::Deberta(ref model) => {
                 let outputs = model
-                    .forward_t(input_ids, mask, None, None, input_embeds, train)
+                    .forward_t(input_ids, mask, None, None, input_embeds, None, None, train)
                     .expect("Error in Deberta forward_t");
                 (outputs.start_logits, outputs.end_logits)
             }
             Self::DebertaV2(ref model) => {
                 let outputs = model
-                    .forward_t(input_ids, mask, None, None, input_embeds, train)
+                    .forward_t(input_ids, mask, None, None, input_embeds, None, None, train)
                     .expect("Error in Deberta V2 forward_t");
                 (outputs.start_logits, outputs.end_logits)
             }
             Self::DistilBert(ref model) => {
                 let outputs = model
-                    .forward_t(input_ids, mask, input_embeds, train)
+                    .forward_t(input_ids, mask, input_embeds, None, None, train)
                     .expect("Error in distilbert forward_t");
                 (outputs.start_logits, outputs.end_logits)
             }
             Self::MobileBert(ref model) => {
                 let outputs = model
-                    .forward_t(input_ids, None, None, input_embeds, mask, train)
+                    .forward_t(input_ids, None, None, input_embeds, mask, None, None, train)
                     .expect("Error in mobilebert forward_t");
                 (outputs.start_logits, outputs.end_logits)
             }
             Self::Roberta(ref model) | Self::XLMRoberta(ref model) => {
-                let outputs = model.forward_t(input_ids, mask, None, None, input_embeds, train);
+                let outputs =
+                    model.forward_t(input_ids, mask, None, None, input_embeds, None, None, train);
                 (outputs.start_logits, outputs.end_logits)
             }
             Self::Albert(ref model) => {
-                let outputs = model.forward_t(input_ids, mask, None, None, input_embeds, train);
+                let outputs =
+                    model.forward_t(input_ids, mask, None, None, input_embeds, None, None, train);
                 (outputs.start_logits, outputs.end_logits)
             }
             Self::XLNet(ref model) => {
-                let outputs =
-                    model.forward_t(input_ids, mask, None, None, None, None, input_embeds, train);
+                let outputs = model.forward_t(
+                    input_ids,
+                    mask,
+                    None,
+                    None,
+                    None,
+                    None,
+                    input_embeds,
+                    None,
+                    None,
+                    train,
+                );
                 (outputs.start_logits, outputs.end_logits)
             }
             Self::Reformer(ref model) => {
                 let outputs = model
-                    .forward_t(input_ids, None, None, mask, None, train)
+                    .forward_t(input_ids, None, None, mask, None, None, None, train)
                     .expect("Error in reformer forward pass");
                 (outputs.start_logits, outputs.end_logits)
             }
             Self::Longformer(ref model) => {
                 let outputs = model
-                    .forward_t(input_ids, mask, None, None, None, None, train)
+                    .forward_t(input_ids, mask, None, None, None, None, None, None, train)
                     .expect("Error in reformer forward pass");
                 (outputs.start_logits, outputs.end_logits)
             }
             Self::FNet(ref model) => {
                 let outputs = model
-                    .forward_t(input_ids, None, None, None, train)
+                    .forward_t(input_ids, None, None, None, None, None, train)
                     .expect("Error in fnet forward pass");
                 (outputs.start_logits, outputs.end_logits)
             }
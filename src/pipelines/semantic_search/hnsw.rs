@@ -0,0 +1,223 @@
+// Copyright 2023 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Hierarchical Navigable Small World graph for approximate nearest neighbours search, following
+//! [Malkov & Yashunin, Efficient and robust approximate nearest neighbor search using Hierarchical Navigable Small World graphs](https://arxiv.org/abs/1603.09320).
+//! Vectors are expected to be normalized, the similarity between vectors is their dot product.
+
+use ordered_float::OrderedFloat;
+use serde::{Deserialize, Serialize};
+use std::cmp::{min, Reverse};
+use std::collections::{BinaryHeap, HashSet};
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+/// # Configuration for the HNSW approximate search backend
+pub struct HnswConfig {
+    /// Maximum number of neighbours of a node on the upper layers, twice this value on the base layer (default: 16)
+    pub max_neighbours: usize,
+    /// Size of the dynamic candidate list when inserting elements (default: 200)
+    pub ef_construction: usize,
+    /// Size of the dynamic candidate list when searching, raised to the number of results requested if lower (default: 64)
+    pub ef_search: usize,
+    /// Seed for the random generation of the node levels (default: 42)
+    pub seed: u64,
+}
+
+impl Default for HnswConfig {
+    fn default() -> Self {
+        HnswConfig {
+            max_neighbours: 16,
+            ef_construction: 200,
+            ef_search: 64,
+            seed: 42,
+        }
+    }
+}
+
+pub(crate) fn dot_product(vector_1: &[f32], vector_2: &[f32]) -> f32 {
+    vector_1
+        .iter()
+        .zip(vector_2.iter())
+        .map(|(value_1, value_2)| value_1 * value_2)
+        .sum()
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct HnswGraph {
+    config: HnswConfig,
+    /// Neighbours of each node for each of the layers it belongs to
+    neighbours: Vec<Vec<Vec<usize>>>,
+    entry_point: Option<usize>,
+    max_level: usize,
+    rng_state: u64,
+}
+
+impl HnswGraph {
+    pub(crate) fn new(config: HnswConfig) -> Self {
+        HnswGraph {
+            config,
+            neighbours: vec![],
+            entry_point: None,
+            max_level: 0,
+            rng_state: config.seed,
+        }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.neighbours.len()
+    }
+
+    /// Uniform sample in (0, 1] using a SplitMix64 generator
+    fn next_uniform(&mut self) -> f64 {
+        self.rng_state = self.rng_state.wrapping_add(0x9E3779B97F4A7C15);
+        let mut value = self.rng_state;
+        value = (value ^ (value >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+        value = (value ^ (value >> 27)).wrapping_mul(0x94D049BB133111EB);
+        value ^= value >> 31;
+        ((value >> 11) as f64 + 1.0) / (1u64 << 53) as f64
+    }
+
+    fn random_level(&mut self) -> usize {
+        let level_multiplier = 1.0 / (self.config.max_neighbours.max(2) as f64).ln();
+        (-self.next_uniform().ln() * level_multiplier).floor() as usize
+    }
+
+    fn max_layer_neighbours(&self, layer: usize) -> usize {
+        if layer == 0 {
+            2 * self.config.max_neighbours
+        } else {
+            self.config.max_neighbours
+        }
+    }
+
+    /// Inserts the next node in the graph. `vectors` contains the vectors of all nodes, the node
+    /// inserted being the one at position `self.len()`.
+    pub(crate) fn insert(&mut self, vectors: &[Vec<f32>]) {
+        let node = self.neighbours.len();
+        let level = self.random_level();
+        self.neighbours.push(vec![vec![]; level + 1]);
+
+        let entry_point = match self.entry_point {
+            Some(entry_point) => entry_point,
+            None => {
+                self.entry_point = Some(node);
+                self.max_level = level;
+                return;
+            }
+        };
+        let query = &vectors[node];
+        let mut entry_points = vec![entry_point];
+        for layer in (level + 1..=self.max_level).rev() {
+            entry_points = vec![self.search_layer(query, &entry_points, 1, layer, vectors)[0].1];
+        }
+        for layer in (0..=min(level, self.max_level)).rev() {
+            let candidates = self.search_layer(
+                query,
+                &entry_points,
+                self.config.ef_construction,
+                layer,
+                vectors,
+            );
+            let max_neighbours = self.max_layer_neighbours(layer);
+            let selected = candidates
+                .iter()
+                .map(|(_, candidate)| *candidate)
+                .filter(|candidate| *candidate != node)
+                .take(max_neighbours)
+                .collect::<Vec<usize>>();
+            for &neighbour in selected.iter() {
+                let links = &mut self.neighbours[neighbour][layer];
+                links.push(node);
+                if links.len() > max_neighbours {
+                    let neighbour_vector = &vectors[neighbour];
+                    links.sort_by_key(|link| {
+                        Reverse(OrderedFloat(dot_product(neighbour_vector, &vectors[*link])))
+                    });
+                    links.truncate(max_neighbours);
+                }
+            }
+            self.neighbours[node][layer] = selected;
+            entry_points = candidates.into_iter().map(|(_, node)| node).collect();
+        }
+        if level > self.max_level {
+            self.max_level = level;
+            self.entry_point = Some(node);
+        }
+    }
+
+    /// Returns up to `ef` approximate nearest nodes with their similarity, by decreasing similarity
+    pub(crate) fn search(
+        &self,
+        query: &[f32],
+        ef: usize,
+        vectors: &[Vec<f32>],
+    ) -> Vec<(f32, usize)> {
+        let entry_point = match self.entry_point {
+            Some(entry_point) => entry_point,
+            None => return vec![],
+        };
+        let mut entry_points = vec![entry_point];
+        for layer in (1..=self.max_level).rev() {
+            entry_points = vec![self.search_layer(query, &entry_points, 1, layer, vectors)[0].1];
+        }
+        self.search_layer(query, &entry_points, ef.max(1), 0, vectors)
+    }
+
+    fn search_layer(
+        &self,
+        query: &[f32],
+        entry_points: &[usize],
+        ef: usize,
+        layer: usize,
+        vectors: &[Vec<f32>],
+    ) -> Vec<(f32, usize)> {
+        let mut visited = HashSet::new();
+        let mut candidates = BinaryHeap::new();
+        let mut results = BinaryHeap::new();
+        for &entry_point in entry_points {
+            if visited.insert(entry_point) {
+                let similarity = OrderedFloat(dot_product(query, &vectors[entry_point]));
+                candidates.push((similarity, entry_point));
+                results.push(Reverse((similarity, entry_point)));
+                if results.len() > ef {
+                    results.pop();
+                }
+            }
+        }
+
+        while let Some((similarity, candidate)) = candidates.pop() {
+            let worst_similarity = results.peek().map(|Reverse((worst, _))| *worst).unwrap();
+            if (similarity < worst_similarity) & (results.len() >= ef) {
+                break;
+            }
+            for &neighbour in self.neighbours[candidate][layer].iter() {
+                if visited.insert(neighbour) {
+                    let neighbour_similarity =
+                        OrderedFloat(dot_product(query, &vectors[neighbour]));
+                    let worst_similarity =
+                        results.peek().map(|Reverse((worst, _))| *worst).unwrap();
+                    if (results.len() < ef) | (neighbour_similarity > worst_similarity) {
+                        candidates.push((neighbour_similarity, neighbour));
+                        results.push(Reverse((neighbour_similarity, neighbour)));
+                        if results.len() > ef {
+                            results.pop();
+                        }
+                    }
+                }
+            }
+        }
+        results
+            .into_sorted_vec()
+            .into_iter()
+            .map(|Reverse((similarity, node))| (similarity.0, node))
+            .collect()
+    }
+}
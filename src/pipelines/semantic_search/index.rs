@@ -0,0 +1,625 @@
+// Copyright 2023 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::pipelines::semantic_search::hnsw::{HnswConfig, HnswGraph};
+use crate::pipelines::sentence_embeddings::{Embedding, SentenceEmbeddingsModel};
+use crate::RustBertError;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::path::Path;
+use tch::{Device, Kind, Tensor};
+
+/// Arbitrary metadata attached to an indexed document
+pub type Metadata = HashMap<String, serde_json::Value>;
+
+/// Predicate on the document metadata, documents for which it returns `false` are excluded from the results
+pub type MetadataFilter = dyn Fn(&Metadata) -> bool;
+
+/// Number of queries scored at once by the exact backend
+const QUERY_BATCH_SIZE: usize = 256;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+/// # Search backend of a semantic search index
+pub enum IndexBackend {
+    /// Exact search, scoring all documents with a matrix multiplication
+    Exact,
+    /// Approximate search over a Hierarchical Navigable Small World graph
+    Hnsw(HnswConfig),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// # Document retrieved by a semantic search query
+pub struct SearchResult {
+    /// Document identifier
+    pub id: String,
+    /// Cosine similarity between the query and the document
+    pub score: f32,
+    /// Metadata attached to the document
+    pub metadata: Metadata,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct IndexEntry {
+    id: String,
+    metadata: Metadata,
+    deleted: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct IndexState {
+    backend: IndexBackend,
+    dimension: Option<usize>,
+    entries: Vec<IndexEntry>,
+    embeddings: Vec<Embedding>,
+    hnsw: Option<HnswGraph>,
+}
+
+/// # In-memory vector index for semantic search
+/// Stores L2-normalized sentence embeddings with an identifier and optional metadata, and retrieves
+/// the documents most similar to a query by cosine similarity.
+/// Removed documents are masked from the results until the index is compacted.
+///
+/// # Example
+///
+/// ```no_run
+/// # fn main() -> anyhow::Result<()> {
+/// use rust_bert::pipelines::semantic_search::{IndexBackend, SemanticSearchIndex};
+/// use rust_bert::pipelines::sentence_embeddings::{
+///     SentenceEmbeddingsBuilder, SentenceEmbeddingsModelType,
+/// };
+///
+/// let model = SentenceEmbeddingsBuilder::remote(SentenceEmbeddingsModelType::AllMiniLmL12V2)
+///     .create_model()?;
+/// let mut index = SemanticSearchIndex::new(IndexBackend::Exact);
+/// index.encode_and_index(
+///     &model,
+///     &["doc_1", "doc_2"],
+///     &["The cat sits on the mat", "Stocks rallied on Friday"],
+///     None,
+///     32,
+/// )?;
+/// let results = index.query(&model, &["Where is the cat?"], 1, None, None)?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct SemanticSearchIndex {
+    state: IndexState,
+    id_map: HashMap<String, usize>,
+    matrix: Option<Tensor>,
+    device: Device,
+}
+
+fn normalize(embedding: &[f32]) -> Embedding {
+    let norm = embedding
+        .iter()
+        .map(|value| value * value)
+        .sum::<f32>()
+        .sqrt()
+        .max(1e-12);
+    embedding.iter().map(|value| value / norm).collect()
+}
+
+fn to_tensor(embeddings: &[Embedding], dimension: usize, device: Device) -> Tensor {
+    let flat = embeddings
+        .iter()
+        .flat_map(|embedding| embedding.iter().copied())
+        .collect::<Vec<f32>>();
+    Tensor::of_slice(&flat)
+        .view((embeddings.len() as i64, dimension as i64))
+        .to(device)
+}
+
+impl SemanticSearchIndex {
+    /// Creates an empty index with the given search backend, stored on the CPU.
+    pub fn new(backend: IndexBackend) -> SemanticSearchIndex {
+        let hnsw = match backend {
+            IndexBackend::Exact => None,
+            IndexBackend::Hnsw(config) => Some(HnswGraph::new(config)),
+        };
+        SemanticSearchIndex {
+            state: IndexState {
+                backend,
+                dimension: None,
+                entries: vec![],
+                embeddings: vec![],
+                hnsw,
+            },
+            id_map: HashMap::new(),
+            matrix: None,
+            device: Device::Cpu,
+        }
+    }
+
+    /// Sets the device on which the exact backend scores the queries.
+    pub fn with_device(mut self, device: Device) -> SemanticSearchIndex {
+        self.device = device;
+        self.matrix = self.matrix.map(|matrix| matrix.to(device));
+        self
+    }
+
+    /// Number of documents in the index (excluding removed documents)
+    pub fn len(&self) -> usize {
+        self.id_map.len()
+    }
+
+    /// Returns `true` if the index contains no document
+    pub fn is_empty(&self) -> bool {
+        self.id_map.is_empty()
+    }
+
+    /// Returns `true` if a document with this identifier is indexed
+    pub fn contains(&self, id: &str) -> bool {
+        self.id_map.contains_key(id)
+    }
+
+    /// Adds documents to the index. A document with an identifier already present replaces the existing one.
+    ///
+    /// # Arguments
+    ///
+    /// * `ids` - Identifiers of the documents
+    /// * `embeddings` - Embeddings of the documents, all of the same dimension
+    /// * `metadata` - Optional metadata for each document
+    pub fn add<S>(
+        &mut self,
+        ids: &[S],
+        embeddings: &[Embedding],
+        metadata: Option<Vec<Metadata>>,
+    ) -> Result<(), RustBertError>
+    where
+        S: AsRef<str>,
+    {
+        if ids.len() != embeddings.len() {
+            return Err(RustBertError::ValueError(format!(
+                "Number of ids ({}) and embeddings ({}) differ",
+                ids.len(),
+                embeddings.len()
+            )));
+        }
+        let metadata = match metadata {
+            Some(metadata) if metadata.len() != ids.len() => {
+                return Err(RustBertError::ValueError(format!(
+                    "Number of ids ({}) and metadata ({}) differ",
+                    ids.len(),
+                    metadata.len()
+                )));
+            }
+            Some(metadata) => metadata,
+            None => vec![Metadata::new(); ids.len()],
+        };
+        let dimension = match (self.state.dimension, embeddings.first()) {
+            (Some(dimension), _) => dimension,
+            (None, Some(embedding)) => embedding.len(),
+            (None, None) => return Ok(()),
+        };
+        if let Some(embedding) = embeddings
+            .iter()
+            .find(|embedding| embedding.len() != dimension)
+        {
+            return Err(RustBertError::ValueError(format!(
+                "Embedding dimension {} does not match the index dimension {dimension}",
+                embedding.len()
+            )));
+        }
+        self.state.dimension = Some(dimension);
+
+        let normalized = embeddings
+            .iter()
+            .map(|embedding| normalize(embedding))
+            .collect::<Vec<Embedding>>();
+        for ((id, embedding), metadata) in ids.iter().zip(normalized.iter()).zip(metadata) {
+            let id = id.as_ref();
+            if let Some(slot) = self.id_map.remove(id) {
+                self.state.entries[slot].deleted = true;
+            }
+            self.id_map.insert(id.to_string(), self.state.entries.len());
+            self.state.entries.push(IndexEntry {
+                id: id.to_string(),
+                metadata,
+                deleted: false,
+            });
+            self.state.embeddings.push(embedding.clone());
+            if let Some(hnsw) = self.state.hnsw.as_mut() {
+                hnsw.insert(&self.state.embeddings);
+            }
+        }
+        if let IndexBackend::Exact = self.state.backend {
+            let new_rows = to_tensor(&normalized, dimension, self.device);
+            self.matrix = Some(match self.matrix.take() {
+                Some(matrix) => Tensor::cat(&[matrix, new_rows], 0),
+                None => new_rows,
+            });
+        }
+        Ok(())
+    }
+
+    /// Removes a document from the index, returning `true` if it was present.
+    pub fn remove(&mut self, id: &str) -> bool {
+        match self.id_map.remove(id) {
+            Some(slot) => {
+                self.state.entries[slot].deleted = true;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Rebuilds the index without the removed documents, releasing their storage.
+    pub fn compact(&mut self) {
+        let (entries, embeddings) = std::mem::take(&mut self.state.entries)
+            .into_iter()
+            .zip(std::mem::take(&mut self.state.embeddings))
+            .filter(|(entry, _)| !entry.deleted)
+            .unzip();
+        let hnsw = match self.state.backend {
+            IndexBackend::Exact => None,
+            IndexBackend::Hnsw(config) => Some(HnswGraph::new(config)),
+        };
+        self.state = IndexState {
+            backend: self.state.backend,
+            dimension: self.state.dimension,
+            entries,
+            embeddings,
+            hnsw,
+        };
+        self.rebuild();
+    }
+
+    /// Rebuilds the identifier map, the HNSW graph if empty and the exact search matrix from the entries
+    fn rebuild(&mut self) {
+        self.id_map = self
+            .state
+            .entries
+            .iter()
+            .enumerate()
+            .filter(|(_, entry)| !entry.deleted)
+            .map(|(slot, entry)| (entry.id.clone(), slot))
+            .collect();
+        let vectors = &self.state.embeddings;
+        if let Some(hnsw) = self.state.hnsw.as_mut() {
+            while hnsw.len() < vectors.len() {
+                hnsw.insert(&vectors[..hnsw.len() + 1]);
+            }
+        }
+        self.matrix = match (self.state.backend, self.state.dimension) {
+            (IndexBackend::Exact, Some(dimension)) if !vectors.is_empty() => {
+                Some(to_tensor(vectors, dimension, self.device))
+            }
+            _ => None,
+        };
+    }
+
+    fn is_allowed(&self, slot: usize, filter: Option<&MetadataFilter>) -> bool {
+        let entry = &self.state.entries[slot];
+        !entry.deleted
+            && match filter {
+                Some(filter) => filter(&entry.metadata),
+                None => true,
+            }
+    }
+
+    fn to_result(&self, slot: usize, score: f32) -> SearchResult {
+        let entry = &self.state.entries[slot];
+        SearchResult {
+            id: entry.id.clone(),
+            score,
+            metadata: entry.metadata.clone(),
+        }
+    }
+
+    /// Retrieves the documents most similar to each query embedding.
+    ///
+    /// # Arguments
+    ///
+    /// * `queries` - Query embeddings
+    /// * `top_k` - Maximum number of documents returned per query
+    /// * `min_score` - Optional minimum cosine similarity of the documents returned
+    /// * `filter` - Optional predicate on the document metadata
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<Vec<SearchResult>>` retrieved documents for each query, by decreasing score
+    pub fn search(
+        &self,
+        queries: &[Embedding],
+        top_k: usize,
+        min_score: Option<f32>,
+        filter: Option<&MetadataFilter>,
+    ) -> Result<Vec<Vec<SearchResult>>, RustBertError> {
+        let dimension = match self.state.dimension {
+            Some(dimension) if !self.is_empty() && top_k > 0 => dimension,
+            _ => return Ok(vec![vec![]; queries.len()]),
+        };
+        if let Some(query) = queries.iter().find(|query| query.len() != dimension) {
+            return Err(RustBertError::ValueError(format!(
+                "Query dimension {} does not match the index dimension {dimension}",
+                query.len()
+            )));
+        }
+        let queries = queries
+            .iter()
+            .map(|query| normalize(query))
+            .collect::<Vec<Embedding>>();
+        let min_score = min_score.unwrap_or(f32::NEG_INFINITY);
+        match self.state.hnsw.as_ref() {
+            Some(hnsw) => Ok(self.search_hnsw(hnsw, &queries, top_k, min_score, filter)),
+            None => self.search_exact(&queries, dimension, top_k, min_score, filter),
+        }
+    }
+
+    fn search_exact(
+        &self,
+        queries: &[Embedding],
+        dimension: usize,
+        top_k: usize,
+        min_score: f32,
+        filter: Option<&MetadataFilter>,
+    ) -> Result<Vec<Vec<SearchResult>>, RustBertError> {
+        let matrix = self.matrix.as_ref().ok_or_else(|| {
+            RustBertError::ValueError("Exact search matrix is not initialized".to_string())
+        })?;
+        let allowed = (0..self.state.entries.len())
+            .map(|slot| self.is_allowed(slot, filter))
+            .collect::<Vec<bool>>();
+        let num_allowed = allowed.iter().filter(|allowed| **allowed).count();
+        if num_allowed == 0 {
+            return Ok(vec![vec![]; queries.len()]);
+        }
+        let top_k = top_k.min(num_allowed) as i64;
+        let excluded = Tensor::of_slice(&allowed)
+            .logical_not()
+            .to(self.device)
+            .unsqueeze(0);
+
+        let mut results = Vec::with_capacity(queries.len());
+        for batch in queries.chunks(QUERY_BATCH_SIZE) {
+            let (scores, indices) = tch::no_grad(|| {
+                to_tensor(batch, dimension, self.device)
+                    .matmul(&matrix.transpose(0, 1))
+                    .masked_fill(&excluded, f64::NEG_INFINITY)
+                    .topk(top_k, -1, true, true)
+            });
+            let scores = Vec::<Vec<f32>>::from(scores.to_kind(Kind::Float));
+            let indices = Vec::<Vec<i64>>::from(indices);
+            for (query_scores, query_indices) in scores.iter().zip(indices.iter()) {
+                results.push(
+                    query_scores
+                        .iter()
+                        .zip(query_indices.iter())
+                        .take_while(|(score, _)| **score >= min_score)
+                        .map(|(score, slot)| self.to_result(*slot as usize, *score))
+                        .collect(),
+                );
+            }
+        }
+        Ok(results)
+    }
+
+    fn search_hnsw(
+        &self,
+        hnsw: &HnswGraph,
+        queries: &[Embedding],
+        top_k: usize,
+        min_score: f32,
+        filter: Option<&MetadataFilter>,
+    ) -> Vec<Vec<SearchResult>> {
+        let vectors = &self.state.embeddings;
+        let ef_search = match self.state.backend {
+            IndexBackend::Hnsw(config) => config.ef_search,
+            IndexBackend::Exact => unreachable!(),
+        };
+        queries
+            .iter()
+            .map(|query| {
+                // Widen the candidate list until enough documents pass the filters
+                let mut ef = ef_search.max(top_k);
+                loop {
+                    let candidates = hnsw.search(query, ef, vectors);
+                    let exhausted = (candidates.len() < ef) | (ef >= vectors.len());
+                    let mut below_threshold = false;
+                    let mut results = Vec::with_capacity(top_k);
+                    for (score, slot) in candidates {
+                        if score < min_score {
+                            below_threshold = true;
+                            break;
+                        }
+                        if self.is_allowed(slot, filter) {
+                            results.push(self.to_result(slot, score));
+                            if results.len() == top_k {
+                                break;
+                            }
+                        }
+                    }
+                    if (results.len() == top_k) | below_threshold | exhausted {
+                        return results;
+                    }
+                    ef *= 2;
+                }
+            })
+            .collect()
+    }
+
+    /// Encodes the queries with a sentence embeddings model and retrieves the most similar documents.
+    /// See [`SemanticSearchIndex::search`] for a description of the arguments.
+    pub fn query<S>(
+        &self,
+        model: &SentenceEmbeddingsModel,
+        queries: &[S],
+        top_k: usize,
+        min_score: Option<f32>,
+        filter: Option<&MetadataFilter>,
+    ) -> Result<Vec<Vec<SearchResult>>, RustBertError>
+    where
+        S: AsRef<str> + Sync,
+    {
+        let embeddings = model.encode(queries)?;
+        self.search(&embeddings, top_k, min_score, filter)
+    }
+
+    /// Encodes documents with a sentence embeddings model, `batch_size` documents at a time, and adds them to the index.
+    ///
+    /// # Arguments
+    ///
+    /// * `model` - Sentence embeddings model used to encode the documents
+    /// * `ids` - Identifiers of the documents
+    /// * `texts` - Texts of the documents
+    /// * `metadata` - Optional metadata for each document
+    /// * `batch_size` - Number of documents encoded at once
+    pub fn encode_and_index<I, S>(
+        &mut self,
+        model: &SentenceEmbeddingsModel,
+        ids: &[I],
+        texts: &[S],
+        metadata: Option<Vec<Metadata>>,
+        batch_size: usize,
+    ) -> Result<(), RustBertError>
+    where
+        I: AsRef<str>,
+        S: AsRef<str> + Sync,
+    {
+        if ids.len() != texts.len() {
+            return Err(RustBertError::ValueError(format!(
+                "Number of ids ({}) and texts ({}) differ",
+                ids.len(),
+                texts.len()
+            )));
+        }
+        if batch_size == 0 {
+            return Err(RustBertError::ValueError(
+                "Batch size must be positive".to_string(),
+            ));
+        }
+        let mut metadata = metadata.map(|metadata| metadata.into_iter());
+        for (batch_ids, batch_texts) in ids.chunks(batch_size).zip(texts.chunks(batch_size)) {
+            let embeddings = model.encode(batch_texts)?;
+            let batch_metadata = metadata
+                .as_mut()
+                .map(|metadata| metadata.by_ref().take(batch_ids.len()).collect());
+            self.add(batch_ids, &embeddings, batch_metadata)?;
+        }
+        Ok(())
+    }
+
+    /// Saves the index (documents, metadata and HNSW graph) to a JSON file.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), RustBertError> {
+        let writer = BufWriter::new(File::create(path)?);
+        serde_json::to_writer(writer, &self.state)
+            .map_err(|error| RustBertError::IOError(error.to_string()))
+    }
+
+    /// Loads an index saved with [`SemanticSearchIndex::save`], stored on the CPU.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<SemanticSearchIndex, RustBertError> {
+        let reader = BufReader::new(File::open(path)?);
+        let state: IndexState = serde_json::from_reader(reader)
+            .map_err(|error| RustBertError::IOError(error.to_string()))?;
+        let mut index = SemanticSearchIndex {
+            state,
+            id_map: HashMap::new(),
+            matrix: None,
+            device: Device::Cpu,
+        };
+        index.rebuild();
+        Ok(index)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn random_embeddings(count: usize, dimension: usize, seed: u64) -> Vec<Embedding> {
+        let mut state = seed;
+        (0..count)
+            .map(|_| {
+                (0..dimension)
+                    .map(|_| {
+                        state = state
+                            .wrapping_mul(6364136223846793005)
+                            .wrapping_add(1442695040888963407);
+                        ((state >> 33) as f32 / (1u64 << 31) as f32) - 0.5
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_hnsw_recall_and_filters() {
+        let embeddings = random_embeddings(500, 16, 0);
+        let ids = (0..500)
+            .map(|i| format!("doc_{i}"))
+            .collect::<Vec<String>>();
+        let metadata = (0..500)
+            .map(|i| {
+                let mut metadata = Metadata::new();
+                metadata.insert("even".to_string(), serde_json::Value::Bool(i % 2 == 0));
+                metadata
+            })
+            .collect::<Vec<Metadata>>();
+        let mut exact = SemanticSearchIndex::new(IndexBackend::Exact);
+        let mut hnsw = SemanticSearchIndex::new(IndexBackend::Hnsw(HnswConfig::default()));
+        exact
+            .add(&ids, &embeddings, Some(metadata.clone()))
+            .unwrap();
+        hnsw.add(&ids, &embeddings, Some(metadata)).unwrap();
+        assert!(hnsw.remove("doc_0"));
+        assert!(exact.remove("doc_0"));
+
+        let queries = random_embeddings(20, 16, 1);
+        let even: &MetadataFilter = &|metadata| metadata["even"] == serde_json::Value::Bool(true);
+        let exact_results = exact.search(&queries, 10, None, Some(even)).unwrap();
+        let hnsw_results = hnsw.search(&queries, 10, None, Some(even)).unwrap();
+        let mut hits = 0;
+        for (exact_result, hnsw_result) in exact_results.iter().zip(hnsw_results.iter()) {
+            assert_eq!(hnsw_result.len(), 10);
+            for result in hnsw_result {
+                assert_ne!(result.id, "doc_0");
+                assert_eq!(result.metadata["even"], serde_json::Value::Bool(true));
+            }
+            hits += exact_result
+                .iter()
+                .filter(|result| hnsw_result.iter().any(|other| other.id == result.id))
+                .count();
+        }
+        assert!(hits as f64 / 200.0 > 0.9);
+
+        let thresholded = exact.search(&queries, 10, Some(0.5), None).unwrap();
+        assert!(thresholded
+            .iter()
+            .flatten()
+            .all(|result| result.score >= 0.5));
+    }
+
+    #[test]
+    fn test_save_load() {
+        let embeddings = random_embeddings(50, 8, 2);
+        let ids = (0..50).map(|i| format!("doc_{i}")).collect::<Vec<String>>();
+        let mut index = SemanticSearchIndex::new(IndexBackend::Hnsw(HnswConfig::default()));
+        index.add(&ids, &embeddings, None).unwrap();
+        index.remove("doc_3");
+
+        let directory = tempfile::tempdir().unwrap();
+        let path = directory.path().join("index.json");
+        index.save(&path).unwrap();
+        let loaded = SemanticSearchIndex::load(&path).unwrap();
+        assert_eq!(loaded.len(), 49);
+        assert!(!loaded.contains("doc_3"));
+        let queries = random_embeddings(5, 8, 3);
+        let expected = index.search(&queries, 5, None, None).unwrap();
+        let actual = loaded.search(&queries, 5, None, None).unwrap();
+        for (expected, actual) in expected.iter().zip(actual.iter()) {
+            let expected_ids = expected.iter().map(|r| &r.id).collect::<Vec<&String>>();
+            let actual_ids = actual.iter().map(|r| &r.id).collect::<Vec<&String>>();
+            assert_eq!(expected_ids, actual_ids);
+        }
+    }
+}
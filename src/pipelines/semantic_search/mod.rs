@@ -0,0 +1,47 @@
+//! # Semantic search
+//!
+//! In-memory vector index over sentence embeddings, retrieving the documents closest to a query by
+//! cosine similarity. Two search backends are available:
+//! - `IndexBackend::Exact`: scores all documents with a batched matrix multiplication (optionally on GPU)
+//! - `IndexBackend::Hnsw`: approximate search over a Hierarchical Navigable Small World graph, suited to large collections
+//!
+//! Documents are added and removed by identifier and may carry metadata used to filter the results.
+//! Indices can be saved to and loaded from disk.
+//!
+//! ```no_run
+//! use rust_bert::pipelines::semantic_search::{
+//!     HnswConfig, IndexBackend, Metadata, MetadataFilter, SemanticSearchIndex,
+//! };
+//! use rust_bert::pipelines::sentence_embeddings::{
+//!     SentenceEmbeddingsBuilder, SentenceEmbeddingsModelType,
+//! };
+//!
+//! # fn main() -> anyhow::Result<()> {
+//! let model = SentenceEmbeddingsBuilder::remote(SentenceEmbeddingsModelType::AllMiniLmL12V2)
+//!     .create_model()?;
+//!
+//! let mut index = SemanticSearchIndex::new(IndexBackend::Hnsw(HnswConfig::default()));
+//! let documents = [
+//!     "A man is eating food.",
+//!     "A man is riding a horse.",
+//!     "A monkey is playing drums.",
+//! ];
+//! let ids = ["doc_1", "doc_2", "doc_3"];
+//! let metadata = ["food", "animals", "animals"]
+//!     .iter()
+//!     .map(|topic| Metadata::from([("topic".to_string(), serde_json::json!(topic))]))
+//!     .collect();
+//! index.encode_and_index(&model, &ids, &documents, Some(metadata), 32)?;
+//!
+//! let animals: &MetadataFilter = &|metadata| metadata["topic"] == "animals";
+//! let results = index.query(&model, &["Someone is on a horse"], 1, Some(0.3), Some(animals))?;
+//! index.save("path/to/index.json")?;
+//! # Ok(())
+//! # }
+//! ```
+
+mod hnsw;
+mod index;
+
+pub use hnsw::HnswConfig;
+pub use index::{IndexBackend, Metadata, MetadataFilter, SearchResult, SemanticSearchIndex};
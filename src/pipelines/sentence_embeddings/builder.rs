@@ -5,8 +5,10 @@ use tch::Device;
 
 use crate::pipelines::common::ModelType;
 use crate::pipelines::sentence_embeddings::{
-    SentenceEmbeddingsConfig, SentenceEmbeddingsModel, SentenceEmbeddingsModulesConfig,
+    SentenceEmbeddingsConfig, SentenceEmbeddingsDenseModuleResources, SentenceEmbeddingsModel,
+    SentenceEmbeddingsModulesConfig, SentenceEmbeddingsTokenizerConfig,
 };
+use crate::resources::LocalResource;
 use crate::{Config, RustBertError};
 
 #[cfg(feature = "remote")]
@@ -66,29 +68,61 @@ impl SentenceEmbeddingsBuilder<Local> {
             .join(&modules.pooling_module().path)
             .join("config.json");
 
-        let (dense_config, dense_weights) = modules
-            .dense_module()
+        match transformer_type {
+            ModelType::Bert
+            | ModelType::DistilBert
+            | ModelType::Roberta
+            | ModelType::XLMRoberta
+            | ModelType::Albert
+            | ModelType::T5
+            | ModelType::Deberta
+            | ModelType::DebertaV2
+            | ModelType::Electra
+            | ModelType::Longformer
+            | ModelType::MobileBert
+            | ModelType::FNet => {}
+            _ => {
+                return Err(RustBertError::InvalidConfigurationError(format!(
+                    "Unsupported transformer model {transformer_type:?} for Sentence Embeddings",
+                )));
+            }
+        };
+
+        let dense_resources = modules
+            .dense_modules()
+            .into_iter()
             .map(|m| {
-                (
-                    Some(model_dir.join(&m.path).join("config.json")),
-                    Some(model_dir.join(&m.path).join("rust_model.ot")),
+                SentenceEmbeddingsDenseModuleResources::new(
+                    LocalResource::from(model_dir.join(&m.path).join("config.json")),
+                    LocalResource::from(model_dir.join(&m.path).join("rust_model.ot")),
                 )
             })
-            .unwrap_or((None, None));
+            .collect();
 
         let tokenizer_config = model_dir.join("tokenizer_config.json");
         let sentence_bert_config = model_dir.join("sentence_bert_config.json");
-        let (tokenizer_vocab, tokenizer_merges) = match transformer_type {
-            ModelType::Bert | ModelType::DistilBert => (model_dir.join("vocab.txt"), None),
-            ModelType::Roberta => (
+        // The tokenizer may differ from the transformer (e.g. a BERT encoder distilled with an
+        // XLM-RoBERTa tokenizer), in which case it is identified by its `tokenizer_class`.
+        let tokenizer_type = SentenceEmbeddingsTokenizerConfig::from_file(&tokenizer_config)
+            .tokenizer_type()
+            .filter(|tokenizer_type| *tokenizer_type != transformer_type);
+        let (tokenizer_vocab, tokenizer_merges) = match tokenizer_type.unwrap_or(transformer_type) {
+            ModelType::Bert
+            | ModelType::DistilBert
+            | ModelType::Electra
+            | ModelType::MobileBert => (model_dir.join("vocab.txt"), None),
+            ModelType::Roberta | ModelType::Deberta | ModelType::Longformer => (
                 model_dir.join("vocab.json"),
                 Some(model_dir.join("merges.txt")),
             ),
-            ModelType::Albert => (model_dir.join("spiece.model"), None),
-            ModelType::T5 => (model_dir.join("spiece.model"), None),
-            _ => {
+            ModelType::XLMRoberta => (model_dir.join("sentencepiece.bpe.model"), None),
+            ModelType::DebertaV2 => (model_dir.join("spm.model"), None),
+            ModelType::Albert | ModelType::T5 | ModelType::FNet => {
+                (model_dir.join("spiece.model"), None)
+            }
+            other => {
                 return Err(RustBertError::InvalidConfigurationError(format!(
-                    "Unsupported transformer model {transformer_type:?} for Sentence Embeddings",
+                    "Unsupported tokenizer {other:?} for Sentence Embeddings",
                 )));
             }
         };
@@ -99,12 +133,12 @@ impl SentenceEmbeddingsBuilder<Local> {
             transformer_config_resource: transformer_config.into(),
             transformer_weights_resource: transformer_weights.into(),
             pooling_config_resource: pooling_config.into(),
-            dense_config_resource: dense_config.map(|r| r.into()),
-            dense_weights_resource: dense_weights.map(|r| r.into()),
+            dense_resources,
             sentence_bert_config_resource: sentence_bert_config.into(),
             tokenizer_config_resource: tokenizer_config.into(),
             tokenizer_vocab_resource: tokenizer_vocab.into(),
             tokenizer_merges_resource: tokenizer_merges.map(|r| r.into()),
+            tokenizer_type,
             device: self.device,
         };
 
@@ -148,13 +182,13 @@ impl SentenceEmbeddingsBuilder<Remote> {
         self
     }
 
-    pub fn dense_config(mut self, resource: RemoteResource) -> Self {
-        self.inner.config.dense_config_resource = Some(Box::new(resource));
-        self
-    }
-
-    pub fn dense_weights(mut self, resource: RemoteResource) -> Self {
-        self.inner.config.dense_weights_resource = Some(Box::new(resource));
+    /// Sets the configuration and weights resources of the Dense layers, in the order they
+    /// are applied.
+    pub fn dense_layers(mut self, resources: Vec<(RemoteResource, RemoteResource)>) -> Self {
+        self.inner.config.dense_resources = resources
+            .into_iter()
+            .map(|(config, weights)| SentenceEmbeddingsDenseModuleResources::new(config, weights))
+            .collect();
         self
     }
 
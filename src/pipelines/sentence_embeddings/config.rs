@@ -41,10 +41,9 @@ pub struct SentenceEmbeddingsConfig {
     pub transformer_weights_resource: Box<dyn ResourceProvider + Send>,
     /// Pooling layer configuration resource
     pub pooling_config_resource: Box<dyn ResourceProvider + Send>,
-    /// Optional dense layer configuration resource
-    pub dense_config_resource: Option<Box<dyn ResourceProvider + Send>>,
-    /// Optional dense layer weights resource
-    pub dense_weights_resource: Option<Box<dyn ResourceProvider + Send>>,
+    /// Dense layers configuration and weights resources, in the order they are applied
+    /// (may be empty)
+    pub dense_resources: Vec<SentenceEmbeddingsDenseModuleResources>,
     /// Sentence BERT specific configuration resource
     pub sentence_bert_config_resource: Box<dyn ResourceProvider + Send>,
     /// Transformer's tokenizer configuration resource
@@ -53,6 +52,9 @@ pub struct SentenceEmbeddingsConfig {
     pub tokenizer_vocab_resource: Box<dyn ResourceProvider + Send>,
     /// Optional transformer's tokenizer merges resource
     pub tokenizer_merges_resource: Option<Box<dyn ResourceProvider + Send>>,
+    /// Tokenizer type, if different from the transformer type
+    /// (e.g. XLM-RoBERTa tokenizer for a BERT transformer)
+    pub tokenizer_type: Option<ModelType>,
     /// Device to place the transformer model on
     pub device: Device,
 }
@@ -75,12 +77,14 @@ impl From<SentenceEmbeddingsModelType> for SentenceEmbeddingsConfig {
                 pooling_config_resource: Box::new(RemoteResource::from_pretrained(
                     SentenceEmbeddingsPoolingConfigResources::DISTILUSE_BASE_MULTILINGUAL_CASED,
                 )),
-                dense_config_resource: Some(Box::new(RemoteResource::from_pretrained(
-                    SentenceEmbeddingsDenseConfigResources::DISTILUSE_BASE_MULTILINGUAL_CASED,
-                ))),
-                dense_weights_resource: Some(Box::new(RemoteResource::from_pretrained(
-                    SentenceEmbeddingsDenseResources::DISTILUSE_BASE_MULTILINGUAL_CASED,
-                ))),
+                dense_resources: vec![SentenceEmbeddingsDenseModuleResources::new(
+                    RemoteResource::from_pretrained(
+                        SentenceEmbeddingsDenseConfigResources::DISTILUSE_BASE_MULTILINGUAL_CASED,
+                    ),
+                    RemoteResource::from_pretrained(
+                        SentenceEmbeddingsDenseResources::DISTILUSE_BASE_MULTILINGUAL_CASED,
+                    ),
+                )],
                 sentence_bert_config_resource: Box::new(RemoteResource::from_pretrained(
                     SentenceEmbeddingsConfigResources::DISTILUSE_BASE_MULTILINGUAL_CASED,
                 )),
@@ -91,6 +95,7 @@ impl From<SentenceEmbeddingsModelType> for SentenceEmbeddingsConfig {
                     DistilBertVocabResources::DISTILUSE_BASE_MULTILINGUAL_CASED,
                 )),
                 tokenizer_merges_resource: None,
+                tokenizer_type: None,
                 device: Device::cuda_if_available(),
             },
 
@@ -108,8 +113,7 @@ impl From<SentenceEmbeddingsModelType> for SentenceEmbeddingsConfig {
                 pooling_config_resource: Box::new(RemoteResource::from_pretrained(
                     SentenceEmbeddingsPoolingConfigResources::BERT_BASE_NLI_MEAN_TOKENS,
                 )),
-                dense_config_resource: None,
-                dense_weights_resource: None,
+                dense_resources: vec![],
                 sentence_bert_config_resource: Box::new(RemoteResource::from_pretrained(
                     SentenceEmbeddingsConfigResources::BERT_BASE_NLI_MEAN_TOKENS,
                 )),
@@ -120,6 +124,7 @@ impl From<SentenceEmbeddingsModelType> for SentenceEmbeddingsConfig {
                     BertVocabResources::BERT_BASE_NLI_MEAN_TOKENS,
                 )),
                 tokenizer_merges_resource: None,
+                tokenizer_type: None,
                 device: Device::cuda_if_available(),
             },
 
@@ -137,8 +142,7 @@ impl From<SentenceEmbeddingsModelType> for SentenceEmbeddingsConfig {
                 pooling_config_resource: Box::new(RemoteResource::from_pretrained(
                     SentenceEmbeddingsPoolingConfigResources::ALL_MINI_LM_L12_V2,
                 )),
-                dense_config_resource: None,
-                dense_weights_resource: None,
+                dense_resources: vec![],
                 sentence_bert_config_resource: Box::new(RemoteResource::from_pretrained(
                     SentenceEmbeddingsConfigResources::ALL_MINI_LM_L12_V2,
                 )),
@@ -149,6 +153,7 @@ impl From<SentenceEmbeddingsModelType> for SentenceEmbeddingsConfig {
                     BertVocabResources::ALL_MINI_LM_L12_V2,
                 )),
                 tokenizer_merges_resource: None,
+                tokenizer_type: None,
                 device: Device::cuda_if_available(),
             },
 
@@ -166,8 +171,7 @@ impl From<SentenceEmbeddingsModelType> for SentenceEmbeddingsConfig {
                 pooling_config_resource: Box::new(RemoteResource::from_pretrained(
                     SentenceEmbeddingsPoolingConfigResources::ALL_MINI_LM_L6_V2,
                 )),
-                dense_config_resource: None,
-                dense_weights_resource: None,
+                dense_resources: vec![],
                 sentence_bert_config_resource: Box::new(RemoteResource::from_pretrained(
                     SentenceEmbeddingsConfigResources::ALL_MINI_LM_L6_V2,
                 )),
@@ -178,6 +182,7 @@ impl From<SentenceEmbeddingsModelType> for SentenceEmbeddingsConfig {
                     BertVocabResources::ALL_MINI_LM_L6_V2,
                 )),
                 tokenizer_merges_resource: None,
+                tokenizer_type: None,
                 device: Device::cuda_if_available(),
             },
 
@@ -195,8 +200,7 @@ impl From<SentenceEmbeddingsModelType> for SentenceEmbeddingsConfig {
                 pooling_config_resource: Box::new(RemoteResource::from_pretrained(
                     SentenceEmbeddingsPoolingConfigResources::ALL_DISTILROBERTA_V1,
                 )),
-                dense_config_resource: None,
-                dense_weights_resource: None,
+                dense_resources: vec![],
                 sentence_bert_config_resource: Box::new(RemoteResource::from_pretrained(
                     SentenceEmbeddingsConfigResources::ALL_DISTILROBERTA_V1,
                 )),
@@ -209,6 +213,7 @@ impl From<SentenceEmbeddingsModelType> for SentenceEmbeddingsConfig {
                 tokenizer_merges_resource: Some(Box::new(RemoteResource::from_pretrained(
                     RobertaMergesResources::ALL_DISTILROBERTA_V1,
                 ))),
+                tokenizer_type: None,
                 device: Device::cuda_if_available(),
             },
 
@@ -226,8 +231,7 @@ impl From<SentenceEmbeddingsModelType> for SentenceEmbeddingsConfig {
                 pooling_config_resource: Box::new(RemoteResource::from_pretrained(
                     SentenceEmbeddingsPoolingConfigResources::PARAPHRASE_ALBERT_SMALL_V2,
                 )),
-                dense_config_resource: None,
-                dense_weights_resource: None,
+                dense_resources: vec![],
                 sentence_bert_config_resource: Box::new(RemoteResource::from_pretrained(
                     SentenceEmbeddingsConfigResources::PARAPHRASE_ALBERT_SMALL_V2,
                 )),
@@ -238,6 +242,7 @@ impl From<SentenceEmbeddingsModelType> for SentenceEmbeddingsConfig {
                     AlbertVocabResources::PARAPHRASE_ALBERT_SMALL_V2,
                 )),
                 tokenizer_merges_resource: None,
+                tokenizer_type: None,
                 device: Device::cuda_if_available(),
             },
 
@@ -255,12 +260,14 @@ impl From<SentenceEmbeddingsModelType> for SentenceEmbeddingsConfig {
                 pooling_config_resource: Box::new(RemoteResource::from_pretrained(
                     SentenceEmbeddingsPoolingConfigResources::SENTENCE_T5_BASE,
                 )),
-                dense_config_resource: Some(Box::new(RemoteResource::from_pretrained(
-                    SentenceEmbeddingsDenseConfigResources::SENTENCE_T5_BASE,
-                ))),
-                dense_weights_resource: Some(Box::new(RemoteResource::from_pretrained(
-                    SentenceEmbeddingsDenseResources::SENTENCE_T5_BASE,
-                ))),
+                dense_resources: vec![SentenceEmbeddingsDenseModuleResources::new(
+                    RemoteResource::from_pretrained(
+                        SentenceEmbeddingsDenseConfigResources::SENTENCE_T5_BASE,
+                    ),
+                    RemoteResource::from_pretrained(
+                        SentenceEmbeddingsDenseResources::SENTENCE_T5_BASE,
+                    ),
+                )],
                 sentence_bert_config_resource: Box::new(RemoteResource::from_pretrained(
                     SentenceEmbeddingsConfigResources::SENTENCE_T5_BASE,
                 )),
@@ -271,8 +278,95 @@ impl From<SentenceEmbeddingsModelType> for SentenceEmbeddingsConfig {
                     T5VocabResources::SENTENCE_T5_BASE,
                 )),
                 tokenizer_merges_resource: None,
+                tokenizer_type: None,
                 device: Device::cuda_if_available(),
             },
+
+            SentenceEmbeddingsModelType::ParaphraseMultilingualMiniLmL12V2 => SentenceEmbeddingsConfig {
+                modules_config_resource: Box::new(RemoteResource::from_pretrained(
+                    SentenceEmbeddingsModulesConfigResources::PARAPHRASE_MULTILINGUAL_MINI_LM_L12_V2,
+                )),
+                transformer_type: ModelType::Bert,
+                transformer_config_resource: Box::new(RemoteResource::from_pretrained(
+                    BertConfigResources::PARAPHRASE_MULTILINGUAL_MINI_LM_L12_V2,
+                )),
+                transformer_weights_resource: Box::new(RemoteResource::from_pretrained(
+                    BertModelResources::PARAPHRASE_MULTILINGUAL_MINI_LM_L12_V2,
+                )),
+                pooling_config_resource: Box::new(RemoteResource::from_pretrained(
+                    SentenceEmbeddingsPoolingConfigResources::PARAPHRASE_MULTILINGUAL_MINI_LM_L12_V2,
+                )),
+                dense_resources: vec![],
+                sentence_bert_config_resource: Box::new(RemoteResource::from_pretrained(
+                    SentenceEmbeddingsConfigResources::PARAPHRASE_MULTILINGUAL_MINI_LM_L12_V2,
+                )),
+                tokenizer_config_resource: Box::new(RemoteResource::from_pretrained(
+                    SentenceEmbeddingsTokenizerConfigResources::PARAPHRASE_MULTILINGUAL_MINI_LM_L12_V2,
+                )),
+                tokenizer_vocab_resource: Box::new(RemoteResource::from_pretrained(
+                    RobertaVocabResources::PARAPHRASE_MULTILINGUAL_MINI_LM_L12_V2,
+                )),
+                tokenizer_merges_resource: None,
+                tokenizer_type: Some(ModelType::XLMRoberta),
+                device: Device::cuda_if_available(),
+            },
+
+            SentenceEmbeddingsModelType::Labse => SentenceEmbeddingsConfig {
+                modules_config_resource: Box::new(RemoteResource::from_pretrained(
+                    SentenceEmbeddingsModulesConfigResources::LABSE,
+                )),
+                transformer_type: ModelType::Bert,
+                transformer_config_resource: Box::new(RemoteResource::from_pretrained(
+                    BertConfigResources::LABSE,
+                )),
+                transformer_weights_resource: Box::new(RemoteResource::from_pretrained(
+                    BertModelResources::LABSE,
+                )),
+                pooling_config_resource: Box::new(RemoteResource::from_pretrained(
+                    SentenceEmbeddingsPoolingConfigResources::LABSE,
+                )),
+                dense_resources: vec![SentenceEmbeddingsDenseModuleResources::new(
+                    RemoteResource::from_pretrained(
+                        SentenceEmbeddingsDenseConfigResources::LABSE,
+                    ),
+                    RemoteResource::from_pretrained(
+                        SentenceEmbeddingsDenseResources::LABSE,
+                    ),
+                )],
+                sentence_bert_config_resource: Box::new(RemoteResource::from_pretrained(
+                    SentenceEmbeddingsConfigResources::LABSE,
+                )),
+                tokenizer_config_resource: Box::new(RemoteResource::from_pretrained(
+                    SentenceEmbeddingsTokenizerConfigResources::LABSE,
+                )),
+                tokenizer_vocab_resource: Box::new(RemoteResource::from_pretrained(
+                    BertVocabResources::LABSE,
+                )),
+                tokenizer_merges_resource: None,
+                tokenizer_type: None,
+                device: Device::cuda_if_available(),
+            },
+        }
+    }
+}
+
+/// Configuration and weights resources of a Dense module
+pub struct SentenceEmbeddingsDenseModuleResources {
+    /// Dense layer configuration resource
+    pub config_resource: Box<dyn ResourceProvider + Send>,
+    /// Dense layer weights resource
+    pub weights_resource: Box<dyn ResourceProvider + Send>,
+}
+
+impl SentenceEmbeddingsDenseModuleResources {
+    pub fn new<RC, RW>(config_resource: RC, weights_resource: RW) -> Self
+    where
+        RC: ResourceProvider + Send + 'static,
+        RW: ResourceProvider + Send + 'static,
+    {
+        SentenceEmbeddingsDenseModuleResources {
+            config_resource: Box::new(config_resource),
+            weights_resource: Box::new(weights_resource),
         }
     }
 }
@@ -350,30 +444,23 @@ impl SentenceEmbeddingsModulesConfig {
         self.get(1).as_ref().unwrap()
     }
 
+    /// Returns the first Dense module, if any
     pub fn dense_module(&self) -> Option<&SentenceEmbeddingsModuleConfig> {
-        for i in 2..=3 {
-            if let Some(SentenceEmbeddingsModuleConfig {
-                module_type: SentenceEmbeddingsModuleType::Dense,
-                ..
-            }) = self.get(i)
-            {
-                return self.get(i);
-            }
-        }
-        None
+        self.dense_modules().into_iter().next()
+    }
+
+    /// Returns the Dense modules following the pooling layer, in the order they are applied
+    pub fn dense_modules(&self) -> Vec<&SentenceEmbeddingsModuleConfig> {
+        self.iter()
+            .skip(2)
+            .filter(|module| matches!(module.module_type, SentenceEmbeddingsModuleType::Dense))
+            .collect()
     }
 
     pub fn has_normalization(&self) -> bool {
-        for i in 2..=3 {
-            if let Some(SentenceEmbeddingsModuleConfig {
-                module_type: SentenceEmbeddingsModuleType::Normalize,
-                ..
-            }) = self.get(i)
-            {
-                return true;
-            }
-        }
-        false
+        self.iter()
+            .skip(2)
+            .any(|module| matches!(module.module_type, SentenceEmbeddingsModuleType::Normalize))
     }
 }
 
@@ -454,6 +541,31 @@ pub struct SentenceEmbeddingsTokenizerConfig {
     pub add_prefix_space: Option<bool>,
     pub strip_accents: Option<bool>,
     pub do_lower_case: Option<bool>,
+    pub tokenizer_class: Option<String>,
+}
+
+impl SentenceEmbeddingsTokenizerConfig {
+    /// Tokenizer type matching the `tokenizer_class` of the configuration, if it is supported
+    pub fn tokenizer_type(&self) -> Option<ModelType> {
+        let tokenizer_class = self.tokenizer_class.as_deref()?;
+        match tokenizer_class
+            .strip_suffix("Fast")
+            .unwrap_or(tokenizer_class)
+        {
+            "BertTokenizer" | "ElectraTokenizer" => Some(ModelType::Bert),
+            "DistilBertTokenizer" => Some(ModelType::DistilBert),
+            "RobertaTokenizer" => Some(ModelType::Roberta),
+            "XLMRobertaTokenizer" => Some(ModelType::XLMRoberta),
+            "DebertaTokenizer" => Some(ModelType::Deberta),
+            "DebertaV2Tokenizer" => Some(ModelType::DebertaV2),
+            "AlbertTokenizer" => Some(ModelType::Albert),
+            "T5Tokenizer" => Some(ModelType::T5),
+            "LongformerTokenizer" => Some(ModelType::Longformer),
+            "MobileBertTokenizer" => Some(ModelType::MobileBert),
+            "FNetTokenizer" => Some(ModelType::FNet),
+            _ => None,
+        }
+    }
 }
 
 impl Config for SentenceEmbeddingsTokenizerConfig {}
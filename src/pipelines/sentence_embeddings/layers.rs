@@ -19,11 +19,17 @@ pub struct PoolingConfig {
     pub pooling_mode_mean_tokens: bool,
     /// Perform mean-pooling, but devide by sqrt(input_length)
     pub pooling_mode_mean_sqrt_len_tokens: bool,
+    /// Perform mean-pooling with weights increasing linearly with the token position (as used by SGPT)
+    #[serde(default)]
+    pub pooling_mode_weightedmean_tokens: bool,
+    /// Use the last (non-padding) token as text representation (for causal language models)
+    #[serde(default)]
+    pub pooling_mode_lasttoken: bool,
 }
 
 impl Config for PoolingConfig {}
 
-/// Performs pooling (max or mean) on the token embeddings.
+/// Performs pooling (CLS token, max, mean, weighted mean or last token) on the token embeddings.
 ///
 /// Using pooling, it generates from a variable sized sentence a fixed sized sentence
 /// embedding. You can concatenate multiple poolings together.
@@ -54,7 +60,7 @@ impl Pooling {
 
         if self.conf.pooling_mode_mean_tokens || self.conf.pooling_mode_mean_sqrt_len_tokens {
             let input_mask_expanded = attention_mask.unsqueeze(-1).expand_as(&token_embeddings);
-            let sum_embeddings = (token_embeddings * &input_mask_expanded).sum_dim_intlist(
+            let sum_embeddings = (&token_embeddings * &input_mask_expanded).sum_dim_intlist(
                 [1].as_slice(),
                 false,
                 Kind::Float,
@@ -70,6 +76,46 @@ impl Pooling {
             }
         }
 
+        if self.conf.pooling_mode_weightedmean_tokens {
+            let sequence_length = token_embeddings.size()[1];
+            let position_weights = Tensor::arange_start(
+                1,
+                sequence_length + 1,
+                (Kind::Float, token_embeddings.device()),
+            )
+            .unsqueeze(0)
+            .unsqueeze(-1);
+            let weighted_mask = attention_mask
+                .unsqueeze(-1)
+                .expand_as(&token_embeddings)
+                .to_kind(Kind::Float)
+                * position_weights;
+            let sum_embeddings = (&token_embeddings * &weighted_mask).sum_dim_intlist(
+                [1].as_slice(),
+                false,
+                Kind::Float,
+            );
+            let sum_mask = weighted_mask
+                .sum_dim_intlist([1].as_slice(), false, Kind::Float)
+                .clamp_min(10e-9);
+            output_vectors.push(sum_embeddings / sum_mask);
+        }
+
+        if self.conf.pooling_mode_lasttoken {
+            // Inputs are right-padded: the last token is at position (number of tokens - 1)
+            let last_token_positions =
+                (attention_mask.sum_dim_intlist([1].as_slice(), false, Kind::Int64) - 1)
+                    .clamp_min(0);
+            let gather_index = last_token_positions
+                .view([-1, 1, 1])
+                .expand(&[-1, 1, token_embeddings.size()[2]], false);
+            output_vectors.push(
+                token_embeddings
+                    .gather(1, &gather_index, false)
+                    .squeeze_dim(1),
+            );
+        }
+
         Tensor::cat(&output_vectors, 1)
     }
 }
@@ -151,3 +197,65 @@ impl Dense {
         self.activation.get_fn()(&x.apply(&self.linear))
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn pooling(weightedmean_tokens: bool, lasttoken: bool) -> Pooling {
+        Pooling::new(PoolingConfig {
+            word_embedding_dimension: 2,
+            pooling_mode_cls_token: false,
+            pooling_mode_max_tokens: false,
+            pooling_mode_mean_tokens: false,
+            pooling_mode_mean_sqrt_len_tokens: false,
+            pooling_mode_weightedmean_tokens: weightedmean_tokens,
+            pooling_mode_lasttoken: lasttoken,
+        })
+    }
+
+    /// Right-padded batch of 2 sequences with 3 and 2 tokens
+    fn padded_batch() -> (Tensor, Tensor) {
+        let token_embeddings = Tensor::of_slice(&[
+            1.0f32, 2.0, 3.0, 4.0, 5.0, 6.0, //
+            -1.0, 0.0, 2.0, 1.0, 100.0, 100.0,
+        ])
+        .view([2, 3, 2]);
+        let attention_mask = Tensor::of_slice(&[1i64, 1, 1, 1, 1, 0]).view([2, 3]);
+        (token_embeddings, attention_mask)
+    }
+
+    #[test]
+    fn test_weighted_mean_pooling() {
+        let (token_embeddings, attention_mask) = padded_batch();
+        let output = pooling(true, false).forward(token_embeddings, &attention_mask);
+
+        // Token weights are their 1-based positions, padding tokens are ignored
+        let expected = Tensor::of_slice(&[
+            (1.0f32 + 2.0 * 3.0 + 3.0 * 5.0) / 6.0,
+            (2.0 + 2.0 * 4.0 + 3.0 * 6.0) / 6.0,
+            (-1.0 + 2.0 * 2.0) / 3.0,
+            (0.0 + 2.0 * 1.0) / 3.0,
+        ])
+        .view([2, 2]);
+        assert!(output.allclose(&expected, 1e-6, 1e-6, false));
+    }
+
+    #[test]
+    fn test_last_token_pooling() {
+        let (token_embeddings, attention_mask) = padded_batch();
+        let output = pooling(false, true).forward(token_embeddings, &attention_mask);
+
+        // The last non-padding token of each sequence is selected
+        let expected = Tensor::of_slice(&[5.0f32, 6.0, 2.0, 1.0]).view([2, 2]);
+        assert!(output.allclose(&expected, 1e-6, 1e-6, false));
+
+        // Both modes are concatenated when enabled
+        let (token_embeddings, attention_mask) = padded_batch();
+        let output = pooling(true, true).forward(token_embeddings, &attention_mask);
+        assert_eq!(output.size(), vec![2, 4]);
+        assert!(output
+            .narrow(1, 2, 2)
+            .allclose(&expected, 1e-6, 1e-6, false));
+    }
+}
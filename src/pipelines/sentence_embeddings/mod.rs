@@ -36,13 +36,13 @@ mod resources;
 
 pub use builder::SentenceEmbeddingsBuilder;
 pub use config::{
-    SentenceEmbeddingsConfig, SentenceEmbeddingsModuleConfig, SentenceEmbeddingsModuleType,
-    SentenceEmbeddingsModulesConfig, SentenceEmbeddingsSentenceBertConfig,
-    SentenceEmbeddingsTokenizerConfig,
+    SentenceEmbeddingsConfig, SentenceEmbeddingsDenseModuleResources,
+    SentenceEmbeddingsModuleConfig, SentenceEmbeddingsModuleType, SentenceEmbeddingsModulesConfig,
+    SentenceEmbeddingsSentenceBertConfig, SentenceEmbeddingsTokenizerConfig,
 };
 pub use pipeline::{
-    SentenceEmbeddingsModel, SentenceEmbeddingsModelOuput, SentenceEmbeddingsOption,
-    SentenceEmbeddingsTokenizerOuput,
+    max_sim, SentenceEmbeddingsModel, SentenceEmbeddingsModelOuput, SentenceEmbeddingsOption,
+    SentenceEmbeddingsTokenizerOuput, TokenEmbeddings,
 };
 
 pub use resources::{
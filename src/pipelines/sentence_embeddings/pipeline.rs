@@ -2,11 +2,18 @@ use std::borrow::Borrow;
 use std::convert::TryInto;
 
 use rust_tokenizers::tokenizer::TruncationStrategy;
+use rust_tokenizers::{Offset, TokenizedInput};
 use tch::{nn, Tensor};
 
 use crate::albert::AlbertForSentenceEmbeddings;
 use crate::bert::BertForSentenceEmbeddings;
+use crate::deberta::DebertaForSentenceEmbeddings;
+use crate::deberta_v2::DebertaV2ForSentenceEmbeddings;
 use crate::distilbert::DistilBertForSentenceEmbeddings;
+use crate::electra::ElectraForSentenceEmbeddings;
+use crate::fnet::FNetForSentenceEmbeddings;
+use crate::longformer::LongformerForSentenceEmbeddings;
+use crate::mobilebert::MobileBertForSentenceEmbeddings;
 use crate::pipelines::common::{ConfigOption, ModelType, TokenizerOption};
 use crate::pipelines::sentence_embeddings::layers::{Dense, DenseConfig, Pooling, PoolingConfig};
 use crate::pipelines::sentence_embeddings::{
@@ -19,6 +26,7 @@ use crate::t5::T5ForSentenceEmbeddings;
 use crate::{Config, RustBertError};
 
 /// # Abstraction that holds one particular sentence embeddings model, for any of the supported models
+#[allow(clippy::large_enum_variant)]
 pub enum SentenceEmbeddingsOption {
     /// Bert for Sentence Embeddings
     Bert(BertForSentenceEmbeddings),
@@ -30,6 +38,21 @@ pub enum SentenceEmbeddingsOption {
     Albert(AlbertForSentenceEmbeddings),
     /// T5 for Sentence Embeddings
     T5(T5ForSentenceEmbeddings),
+    /// DeBERTa for Sentence Embeddings
+    Deberta(DebertaForSentenceEmbeddings),
+    /// DeBERTa V2 for Sentence Embeddings
+    DebertaV2(DebertaV2ForSentenceEmbeddings),
+    /// XLM-RoBERTa for Sentence Embeddings
+    XLMRoberta(RobertaForSentenceEmbeddings),
+    /// Electra for Sentence Embeddings
+    Electra(ElectraForSentenceEmbeddings),
+    /// Longformer for Sentence Embeddings
+    Longformer(LongformerForSentenceEmbeddings),
+    /// MobileBERT for Sentence Embeddings
+    MobileBert(MobileBertForSentenceEmbeddings),
+    /// FNet for Sentence Embeddings (FNet does not take an attention mask: padding tokens are
+    /// mixed into the token representations)
+    FNet(FNetForSentenceEmbeddings),
 }
 
 impl SentenceEmbeddingsOption {
@@ -63,6 +86,38 @@ impl SentenceEmbeddingsOption {
             )),
             ModelType::Albert => Albert(AlbertForSentenceEmbeddings::new(p, &(config.try_into()?))),
             ModelType::T5 => T5(T5ForSentenceEmbeddings::new(p, &(config.try_into()?))),
+            ModelType::Deberta => {
+                Deberta(DebertaForSentenceEmbeddings::new(p, &(config.try_into()?)))
+            }
+            ModelType::DebertaV2 => DebertaV2(DebertaV2ForSentenceEmbeddings::new(
+                p,
+                &(config.try_into()?),
+            )),
+            ModelType::XLMRoberta => {
+                XLMRoberta(RobertaForSentenceEmbeddings::new_with_optional_pooler(
+                    p,
+                    &(config.try_into()?),
+                    false,
+                ))
+            }
+            ModelType::Electra => {
+                Electra(ElectraForSentenceEmbeddings::new(p, &(config.try_into()?)))
+            }
+            ModelType::Longformer => Longformer(LongformerForSentenceEmbeddings::new(
+                p,
+                &(config.try_into()?),
+                false,
+            )),
+            ModelType::MobileBert => MobileBert(MobileBertForSentenceEmbeddings::new(
+                p,
+                &(config.try_into()?),
+                false,
+            )),
+            ModelType::FNet => FNet(FNetForSentenceEmbeddings::new(
+                p,
+                &(config.try_into()?),
+                false,
+            )),
             _ => {
                 return Err(RustBertError::InvalidConfigurationError(format!(
                     "Unsupported transformer model {transformer_type:?} for Sentence Embeddings"
@@ -105,7 +160,7 @@ impl SentenceEmbeddingsOption {
                         transformer_output.all_attentions,
                     )
                 }),
-            Self::Roberta(transformer) => transformer
+            Self::Roberta(transformer) | Self::XLMRoberta(transformer) => transformer
                 .forward_t(
                     Some(tokens_ids),
                     Some(tokens_masks),
@@ -146,6 +201,82 @@ impl SentenceEmbeddingsOption {
                     )
                 }),
             Self::T5(transformer) => transformer.forward(tokens_ids, tokens_masks),
+            Self::Deberta(transformer) => transformer
+                .forward_t(
+                    Some(tokens_ids),
+                    Some(tokens_masks),
+                    None,
+                    None,
+                    None,
+                    false,
+                )
+                .map(|transformer_output| {
+                    (
+                        transformer_output.hidden_state,
+                        transformer_output.all_attentions,
+                    )
+                }),
+            Self::DebertaV2(transformer) => transformer
+                .forward_t(
+                    Some(tokens_ids),
+                    Some(tokens_masks),
+                    None,
+                    None,
+                    None,
+                    false,
+                )
+                .map(|transformer_output| {
+                    (
+                        transformer_output.hidden_state,
+                        transformer_output.all_attentions,
+                    )
+                }),
+            Self::Electra(transformer) => transformer
+                .forward_t(
+                    Some(tokens_ids),
+                    Some(tokens_masks),
+                    None,
+                    None,
+                    None,
+                    false,
+                )
+                .map(|transformer_output| {
+                    (
+                        transformer_output.hidden_state,
+                        transformer_output.all_attentions,
+                    )
+                }),
+            // The local attentions of Longformer are computed over a sliding window and do not
+            // have the `(batch, heads, sequence, sequence)` shape of the other models
+            Self::Longformer(transformer) => transformer
+                .forward_t(
+                    Some(tokens_ids),
+                    Some(tokens_masks),
+                    None,
+                    None,
+                    None,
+                    None,
+                    false,
+                )
+                .map(|transformer_output| (transformer_output.hidden_state, None)),
+            Self::MobileBert(transformer) => transformer
+                .forward_t(
+                    Some(tokens_ids),
+                    None,
+                    None,
+                    None,
+                    Some(tokens_masks),
+                    false,
+                )
+                .map(|transformer_output| {
+                    (
+                        transformer_output.hidden_state,
+                        transformer_output.all_attentions,
+                    )
+                }),
+            Self::FNet(transformer) => transformer
+                .forward_t(Some(tokens_ids), None, None, None, false)
+                .map(|transformer_output| (transformer_output.hidden_states, None)),
         }
     }
 }
@@ -155,7 +286,7 @@ impl SentenceEmbeddingsOption {
 /// It is made of the following blocks:
 /// - `transformer`: Base transformer model
 /// - `pooling`: Pooling layer
-/// - `dense` _(optional)_: Linear (feed forward) layer
+/// - `dense` _(optional)_: Linear (feed forward) layers
 /// - `normalization` _(optional)_: Embeddings normalization
 pub struct SentenceEmbeddingsModel {
     sentence_bert_config: SentenceEmbeddingsSentenceBertConfig,
@@ -165,8 +296,9 @@ pub struct SentenceEmbeddingsModel {
     transformer: SentenceEmbeddingsOption,
     transformer_config: ConfigOption,
     pooling_layer: Pooling,
-    dense_layer: Option<Dense>,
+    dense_layers: Vec<Dense>,
     normalize_embeddings: bool,
+    truncate_dim: Option<i64>,
 }
 
 impl SentenceEmbeddingsModel {
@@ -186,8 +318,8 @@ impl SentenceEmbeddingsModel {
             transformer_config_resource,
             transformer_weights_resource,
             pooling_config_resource,
-            dense_config_resource,
-            dense_weights_resource,
+            dense_resources,
+            tokenizer_type,
             device,
         } = config;
 
@@ -204,7 +336,7 @@ impl SentenceEmbeddingsModel {
             sentence_bert_config_resource.get_local_path()?,
         );
         let tokenizer = TokenizerOption::from_file(
-            transformer_type,
+            tokenizer_type.unwrap_or(transformer_type),
             tokenizer_vocab_resource
                 .get_local_path()?
                 .to_string_lossy()
@@ -238,19 +370,27 @@ impl SentenceEmbeddingsModel {
         let pooling_config = PoolingConfig::from_file(pooling_config_resource.get_local_path()?);
         let pooling_layer = Pooling::new(pooling_config);
 
-        // Setup dense layer
+        // Setup dense layers
 
-        let dense_layer = if modules.dense_module().is_some() {
-            let dense_config =
-                DenseConfig::from_file(dense_config_resource.unwrap().get_local_path()?);
-            Some(Dense::new(
-                dense_config,
-                dense_weights_resource.unwrap().get_local_path()?,
-                device,
-            )?)
-        } else {
-            None
-        };
+        let num_dense_modules = modules.dense_modules().len();
+        if dense_resources.len() != num_dense_modules {
+            return Err(RustBertError::InvalidConfigurationError(format!(
+                "{num_dense_modules} Dense modules defined in modules.json but {} dense resources provided",
+                dense_resources.len()
+            )));
+        }
+        let dense_layers = dense_resources
+            .iter()
+            .map(|dense_resources| {
+                let dense_config =
+                    DenseConfig::from_file(dense_resources.config_resource.get_local_path()?);
+                Dense::new(
+                    dense_config,
+                    dense_resources.weights_resource.get_local_path()?,
+                    device,
+                )
+            })
+            .collect::<Result<Vec<Dense>, RustBertError>>()?;
 
         let normalize_embeddings = modules.has_normalization();
 
@@ -262,8 +402,9 @@ impl SentenceEmbeddingsModel {
             transformer,
             transformer_config,
             pooling_layer,
-            dense_layer,
+            dense_layers,
             normalize_embeddings,
+            truncate_dim: None,
         })
     }
 
@@ -272,18 +413,36 @@ impl SentenceEmbeddingsModel {
         self.tokenizer_truncation_strategy = truncation_strategy;
     }
 
+    /// Sets the dimension the sentence embeddings are truncated to (Matryoshka embeddings), `None` to keep
+    /// the full dimension. Truncation is applied before the optional normalization.
+    pub fn set_truncate_dim(&mut self, truncate_dim: Option<i64>) {
+        self.truncate_dim = truncate_dim;
+    }
+
     /// Tokenizes the inputs
     pub fn tokenize<S>(&self, inputs: &[S]) -> SentenceEmbeddingsTokenizerOuput
     where
         S: AsRef<str> + Sync,
     {
-        let tokenized_input = self.tokenizer.encode_list(
+        self.pad_tokenized_input(self.encode_inputs(inputs))
+    }
+
+    fn encode_inputs<S>(&self, inputs: &[S]) -> Vec<TokenizedInput>
+    where
+        S: AsRef<str> + Sync,
+    {
+        self.tokenizer.encode_list(
             inputs,
             self.sentence_bert_config.max_seq_length,
             &self.tokenizer_truncation_strategy,
             0,
-        );
+        )
+    }
 
+    fn pad_tokenized_input(
+        &self,
+        tokenized_input: Vec<TokenizedInput>,
+    ) -> SentenceEmbeddingsTokenizerOuput {
         let max_len = tokenized_input
             .iter()
             .map(|input| input.token_ids.len())
@@ -343,10 +502,18 @@ impl SentenceEmbeddingsModel {
 
         let mean_pool =
             tch::no_grad(|| self.pooling_layer.forward(tokens_embeddings, &tokens_masks));
-        let maybe_linear = if let Some(dense_layer) = &self.dense_layer {
-            tch::no_grad(|| dense_layer.forward(&mean_pool))
-        } else {
-            mean_pool
+        let maybe_linear = tch::no_grad(|| {
+            self.dense_layers
+                .iter()
+                .fold(mean_pool, |embeddings, dense_layer| {
+                    dense_layer.forward(&embeddings)
+                })
+        });
+        let maybe_linear = match self.truncate_dim {
+            Some(truncate_dim) if truncate_dim < maybe_linear.size()[1] => {
+                maybe_linear.narrow(1, 0, truncate_dim)
+            }
+            _ => maybe_linear,
         };
         let maybe_normalized = if self.normalize_embeddings {
             let norm = &maybe_linear
@@ -373,6 +540,102 @@ impl SentenceEmbeddingsModel {
         Ok(Vec::from(embeddings))
     }
 
+    /// Computes token-level embeddings (the transformer hidden states of each token, without pooling),
+    /// for example for late interaction scoring with [`max_sim`] or span-level matching.
+    /// Padding tokens are removed, and the token embeddings are normalized if the model normalizes its sentence embeddings.
+    ///
+    /// # Arguments
+    ///
+    /// * `inputs` - Texts to encode
+    /// * `keep_special_tokens` - Keep the embeddings of special tokens (e.g. `[CLS]` and `[SEP]`), which have no offset
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<TokenEmbeddings>` containing the token ids, offsets and embeddings of each input
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::sentence_embeddings::{
+    ///     max_sim, SentenceEmbeddingsBuilder, SentenceEmbeddingsModelType,
+    /// };
+    ///
+    /// let model = SentenceEmbeddingsBuilder::remote(SentenceEmbeddingsModelType::AllMiniLmL12V2)
+    ///     .create_model()?;
+    /// let output = model.encode_tokens(&["Where is the cat?", "The cat sits on the mat"], false)?;
+    /// let score = max_sim(&output[0].embeddings, &output[1].embeddings);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn encode_tokens<S>(
+        &self,
+        inputs: &[S],
+        keep_special_tokens: bool,
+    ) -> Result<Vec<TokenEmbeddings>, RustBertError>
+    where
+        S: AsRef<str> + Sync,
+    {
+        let tokenized_input = self.encode_inputs(inputs);
+        let token_info = tokenized_input
+            .iter()
+            .map(|input| {
+                (
+                    input.token_ids.clone(),
+                    input.token_offsets.clone(),
+                    input.special_tokens_mask.clone(),
+                )
+            })
+            .collect::<Vec<_>>();
+        let SentenceEmbeddingsTokenizerOuput {
+            tokens_ids,
+            tokens_masks,
+        } = self.pad_tokenized_input(tokenized_input);
+        let tokens_ids = Tensor::stack(&tokens_ids, 0).to(self.var_store.device());
+        let tokens_masks = Tensor::stack(&tokens_masks, 0).to(self.var_store.device());
+
+        let (tokens_embeddings, _) =
+            tch::no_grad(|| self.transformer.forward(&tokens_ids, &tokens_masks))?;
+        let tokens_embeddings = if self.normalize_embeddings {
+            let norm = tokens_embeddings
+                .norm_scalaropt_dim(2, &[-1], true)
+                .clamp_min(1e-12);
+            tokens_embeddings / norm
+        } else {
+            tokens_embeddings
+        };
+
+        Ok(token_info
+            .into_iter()
+            .enumerate()
+            .map(|(index, (token_ids, offsets, special_tokens_mask))| {
+                let embeddings: Vec<Embedding> = Vec::from(
+                    tokens_embeddings
+                        .get(index as i64)
+                        .narrow(0, 0, token_ids.len() as i64),
+                );
+                let mut output = TokenEmbeddings {
+                    token_ids: Vec::with_capacity(token_ids.len()),
+                    offsets: Vec::with_capacity(token_ids.len()),
+                    embeddings: Vec::with_capacity(token_ids.len()),
+                };
+                for (((token_id, offset), special_token), embedding) in token_ids
+                    .into_iter()
+                    .zip(offsets)
+                    .zip(special_tokens_mask)
+                    .zip(embeddings)
+                {
+                    if keep_special_tokens || special_token == 0 {
+                        output.token_ids.push(token_id);
+                        output.offsets.push(offset);
+                        output.embeddings.push(embedding);
+                    }
+                }
+                output
+            })
+            .collect())
+    }
+
     fn nb_layers(&self) -> usize {
         use SentenceEmbeddingsOption::*;
         match (&self.transformer, &self.transformer_config) {
@@ -380,12 +643,26 @@ impl SentenceEmbeddingsModel {
             (Bert(_), _) => unreachable!(),
             (DistilBert(_), ConfigOption::DistilBert(conf)) => conf.n_layers as usize,
             (DistilBert(_), _) => unreachable!(),
-            (Roberta(_), ConfigOption::Bert(conf)) => conf.num_hidden_layers as usize,
-            (Roberta(_), _) => unreachable!(),
+            (Roberta(_) | XLMRoberta(_), ConfigOption::Roberta(conf)) => {
+                conf.num_hidden_layers as usize
+            }
+            (Roberta(_) | XLMRoberta(_), _) => unreachable!(),
             (Albert(_), ConfigOption::Albert(conf)) => conf.num_hidden_layers as usize,
             (Albert(_), _) => unreachable!(),
             (T5(_), ConfigOption::T5(conf)) => conf.num_layers as usize,
             (T5(_), _) => unreachable!(),
+            (Deberta(_), ConfigOption::Deberta(conf)) => conf.num_hidden_layers as usize,
+            (Deberta(_), _) => unreachable!(),
+            (DebertaV2(_), ConfigOption::DebertaV2(conf)) => conf.num_hidden_layers as usize,
+            (DebertaV2(_), _) => unreachable!(),
+            (Electra(_), ConfigOption::Electra(conf)) => conf.num_hidden_layers as usize,
+            (Electra(_), _) => unreachable!(),
+            (Longformer(_), ConfigOption::Longformer(conf)) => conf.num_hidden_layers as usize,
+            (Longformer(_), _) => unreachable!(),
+            (MobileBert(_), ConfigOption::MobileBert(conf)) => conf.num_hidden_layers as usize,
+            (MobileBert(_), _) => unreachable!(),
+            (FNet(_), ConfigOption::FNet(conf)) => conf.num_hidden_layers as usize,
+            (FNet(_), _) => unreachable!(),
         }
     }
 
@@ -396,12 +673,27 @@ impl SentenceEmbeddingsModel {
             (Bert(_), _) => unreachable!(),
             (DistilBert(_), ConfigOption::DistilBert(conf)) => conf.n_heads as usize,
             (DistilBert(_), _) => unreachable!(),
-            (Roberta(_), ConfigOption::Roberta(conf)) => conf.num_attention_heads as usize,
-            (Roberta(_), _) => unreachable!(),
+            (Roberta(_) | XLMRoberta(_), ConfigOption::Roberta(conf)) => {
+                conf.num_attention_heads as usize
+            }
+            (Roberta(_) | XLMRoberta(_), _) => unreachable!(),
             (Albert(_), ConfigOption::Albert(conf)) => conf.num_attention_heads as usize,
             (Albert(_), _) => unreachable!(),
             (T5(_), ConfigOption::T5(conf)) => conf.num_heads as usize,
             (T5(_), _) => unreachable!(),
+            (Deberta(_), ConfigOption::Deberta(conf)) => conf.num_attention_heads as usize,
+            (Deberta(_), _) => unreachable!(),
+            (DebertaV2(_), ConfigOption::DebertaV2(conf)) => conf.num_attention_heads as usize,
+            (DebertaV2(_), _) => unreachable!(),
+            (Electra(_), ConfigOption::Electra(conf)) => conf.num_attention_heads as usize,
+            (Electra(_), _) => unreachable!(),
+            (Longformer(_), ConfigOption::Longformer(conf)) => conf.num_attention_heads as usize,
+            (Longformer(_), _) => unreachable!(),
+            (MobileBert(_), ConfigOption::MobileBert(conf)) => conf.num_attention_heads as usize,
+            (MobileBert(_), _) => unreachable!(),
+            // FNet mixes tokens with a Fourier transform and has no attention heads
+            (FNet(_), ConfigOption::FNet(_)) => 0,
+            (FNet(_), _) => unreachable!(),
         }
     }
 
@@ -446,6 +738,43 @@ impl SentenceEmbeddingsModel {
     }
 }
 
+/// Late interaction (ColBERT-style) score between token-level embeddings: sum over the query tokens of
+/// the maximum dot product with the document tokens. Embeddings should be normalized for cosine similarity.
+///
+/// # Arguments
+///
+/// * `query` - Token embeddings of the query
+/// * `document` - Token embeddings of the document
+pub fn max_sim(query: &[Embedding], document: &[Embedding]) -> f32 {
+    query
+        .iter()
+        .map(|query_token| {
+            document
+                .iter()
+                .map(|document_token| {
+                    query_token
+                        .iter()
+                        .zip(document_token.iter())
+                        .map(|(value_1, value_2)| value_1 * value_2)
+                        .sum::<f32>()
+                })
+                .fold(f32::NEG_INFINITY, f32::max)
+        })
+        .filter(|score| score.is_finite())
+        .sum()
+}
+
+/// Token-level embeddings of an input, padding tokens excluded.
+#[derive(Debug, Clone)]
+pub struct TokenEmbeddings {
+    /// Token ids
+    pub token_ids: Vec<i64>,
+    /// Character offsets of the tokens in the input text (`None` for special tokens)
+    pub offsets: Vec<Option<Offset>>,
+    /// Embedding of each token
+    pub embeddings: Vec<Embedding>,
+}
+
 /// Container for the SentenceEmbeddings tokenizer output.
 pub struct SentenceEmbeddingsTokenizerOuput {
     pub tokens_ids: Vec<Tensor>,
@@ -457,3 +786,16 @@ pub struct SentenceEmbeddingsModelOuput {
     pub embeddings: Tensor,
     pub all_attentions: Option<Vec<Tensor>>,
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_max_sim() {
+        let query = vec![vec![1.0, 0.0], vec![0.0, 1.0]];
+        let document = vec![vec![0.6, 0.8], vec![1.0, 0.0], vec![0.0, -1.0]];
+        assert!((max_sim(&query, &document) - 1.8).abs() < 1e-6);
+        assert_eq!(max_sim(&query, &[]), 0.0);
+    }
+}
@@ -24,6 +24,8 @@ pub enum SentenceEmbeddingsModelType {
     AllDistilrobertaV1,
     ParaphraseAlbertSmallV2,
     SentenceT5Base,
+    ParaphraseMultilingualMiniLmL12V2,
+    Labse,
 }
 
 impl SentenceEmbeddingsModulesConfigResources {
@@ -62,6 +64,16 @@ impl SentenceEmbeddingsModulesConfigResources {
         "sentence-t5-base/sbert-config",
         "https://huggingface.co/sentence-transformers/sentence-t5-base/resolve/main/modules.json",
     );
+    /// Shared under Apache 2.0 license at <https://huggingface.co/sentence-transformers/paraphrase-multilingual-MiniLM-L12-v2>. Modified with conversion to C-array format.
+    pub const PARAPHRASE_MULTILINGUAL_MINI_LM_L12_V2: (&'static str, &'static str) = (
+        "paraphrase-multilingual-mini-lm-l12-v2/sbert-config",
+        "https://huggingface.co/sentence-transformers/paraphrase-multilingual-MiniLM-L12-v2/resolve/main/modules.json",
+    );
+    /// Shared under Apache 2.0 license at <https://huggingface.co/sentence-transformers/LaBSE>. Modified with conversion to C-array format.
+    pub const LABSE: (&'static str, &'static str) = (
+        "labse/sbert-config",
+        "https://huggingface.co/sentence-transformers/LaBSE/resolve/main/modules.json",
+    );
 }
 
 impl SentenceEmbeddingsDenseResources {
@@ -75,6 +87,11 @@ impl SentenceEmbeddingsDenseResources {
         "sentence-t5-base/sbert-dense",
         "https://huggingface.co/sentence-transformers/sentence-t5-base/resolve/main/2_Dense/rust_model.ot",
     );
+    /// Shared under Apache 2.0 license at <https://huggingface.co/sentence-transformers/LaBSE>. Modified with conversion to C-array format.
+    pub const LABSE: (&'static str, &'static str) = (
+        "labse/sbert-dense",
+        "https://huggingface.co/sentence-transformers/LaBSE/resolve/main/2_Dense/rust_model.ot",
+    );
 }
 
 impl SentenceEmbeddingsDenseConfigResources {
@@ -88,6 +105,11 @@ impl SentenceEmbeddingsDenseConfigResources {
         "sentence-t5-base/sbert-dense-config",
         "https://huggingface.co/sentence-transformers/sentence-t5-base/resolve/main/2_Dense/config.json",
     );
+    /// Shared under Apache 2.0 license at <https://huggingface.co/sentence-transformers/LaBSE>. Modified with conversion to C-array format.
+    pub const LABSE: (&'static str, &'static str) = (
+        "labse/sbert-dense-config",
+        "https://huggingface.co/sentence-transformers/LaBSE/resolve/main/2_Dense/config.json",
+    );
 }
 
 impl SentenceEmbeddingsPoolingConfigResources {
@@ -126,6 +148,16 @@ impl SentenceEmbeddingsPoolingConfigResources {
         "sentence-t5-base/sbert-pooling-config",
         "https://huggingface.co/sentence-transformers/sentence-t5-base/resolve/main/1_Pooling/config.json",
     );
+    /// Shared under Apache 2.0 license at <https://huggingface.co/sentence-transformers/paraphrase-multilingual-MiniLM-L12-v2>. Modified with conversion to C-array format.
+    pub const PARAPHRASE_MULTILINGUAL_MINI_LM_L12_V2: (&'static str, &'static str) = (
+        "paraphrase-multilingual-mini-lm-l12-v2/sbert-pooling-config",
+        "https://huggingface.co/sentence-transformers/paraphrase-multilingual-MiniLM-L12-v2/resolve/main/1_Pooling/config.json",
+    );
+    /// Shared under Apache 2.0 license at <https://huggingface.co/sentence-transformers/LaBSE>. Modified with conversion to C-array format.
+    pub const LABSE: (&'static str, &'static str) = (
+        "labse/sbert-pooling-config",
+        "https://huggingface.co/sentence-transformers/LaBSE/resolve/main/1_Pooling/config.json",
+    );
 }
 
 impl SentenceEmbeddingsConfigResources {
@@ -164,6 +196,16 @@ impl SentenceEmbeddingsConfigResources {
         "sentence-t5-base/sbert-config",
         "https://huggingface.co/sentence-transformers/sentence-t5-base/resolve/main/sentence_bert_config.json",
     );
+    /// Shared under Apache 2.0 license at <https://huggingface.co/sentence-transformers/paraphrase-multilingual-MiniLM-L12-v2>. Modified with conversion to C-array format.
+    pub const PARAPHRASE_MULTILINGUAL_MINI_LM_L12_V2: (&'static str, &'static str) = (
+        "paraphrase-multilingual-mini-lm-l12-v2/sbert-config",
+        "https://huggingface.co/sentence-transformers/paraphrase-multilingual-MiniLM-L12-v2/resolve/main/sentence_bert_config.json",
+    );
+    /// Shared under Apache 2.0 license at <https://huggingface.co/sentence-transformers/LaBSE>. Modified with conversion to C-array format.
+    pub const LABSE: (&'static str, &'static str) = (
+        "labse/sbert-config",
+        "https://huggingface.co/sentence-transformers/LaBSE/resolve/main/sentence_bert_config.json",
+    );
 }
 
 impl SentenceEmbeddingsTokenizerConfigResources {
@@ -202,4 +244,14 @@ impl SentenceEmbeddingsTokenizerConfigResources {
         "sentence-t5-base/tokenizer-config",
         "https://huggingface.co/sentence-transformers/sentence-t5-base/resolve/main/tokenizer_config.json",
     );
+    /// Shared under Apache 2.0 license at <https://huggingface.co/sentence-transformers/paraphrase-multilingual-MiniLM-L12-v2>. Modified with conversion to C-array format.
+    pub const PARAPHRASE_MULTILINGUAL_MINI_LM_L12_V2: (&'static str, &'static str) = (
+        "paraphrase-multilingual-mini-lm-l12-v2/tokenizer-config",
+        "https://huggingface.co/sentence-transformers/paraphrase-multilingual-MiniLM-L12-v2/resolve/main/tokenizer_config.json",
+    );
+    /// Shared under Apache 2.0 license at <https://huggingface.co/sentence-transformers/LaBSE>. Modified with conversion to C-array format.
+    pub const LABSE: (&'static str, &'static str) = (
+        "labse/tokenizer-config",
+        "https://huggingface.co/sentence-transformers/LaBSE/resolve/main/tokenizer_config.json",
+    );
 }
@@ -11,9 +11,11 @@
 // limitations under the License.
 
 //! # Sentiment Analysis pipeline
-//! Predicts the binary sentiment for a sentence. By default, the dependencies for this
-//! model will be downloaded for a DistilBERT model finetuned on SST-2.
-//! Customized DistilBERT models can be loaded by overwriting the resources in the configuration.
+//! Predicts the sentiment for a sentence. By default, the dependencies for this
+//! model will be downloaded for a DistilBERT model finetuned on SST-2 (binary sentiment).
+//! Customized models can be loaded by overwriting the resources in the configuration. The sentiment
+//! polarities are derived from the label mapping of the model, supporting binary (negative/positive),
+//! 3-class (negative/neutral/positive) and 5-star rating models.
 //! The dependencies will be downloaded to the user's home directory, under ~/.cache/.rustbert/distilbert-sst2
 //!
 //! ```no_run
@@ -41,30 +43,76 @@
 //!     Sentiment {
 //!         polarity: Positive,
 //!         score: 0.998,
+//!         stars: None,
 //!     },
 //!     Sentiment {
 //!         polarity: Negative,
 //!         score: 0.992,
+//!         stars: None,
 //!     },
 //!     Sentiment {
 //!         polarity: Positive,
 //!         score: 0.999,
+//!         stars: None,
 //!     },
 //! ]
 //! # ;
 //! ```
+//!
+//! Aspect-based sentiment analysis is performed by the `AspectSentimentModel`, scoring the sentiment
+//! expressed towards aspect terms in the sentences mentioning them with a sentence pair classification
+//! or natural language inference model.
+//!
+//! ```no_run
+//! use rust_bert::pipelines::sentiment::AspectSentimentModel;
+//!
+//! # fn main() -> anyhow::Result<()> {
+//! let aspect_sentiment_model = AspectSentimentModel::new(Default::default())?;
+//! let input = ["The food was delicious. The service, however, was painfully slow."];
+//! let output = aspect_sentiment_model.predict(&input, &["food", "service"]);
+//! # Ok(())
+//! # }
+//! ```
 
 use crate::common::error::RustBertError;
 use crate::pipelines::sequence_classification::{
     SequenceClassificationConfig, SequenceClassificationModel,
 };
+use rust_tokenizers::Offset;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
-#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg(feature = "remote")]
+use crate::{
+    bart::{BartConfigResources, BartMergesResources, BartModelResources, BartVocabResources},
+    pipelines::common::ModelType,
+    resources::RemoteResource,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 /// Enum with the possible sentiment polarities. Note that the pre-trained SST2 model does not include neutral sentiment.
 pub enum SentimentPolarity {
     Positive,
     Negative,
+    Neutral,
+}
+
+impl SentimentPolarity {
+    fn from_stars(stars: u8) -> SentimentPolarity {
+        match stars {
+            0..=2 => SentimentPolarity::Negative,
+            3 => SentimentPolarity::Neutral,
+            _ => SentimentPolarity::Positive,
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            SentimentPolarity::Positive => "positive",
+            SentimentPolarity::Negative => "negative",
+            SentimentPolarity::Neutral => "neutral",
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -74,13 +122,71 @@ pub struct Sentiment {
     pub polarity: SentimentPolarity,
     /// Confidence score
     pub score: f64,
+    /// Star rating (from 1 to 5) for 5-star rating models
+    #[serde(default)]
+    pub stars: Option<u8>,
 }
 
 pub type SentimentConfig = SequenceClassificationConfig;
 
+/// Known sentiment labels (lower-cased) with their polarity and star rating
+const SENTIMENT_LABELS: [(&str, SentimentPolarity, Option<u8>); 11] = [
+    ("negative", SentimentPolarity::Negative, None),
+    ("neg", SentimentPolarity::Negative, None),
+    ("neutral", SentimentPolarity::Neutral, None),
+    ("neu", SentimentPolarity::Neutral, None),
+    ("positive", SentimentPolarity::Positive, None),
+    ("pos", SentimentPolarity::Positive, None),
+    ("1 star", SentimentPolarity::Negative, Some(1)),
+    ("2 stars", SentimentPolarity::Negative, Some(2)),
+    ("3 stars", SentimentPolarity::Neutral, Some(3)),
+    ("4 stars", SentimentPolarity::Positive, Some(4)),
+    ("5 stars", SentimentPolarity::Positive, Some(5)),
+];
+
+/// Parses a known sentiment label (e.g. `NEGATIVE`, `neutral`, `4 stars`) to a polarity and optional star rating
+fn parse_sentiment_label(label: &str) -> Option<(SentimentPolarity, Option<u8>)> {
+    let label = label.trim().to_lowercase();
+    SENTIMENT_LABELS
+        .iter()
+        .find(|(known_label, _, _)| *known_label == label)
+        .map(|(_, polarity, stars)| (*polarity, *stars))
+}
+
+/// Maps the label ids of a sentiment classification model to sentiment polarities. Labels without
+/// sentiment information (e.g. `LABEL_0`) are mapped from their index for 2, 3 and 5 classes models.
+fn get_label_polarities(
+    label_mapping: &HashMap<i64, String>,
+) -> Result<HashMap<i64, (SentimentPolarity, Option<u8>)>, RustBertError> {
+    let num_labels = label_mapping.len();
+    label_mapping
+        .iter()
+        .map(|(&id, label)| {
+            parse_sentiment_label(label)
+                .or_else(|| match (num_labels, id) {
+                    (2, 0) | (3, 0) => Some((SentimentPolarity::Negative, None)),
+                    (3, 1) => Some((SentimentPolarity::Neutral, None)),
+                    (2, 1) | (3, 2) => Some((SentimentPolarity::Positive, None)),
+                    (5, 0..=4) => {
+                        let stars = id as u8 + 1;
+                        Some((SentimentPolarity::from_stars(stars), Some(stars)))
+                    }
+                    _ => None,
+                })
+                .map(|polarity| (id, polarity))
+                .ok_or_else(|| {
+                    RustBertError::InvalidConfigurationError(format!(
+                        "Could not map label {label} (id {id}) to a sentiment polarity"
+                    ))
+                })
+        })
+        .collect()
+}
+
 /// # SentimentClassifier to perform sentiment analysis
 pub struct SentimentModel {
     sequence_classification_model: SequenceClassificationModel,
+    label_polarities: HashMap<i64, (SentimentPolarity, Option<u8>)>,
 }
 
 impl SentimentModel {
@@ -102,8 +208,11 @@ impl SentimentModel {
     /// ```
     pub fn new(sentiment_config: SentimentConfig) -> Result<SentimentModel, RustBertError> {
         let sequence_classification_model = SequenceClassificationModel::new(sentiment_config)?;
+        let label_polarities =
+            get_label_polarities(sequence_classification_model.get_label_mapping())?;
         Ok(SentimentModel {
             sequence_classification_model,
+            label_polarities,
         })
     }
 
@@ -141,19 +250,336 @@ impl SentimentModel {
         let labels = self.sequence_classification_model.predict(input);
         let mut sentiments = Vec::with_capacity(labels.len());
         for label in labels {
-            let polarity = if label.id == 1 {
-                SentimentPolarity::Positive
-            } else {
-                SentimentPolarity::Negative
-            };
+            let (polarity, stars) = self.label_polarities[&label.id];
             sentiments.push(Sentiment {
                 polarity,
                 score: label.score,
+                stars,
             })
         }
         sentiments
     }
 }
+
+/// # Method used to score the sentiment towards an aspect
+pub enum AspectSentimentMethod {
+    /// Sentence pair classification model trained for aspect-based sentiment analysis, taking the
+    /// sentence and the aspect as inputs. The polarities are derived from the model label mapping.
+    PairClassification,
+    /// Natural language inference model scoring the entailment of a hypothesis for each polarity.
+    /// The template placeholders `{aspect}` and `{polarity}` are replaced by the aspect term and
+    /// the polarity (`positive`, `negative` or `neutral`).
+    NaturalLanguageInference { hypothesis_template: String },
+}
+
+/// # Configuration for AspectSentimentModel
+pub struct AspectSentimentConfig {
+    /// Configuration of the pair classification or natural language inference model
+    pub classification_config: SequenceClassificationConfig,
+    /// Method used to score the sentiment towards an aspect
+    pub method: AspectSentimentMethod,
+}
+
+impl AspectSentimentConfig {
+    /// Instantiate a new aspect-based sentiment analysis configuration
+    ///
+    /// # Arguments
+    ///
+    /// * `classification_config` - `SequenceClassificationConfig` of the pair classification or NLI model
+    /// * `method` - `AspectSentimentMethod` matching the model
+    pub fn new(
+        classification_config: SequenceClassificationConfig,
+        method: AspectSentimentMethod,
+    ) -> AspectSentimentConfig {
+        AspectSentimentConfig {
+            classification_config,
+            method,
+        }
+    }
+}
+
+#[cfg(feature = "remote")]
+impl Default for AspectSentimentConfig {
+    /// Provides a BART model fine-tuned on MNLI, used as natural language inference model
+    fn default() -> AspectSentimentConfig {
+        AspectSentimentConfig::new(
+            SequenceClassificationConfig::new(
+                ModelType::Bart,
+                RemoteResource::from_pretrained(BartModelResources::BART_MNLI),
+                RemoteResource::from_pretrained(BartConfigResources::BART_MNLI),
+                RemoteResource::from_pretrained(BartVocabResources::BART_MNLI),
+                Some(RemoteResource::from_pretrained(
+                    BartMergesResources::BART_MNLI,
+                )),
+                false,
+                None,
+                None,
+            ),
+            AspectSentimentMethod::NaturalLanguageInference {
+                hypothesis_template: "The sentiment towards {aspect} is {polarity}.".to_string(),
+            },
+        )
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Sentiment towards an aspect returned by the model.
+pub struct AspectSentiment {
+    /// Aspect term
+    pub aspect: String,
+    /// Polarity of the sentiment towards the aspect
+    pub polarity: SentimentPolarity,
+    /// Confidence score
+    pub score: f64,
+    /// Text span (sentence) mentioning the aspect, used as evidence for the sentiment
+    pub span: String,
+    /// Offset of the span in the input text (in characters)
+    pub offset: Offset,
+}
+
+enum AspectScorer {
+    PairClassification(HashMap<i64, (SentimentPolarity, Option<u8>)>),
+    NaturalLanguageInference {
+        hypothesis_template: String,
+        entailment_id: usize,
+    },
+}
+
+/// # AspectSentimentModel to perform aspect-based sentiment analysis
+pub struct AspectSentimentModel {
+    sequence_classification_model: SequenceClassificationModel,
+    scorer: AspectScorer,
+}
+
+impl AspectSentimentModel {
+    /// Build a new `AspectSentimentModel`
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - `AspectSentimentConfig` object containing the classification model configuration and scoring method
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::sentiment::AspectSentimentModel;
+    ///
+    /// let aspect_sentiment_model = AspectSentimentModel::new(Default::default())?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn new(config: AspectSentimentConfig) -> Result<AspectSentimentModel, RustBertError> {
+        let sequence_classification_model =
+            SequenceClassificationModel::new(config.classification_config)?;
+        let label_mapping = sequence_classification_model.get_label_mapping();
+        let scorer = match config.method {
+            AspectSentimentMethod::PairClassification => {
+                AspectScorer::PairClassification(get_label_polarities(label_mapping)?)
+            }
+            AspectSentimentMethod::NaturalLanguageInference {
+                hypothesis_template,
+            } => {
+                // Defaults to the last label, following the zero-shot classification convention
+                let entailment_id = label_mapping
+                    .iter()
+                    .find(|(_, label)| label.to_lowercase().starts_with("entail"))
+                    .map(|(&id, _)| id)
+                    .or_else(|| label_mapping.keys().max().copied())
+                    .ok_or_else(|| {
+                        RustBertError::InvalidConfigurationError(
+                            "The natural language inference model has no labels".to_string(),
+                        )
+                    })?;
+                AspectScorer::NaturalLanguageInference {
+                    hypothesis_template,
+                    entailment_id: entailment_id as usize,
+                }
+            }
+        };
+        Ok(AspectSentimentModel {
+            sequence_classification_model,
+            scorer,
+        })
+    }
+
+    /// Extract the sentiment towards aspect terms from an array of text inputs. The texts are split in
+    /// sentences, and the sentiment is predicted for each sentence mentioning an aspect as a whole word (case-insensitive).
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - `&[&str]` Array of texts to extract the sentiments from.
+    /// * `aspects` - `&[&str]` Aspect terms.
+    ///
+    /// # Returns
+    /// * `Vec<Vec<AspectSentiment>>` Sentiments towards the aspects mentioned in each text.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::sentiment::AspectSentimentModel;
+    ///
+    /// let aspect_sentiment_model = AspectSentimentModel::new(Default::default())?;
+    /// let input = [
+    ///     "The food was delicious. The service, however, was painfully slow.",
+    ///     "Great location, but the rooms were noisy.",
+    /// ];
+    /// let output = aspect_sentiment_model.predict(&input, &["food", "service", "rooms"]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn predict(&self, input: &[&str], aspects: &[&str]) -> Vec<Vec<AspectSentiment>> {
+        let mut candidates = vec![];
+        for (text_index, text) in input.iter().enumerate() {
+            for (offset, span) in split_sentences(text) {
+                let lowercase_span = span.to_lowercase();
+                for aspect in aspects {
+                    if contains_word(&lowercase_span, &aspect.to_lowercase()) {
+                        candidates.push((text_index, *aspect, span, offset));
+                    }
+                }
+            }
+        }
+
+        let mut output: Vec<Vec<AspectSentiment>> = input.iter().map(|_| vec![]).collect();
+        if candidates.is_empty() {
+            return output;
+        }
+        let scores = match &self.scorer {
+            AspectScorer::PairClassification(label_polarities) => {
+                let pairs = candidates
+                    .iter()
+                    .map(|(_, aspect, span, _)| (*span, *aspect))
+                    .collect::<Vec<(&str, &str)>>();
+                self.sequence_classification_model
+                    .predict_pairs(&pairs)
+                    .into_iter()
+                    .map(|label| (label_polarities[&label.id].0, label.score))
+                    .collect::<Vec<(SentimentPolarity, f64)>>()
+            }
+            AspectScorer::NaturalLanguageInference {
+                hypothesis_template,
+                entailment_id,
+            } => {
+                let polarities = [
+                    SentimentPolarity::Negative,
+                    SentimentPolarity::Neutral,
+                    SentimentPolarity::Positive,
+                ];
+                let hypotheses = candidates
+                    .iter()
+                    .flat_map(|(_, aspect, _, _)| {
+                        polarities.iter().map(move |polarity| {
+                            hypothesis_template
+                                .replace("{aspect}", aspect)
+                                .replace("{polarity}", polarity.as_str())
+                        })
+                    })
+                    .collect::<Vec<String>>();
+                let pairs = candidates
+                    .iter()
+                    .flat_map(|(_, _, span, _)| polarities.iter().map(move |_| *span))
+                    .zip(hypotheses.iter().map(String::as_str))
+                    .collect::<Vec<(&str, &str)>>();
+                let logits = self
+                    .sequence_classification_model
+                    .predict_pair_logits(&pairs);
+                logits
+                    .chunks(polarities.len())
+                    .map(|candidate_logits| {
+                        let entailment_logits = candidate_logits
+                            .iter()
+                            .map(|logits| logits[*entailment_id])
+                            .collect::<Vec<f64>>();
+                        let (index, score) = softmax_max(&entailment_logits);
+                        (polarities[index], score)
+                    })
+                    .collect::<Vec<(SentimentPolarity, f64)>>()
+            }
+        };
+
+        for ((text_index, aspect, span, offset), (polarity, score)) in
+            candidates.into_iter().zip(scores)
+        {
+            output[text_index].push(AspectSentiment {
+                aspect: aspect.to_string(),
+                polarity,
+                score,
+                span: span.to_string(),
+                offset,
+            });
+        }
+        output
+    }
+}
+
+/// Returns the index and probability of the most likely class from logits
+fn softmax_max(logits: &[f64]) -> (usize, f64) {
+    let max_logit = logits.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    let exponentials = logits
+        .iter()
+        .map(|logit| (logit - max_logit).exp())
+        .collect::<Vec<f64>>();
+    let sum = exponentials.iter().sum::<f64>();
+    let (index, max_exponential) = exponentials
+        .iter()
+        .enumerate()
+        .max_by(|(_, a), (_, b)| a.total_cmp(b))
+        .unwrap();
+    (index, max_exponential / sum)
+}
+
+/// Splits a text in sentences (or clauses ending with `;`), returning the trimmed sentences with their
+/// character offsets. Punctuation followed by a non-whitespace character (e.g. `3.5`) does not end a sentence.
+fn split_sentences(text: &str) -> Vec<(Offset, &str)> {
+    let mut sentences = vec![];
+    let mut start = (0, 0);
+    for (char_index, (byte_index, character)) in text.char_indices().enumerate() {
+        let end_byte = byte_index + character.len_utf8();
+        if character == '\n'
+            || (matches!(character, '.' | '!' | '?' | ';')
+                && !text[end_byte..].starts_with(|c: char| !c.is_whitespace()))
+        {
+            let end = (end_byte, char_index as u32 + 1);
+            push_sentence(text, start, end, &mut sentences);
+            start = end;
+        }
+    }
+    push_sentence(text, start, (text.len(), char_length(text)), &mut sentences);
+    sentences
+}
+
+fn push_sentence<'a>(
+    text: &'a str,
+    start: (usize, u32),
+    end: (usize, u32),
+    sentences: &mut Vec<(Offset, &'a str)>,
+) {
+    let segment = &text[start.0..end.0];
+    let left_trimmed = segment.trim_start();
+    let sentence = left_trimmed.trim_end();
+    if !sentence.is_empty() {
+        let begin = start.1 + char_length(&segment[..segment.len() - left_trimmed.len()]);
+        sentences.push((Offset::new(begin, begin + char_length(sentence)), sentence));
+    }
+}
+
+fn char_length(text: &str) -> u32 {
+    text.chars().count() as u32
+}
+
+/// Checks if a text contains a word (or sequence of words) delimited by non-alphanumeric characters,
+/// so that e.g. `food` is not found in `seafood`
+fn contains_word(text: &str, word: &str) -> bool {
+    !word.is_empty()
+        && text.match_indices(word).any(|(start, _)| {
+            let before = text[..start].chars().next_back();
+            let after = text[start + word.len()..].chars().next();
+            !matches!(before, Some(c) if c.is_alphanumeric())
+                && !matches!(after, Some(c) if c.is_alphanumeric())
+        })
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -164,4 +590,110 @@ mod test {
         let config = SentimentConfig::default();
         let _: Box<dyn Send> = Box::new(SentimentModel::new(config));
     }
+
+    #[test]
+    fn test_label_polarities() {
+        let label_mapping: HashMap<i64, String> = vec![
+            (0, "1 star".to_string()),
+            (1, "2 stars".to_string()),
+            (2, "3 stars".to_string()),
+            (3, "4 stars".to_string()),
+            (4, "5 stars".to_string()),
+        ]
+        .into_iter()
+        .collect();
+        let label_polarities = get_label_polarities(&label_mapping).unwrap();
+        assert_eq!(label_polarities[&1], (SentimentPolarity::Negative, Some(2)));
+        assert_eq!(label_polarities[&2], (SentimentPolarity::Neutral, Some(3)));
+
+        let label_mapping: HashMap<i64, String> = vec![
+            (0, "LABEL_0".to_string()),
+            (1, "LABEL_1".to_string()),
+            (2, "LABEL_2".to_string()),
+        ]
+        .into_iter()
+        .collect();
+        let label_polarities = get_label_polarities(&label_mapping).unwrap();
+        assert_eq!(label_polarities[&1], (SentimentPolarity::Neutral, None));
+        assert_eq!(label_polarities[&2], (SentimentPolarity::Positive, None));
+
+        let label_mapping: HashMap<i64, String> =
+            vec![(0, "POSITIVE".to_string()), (1, "NEGATIVE".to_string())]
+                .into_iter()
+                .collect();
+        let label_polarities = get_label_polarities(&label_mapping).unwrap();
+        assert_eq!(label_polarities[&0], (SentimentPolarity::Positive, None));
+
+        let label_mapping: HashMap<i64, String> = vec![
+            (0, "sports".to_string()),
+            (1, "politics".to_string()),
+            (2, "science".to_string()),
+            (3, "arts".to_string()),
+        ]
+        .into_iter()
+        .collect();
+        assert!(get_label_polarities(&label_mapping).is_err());
+    }
+
+    #[test]
+    fn test_parse_sentiment_label() {
+        assert_eq!(
+            parse_sentiment_label("NEGATIVE"),
+            Some((SentimentPolarity::Negative, None))
+        );
+        assert_eq!(
+            parse_sentiment_label(" Neutral "),
+            Some((SentimentPolarity::Neutral, None))
+        );
+        assert_eq!(
+            parse_sentiment_label("1 star"),
+            Some((SentimentPolarity::Negative, Some(1)))
+        );
+        assert_eq!(
+            parse_sentiment_label("4 stars"),
+            Some((SentimentPolarity::Positive, Some(4)))
+        );
+        // Labels merely containing a known label are not sentiment labels
+        assert_eq!(parse_sentiment_label("position"), None);
+        assert_eq!(parse_sentiment_label("not negative"), None);
+        assert_eq!(parse_sentiment_label("6 stars"), None);
+        assert_eq!(parse_sentiment_label("starred"), None);
+    }
+
+    #[test]
+    fn test_contains_word() {
+        assert!(contains_word("the food was delicious.", "food"));
+        assert!(contains_word("food, however, was cold", "food"));
+        assert!(contains_word("the self-service was slow", "service"));
+        assert!(contains_word("the room service was slow", "room service"));
+        assert!(contains_word("great seafood and food", "food"));
+        assert!(!contains_word("the seafood was delicious", "food"));
+        assert!(!contains_word("the foods were delicious", "food"));
+        assert!(!contains_word("the food was delicious", ""));
+    }
+
+    #[test]
+    fn test_split_sentences() {
+        let text =
+            "  The café was great!  Prices were 3.5 times higher; service slow.\nWould return";
+        let sentences = split_sentences(text);
+        assert_eq!(
+            sentences.iter().map(|(_, s)| *s).collect::<Vec<&str>>(),
+            vec![
+                "The café was great!",
+                "Prices were 3.5 times higher;",
+                "service slow.",
+                "Would return"
+            ]
+        );
+        for (offset, sentence) in sentences {
+            assert_eq!(
+                text.chars()
+                    .skip(offset.begin as usize)
+                    .take((offset.end - offset.begin) as usize)
+                    .collect::<String>(),
+                sentence
+            );
+        }
+    }
 }
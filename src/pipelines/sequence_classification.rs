@@ -61,6 +61,7 @@ use crate::albert::AlbertForSequenceClassification;
 use crate::bart::BartForSequenceClassification;
 use crate::bert::BertForSequenceClassification;
 use crate::common::error::RustBertError;
+pub use crate::common::loss::ProblemType;
 use crate::deberta::DebertaForSequenceClassification;
 use crate::distilbert::DistilBertModelClassifier;
 use crate::fnet::FNetForSequenceClassification;
@@ -121,6 +122,10 @@ pub struct SequenceClassificationConfig {
     pub add_prefix_space: Option<bool>,
     /// Device to place the model on (default: CUDA/GPU when available)
     pub device: Device,
+    /// Problem type (default: `problem_type` of the model configuration file if set, regression for models with a single label, single-label classification otherwise)
+    pub problem_type: Option<ProblemType>,
+    /// Number of sequences processed in a single forward pass (default: 64)
+    pub batch_size: usize,
 }
 
 impl SequenceClassificationConfig {
@@ -159,6 +164,8 @@ impl SequenceClassificationConfig {
             strip_accents: strip_accents.into(),
             add_prefix_space: add_prefix_space.into(),
             device: Device::cuda_if_available(),
+            problem_type: None,
+            batch_size: 64,
         }
     }
 }
@@ -407,132 +414,166 @@ impl SequenceClassificationOption {
         input_embeds: Option<&Tensor>,
         train: bool,
     ) -> Tensor {
+        self.forward_t_with_labels(
+            input_ids,
+            mask,
+            token_type_ids,
+            position_ids,
+            input_embeds,
+            None,
+            train,
+        )
+        .0
+    }
+
+    /// Interface method to forward_t() of the particular models, computing the loss if labels are provided.
+    /// Returns the logits and the optional loss.
+    pub fn forward_t_with_labels(
+        &self,
+        input_ids: Option<&Tensor>,
+        mask: Option<&Tensor>,
+        token_type_ids: Option<&Tensor>,
+        position_ids: Option<&Tensor>,
+        input_embeds: Option<&Tensor>,
+        labels: Option<&Tensor>,
+        train: bool,
+    ) -> (Tensor, Option<Tensor>) {
         match *self {
             Self::Bart(ref model) => {
-                model
-                    .forward_t(
-                        input_ids.expect("`input_ids` must be provided for BART models"),
-                        mask,
-                        None,
-                        None,
-                        None,
-                        train,
-                    )
-                    .decoder_output
+                let output = model.forward_t(
+                    input_ids.expect("`input_ids` must be provided for BART models"),
+                    mask,
+                    None,
+                    None,
+                    None,
+                    labels,
+                    train,
+                );
+                (output.decoder_output, output.loss)
             }
             Self::Bert(ref model) => {
-                model
-                    .forward_t(
-                        input_ids,
-                        mask,
-                        token_type_ids,
-                        position_ids,
-                        input_embeds,
-                        train,
-                    )
-                    .logits
+                let output = model.forward_t(
+                    input_ids,
+                    mask,
+                    token_type_ids,
+                    position_ids,
+                    input_embeds,
+                    labels,
+                    train,
+                );
+                (output.logits, output.loss)
             }
             Self::Deberta(ref model) => {
-                model
+                let output = model
                     .forward_t(
                         input_ids,
                         mask,
                         token_type_ids,
                         position_ids,
                         input_embeds,
+                        labels,
                         train,
                     )
-                    .expect("Error in Deberta forward_t")
-                    .logits
+                    .expect("Error in Deberta forward_t");
+                (output.logits, output.loss)
             }
             Self::DebertaV2(ref model) => {
-                model
+                let output = model
                     .forward_t(
                         input_ids,
                         mask,
                         token_type_ids,
                         position_ids,
                         input_embeds,
+                        labels,
                         train,
                     )
-                    .expect("Error in Deberta V2 forward_t")
-                    .logits
+                    .expect("Error in Deberta V2 forward_t");
+                (output.logits, output.loss)
             }
             Self::DistilBert(ref model) => {
-                model
-                    .forward_t(input_ids, mask, input_embeds, train)
-                    .expect("Error in distilbert forward_t")
-                    .logits
+                let output = model
+                    .forward_t(input_ids, mask, input_embeds, labels, train)
+                    .expect("Error in distilbert forward_t");
+                (output.logits, output.loss)
             }
             Self::MobileBert(ref model) => {
-                model
-                    .forward_t(input_ids, None, None, input_embeds, mask, train)
-                    .expect("Error in mobilebert forward_t")
-                    .logits
+                let output = model
+                    .forward_t(input_ids, None, None, input_embeds, mask, labels, train)
+                    .expect("Error in mobilebert forward_t");
+                (output.logits, output.loss)
             }
             Self::Roberta(ref model) | Self::XLMRoberta(ref model) => {
-                model
-                    .forward_t(
-                        input_ids,
-                        mask,
-                        token_type_ids,
-                        position_ids,
-                        input_embeds,
-                        train,
-                    )
-                    .logits
+                let output = model.forward_t(
+                    input_ids,
+                    mask,
+                    token_type_ids,
+                    position_ids,
+                    input_embeds,
+                    labels,
+                    train,
+                );
+                (output.logits, output.loss)
             }
             Self::Albert(ref model) => {
-                model
-                    .forward_t(
-                        input_ids,
-                        mask,
-                        token_type_ids,
-                        position_ids,
-                        input_embeds,
-                        train,
-                    )
-                    .logits
+                let output = model.forward_t(
+                    input_ids,
+                    mask,
+                    token_type_ids,
+                    position_ids,
+                    input_embeds,
+                    labels,
+                    train,
+                );
+                (output.logits, output.loss)
             }
             Self::XLNet(ref model) => {
-                model
+                let output = model.forward_t(
+                    input_ids,
+                    mask,
+                    None,
+                    None,
+                    None,
+                    token_type_ids,
+                    input_embeds,
+                    labels,
+                    train,
+                );
+                (output.logits, output.loss)
+            }
+            Self::Reformer(ref model) => {
+                let output = model
+                    .forward_t(input_ids, None, None, mask, None, labels, train)
+                    .expect("Error in Reformer forward pass.");
+                (output.logits, output.loss)
+            }
+            Self::Longformer(ref model) => {
+                let output = model
                     .forward_t(
                         input_ids,
                         mask,
                         None,
-                        None,
-                        None,
                         token_type_ids,
+                        position_ids,
                         input_embeds,
+                        labels,
                         train,
                     )
-                    .logits
+                    .expect("Error in Longformer forward pass.");
+                (output.logits, output.loss)
             }
-            Self::Reformer(ref model) => {
-                model
-                    .forward_t(input_ids, None, None, mask, None, train)
-                    .expect("Error in Reformer forward pass.")
-                    .logits
-            }
-            Self::Longformer(ref model) => {
-                model
+            Self::FNet(ref model) => {
+                let output = model
                     .forward_t(
                         input_ids,
-                        mask,
-                        None,
                         token_type_ids,
                         position_ids,
                         input_embeds,
+                        labels,
                         train,
                     )
-                    .expect("Error in Longformer forward pass.")
-                    .logits
-            }
-            Self::FNet(ref model) => {
-                model
-                    .forward_t(input_ids, token_type_ids, position_ids, input_embeds, train)
-                    .expect("Error in FNet forward pass.")
-                    .logits
+                    .expect("Error in FNet forward pass.");
+                (output.logits, output.loss)
             }
         }
     }
@@ -545,6 +586,8 @@ pub struct SequenceClassificationModel {
     label_mapping: HashMap<i64, String>,
     var_store: VarStore,
     max_length: usize,
+    problem_type: ProblemType,
+    batch_size: usize,
 }
 
 impl SequenceClassificationModel {
@@ -567,6 +610,11 @@ impl SequenceClassificationModel {
     pub fn new(
         config: SequenceClassificationConfig,
     ) -> Result<SequenceClassificationModel, RustBertError> {
+        if config.batch_size == 0 {
+            return Err(RustBertError::InvalidConfigurationError(
+                "The batch size for sequence classification must be positive".to_string(),
+            ));
+        }
         let config_path = config.config_resource.get_local_path()?;
         let vocab_path = config.vocab_resource.get_local_path()?;
         let weights_path = config.model_resource.get_local_path()?;
@@ -586,7 +634,10 @@ impl SequenceClassificationModel {
             config.add_prefix_space,
         )?;
         let mut var_store = VarStore::new(device);
-        let model_config = ConfigOption::from_file(config.model_type, config_path);
+        let mut model_config = ConfigOption::from_file(config.model_type, &config_path);
+        if let Some(problem_type) = config.problem_type {
+            model_config.set_problem_type(problem_type)?;
+        }
         let max_length = model_config
             .get_max_len()
             .map(|v| v as usize)
@@ -594,6 +645,13 @@ impl SequenceClassificationModel {
         let sequence_classifier =
             SequenceClassificationOption::new(config.model_type, var_store.root(), &model_config)?;
         let label_mapping = model_config.get_label_mapping().clone();
+        let problem_type = model_config
+            .get_problem_type()
+            .unwrap_or(if label_mapping.len() == 1 {
+                ProblemType::Regression
+            } else {
+                ProblemType::SingleLabelClassification
+            });
         var_store.load(weights_path)?;
         Ok(SequenceClassificationModel {
             tokenizer,
@@ -601,40 +659,134 @@ impl SequenceClassificationModel {
             label_mapping,
             var_store,
             max_length,
+            problem_type,
+            batch_size: config.batch_size,
         })
     }
 
-    fn prepare_for_model<'a, S>(&self, input: S) -> Tensor
-    where
-        S: AsRef<[&'a str]>,
-    {
-        let tokenized_input: Vec<TokenizedInput> = self.tokenizer.encode_list(
-            input.as_ref(),
+    /// Returns the problem type used to interpret the model outputs
+    pub fn get_problem_type(&self) -> ProblemType {
+        self.problem_type
+    }
+
+    /// Returns the mapping from label index to label name
+    pub fn get_label_mapping(&self) -> &HashMap<i64, String> {
+        &self.label_mapping
+    }
+
+    fn encode_texts(&self, input: &[&str]) -> Vec<TokenizedInput> {
+        self.tokenizer
+            .encode_list(input, self.max_length, &TruncationStrategy::LongestFirst, 0)
+    }
+
+    fn encode_pairs(&self, input: &[(&str, &str)]) -> Vec<TokenizedInput> {
+        self.tokenizer.encode_pair_list(
+            input,
             self.max_length,
             &TruncationStrategy::LongestFirst,
             0,
-        );
-        let max_len = tokenized_input
-            .iter()
-            .map(|input| input.token_ids.len())
-            .max()
-            .unwrap();
+        )
+    }
+
+    /// Runs the model on tokenized inputs `batch_size` sequences at a time, returning the logits as a
+    /// (number of inputs, number of labels) tensor on the CPU
+    fn forward_logits(&self, tokenized_input: &[TokenizedInput]) -> Tensor {
         let pad_id = self
             .tokenizer
             .get_pad_id()
             .expect("The Tokenizer used for sequence classification should contain a PAD id");
-        let tokenized_input_tensors: Vec<tch::Tensor> = tokenized_input
+        let device = self.var_store.device();
+        let mut batch_logits = Vec::with_capacity(tokenized_input.len() / self.batch_size + 1);
+        for batch in tokenized_input.chunks(self.batch_size) {
+            let (input_tensor, token_type_ids, mask) =
+                pad_tokenized_input(batch, pad_id, self.tokenizer.model_type(), device);
+            batch_logits.push(no_grad(|| {
+                self.sequence_classifier
+                    .forward_t(
+                        Some(&input_tensor),
+                        Some(&mask),
+                        Some(&token_type_ids),
+                        None,
+                        None,
+                        false,
+                    )
+                    .to_kind(Kind::Double)
+                    .detach()
+                    .to(Device::Cpu)
+            }));
+        }
+        if batch_logits.is_empty() {
+            Tensor::zeros(
+                &[0, self.label_mapping.len() as i64],
+                (Kind::Double, Device::Cpu),
+            )
+        } else {
+            Tensor::cat(&batch_logits, 0)
+        }
+    }
+
+    /// Converts logits to scores according to the problem type (raw values for regression, softmax
+    /// for single-label and sigmoid for multi-label classification)
+    fn activate(&self, logits: Tensor) -> Tensor {
+        match self.problem_type {
+            ProblemType::Regression => logits,
+            ProblemType::SingleLabelClassification => logits.softmax(-1, Kind::Double),
+            ProblemType::MultiLabelClassification => logits.sigmoid(),
+        }
+    }
+
+    fn get_labels(&self, scores: Tensor) -> Vec<Label> {
+        Vec::<Vec<f64>>::from(scores)
+            .into_iter()
+            .enumerate()
+            .map(|(sentence, scores)| {
+                let (id, score) = match self.problem_type {
+                    ProblemType::Regression => (0, scores[0]),
+                    _ => scores.iter().enumerate().fold(
+                        (0, f64::NEG_INFINITY),
+                        |best, (id, score)| {
+                            if *score > best.1 {
+                                (id as i64, *score)
+                            } else {
+                                best
+                            }
+                        },
+                    ),
+                };
+                Label {
+                    text: self.label_mapping.get(&id).cloned().unwrap_or_default(),
+                    score,
+                    id,
+                    sentence,
+                }
+            })
+            .collect()
+    }
+
+    fn get_multilabels(&self, probabilities: Tensor, threshold: f64) -> Vec<Vec<Label>> {
+        Vec::<Vec<f64>>::from(probabilities)
             .into_iter()
-            .map(|mut input| {
-                input.token_ids.resize(max_len, pad_id);
-                Tensor::of_slice(&(input.token_ids))
+            .enumerate()
+            .map(|(sentence, scores)| {
+                scores
+                    .into_iter()
+                    .enumerate()
+                    .filter(|(_, score)| *score >= threshold)
+                    .map(|(id, score)| Label {
+                        text: self.label_mapping.get(&(id as i64)).unwrap().to_owned(),
+                        score,
+                        id: id as i64,
+                        sentence,
+                    })
+                    .collect()
             })
-            .collect::<Vec<_>>();
-        Tensor::stack(tokenized_input_tensors.as_slice(), 0).to(self.var_store.device())
+            .collect()
     }
 
     /// Classify texts
     ///
+    /// Returns the most likely label for each text. For regression models, the label score is the raw model output.
+    ///
     /// # Arguments
     ///
     /// * `input` - `&[&str]` Array of texts to classify.
@@ -663,45 +815,100 @@ impl SequenceClassificationModel {
     where
         S: AsRef<[&'a str]>,
     {
-        let input_tensor = self.prepare_for_model(input.as_ref());
-        let output = no_grad(|| {
-            let output = self.sequence_classifier.forward_t(
-                Some(&input_tensor),
-                None,
-                None,
-                None,
-                None,
-                false,
-            );
-            output.softmax(-1, Kind::Float).detach().to(Device::Cpu)
-        });
-        let label_indices = output.as_ref().argmax(-1, true).squeeze_dim(1);
-        let scores = output
-            .gather(1, &label_indices.unsqueeze(-1), false)
-            .squeeze_dim(1);
-        let label_indices = label_indices.iter::<i64>().unwrap().collect::<Vec<i64>>();
-        let scores = scores.iter::<f64>().unwrap().collect::<Vec<f64>>();
-
-        let mut labels: Vec<Label> = vec![];
-        for sentence_idx in 0..label_indices.len() {
-            let label_string = self
-                .label_mapping
-                .get(&label_indices[sentence_idx])
-                .unwrap()
-                .clone();
-            let label = Label {
-                text: label_string,
-                score: scores[sentence_idx],
-                id: label_indices[sentence_idx],
-                sentence: sentence_idx,
-            };
-            labels.push(label)
-        }
-        labels
+        let logits = self.forward_logits(&self.encode_texts(input.as_ref()));
+        self.get_labels(self.activate(logits))
+    }
+
+    /// Classify sentence pairs (e.g. natural language inference, paraphrase detection or semantic similarity)
+    ///
+    /// Returns the most likely label for each pair. For regression models, the label score is the raw model output.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - `&[(&str, &str)]` Array of sentence pairs to classify.
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<Label>` containing labels for input pairs
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// # use rust_bert::pipelines::sequence_classification::SequenceClassificationModel;
+    ///
+    /// let sequence_classification_model = SequenceClassificationModel::new(Default::default())?;
+    /// let input = [(
+    ///     "A man is playing a guitar.",
+    ///     "A person is playing an instrument.",
+    /// )];
+    /// let output = sequence_classification_model.predict_pairs(&input);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn predict_pairs(&self, input: &[(&str, &str)]) -> Vec<Label> {
+        let logits = self.forward_logits(&self.encode_pairs(input));
+        self.get_labels(self.activate(logits))
+    }
+
+    /// Raw model outputs (logits) for texts
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - `&[&str]` Array of texts to classify.
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<Vec<f64>>` containing the logits over all labels for each text
+    pub fn predict_logits(&self, input: &[&str]) -> Vec<Vec<f64>> {
+        Vec::from(self.forward_logits(&self.encode_texts(input)))
+    }
+
+    /// Raw model outputs (logits) for sentence pairs
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - `&[(&str, &str)]` Array of sentence pairs to classify.
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<Vec<f64>>` containing the logits over all labels for each pair
+    pub fn predict_pair_logits(&self, input: &[(&str, &str)]) -> Vec<Vec<f64>> {
+        Vec::from(self.forward_logits(&self.encode_pairs(input)))
+    }
+
+    /// Scores over all labels for texts: probabilities (softmax for single-label, sigmoid for multi-label
+    /// classification) or raw values for regression
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - `&[&str]` Array of texts to classify.
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<Vec<f64>>` containing the scores of all labels for each text, indexed by label id
+    pub fn predict_probabilities(&self, input: &[&str]) -> Vec<Vec<f64>> {
+        Vec::from(self.activate(self.forward_logits(&self.encode_texts(input))))
+    }
+
+    /// Scores over all labels for sentence pairs: probabilities (softmax for single-label, sigmoid for multi-label
+    /// classification) or raw values for regression
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - `&[(&str, &str)]` Array of sentence pairs to classify.
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<Vec<f64>>` containing the scores of all labels for each pair, indexed by label id
+    pub fn predict_pair_probabilities(&self, input: &[(&str, &str)]) -> Vec<Vec<f64>> {
+        Vec::from(self.activate(self.forward_logits(&self.encode_pairs(input))))
     }
 
     /// Multi-label classification of texts
     ///
+    /// Label probabilities are computed with a sigmoid, independently of the model problem type.
+    ///
     /// # Arguments
     ///
     /// * `input` - `&[&str]` Array of texts to classify.
@@ -732,54 +939,84 @@ impl SequenceClassificationModel {
         input: &[&str],
         threshold: f64,
     ) -> Result<Vec<Vec<Label>>, RustBertError> {
-        let input_tensor = self.prepare_for_model(input);
-        let output = no_grad(|| {
-            let output = self.sequence_classifier.forward_t(
-                Some(&input_tensor),
-                None,
-                None,
-                None,
-                None,
-                false,
-            );
-            output.sigmoid().detach().to(Device::Cpu)
-        });
-        let label_indices = output.as_ref().ge(threshold).nonzero();
-
-        let mut labels: Vec<Vec<Label>> = vec![];
-        let mut sequence_labels: Vec<Label> = vec![];
-
-        for sentence_idx in 0..label_indices.size()[0] {
-            let label_index_tensor = label_indices.get(sentence_idx);
-            let sentence_label = label_index_tensor
-                .iter::<i64>()
-                .unwrap()
-                .collect::<Vec<i64>>();
-            let (sentence, id) = (sentence_label[0], sentence_label[1]);
-            if sentence as usize > labels.len() {
-                labels.push(sequence_labels);
-                sequence_labels = vec![];
-            }
-            let score = output.double_value(sentence_label.as_slice());
-            let label_string = self.label_mapping.get(&id).unwrap().to_owned();
-            let label = Label {
-                text: label_string,
-                score,
-                id,
-                sentence: sentence as usize,
-            };
-            sequence_labels.push(label);
-        }
-        if !sequence_labels.is_empty() {
-            labels.push(sequence_labels);
-        }
-        Ok(labels)
+        let logits = self.forward_logits(&self.encode_texts(input));
+        Ok(self.get_multilabels(logits.sigmoid(), threshold))
+    }
+
+    /// Multi-label classification of sentence pairs
+    ///
+    /// Label probabilities are computed with a sigmoid, independently of the model problem type.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - `&[(&str, &str)]` Array of sentence pairs to classify.
+    /// * `threshold` - `f64` threshold above which a label will be considered true by the classifier
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<Vec<Label>>` containing a vector of true labels for each input pair
+    pub fn predict_multilabel_pairs(
+        &self,
+        input: &[(&str, &str)],
+        threshold: f64,
+    ) -> Result<Vec<Vec<Label>>, RustBertError> {
+        let logits = self.forward_logits(&self.encode_pairs(input));
+        Ok(self.get_multilabels(logits.sigmoid(), threshold))
+    }
+}
+
+/// Pads tokenized inputs to the length of the longest input and returns the token ids, segment ids
+/// and attention mask as (*batch size*, *sequence_length*) tensors on `device`. Padded positions
+/// receive `pad_id`, a segment id of 0 and an attention mask value of 0. RoBERTa-based models have a
+/// single token type: their segment ids are all set to 0.
+pub(crate) fn pad_tokenized_input(
+    tokenized_input: &[TokenizedInput],
+    pad_id: i64,
+    model_type: ModelType,
+    device: Device,
+) -> (Tensor, Tensor, Tensor) {
+    let single_token_type = matches!(
+        model_type,
+        ModelType::Roberta | ModelType::XLMRoberta | ModelType::Longformer
+    );
+    let max_len = tokenized_input
+        .iter()
+        .map(|input| input.token_ids.len())
+        .max()
+        .unwrap_or(0);
+    let mut token_ids = Vec::with_capacity(tokenized_input.len());
+    let mut segment_ids = Vec::with_capacity(tokenized_input.len());
+    let mut attention_masks = Vec::with_capacity(tokenized_input.len());
+    for input in tokenized_input {
+        let mut input_token_ids = input.token_ids.clone();
+        input_token_ids.resize(max_len, pad_id);
+        let mut input_segment_ids = if single_token_type {
+            vec![0; input.segment_ids.len()]
+        } else {
+            input
+                .segment_ids
+                .iter()
+                .map(|segment_id| *segment_id as i64)
+                .collect::<Vec<i64>>()
+        };
+        input_segment_ids.resize(max_len, 0);
+        let mut attention_mask = vec![1i64; input.token_ids.len()];
+        attention_mask.resize(max_len, 0);
+        token_ids.push(Tensor::of_slice(&input_token_ids));
+        segment_ids.push(Tensor::of_slice(&input_segment_ids));
+        attention_masks.push(Tensor::of_slice(&attention_mask));
     }
+    (
+        Tensor::stack(&token_ids, 0).to(device),
+        Tensor::stack(&segment_ids, 0).to(device),
+        Tensor::stack(&attention_masks, 0).to(device),
+    )
 }
 
 #[cfg(test)]
 mod test {
     use super::*;
+    use rust_tokenizers::Mask;
 
     #[test]
     #[ignore] // no need to run, compilation is enough to verify it is Send
@@ -787,4 +1024,47 @@ mod test {
         let config = SequenceClassificationConfig::default();
         let _: Box<dyn Send> = Box::new(SequenceClassificationModel::new(config));
     }
+
+    #[test]
+    fn test_pad_tokenized_input() {
+        let tokenized_input = |token_ids: Vec<i64>, segment_ids: Vec<i8>| TokenizedInput {
+            special_tokens_mask: vec![0; token_ids.len()],
+            overflowing_tokens: vec![],
+            num_truncated_tokens: 0,
+            token_offsets: vec![None; token_ids.len()],
+            reference_offsets: vec![vec![]; token_ids.len()],
+            mask: vec![Mask::None; token_ids.len()],
+            token_ids,
+            segment_ids,
+        };
+        let inputs = vec![
+            tokenized_input(vec![101, 7, 8, 102], vec![0, 0, 1, 1]),
+            tokenized_input(vec![101, 9], vec![0, 1]),
+        ];
+        let (token_ids, segment_ids, attention_mask) =
+            pad_tokenized_input(&inputs, 0, ModelType::Bert, Device::Cpu);
+
+        assert_eq!(token_ids.size(), vec![2, 4]);
+        assert_eq!(
+            Vec::<i64>::from(token_ids.view([-1])),
+            vec![101, 7, 8, 102, 101, 9, 0, 0]
+        );
+        assert_eq!(
+            Vec::<i64>::from(segment_ids.view([-1])),
+            vec![0, 0, 1, 1, 0, 1, 0, 0]
+        );
+        assert_eq!(
+            Vec::<i64>::from(attention_mask.view([-1])),
+            vec![1, 1, 1, 1, 1, 1, 0, 0]
+        );
+
+        // RoBERTa-based models do not use the segment ids of the second sequence
+        let (token_ids, segment_ids, _) =
+            pad_tokenized_input(&inputs, 1, ModelType::XLMRoberta, Device::Cpu);
+        assert_eq!(
+            Vec::<i64>::from(token_ids.view([-1])),
+            vec![101, 7, 8, 102, 101, 9, 1, 1]
+        );
+        assert_eq!(Vec::<i64>::from(segment_ids.view([-1])), vec![0; 8]);
+    }
 }
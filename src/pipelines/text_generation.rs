@@ -19,6 +19,11 @@
 //! - OpenAI GPT
 //! - OpenAI GPT2
 //! - GPT-Neo
+//! - GPT-J
+//! - GPT-NeoX
+//! - LLaMA
+//! - OPT
+//! - BLOOM
 //! - XLNet
 //! - Reformer
 //!
@@ -33,10 +38,15 @@
 //! The dependencies will be downloaded to the user's home directory, e.g. under ~/.cache/.rustbert/gpt2
 use tch::Device;
 
+use crate::bloom::BloomGenerator;
 use crate::common::error::RustBertError;
 use crate::gpt2::GPT2Generator;
 use crate::gpt_neo::GptNeoGenerator;
+use crate::gpt_neox::GptNeoXGenerator;
+use crate::gptj::GptJGenerator;
+use crate::llama::LlamaGenerator;
 use crate::openai_gpt::OpenAIGenerator;
+use crate::opt::OptGenerator;
 use crate::pipelines::common::{ModelType, TokenizerOption};
 use crate::pipelines::generation_utils::private_generation_utils::PrivateLanguageGenerator;
 use crate::pipelines::generation_utils::{GenerateConfig, GenerateOptions, LanguageGenerator};
@@ -192,6 +202,16 @@ pub enum TextGenerationOption {
     GPT(OpenAIGenerator),
     /// Text Generator based on GPT-Neo model
     GPTNeo(GptNeoGenerator),
+    /// Text Generator based on GPT-J model
+    GPTJ(GptJGenerator),
+    /// Text Generator based on GPT-NeoX model
+    GPTNeoX(GptNeoXGenerator),
+    /// Text Generator based on LLaMA model
+    Llama(LlamaGenerator),
+    /// Text Generator based on OPT model
+    OPT(OptGenerator),
+    /// Text Generator based on BLOOM model
+    Bloom(BloomGenerator),
     /// Text Generator based on XLNet model
     XLNet(XLNetGenerator),
     /// Text Generator based on Reformer model
@@ -216,6 +236,19 @@ impl TextGenerationOption {
             ModelType::GPTNeo => Ok(TextGenerationOption::GPTNeo(GptNeoGenerator::new(
                 config.into(),
             )?)),
+            ModelType::GPTJ => Ok(TextGenerationOption::GPTJ(GptJGenerator::new(
+                config.into(),
+            )?)),
+            ModelType::GPTNeoX => Ok(TextGenerationOption::GPTNeoX(GptNeoXGenerator::new(
+                config.into(),
+            )?)),
+            ModelType::Llama => Ok(TextGenerationOption::Llama(LlamaGenerator::new(
+                config.into(),
+            )?)),
+            ModelType::OPT => Ok(TextGenerationOption::OPT(OptGenerator::new(config.into())?)),
+            ModelType::Bloom => Ok(TextGenerationOption::Bloom(BloomGenerator::new(
+                config.into(),
+            )?)),
             _ => Err(RustBertError::InvalidConfigurationError(format!(
                 "Text generation not implemented for {:?}!",
                 config.model_type
@@ -229,6 +262,11 @@ impl TextGenerationOption {
             Self::GPT(_) => ModelType::OpenAiGpt,
             Self::GPT2(_) => ModelType::GPT2,
             Self::GPTNeo(_) => ModelType::GPTNeo,
+            Self::GPTJ(_) => ModelType::GPTJ,
+            Self::GPTNeoX(_) => ModelType::GPTNeoX,
+            Self::Llama(_) => ModelType::Llama,
+            Self::OPT(_) => ModelType::OPT,
+            Self::Bloom(_) => ModelType::Bloom,
             Self::XLNet(_) => ModelType::XLNet,
             Self::Reformer(_) => ModelType::Reformer,
         }
@@ -240,6 +278,11 @@ impl TextGenerationOption {
             Self::GPT(model_ref) => model_ref._get_tokenizer(),
             Self::GPT2(model_ref) => model_ref._get_tokenizer(),
             Self::GPTNeo(model_ref) => model_ref._get_tokenizer(),
+            Self::GPTJ(model_ref) => model_ref._get_tokenizer(),
+            Self::GPTNeoX(model_ref) => model_ref._get_tokenizer(),
+            Self::Llama(model_ref) => model_ref._get_tokenizer(),
+            Self::OPT(model_ref) => model_ref._get_tokenizer(),
+            Self::Bloom(model_ref) => model_ref._get_tokenizer(),
             Self::XLNet(model_ref) => model_ref._get_tokenizer(),
             Self::Reformer(model_ref) => model_ref._get_tokenizer(),
         }
@@ -276,6 +319,31 @@ impl TextGenerationOption {
                 .into_iter()
                 .map(|output| output.indices)
                 .collect(),
+            Self::GPTJ(ref model) => model
+                .generate_indices(prompt_texts, generate_options)
+                .into_iter()
+                .map(|output| output.indices)
+                .collect(),
+            Self::GPTNeoX(ref model) => model
+                .generate_indices(prompt_texts, generate_options)
+                .into_iter()
+                .map(|output| output.indices)
+                .collect(),
+            Self::Llama(ref model) => model
+                .generate_indices(prompt_texts, generate_options)
+                .into_iter()
+                .map(|output| output.indices)
+                .collect(),
+            Self::OPT(ref model) => model
+                .generate_indices(prompt_texts, generate_options)
+                .into_iter()
+                .map(|output| output.indices)
+                .collect(),
+            Self::Bloom(ref model) => model
+                .generate_indices(prompt_texts, generate_options)
+                .into_iter()
+                .map(|output| output.indices)
+                .collect(),
             Self::XLNet(ref model) => model
                 .generate_indices(prompt_texts, generate_options)
                 .into_iter()
@@ -294,6 +362,11 @@ impl TextGenerationOption {
             Self::GPT(model_ref) => model_ref.half(),
             Self::GPT2(model_ref) => model_ref.half(),
             Self::GPTNeo(model_ref) => model_ref.half(),
+            Self::GPTJ(model_ref) => model_ref.half(),
+            Self::GPTNeoX(model_ref) => model_ref.half(),
+            Self::Llama(model_ref) => model_ref.half(),
+            Self::OPT(model_ref) => model_ref.half(),
+            Self::Bloom(model_ref) => model_ref.half(),
             Self::XLNet(model_ref) => model_ref.half(),
             Self::Reformer(model_ref) => model_ref.half(),
         }
@@ -304,6 +377,11 @@ impl TextGenerationOption {
             Self::GPT(model_ref) => model_ref.float(),
             Self::GPT2(model_ref) => model_ref.float(),
             Self::GPTNeo(model_ref) => model_ref.float(),
+            Self::GPTJ(model_ref) => model_ref.float(),
+            Self::GPTNeoX(model_ref) => model_ref.float(),
+            Self::Llama(model_ref) => model_ref.float(),
+            Self::OPT(model_ref) => model_ref.float(),
+            Self::Bloom(model_ref) => model_ref.float(),
             Self::XLNet(model_ref) => model_ref.float(),
             Self::Reformer(model_ref) => model_ref.float(),
         }
@@ -314,6 +392,11 @@ impl TextGenerationOption {
             Self::GPT(model_ref) => model_ref.set_device(device),
             Self::GPT2(model_ref) => model_ref.set_device(device),
             Self::GPTNeo(model_ref) => model_ref.set_device(device),
+            Self::GPTJ(model_ref) => model_ref.set_device(device),
+            Self::GPTNeoX(model_ref) => model_ref.set_device(device),
+            Self::Llama(model_ref) => model_ref.set_device(device),
+            Self::OPT(model_ref) => model_ref.set_device(device),
+            Self::Bloom(model_ref) => model_ref.set_device(device),
             Self::XLNet(model_ref) => model_ref.set_device(device),
             Self::Reformer(model_ref) => model_ref.set_device(device),
         }
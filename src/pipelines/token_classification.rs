@@ -128,7 +128,6 @@ use ordered_float::OrderedFloat;
 use rust_tokenizers::tokenizer::Tokenizer;
 use rust_tokenizers::{
     ConsolidatableTokens, ConsolidatedTokenIterator, Mask, Offset, TokenIdsWithOffsets, TokenTrait,
-    TokenizedInput,
 };
 use serde::{Deserialize, Serialize};
 use std::borrow::Borrow;
@@ -197,6 +196,8 @@ struct InputFeature {
     mask: Vec<Mask>,
     /// per-token flag indicating if this feature carries the output label for this token
     reference_feature: Vec<bool>,
+    /// Position of each token in the full (unwindowed) example, `None` for special tokens
+    token_positions: Vec<Option<usize>>,
     /// Reference example index (long inputs may be broken into multiple input features)
     example_index: usize,
 }
@@ -216,6 +217,16 @@ pub enum LabelAggregationOption {
     Custom(LabelAggregationFunction),
 }
 
+/// # Enum defining how predictions are resolved for tokens shared by overlapping windows
+/// Long inputs are split into overlapping windows, tokens in the overlap receive a prediction from each window.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum OverlapResolution {
+    /// The prediction of the window offering the most context on both sides of the token is kept
+    CenterMost,
+    /// The prediction with the highest score across windows is kept
+    HighestScore,
+}
+
 /// # Configuration for TokenClassificationModel
 /// Contains information regarding the model to load and device to place the model on.
 pub struct TokenClassificationConfig {
@@ -241,6 +252,12 @@ pub struct TokenClassificationConfig {
     pub label_aggregation_function: LabelAggregationOption,
     /// Batch size for predictions
     pub batch_size: usize,
+    /// Size of the windows long inputs are split into, in tokens including special tokens (default: model maximum input length)
+    pub max_length: Option<usize>,
+    /// Number of overlapping tokens between consecutive windows (default: a quarter of the window size)
+    pub stride: Option<usize>,
+    /// Resolution of the predictions for tokens appearing in several windows (default: `OverlapResolution::CenterMost`)
+    pub overlap_resolution: OverlapResolution,
 }
 
 impl TokenClassificationConfig {
@@ -282,6 +299,9 @@ impl TokenClassificationConfig {
             device: Device::cuda_if_available(),
             label_aggregation_function,
             batch_size: 64,
+            max_length: None,
+            stride: None,
+            overlap_resolution: OverlapResolution::CenterMost,
         }
     }
 }
@@ -516,126 +536,160 @@ impl TokenClassificationOption {
         input_embeds: Option<&Tensor>,
         train: bool,
     ) -> Tensor {
+        self.forward_t_with_labels(
+            input_ids,
+            mask,
+            token_type_ids,
+            position_ids,
+            input_embeds,
+            None,
+            train,
+        )
+        .0
+    }
+
+    /// Interface method to forward_t() of the particular models, computing the loss if labels are provided.
+    /// Returns the logits and the optional loss.
+    pub fn forward_t_with_labels(
+        &self,
+        input_ids: Option<&Tensor>,
+        mask: Option<&Tensor>,
+        token_type_ids: Option<&Tensor>,
+        position_ids: Option<&Tensor>,
+        input_embeds: Option<&Tensor>,
+        labels: Option<&Tensor>,
+        train: bool,
+    ) -> (Tensor, Option<Tensor>) {
         match *self {
             Self::Bert(ref model) => {
-                model
-                    .forward_t(
-                        input_ids,
-                        mask,
-                        token_type_ids,
-                        position_ids,
-                        input_embeds,
-                        train,
-                    )
-                    .logits
+                let output = model.forward_t(
+                    input_ids,
+                    mask,
+                    token_type_ids,
+                    position_ids,
+                    input_embeds,
+                    labels,
+                    train,
+                );
+                (output.logits, output.loss)
             }
             Self::Deberta(ref model) => {
-                model
+                let output = model
                     .forward_t(
                         input_ids,
                         mask,
                         token_type_ids,
                         position_ids,
                         input_embeds,
+                        labels,
                         train,
                     )
-                    .expect("Error in DeBERTa forward_t")
-                    .logits
+                    .expect("Error in DeBERTa forward_t");
+                (output.logits, output.loss)
             }
             Self::DebertaV2(ref model) => {
-                model
+                let output = model
                     .forward_t(
                         input_ids,
                         mask,
                         token_type_ids,
                         position_ids,
                         input_embeds,
+                        labels,
                         train,
                     )
-                    .expect("Error in DeBERTa V2 forward_t")
-                    .logits
+                    .expect("Error in DeBERTa V2 forward_t");
+                (output.logits, output.loss)
             }
             Self::DistilBert(ref model) => {
-                model
-                    .forward_t(input_ids, mask, input_embeds, train)
-                    .expect("Error in distilbert forward_t")
-                    .logits
+                let output = model
+                    .forward_t(input_ids, mask, input_embeds, labels, train)
+                    .expect("Error in distilbert forward_t");
+                (output.logits, output.loss)
             }
             Self::MobileBert(ref model) => {
-                model
-                    .forward_t(input_ids, None, None, input_embeds, mask, train)
-                    .expect("Error in mobilebert forward_t")
-                    .logits
+                let output = model
+                    .forward_t(input_ids, None, None, input_embeds, mask, labels, train)
+                    .expect("Error in mobilebert forward_t");
+                (output.logits, output.loss)
             }
             Self::Roberta(ref model) | Self::XLMRoberta(ref model) => {
-                model
-                    .forward_t(
-                        input_ids,
-                        mask,
-                        token_type_ids,
-                        position_ids,
-                        input_embeds,
-                        train,
-                    )
-                    .logits
+                let output = model.forward_t(
+                    input_ids,
+                    mask,
+                    token_type_ids,
+                    position_ids,
+                    input_embeds,
+                    labels,
+                    train,
+                );
+                (output.logits, output.loss)
             }
             Self::Electra(ref model) => {
-                model
-                    .forward_t(
-                        input_ids,
-                        mask,
-                        token_type_ids,
-                        position_ids,
-                        input_embeds,
-                        train,
-                    )
-                    .logits
+                let output = model.forward_t(
+                    input_ids,
+                    mask,
+                    token_type_ids,
+                    position_ids,
+                    input_embeds,
+                    labels,
+                    train,
+                );
+                (output.logits, output.loss)
             }
             Self::Albert(ref model) => {
-                model
-                    .forward_t(
-                        input_ids,
-                        mask,
-                        token_type_ids,
-                        position_ids,
-                        input_embeds,
-                        train,
-                    )
-                    .logits
+                let output = model.forward_t(
+                    input_ids,
+                    mask,
+                    token_type_ids,
+                    position_ids,
+                    input_embeds,
+                    labels,
+                    train,
+                );
+                (output.logits, output.loss)
             }
             Self::XLNet(ref model) => {
-                model
+                let output = model.forward_t(
+                    input_ids,
+                    mask,
+                    None,
+                    None,
+                    None,
+                    token_type_ids,
+                    input_embeds,
+                    labels,
+                    train,
+                );
+                (output.logits, output.loss)
+            }
+            Self::Longformer(ref model) => {
+                let output = model
                     .forward_t(
                         input_ids,
                         mask,
                         None,
-                        None,
-                        None,
                         token_type_ids,
+                        position_ids,
                         input_embeds,
+                        labels,
                         train,
                     )
-                    .logits
+                    .expect("Error in longformer forward_t");
+                (output.logits, output.loss)
             }
-            Self::Longformer(ref model) => {
-                model
+            Self::FNet(ref model) => {
+                let output = model
                     .forward_t(
                         input_ids,
-                        mask,
-                        None,
                         token_type_ids,
                         position_ids,
                         input_embeds,
+                        labels,
                         train,
                     )
-                    .expect("Error in longformer forward_t")
-                    .logits
-            }
-            Self::FNet(ref model) => {
-                model
-                    .forward_t(input_ids, token_type_ids, position_ids, input_embeds, train)
-                    .expect("Error in fnet forward_t")
-                    .logits
+                    .expect("Error in fnet forward_t");
+                (output.logits, output.loss)
             }
         }
     }
@@ -649,6 +703,8 @@ pub struct TokenClassificationModel {
     var_store: VarStore,
     label_aggregation_function: LabelAggregationOption,
     max_length: usize,
+    stride: usize,
+    overlap_resolution: OverlapResolution,
     batch_size: usize,
 }
 
@@ -693,10 +749,34 @@ impl TokenClassificationModel {
         )?;
         let mut var_store = VarStore::new(device);
         let model_config = ConfigOption::from_file(config.model_type, config_path);
-        let max_length = model_config
+        let model_max_length = model_config
             .get_max_len()
             .map(|v| v as usize)
             .unwrap_or(usize::MAX);
+        let max_length = config.max_length.unwrap_or(model_max_length);
+        if max_length > model_max_length {
+            return Err(RustBertError::InvalidConfigurationError(format!(
+                "Window size ({max_length}) exceeds the maximum input length of the model ({model_max_length})"
+            )));
+        }
+        let stride = config.stride.unwrap_or(max_length / 4);
+        let sequence_added_tokens = tokenizer
+            .build_input_with_special_tokens(
+                TokenIdsWithOffsets {
+                    ids: vec![],
+                    offsets: vec![],
+                    reference_offsets: vec![],
+                    masks: vec![],
+                },
+                None,
+            )
+            .token_ids
+            .len();
+        if max_length <= sequence_added_tokens + stride {
+            return Err(RustBertError::InvalidConfigurationError(format!(
+                "Window size ({max_length}) must exceed the stride ({stride}) and the number of special tokens ({sequence_added_tokens})"
+            )));
+        }
         let token_sequence_classifier =
             TokenClassificationOption::new(config.model_type, var_store.root(), &model_config)?;
         let label_mapping = model_config.get_label_mapping().clone();
@@ -709,6 +789,8 @@ impl TokenClassificationModel {
             var_store,
             label_aggregation_function,
             max_length,
+            stride,
+            overlap_resolution: config.overlap_resolution,
             batch_size,
         })
     }
@@ -717,117 +799,14 @@ impl TokenClassificationModel {
     where
         S: AsRef<str>,
     {
-        let tokenized_input = self.tokenizer.tokenize_with_offsets(input.as_ref());
-        let encoded_input = TokenIdsWithOffsets {
-            ids: self
-                .tokenizer
-                .convert_tokens_to_ids(&tokenized_input.tokens),
-            offsets: tokenized_input.offsets,
-            reference_offsets: tokenized_input.reference_offsets,
-            masks: tokenized_input.masks,
-        };
-
-        let sequence_added_tokens = self
-            .tokenizer
-            .build_input_with_special_tokens(
-                TokenIdsWithOffsets {
-                    ids: vec![],
-                    offsets: vec![],
-                    reference_offsets: vec![],
-                    masks: vec![],
-                },
-                None,
-            )
-            .token_ids
-            .len();
-
-        let max_content_length = self.max_length - sequence_added_tokens;
-        let doc_stride = self.max_length / 4;
-
-        let mut spans: Vec<InputFeature> = vec![];
-        let mut start_token = 0_usize;
-        let total_length = encoded_input.ids.len();
-
-        while (spans.len() * doc_stride) < encoded_input.ids.len() {
-            let end_token = min(start_token + max_content_length, total_length);
-            let sub_encoded_input = TokenIdsWithOffsets {
-                ids: encoded_input.ids[start_token..end_token].to_vec(),
-                offsets: encoded_input.offsets[start_token..end_token].to_vec(),
-                reference_offsets: encoded_input.reference_offsets[start_token..end_token].to_vec(),
-                masks: encoded_input.masks[start_token..end_token].to_vec(),
-            };
-
-            let encoded_span = self
-                .tokenizer
-                .build_input_with_special_tokens(sub_encoded_input, None);
-
-            let reference_feature = self.get_reference_feature_flag(
-                start_token,
-                end_token,
-                total_length,
-                doc_stride,
-                &encoded_span,
-            );
-
-            let feature = InputFeature {
-                input_ids: encoded_span.token_ids,
-                offsets: encoded_span.token_offsets,
-                mask: encoded_span.mask,
-                reference_feature,
-                example_index,
-            };
-            spans.push(feature);
-            if end_token == encoded_input.ids.len() {
-                break;
-            }
-            start_token = end_token - doc_stride;
-        }
-        spans
-    }
-
-    fn get_reference_feature_flag(
-        &self,
-        start_token: usize,
-        end_token: usize,
-        total_length: usize,
-        doc_stride: usize,
-        encoded_span: &TokenizedInput,
-    ) -> Vec<bool> {
-        // set halfway through the doc_stride to be false if the feature is not the first/last
-        let start_cutoff = if start_token > 0 {
-            let leading_special_tokens = {
-                let mut counter = 0;
-                let mut masks = encoded_span.mask.iter();
-                while masks.next().unwrap_or(&Mask::None) == &Mask::Special {
-                    counter += 1;
-                }
-                counter
-            };
-            doc_stride / 2 + leading_special_tokens
-        } else {
-            0
-        };
-        let end_cutoff = if end_token < total_length {
-            let trailing_special_tokens = {
-                let mut counter = 0;
-                let mut masks = encoded_span.mask.iter().rev();
-                while masks.next().unwrap_or(&Mask::None) == &Mask::Special {
-                    counter += 1;
-                }
-                counter
-            };
-            encoded_span.token_ids.len() - doc_stride / 2 - trailing_special_tokens
-        } else {
-            encoded_span.token_ids.len()
-        };
-        let mut reference_feature = vec![true; encoded_span.token_ids.len()];
-        reference_feature[..start_cutoff]
-            .iter_mut()
-            .for_each(|v| *v = false);
-        reference_feature[end_cutoff..]
-            .iter_mut()
-            .for_each(|v| *v = false);
-        reference_feature
+        generate_features(
+            &self.tokenizer,
+            input.as_ref(),
+            example_index,
+            self.max_length,
+            self.stride,
+            self.overlap_resolution,
+        )
     }
 
     /// Classify tokens in a text sequence
@@ -863,6 +842,70 @@ impl TokenClassificationModel {
         consolidate_sub_tokens: bool,
         return_special: bool,
     ) -> Vec<Vec<Token>>
+    where
+        S: AsRef<str>,
+    {
+        let (mut tokens, _) = self.predict_tokens(input, return_special, false);
+
+        if consolidate_sub_tokens {
+            self.consolidate_tokens(&mut tokens, &self.label_aggregation_function);
+        }
+        tokens
+    }
+
+    /// Classify sub-tokens in a text sequence, returning the probability of every label along with each sub-token.
+    /// Can be used to implement custom aggregation strategies over the full label distribution.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - `&[&str]` Array of texts to extract entities from.
+    /// * `return_special` - bool flag indicating if labels for special tokens should be returned
+    ///
+    /// # Returns
+    ///
+    /// * `Vec<Vec<(Token, Vec<f64>)>>` containing sub-tokens and their label probabilities (indexed by label id) for each input provided
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// # use rust_bert::pipelines::token_classification::TokenClassificationModel;
+    ///
+    /// let ner_model = TokenClassificationModel::new(Default::default())?;
+    /// let input = ["My name is Amy. I live in Paris."];
+    /// let output = ner_model.predict_label_scores(&input, false);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn predict_label_scores<S>(
+        &self,
+        input: &[S],
+        return_special: bool,
+    ) -> Vec<Vec<(Token, Vec<f64>)>>
+    where
+        S: AsRef<str>,
+    {
+        let (tokens, label_scores) = self.predict_tokens(input, return_special, true);
+        tokens
+            .into_iter()
+            .zip(label_scores)
+            .map(|(sequence_tokens, sequence_scores)| {
+                sequence_tokens.into_iter().zip(sequence_scores).collect()
+            })
+            .collect()
+    }
+
+    /// Returns the mapping from label indices to label names of the model
+    pub fn get_label_mapping(&self) -> &HashMap<i64, String> {
+        &self.label_mapping
+    }
+
+    fn predict_tokens<S>(
+        &self,
+        input: &[S],
+        return_special: bool,
+        return_label_scores: bool,
+    ) -> (Vec<Vec<Token>>, Vec<Vec<Vec<f64>>>)
     where
         S: AsRef<str>,
     {
@@ -872,7 +915,9 @@ impl TokenClassificationModel {
             .flat_map(|(example_index, example)| self.generate_features(example, example_index))
             .collect();
 
-        let mut example_tokens_map: Vec<Vec<Token>> = vec![Vec::new(); input.len()];
+        let mut example_tokens_map: Vec<Vec<(Token, Vec<f64>)>> = vec![Vec::new(); input.len()];
+        let mut example_positions_map: Vec<HashMap<usize, usize>> =
+            vec![HashMap::new(); input.len()];
         let mut start = 0usize;
         let len_features = features.len();
 
@@ -897,13 +942,14 @@ impl TokenClassificationModel {
                 let label_indices = score.argmax(-1, true);
                 for sentence_idx in 0..label_indices.size()[0] {
                     let labels = label_indices.get(sentence_idx);
-                    let feature = &features[sentence_idx as usize];
+                    let feature = &features[start + sentence_idx as usize];
                     let sentence_reference_flag = &feature.reference_feature;
                     let original_chars = input[feature.example_index]
                         .as_ref()
                         .chars()
                         .collect::<Vec<char>>();
-                    let mut word_idx: u16 = 0;
+                    let example_tokens = &mut example_tokens_map[feature.example_index];
+                    let example_positions = &mut example_positions_map[feature.example_index];
                     for position_idx in sentence_reference_flag
                         .iter()
                         .enumerate()
@@ -914,33 +960,61 @@ impl TokenClassificationModel {
                         if (mask == Mask::Special) & (!return_special) {
                             continue;
                         }
-                        if !(mask == Mask::Continuation) {
-                            word_idx += 1;
-                        }
-                        let token = {
-                            self.decode_token(
-                                &original_chars,
-                                feature,
-                                &input_ids,
-                                &labels,
-                                &score,
-                                sentence_idx,
-                                position_idx as i64,
-                                word_idx,
-                            )
+                        let token = self.decode_token(
+                            &original_chars,
+                            feature,
+                            &input_ids,
+                            &labels,
+                            &score,
+                            sentence_idx,
+                            position_idx as i64,
+                            0,
+                        );
+                        let label_scores = if return_label_scores {
+                            Vec::<f64>::from(score.get(sentence_idx).get(position_idx as i64))
+                        } else {
+                            vec![]
                         };
-                        example_tokens_map[feature.example_index].push(token);
+                        match feature.token_positions[position_idx]
+                            .and_then(|token_position| example_positions.get(&token_position))
+                        {
+                            Some(&previous_index) => {
+                                if token.score > example_tokens[previous_index].0.score {
+                                    example_tokens[previous_index] = (token, label_scores);
+                                }
+                            }
+                            None => {
+                                if let Some(token_position) = feature.token_positions[position_idx]
+                                {
+                                    example_positions.insert(token_position, example_tokens.len());
+                                }
+                                example_tokens.push((token, label_scores));
+                            }
+                        }
                     }
                 }
             });
             start = end;
         }
-        let mut tokens = example_tokens_map;
 
-        if consolidate_sub_tokens {
-            self.consolidate_tokens(&mut tokens, &self.label_aggregation_function);
+        let mut tokens = Vec::with_capacity(input.len());
+        let mut label_scores = Vec::with_capacity(input.len());
+        for example_tokens in example_tokens_map {
+            let mut word_idx: u16 = 0;
+            let (sequence_tokens, sequence_scores): (Vec<Token>, Vec<Vec<f64>>) = example_tokens
+                .into_iter()
+                .map(|(mut token, token_scores)| {
+                    if token.mask != Mask::Continuation {
+                        word_idx += 1;
+                    }
+                    token.word_index = word_idx;
+                    (token, token_scores)
+                })
+                .unzip();
+            tokens.push(sequence_tokens);
+            label_scores.push(sequence_scores);
         }
-        tokens
+        (tokens, label_scores)
     }
 
     fn pad_features(&self, features: &mut [InputFeature]) -> (Tensor, Tensor) {
@@ -970,6 +1044,7 @@ impl TokenClassificationModel {
             feature.input_ids.resize(max_len, padding_index);
             feature.offsets.resize(max_len, None);
             feature.reference_feature.resize(max_len, false);
+            feature.token_positions.resize(max_len, None);
         }
 
         let padded_input_ids = features
@@ -1023,6 +1098,7 @@ impl TokenClassificationModel {
             Some(offsets) => {
                 let (start_char, end_char) = (offsets.begin as usize, offsets.end as usize);
                 let end_char = min(end_char, original_sentence_chars.len());
+                let start_char = min(start_char, end_char);
                 let text = original_sentence_chars[start_char..end_char]
                     .iter()
                     .collect();
@@ -1147,3 +1223,296 @@ impl TokenClassificationModel {
         }
     }
 }
+
+/// Splits a sequence of `total_length` tokens into windows of at most `max_content_length` tokens,
+/// consecutive windows overlapping by `stride` tokens.
+fn get_windows(
+    total_length: usize,
+    max_content_length: usize,
+    stride: usize,
+) -> Vec<(usize, usize)> {
+    let mut windows = vec![];
+    let mut start_token = 0_usize;
+    while start_token < total_length {
+        let end_token = min(start_token.saturating_add(max_content_length), total_length);
+        windows.push((start_token, end_token));
+        if end_token == total_length {
+            break;
+        }
+        start_token = end_token - stride;
+    }
+    windows
+}
+
+/// Returns for each token the index of the window offering the most context around it.
+/// The edges of the sequence are not counted as missing context.
+fn get_center_most_windows(windows: &[(usize, usize)], total_length: usize) -> Vec<usize> {
+    let mut reference_windows = vec![0; total_length];
+    let mut best_context: Vec<Option<usize>> = vec![None; total_length];
+    for (window_index, &(start_token, end_token)) in windows.iter().enumerate() {
+        for token_position in start_token..end_token {
+            let left_context = if start_token > 0 {
+                token_position - start_token
+            } else {
+                usize::MAX
+            };
+            let right_context = if end_token < total_length {
+                end_token - 1 - token_position
+            } else {
+                usize::MAX
+            };
+            let context = min(left_context, right_context);
+            // `None` (token not seen in a previous window) compares lower than any context
+            if Some(context) > best_context[token_position] {
+                reference_windows[token_position] = window_index;
+                best_context[token_position] = Some(context);
+            }
+        }
+    }
+    reference_windows
+}
+
+fn generate_features(
+    tokenizer: &TokenizerOption,
+    input: &str,
+    example_index: usize,
+    max_length: usize,
+    stride: usize,
+    overlap_resolution: OverlapResolution,
+) -> Vec<InputFeature> {
+    let tokenized_input = tokenizer.tokenize_with_offsets(input);
+    // Offsets are expressed in characters (unicode scalar values) of the original input
+    let num_chars = input.chars().count() as u32;
+    let offsets = tokenized_input
+        .offsets
+        .into_iter()
+        .map(|offset| {
+            offset.map(|offset| {
+                let end = offset.end.min(num_chars);
+                Offset::new(offset.begin.min(end), end)
+            })
+        })
+        .collect::<Vec<Option<Offset>>>();
+    let encoded_input = TokenIdsWithOffsets {
+        ids: tokenizer.convert_tokens_to_ids(&tokenized_input.tokens),
+        offsets,
+        reference_offsets: tokenized_input.reference_offsets,
+        masks: tokenized_input.masks,
+    };
+
+    let sequence_added_tokens = tokenizer
+        .build_input_with_special_tokens(
+            TokenIdsWithOffsets {
+                ids: vec![],
+                offsets: vec![],
+                reference_offsets: vec![],
+                masks: vec![],
+            },
+            None,
+        )
+        .token_ids
+        .len();
+
+    let max_content_length = max_length - sequence_added_tokens;
+    let total_length = encoded_input.ids.len();
+    let windows = get_windows(total_length, max_content_length, stride);
+    let reference_windows = get_center_most_windows(&windows, total_length);
+
+    let mut spans: Vec<InputFeature> = Vec::with_capacity(windows.len());
+    for (window_index, &(start_token, end_token)) in windows.iter().enumerate() {
+        let sub_encoded_input = TokenIdsWithOffsets {
+            ids: encoded_input.ids[start_token..end_token].to_vec(),
+            offsets: encoded_input.offsets[start_token..end_token].to_vec(),
+            reference_offsets: encoded_input.reference_offsets[start_token..end_token].to_vec(),
+            masks: encoded_input.masks[start_token..end_token].to_vec(),
+        };
+
+        let encoded_span = tokenizer.build_input_with_special_tokens(sub_encoded_input, None);
+
+        let leading_special_tokens = encoded_span
+            .mask
+            .iter()
+            .take_while(|mask| **mask == Mask::Special)
+            .count();
+        let content_length = end_token - start_token;
+        let is_last_window = window_index == windows.len() - 1;
+        let mut token_positions = Vec::with_capacity(encoded_span.token_ids.len());
+        let mut reference_feature = Vec::with_capacity(encoded_span.token_ids.len());
+        for position in 0..encoded_span.token_ids.len() {
+            if position < leading_special_tokens {
+                // leading special tokens are reported once, from the first window
+                token_positions.push(None);
+                reference_feature.push(window_index == 0);
+            } else if position >= leading_special_tokens + content_length {
+                // trailing special tokens are reported once, from the last window
+                token_positions.push(None);
+                reference_feature.push(is_last_window);
+            } else {
+                let token_position = start_token + position - leading_special_tokens;
+                token_positions.push(Some(token_position));
+                reference_feature.push(match overlap_resolution {
+                    OverlapResolution::CenterMost => {
+                        reference_windows[token_position] == window_index
+                    }
+                    OverlapResolution::HighestScore => true,
+                });
+            }
+        }
+
+        spans.push(InputFeature {
+            input_ids: encoded_span.token_ids,
+            offsets: encoded_span.token_offsets,
+            mask: encoded_span.mask,
+            reference_feature,
+            token_positions,
+            example_index,
+        });
+    }
+    spans
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use proptest::prelude::*;
+    use std::io::Write;
+
+    #[test]
+    #[ignore] // no need to run, compilation is enough to verify it is Send
+    fn test() {
+        let config = TokenClassificationConfig::default();
+        let _: Box<dyn Send> = Box::new(TokenClassificationModel::new(config));
+    }
+
+    fn build_tokenizer() -> TokenizerOption {
+        let mut vocab_file = tempfile::NamedTempFile::new().unwrap();
+        for token in [
+            "[PAD]", "[UNK]", "[CLS]", "[SEP]", "[MASK]", "hello", "world", "am", "##é", "##lie",
+            "e", "a", "b", "c", "##a", "##b", "##c", ".", ",",
+        ] {
+            writeln!(vocab_file, "{token}").unwrap();
+        }
+        TokenizerOption::from_file(
+            ModelType::Bert,
+            vocab_file.path().to_str().unwrap(),
+            None,
+            false,
+            None,
+            None,
+        )
+        .unwrap()
+    }
+
+    fn text_strategy() -> impl Strategy<Value = String> {
+        prop::collection::vec(
+            prop_oneof![
+                Just("hello".to_string()),
+                Just("world".to_string()),
+                Just("Amélie".to_string()),
+                Just("😀".to_string()),
+                Just("👩‍👩‍👧".to_string()),
+                Just("e\u{301}".to_string()),
+                Just("\r\n".to_string()),
+                Just(" ".to_string()),
+                Just(".".to_string()),
+                "[abc]{1,6}",
+            ],
+            0..48,
+        )
+        .prop_map(|fragments| fragments.concat())
+    }
+
+    proptest! {
+        #[test]
+        fn windows_cover_sequence(
+            total_length in 0usize..200,
+            max_content_length in 1usize..32,
+            stride_fraction in 0.0f64..1.0,
+        ) {
+            let stride = (stride_fraction * max_content_length as f64) as usize;
+            let windows = get_windows(total_length, max_content_length, stride);
+            let reference_windows = get_center_most_windows(&windows, total_length);
+            for (token_position, &window_index) in reference_windows.iter().enumerate() {
+                let (start_token, end_token) = windows[window_index];
+                prop_assert!((start_token..end_token).contains(&token_position));
+            }
+            if let (Some(first), Some(last)) = (windows.first(), windows.last()) {
+                prop_assert_eq!(first.0, 0);
+                prop_assert_eq!(last.1, total_length);
+            }
+            for pair in windows.windows(2) {
+                prop_assert_eq!(pair[0].1 - pair[1].0, stride);
+            }
+        }
+
+        #[test]
+        fn features_map_to_original_characters(
+            text in text_strategy(),
+            max_length in 6usize..24,
+            stride_fraction in 0.0f64..1.0,
+            highest_score in any::<bool>(),
+        ) {
+            let tokenizer = build_tokenizer();
+            let max_content_length = max_length - 2;
+            let stride = (stride_fraction * max_content_length as f64) as usize;
+            let overlap_resolution = if highest_score {
+                OverlapResolution::HighestScore
+            } else {
+                OverlapResolution::CenterMost
+            };
+            let features =
+                generate_features(&tokenizer, &text, 0, max_length, stride, overlap_resolution);
+            let chars = text.chars().collect::<Vec<char>>();
+            let tokens = tokenizer.tokenize_with_offsets(&text).tokens;
+            let total_length = tokens.len();
+
+            let mut reference_counts = vec![0; total_length];
+            let mut previous_begin = 0;
+            for feature in features.iter() {
+                prop_assert!(feature.input_ids.len() <= max_length);
+                for (position, token_position) in feature.token_positions.iter().enumerate() {
+                    let offset = feature.offsets[position];
+                    match token_position {
+                        Some(token_position) => {
+                            let offset = offset.expect("content tokens have offsets");
+                            prop_assert!(offset.begin <= offset.end);
+                            prop_assert!(offset.end as usize <= chars.len());
+                            // The offsets point to the characters of the token in the original text
+                            let token = &tokens[*token_position];
+                            prop_assert_eq!(
+                                feature.input_ids[position],
+                                tokenizer.convert_tokens_to_ids(&[token])[0]
+                            );
+                            let original_text = chars[offset.begin as usize..offset.end as usize]
+                                .iter()
+                                .collect::<String>();
+                            if token == "[UNK]" {
+                                prop_assert!(!original_text.is_empty());
+                                prop_assert!(!original_text.contains(char::is_whitespace));
+                            } else {
+                                prop_assert_eq!(
+                                    original_text.as_str(),
+                                    token.strip_prefix("##").unwrap_or(token)
+                                );
+                            }
+                            if feature.reference_feature[position] {
+                                reference_counts[*token_position] += 1;
+                                if overlap_resolution == OverlapResolution::CenterMost {
+                                    prop_assert!(offset.begin >= previous_begin);
+                                    previous_begin = offset.begin;
+                                }
+                            }
+                        }
+                        None => prop_assert_eq!(feature.mask[position], Mask::Special),
+                    }
+                }
+            }
+            for count in reference_counts {
+                match overlap_resolution {
+                    OverlapResolution::CenterMost => prop_assert_eq!(count, 1),
+                    OverlapResolution::HighestScore => prop_assert!(count >= 1),
+                }
+            }
+        }
+    }
+}
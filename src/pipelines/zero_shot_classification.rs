@@ -21,6 +21,10 @@
 //! - `predict` performs single-class classification (one and exactly one label must be true for each provided input)
 //! - `predict_multilabel` performs multi-label classification (zero, one or more labels may be true for each provided input)
 //!
+//! The (input, label hypothesis) pairs are processed in batches of `batch_size` pairs, and the tokenized hypotheses are cached
+//! across calls (keeping the `hypothesis_cache_size` most recently used hypotheses). For large label sets, `predict_with_prefilter` and `predict_multilabel_with_prefilter` first select the
+//! most similar labels for each input using a `SentenceEmbeddingsModel`, and only run the inference model on these.
+//!
 //! ```no_run
 //! # use rust_bert::pipelines::zero_shot_classification::ZeroShotClassificationModel;
 //! # fn main() -> anyhow::Result<()> {
@@ -106,15 +110,17 @@ use crate::distilbert::DistilBertModelClassifier;
 use crate::longformer::LongformerForSequenceClassification;
 use crate::mobilebert::MobileBertForSequenceClassification;
 use crate::pipelines::common::{ConfigOption, ModelType, TokenizerOption};
+use crate::pipelines::sentence_embeddings::SentenceEmbeddingsModel;
 use crate::pipelines::sequence_classification::Label;
 use crate::resources::ResourceProvider;
 use crate::roberta::RobertaForSequenceClassification;
 use crate::xlnet::XLNetForSequenceClassification;
 use crate::RustBertError;
-use rust_tokenizers::tokenizer::TruncationStrategy;
-use rust_tokenizers::TokenizedInput;
+use rust_tokenizers::tokenizer::{truncate_sequences, TruncationStrategy};
+use rust_tokenizers::TokenIdsWithOffsets;
 use std::borrow::Borrow;
-use std::ops::Deref;
+use std::collections::HashMap;
+use std::sync::Mutex;
 use tch::kind::Kind::{Bool, Float};
 use tch::nn::VarStore;
 use tch::{nn, no_grad, Device, Tensor};
@@ -146,6 +152,11 @@ pub struct ZeroShotClassificationConfig {
     pub add_prefix_space: Option<bool>,
     /// Device to place the model on (default: CUDA/GPU when available)
     pub device: Device,
+    /// Maximum number of (input, label hypothesis) pairs processed in a single forward pass (default: 64)
+    pub batch_size: usize,
+    /// Maximum number of tokenized label hypotheses cached across calls, the least recently used
+    /// hypotheses being evicted first (default: 1024)
+    pub hypothesis_cache_size: usize,
 }
 
 impl ZeroShotClassificationConfig {
@@ -184,6 +195,8 @@ impl ZeroShotClassificationConfig {
             strip_accents: strip_accents.into(),
             add_prefix_space: add_prefix_space.into(),
             device: Device::cuda_if_available(),
+            batch_size: 64,
+            hypothesis_cache_size: 1024,
         }
     }
 }
@@ -210,6 +223,8 @@ impl Default for ZeroShotClassificationConfig {
             strip_accents: None,
             add_prefix_space: None,
             device: Device::cuda_if_available(),
+            batch_size: 64,
+            hypothesis_cache_size: 1024,
         }
     }
 }
@@ -410,6 +425,7 @@ impl ZeroShotClassificationOption {
                         None,
                         None,
                         None,
+                        None,
                         train,
                     )
                     .decoder_output
@@ -422,6 +438,7 @@ impl ZeroShotClassificationOption {
                         token_type_ids,
                         position_ids,
                         input_embeds,
+                        None,
                         train,
                     )
                     .logits
@@ -434,6 +451,7 @@ impl ZeroShotClassificationOption {
                         token_type_ids,
                         position_ids,
                         input_embeds,
+                        None,
                         train,
                     )
                     .expect("Error in DeBERTa forward_t")
@@ -441,13 +459,13 @@ impl ZeroShotClassificationOption {
             }
             Self::DistilBert(ref model) => {
                 model
-                    .forward_t(input_ids, mask, input_embeds, train)
+                    .forward_t(input_ids, mask, input_embeds, None, train)
                     .expect("Error in distilbert forward_t")
                     .logits
             }
             Self::MobileBert(ref model) => {
                 model
-                    .forward_t(input_ids, None, None, input_embeds, mask, train)
+                    .forward_t(input_ids, None, None, input_embeds, mask, None, train)
                     .expect("Error in mobilebert forward_t")
                     .logits
             }
@@ -459,6 +477,7 @@ impl ZeroShotClassificationOption {
                         token_type_ids,
                         position_ids,
                         input_embeds,
+                        None,
                         train,
                     )
                     .logits
@@ -471,6 +490,7 @@ impl ZeroShotClassificationOption {
                         token_type_ids,
                         position_ids,
                         input_embeds,
+                        None,
                         train,
                     )
                     .logits
@@ -485,6 +505,7 @@ impl ZeroShotClassificationOption {
                         None,
                         token_type_ids,
                         input_embeds,
+                        None,
                         train,
                     )
                     .logits
@@ -498,6 +519,7 @@ impl ZeroShotClassificationOption {
                         token_type_ids,
                         position_ids,
                         input_embeds,
+                        None,
                         train,
                     )
                     .expect("Error in Longformer forward pass.")
@@ -531,6 +553,8 @@ pub struct ZeroShotClassificationModel {
     tokenizer: TokenizerOption,
     zero_shot_classifier: ZeroShotClassificationOption,
     var_store: VarStore,
+    batch_size: usize,
+    hypothesis_cache: Mutex<HypothesisCache>,
 }
 
 impl ZeroShotClassificationModel {
@@ -576,25 +600,25 @@ impl ZeroShotClassificationModel {
         let zero_shot_classifier =
             ZeroShotClassificationOption::new(config.model_type, var_store.root(), &model_config)?;
         var_store.load(weights_path)?;
+        if config.batch_size == 0 {
+            return Err(RustBertError::InvalidConfigurationError(
+                "Batch size for zero-shot classification must be positive".to_string(),
+            ));
+        }
         Ok(ZeroShotClassificationModel {
             tokenizer,
             zero_shot_classifier,
             var_store,
+            batch_size: config.batch_size,
+            hypothesis_cache: Mutex::new(HypothesisCache::new(config.hypothesis_cache_size)),
         })
     }
 
-    fn prepare_for_model<'a, S, T>(
-        &self,
-        inputs: S,
-        labels: T,
-        template: Option<ZeroShotTemplate>,
-        max_len: usize,
-    ) -> Result<(Tensor, Tensor), RustBertError>
+    fn get_hypotheses<'a, T>(labels: T, template: Option<ZeroShotTemplate>) -> Vec<String>
     where
-        S: AsRef<[&'a str]>,
         T: AsRef<[&'a str]>,
     {
-        let label_sentences: Vec<String> = match template {
+        match template {
             Some(function) => labels
                 .as_ref()
                 .iter()
@@ -605,53 +629,165 @@ impl ZeroShotClassificationModel {
                 .iter()
                 .map(|label| format!("This example is about {label}."))
                 .collect(),
-        };
+        }
+    }
+
+    fn encode_text(&self, text: &str) -> TokenIdsWithOffsets {
+        let tokens = self.tokenizer.tokenize_with_offsets(text);
+        TokenIdsWithOffsets {
+            ids: self.tokenizer.convert_tokens_to_ids(&tokens.tokens),
+            offsets: tokens.offsets,
+            reference_offsets: tokens.reference_offsets,
+            masks: tokens.masks,
+        }
+    }
 
-        let text_pair_list = inputs
-            .as_ref()
+    /// Tokenized hypotheses, re-using the hypotheses tokenized in previous calls
+    fn encode_hypotheses(&self, hypotheses: &[String]) -> Vec<TokenIdsWithOffsets> {
+        let mut cache = self.hypothesis_cache.lock().unwrap();
+        hypotheses
             .iter()
-            .flat_map(|input| {
-                label_sentences
-                    .iter()
-                    .map(move |label_sentence| (input.deref(), label_sentence.as_str()))
+            .map(|hypothesis| {
+                cache.get(hypothesis).unwrap_or_else(|| {
+                    let encoded_hypothesis = self.encode_text(hypothesis);
+                    cache.insert(hypothesis.clone(), encoded_hypothesis.clone());
+                    encoded_hypothesis
+                })
             })
-            .collect::<Vec<(&str, &str)>>();
-
-        let tokenized_input: Vec<TokenizedInput> = self.tokenizer.encode_pair_list(
-            text_pair_list.as_ref(),
-            max_len,
-            &TruncationStrategy::LongestFirst,
-            0,
-        );
-        let max_len = tokenized_input
+            .collect()
+    }
+
+    /// Clears the cache of tokenized label hypotheses
+    pub fn clear_hypothesis_cache(&self) {
+        self.hypothesis_cache.lock().unwrap().clear();
+    }
+
+    /// Runs the inference model over (input index, hypothesis index) pairs in batches, returning the
+    /// logits for each pair with shape (number of pairs, number of NLI classes)
+    fn forward_pairs(
+        &self,
+        inputs: &[&str],
+        hypotheses: &[String],
+        pairs: &[(usize, usize)],
+        max_len: usize,
+    ) -> Result<Tensor, RustBertError> {
+        if pairs.is_empty() {
+            return Err(RustBertError::ValueError(
+                "Got empty iterator as input".to_string(),
+            ));
+        }
+        let encoded_inputs = inputs
             .iter()
-            .map(|input| input.token_ids.len())
-            .max()
-            .ok_or_else(|| RustBertError::ValueError("Got empty iterator as input".to_string()))?;
+            .map(|input| self.encode_text(input))
+            .collect::<Vec<TokenIdsWithOffsets>>();
+        let encoded_hypotheses = self.encode_hypotheses(hypotheses);
+        let empty_sequence = TokenIdsWithOffsets {
+            ids: vec![],
+            offsets: vec![],
+            reference_offsets: vec![],
+            masks: vec![],
+        };
+        let added_tokens = self
+            .tokenizer
+            .build_input_with_special_tokens(empty_sequence.clone(), Some(empty_sequence))
+            .token_ids
+            .len();
 
         let pad_id = self
             .tokenizer
             .get_pad_id()
-            .expect("The Tokenizer used for sequence classification should contain a PAD id");
-        let tokenized_input_tensors = tokenized_input
-            .into_iter()
-            .map(|mut input| {
-                input.token_ids.resize(max_len, pad_id);
-                Tensor::of_slice(&(input.token_ids))
-            })
-            .collect::<Vec<_>>();
+            .expect("The Tokenizer used for zero shot classification should contain a PAD id");
+
+        let mut batch_logits = Vec::with_capacity(pairs.len() / self.batch_size + 1);
+        for batch_pairs in pairs.chunks(self.batch_size) {
+            let mut batch_token_ids = Vec::with_capacity(batch_pairs.len());
+            for &(input_index, hypothesis_index) in batch_pairs {
+                let encoded_input = encoded_inputs[input_index].clone();
+                let encoded_hypothesis = encoded_hypotheses[hypothesis_index].clone();
+                let num_tokens_to_remove =
+                    (encoded_input.ids.len() + encoded_hypothesis.ids.len() + added_tokens)
+                        .saturating_sub(max_len);
+                let (encoded_input, encoded_hypothesis, _, _) = truncate_sequences(
+                    encoded_input,
+                    Some(encoded_hypothesis),
+                    num_tokens_to_remove,
+                    &TruncationStrategy::LongestFirst,
+                    0,
+                )?;
+                batch_token_ids.push(
+                    self.tokenizer
+                        .build_input_with_special_tokens(encoded_input, encoded_hypothesis)
+                        .token_ids,
+                );
+            }
+            let batch_max_len = batch_token_ids
+                .iter()
+                .map(|token_ids| token_ids.len())
+                .max()
+                .unwrap();
+            let input_tensor = Tensor::stack(
+                &batch_token_ids
+                    .into_iter()
+                    .map(|mut token_ids| {
+                        token_ids.resize(batch_max_len, pad_id);
+                        Tensor::of_slice(&token_ids)
+                    })
+                    .collect::<Vec<Tensor>>(),
+                0,
+            )
+            .to(self.var_store.device());
+            let mask = input_tensor.ne(pad_id).to_kind(Bool);
 
-        let tokenized_input_tensors =
-            Tensor::stack(tokenized_input_tensors.as_slice(), 0).to(self.var_store.device());
+            batch_logits.push(no_grad(|| {
+                self.zero_shot_classifier.forward_t(
+                    Some(&input_tensor),
+                    Some(&mask),
+                    None,
+                    None,
+                    None,
+                    false,
+                )
+            }));
+        }
+        Ok(Tensor::cat(&batch_logits, 0))
+    }
 
-        let mask = tokenized_input_tensors
-            .ne(self
-                .tokenizer
-                .get_pad_id()
-                .expect("The Tokenizer used for zero shot classification should contain a PAD id"))
-            .to_kind(Bool);
+    /// Selects for each input the indices of the `top_m` labels most similar to the input in the
+    /// sentence embeddings space
+    fn prefilter_labels(
+        inputs: &[&str],
+        labels: &[&str],
+        embeddings_model: &SentenceEmbeddingsModel,
+        top_m: usize,
+    ) -> Result<Vec<Vec<usize>>, RustBertError> {
+        if inputs.is_empty() | labels.is_empty() {
+            return Err(RustBertError::ValueError(
+                "Got empty iterator as input".to_string(),
+            ));
+        }
+        let normalize = |embeddings: Tensor| {
+            let norm = embeddings
+                .norm_scalaropt_dim(2, [1].as_slice(), true)
+                .clamp_min(1e-12);
+            embeddings / norm
+        };
+        let input_embeddings = normalize(embeddings_model.encode_as_tensor(inputs)?.embeddings);
+        let label_embeddings = normalize(embeddings_model.encode_as_tensor(labels)?.embeddings);
+        let similarities = input_embeddings.matmul(&label_embeddings.transpose(0, 1));
+        let top_m = top_m.clamp(1, labels.len()) as i64;
+        let (_, top_indices) = similarities.topk(top_m, -1, true, true);
 
-        Ok((tokenized_input_tensors, mask))
+        let mut selected_labels = Vec::with_capacity(inputs.len());
+        for input_index in 0..inputs.len() {
+            let mut label_indices = top_indices
+                .get(input_index as i64)
+                .iter::<i64>()?
+                .map(|label_index| label_index as usize)
+                .collect::<Vec<usize>>();
+            label_indices.sort_unstable();
+            selected_labels.push(label_indices);
+        }
+        Ok(selected_labels)
     }
 
     /// Zero shot classification with 1 (and exactly 1) true label.
@@ -720,36 +856,123 @@ impl ZeroShotClassificationModel {
         T: AsRef<[&'a str]>,
     {
         let num_inputs = inputs.as_ref().len();
-        let (input_tensor, mask) =
-            self.prepare_for_model(inputs.as_ref(), labels.as_ref(), template, max_length)?;
-
-        let output = no_grad(|| {
-            let output = self.zero_shot_classifier.forward_t(
-                Some(&input_tensor),
-                Some(&mask),
-                None,
-                None,
-                None,
-                false,
-            );
-            output.view((num_inputs as i64, labels.as_ref().len() as i64, -1i64))
-        });
+        let num_labels = labels.as_ref().len();
+        let hypotheses = Self::get_hypotheses(labels.as_ref(), template);
+        let label_indices = (0..num_inputs)
+            .map(|_| (0..num_labels).collect())
+            .collect::<Vec<Vec<usize>>>();
+        let pairs = Self::get_pairs(&label_indices);
+        let output = self
+            .forward_pairs(inputs.as_ref(), &hypotheses, &pairs, max_length)?
+            .view((num_inputs as i64, num_labels as i64, -1i64));
+
+        Self::get_single_labels(&output, labels.as_ref(), &label_indices)
+    }
+
+    /// Zero shot classification with 1 (and exactly 1) true label, running the inference model only
+    /// on the `top_m` labels closest to each input according to a sentence embeddings model.
+    /// This allows scaling to large label sets, the probabilities are normalized over the retained labels.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - `&[&str]` Array of texts to classify.
+    /// * `labels` - `&[&str]` Possible labels for the inputs.
+    /// * `template` - `Option<Box<dyn Fn(&str) -> String>>` closure to build label propositions. If None, will default to `"This example is about {}."`.
+    /// * `max_length` -`usize` Maximum sequence length for the inputs. If needed, the input sequence will be truncated before the label template.
+    /// * `embeddings_model` - `&SentenceEmbeddingsModel` used to compute the similarity between inputs and labels
+    /// * `top_m` - `usize` number of labels retained for each input
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Vec<Label>, RustBertError>` containing the most likely label for each input sentence or error, if any.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::sentence_embeddings::{
+    ///     SentenceEmbeddingsBuilder, SentenceEmbeddingsModelType,
+    /// };
+    /// use rust_bert::pipelines::zero_shot_classification::ZeroShotClassificationModel;
+    ///
+    /// let sequence_classification_model = ZeroShotClassificationModel::new(Default::default())?;
+    /// let embeddings_model =
+    ///     SentenceEmbeddingsBuilder::remote(SentenceEmbeddingsModelType::AllMiniLmL12V2)
+    ///         .create_model()?;
+    ///
+    /// let input_sentence = "Who are you voting for in 2020?";
+    /// let candidate_labels = &["politics", "public health", "economics", "sports", "cooking"];
+    ///
+    /// let output = sequence_classification_model.predict_with_prefilter(
+    ///     &[input_sentence],
+    ///     candidate_labels,
+    ///     None,
+    ///     128,
+    ///     &embeddings_model,
+    ///     2,
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn predict_with_prefilter<'a, S, T>(
+        &self,
+        inputs: S,
+        labels: T,
+        template: Option<ZeroShotTemplate>,
+        max_length: usize,
+        embeddings_model: &SentenceEmbeddingsModel,
+        top_m: usize,
+    ) -> Result<Vec<Label>, RustBertError>
+    where
+        S: AsRef<[&'a str]>,
+        T: AsRef<[&'a str]>,
+    {
+        let label_indices =
+            Self::prefilter_labels(inputs.as_ref(), labels.as_ref(), embeddings_model, top_m)?;
+        let num_selected = label_indices[0].len();
+        let hypotheses = Self::get_hypotheses(labels.as_ref(), template);
+        let pairs = Self::get_pairs(&label_indices);
+        let output = self
+            .forward_pairs(inputs.as_ref(), &hypotheses, &pairs, max_length)?
+            .view((inputs.as_ref().len() as i64, num_selected as i64, -1i64));
+
+        Self::get_single_labels(&output, labels.as_ref(), &label_indices)
+    }
+
+    fn get_pairs(label_indices: &[Vec<usize>]) -> Vec<(usize, usize)> {
+        label_indices
+            .iter()
+            .enumerate()
+            .flat_map(|(input_index, input_labels)| {
+                input_labels
+                    .iter()
+                    .map(move |label_index| (input_index, *label_index))
+            })
+            .collect()
+    }
 
+    /// Picks the most likely label from logits of shape (inputs, candidate labels, NLI classes),
+    /// `label_indices` mapping the candidate labels of each input to the original labels.
+    fn get_single_labels(
+        output: &Tensor,
+        labels: &[&str],
+        label_indices: &[Vec<usize>],
+    ) -> Result<Vec<Label>, RustBertError> {
         let scores = output.softmax(1, Float).select(-1, -1);
-        let label_indices = scores.as_ref().argmax(-1, true).squeeze_dim(1);
+        let candidate_indices = scores.as_ref().argmax(-1, true).squeeze_dim(1);
         let scores = scores
-            .gather(1, &label_indices.unsqueeze(-1), false)
+            .gather(1, &candidate_indices.unsqueeze(-1), false)
             .squeeze_dim(1);
-        let label_indices = label_indices.iter::<i64>()?.collect::<Vec<i64>>();
+        let candidate_indices = candidate_indices.iter::<i64>()?.collect::<Vec<i64>>();
         let scores = scores.iter::<f64>()?.collect::<Vec<f64>>();
 
         let mut output_labels: Vec<Label> = vec![];
-        for sentence_idx in 0..label_indices.len() {
-            let label_string = labels.as_ref()[label_indices[sentence_idx] as usize].to_string();
+        for sentence_idx in 0..candidate_indices.len() {
+            let label_index = label_indices[sentence_idx][candidate_indices[sentence_idx] as usize];
             let label = Label {
-                text: label_string,
+                text: labels[label_index].to_string(),
                 score: scores[sentence_idx],
-                id: label_indices[sentence_idx],
+                id: label_index as i64,
                 sentence: sentence_idx,
             };
             output_labels.push(label)
@@ -862,34 +1085,111 @@ impl ZeroShotClassificationModel {
         T: AsRef<[&'a str]>,
     {
         let num_inputs = inputs.as_ref().len();
-        let (input_tensor, mask) =
-            self.prepare_for_model(inputs.as_ref(), labels.as_ref(), template, max_length)?;
-
-        let output = no_grad(|| {
-            let output = self.zero_shot_classifier.forward_t(
-                Some(&input_tensor),
-                Some(&mask),
-                None,
-                None,
-                None,
-                false,
-            );
-            output.view((num_inputs as i64, labels.as_ref().len() as i64, -1i64))
-        });
+        let num_labels = labels.as_ref().len();
+        let hypotheses = Self::get_hypotheses(labels.as_ref(), template);
+        let label_indices = (0..num_inputs)
+            .map(|_| (0..num_labels).collect())
+            .collect::<Vec<Vec<usize>>>();
+        let pairs = Self::get_pairs(&label_indices);
+        let output = self
+            .forward_pairs(inputs.as_ref(), &hypotheses, &pairs, max_length)?
+            .view((num_inputs as i64, num_labels as i64, -1i64));
+
+        Self::get_multilabel_labels(&output, labels.as_ref(), &label_indices)
+    }
+
+    /// Zero shot multi-label classification, running the inference model only on the `top_m` labels
+    /// closest to each input according to a sentence embeddings model. Only the retained labels are
+    /// returned for each input.
+    ///
+    /// # Arguments
+    ///
+    /// * `input` - `&[&str]` Array of texts to classify.
+    /// * `labels` - `&[&str]` Possible labels for the inputs.
+    /// * `template` - `Option<Box<dyn Fn(&str) -> String>>` closure to build label propositions. If None, will default to `"This example is about {}."`.
+    /// * `max_length` -`usize` Maximum sequence length for the inputs. If needed, the input sequence will be truncated before the label template.
+    /// * `embeddings_model` - `&SentenceEmbeddingsModel` used to compute the similarity between inputs and labels
+    /// * `top_m` - `usize` number of labels retained for each input
+    ///
+    /// # Returns
+    ///
+    /// * `Result<Vec<Vec<Label>>, RustBertError>` containing a vector of retained labels and their probability for each input text, or error, if any.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::sentence_embeddings::{
+    ///     SentenceEmbeddingsBuilder, SentenceEmbeddingsModelType,
+    /// };
+    /// use rust_bert::pipelines::zero_shot_classification::ZeroShotClassificationModel;
+    ///
+    /// let sequence_classification_model = ZeroShotClassificationModel::new(Default::default())?;
+    /// let embeddings_model =
+    ///     SentenceEmbeddingsBuilder::remote(SentenceEmbeddingsModelType::AllMiniLmL12V2)
+    ///         .create_model()?;
+    ///
+    /// let input_sentence = "The central bank is meeting today to discuss monetary policy.";
+    /// let candidate_labels = &["politics", "public health", "economics", "sports", "cooking"];
+    ///
+    /// let output = sequence_classification_model.predict_multilabel_with_prefilter(
+    ///     &[input_sentence],
+    ///     candidate_labels,
+    ///     None,
+    ///     128,
+    ///     &embeddings_model,
+    ///     3,
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn predict_multilabel_with_prefilter<'a, S, T>(
+        &self,
+        inputs: S,
+        labels: T,
+        template: Option<ZeroShotTemplate>,
+        max_length: usize,
+        embeddings_model: &SentenceEmbeddingsModel,
+        top_m: usize,
+    ) -> Result<Vec<Vec<Label>>, RustBertError>
+    where
+        S: AsRef<[&'a str]>,
+        T: AsRef<[&'a str]>,
+    {
+        let label_indices =
+            Self::prefilter_labels(inputs.as_ref(), labels.as_ref(), embeddings_model, top_m)?;
+        let num_selected = label_indices[0].len();
+        let hypotheses = Self::get_hypotheses(labels.as_ref(), template);
+        let pairs = Self::get_pairs(&label_indices);
+        let output = self
+            .forward_pairs(inputs.as_ref(), &hypotheses, &pairs, max_length)?
+            .view((inputs.as_ref().len() as i64, num_selected as i64, -1i64));
+
+        Self::get_multilabel_labels(&output, labels.as_ref(), &label_indices)
+    }
+
+    /// Computes the entailment probability of each candidate label from logits of shape
+    /// (inputs, candidate labels, NLI classes), `label_indices` mapping the candidate labels of each
+    /// input to the original labels.
+    fn get_multilabel_labels(
+        output: &Tensor,
+        labels: &[&str],
+        label_indices: &[Vec<usize>],
+    ) -> Result<Vec<Vec<Label>>, RustBertError> {
         let scores = output.slice(-1, 0, 3, 2).softmax(-1, Float).select(-1, -1);
 
         let mut output_labels = vec![];
-        for sentence_idx in 0..num_inputs {
+        for (sentence_idx, sentence_label_indices) in label_indices.iter().enumerate() {
             let mut sentence_labels = vec![];
 
-            for (label_index, score) in scores
+            for (candidate_index, score) in scores
                 .select(0, sentence_idx as i64)
                 .iter::<f64>()?
                 .enumerate()
             {
-                let label_string = labels.as_ref()[label_index].to_string();
+                let label_index = sentence_label_indices[candidate_index];
                 let label = Label {
-                    text: label_string,
+                    text: labels[label_index].to_string(),
                     score,
                     id: label_index as i64,
                     sentence: sentence_idx,
@@ -901,9 +1201,61 @@ impl ZeroShotClassificationModel {
         Ok(output_labels)
     }
 }
+/// Least recently used cache of tokenized label hypotheses, holding at most `capacity` entries
+struct HypothesisCache {
+    capacity: usize,
+    entries: HashMap<String, (TokenIdsWithOffsets, u64)>,
+    counter: u64,
+}
+
+impl HypothesisCache {
+    fn new(capacity: usize) -> HypothesisCache {
+        HypothesisCache {
+            capacity,
+            entries: HashMap::new(),
+            counter: 0,
+        }
+    }
+
+    fn get(&mut self, hypothesis: &str) -> Option<TokenIdsWithOffsets> {
+        self.counter += 1;
+        let counter = self.counter;
+        self.entries
+            .get_mut(hypothesis)
+            .map(|(encoded_hypothesis, last_used)| {
+                *last_used = counter;
+                encoded_hypothesis.clone()
+            })
+    }
+
+    fn insert(&mut self, hypothesis: String, encoded_hypothesis: TokenIdsWithOffsets) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.entries.len() >= self.capacity && !self.entries.contains_key(&hypothesis) {
+            if let Some(least_recently_used) = self
+                .entries
+                .iter()
+                .min_by_key(|(_, (_, last_used))| *last_used)
+                .map(|(key, _)| key.clone())
+            {
+                self.entries.remove(&least_recently_used);
+            }
+        }
+        self.counter += 1;
+        self.entries
+            .insert(hypothesis, (encoded_hypothesis, self.counter));
+    }
+
+    fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
+    use rust_tokenizers::Mask;
 
     #[test]
     #[ignore] // no need to run, compilation is enough to verify it is Send
@@ -911,4 +1263,32 @@ mod test {
         let config = ZeroShotClassificationConfig::default();
         let _: Box<dyn Send> = Box::new(ZeroShotClassificationModel::new(config));
     }
+
+    fn encoded_hypothesis(id: i64) -> TokenIdsWithOffsets {
+        TokenIdsWithOffsets {
+            ids: vec![id],
+            offsets: vec![None],
+            reference_offsets: vec![vec![]],
+            masks: vec![Mask::None],
+        }
+    }
+
+    #[test]
+    fn test_hypothesis_cache_evicts_least_recently_used() {
+        let mut cache = HypothesisCache::new(2);
+        cache.insert("a".to_string(), encoded_hypothesis(1));
+        cache.insert("b".to_string(), encoded_hypothesis(2));
+        // Accessing "a" makes "b" the least recently used entry
+        assert_eq!(cache.get("a").unwrap().ids, vec![1]);
+        cache.insert("c".to_string(), encoded_hypothesis(3));
+
+        assert_eq!(cache.entries.len(), 2);
+        assert!(cache.get("b").is_none());
+        assert_eq!(cache.get("a").unwrap().ids, vec![1]);
+        assert_eq!(cache.get("c").unwrap().ids, vec![3]);
+
+        let mut cache = HypothesisCache::new(0);
+        cache.insert("a".to_string(), encoded_hypothesis(1));
+        assert!(cache.entries.is_empty());
+    }
 }
@@ -631,8 +631,13 @@ impl LMHeadModel for ProphetNetForConditionalGeneration {
         Ok(LMModelOutput {
             lm_logits: base_model_output.logits,
             cache: Cache::ProphetNetCache(base_model_output.next_decoder_cache),
+            loss: None,
         })
     }
+
+    fn predicts_next_token(&self) -> bool {
+        false
+    }
 }
 
 /// # ProphetNet Model for causal generation
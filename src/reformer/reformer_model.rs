@@ -23,6 +23,7 @@ use tch::{nn, Device, Kind, Tensor};
 use crate::common::activations::Activation;
 use crate::common::dropout::Dropout;
 use crate::common::embeddings::get_shape_and_device_from_ids_embeddings_pair;
+use crate::common::loss::{question_answering_loss, sequence_classification_loss, ProblemType};
 use crate::pipelines::common::{ModelType, TokenizerOption};
 use crate::pipelines::generation_utils::private_generation_utils::{
     PreparedInput, PrivateLanguageGenerator,
@@ -108,6 +109,8 @@ pub struct ReformerConfig {
     pub use_cache: Option<bool>,
     pub id2label: Option<HashMap<i64, String>>,
     pub label2id: Option<HashMap<String, i64>>,
+    /// Type of problem defining the loss of the sequence classification head (default: inferred from the labels)
+    pub problem_type: Option<ProblemType>,
     pub output_attentions: Option<bool>,
     pub output_hidden_states: Option<bool>,
 }
@@ -159,6 +162,7 @@ impl Default for ReformerConfig {
             use_cache: None,
             id2label: None,
             label2id: None,
+            problem_type: None,
             output_attentions: None,
             output_hidden_states: None,
         }
@@ -683,6 +687,7 @@ impl LMHeadModel for ReformerModelWithLMHead {
         Ok(LMModelOutput {
             lm_logits: output.logits,
             cache: Cache::ReformerCache(output.next_cache),
+            loss: None,
         })
     }
 }
@@ -752,6 +757,7 @@ impl ReformerClassificationHead {
 pub struct ReformerForSequenceClassification {
     reformer: ReformerModel,
     classifier: ReformerClassificationHead,
+    problem_type: Option<ProblemType>,
 }
 
 impl ReformerForSequenceClassification {
@@ -792,6 +798,7 @@ impl ReformerForSequenceClassification {
         Ok(ReformerForSequenceClassification {
             reformer,
             classifier,
+            problem_type: config.problem_type,
         })
     }
 
@@ -804,12 +811,14 @@ impl ReformerForSequenceClassification {
     /// * `input_embeds` - Optional input tensor of shape (*batch size*, *sequence_length*, *embeddings_dim*). Must be provided when no input ids are given.
     /// * `attention_mask` - Optional attention mask of shape (*batch size*, *sequence_length*). Positions with a mask with value 0 will be masked.
     /// * `num_hashes` - Optional specification of the number of hashes to use. If not provided will use the value provided in the model configuration.
+    /// * `labels` - Optional labels of shape (*batch size*) used to compute the loss: class indices for single-label classification or target values for regression. Multi-label classification uses floating point targets of shape (*batch size*, *num_labels*). The loss follows the `problem_type` of the configuration, inferred from the labels if not set.
     /// * `train` - boolean flag to turn on/off the dropout layers in the model. Should be set to false for inference.
     ///
     /// # Returns
     ///
     /// * `ReformerClassificationOutput` containing:
     ///   - `logits` - `Tensor` of shape (*batch size*, *sequence_length*, *num_classes*) representing the logits for each target class
+    ///   - `loss` - `Option<Tensor>` scalar loss, computed if labels are provided
     ///   - `all_hidden_states` - `Option<Vec<Tensor>>` of length *n_layers* with shape (*batch size*, *sequence_length*, *hidden_size*)
     ///   - `all_attentions` - `Option<Vec<Tensor>>` of length *n_layers* with shape (*batch size*, *sequence_length*, *hidden_size*)
     ///
@@ -839,6 +848,7 @@ impl ReformerForSequenceClassification {
     ///         None,
     ///         Some(&attention_mask),
     ///         Some(4),
+    ///         None,
     ///         false,
     ///     )
     /// });
@@ -850,6 +860,7 @@ impl ReformerForSequenceClassification {
         input_embeds: Option<&Tensor>,
         attention_mask: Option<&Tensor>,
         num_hashes: Option<i64>,
+        labels: Option<&Tensor>,
         train: bool,
     ) -> Result<ReformerClassificationOutput, RustBertError> {
         let reformer_output = self.reformer.forward_t(
@@ -866,7 +877,10 @@ impl ReformerForSequenceClassification {
             .classifier
             .forward_t(&reformer_output.hidden_states, train);
 
+        let loss =
+            labels.map(|labels| sequence_classification_loss(&logits, labels, self.problem_type));
         Ok(ReformerClassificationOutput {
+            loss,
             logits,
             all_hidden_states: reformer_output.all_hidden_states,
             all_attentions: reformer_output.all_attentions,
@@ -941,6 +955,8 @@ impl ReformerForQuestionAnswering {
     /// * `input_embeds` - Optional input tensor of shape (*batch size*, *sequence_length*, *embeddings_dim*). Must be provided when no input ids are given.
     /// * `attention_mask` - Optional attention mask of shape (*batch size*, *sequence_length*). Positions with a mask with value 0 will be masked.
     /// * `num_hashes` - Optional specification of the number of hashes to use. If not provided will use the value provided in the model configuration.
+    /// * `start_positions` - Optional answer start positions of shape (*batch size*) used to compute the loss. Positions outside of the sequence are ignored.
+    /// * `end_positions` - Optional answer end positions of shape (*batch size*) used to compute the loss. Positions outside of the sequence are ignored.
     /// * `train` - boolean flag to turn on/off the dropout layers in the model. Should be set to false for inference.
     ///
     /// # Returns
@@ -948,6 +964,7 @@ impl ReformerForQuestionAnswering {
     /// * `ReformerClassificationOutput` containing:
     ///   - `start_logits` -  `Tensor` of shape (*batch size*, *sequence_length*) containing the logits for start of the answer
     ///   - `end_logits` - `Tensor` of shape (*batch size*, *sequence_length*) containing the logits for end of the answer
+    ///   - `loss` - `Option<Tensor>` scalar loss, computed if the start and end positions are provided
     ///   - `all_hidden_states` - `Option<Vec<Tensor>>` of length *n_layers* with shape (*batch size*, *sequence_length*, *hidden_size*)
     ///   - `all_attentions` - `Option<Vec<Tensor>>` of length *n_layers* with shape (*batch size*, *sequence_length*, *hidden_size*)
     ///
@@ -977,6 +994,8 @@ impl ReformerForQuestionAnswering {
     ///         None,
     ///         Some(&attention_mask),
     ///         Some(4),
+    ///         None,
+    ///         None,
     ///         false,
     ///     )
     /// });
@@ -988,6 +1007,8 @@ impl ReformerForQuestionAnswering {
         input_embeds: Option<&Tensor>,
         attention_mask: Option<&Tensor>,
         num_hashes: Option<i64>,
+        start_positions: Option<&Tensor>,
+        end_positions: Option<&Tensor>,
         train: bool,
     ) -> Result<ReformerQuestionAnsweringModelOutput, RustBertError> {
         let reformer_output = self.reformer.forward_t(
@@ -1008,7 +1029,13 @@ impl ReformerForQuestionAnswering {
         let start_logits = start_logits.squeeze_dim(-1);
         let end_logits = end_logits.squeeze_dim(-1);
 
+        let loss = start_positions
+            .zip(end_positions)
+            .map(|(start_positions, end_positions)| {
+                question_answering_loss(&start_logits, &end_logits, start_positions, end_positions)
+            });
         Ok(ReformerQuestionAnsweringModelOutput {
+            loss,
             start_logits,
             end_logits,
             all_hidden_states: reformer_output.all_hidden_states,
@@ -1037,6 +1064,8 @@ pub struct ReformerClassificationOutput {
     pub all_hidden_states: Option<Vec<Tensor>>,
     /// Attention weights for all intermediate layers
     pub all_attentions: Option<Vec<Tensor>>,
+    /// Loss computed from the labels, if provided
+    pub loss: Option<Tensor>,
 }
 
 ///Container holding a Reformer model with question answering head
@@ -1049,6 +1078,8 @@ pub struct ReformerQuestionAnsweringModelOutput {
     pub all_hidden_states: Option<Vec<Tensor>>,
     /// Attention weights for all intermediate layers
     pub all_attentions: Option<Vec<Tensor>>,
+    /// Loss computed from the labels, if provided
+    pub loss: Option<Tensor>,
 }
 
 pub struct ReformerGenerator {
@@ -11,7 +11,7 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use crate::bert::{BertConfig, BertEmbedding};
+use crate::bert::{BertConfig, BertEmbedding, PositionEmbeddingType};
 use crate::common::dropout::Dropout;
 use crate::common::embeddings::process_ids_embeddings_pair;
 use crate::RustBertError;
@@ -24,7 +24,7 @@ use tch::{nn, Kind, Tensor};
 /// Implementation of the `BertEmbedding` trait for RoBERTa models
 pub struct RobertaEmbeddings {
     word_embeddings: nn::Embedding,
-    position_embeddings: nn::Embedding,
+    position_embeddings: Option<nn::Embedding>,
     token_type_embeddings: nn::Embedding,
     layer_norm: nn::LayerNorm,
     dropout: Dropout,
@@ -90,12 +90,15 @@ impl BertEmbedding for RobertaEmbeddings {
             embedding_config,
         );
 
-        let position_embeddings: nn::Embedding = embedding(
-            p / "position_embeddings",
-            config.max_position_embeddings,
-            config.hidden_size,
-            Default::default(),
-        );
+        let position_embeddings = match config.position_embedding_type.unwrap_or_default() {
+            PositionEmbeddingType::Absolute => Some(embedding(
+                p / "position_embeddings",
+                config.max_position_embeddings,
+                config.hidden_size,
+                Default::default(),
+            )),
+            _ => None,
+        };
 
         let token_type_embeddings: nn::Embedding = embedding(
             p / "token_type_embeddings",
@@ -183,15 +186,6 @@ impl BertEmbedding for RobertaEmbeddings {
         let input_embeddings =
             input_embeds.unwrap_or_else(|| calc_input_embeddings.as_ref().unwrap());
 
-        let calc_position_ids = if position_ids.is_none() {
-            Some(match input_ids {
-                Some(value) => self.create_position_ids_from_input_ids(value),
-                None => self.create_position_ids_from_embeddings(input_embeds.unwrap()),
-            })
-        } else {
-            None
-        };
-
         let calc_token_type_ids = if token_type_ids.is_none() {
             Some(Tensor::zeros(
                 &input_shape,
@@ -201,15 +195,23 @@ impl BertEmbedding for RobertaEmbeddings {
             None
         };
 
-        let position_ids = position_ids.unwrap_or_else(|| calc_position_ids.as_ref().unwrap());
         let token_type_ids =
             token_type_ids.unwrap_or_else(|| calc_token_type_ids.as_ref().unwrap());
-
-        let position_embeddings = position_ids.apply(&self.position_embeddings);
         let token_type_embeddings = token_type_ids.apply(&self.token_type_embeddings);
 
-        let input_embeddings: Tensor =
-            input_embeddings + position_embeddings + token_type_embeddings;
+        let mut input_embeddings: Tensor = input_embeddings + token_type_embeddings;
+        if let Some(position_embeddings) = &self.position_embeddings {
+            let position_embeddings = match (position_ids, input_ids) {
+                (Some(position_ids), _) => position_ids.apply(position_embeddings),
+                (None, Some(input_ids)) => self
+                    .create_position_ids_from_input_ids(input_ids)
+                    .apply(position_embeddings),
+                (None, None) => self
+                    .create_position_ids_from_embeddings(input_embeds.unwrap())
+                    .apply(position_embeddings),
+            };
+            input_embeddings += position_embeddings;
+        }
         Ok(input_embeddings
             .apply(&self.layer_norm)
             .apply_t(&self.dropout, train))
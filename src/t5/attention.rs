@@ -10,9 +10,13 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use crate::common::attention::{
+    concatenate_3_blocks, get_local_attention_mask, make_3block_relative_position_ids,
+    split_into_blocks,
+};
 use crate::common::dropout::Dropout;
 use crate::t5::layer_norm::T5LayerNorm;
-use crate::t5::T5Config;
+use crate::t5::{EncoderAttentionType, T5Config};
 use std::borrow::Borrow;
 use tch::nn::LinearConfig;
 use tch::{nn, Device, Kind, Tensor};
@@ -43,6 +47,37 @@ impl LayerState {
     }
 }
 
+fn get_relative_position_bucket(
+    relative_position: &Tensor,
+    bidirectional: bool,
+    num_buckets: i64,
+    max_distance: i64,
+) -> Tensor {
+    let n = -relative_position;
+    let mut num_buckets = num_buckets;
+    let mut ret = n.zeros_like();
+    let n = if bidirectional {
+        num_buckets /= 2;
+        ret += n.lt(0).to_kind(Kind::Int64) * num_buckets;
+        n.abs()
+    } else {
+        n.max_other(&n.zeros_like())
+    };
+
+    let max_exact = num_buckets / 2;
+    let is_small = n.lt(max_exact);
+
+    let value_if_large: Tensor = ((n.to_kind(Kind::Float) / max_exact as f64).log2()
+        / (max_distance as f64 / max_exact as f64).log2()
+        * (num_buckets - max_exact) as f64)
+        .to_kind(Kind::Int64)
+        + max_exact;
+
+    let value_if_large = value_if_large.min_other(&value_if_large.full_like(num_buckets - 1));
+    ret += n.where_self(&is_small, &value_if_large);
+    ret
+}
+
 #[derive(Debug)]
 pub struct T5Attention {
     is_decoder: bool,
@@ -245,46 +280,306 @@ impl T5Attention {
         (context, attention_weights, position_bias, layer_state)
     }
 
-    fn get_relative_position_bucket(
-        &self,
-        relative_position: &Tensor,
-        bidirectional: bool,
-        num_buckets: i64,
-        max_distance: i64,
-    ) -> Tensor {
-        let n = -relative_position;
-        let mut num_buckets = num_buckets;
-        let mut ret = n.zeros_like();
-        let n = if bidirectional {
-            num_buckets /= 2;
-            ret += n.lt(0).to_kind(Kind::Int64) * num_buckets;
-            n.abs()
+    fn compute_bias(&self, q_len: i64, k_len: i64, device: Device) -> Tensor {
+        let context_position = Tensor::arange(q_len, (Kind::Int64, device)).unsqueeze(1);
+        let memory_position = Tensor::arange(k_len, (Kind::Int64, device)).unsqueeze(0);
+        let relative_position = memory_position - context_position;
+
+        let rp_bucket = get_relative_position_bucket(
+            &relative_position,
+            self.is_bidirectional,
+            self.relative_attention_num_buckets,
+            self.relative_attention_max_distance,
+        );
+        rp_bucket
+            .apply(self.relative_attention_bias.as_ref().unwrap())
+            .permute(&[2, 0, 1])
+            .unsqueeze(0)
+    }
+}
+
+/// Assigns each token to a global block of `global_block_size` tokens. Returns the block ids of shape
+/// (*batch size*, *sequence_length*), set to -1 for padding tokens, and the global segment ids of shape
+/// (*batch size*, *sequence_length* / `global_block_size`), set to 1 for the global blocks that contain tokens.
+fn make_global_fixed_block_ids(
+    attention_mask: &Tensor,
+    global_block_size: i64,
+) -> (Tensor, Tensor) {
+    let seq_len = attention_mask.size()[1];
+    let device = attention_mask.device();
+
+    let fixed_block_mask = attention_mask.ones_like() / global_block_size as f64;
+    let fixed_block_mask = fixed_block_mask.cumsum(1, Kind::Float) - &fixed_block_mask;
+    let mask = attention_mask.ne(0.0).to_kind(Kind::Float) * 1001.0 - 1000.0;
+    let global_block_ids = (mask + fixed_block_mask - 1.0).floor().clamp_min(-1.0);
+    let global_block_ids = &global_block_ids * attention_mask + (attention_mask - 1.0);
+
+    // Tokens of an incomplete last block are assigned to the previous full block
+    let block_ends = Tensor::arange(seq_len, (Kind::Int64, device))
+        .remainder(global_block_size)
+        .eq(global_block_size - 1);
+    let full_blocks = block_ends
+        .unsqueeze(0)
+        .logical_and(&global_block_ids.ge(0.0))
+        .sum_dim_intlist([-1].as_slice(), true, Kind::Float)
+        - 1.0;
+    let global_block_ids = global_block_ids.minimum(&full_blocks).to_kind(Kind::Int64);
+
+    let num_globals = seq_len / global_block_size;
+    let global_segment_ids = Tensor::arange(num_globals, (Kind::Int64, device))
+        .unsqueeze(0)
+        .le_tensor(&global_block_ids.amax(&[-1], true))
+        .to_kind(Kind::Int64);
+
+    (global_block_ids, global_segment_ids)
+}
+
+/// Sums the hidden states of the tokens belonging to each global block, returning the global inputs of shape
+/// (*batch size*, *global_sequence_length*, *hidden_size*)
+fn create_global_aggregates(
+    hidden_states: &Tensor,
+    block_ids: &Tensor,
+    global_seq_len: i64,
+) -> Tensor {
+    let block_ids = block_ids.where_scalarother(&block_ids.ge(0), global_seq_len);
+    let one_hot_block_ids = block_ids
+        .one_hot(global_seq_len + 1)
+        .narrow(-1, 0, global_seq_len)
+        .to_kind(hidden_states.kind());
+    Tensor::einsum("bnd,bng->bgd", &[hidden_states, &one_hot_block_ids], None)
+}
+
+#[derive(Debug)]
+/// # LongT5 encoder self-attention
+/// Local attention splitting the sequence into blocks of `local_radius + 1` tokens, each token attending to the
+/// tokens of its own and neighbouring blocks within `local_radius` positions. The transient-global variant
+/// additionally attends to the (layer-normalized) sums of the hidden states over blocks of `global_block_size` tokens.
+/// This attention is only used in the encoder and does not store a cache.
+pub struct LongT5Attention {
+    has_relative_attention_bias: bool,
+    relative_attention_num_buckets: i64,
+    relative_attention_max_distance: i64,
+    d_kv: i64,
+    n_heads: i64,
+    inner_dim: i64,
+    block_len: i64,
+    global_block_size: Option<i64>,
+    dropout: Dropout,
+    output_attentions: bool,
+    query: nn::Linear,
+    key: nn::Linear,
+    value: nn::Linear,
+    output: nn::Linear,
+    relative_attention_bias: Option<nn::Embedding>,
+    global_relative_attention_bias: Option<nn::Embedding>,
+    global_input_layer_norm: Option<T5LayerNorm>,
+}
+
+impl LongT5Attention {
+    pub fn new<'p, P>(
+        p: P,
+        config: &T5Config,
+        encoder_attention_type: EncoderAttentionType,
+        output_attentions: bool,
+        has_relative_attention_bias: bool,
+    ) -> LongT5Attention
+    where
+        P: Borrow<nn::Path<'p>>,
+    {
+        let p = p.borrow();
+
+        let linear_config = LinearConfig {
+            bias: false,
+            ..Default::default()
+        };
+
+        let inner_dim = config.num_heads * config.d_kv;
+        let key = nn::linear(p / "k", config.d_model, inner_dim, linear_config);
+        let value = nn::linear(p / "v", config.d_model, inner_dim, linear_config);
+        let query = nn::linear(p / "q", config.d_model, inner_dim, linear_config);
+        let output = nn::linear(p / "o", inner_dim, config.d_model, linear_config);
+
+        let dropout = Dropout::new(config.dropout_rate);
+        let relative_attention_bias = if has_relative_attention_bias {
+            Some(nn::embedding(
+                p / "relative_attention_bias",
+                config.relative_attention_num_buckets,
+                config.num_heads,
+                Default::default(),
+            ))
         } else {
-            n.max_other(&n.zeros_like())
+            None
         };
 
-        let max_exact = num_buckets / 2;
-        let is_small = n.lt(max_exact);
+        let (global_block_size, global_relative_attention_bias, global_input_layer_norm) =
+            match encoder_attention_type {
+                EncoderAttentionType::Local => (None, None, None),
+                EncoderAttentionType::TransientGlobal => {
+                    let global_relative_attention_bias = if has_relative_attention_bias {
+                        Some(nn::embedding(
+                            p / "global_relative_attention_bias",
+                            config.relative_attention_num_buckets,
+                            config.num_heads,
+                            Default::default(),
+                        ))
+                    } else {
+                        None
+                    };
+                    let global_input_layer_norm = T5LayerNorm::new(
+                        p / "global_input_layer_norm",
+                        config.d_model,
+                        config.layer_norm_epsilon,
+                    );
+                    (
+                        Some(config.global_block_size.unwrap_or(16)),
+                        global_relative_attention_bias,
+                        Some(global_input_layer_norm),
+                    )
+                }
+            };
 
-        let value_if_large: Tensor = ((n.to_kind(Kind::Float) / max_exact as f64).log2()
-            / (max_distance as f64 / max_exact as f64).log2()
-            * (num_buckets - max_exact) as f64)
-            .to_kind(Kind::Int64)
-            + max_exact;
+        LongT5Attention {
+            has_relative_attention_bias,
+            relative_attention_num_buckets: config.relative_attention_num_buckets,
+            relative_attention_max_distance: config.relative_attention_max_distance.unwrap_or(128),
+            d_kv: config.d_kv,
+            n_heads: config.num_heads,
+            inner_dim,
+            block_len: config.local_radius.unwrap_or(127) + 1,
+            global_block_size,
+            dropout,
+            output_attentions,
+            query,
+            key,
+            value,
+            output,
+            relative_attention_bias,
+            global_relative_attention_bias,
+            global_input_layer_norm,
+        }
+    }
 
-        let value_if_large = value_if_large.min_other(&value_if_large.full_like(num_buckets - 1));
-        ret += n.where_self(&is_small, &value_if_large);
-        ret
+    fn shape(&self, x: Tensor, bs: i64) -> Tensor {
+        x.view((bs, -1, self.n_heads, self.d_kv))
     }
 
-    fn compute_bias(&self, q_len: i64, k_len: i64, device: Device) -> Tensor {
-        let context_position = Tensor::arange(q_len, (Kind::Int64, device)).unsqueeze(1);
-        let memory_position = Tensor::arange(k_len, (Kind::Int64, device)).unsqueeze(0);
-        let relative_position = memory_position - context_position;
+    /// Forward pass through the attention layer. Unlike `T5Attention`, the `attention_mask` is the padding
+    /// mask of shape (*batch size*, *sequence_length*) (1 for tokens to attend to, 0 for padding), and the
+    /// attention weights returned have shape (*batch size*, *num_blocks*, *num_heads*, *block_len*, *key_length*).
+    pub fn forward_t(
+        &self,
+        hidden_states: &Tensor,
+        position_bias: Option<&Tensor>,
+        attention_mask: Option<&Tensor>,
+        train: bool,
+    ) -> (Tensor, Option<Tensor>, Option<Tensor>) {
+        let input_size = hidden_states.size();
+        let (bs, seq_length) = (input_size[0], input_size[1]);
 
-        let rp_bucket = self.get_relative_position_bucket(
+        let mask = match attention_mask {
+            Some(mask) => mask.to_kind(Kind::Float),
+            None => Tensor::ones(&[bs, seq_length], (Kind::Float, hidden_states.device())),
+        };
+
+        let q = self.shape(hidden_states.apply(&self.query), bs);
+        let k = self.shape(hidden_states.apply(&self.key), bs);
+        let v = self.shape(hidden_states.apply(&self.value), bs);
+
+        let q = split_into_blocks(&q, self.block_len, 1);
+        let mut k = concatenate_3_blocks(&split_into_blocks(&k, self.block_len, 1), 1, 2);
+        let mut v = concatenate_3_blocks(&split_into_blocks(&v, self.block_len, 1), 1, 2);
+
+        let global_block_ids = if let Some(global_block_size) = self.global_block_size {
+            let (block_ids, global_segment_ids) =
+                make_global_fixed_block_ids(&mask, global_block_size);
+            let global_seq_len = global_segment_ids.size()[1];
+            let global_inputs = create_global_aggregates(hidden_states, &block_ids, global_seq_len)
+                .apply(self.global_input_layer_norm.as_ref().unwrap());
+
+            let num_blocks = k.size()[1];
+            let side_k = self
+                .shape(global_inputs.apply(&self.key), bs)
+                .unsqueeze(1)
+                .repeat(&[1, num_blocks, 1, 1, 1]);
+            let side_v = self
+                .shape(global_inputs.apply(&self.value), bs)
+                .unsqueeze(1)
+                .repeat(&[1, num_blocks, 1, 1, 1]);
+            k = Tensor::cat(&[k, side_k], 2);
+            v = Tensor::cat(&[v, side_v], 2);
+            Some((block_ids, global_segment_ids))
+        } else {
+            None
+        };
+
+        let mut scores = Tensor::einsum("bnqhd,bnkhd->bnhqk", &[q, k], None);
+
+        let calculated_position_bias = if position_bias.is_none() {
+            let mut temp_value = if self.has_relative_attention_bias {
+                self.compute_bias(hidden_states.device())
+            } else {
+                Tensor::zeros(
+                    &[1, 1, self.n_heads, self.block_len, 3 * self.block_len],
+                    (scores.kind(), scores.device()),
+                )
+            };
+            let local_attention_mask = get_local_attention_mask(&mask, self.block_len)
+                .logical_not()
+                .to_kind(Kind::Float)
+                * -1e10;
+            temp_value = temp_value + local_attention_mask.transpose(1, 2);
+
+            if let Some((block_ids, global_segment_ids)) = &global_block_ids {
+                let side_position_bias = split_into_blocks(
+                    &self.compute_side_bias(&mask, block_ids, global_segment_ids),
+                    self.block_len,
+                    -2,
+                )
+                .transpose(1, 2);
+                temp_value = Tensor::cat(&[temp_value, side_position_bias], -1);
+            }
+            Some(temp_value.to_kind(scores.kind()))
+        } else {
+            None
+        };
+
+        let position_bias = if let Some(position_bias) = position_bias {
+            position_bias
+        } else {
+            calculated_position_bias.as_ref().unwrap()
+        };
+
+        scores += position_bias;
+
+        let attention_weights = scores
+            .softmax(-1, scores.kind())
+            .apply_t(&self.dropout, train);
+        let context = Tensor::einsum("bnhqk,bnkhd->bnqhd", &[&attention_weights, &v], None)
+            .contiguous()
+            .view((bs, -1, self.inner_dim))
+            .narrow(1, 0, seq_length)
+            .apply(&self.output);
+
+        let attention_weights = if self.output_attentions {
+            Some(attention_weights)
+        } else {
+            None
+        };
+
+        let position_bias = if self.has_relative_attention_bias {
+            calculated_position_bias
+        } else {
+            None
+        };
+
+        (context, attention_weights, position_bias)
+    }
+
+    fn compute_bias(&self, device: Device) -> Tensor {
+        let relative_position = make_3block_relative_position_ids(self.block_len, device);
+        let rp_bucket = get_relative_position_bucket(
             &relative_position,
-            self.is_bidirectional,
+            true,
             self.relative_attention_num_buckets,
             self.relative_attention_max_distance,
         );
@@ -292,11 +587,50 @@ impl T5Attention {
             .apply(self.relative_attention_bias.as_ref().unwrap())
             .permute(&[2, 0, 1])
             .unsqueeze(0)
+            .unsqueeze(0)
+    }
+
+    fn compute_side_bias(
+        &self,
+        mask: &Tensor,
+        block_ids: &Tensor,
+        global_segment_ids: &Tensor,
+    ) -> Tensor {
+        let side_attention_mask = mask
+            .unsqueeze(-1)
+            .eq_tensor(&global_segment_ids.unsqueeze(1).to_kind(mask.kind()))
+            .unsqueeze(1);
+        let attention_side_bias = side_attention_mask.logical_not().to_kind(Kind::Float) * -1e10;
+
+        let global_seq_len = global_segment_ids.size()[1];
+        let side_relative_position =
+            Tensor::arange(global_seq_len, (Kind::Int64, block_ids.device()))
+                - block_ids.unsqueeze(-1);
+        let side_bucket = get_relative_position_bucket(
+            &side_relative_position,
+            true,
+            self.relative_attention_num_buckets,
+            self.relative_attention_max_distance,
+        );
+        match &self.global_relative_attention_bias {
+            Some(global_relative_attention_bias) => {
+                attention_side_bias
+                    + side_bucket
+                        .apply(global_relative_attention_bias)
+                        .permute(&[0, 3, 1, 2])
+            }
+            None => attention_side_bias,
+        }
     }
 }
 
+pub enum T5SelfAttention {
+    T5Attention(T5Attention),
+    LongT5Attention(LongT5Attention),
+}
+
 pub struct T5LayerSelfAttention {
-    self_attention: T5Attention,
+    self_attention: T5SelfAttention,
     layer_norm: T5LayerNorm,
     dropout: Dropout,
 }
@@ -315,15 +649,30 @@ impl T5LayerSelfAttention {
     {
         let p = p.borrow();
 
-        let self_attention = T5Attention::new(
-            p / "SelfAttention",
-            config,
-            is_decoder,
-            !is_decoder,
-            store_cache,
-            output_attentions,
-            has_relative_attention_bias,
-        );
+        let self_attention = match config.encoder_attention_type {
+            Some(encoder_attention_type) if !is_decoder => {
+                let path = match encoder_attention_type {
+                    EncoderAttentionType::Local => "LocalSelfAttention",
+                    EncoderAttentionType::TransientGlobal => "TransientGlobalSelfAttention",
+                };
+                T5SelfAttention::LongT5Attention(LongT5Attention::new(
+                    p / path,
+                    config,
+                    encoder_attention_type,
+                    output_attentions,
+                    has_relative_attention_bias,
+                ))
+            }
+            _ => T5SelfAttention::T5Attention(T5Attention::new(
+                p / "SelfAttention",
+                config,
+                is_decoder,
+                !is_decoder,
+                store_cache,
+                output_attentions,
+                has_relative_attention_bias,
+            )),
+        };
 
         let layer_norm =
             T5LayerNorm::new(p / "layer_norm", config.d_model, config.layer_norm_epsilon);
@@ -345,15 +694,22 @@ impl T5LayerSelfAttention {
         train: bool,
     ) -> (Tensor, Option<Tensor>, Option<Tensor>, Option<LayerState>) {
         let norm_x = hidden_states.apply(&self.layer_norm);
-        let (y, attention_weights, position_bias, layer_state) = self.self_attention.forward_t(
-            &norm_x,
-            None,
-            position_bias,
-            attention_mask,
-            layer_state,
-            None,
-            train,
-        );
+        let (y, attention_weights, position_bias, layer_state) = match &self.self_attention {
+            T5SelfAttention::T5Attention(ref attention) => attention.forward_t(
+                &norm_x,
+                None,
+                position_bias,
+                attention_mask,
+                layer_state,
+                None,
+                train,
+            ),
+            T5SelfAttention::LongT5Attention(ref attention) => {
+                let (y, attention_weights, position_bias) =
+                    attention.forward_t(&norm_x, position_bias, attention_mask, train);
+                (y, attention_weights, position_bias, None)
+            }
+        };
 
         let output = hidden_states + y.apply_t(&self.dropout, train);
 
@@ -305,6 +305,7 @@ pub struct T5Stack {
     output_attentions: bool,
     output_hidden_states: bool,
     is_decoder: bool,
+    local_attention: bool,
     store_cache: bool,
 }
 
@@ -325,7 +326,12 @@ impl T5Stack {
 
         let mut blocks: Vec<T5Block> = vec![];
         let p_layers = p / "block";
-        for layer_index in 0..config.num_layers {
+        let num_layers = if is_decoder {
+            config.num_decoder_layers.unwrap_or(config.num_layers)
+        } else {
+            config.num_layers
+        };
+        for layer_index in 0..num_layers {
             blocks.push(T5Block::new(
                 &p_layers / layer_index,
                 config,
@@ -349,6 +355,7 @@ impl T5Stack {
             output_attentions,
             output_hidden_states,
             is_decoder,
+            local_attention: !is_decoder & config.encoder_attention_type.is_some(),
             store_cache,
         }
     }
@@ -399,36 +406,46 @@ impl T5Stack {
             Some(value) => value,
             None => calculated_attention_mask.as_ref().unwrap(),
         };
-        let extended_attention_mask = match attention_mask.dim() {
-            3 => attention_mask.unsqueeze(1),
-            2 => {
-                if self.is_decoder {
-                    let seq_ids = Tensor::arange(
-                        input_shape[1],
-                        (input_embeddings.kind(), input_embeddings.device()),
-                    );
-                    let causal_mask = seq_ids.unsqueeze(0).unsqueeze(0).repeat(&[
-                        input_shape[0],
-                        input_shape[1],
-                        1,
-                    ]);
-                    let causal_mask = causal_mask.le_tensor(&seq_ids.unsqueeze(0).unsqueeze(-1));
-                    causal_mask.unsqueeze(1) * attention_mask.unsqueeze(1).unsqueeze(1)
-                } else {
-                    attention_mask.unsqueeze(1).unsqueeze(1)
-                }
-            }
-            _ => {
+        let extended_attention_mask = if self.local_attention {
+            // LongT5 local attention layers build their block-wise masks from the padding mask
+            if attention_mask.dim() != 2 {
                 return Err(RustBertError::ValueError(
-                    "Invalid attention mask dimension, must be 2 or 3".into(),
+                    "Invalid attention mask dimension, LongT5 local attention requires a 2D mask"
+                        .into(),
                 ));
             }
-        };
-
-        let extended_attention_mask: Option<Tensor> = Some(
+            attention_mask.to_kind(input_embeddings.kind())
+        } else {
+            let extended_attention_mask = match attention_mask.dim() {
+                3 => attention_mask.unsqueeze(1),
+                2 => {
+                    if self.is_decoder {
+                        let seq_ids = Tensor::arange(
+                            input_shape[1],
+                            (input_embeddings.kind(), input_embeddings.device()),
+                        );
+                        let causal_mask = seq_ids.unsqueeze(0).unsqueeze(0).repeat(&[
+                            input_shape[0],
+                            input_shape[1],
+                            1,
+                        ]);
+                        let causal_mask =
+                            causal_mask.le_tensor(&seq_ids.unsqueeze(0).unsqueeze(-1));
+                        causal_mask.unsqueeze(1) * attention_mask.unsqueeze(1).unsqueeze(1)
+                    } else {
+                        attention_mask.unsqueeze(1).unsqueeze(1)
+                    }
+                }
+                _ => {
+                    return Err(RustBertError::ValueError(
+                        "Invalid attention mask dimension, must be 2 or 3".into(),
+                    ));
+                }
+            };
             ((extended_attention_mask.ones_like() - extended_attention_mask) * -1e4)
-                .to_kind(input_embeddings.kind()),
-        );
+                .to_kind(input_embeddings.kind())
+        };
+        let extended_attention_mask = Some(extended_attention_mask);
 
         let extended_encoder_attention_mask = if self.is_decoder & encoder_hidden_states.is_some() {
             let encoder_hidden_states = encoder_hidden_states.as_ref().unwrap();
@@ -14,6 +14,11 @@
 //!
 //! Pretrained models for a number of language pairs are available and can be downloaded using RemoteResources.
 //!
+//! T5 v1.1 checkpoints (including Flan-T5) are supported through the `feed_forward_proj` (`gated-gelu`) and
+//! `tie_word_embeddings` configuration options. LongT5 checkpoints are loaded with the same model and `ModelType::T5`:
+//! setting `encoder_attention_type` to `local` or `transient-global` replaces the encoder self-attention with
+//! the LongT5 local (`local_radius`) or transient-global (`local_radius` and `global_block_size`) attention.
+//!
 //! ```no_run
 //! # fn main() -> anyhow::Result<()> {
 //! #
@@ -55,7 +60,7 @@ mod t5_model;
 
 pub use attention::LayerState;
 pub use t5_model::{
-    T5Config, T5ConfigResources, T5ForConditionalGeneration, T5ForSentenceEmbeddings, T5Generator,
-    T5Model, T5ModelOutput, T5ModelResources, T5Prefix, T5SourceLanguages, T5TargetLanguages,
-    T5VocabResources,
+    EncoderAttentionType, FeedForwardProj, T5Config, T5ConfigResources, T5ForConditionalGeneration,
+    T5ForSentenceEmbeddings, T5Generator, T5Model, T5ModelOutput, T5ModelResources, T5Prefix,
+    T5SourceLanguages, T5TargetLanguages, T5VocabResources,
 };
@@ -124,6 +124,17 @@ pub enum FeedForwardProj {
     GatedGelu,
 }
 
+#[derive(Clone, Debug, Serialize, Deserialize, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+/// # Options for the LongT5 encoder self-attention
+pub enum EncoderAttentionType {
+    /// Local attention: each token attends to the tokens within `local_radius`
+    Local,
+    /// Transient-global attention: local attention extended with attention to the aggregated
+    /// representations of blocks of `global_block_size` tokens
+    TransientGlobal,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 /// # T5 model configuration
 /// Defines the T5 model architecture (e.g. number of layers, hidden layer size, label mapping...)
@@ -140,6 +151,7 @@ pub struct T5Config {
     pub layer_norm_epsilon: f64,
     pub num_heads: i64,
     pub num_layers: i64,
+    pub num_decoder_layers: Option<i64>,
     pub output_past: Option<bool>,
     pub pad_token_id: Option<i64>,
     pub relative_attention_num_buckets: i64,
@@ -147,6 +159,9 @@ pub struct T5Config {
     pub vocab_size: i64,
     pub feed_forward_proj: Option<FeedForwardProj>,
     pub tie_word_embeddings: Option<bool>,
+    pub encoder_attention_type: Option<EncoderAttentionType>,
+    pub local_radius: Option<i64>,
+    pub global_block_size: Option<i64>,
     task_specific_params: Option<TaskSpecificParams>,
     pub output_attentions: Option<bool>,
     pub output_hidden_states: Option<bool>,
@@ -218,6 +233,7 @@ impl Default for T5Config {
             layer_norm_epsilon: 1e-6,
             num_heads: 8,
             num_layers: 6,
+            num_decoder_layers: None,
             output_past: None,
             pad_token_id: Some(0),
             relative_attention_num_buckets: 32,
@@ -225,6 +241,9 @@ impl Default for T5Config {
             vocab_size: 32128,
             feed_forward_proj: Some(FeedForwardProj::Relu),
             tie_word_embeddings: None,
+            encoder_attention_type: None,
+            local_radius: None,
+            global_block_size: None,
             task_specific_params: None,
             output_attentions: None,
             output_hidden_states: None,
@@ -736,8 +755,13 @@ impl LMHeadModel for T5ForConditionalGeneration {
         Ok(LMModelOutput {
             lm_logits,
             cache: Cache::T5Cache(base_model_output.next_cache),
+            loss: None,
         })
     }
+
+    fn predicts_next_token(&self) -> bool {
+        false
+    }
 }
 
 /// # T5 for sentence embeddings
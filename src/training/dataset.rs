@@ -0,0 +1,266 @@
+// Copyright 2023 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! # Training examples and batches
+//! Tokenized training examples and their collation into padded batches of tensors.
+
+use crate::common::error::RustBertError;
+use crate::common::loss::IGNORE_INDEX;
+use rust_tokenizers::TokenizedInput;
+use tch::{Device, Kind, Tensor};
+
+#[derive(Debug, Clone, PartialEq)]
+/// # Label of a training example
+pub enum TrainingLabel {
+    /// Class index of the sequence (single-label classification)
+    Class(i64),
+    /// Target values of the sequence: a single value for regression, or one value (0 or 1) per label for
+    /// multi-label classification
+    Values(Vec<f32>),
+    /// Class index of each token (token classification). Tokens with a label of -100 (e.g. special tokens
+    /// or continuation sub-tokens) do not contribute to the loss.
+    Tokens(Vec<i64>),
+}
+
+#[derive(Debug, Clone)]
+/// # Tokenized training example
+pub struct TrainingExample {
+    /// Token ids of the example
+    pub input_ids: Vec<i64>,
+    /// Segment ids of the example (0 for the first sentence, 1 for the second sentence)
+    pub token_type_ids: Vec<i8>,
+    /// Training label
+    pub label: TrainingLabel,
+}
+
+impl TrainingExample {
+    /// Creates a new training example from a tokenized input and its label
+    ///
+    /// # Arguments
+    ///
+    /// * `tokenized_input` - `TokenizedInput` obtained from the model tokenizer (including special tokens)
+    /// * `label` - `TrainingLabel` of the example
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::common::{ModelType, TokenizerOption};
+    /// use rust_bert::training::dataset::{TrainingExample, TrainingLabel};
+    /// use rust_tokenizers::tokenizer::TruncationStrategy;
+    ///
+    /// let tokenizer = TokenizerOption::from_file(
+    ///     ModelType::DistilBert,
+    ///     "path/to/vocab.txt",
+    ///     None,
+    ///     true,
+    ///     None,
+    ///     None,
+    /// )?;
+    /// let tokenized_input = tokenizer.encode_list(
+    ///     &["This movie was great"],
+    ///     128,
+    ///     &TruncationStrategy::LongestFirst,
+    ///     0,
+    /// );
+    /// let example = TrainingExample::new(
+    ///     tokenized_input.into_iter().next().unwrap(),
+    ///     TrainingLabel::Class(1),
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn new(tokenized_input: TokenizedInput, label: TrainingLabel) -> TrainingExample {
+        TrainingExample {
+            input_ids: tokenized_input.token_ids,
+            token_type_ids: tokenized_input.segment_ids,
+            label,
+        }
+    }
+}
+
+/// # Batch of padded training examples
+pub struct TrainingBatch {
+    /// Token ids of shape (*batch size*, *sequence_length*)
+    pub input_ids: Tensor,
+    /// Attention mask of shape (*batch size*, *sequence_length*), with a value of 0 for padding positions
+    pub attention_mask: Tensor,
+    /// Segment ids of shape (*batch size*, *sequence_length*)
+    pub token_type_ids: Tensor,
+    /// Labels of shape (*batch size*) for classification, (*batch size*, *num_labels*) for regression and
+    /// multi-label classification or (*batch size*, *sequence_length*) for token classification
+    pub labels: Tensor,
+}
+
+impl TrainingBatch {
+    /// Pads a set of training examples to the length of the longest example and stacks them into tensors.
+    /// Token labels are padded with -100 so that padding positions are ignored by the loss.
+    ///
+    /// # Arguments
+    ///
+    /// * `examples` - Slice of `TrainingExample` to collate. All examples must have the same label type.
+    /// * `pad_token_id` - Token id used to pad the input ids
+    /// * `device` - `Device` on which the tensors are created
+    ///
+    /// # Returns
+    ///
+    /// * `TrainingBatch` containing the padded inputs and labels
+    pub fn from_examples(
+        examples: &[TrainingExample],
+        pad_token_id: i64,
+        device: Device,
+    ) -> Result<TrainingBatch, RustBertError> {
+        let max_length = examples
+            .iter()
+            .map(|example| example.input_ids.len())
+            .max()
+            .ok_or_else(|| {
+                RustBertError::ValueError("Cannot create a batch without examples".to_string())
+            })?;
+
+        let mut input_ids = Vec::with_capacity(examples.len() * max_length);
+        let mut attention_mask = Vec::with_capacity(examples.len() * max_length);
+        let mut token_type_ids = Vec::with_capacity(examples.len() * max_length);
+        for example in examples {
+            let padding_length = max_length - example.input_ids.len();
+            input_ids.extend_from_slice(&example.input_ids);
+            input_ids.extend(std::iter::repeat_n(pad_token_id, padding_length));
+            attention_mask.extend(std::iter::repeat_n(1i64, example.input_ids.len()));
+            attention_mask.extend(std::iter::repeat_n(0i64, padding_length));
+            token_type_ids.extend(example.token_type_ids.iter().map(|&id| id as i64));
+            token_type_ids.extend(std::iter::repeat_n(
+                0i64,
+                max_length - example.token_type_ids.len(),
+            ));
+        }
+        let shape = [examples.len() as i64, max_length as i64];
+        let labels = collate_labels(examples, max_length)?;
+
+        Ok(TrainingBatch {
+            input_ids: Tensor::of_slice(&input_ids).view(shape).to(device),
+            attention_mask: Tensor::of_slice(&attention_mask).view(shape).to(device),
+            token_type_ids: Tensor::of_slice(&token_type_ids).view(shape).to(device),
+            labels: labels.to(device),
+        })
+    }
+}
+
+fn collate_labels(
+    examples: &[TrainingExample],
+    max_length: usize,
+) -> Result<Tensor, RustBertError> {
+    let mismatched_labels = || {
+        RustBertError::ValueError(
+            "All examples of a batch must have the same label type".to_string(),
+        )
+    };
+    match &examples[0].label {
+        TrainingLabel::Class(_) => {
+            let labels = examples
+                .iter()
+                .map(|example| match example.label {
+                    TrainingLabel::Class(label) => Ok(label),
+                    _ => Err(mismatched_labels()),
+                })
+                .collect::<Result<Vec<i64>, RustBertError>>()?;
+            Ok(Tensor::of_slice(&labels))
+        }
+        TrainingLabel::Values(first_values) => {
+            let num_values = first_values.len();
+            let mut labels = Vec::with_capacity(examples.len() * num_values);
+            for example in examples {
+                match &example.label {
+                    TrainingLabel::Values(values) if values.len() == num_values => {
+                        labels.extend_from_slice(values)
+                    }
+                    TrainingLabel::Values(_) => {
+                        return Err(RustBertError::ValueError(
+                            "All examples of a batch must have the same number of target values"
+                                .to_string(),
+                        ));
+                    }
+                    _ => return Err(mismatched_labels()),
+                }
+            }
+            Ok(Tensor::of_slice(&labels)
+                .view([examples.len() as i64, num_values as i64])
+                .to_kind(Kind::Float))
+        }
+        TrainingLabel::Tokens(_) => {
+            let mut labels = Vec::with_capacity(examples.len() * max_length);
+            for example in examples {
+                match &example.label {
+                    TrainingLabel::Tokens(token_labels)
+                        if token_labels.len() == example.input_ids.len() =>
+                    {
+                        labels.extend_from_slice(token_labels);
+                        labels.extend(std::iter::repeat_n(
+                            IGNORE_INDEX,
+                            max_length - token_labels.len(),
+                        ));
+                    }
+                    TrainingLabel::Tokens(_) => {
+                        return Err(RustBertError::ValueError(
+                            "Token labels must have the same length as the input ids".to_string(),
+                        ));
+                    }
+                    _ => return Err(mismatched_labels()),
+                }
+            }
+            Ok(Tensor::of_slice(&labels).view([examples.len() as i64, max_length as i64]))
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_batch_padding() {
+        let examples = vec![
+            TrainingExample {
+                input_ids: vec![101, 7, 102],
+                token_type_ids: vec![0, 0, 0],
+                label: TrainingLabel::Tokens(vec![-100, 3, -100]),
+            },
+            TrainingExample {
+                input_ids: vec![101, 102],
+                token_type_ids: vec![0, 0],
+                label: TrainingLabel::Tokens(vec![-100, -100]),
+            },
+        ];
+        let batch = TrainingBatch::from_examples(&examples, 0, Device::Cpu).unwrap();
+        assert_eq!(batch.input_ids.size(), vec![2, 3]);
+        assert_eq!(
+            Vec::<i64>::from(batch.input_ids.view([-1])),
+            vec![101, 7, 102, 101, 102, 0]
+        );
+        assert_eq!(
+            Vec::<i64>::from(batch.attention_mask.view([-1])),
+            vec![1, 1, 1, 1, 1, 0]
+        );
+        assert_eq!(
+            Vec::<i64>::from(batch.labels.view([-1])),
+            vec![-100, 3, -100, -100, -100, -100]
+        );
+
+        let mixed_examples = vec![
+            examples[0].clone(),
+            TrainingExample {
+                input_ids: vec![101, 102],
+                token_type_ids: vec![0, 0],
+                label: TrainingLabel::Class(1),
+            },
+        ];
+        assert!(TrainingBatch::from_examples(&mixed_examples, 0, Device::Cpu).is_err());
+    }
+}
@@ -0,0 +1,66 @@
+//! # Fine-tuning of classification models
+//!
+//! Training loop to fine-tune sequence classification and token classification models in Rust, without
+//! exporting them to Python:
+//! - [`dataset`]: tokenized training examples and their collation into padded batches
+//! - [`scheduler`]: linear warmup and decay learning rate schedule
+//! - [`trainer`]: `Trainer` optimizing the model with AdamW, with gradient accumulation, gradient clipping,
+//!   evaluation hooks and checkpointing
+//!
+//! The model heads compute the loss from the labels of the batches (cross-entropy for single-label and token
+//! classification, mean squared error for regression and binary cross-entropy for multi-label classification).
+//! Checkpoints contain the weights (`rust_model.ot`) and the configuration with the new label mapping
+//! (`config.json`), and can be loaded by the `SequenceClassificationModel` and `TokenClassificationModel`
+//! pipelines with local resources.
+//!
+//! ```no_run
+//! use rust_bert::pipelines::common::{ConfigOption, ModelType, TokenizerOption};
+//! use rust_bert::pipelines::sequence_classification::SequenceClassificationOption;
+//! use rust_bert::training::dataset::{TrainingExample, TrainingLabel};
+//! use rust_bert::training::trainer::{Trainer, TrainingConfig};
+//! use rust_tokenizers::tokenizer::TruncationStrategy;
+//! use std::collections::HashMap;
+//! use tch::{nn, Device};
+//! # fn main() -> anyhow::Result<()> {
+//! let tokenizer = TokenizerOption::from_file(
+//!     ModelType::DistilBert,
+//!     "path/to/vocab.txt",
+//!     None,
+//!     true,
+//!     None,
+//!     None,
+//! )?;
+//! let mut model_config = ConfigOption::from_file(ModelType::DistilBert, "path/to/config.json");
+//! model_config.set_label_mapping(HashMap::from([
+//!     (0, "negative".to_string()),
+//!     (1, "positive".to_string()),
+//! ]))?;
+//! let mut var_store = nn::VarStore::new(Device::Cpu);
+//! let model =
+//!     SequenceClassificationOption::new(ModelType::DistilBert, var_store.root(), &model_config)?;
+//! var_store.load_partial("path/to/rust_model.ot")?;
+//!
+//! let texts = ["A great movie", "A waste of time"];
+//! let labels = [1, 0];
+//! let examples = tokenizer
+//!     .encode_list(&texts, 128, &TruncationStrategy::LongestFirst, 0)
+//!     .into_iter()
+//!     .zip(labels)
+//!     .map(|(input, label)| TrainingExample::new(input, TrainingLabel::Class(label)))
+//!     .collect::<Vec<TrainingExample>>();
+//!
+//! let training_config = TrainingConfig {
+//!     warmup_steps: 10,
+//!     pad_token_id: tokenizer.get_pad_id().unwrap(),
+//!     ..TrainingConfig::new(5e-5, 16, 3)
+//! };
+//! let mut trainer = Trainer::new(model, var_store, model_config, training_config)?;
+//! trainer.train(&examples)?;
+//! trainer.save_checkpoint("path/to/output")?;
+//! # Ok(())
+//! # }
+//! ```
+
+pub mod dataset;
+pub mod scheduler;
+pub mod trainer;
@@ -0,0 +1,95 @@
+// Copyright 2023 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! # Learning rate schedules
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// # Linear warmup and decay learning rate schedule
+/// The learning rate increases linearly from 0 to the peak learning rate during the warmup steps, and then
+/// decreases linearly to 0 at the last training step (equivalent to `get_linear_schedule_with_warmup` in
+/// the Python Transformers library).
+pub struct LinearWarmupScheduler {
+    /// Peak learning rate, reached at the end of the warmup
+    pub learning_rate: f64,
+    /// Number of warmup steps
+    pub warmup_steps: usize,
+    /// Total number of optimization steps. If None, the learning rate stays constant after the warmup.
+    pub total_steps: Option<usize>,
+}
+
+impl LinearWarmupScheduler {
+    /// Creates a new linear warmup scheduler
+    ///
+    /// # Arguments
+    ///
+    /// * `learning_rate` - Peak learning rate, reached at the end of the warmup
+    /// * `warmup_steps` - Number of warmup steps
+    /// * `total_steps` - Total number of optimization steps (the learning rate is constant after the warmup if None)
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// use rust_bert::training::scheduler::LinearWarmupScheduler;
+    ///
+    /// let scheduler = LinearWarmupScheduler::new(5e-5, 100, Some(1000));
+    /// let learning_rate = scheduler.get_learning_rate(50);
+    /// ```
+    pub fn new(
+        learning_rate: f64,
+        warmup_steps: usize,
+        total_steps: impl Into<Option<usize>>,
+    ) -> LinearWarmupScheduler {
+        LinearWarmupScheduler {
+            learning_rate,
+            warmup_steps,
+            total_steps: total_steps.into(),
+        }
+    }
+
+    /// Returns the learning rate to use for an optimization step
+    ///
+    /// # Arguments
+    ///
+    /// * `step` - Number of optimization steps already performed
+    pub fn get_learning_rate(&self, step: usize) -> f64 {
+        if step < self.warmup_steps {
+            return self.learning_rate * step as f64 / self.warmup_steps as f64;
+        }
+        match self.total_steps {
+            Some(total_steps) => {
+                let decay_steps = total_steps.saturating_sub(self.warmup_steps).max(1);
+                let remaining_steps = total_steps.saturating_sub(step);
+                self.learning_rate * remaining_steps as f64 / decay_steps as f64
+            }
+            None => self.learning_rate,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_linear_warmup_schedule() {
+        let scheduler = LinearWarmupScheduler::new(1.0, 10, Some(110));
+        assert_eq!(scheduler.get_learning_rate(0), 0.0);
+        assert_eq!(scheduler.get_learning_rate(5), 0.5);
+        assert_eq!(scheduler.get_learning_rate(10), 1.0);
+        assert_eq!(scheduler.get_learning_rate(60), 0.5);
+        assert_eq!(scheduler.get_learning_rate(110), 0.0);
+        assert_eq!(scheduler.get_learning_rate(200), 0.0);
+
+        let scheduler = LinearWarmupScheduler::new(1.0, 0, None);
+        assert_eq!(scheduler.get_learning_rate(0), 1.0);
+        assert_eq!(scheduler.get_learning_rate(1000), 1.0);
+    }
+}
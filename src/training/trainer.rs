@@ -0,0 +1,807 @@
+// Copyright 2023 Guillaume Becquin
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//     http://www.apache.org/licenses/LICENSE-2.0
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! # Training loop
+//! Fine-tuning of sequence and token classification models with AdamW, a linear warmup schedule,
+//! gradient accumulation and gradient clipping.
+
+use crate::common::error::RustBertError;
+use crate::common::loss::IGNORE_INDEX;
+use crate::pipelines::common::ConfigOption;
+use crate::pipelines::sequence_classification::SequenceClassificationOption;
+use crate::pipelines::token_classification::TokenClassificationOption;
+use crate::training::dataset::{TrainingBatch, TrainingExample};
+use crate::training::scheduler::LinearWarmupScheduler;
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tch::nn::{OptimizerConfig, VarStore};
+use tch::{nn, no_grad, Kind, Tensor};
+
+/// # Model that can be fine-tuned by the `Trainer`
+pub trait TrainableModel {
+    /// Forward pass on a batch of training examples
+    ///
+    /// # Arguments
+    ///
+    /// * `batch` - `TrainingBatch` containing the inputs and labels
+    /// * `train` - boolean flag to turn on/off the dropout layers in the model
+    ///
+    /// # Returns
+    ///
+    /// * `(Tensor, Tensor)` containing the logits and the scalar loss
+    fn forward_loss(
+        &self,
+        batch: &TrainingBatch,
+        train: bool,
+    ) -> Result<(Tensor, Tensor), RustBertError>;
+}
+
+impl TrainableModel for SequenceClassificationOption {
+    fn forward_loss(
+        &self,
+        batch: &TrainingBatch,
+        train: bool,
+    ) -> Result<(Tensor, Tensor), RustBertError> {
+        let (logits, loss) = self.forward_t_with_labels(
+            Some(&batch.input_ids),
+            Some(&batch.attention_mask),
+            Some(&batch.token_type_ids),
+            None,
+            None,
+            Some(&batch.labels),
+            train,
+        );
+        Ok((logits, loss.ok_or_else(missing_loss)?))
+    }
+}
+
+impl TrainableModel for TokenClassificationOption {
+    fn forward_loss(
+        &self,
+        batch: &TrainingBatch,
+        train: bool,
+    ) -> Result<(Tensor, Tensor), RustBertError> {
+        let (logits, loss) = self.forward_t_with_labels(
+            Some(&batch.input_ids),
+            Some(&batch.attention_mask),
+            Some(&batch.token_type_ids),
+            None,
+            None,
+            Some(&batch.labels),
+            train,
+        );
+        Ok((logits, loss.ok_or_else(missing_loss)?))
+    }
+}
+
+fn missing_loss() -> RustBertError {
+    RustBertError::ValueError("The model did not return a loss for the labels provided".to_string())
+}
+
+/// Optimizer parameter group of the variables excluded from weight decay
+const NO_DECAY_GROUP: usize = 1;
+
+fn is_no_decay_variable(name: &str) -> bool {
+    name.ends_with("bias")
+        || name.contains("LayerNorm")
+        || name.contains("layer_norm")
+        || name.contains("layernorm")
+}
+
+/// Moves the biases and layer normalization weights of the `VarStore` to the `NO_DECAY_GROUP` parameter
+/// group, returning true if any variable was moved.
+fn set_no_decay_group(var_store: &VarStore) -> bool {
+    let mut variables = var_store.variables_.lock().unwrap();
+    let no_decay_pointers = variables
+        .named_variables
+        .iter()
+        .filter(|(name, _)| is_no_decay_variable(name))
+        .map(|(_, variable)| variable.data_ptr())
+        .collect::<HashSet<_>>();
+    let mut has_no_decay_group = false;
+    for variable in variables.trainable_variables.iter_mut() {
+        if no_decay_pointers.contains(&variable.tensor.data_ptr()) {
+            variable.group = NO_DECAY_GROUP;
+            has_no_decay_group = true;
+        }
+    }
+    has_no_decay_group
+}
+
+/// Rescales the gradients of the trainable variables so that their global norm does not exceed `max_norm`.
+/// Unlike `Optimizer::clip_grad_norm`, variables without a gradient (frozen, or not contributing to the
+/// loss such as the pooler of token classification models) are skipped.
+fn clip_grad_norm(var_store: &VarStore, max_norm: f64) {
+    let mut gradients = var_store
+        .trainable_variables()
+        .iter()
+        .filter(|variable| variable.requires_grad())
+        .map(Tensor::grad)
+        .filter(Tensor::defined)
+        .collect::<Vec<Tensor>>();
+    let total_norm = gradients
+        .iter()
+        .map(|gradient| f64::from(gradient.norm()).powi(2))
+        .sum::<f64>()
+        .sqrt();
+    let clip_coefficient = max_norm / (total_norm + 1e-6);
+    if clip_coefficient < 1.0 {
+        no_grad(|| {
+            for gradient in gradients.iter_mut() {
+                let _ = gradient.g_mul_scalar_(clip_coefficient);
+            }
+        });
+    }
+}
+
+/// # Configuration for fine-tuning a model
+pub struct TrainingConfig {
+    /// Peak learning rate (default: 5e-5)
+    pub learning_rate: f64,
+    /// Decoupled weight decay of the AdamW optimizer, not applied to the biases and layer normalization
+    /// weights (default: 0.01)
+    pub weight_decay: f64,
+    /// Exponential decay rate for the first moment estimates of AdamW (default: 0.9)
+    pub adam_beta1: f64,
+    /// Exponential decay rate for the second moment estimates of AdamW (default: 0.999)
+    pub adam_beta2: f64,
+    /// Number of examples per forward pass (default: 16)
+    pub batch_size: usize,
+    /// Number of batches over which the gradients are accumulated before an optimization step (default: 1)
+    pub gradient_accumulation_steps: usize,
+    /// Maximum L2 norm of the gradients, if clipping is enabled (default: 1.0)
+    pub max_grad_norm: Option<f64>,
+    /// Number of passes over the training set (default: 3)
+    pub num_epochs: usize,
+    /// Number of warmup steps of the linear learning rate schedule (default: 0)
+    pub warmup_steps: usize,
+    /// Total number of optimization steps for the learning rate decay. Computed from the size of the
+    /// training set by `Trainer::train`, must be set when training with `Trainer::train_epoch` for
+    /// the learning rate to decay.
+    pub num_training_steps: Option<usize>,
+    /// Shuffle the training set at each epoch (default: true)
+    pub shuffle: bool,
+    /// Seed for the shuffling of the training set
+    pub seed: Option<i64>,
+    /// Token id used to pad the inputs, should be set to the tokenizer padding token id (default: 0)
+    pub pad_token_id: i64,
+    /// Directory in which a checkpoint is saved at the end of each epoch, if provided
+    pub output_dir: Option<PathBuf>,
+}
+
+impl TrainingConfig {
+    /// Instantiate a new training configuration with the default hyperparameters for the learning
+    /// rate, batch size and number of epochs provided
+    ///
+    /// # Arguments
+    ///
+    /// * `learning_rate` - Peak learning rate
+    /// * `batch_size` - Number of examples per forward pass
+    /// * `num_epochs` - Number of passes over the training set
+    pub fn new(learning_rate: f64, batch_size: usize, num_epochs: usize) -> TrainingConfig {
+        TrainingConfig {
+            learning_rate,
+            batch_size,
+            num_epochs,
+            ..Default::default()
+        }
+    }
+}
+
+impl Default for TrainingConfig {
+    fn default() -> TrainingConfig {
+        TrainingConfig {
+            learning_rate: 5e-5,
+            weight_decay: 0.01,
+            adam_beta1: 0.9,
+            adam_beta2: 0.999,
+            batch_size: 16,
+            gradient_accumulation_steps: 1,
+            max_grad_norm: Some(1.0),
+            num_epochs: 3,
+            warmup_steps: 0,
+            num_training_steps: None,
+            shuffle: true,
+            seed: None,
+            pad_token_id: 0,
+            output_dir: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+/// # State of the training
+pub struct TrainingState {
+    /// Number of completed epochs
+    pub epoch: usize,
+    /// Number of optimization steps performed
+    pub global_step: usize,
+    /// Learning rate of the last optimization step
+    pub learning_rate: f64,
+    /// Mean training loss over the last epoch
+    pub train_loss: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// # Output of an evaluation of the model
+pub struct EvaluationOutput {
+    /// Mean loss over the evaluation set
+    pub loss: f64,
+    /// Predicted class indices of the examples (sequence classification) or labelled tokens (token
+    /// classification). Empty for regression and multi-label classification.
+    pub predictions: Vec<i64>,
+    /// Reference class indices matching the predictions
+    pub references: Vec<i64>,
+}
+
+/// # Hook called after each evaluation of the model during training
+/// Can be used to compute metrics (for example with the functions of the `evaluation` module), log the
+/// progress or save the best checkpoint. Implemented for closures taking the training state and the
+/// evaluation output.
+pub trait EvaluationHook {
+    /// Called at the end of each epoch with the output of the evaluation on the evaluation set
+    fn on_evaluation(
+        &mut self,
+        state: &TrainingState,
+        output: &EvaluationOutput,
+    ) -> Result<(), RustBertError>;
+}
+
+impl<F> EvaluationHook for F
+where
+    F: FnMut(&TrainingState, &EvaluationOutput) -> Result<(), RustBertError>,
+{
+    fn on_evaluation(
+        &mut self,
+        state: &TrainingState,
+        output: &EvaluationOutput,
+    ) -> Result<(), RustBertError> {
+        self(state, output)
+    }
+}
+
+/// # Trainer for sequence and token classification models
+/// Fine-tunes a `TrainableModel` (`SequenceClassificationOption` or `TokenClassificationOption`) with the
+/// AdamW optimizer and a linear warmup learning rate schedule. Checkpoints contain the model weights
+/// (`rust_model.ot`) and configuration (`config.json`, including the label mapping), and can be loaded
+/// by the corresponding pipelines.
+pub struct Trainer<M: TrainableModel> {
+    model: M,
+    var_store: VarStore,
+    model_config: ConfigOption,
+    optimizer: nn::Optimizer,
+    scheduler: LinearWarmupScheduler,
+    config: TrainingConfig,
+    state: TrainingState,
+    accumulated_batches: usize,
+    evaluation_set: Option<(Vec<TrainingExample>, Box<dyn EvaluationHook>)>,
+}
+
+impl<M: TrainableModel> Trainer<M> {
+    /// Creates a new trainer.
+    ///
+    /// # Arguments
+    ///
+    /// * `model` - Model to fine-tune, with its variables registered in `var_store`
+    /// * `var_store` - `VarStore` holding the model variables (with the pretrained weights loaded)
+    /// * `model_config` - `ConfigOption` used to create the model, saved with the checkpoints
+    /// * `config` - `TrainingConfig` with the training hyperparameters
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # fn main() -> anyhow::Result<()> {
+    /// use rust_bert::pipelines::common::{ConfigOption, ModelType};
+    /// use rust_bert::pipelines::sequence_classification::SequenceClassificationOption;
+    /// use rust_bert::training::trainer::{Trainer, TrainingConfig};
+    /// use std::collections::HashMap;
+    /// use tch::{nn, Device};
+    ///
+    /// let mut model_config = ConfigOption::from_file(ModelType::DistilBert, "path/to/config.json");
+    /// model_config.set_label_mapping(HashMap::from([
+    ///     (0, "negative".to_string()),
+    ///     (1, "positive".to_string()),
+    /// ]))?;
+    /// let mut var_store = nn::VarStore::new(Device::Cpu);
+    /// let model =
+    ///     SequenceClassificationOption::new(ModelType::DistilBert, var_store.root(), &model_config)?;
+    /// var_store.load_partial("path/to/rust_model.ot")?;
+    ///
+    /// let trainer = Trainer::new(model, var_store, model_config, TrainingConfig::default())?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn new(
+        model: M,
+        var_store: VarStore,
+        model_config: ConfigOption,
+        config: TrainingConfig,
+    ) -> Result<Trainer<M>, RustBertError> {
+        if config.batch_size == 0 || config.gradient_accumulation_steps == 0 {
+            return Err(RustBertError::InvalidConfigurationError(
+                "The batch size and gradient accumulation steps must be positive".to_string(),
+            ));
+        }
+        let has_no_decay_group = set_no_decay_group(&var_store);
+        let mut optimizer = nn::adamw(config.adam_beta1, config.adam_beta2, config.weight_decay)
+            .build(&var_store, config.learning_rate)?;
+        if has_no_decay_group {
+            optimizer.set_weight_decay_group(NO_DECAY_GROUP, 0.0);
+        }
+        let scheduler = LinearWarmupScheduler::new(
+            config.learning_rate,
+            config.warmup_steps,
+            config.num_training_steps,
+        );
+        Ok(Trainer {
+            model,
+            var_store,
+            model_config,
+            optimizer,
+            scheduler,
+            config,
+            state: TrainingState {
+                epoch: 0,
+                global_step: 0,
+                learning_rate: 0.0,
+                train_loss: 0.0,
+            },
+            accumulated_batches: 0,
+            evaluation_set: None,
+        })
+    }
+
+    /// Sets an evaluation set, on which the model is evaluated at the end of each epoch. The hook is
+    /// called with the result of each evaluation.
+    ///
+    /// # Arguments
+    ///
+    /// * `examples` - Evaluation examples
+    /// * `hook` - `EvaluationHook` called after each evaluation
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use rust_bert::pipelines::sequence_classification::SequenceClassificationOption;
+    /// # use rust_bert::training::trainer::Trainer;
+    /// # fn main() -> anyhow::Result<()> {
+    /// # let trainer: Trainer<SequenceClassificationOption> = unimplemented!();
+    /// # let eval_examples = vec![];
+    /// use rust_bert::evaluation::classification::accuracy;
+    /// use rust_bert::training::trainer::{EvaluationOutput, TrainingState};
+    ///
+    /// let trainer = trainer.with_evaluation(
+    ///     eval_examples,
+    ///     |state: &TrainingState, output: &EvaluationOutput| {
+    ///         let accuracy = accuracy(&output.references, &output.predictions)?;
+    ///         println!("epoch {}: loss {}, accuracy {accuracy}", state.epoch, output.loss);
+    ///         Ok(())
+    ///     },
+    /// );
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn with_evaluation(
+        mut self,
+        examples: Vec<TrainingExample>,
+        hook: impl EvaluationHook + 'static,
+    ) -> Trainer<M> {
+        self.evaluation_set = Some((examples, Box::new(hook)));
+        self
+    }
+
+    /// Returns a reference to the model being trained
+    pub fn model(&self) -> &M {
+        &self.model
+    }
+
+    /// Returns a reference to the `VarStore` holding the model variables
+    pub fn var_store(&self) -> &VarStore {
+        &self.var_store
+    }
+
+    /// Returns the current state of the training
+    pub fn state(&self) -> TrainingState {
+        self.state
+    }
+
+    /// Trains the model for `num_epochs` epochs over a training set. The number of training steps for the
+    /// learning rate schedule is computed from the size of the training set if not set in the configuration.
+    ///
+    /// # Arguments
+    ///
+    /// * `examples` - Training examples
+    ///
+    /// # Returns
+    ///
+    /// * `TrainingState` at the end of the training
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # use rust_bert::pipelines::sequence_classification::SequenceClassificationOption;
+    /// # use rust_bert::training::trainer::Trainer;
+    /// # fn main() -> anyhow::Result<()> {
+    /// # let mut trainer: Trainer<SequenceClassificationOption> = unimplemented!();
+    /// # let train_examples = vec![];
+    /// let final_state = trainer.train(&train_examples)?;
+    /// trainer.save_checkpoint("path/to/output")?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn train(&mut self, examples: &[TrainingExample]) -> Result<TrainingState, RustBertError> {
+        if self.scheduler.total_steps.is_none() {
+            let batches_per_epoch = examples.len().div_ceil(self.config.batch_size);
+            let steps_per_epoch =
+                batches_per_epoch.div_ceil(self.config.gradient_accumulation_steps);
+            self.scheduler.total_steps =
+                Some(self.state.global_step + steps_per_epoch * self.config.num_epochs);
+        }
+        if let Some(seed) = self.config.seed {
+            tch::manual_seed(seed);
+        }
+        for _ in 0..self.config.num_epochs {
+            if self.config.shuffle {
+                let permutation = Vec::<i64>::from(Tensor::randperm(
+                    examples.len() as i64,
+                    (Kind::Int64, tch::Device::Cpu),
+                ));
+                self.train_epoch(
+                    permutation
+                        .into_iter()
+                        .map(|index| examples[index as usize].clone()),
+                )?;
+            } else {
+                self.train_epoch(examples.iter().cloned())?;
+            }
+        }
+        Ok(self.state)
+    }
+
+    /// Trains the model for a single epoch over a (possibly streamed) set of examples. At the end of the
+    /// epoch the model is evaluated on the evaluation set and a checkpoint is saved, if configured.
+    ///
+    /// # Arguments
+    ///
+    /// * `examples` - Iterator over the training examples
+    ///
+    /// # Returns
+    ///
+    /// * `TrainingState` at the end of the epoch
+    pub fn train_epoch<I>(&mut self, examples: I) -> Result<TrainingState, RustBertError>
+    where
+        I: IntoIterator<Item = TrainingExample>,
+    {
+        let mut total_loss = 0f64;
+        let mut num_batches = 0usize;
+        let mut batch = Vec::with_capacity(self.config.batch_size);
+        let mut examples = examples.into_iter().peekable();
+        while examples.peek().is_some() {
+            batch.clear();
+            batch.extend(examples.by_ref().take(self.config.batch_size));
+            total_loss += self.training_step(&batch)?;
+            num_batches += 1;
+        }
+        if self.accumulated_batches > 0 {
+            self.optimizer_step();
+        }
+        self.state.epoch += 1;
+        self.state.train_loss = total_loss / num_batches.max(1) as f64;
+
+        if let Some((examples, mut hook)) = self.evaluation_set.take() {
+            let evaluation = self.evaluate(examples.iter().cloned());
+            let hook_result =
+                evaluation.and_then(|output| hook.on_evaluation(&self.state, &output));
+            self.evaluation_set = Some((examples, hook));
+            hook_result?;
+        }
+        if let Some(output_dir) = &self.config.output_dir {
+            let checkpoint_dir = output_dir.join(format!("checkpoint-{}", self.state.global_step));
+            self.save_checkpoint(checkpoint_dir)?;
+        }
+        Ok(self.state)
+    }
+
+    /// Forward and backward pass on a batch, performing an optimization step once the gradients of
+    /// `gradient_accumulation_steps` batches have been accumulated. Returns the loss of the batch.
+    fn training_step(&mut self, examples: &[TrainingExample]) -> Result<f64, RustBertError> {
+        let batch = TrainingBatch::from_examples(
+            examples,
+            self.config.pad_token_id,
+            self.var_store.device(),
+        )?;
+        let (_, loss) = self.model.forward_loss(&batch, true)?;
+        (&loss / self.config.gradient_accumulation_steps as f64).backward();
+        self.accumulated_batches += 1;
+        if self.accumulated_batches == self.config.gradient_accumulation_steps {
+            self.optimizer_step();
+        }
+        Ok(f64::from(loss))
+    }
+
+    fn optimizer_step(&mut self) {
+        if let Some(max_grad_norm) = self.config.max_grad_norm {
+            clip_grad_norm(&self.var_store, max_grad_norm);
+        }
+        let learning_rate = self.scheduler.get_learning_rate(self.state.global_step);
+        self.optimizer.set_lr(learning_rate);
+        self.optimizer.step();
+        self.optimizer.zero_grad();
+        self.accumulated_batches = 0;
+        self.state.global_step += 1;
+        self.state.learning_rate = learning_rate;
+    }
+
+    /// Evaluates the model on a set of examples, without updating its weights.
+    ///
+    /// # Arguments
+    ///
+    /// * `examples` - Iterator over the evaluation examples
+    ///
+    /// # Returns
+    ///
+    /// * `EvaluationOutput` with the mean loss, predictions and references
+    pub fn evaluate<I>(&self, examples: I) -> Result<EvaluationOutput, RustBertError>
+    where
+        I: IntoIterator<Item = TrainingExample>,
+    {
+        let mut total_loss = 0f64;
+        let mut num_batches = 0usize;
+        let mut predictions = Vec::new();
+        let mut references = Vec::new();
+        let mut batch = Vec::with_capacity(self.config.batch_size);
+        let mut examples = examples.into_iter().peekable();
+        while examples.peek().is_some() {
+            batch.clear();
+            batch.extend(examples.by_ref().take(self.config.batch_size));
+            let batch = TrainingBatch::from_examples(
+                &batch,
+                self.config.pad_token_id,
+                self.var_store.device(),
+            )?;
+            let (logits, loss) = no_grad(|| self.model.forward_loss(&batch, false))?;
+            total_loss += f64::from(loss);
+            num_batches += 1;
+            if !batch.labels.is_floating_point() {
+                let labels = batch.labels.view([-1]).to_device(tch::Device::Cpu);
+                let batch_predictions = logits
+                    .argmax(-1, false)
+                    .view([-1])
+                    .to_device(tch::Device::Cpu);
+                let labelled = labels.ne(IGNORE_INDEX);
+                predictions.extend(Vec::<i64>::from(batch_predictions.masked_select(&labelled)));
+                references.extend(Vec::<i64>::from(labels.masked_select(&labelled)));
+            }
+        }
+        Ok(EvaluationOutput {
+            loss: total_loss / num_batches.max(1) as f64,
+            predictions,
+            references,
+        })
+    }
+
+    /// Saves a checkpoint of the model: weights (`rust_model.ot`) and configuration (`config.json`,
+    /// including the label mapping) that can be loaded by the pipelines using local resources.
+    ///
+    /// # Arguments
+    ///
+    /// * `directory` - Directory in which the checkpoint is saved (created if it does not exist)
+    pub fn save_checkpoint<P: AsRef<Path>>(&self, directory: P) -> Result<(), RustBertError> {
+        let directory = directory.as_ref();
+        fs::create_dir_all(directory)?;
+        self.var_store.save(directory.join("rust_model.ot"))?;
+        self.model_config.to_file(directory.join("config.json"))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::bert::BertConfig;
+    use crate::distilbert::DistilBertConfig;
+    use crate::pipelines::common::ModelType;
+    use crate::training::dataset::TrainingLabel;
+    use std::collections::HashMap;
+    use tch::Device;
+
+    fn tiny_model() -> (SequenceClassificationOption, VarStore, ConfigOption) {
+        let mut config = ConfigOption::DistilBert(DistilBertConfig {
+            dim: 16,
+            hidden_dim: 32,
+            n_heads: 2,
+            n_layers: 1,
+            vocab_size: 16,
+            max_position_embeddings: 16,
+            dropout: 0.0,
+            attention_dropout: 0.0,
+            seq_classif_dropout: 0.0,
+            ..Default::default()
+        });
+        config
+            .set_label_mapping(HashMap::from([
+                (0, "negative".to_string()),
+                (1, "positive".to_string()),
+            ]))
+            .unwrap();
+        let var_store = VarStore::new(Device::Cpu);
+        let model =
+            SequenceClassificationOption::new(ModelType::DistilBert, var_store.root(), &config)
+                .unwrap();
+        (model, var_store, config)
+    }
+
+    fn training_examples() -> Vec<TrainingExample> {
+        (0..8)
+            .map(|index| {
+                let label = index % 2;
+                TrainingExample {
+                    input_ids: vec![1, 2 + 4 * label + index % 4, 3 + 4 * label, 15],
+                    token_type_ids: vec![0; 4],
+                    label: TrainingLabel::Class(label),
+                }
+            })
+            .collect()
+    }
+
+    fn training_config() -> TrainingConfig {
+        TrainingConfig {
+            shuffle: false,
+            ..TrainingConfig::new(1e-2, 4, 10)
+        }
+    }
+
+    #[test]
+    fn test_training_decreases_loss() {
+        tch::manual_seed(42);
+        let (model, var_store, config) = tiny_model();
+        let mut trainer = Trainer::new(model, var_store, config, training_config()).unwrap();
+        let examples = training_examples();
+
+        let initial_loss = trainer.evaluate(examples.iter().cloned()).unwrap().loss;
+        let state = trainer.train(&examples).unwrap();
+        let final_loss = trainer.evaluate(examples.iter().cloned()).unwrap().loss;
+
+        assert_eq!(state.epoch, 10);
+        assert_eq!(state.global_step, 20);
+        assert!(
+            final_loss < initial_loss,
+            "loss did not decrease: {} -> {}",
+            initial_loss,
+            final_loss
+        );
+    }
+
+    #[test]
+    fn test_checkpoint_round_trip() {
+        tch::manual_seed(42);
+        let (model, var_store, config) = tiny_model();
+        let mut trainer = Trainer::new(model, var_store, config, training_config()).unwrap();
+        let examples = training_examples();
+        trainer.train_epoch(examples.iter().cloned()).unwrap();
+        let directory = tempfile::tempdir().unwrap();
+        trainer.save_checkpoint(directory.path()).unwrap();
+
+        let config =
+            ConfigOption::from_file(ModelType::DistilBert, directory.path().join("config.json"));
+        assert_eq!(config.get_label_mapping()[&1], "positive");
+        let mut var_store = VarStore::new(Device::Cpu);
+        let model =
+            SequenceClassificationOption::new(ModelType::DistilBert, var_store.root(), &config)
+                .unwrap();
+        var_store
+            .load(directory.path().join("rust_model.ot"))
+            .unwrap();
+
+        let batch = TrainingBatch::from_examples(&examples, 0, Device::Cpu).unwrap();
+        let (expected_logits, expected_loss) =
+            no_grad(|| trainer.model().forward_loss(&batch, false)).unwrap();
+        let (logits, loss) = no_grad(|| model.forward_loss(&batch, false)).unwrap();
+        assert!(logits.allclose(&expected_logits, 1e-6, 1e-6, false));
+        assert_eq!(f64::from(loss), f64::from(expected_loss));
+    }
+
+    #[test]
+    fn test_weight_decay_groups() {
+        let (model, var_store, config) = tiny_model();
+        let trainer = Trainer::new(model, var_store, config, TrainingConfig::default()).unwrap();
+
+        let variables = trainer.var_store().variables_.lock().unwrap();
+        let groups = variables
+            .trainable_variables
+            .iter()
+            .map(|variable| (variable.tensor.data_ptr(), variable.group))
+            .collect::<HashMap<_, _>>();
+        for (name, variable) in variables.named_variables.iter() {
+            let expected_group = if name.ends_with("bias")
+                || name.contains("LayerNorm")
+                || name.contains("layer_norm")
+            {
+                NO_DECAY_GROUP
+            } else {
+                0
+            };
+            assert_eq!(groups[&variable.data_ptr()], expected_group, "{}", name);
+        }
+        assert_eq!(
+            groups[&variables.named_variables
+                ["distilbert.transformer.layer.0.sa_layer_norm.weight"]
+                .data_ptr()],
+            NO_DECAY_GROUP
+        );
+    }
+
+    #[test]
+    fn test_token_classification_training_with_unused_variables() {
+        tch::manual_seed(42);
+        let mut config = ConfigOption::Bert(BertConfig {
+            hidden_size: 16,
+            intermediate_size: 32,
+            max_position_embeddings: 16,
+            num_attention_heads: 2,
+            num_hidden_layers: 1,
+            vocab_size: 16,
+            hidden_dropout_prob: 0.0,
+            attention_probs_dropout_prob: 0.0,
+            ..Default::default()
+        });
+        config
+            .set_label_mapping(HashMap::from([
+                (0, "O".to_string()),
+                (1, "B-PER".to_string()),
+                (2, "I-PER".to_string()),
+            ]))
+            .unwrap();
+        let var_store = VarStore::new(Device::Cpu);
+        let model =
+            TokenClassificationOption::new(ModelType::Bert, var_store.root(), &config).unwrap();
+        let examples = (0..8)
+            .map(|index| TrainingExample {
+                input_ids: vec![1, 4 + index % 4, 8 + index % 2, 2],
+                token_type_ids: vec![0; 4],
+                label: TrainingLabel::Tokens(vec![-100, 1, (index % 2) * 2, -100]),
+            })
+            .collect::<Vec<TrainingExample>>();
+        // The gradients are clipped (default maximum norm of 1.0) while the pooler gets no gradient
+        let training_config = training_config();
+        assert_eq!(training_config.max_grad_norm, Some(1.0));
+        let mut trainer = Trainer::new(model, var_store, config, training_config).unwrap();
+
+        let initial_loss = trainer.evaluate(examples.iter().cloned()).unwrap().loss;
+        trainer.train(&examples).unwrap();
+        let final_loss = trainer.evaluate(examples.iter().cloned()).unwrap().loss;
+        assert!(
+            final_loss < initial_loss,
+            "loss did not decrease: {} -> {}",
+            initial_loss,
+            final_loss
+        );
+        assert!(!trainer.var_store().variables()["bert.pooler.dense.weight"]
+            .grad()
+            .defined());
+    }
+
+    #[test]
+    fn test_clip_grad_norm() {
+        let var_store = VarStore::new(Device::Cpu);
+        let root = var_store.root();
+        let used = root.var("used", &[2], nn::Init::Const(1.0));
+        let _unused = root.var("unused", &[2], nn::Init::Const(1.0));
+        (&used * Tensor::of_slice(&[3.0f32, 4.0]))
+            .sum(Kind::Float)
+            .backward();
+
+        clip_grad_norm(&var_store, 1.0);
+        let gradient = Vec::<f64>::from(used.grad().to_kind(Kind::Double));
+        assert!((gradient[0] - 0.6).abs() < 1e-5);
+        assert!((gradient[1] - 0.8).abs() < 1e-5);
+    }
+}
@@ -14,6 +14,9 @@
 
 use crate::common::activations::Activation;
 use crate::common::dropout::Dropout;
+use crate::common::loss::{
+    cross_entropy_loss, question_answering_loss, sequence_classification_loss, ProblemType,
+};
 use crate::common::summary::{SequenceSummary, SummaryConfig, SummaryType};
 use crate::pipelines::common::{ModelType, TokenizerOption};
 use crate::pipelines::generation_utils::private_generation_utils::{
@@ -111,6 +114,8 @@ pub struct XLNetConfig {
     pub pad_token_id: i64,
     pub id2label: Option<HashMap<i64, String>>,
     pub label2id: Option<HashMap<String, i64>>,
+    /// Type of problem defining the loss of the sequence classification head (default: inferred from the labels)
+    pub problem_type: Option<ProblemType>,
     pub output_attentions: Option<bool>,
     pub output_hidden_states: Option<bool>,
     pub chunk_size_feed_forward: Option<i64>,
@@ -152,6 +157,7 @@ impl Default for XLNetConfig {
             pad_token_id: 5,
             id2label: None,
             label2id: None,
+            problem_type: None,
             output_attentions: None,
             output_hidden_states: None,
             chunk_size_feed_forward: None,
@@ -787,6 +793,7 @@ impl XLNetLMHeadModel {
         Ok(LMModelOutput {
             lm_logits,
             cache: Cache::XLNetCache(base_model_output.next_cache),
+            loss: None,
         })
     }
 }
@@ -885,6 +892,10 @@ impl LMHeadModel for XLNetLMHeadModel {
             )),
         }
     }
+
+    fn predicts_next_token(&self) -> bool {
+        false
+    }
 }
 
 /// # XLNetForSequenceClassification
@@ -897,6 +908,7 @@ pub struct XLNetForSequenceClassification {
     base_model: XLNetModel,
     sequence_summary: SequenceSummary,
     logits_proj: nn::Linear,
+    problem_type: Option<ProblemType>,
 }
 
 impl XLNetForSequenceClassification {
@@ -954,6 +966,7 @@ impl XLNetForSequenceClassification {
             base_model,
             sequence_summary,
             logits_proj,
+            problem_type: config.problem_type,
         })
     }
 
@@ -968,6 +981,7 @@ impl XLNetForSequenceClassification {
     /// * `token_type_ids` - Optional tensor (*batch size*, *sequence_length*) indicating the sentence ID of the token (0: first sentence, 1: second sentence).
     /// * `input_embeds` - Optional input tensor of shape (*batch size*, *sequence_length*, *embeddings dimension*). This or `input_ids` must be provided.
     /// * `old_layer_states` - Optional vector of length `num_layers` containing optional `LayerStates` containing the last calculated content for the attention layers. This avoids recomputing attention weights at past positions and speeds up decoding.
+    /// * `labels` - Optional labels of shape (*batch size*) used to compute the loss: class indices for single-label classification or target values for regression. Multi-label classification uses floating point targets of shape (*batch size*, *num_labels*). The loss follows the `problem_type` of the configuration, inferred from the labels if not set.
     /// * `train` - boolean flag to turn on/off the dropout layers in the model. Should be set to false for inference.
     ///
     /// # Returns
@@ -975,6 +989,7 @@ impl XLNetForSequenceClassification {
     /// * `XLNetSequenceClassificationOutput` containing:
     ///   - `logits` - `Tensor` of shape (*batch size*, *num_classes*) representing the logits for each batch item and class
     ///   - `next_cache` - `Option<Vec<Option<LayerState>>>` of length *n_layer* containing the past content for the the attention layers with shape (*past_sequence_length*, *batch size*, *hidden_size*)
+    ///   - `loss` - `Option<Tensor>` scalar loss, computed if labels are provided
     ///   - `all_hidden_states` - `Option<Vec<(Tensor, Option<Tensor>)>>` of length *n_layer* with shape (*batch size*, *sequence_length*, *hidden_size*) (with optional query stream states if used)
     ///   - `all_attentions` - `Option<Vec<(Tensor, Option<Tensor>)>>` of length *n_layer* with shape (*batch size*, *sequence_length*, *hidden_size*) (with optional query stream states if used)
     ///
@@ -1009,6 +1024,7 @@ impl XLNetForSequenceClassification {
     ///         None,
     ///         None,
     ///         None,
+    ///         None,
     ///         false
     ///     )
     /// });
@@ -1024,6 +1040,7 @@ impl XLNetForSequenceClassification {
         target_mapping: Option<&Tensor>,
         token_type_ids: Option<&Tensor>,
         input_embeds: Option<&Tensor>,
+        labels: Option<&Tensor>,
         train: bool,
     ) -> XLNetSequenceClassificationOutput {
         let base_model_output = self
@@ -1045,7 +1062,10 @@ impl XLNetForSequenceClassification {
             .forward_t(&base_model_output.hidden_state, None, train)
             .apply(&self.logits_proj);
 
+        let loss =
+            labels.map(|labels| sequence_classification_loss(&logits, labels, self.problem_type));
         XLNetSequenceClassificationOutput {
+            loss,
             logits,
             next_cache: base_model_output.next_cache,
             all_hidden_states: base_model_output.all_hidden_states,
@@ -1130,6 +1150,7 @@ impl XLNetForTokenClassification {
     /// * `token_type_ids` - Optional tensor (*batch size*, *sequence_length*) indicating the sentence ID of the token (0: first sentence, 1: second sentence).
     /// * `input_embeds` - Optional input tensor of shape (*batch size*, *sequence_length*, *embeddings dimension*). This or `input_ids` must be provided.
     /// * `old_layer_states` - Optional vector of length `num_layers` containing optional `LayerStates` containing the last calculated content for the attention layers. This avoids recomputing attention weights at past positions and speeds up decoding.
+    /// * `labels` - Optional labels of shape (*batch size*, *sequence_length*) used to compute the cross-entropy loss. Positions with a label of -100 are ignored.
     /// * `train` - boolean flag to turn on/off the dropout layers in the model. Should be set to false for inference.
     ///
     /// # Returns
@@ -1137,6 +1158,7 @@ impl XLNetForTokenClassification {
     /// * `XLNetTokenClassificationOutput` containing:
     ///   - `logits` - `Tensor` of shape (*batch size*, *sequence_length*, *num_classes*) representing the logits for each batch item, token position and class
     ///   - `next_cache` - `Option<Vec<Option<LayerState>>>` of length *n_layer* containing the past content for the the attention layers with shape (*past_sequence_length*, *batch size*, *hidden_size*)
+    ///   - `loss` - `Option<Tensor>` scalar loss, computed if labels are provided
     ///   - `all_hidden_states` - `Option<Vec<(Tensor, Option<Tensor>)>>` of length *n_layer* with shape (*batch size*, *sequence_length*, *hidden_size*) (with optional query stream states if used)
     ///   - `all_attentions` - `Option<Vec<(Tensor, Option<Tensor>)>>` of length *n_layer* with shape (*batch size*, *sequence_length*, *hidden_size*) (with optional query stream states if used)
     ///
@@ -1171,6 +1193,7 @@ impl XLNetForTokenClassification {
     ///         None,
     ///         None,
     ///         None,
+    ///         None,
     ///         false
     ///     )
     /// });
@@ -1186,6 +1209,7 @@ impl XLNetForTokenClassification {
         target_mapping: Option<&Tensor>,
         token_type_ids: Option<&Tensor>,
         input_embeds: Option<&Tensor>,
+        labels: Option<&Tensor>,
         train: bool,
     ) -> XLNetTokenClassificationOutput {
         let base_model_output = self
@@ -1204,7 +1228,9 @@ impl XLNetForTokenClassification {
 
         let logits = base_model_output.hidden_state.apply(&self.classifier);
 
+        let loss = labels.map(|labels| cross_entropy_loss(&logits, labels));
         XLNetTokenClassificationOutput {
+            loss,
             logits,
             next_cache: base_model_output.next_cache,
             all_hidden_states: base_model_output.all_hidden_states,
@@ -1378,6 +1404,7 @@ impl XLNetForMultipleChoice {
             .view((-1, num_choices));
 
         XLNetSequenceClassificationOutput {
+            loss: None,
             logits,
             next_cache: base_model_output.next_cache,
             all_hidden_states: base_model_output.all_hidden_states,
@@ -1449,6 +1476,8 @@ impl XLNetForQuestionAnswering {
     /// * `token_type_ids` - Optional tensor (*batch size*, *sequence_length*) indicating the sentence ID of the token (0: first sentence, 1: second sentence).
     /// * `input_embeds` - Optional input tensor of shape (*batch size*, *sequence_length*, *embeddings dimension*). This or `input_ids` must be provided.
     /// * `old_layer_states` - Optional vector of length `num_layers` containing optional `LayerStates` containing the last calculated content for the attention layers. This avoids recomputing attention weights at past positions and speeds up decoding.
+    /// * `start_positions` - Optional answer start positions of shape (*batch size*) used to compute the loss. Positions outside of the sequence are ignored.
+    /// * `end_positions` - Optional answer end positions of shape (*batch size*) used to compute the loss. Positions outside of the sequence are ignored.
     /// * `train` - boolean flag to turn on/off the dropout layers in the model. Should be set to false for inference.
     ///
     /// # Returns
@@ -1457,6 +1486,7 @@ impl XLNetForQuestionAnswering {
     ///   - `start_logits` - `Tensor` of shape (*batch size*, *sequence_length*) containing the logits for start of the answer
     ///   - `end_logits` - `Tensor` of shape (*batch size*, *sequence_length*) containing the logits for end of the answer
     ///   - `next_cache` - `Option<Vec<Option<LayerState>>>` of length *n_layer* containing the past content for the the attention layers with shape (*past_sequence_length*, *batch size*, *hidden_size*)
+    ///   - `loss` - `Option<Tensor>` scalar loss, computed if the start and end positions are provided
     ///   - `all_hidden_states` - `Option<Vec<(Tensor, Option<Tensor>)>>` of length *n_layer* with shape (*batch size*, *sequence_length*, *hidden_size*) (with optional query stream states if used)
     ///   - `all_attentions` - `Option<Vec<(Tensor, Option<Tensor>)>>` of length *n_layer* with shape (*batch size*, *sequence_length*, *hidden_size*) (with optional query stream states if used)
     ///
@@ -1491,6 +1521,8 @@ impl XLNetForQuestionAnswering {
     ///         None,
     ///         None,
     ///         None,
+    ///         None,
+    ///         None,
     ///         false
     ///     )
     /// });
@@ -1506,6 +1538,8 @@ impl XLNetForQuestionAnswering {
         target_mapping: Option<&Tensor>,
         token_type_ids: Option<&Tensor>,
         input_embeds: Option<&Tensor>,
+        start_positions: Option<&Tensor>,
+        end_positions: Option<&Tensor>,
         train: bool,
     ) -> XLNetQuestionAnsweringOutput {
         let base_model_output = self
@@ -1528,7 +1562,13 @@ impl XLNetForQuestionAnswering {
         let start_logits = start_logits.squeeze_dim(-1);
         let end_logits = end_logits.squeeze_dim(-1);
 
+        let loss = start_positions
+            .zip(end_positions)
+            .map(|(start_positions, end_positions)| {
+                question_answering_loss(&start_logits, &end_logits, start_positions, end_positions)
+            });
         XLNetQuestionAnsweringOutput {
+            loss,
             start_logits,
             end_logits,
             next_cache: base_model_output.next_cache,
@@ -1560,6 +1600,8 @@ pub struct XLNetSequenceClassificationOutput {
     pub all_hidden_states: Option<Vec<(Tensor, Option<Tensor>)>>,
     /// Attention weights for all intermediate layers
     pub all_attentions: Option<Vec<(Tensor, Option<Tensor>)>>,
+    /// Loss computed from the labels, if provided
+    pub loss: Option<Tensor>,
 }
 
 /// Container for the XLNet token classification model output.
@@ -1572,6 +1614,8 @@ pub struct XLNetTokenClassificationOutput {
     pub all_hidden_states: Option<Vec<(Tensor, Option<Tensor>)>>,
     /// Attention weights for all intermediate layers
     pub all_attentions: Option<Vec<(Tensor, Option<Tensor>)>>,
+    /// Loss computed from the labels, if provided
+    pub loss: Option<Tensor>,
 }
 
 /// Container for the XLNet question answering model output.
@@ -1586,6 +1630,8 @@ pub struct XLNetQuestionAnsweringOutput {
     pub all_hidden_states: Option<Vec<(Tensor, Option<Tensor>)>>,
     /// Attention weights for all intermediate layers
     pub all_attentions: Option<Vec<(Tensor, Option<Tensor>)>>,
+    /// Loss computed from the labels, if provided
+    pub loss: Option<Tensor>,
 }
 
 /// # Language generation model based on the XLNet architecture
@@ -61,8 +61,9 @@ fn albert_masked_lm() -> anyhow::Result<()> {
     let input_tensor = Tensor::stack(tokenized_input.as_slice(), 0).to(device);
 
     //    Forward pass
-    let model_output =
-        no_grad(|| albert_model.forward_t(Some(&input_tensor), None, None, None, None, false));
+    let model_output = no_grad(|| {
+        albert_model.forward_t(Some(&input_tensor), None, None, None, None, None, false)
+    });
 
     //    Print masked tokens
     let index_1 = model_output
@@ -134,8 +135,9 @@ fn albert_for_sequence_classification() -> anyhow::Result<()> {
     let input_tensor = Tensor::stack(tokenized_input.as_slice(), 0).to(device);
 
     //    Forward pass
-    let model_output =
-        no_grad(|| albert_model.forward_t(Some(&input_tensor), None, None, None, None, false));
+    let model_output = no_grad(|| {
+        albert_model.forward_t(Some(&input_tensor), None, None, None, None, None, false)
+    });
 
     assert_eq!(model_output.logits.size(), &[2, 3]);
     assert_eq!(
@@ -267,8 +269,9 @@ fn albert_for_token_classification() -> anyhow::Result<()> {
     let input_tensor = Tensor::stack(tokenized_input.as_slice(), 0).to(device);
 
     //    Forward pass
-    let model_output =
-        no_grad(|| albert_model.forward_t(Some(&input_tensor), None, None, None, None, false));
+    let model_output = no_grad(|| {
+        albert_model.forward_t(Some(&input_tensor), None, None, None, None, None, false)
+    });
 
     assert_eq!(model_output.logits.size(), &[2, 12, 4]);
     assert_eq!(
@@ -328,8 +331,18 @@ fn albert_for_question_answering() -> anyhow::Result<()> {
     let input_tensor = Tensor::stack(tokenized_input.as_slice(), 0).to(device);
 
     //    Forward pass
-    let model_output =
-        no_grad(|| albert_model.forward_t(Some(&input_tensor), None, None, None, None, false));
+    let model_output = no_grad(|| {
+        albert_model.forward_t(
+            Some(&input_tensor),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            false,
+        )
+    });
 
     assert_eq!(model_output.start_logits.size(), &[2, 12]);
     assert_eq!(model_output.end_logits.size(), &[2, 12]);
@@ -230,6 +230,44 @@ fn bart_zero_shot_classification() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[test]
+#[cfg_attr(not(feature = "all-tests"), ignore)]
+fn bart_zero_shot_classification_hypothesis_cache() -> anyhow::Result<()> {
+    //    Set-up model
+    let zero_shot_config = ZeroShotClassificationConfig {
+        device: Device::Cpu,
+        batch_size: 3,
+        hypothesis_cache_size: 2,
+        ..Default::default()
+    };
+    let sequence_classification_model = ZeroShotClassificationModel::new(zero_shot_config)?;
+
+    let inputs = [
+        "Who are you voting for in 2020?",
+        "The prime minister has announced a stimulus package which was widely criticized by the opposition.",
+    ];
+    let candidate_labels = &["politics", "public health", "economy", "sports"];
+
+    // The first call populates the cache, the second call re-uses (and evicts) cached hypotheses
+    let first_output =
+        sequence_classification_model.predict_multilabel(inputs, candidate_labels, None, 128)?;
+    let cached_output =
+        sequence_classification_model.predict_multilabel(inputs, candidate_labels, None, 128)?;
+    sequence_classification_model.clear_hypothesis_cache();
+    let uncached_output =
+        sequence_classification_model.predict_multilabel(inputs, candidate_labels, None, 128)?;
+
+    for output in [&cached_output, &uncached_output] {
+        for (labels, expected_labels) in output.iter().zip(first_output.iter()) {
+            for (label, expected_label) in labels.iter().zip(expected_labels.iter()) {
+                assert_eq!(label.text, expected_label.text);
+                assert!((label.score - expected_label.score).abs() < 1e-6);
+            }
+        }
+    }
+    Ok(())
+}
+
 #[test]
 #[cfg_attr(not(feature = "all-tests"), ignore)]
 fn bart_zero_shot_classification_try_error() -> anyhow::Result<()> {
@@ -1,23 +1,31 @@
 extern crate anyhow;
 extern crate dirs;
 
+mod common;
+
+use common::{set_reference_weights, tiny_bert_config};
 use rust_bert::bert::{
-    BertConfig, BertConfigResources, BertForMaskedLM, BertForMultipleChoice,
-    BertForQuestionAnswering, BertForSequenceClassification, BertForTokenClassification,
-    BertModelResources, BertVocabResources,
+    BertConfig, BertConfigResources, BertEmbeddings, BertForMaskedLM, BertForMultipleChoice,
+    BertForQuestionAnswering, BertForSequenceClassification, BertForTokenClassification, BertModel,
+    BertModelResources, BertVocabResources, PositionEmbeddingType,
+};
+use rust_bert::pipelines::common::{ConfigOption, ModelType};
+use rust_bert::pipelines::cross_encoder::{
+    CrossEncoderActivation, CrossEncoderConfig, CrossEncoderModel,
+};
+use rust_bert::pipelines::masked_language::{
+    MaskDecodingStrategy, MaskedLanguageConfig, MaskedLanguageModel, MaskedToken,
 };
-use rust_bert::pipelines::common::ModelType;
-use rust_bert::pipelines::masked_language::{MaskedLanguageConfig, MaskedLanguageModel};
 use rust_bert::pipelines::ner::NERModel;
 use rust_bert::pipelines::question_answering::{
     QaInput, QuestionAnsweringConfig, QuestionAnsweringModel,
 };
-use rust_bert::resources::{RemoteResource, ResourceProvider};
+use rust_bert::resources::{LocalResource, RemoteResource, ResourceProvider};
 use rust_bert::Config;
 use rust_tokenizers::tokenizer::{BertTokenizer, MultiThreadedTokenizer, TruncationStrategy};
 use rust_tokenizers::vocab::Vocab;
 use std::collections::HashMap;
-use tch::{nn, no_grad, Device, Tensor};
+use tch::{nn, no_grad, Device, Kind, Tensor};
 
 #[test]
 fn bert_masked_lm() -> anyhow::Result<()> {
@@ -77,6 +85,7 @@ fn bert_masked_lm() -> anyhow::Result<()> {
             None,
             None,
             None,
+            None,
             false,
         )
     });
@@ -141,6 +150,114 @@ fn bert_masked_lm_pipeline() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[test]
+fn bert_masked_lm_pipeline_top_k_and_targets() -> anyhow::Result<()> {
+    //    Set-up model
+    let config = MaskedLanguageConfig::new(
+        ModelType::Bert,
+        RemoteResource::from_pretrained(BertModelResources::BERT),
+        RemoteResource::from_pretrained(BertConfigResources::BERT),
+        RemoteResource::from_pretrained(BertVocabResources::BERT),
+        None,
+        true,
+        None,
+        None,
+        Some(String::from("<mask>")),
+    );
+
+    let mask_language_model = MaskedLanguageModel::new(config)?;
+    let input = [
+        "Hello I am a <mask> student",
+        "Paris is the <mask> of France. It is <mask> in Europe.",
+    ];
+
+    //    Top-k candidates, sorted by decreasing probability
+    let joint_output = mask_language_model.predict_top_k(input, 3, MaskDecodingStrategy::Joint)?;
+    let iterative_output =
+        mask_language_model.predict_top_k(input, 3, MaskDecodingStrategy::Iterative)?;
+    for output in [&joint_output, &iterative_output] {
+        assert_eq!(output.len(), 2);
+        assert_eq!(output[0].len(), 1);
+        assert_eq!(output[1].len(), 2);
+        for candidates in output.iter().flatten() {
+            assert_eq!(candidates.len(), 3);
+            assert!(candidates
+                .windows(2)
+                .all(|pair| pair[0].score >= pair[1].score));
+            assert!(candidates
+                .iter()
+                .all(|candidate| candidate.score > 0.0 && candidate.score <= 1.0));
+        }
+        assert_eq!(output[0][0][0].text, "college");
+        assert_eq!(output[1][0][0].id, 3007);
+        assert_eq!(output[1][0][0].text, "capital");
+    }
+    // The first mask is filled before the second one is predicted with the iterative strategy
+    assert_eq!(joint_output[1][1][0].text, "located");
+    let candidate_ids = |candidates: &[MaskedToken]| {
+        candidates
+            .iter()
+            .map(|candidate| candidate.id)
+            .collect::<Vec<i64>>()
+    };
+    assert_eq!(
+        candidate_ids(&iterative_output[1][0]),
+        candidate_ids(&joint_output[1][0])
+    );
+
+    //    More candidates than tokens in the vocabulary
+    let output = mask_language_model.predict_top_k(
+        ["Hello I am a <mask> student"],
+        50000,
+        MaskDecodingStrategy::Joint,
+    )?;
+    assert_eq!(output[0][0].len(), 30522);
+    let total_probability = output[0][0]
+        .iter()
+        .map(|candidate| candidate.score)
+        .sum::<f64>();
+    assert!((total_probability - 1.0).abs() < 1e-4);
+
+    //    Targets scores, matching the top-k probabilities of single sub-token targets
+    let targets = ["capital", "city", "heart"];
+    for decoding_strategy in [MaskDecodingStrategy::Joint, MaskDecodingStrategy::Iterative] {
+        let output = mask_language_model.predict_targets(
+            ["Paris is the <mask> of France."],
+            &targets,
+            decoding_strategy,
+        )?;
+        assert_eq!(output.len(), 1);
+        assert_eq!(output[0].len(), 1);
+        assert_eq!(output[0][0].len(), 3);
+        assert_eq!(output[0][0][0].text, "capital");
+        assert_eq!(output[0][0][0].id, 3007);
+        assert!(output[0][0]
+            .windows(2)
+            .all(|pair| pair[0].score >= pair[1].score));
+    }
+    let top_k_output = mask_language_model.predict_top_k(
+        ["Paris is the <mask> of France."],
+        1,
+        MaskDecodingStrategy::Joint,
+    )?;
+    let targets_output = mask_language_model.predict_targets(
+        ["Paris is the <mask> of France."],
+        &["capital"],
+        MaskDecodingStrategy::Joint,
+    )?;
+    assert!((targets_output[0][0][0].score - top_k_output[0][0][0].score).abs() < 1e-6);
+
+    //    Targets spanning several sub-tokens are scored by the product of their sub-tokens probabilities
+    let output = mask_language_model.predict_targets(
+        ["Paris is the <mask> of France."],
+        &["capital", "metropolitan area"],
+        MaskDecodingStrategy::Iterative,
+    )?;
+    assert_eq!(output[0][0][0].text, "capital");
+    assert!(output[0][0][1].score > 0.0 && output[0][0][1].score < output[0][0][0].score);
+    Ok(())
+}
+
 #[test]
 fn bert_for_sequence_classification() -> anyhow::Result<()> {
     //    Resources paths
@@ -188,7 +305,7 @@ fn bert_for_sequence_classification() -> anyhow::Result<()> {
 
     //    Forward pass
     let model_output =
-        no_grad(|| bert_model.forward_t(Some(&input_tensor), None, None, None, None, false));
+        no_grad(|| bert_model.forward_t(Some(&input_tensor), None, None, None, None, None, false));
 
     assert_eq!(model_output.logits.size(), &[2, 3]);
     assert_eq!(
@@ -246,7 +363,8 @@ fn bert_for_multiple_choice() -> anyhow::Result<()> {
         .unsqueeze(0);
 
     //    Forward pass
-    let model_output = no_grad(|| bert_model.forward_t(&input_tensor, None, None, None, false));
+    let model_output =
+        no_grad(|| bert_model.forward_t(&input_tensor, None, None, None, None, false));
 
     assert_eq!(model_output.logits.size(), &[1, 2]);
     assert_eq!(
@@ -309,7 +427,7 @@ fn bert_for_token_classification() -> anyhow::Result<()> {
 
     //    Forward pass
     let model_output =
-        no_grad(|| bert_model.forward_t(Some(&input_tensor), None, None, None, None, false));
+        no_grad(|| bert_model.forward_t(Some(&input_tensor), None, None, None, None, None, false));
 
     assert_eq!(model_output.logits.size(), &[2, 11, 4]);
     assert_eq!(
@@ -365,8 +483,18 @@ fn bert_for_question_answering() -> anyhow::Result<()> {
     let input_tensor = Tensor::stack(tokenized_input.as_slice(), 0).to(device);
 
     //    Forward pass
-    let model_output =
-        no_grad(|| bert_model.forward_t(Some(&input_tensor), None, None, None, None, false));
+    let model_output = no_grad(|| {
+        bert_model.forward_t(
+            Some(&input_tensor),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            false,
+        )
+    });
 
     assert_eq!(model_output.start_logits.size(), &[2, 11]);
     assert_eq!(model_output.end_logits.size(), &[2, 11]);
@@ -482,3 +610,588 @@ fn bert_question_answering() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn bert_position_embedding_types() -> anyhow::Result<()> {
+    for (position_embedding_type, has_distance_embedding) in [
+        (PositionEmbeddingType::RelativeKey, true),
+        (PositionEmbeddingType::RelativeKeyQuery, true),
+        (PositionEmbeddingType::Rotary, false),
+    ] {
+        let config = BertConfig {
+            position_embedding_type: Some(position_embedding_type),
+            ..tiny_bert_config()
+        };
+        // The pipelines do not truncate inputs of models without absolute positions
+        assert_eq!(ConfigOption::Bert(config.clone()).get_max_len(), None);
+        let vs = nn::VarStore::new(Device::Cpu);
+        let model: BertModel<BertEmbeddings> = BertModel::new(vs.root(), &config);
+
+        let variables = vs.variables();
+        assert!(!variables.contains_key("embeddings.position_embeddings.weight"));
+        assert_eq!(
+            variables.contains_key("encoder.layer.1.attention.self.distance_embedding.weight"),
+            has_distance_embedding
+        );
+
+        // Inputs are not limited to `max_position_embeddings` tokens
+        let input_ids = Tensor::randint(100, &[2, 40], (Kind::Int64, Device::Cpu));
+        let output = no_grad(|| {
+            model.forward_t(Some(&input_ids), None, None, None, None, None, None, false)
+        })?;
+        assert_eq!(output.hidden_state.size(), [2, 40, 32]);
+    }
+
+    let config: BertConfig = serde_json::from_str(
+        r#"{"position_embedding_type": "relative_key_query", "hidden_act": "gelu", "attention_probs_dropout_prob": 0.1, "hidden_dropout_prob": 0.1, "hidden_size": 32, "initializer_range": 0.02, "intermediate_size": 64, "max_position_embeddings": 16, "num_attention_heads": 4, "num_hidden_layers": 2, "type_vocab_size": 2, "vocab_size": 100}"#,
+    )?;
+    assert_eq!(
+        config.position_embedding_type,
+        Some(PositionEmbeddingType::RelativeKeyQuery)
+    );
+    assert_eq!(
+        ConfigOption::Bert(tiny_bert_config()).get_max_len(),
+        Some(16)
+    );
+
+    Ok(())
+}
+
+#[test]
+fn bert_relative_key_query_reference_hidden_states() -> anyhow::Result<()> {
+    let config = BertConfig {
+        position_embedding_type: Some(PositionEmbeddingType::RelativeKeyQuery),
+        ..tiny_bert_config()
+    };
+    let vs = nn::VarStore::new(Device::Cpu);
+    let model: BertModel<BertEmbeddings> = BertModel::new(vs.root(), &config);
+    assert_eq!(vs.variables().len(), 40);
+    set_reference_weights(&vs);
+
+    let input_ids = Tensor::of_slice(&[3i64, 17, 8, 42, 5, 11]).unsqueeze(0);
+    let output =
+        no_grad(|| model.forward_t(Some(&input_ids), None, None, None, None, None, None, false))?;
+
+    // Reference hidden states of the Transformers implementation, printed by `utils/reference_logits.py`
+    let expected_position_2 =
+        Tensor::of_slice(&[1.3711, -1.0192, 2.4296, -0.8605, -2.0679, -1.0895]);
+    let expected_position_5 =
+        Tensor::of_slice(&[0.8599, -1.1405, 1.2702, -1.0613, -1.7981, 0.2606]);
+    let hidden_state = output.hidden_state.get(0).to_kind(Kind::Double);
+    assert!(hidden_state
+        .get(2)
+        .narrow(0, 0, 6)
+        .allclose(&expected_position_2, 1e-4, 1e-4, false));
+    assert!(hidden_state
+        .get(5)
+        .narrow(0, 0, 6)
+        .allclose(&expected_position_5, 1e-4, 1e-4, false));
+
+    Ok(())
+}
+
+#[test]
+fn bert_local_attention_matches_global_attention() -> anyhow::Result<()> {
+    // A window larger than the input: local attention layers attend to all tokens
+    let global_config = BertConfig {
+        position_embedding_type: Some(PositionEmbeddingType::RelativeKeyQuery),
+        ..tiny_bert_config()
+    };
+    let local_config = BertConfig {
+        local_attention_window: Some(64),
+        global_attention_every_n_layers: Some(0),
+        ..global_config.clone()
+    };
+    let global_vs = nn::VarStore::new(Device::Cpu);
+    let global_model: BertModel<BertEmbeddings> =
+        BertModel::new_with_optional_pooler(global_vs.root(), &global_config, false);
+    let mut local_vs = nn::VarStore::new(Device::Cpu);
+    let local_model: BertModel<BertEmbeddings> =
+        BertModel::new_with_optional_pooler(local_vs.root(), &local_config, false);
+    local_vs.copy(&global_vs)?;
+
+    let input_ids = Tensor::randint(100, &[2, 10], (Kind::Int64, Device::Cpu));
+    let mask = Tensor::of_slice2(&[[1; 10], [1, 1, 1, 1, 1, 1, 1, 0, 0, 0]]);
+    let global_output = no_grad(|| {
+        global_model.forward_t(
+            Some(&input_ids),
+            Some(&mask),
+            None,
+            None,
+            None,
+            None,
+            None,
+            false,
+        )
+    })?;
+    let local_output = no_grad(|| {
+        local_model.forward_t(
+            Some(&input_ids),
+            Some(&mask),
+            None,
+            None,
+            None,
+            None,
+            None,
+            false,
+        )
+    })?;
+
+    // Padding positions are fully masked in the local attention layers: only non-padded positions are compared
+    assert!(local_output.hidden_state.get(0).allclose(
+        &global_output.hidden_state.get(0),
+        1e-5,
+        1e-5,
+        false
+    ));
+    assert!(local_output.hidden_state.get(1).narrow(0, 0, 7).allclose(
+        &global_output.hidden_state.get(1).narrow(0, 0, 7),
+        1e-5,
+        1e-5,
+        false
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn bert_sliding_window_attention() -> anyhow::Result<()> {
+    // Single local attention layer, each token attending to the 2 tokens on each side
+    let config = BertConfig {
+        position_embedding_type: Some(PositionEmbeddingType::RelativeKey),
+        num_hidden_layers: 1,
+        local_attention_window: Some(4),
+        global_attention_every_n_layers: Some(0),
+        ..tiny_bert_config()
+    };
+    let vs = nn::VarStore::new(Device::Cpu);
+    let model: BertModel<BertEmbeddings> =
+        BertModel::new_with_optional_pooler(vs.root(), &config, false);
+
+    let input_ids = Tensor::randint(100, &[1, 23], (Kind::Int64, Device::Cpu));
+    let blocked_output =
+        no_grad(|| model.forward_t(Some(&input_ids), None, None, None, None, None, None, false))?;
+
+    // Query-dependent (3D) attention masks are applied on the full attention scores
+    let mask = Tensor::ones(&[1, 23, 23], (Kind::Int64, Device::Cpu));
+    let full_output = no_grad(|| {
+        model.forward_t(
+            Some(&input_ids),
+            Some(&mask),
+            None,
+            None,
+            None,
+            None,
+            None,
+            false,
+        )
+    })?;
+    assert!(blocked_output
+        .hidden_state
+        .allclose(&full_output.hidden_state, 1e-5, 1e-5, false));
+
+    // Tokens outside of the window do not contribute to the hidden states
+    let modified_input_ids = input_ids.copy();
+    let _ = modified_input_ids
+        .get(0)
+        .get(10)
+        .fill_((input_ids.int64_value(&[0, 10]) + 1) % 100);
+    let modified_output = no_grad(|| {
+        model.forward_t(
+            Some(&modified_input_ids),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            false,
+        )
+    })?;
+    assert!(modified_output.hidden_state.narrow(1, 0, 8).allclose(
+        &blocked_output.hidden_state.narrow(1, 0, 8),
+        1e-6,
+        1e-6,
+        false
+    ));
+    assert!(!modified_output.hidden_state.narrow(1, 8, 5).allclose(
+        &blocked_output.hidden_state.narrow(1, 8, 5),
+        1e-6,
+        1e-6,
+        false
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn bert_chunked_attention_matches_full_attention() -> anyhow::Result<()> {
+    let input_ids = Tensor::randint(100, &[2, 10], (Kind::Int64, Device::Cpu));
+    let mask = Tensor::of_slice2(&[[1; 10], [1, 1, 1, 1, 1, 1, 1, 0, 0, 0]]);
+    let encoder_hidden_states = Tensor::randn(&[2, 7, 32], (Kind::Float, Device::Cpu));
+    let encoder_mask = Tensor::of_slice2(&[[1; 7], [1, 1, 1, 1, 0, 0, 0]]);
+
+    for position_embedding_type in [
+        PositionEmbeddingType::Absolute,
+        PositionEmbeddingType::RelativeKeyQuery,
+        PositionEmbeddingType::Rotary,
+    ] {
+        // Decoders also chunk the queries of the causal self-attention and of the cross-attention
+        for is_decoder in [false, true] {
+            let config = BertConfig {
+                position_embedding_type: Some(position_embedding_type),
+                is_decoder: Some(is_decoder),
+                ..tiny_bert_config()
+            };
+            // The chunk size does not divide the sequence length (10) nor the encoder sequence length (7)
+            let chunked_config = BertConfig {
+                attention_chunk_size: Some(3),
+                ..config.clone()
+            };
+            let vs = nn::VarStore::new(Device::Cpu);
+            let model: BertModel<BertEmbeddings> =
+                BertModel::new_with_optional_pooler(vs.root(), &config, false);
+            let mut chunked_vs = nn::VarStore::new(Device::Cpu);
+            let chunked_model: BertModel<BertEmbeddings> =
+                BertModel::new_with_optional_pooler(chunked_vs.root(), &chunked_config, false);
+            chunked_vs.copy(&vs)?;
+
+            let (encoder_hidden_states, encoder_mask) = if is_decoder {
+                (Some(&encoder_hidden_states), Some(&encoder_mask))
+            } else {
+                (None, None)
+            };
+            let output = no_grad(|| {
+                model.forward_t(
+                    Some(&input_ids),
+                    Some(&mask),
+                    None,
+                    None,
+                    None,
+                    encoder_hidden_states,
+                    encoder_mask,
+                    false,
+                )
+            })?;
+            let chunked_output = no_grad(|| {
+                chunked_model.forward_t(
+                    Some(&input_ids),
+                    Some(&mask),
+                    None,
+                    None,
+                    None,
+                    encoder_hidden_states,
+                    encoder_mask,
+                    false,
+                )
+            })?;
+
+            assert!(
+                chunked_output
+                    .hidden_state
+                    .allclose(&output.hidden_state, 1e-5, 1e-5, false),
+                "{:?} (decoder: {})",
+                position_embedding_type,
+                is_decoder
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Mean cross-entropy of rows of logits, skipping the labels equal to -100
+fn expected_cross_entropy(logits: &Tensor, labels: &[i64]) -> f64 {
+    let num_labels = *logits.size().last().unwrap() as usize;
+    let logits = Vec::<f64>::from(logits.detach().to_kind(Kind::Double).view([-1]));
+    let losses = logits
+        .chunks(num_labels)
+        .zip(labels.iter())
+        .filter(|(_, &label)| label != -100)
+        .map(|(row, &label)| {
+            let log_sum_exp = row.iter().map(|value| value.exp()).sum::<f64>().ln();
+            log_sum_exp - row[label as usize]
+        })
+        .collect::<Vec<f64>>();
+    losses.iter().sum::<f64>() / losses.len() as f64
+}
+
+#[test]
+fn bert_sequence_classification_loss() -> anyhow::Result<()> {
+    let input_ids = Tensor::of_slice2(&[[1i64, 5, 7, 2], [1, 9, 3, 2]]);
+
+    // Cross-entropy for integer labels
+    let config = BertConfig {
+        id2label: Some(HashMap::from([
+            (0, "a".to_string()),
+            (1, "b".to_string()),
+            (2, "c".to_string()),
+        ])),
+        ..tiny_bert_config()
+    };
+    let vs = nn::VarStore::new(Device::Cpu);
+    let model = BertForSequenceClassification::new(vs.root(), &config)?;
+    let labels = Tensor::of_slice(&[2i64, 0]);
+    let output = model.forward_t(
+        Some(&input_ids),
+        None,
+        None,
+        None,
+        None,
+        Some(&labels),
+        false,
+    );
+    let loss = output.loss.unwrap();
+    assert!((f64::from(&loss) - expected_cross_entropy(&output.logits, &[2, 0])).abs() < 1e-5);
+    loss.backward();
+    assert!(
+        vs.variables()["classifier.weight"]
+            .grad()
+            .abs()
+            .sum(Kind::Float)
+            .double_value(&[])
+            > 0.0
+    );
+
+    // Mean squared error for a single label (regression)
+    let config = BertConfig {
+        id2label: Some(HashMap::from([(0, "score".to_string())])),
+        ..tiny_bert_config()
+    };
+    let vs = nn::VarStore::new(Device::Cpu);
+    let model = BertForSequenceClassification::new(vs.root(), &config)?;
+    let labels = Tensor::of_slice(&[0.5f32, -1.0]);
+    let output = model.forward_t(
+        Some(&input_ids),
+        None,
+        None,
+        None,
+        None,
+        Some(&labels),
+        false,
+    );
+    let loss = output.loss.unwrap();
+    let predictions = Vec::<f64>::from(output.logits.detach().to_kind(Kind::Double).view([-1]));
+    let expected_loss = ((predictions[0] - 0.5).powi(2) + (predictions[1] + 1.0).powi(2)) / 2.0;
+    assert!((f64::from(&loss) - expected_loss).abs() < 1e-5);
+    loss.backward();
+    assert!(
+        vs.variables()["classifier.weight"]
+            .grad()
+            .abs()
+            .sum(Kind::Float)
+            .double_value(&[])
+            > 0.0
+    );
+
+    Ok(())
+}
+
+#[test]
+fn bert_token_classification_loss() -> anyhow::Result<()> {
+    let config = BertConfig {
+        id2label: Some(HashMap::from([
+            (0, "O".to_string()),
+            (1, "B-PER".to_string()),
+            (2, "I-PER".to_string()),
+        ])),
+        ..tiny_bert_config()
+    };
+    let vs = nn::VarStore::new(Device::Cpu);
+    let model = BertForTokenClassification::new(vs.root(), &config)?;
+    let input_ids = Tensor::of_slice(&[1i64, 5, 7, 2]).unsqueeze(0);
+
+    // The special tokens labelled -100 do not contribute to the loss
+    let labels = [-100i64, 2, 0, -100];
+    let output = model.forward_t(
+        Some(&input_ids),
+        None,
+        None,
+        None,
+        None,
+        Some(&Tensor::of_slice(&labels).unsqueeze(0)),
+        false,
+    );
+    let loss = output.loss.unwrap();
+    assert!((f64::from(&loss) - expected_cross_entropy(&output.logits, &labels)).abs() < 1e-5);
+    loss.backward();
+    assert!(
+        vs.variables()["classifier.weight"]
+            .grad()
+            .abs()
+            .sum(Kind::Float)
+            .double_value(&[])
+            > 0.0
+    );
+
+    Ok(())
+}
+
+/// Saves a randomly initialized tiny BERT sequence classifier with `num_labels` outputs and a small
+/// vocabulary to `directory`
+fn save_tiny_bert_cross_encoder(
+    directory: &std::path::Path,
+    num_labels: i64,
+) -> anyhow::Result<()> {
+    let mut config = tiny_bert_config();
+    config.id2label = Some(
+        (0..num_labels)
+            .map(|label| (label, format!("LABEL_{label}")))
+            .collect(),
+    );
+    std::fs::write(
+        directory.join("config.json"),
+        serde_json::to_string(&config)?,
+    )?;
+    let vs = nn::VarStore::new(Device::Cpu);
+    let _ = BertForSequenceClassification::new(vs.root(), &config)?;
+    vs.save(directory.join("rust_model.ot"))?;
+    let vocab = "[PAD] [UNK] [CLS] [SEP] [MASK] how many people live in berlin ? has a population \
+                 of million new york is famous for its museums the weather nice today .";
+    std::fs::write(directory.join("vocab.txt"), vocab.replace(' ', "\n"))?;
+    Ok(())
+}
+
+/// CPU cross-encoder configuration for the model saved by `save_tiny_bert_cross_encoder`
+fn tiny_bert_cross_encoder_config(
+    directory: &std::path::Path,
+    activation: CrossEncoderActivation,
+) -> CrossEncoderConfig {
+    let mut config = CrossEncoderConfig::new(
+        ModelType::Bert,
+        LocalResource::from(directory.join("rust_model.ot")),
+        LocalResource::from(directory.join("config.json")),
+        LocalResource::from(directory.join("vocab.txt")),
+        None,
+        true,
+        None,
+        None,
+    );
+    config.device = Device::Cpu;
+    config.activation = activation;
+    config
+}
+
+#[test]
+fn bert_cross_encoder_activations() -> anyhow::Result<()> {
+    let pairs = [
+        (
+            "How many people live in Berlin?",
+            "Berlin has a population of 3.5 million.",
+        ),
+        (
+            "How many people live in Berlin?",
+            "New York is famous for its museums.",
+        ),
+        (
+            "How many people live in Berlin?",
+            "The weather is nice today.",
+        ),
+    ];
+
+    for num_labels in [1, 3] {
+        let directory = tempfile::tempdir()?;
+        save_tiny_bert_cross_encoder(directory.path(), num_labels)?;
+        let predict_scores = |activation| -> anyhow::Result<Vec<Vec<f64>>> {
+            let config = tiny_bert_cross_encoder_config(directory.path(), activation);
+            Ok(CrossEncoderModel::new(config)?.predict_scores(&pairs)?)
+        };
+
+        let logits = predict_scores(CrossEncoderActivation::Identity)?;
+        assert_eq!(logits.len(), pairs.len());
+        assert!(logits
+            .iter()
+            .all(|scores| scores.len() == num_labels as usize));
+
+        let sigmoid_scores = predict_scores(CrossEncoderActivation::Sigmoid)?;
+        let softmax_scores = predict_scores(CrossEncoderActivation::Softmax)?;
+        for ((logits, sigmoid_scores), softmax_scores) in logits
+            .iter()
+            .zip(sigmoid_scores.iter())
+            .zip(softmax_scores.iter())
+        {
+            let normalization = logits.iter().map(|logit| logit.exp()).sum::<f64>();
+            for ((logit, sigmoid_score), softmax_score) in logits
+                .iter()
+                .zip(sigmoid_scores.iter())
+                .zip(softmax_scores.iter())
+            {
+                assert!((sigmoid_score - 1.0 / (1.0 + (-logit).exp())).abs() < 1e-5);
+                assert!((softmax_score - logit.exp() / normalization).abs() < 1e-5);
+            }
+            // A single output is always mapped to 1 by the softmax
+            assert!((softmax_scores.iter().sum::<f64>() - 1.0).abs() < 1e-5);
+        }
+
+        // The relevance score of a pair is its last output
+        let relevance_scores = CrossEncoderModel::new(tiny_bert_cross_encoder_config(
+            directory.path(),
+            CrossEncoderActivation::Sigmoid,
+        ))?
+        .predict(&pairs)?;
+        for (relevance_score, scores) in relevance_scores.iter().zip(sigmoid_scores.iter()) {
+            assert_eq!(relevance_score, scores.last().unwrap());
+        }
+    }
+
+    Ok(())
+}
+
+#[test]
+fn bert_cross_encoder_rerank() -> anyhow::Result<()> {
+    let directory = tempfile::tempdir()?;
+    save_tiny_bert_cross_encoder(directory.path(), 1)?;
+    let mut config =
+        tiny_bert_cross_encoder_config(directory.path(), CrossEncoderActivation::Sigmoid);
+    // Several batches, the last one being incomplete
+    config.batch_size = 2;
+    let cross_encoder = CrossEncoderModel::new(config)?;
+
+    let query = "How many people live in Berlin?";
+    let documents = [
+        "Berlin has a population of 3.5 million.",
+        "New York is famous for its museums.",
+        "The weather is nice today.",
+        "How many people live in New York?",
+        "Berlin is famous for its museums.",
+    ];
+    let pairs = documents
+        .iter()
+        .map(|document| (query, *document))
+        .collect::<Vec<(&str, &str)>>();
+    let scores = cross_encoder.predict(&pairs)?;
+
+    let ranked_documents = cross_encoder.rerank(query, &documents, documents.len())?;
+    assert_eq!(ranked_documents.len(), documents.len());
+    // Documents are sorted by decreasing score and point back to their input position
+    for ranked_document in ranked_documents.iter() {
+        assert_eq!(ranked_document.text, documents[ranked_document.index]);
+        assert!((ranked_document.score - scores[ranked_document.index]).abs() < 1e-6);
+    }
+    assert!(ranked_documents
+        .windows(2)
+        .all(|pair| pair[0].score >= pair[1].score));
+    let mut indices = ranked_documents
+        .iter()
+        .map(|document| document.index)
+        .collect::<Vec<usize>>();
+    indices.sort_unstable();
+    assert_eq!(indices, [0, 1, 2, 3, 4]);
+
+    // Only the top_k documents are returned
+    let top_documents = cross_encoder.rerank(query, &documents, 2)?;
+    assert_eq!(
+        top_documents
+            .iter()
+            .map(|document| document.index)
+            .collect::<Vec<usize>>(),
+        ranked_documents[..2]
+            .iter()
+            .map(|document| document.index)
+            .collect::<Vec<usize>>()
+    );
+    assert!(cross_encoder.rerank(query, &documents, 0)?.is_empty());
+    assert!(cross_encoder.rerank::<&str>(query, &[], 3)?.is_empty());
+
+    Ok(())
+}
@@ -0,0 +1,121 @@
+mod common;
+
+use common::{set_reference_weights, tiny_bloom_config};
+use rust_bert::bloom::{BloomConfig, BloomForCausalLM};
+use tch::{nn, no_grad, Device, Kind, Tensor};
+
+#[test]
+fn bloom_parameter_names() -> anyhow::Result<()> {
+    let vs = nn::VarStore::new(Device::Cpu);
+    let config = tiny_bloom_config();
+    let _ = BloomForCausalLM::new(vs.root(), &config);
+
+    let variables = vs.variables();
+    assert_eq!(
+        variables["transformer.word_embeddings_layernorm.weight"].size(),
+        vec![32]
+    );
+    assert_eq!(
+        variables["transformer.h.0.self_attention.query_key_value.weight"].size(),
+        vec![96, 32]
+    );
+    assert_eq!(
+        variables["transformer.h.1.mlp.dense_h_to_4h.weight"].size(),
+        vec![128, 32]
+    );
+    assert_eq!(variables["transformer.ln_f.weight"].size(), vec![32]);
+    assert!(!variables.contains_key("lm_head.weight"));
+
+    Ok(())
+}
+
+#[test]
+fn bloom_config_n_embed_alias() -> anyhow::Result<()> {
+    let config: BloomConfig = serde_json::from_str(
+        r#"{"vocab_size": 64, "n_embed": 32, "n_layer": 2, "n_head": 4, "layer_norm_epsilon": 1e-5,
+        "initializer_range": 0.02, "hidden_dropout": 0.0, "attention_dropout": 0.0}"#,
+    )?;
+    assert_eq!(config.hidden_size, 32);
+
+    Ok(())
+}
+
+#[test]
+fn bloom_cached_decoding() -> anyhow::Result<()> {
+    tch::manual_seed(42);
+    let vs = nn::VarStore::new(Device::Cpu);
+    let config = tiny_bloom_config();
+    let model = BloomForCausalLM::new(vs.root(), &config);
+
+    let input_ids = Tensor::of_slice(&[3i64, 17, 8, 42, 5, 11]).unsqueeze(0);
+    let output = no_grad(|| model.forward_t(Some(&input_ids), None, None, None, false))?;
+    assert_eq!(output.lm_logits.size(), vec![1, 6, 64]);
+
+    // Decoding the last tokens with the cache of the first tokens gives the same logits
+    let prefix_output =
+        no_grad(|| model.forward_t(Some(&input_ids.narrow(1, 0, 4)), None, None, None, false))?;
+    let cached_output = no_grad(|| {
+        model.forward_t(
+            Some(&input_ids.narrow(1, 4, 2)),
+            None,
+            prefix_output.next_cache,
+            Some(&Tensor::ones(&[1, 6], (Kind::Int64, Device::Cpu))),
+            false,
+        )
+    })?;
+    assert!(cached_output
+        .lm_logits
+        .allclose(&output.lm_logits.narrow(1, 4, 2), 1e-5, 1e-5, false));
+
+    // Left padding does not change the logits of the padded sequence
+    let padded_input_ids = Tensor::of_slice(&[3i64, 3, 3, 17, 8, 42, 5, 11]).unsqueeze(0);
+    let attention_mask = Tensor::of_slice(&[0i64, 0, 1, 1, 1, 1, 1, 1]).unsqueeze(0);
+    let padded_output = no_grad(|| {
+        model.forward_t(
+            Some(&padded_input_ids),
+            None,
+            None,
+            Some(&attention_mask),
+            false,
+        )
+    })?;
+    assert!(padded_output
+        .lm_logits
+        .narrow(1, 2, 6)
+        .allclose(&output.lm_logits, 1e-5, 1e-5, false));
+
+    Ok(())
+}
+
+#[test]
+fn bloom_reference_logits() -> anyhow::Result<()> {
+    let vs = nn::VarStore::new(Device::Cpu);
+    // A number of heads that is not a power of 2 exercises the interpolated ALiBi slopes
+    let config = BloomConfig {
+        hidden_size: 48,
+        n_head: 6,
+        ..tiny_bloom_config()
+    };
+    let model = BloomForCausalLM::new(vs.root(), &config);
+    assert_eq!(vs.variables().len(), 29);
+    set_reference_weights(&vs);
+
+    let input_ids = Tensor::of_slice(&[3i64, 17, 8, 42, 5, 11]).unsqueeze(0);
+    let output = no_grad(|| model.forward_t(Some(&input_ids), None, None, None, false))?;
+
+    // Reference logits of the Transformers implementation, printed by `utils/reference_logits.py`
+    let expected_position_2 =
+        Tensor::of_slice(&[-0.1535, -0.8646, 0.7689, -0.3711, 0.4099, 0.1140]);
+    let expected_position_5 = Tensor::of_slice(&[1.5038, 0.5811, -0.0512, 0.1644, 0.3581, -2.6217]);
+    let logits = output.lm_logits.get(0).to_kind(Kind::Double);
+    assert!(logits
+        .get(2)
+        .narrow(0, 0, 6)
+        .allclose(&expected_position_2, 1e-4, 1e-4, false));
+    assert!(logits
+        .get(5)
+        .narrow(0, 0, 6)
+        .allclose(&expected_position_5, 1e-4, 1e-4, false));
+
+    Ok(())
+}
@@ -0,0 +1,95 @@
+mod common;
+
+use common::tiny_clip_config;
+use rust_bert::clip::{ClipImageProcessor, ClipImageProcessorConfig, ClipModel, RgbImage};
+use tch::{nn, no_grad, Device, Kind, Tensor};
+
+#[test]
+fn clip_parameter_names() -> anyhow::Result<()> {
+    let vs = nn::VarStore::new(Device::Cpu);
+    let _ = ClipModel::new(vs.root(), &tiny_clip_config());
+
+    let variables = vs.variables();
+    for name in [
+        "logit_scale",
+        "text_projection.weight",
+        "visual_projection.weight",
+        "text_model.embeddings.token_embedding.weight",
+        "text_model.embeddings.position_embedding.weight",
+        "text_model.encoder.layers.1.self_attn.out_proj.bias",
+        "text_model.final_layer_norm.weight",
+        "vision_model.embeddings.class_embedding",
+        "vision_model.embeddings.patch_embedding.weight",
+        "vision_model.embeddings.position_embedding.weight",
+        "vision_model.pre_layrnorm.weight",
+        "vision_model.encoder.layers.0.mlp.fc1.weight",
+        "vision_model.post_layernorm.bias",
+    ] {
+        assert!(variables.contains_key(name), "missing variable {}", name);
+    }
+    assert!(!variables.contains_key("text_projection.bias"));
+    assert!(!variables.contains_key("vision_model.embeddings.patch_embedding.bias"));
+    assert_eq!(
+        variables["vision_model.embeddings.position_embedding.weight"].size(),
+        [17, 32]
+    );
+
+    Ok(())
+}
+
+#[test]
+fn clip_embeddings() -> anyhow::Result<()> {
+    let vs = nn::VarStore::new(Device::Cpu);
+    let model = ClipModel::new(vs.root(), &tiny_clip_config());
+
+    let input_ids = Tensor::of_slice2(&[[98, 5, 6, 7, 99], [98, 8, 99, 99, 99]]);
+    let attention_mask = Tensor::of_slice2(&[[1, 1, 1, 1, 1], [1, 1, 1, 0, 0]]);
+    let pixel_values = Tensor::rand(&[3, 3, 32, 32], (Kind::Float, Device::Cpu));
+
+    let output =
+        no_grad(|| model.forward_t(&input_ids, Some(&attention_mask), &pixel_values, false))?;
+
+    assert_eq!(output.text_embeds.size(), [2, 16]);
+    assert_eq!(output.image_embeds.size(), [3, 16]);
+    assert_eq!(output.logits_per_text.size(), [2, 3]);
+    assert_eq!(output.logits_per_image.size(), [3, 2]);
+    let text_norms = output.text_embeds.norm_scalaropt_dim(2, &[-1], false);
+    assert!(text_norms.allclose(
+        &Tensor::ones(&[2], (Kind::Float, Device::Cpu)),
+        1e-5,
+        1e-5,
+        false
+    ));
+
+    // The pooled output of a padded sequence matches the unpadded sequence
+    let unpadded_features =
+        no_grad(|| model.get_text_features(&Tensor::of_slice2(&[[98, 8, 99]]), None, false))?;
+    let padded_features =
+        no_grad(|| model.get_text_features(&input_ids, Some(&attention_mask), false))?;
+    assert!(padded_features
+        .get(1)
+        .allclose(&unpadded_features.get(0), 1e-5, 1e-5, false));
+
+    Ok(())
+}
+
+#[test]
+fn clip_image_preprocessing() -> anyhow::Result<()> {
+    let processor = ClipImageProcessor::new(ClipImageProcessorConfig::default());
+    let wide_pixels = vec![255u8; 3 * 640 * 480];
+    let small_pixels = vec![0u8; 3 * 100 * 50];
+    let pixel_values = processor.preprocess(&[
+        RgbImage::new(&wide_pixels, 640, 480)?,
+        RgbImage::new(&small_pixels, 100, 50)?,
+    ])?;
+
+    assert_eq!(pixel_values.size(), [2, 3, 224, 224]);
+    let mean = ClipImageProcessorConfig::default().image_mean;
+    let std = ClipImageProcessorConfig::default().image_std;
+    let white_red = pixel_values.double_value(&[0, 0, 112, 112]);
+    assert!((white_red - (1.0 - mean[0]) / std[0]).abs() < 1e-4);
+    let black_red = pixel_values.double_value(&[1, 0, 112, 112]);
+    assert!((black_red + mean[0] / std[0]).abs() < 1e-4);
+
+    Ok(())
+}
@@ -0,0 +1,149 @@
+//! Fixtures shared by the integration tests: tiny model configurations and deterministic weights.
+#![allow(dead_code)]
+
+use rust_bert::bert::BertConfig;
+use rust_bert::bloom::BloomConfig;
+use rust_bert::clip::{ClipConfig, ClipTextConfig, ClipVisionConfig};
+use rust_bert::gpt_neox::GptNeoXConfig;
+use rust_bert::gptj::GptJConfig;
+use rust_bert::llama::LlamaConfig;
+use rust_bert::opt::OptConfig;
+use rust_bert::t5::T5Config;
+use tch::{nn, no_grad, Tensor};
+
+/// Fills every variable with deterministic values derived from its name, shifted by 1 for the
+/// normalization weights. The reference outputs are generated by `utils/reference_logits.py`.
+pub fn set_reference_weights(vs: &nn::VarStore) {
+    for (name, mut variable) in vs.variables() {
+        let seed = name.bytes().map(|byte| byte as f64).sum::<f64>() / 100.0;
+        let offset = if name.ends_with("norm.weight")
+            || name.ends_with("LayerNorm.weight")
+            || (name.contains("ln_") && name.ends_with(".weight"))
+        {
+            1.0
+        } else {
+            0.0
+        };
+        let values = (0..variable.numel())
+            .map(|k| {
+                let k = k as f64;
+                offset + 0.2 * (0.37 * k * k + seed * (k + 1.0)).sin()
+            })
+            .collect::<Vec<f64>>();
+        let size = variable.size();
+        let values = Tensor::of_slice(&values)
+            .view(size.as_slice())
+            .to_kind(variable.kind());
+        no_grad(|| variable.copy_(&values));
+    }
+}
+
+pub fn tiny_bert_config() -> BertConfig {
+    BertConfig {
+        hidden_size: 32,
+        intermediate_size: 64,
+        max_position_embeddings: 16,
+        num_attention_heads: 4,
+        num_hidden_layers: 2,
+        vocab_size: 100,
+        ..Default::default()
+    }
+}
+
+pub fn tiny_bloom_config() -> BloomConfig {
+    BloomConfig {
+        vocab_size: 64,
+        hidden_size: 32,
+        n_layer: 2,
+        n_head: 4,
+        ..Default::default()
+    }
+}
+
+pub fn tiny_clip_config() -> ClipConfig {
+    ClipConfig {
+        text_config: ClipTextConfig {
+            vocab_size: 100,
+            hidden_size: 32,
+            intermediate_size: 64,
+            num_hidden_layers: 2,
+            num_attention_heads: 4,
+            max_position_embeddings: 16,
+            eos_token_id: Some(99),
+            ..Default::default()
+        },
+        vision_config: ClipVisionConfig {
+            hidden_size: 32,
+            intermediate_size: 64,
+            num_hidden_layers: 2,
+            num_attention_heads: 4,
+            image_size: 32,
+            patch_size: 8,
+            ..Default::default()
+        },
+        projection_dim: 16,
+        logit_scale_init_value: 2.6592,
+    }
+}
+
+pub fn tiny_gpt_neox_config() -> GptNeoXConfig {
+    GptNeoXConfig {
+        vocab_size: 64,
+        hidden_size: 32,
+        intermediate_size: 48,
+        num_hidden_layers: 2,
+        num_attention_heads: 4,
+        max_position_embeddings: 64,
+        ..Default::default()
+    }
+}
+
+pub fn tiny_gptj_config() -> GptJConfig {
+    GptJConfig {
+        vocab_size: 64,
+        n_positions: 64,
+        n_embd: 32,
+        n_layer: 2,
+        n_head: 4,
+        rotary_dim: Some(4),
+        ..Default::default()
+    }
+}
+
+pub fn tiny_llama_config() -> LlamaConfig {
+    LlamaConfig {
+        vocab_size: 64,
+        hidden_size: 32,
+        intermediate_size: 48,
+        num_hidden_layers: 2,
+        num_attention_heads: 4,
+        num_key_value_heads: Some(2),
+        max_position_embeddings: 64,
+        ..Default::default()
+    }
+}
+
+pub fn tiny_opt_config() -> OptConfig {
+    OptConfig {
+        vocab_size: 64,
+        hidden_size: 32,
+        num_hidden_layers: 2,
+        ffn_dim: 48,
+        max_position_embeddings: 64,
+        num_attention_heads: 4,
+        ..Default::default()
+    }
+}
+
+pub fn tiny_t5_config() -> T5Config {
+    let mut config = T5Config::default();
+    config.d_model = 32;
+    config.d_ff = 64;
+    config.d_kv = 8;
+    config.num_heads = 4;
+    config.num_layers = 2;
+    config.relative_attention_num_buckets = 8;
+    config.relative_attention_max_distance = Some(16);
+    config.vocab_size = 64;
+    config
+}
@@ -1,8 +1,9 @@
 use rust_bert::deberta::{
     DebertaConfig, DebertaConfigResources, DebertaForMaskedLM, DebertaForQuestionAnswering,
     DebertaForSequenceClassification, DebertaForTokenClassification, DebertaMergesResources,
-    DebertaModelResources, DebertaVocabResources,
+    DebertaModel, DebertaModelResources, DebertaVocabResources,
 };
+use rust_bert::deberta_v2::{DebertaV2Config, DebertaV2Model};
 use rust_bert::resources::{RemoteResource, ResourceProvider};
 use rust_bert::Config;
 use rust_tokenizers::tokenizer::{DeBERTaTokenizer, MultiThreadedTokenizer, TruncationStrategy};
@@ -72,7 +73,7 @@ fn deberta_natural_language_inference() -> anyhow::Result<()> {
 
     //    Forward pass
     let model_output =
-        no_grad(|| model.forward_t(Some(&input_tensor), None, None, None, None, false))?;
+        no_grad(|| model.forward_t(Some(&input_tensor), None, None, None, None, None, false))?;
 
     let output = model_output.logits.softmax(-1, Kind::Float);
 
@@ -112,6 +113,7 @@ fn deberta_masked_lm() -> anyhow::Result<()> {
             Some(&token_type_ids),
             Some(&position_ids),
             None,
+            None,
             false,
         )
     })?;
@@ -192,8 +194,17 @@ fn deberta_for_token_classification() -> anyhow::Result<()> {
     let input_tensor = Tensor::stack(tokenized_input.as_slice(), 0).to(device);
 
     //    Forward pass
-    let model_output =
-        no_grad(|| model.forward_t(Some(input_tensor.as_ref()), None, None, None, None, false))?;
+    let model_output = no_grad(|| {
+        model.forward_t(
+            Some(input_tensor.as_ref()),
+            None,
+            None,
+            None,
+            None,
+            None,
+            false,
+        )
+    })?;
 
     assert_eq!(model_output.logits.size(), &[2, 7, 4]);
 
@@ -252,10 +263,109 @@ fn deberta_for_question_answering() -> anyhow::Result<()> {
     let input_tensor = Tensor::stack(tokenized_input.as_slice(), 0).to(device);
 
     //    Forward pass
-    let model_output =
-        no_grad(|| model.forward_t(Some(input_tensor.as_ref()), None, None, None, None, false))?;
+    let model_output = no_grad(|| {
+        model.forward_t(
+            Some(input_tensor.as_ref()),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            false,
+        )
+    })?;
 
     assert_eq!(model_output.start_logits.size(), &[1, 15]);
     assert_eq!(model_output.end_logits.size(), &[1, 15]);
     Ok(())
 }
+
+#[test]
+fn deberta_chunked_attention_matches_full_attention() -> anyhow::Result<()> {
+    let config: DebertaConfig = serde_json::from_str(
+        r#"{
+            "hidden_act": "gelu",
+            "attention_probs_dropout_prob": 0.1,
+            "hidden_dropout_prob": 0.1,
+            "hidden_size": 32,
+            "initializer_range": 0.02,
+            "intermediate_size": 64,
+            "max_position_embeddings": 16,
+            "num_attention_heads": 4,
+            "num_hidden_layers": 2,
+            "type_vocab_size": 0,
+            "vocab_size": 100,
+            "position_biased_input": false,
+            "pos_att_type": "c2p|p2c",
+            "relative_attention": true,
+            "talking_head": true
+        }"#,
+    )?;
+    let chunked_config = DebertaConfig {
+        attention_chunk_size: Some(3),
+        ..config.clone()
+    };
+    let vs = nn::VarStore::new(Device::Cpu);
+    let model = DebertaModel::new(vs.root(), &config);
+    let mut chunked_vs = nn::VarStore::new(Device::Cpu);
+    let chunked_model = DebertaModel::new(chunked_vs.root(), &chunked_config);
+    chunked_vs.copy(&vs)?;
+
+    let input_ids = Tensor::randint(100, &[2, 10], (Kind::Int64, Device::Cpu));
+    let mask = Tensor::of_slice2(&[[1; 10], [1, 1, 1, 1, 1, 1, 1, 0, 0, 0]]);
+    let output =
+        no_grad(|| model.forward_t(Some(&input_ids), Some(&mask), None, None, None, false))?;
+    let chunked_output = no_grad(|| {
+        chunked_model.forward_t(Some(&input_ids), Some(&mask), None, None, None, false)
+    })?;
+
+    assert!(chunked_output
+        .hidden_state
+        .allclose(&output.hidden_state, 1e-5, 1e-5, false));
+
+    // DeBERTa-v2 with log-bucket relative positions and shared attention keys
+    let v2_config: DebertaV2Config = serde_json::from_str(
+        r#"{
+            "hidden_act": "gelu",
+            "attention_probs_dropout_prob": 0.1,
+            "hidden_dropout_prob": 0.1,
+            "hidden_size": 32,
+            "initializer_range": 0.02,
+            "intermediate_size": 64,
+            "max_position_embeddings": 16,
+            "num_attention_heads": 4,
+            "num_hidden_layers": 2,
+            "type_vocab_size": 0,
+            "vocab_size": 100,
+            "position_biased_input": false,
+            "pos_att_type": ["p2c", "c2p"],
+            "relative_attention": true,
+            "position_buckets": 4,
+            "max_relative_positions": 16,
+            "share_att_key": true,
+            "norm_rel_ebd": "layer_norm"
+        }"#,
+    )?;
+    let chunked_v2_config = DebertaV2Config {
+        attention_chunk_size: Some(3),
+        ..v2_config.clone()
+    };
+    let v2_vs = nn::VarStore::new(Device::Cpu);
+    let v2_model = DebertaV2Model::new(v2_vs.root(), &v2_config);
+    let mut chunked_v2_vs = nn::VarStore::new(Device::Cpu);
+    let chunked_v2_model = DebertaV2Model::new(chunked_v2_vs.root(), &chunked_v2_config);
+    chunked_v2_vs.copy(&v2_vs)?;
+
+    let output =
+        no_grad(|| v2_model.forward_t(Some(&input_ids), Some(&mask), None, None, None, false))?;
+    let chunked_output = no_grad(|| {
+        chunked_v2_model.forward_t(Some(&input_ids), Some(&mask), None, None, None, false)
+    })?;
+
+    assert!(chunked_output
+        .hidden_state
+        .allclose(&output.hidden_state, 1e-5, 1e-5, false));
+
+    Ok(())
+}
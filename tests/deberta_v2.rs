@@ -38,6 +38,7 @@ fn deberta_v2_masked_lm() -> anyhow::Result<()> {
             Some(&token_type_ids),
             Some(&position_ids),
             None,
+            None,
             false,
         )
     })?;
@@ -110,8 +111,17 @@ fn deberta_v2_for_sequence_classification() -> anyhow::Result<()> {
     let input_tensor = Tensor::stack(tokenized_input.as_slice(), 0).to(device);
 
     //    Forward pass
-    let model_output =
-        no_grad(|| model.forward_t(Some(input_tensor.as_ref()), None, None, None, None, false))?;
+    let model_output = no_grad(|| {
+        model.forward_t(
+            Some(input_tensor.as_ref()),
+            None,
+            None,
+            None,
+            None,
+            None,
+            false,
+        )
+    })?;
 
     assert_eq!(model_output.logits.size(), &[2, 3]);
 
@@ -164,8 +174,17 @@ fn deberta_v2_for_token_classification() -> anyhow::Result<()> {
     let input_tensor = Tensor::stack(tokenized_input.as_slice(), 0).to(device);
 
     //    Forward pass
-    let model_output =
-        no_grad(|| model.forward_t(Some(input_tensor.as_ref()), None, None, None, None, false))?;
+    let model_output = no_grad(|| {
+        model.forward_t(
+            Some(input_tensor.as_ref()),
+            None,
+            None,
+            None,
+            None,
+            None,
+            false,
+        )
+    })?;
 
     assert_eq!(model_output.logits.size(), &[2, 7, 4]);
 
@@ -217,8 +236,18 @@ fn deberta_v2_for_question_answering() -> anyhow::Result<()> {
     let input_tensor = Tensor::stack(tokenized_input.as_slice(), 0).to(device);
 
     //    Forward pass
-    let model_output =
-        no_grad(|| model.forward_t(Some(input_tensor.as_ref()), None, None, None, None, false))?;
+    let model_output = no_grad(|| {
+        model.forward_t(
+            Some(input_tensor.as_ref()),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            false,
+        )
+    })?;
 
     assert_eq!(model_output.start_logits.size(), &[1, 16]);
     assert_eq!(model_output.end_logits.size(), &[1, 16]);
@@ -1,7 +1,7 @@
 use rust_bert::distilbert::{
     DistilBertConfig, DistilBertConfigResources, DistilBertForQuestionAnswering,
-    DistilBertForTokenClassification, DistilBertModelMaskedLM, DistilBertModelResources,
-    DistilBertVocabResources,
+    DistilBertForTokenClassification, DistilBertModel, DistilBertModelMaskedLM,
+    DistilBertModelResources, DistilBertVocabResources,
 };
 use rust_bert::pipelines::question_answering::{QaInput, QuestionAnsweringModel};
 use rust_bert::pipelines::sentiment::{SentimentModel, SentimentPolarity};
@@ -10,7 +10,7 @@ use rust_bert::Config;
 use rust_tokenizers::tokenizer::{BertTokenizer, MultiThreadedTokenizer, TruncationStrategy};
 use rust_tokenizers::vocab::Vocab;
 use std::collections::HashMap;
-use tch::{nn, no_grad, Device, Tensor};
+use tch::{nn, no_grad, Device, Kind, Tensor};
 
 extern crate anyhow;
 
@@ -96,7 +96,7 @@ fn distilbert_masked_lm() -> anyhow::Result<()> {
     //    Forward pass
     let model_output = no_grad(|| {
         distil_bert_model
-            .forward_t(Some(&input_tensor), None, None, false)
+            .forward_t(Some(&input_tensor), None, None, None, false)
             .unwrap()
     });
 
@@ -167,7 +167,7 @@ fn distilbert_for_question_answering() -> anyhow::Result<()> {
     //    Forward pass
     let model_output = no_grad(|| {
         distil_bert_model
-            .forward_t(Some(&input_tensor), None, None, false)
+            .forward_t(Some(&input_tensor), None, None, None, None, false)
             .unwrap()
     });
 
@@ -238,7 +238,7 @@ fn distilbert_for_token_classification() -> anyhow::Result<()> {
     //    Forward pass
     let model_output = no_grad(|| {
         distil_bert_model
-            .forward_t(Some(&input_tensor), None, None, false)
+            .forward_t(Some(&input_tensor), None, None, None, false)
             .unwrap()
     });
 
@@ -276,3 +276,37 @@ fn distilbert_question_answering() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn distilbert_chunked_attention_matches_full_attention() -> anyhow::Result<()> {
+    let config = DistilBertConfig {
+        dim: 32,
+        hidden_dim: 64,
+        max_position_embeddings: 16,
+        n_heads: 4,
+        n_layers: 2,
+        vocab_size: 100,
+        ..Default::default()
+    };
+    let chunked_config = DistilBertConfig {
+        attention_chunk_size: Some(3),
+        ..config.clone()
+    };
+    let vs = nn::VarStore::new(Device::Cpu);
+    let model = DistilBertModel::new(vs.root(), &config);
+    let mut chunked_vs = nn::VarStore::new(Device::Cpu);
+    let chunked_model = DistilBertModel::new(chunked_vs.root(), &chunked_config);
+    chunked_vs.copy(&vs)?;
+
+    let input_ids = Tensor::randint(100, &[2, 10], (Kind::Int64, Device::Cpu));
+    let mask = Tensor::of_slice2(&[[1; 10], [1, 1, 1, 1, 1, 1, 1, 0, 0, 0]]);
+    let output = no_grad(|| model.forward_t(Some(&input_ids), Some(&mask), None, false))?;
+    let chunked_output =
+        no_grad(|| chunked_model.forward_t(Some(&input_ids), Some(&mask), None, false))?;
+
+    assert!(chunked_output
+        .hidden_state
+        .allclose(&output.hidden_state, 1e-5, 1e-5, false));
+
+    Ok(())
+}
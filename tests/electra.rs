@@ -58,8 +58,9 @@ fn electra_masked_lm() -> anyhow::Result<()> {
     let input_tensor = Tensor::stack(tokenized_input.as_slice(), 0).to(device);
 
     //    Forward pass
-    let model_output =
-        no_grad(|| electra_model.forward_t(Some(&input_tensor), None, None, None, None, false));
+    let model_output = no_grad(|| {
+        electra_model.forward_t(Some(&input_tensor), None, None, None, None, None, false)
+    });
 
     //    Decode output
     let index_1 = model_output
@@ -57,7 +57,7 @@ fn fnet_masked_lm() -> anyhow::Result<()> {
 
     //    Forward pass
     let model_output =
-        no_grad(|| fnet_model.forward_t(Some(&input_tensor), None, None, None, false))?;
+        no_grad(|| fnet_model.forward_t(Some(&input_tensor), None, None, None, None, false))?;
 
     //    Print masked tokens
     let index_1 = model_output
@@ -229,7 +229,7 @@ fn fnet_for_token_classification() -> anyhow::Result<()> {
     //    Forward pass
     let model_output = no_grad(|| {
         fnet_model
-            .forward_t(Some(&input_tensor), None, None, None, false)
+            .forward_t(Some(&input_tensor), None, None, None, None, false)
             .unwrap()
     });
 
@@ -284,7 +284,7 @@ fn fnet_for_question_answering() -> anyhow::Result<()> {
     //    Forward pass
     let model_output = no_grad(|| {
         fnet_model
-            .forward_t(Some(&input_tensor), None, None, None, false)
+            .forward_t(Some(&input_tensor), None, None, None, None, None, false)
             .unwrap()
     });
 
@@ -13,7 +13,7 @@ use rust_bert::pipelines::text_generation::{TextGenerationConfig, TextGeneration
 use rust_bert::resources::{RemoteResource, ResourceProvider};
 use rust_bert::Config;
 use rust_tokenizers::tokenizer::{Gpt2Tokenizer, Tokenizer, TruncationStrategy};
-use tch::{nn, Device, Tensor};
+use tch::{nn, Device, Kind, Tensor};
 
 #[test]
 fn gpt2_lm_model() -> anyhow::Result<()> {
@@ -901,3 +901,56 @@ fn dialogpt_multiple_multi_turn_conversation_with_conversation_deletion() -> any
 
     Ok(())
 }
+
+#[test]
+fn gpt2_lm_loss() -> anyhow::Result<()> {
+    let config = Gpt2Config {
+        n_ctx: 16,
+        n_embd: 16,
+        n_head: 2,
+        n_layer: 1,
+        n_positions: 16,
+        vocab_size: 20,
+        ..Default::default()
+    };
+    let vs = nn::VarStore::new(Device::Cpu);
+    let model = GPT2LMHeadModel::new(vs.root(), &config);
+    let input_ids = [3i64, 11, 7, 2, 15];
+    let input_tensor = Tensor::of_slice(&input_ids).unsqueeze(0);
+    let output = model.forward_t_with_labels(
+        Some(&input_tensor),
+        Cache::None,
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+        &input_tensor,
+        false,
+    )?;
+
+    // The logits at each position are scored against the label of the next position
+    let logits = Vec::<f64>::from(output.lm_logits.detach().to_kind(Kind::Double).view([-1]));
+    let expected_loss = logits
+        .chunks(20)
+        .zip(input_ids.iter().skip(1))
+        .map(|(row, &label)| {
+            row.iter().map(|value| value.exp()).sum::<f64>().ln() - row[label as usize]
+        })
+        .sum::<f64>()
+        / 4.0;
+    let loss = output.loss.unwrap();
+    assert!((f64::from(&loss) - expected_loss).abs() < 1e-5);
+    loss.backward();
+    assert!(
+        vs.variables()["transformer.wte.weight"]
+            .grad()
+            .abs()
+            .sum(Kind::Float)
+            .double_value(&[])
+            > 0.0
+    );
+
+    Ok(())
+}
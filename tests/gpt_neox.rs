@@ -0,0 +1,140 @@
+mod common;
+
+use common::{set_reference_weights, tiny_gpt_neox_config};
+use rust_bert::gpt_neox::{GptNeoXConfig, GptNeoXForCausalLM};
+use tch::{nn, no_grad, Device, Kind, Tensor};
+
+#[test]
+fn gpt_neox_parameter_names() -> anyhow::Result<()> {
+    let vs = nn::VarStore::new(Device::Cpu);
+    let config = tiny_gpt_neox_config();
+    let _ = GptNeoXForCausalLM::new(vs.root(), &config);
+
+    let variables = vs.variables();
+    assert_eq!(
+        variables["gpt_neox.layers.0.attention.query_key_value.weight"].size(),
+        vec![96, 32]
+    );
+    assert_eq!(
+        variables["gpt_neox.layers.0.attention.query_key_value.bias"].size(),
+        vec![96]
+    );
+    assert_eq!(
+        variables["gpt_neox.layers.1.mlp.dense_h_to_4h.weight"].size(),
+        vec![48, 32]
+    );
+    assert_eq!(
+        variables["gpt_neox.layers.1.post_attention_layernorm.weight"].size(),
+        vec![32]
+    );
+    assert_eq!(
+        variables["gpt_neox.final_layer_norm.weight"].size(),
+        vec![32]
+    );
+    assert_eq!(variables["embed_out.weight"].size(), vec![64, 32]);
+    assert!(!variables.contains_key("embed_out.bias"));
+
+    Ok(())
+}
+
+#[test]
+fn gpt_neox_cached_decoding() -> anyhow::Result<()> {
+    tch::manual_seed(42);
+    let vs = nn::VarStore::new(Device::Cpu);
+    let config = tiny_gpt_neox_config();
+    let model = GptNeoXForCausalLM::new(vs.root(), &config);
+
+    let input_ids = Tensor::of_slice(&[3i64, 17, 8, 42, 5, 11]).unsqueeze(0);
+    let output = no_grad(|| model.forward_t(Some(&input_ids), None, None, None, None, false))?;
+    assert_eq!(output.lm_logits.size(), vec![1, 6, 64]);
+
+    // Decoding the last tokens with the cache of the first tokens gives the same logits
+    let prefix_output = no_grad(|| {
+        model.forward_t(
+            Some(&input_ids.narrow(1, 0, 4)),
+            None,
+            None,
+            None,
+            None,
+            false,
+        )
+    })?;
+    let cached_output = no_grad(|| {
+        model.forward_t(
+            Some(&input_ids.narrow(1, 4, 2)),
+            None,
+            None,
+            prefix_output.next_cache,
+            Some(&Tensor::ones(&[1, 6], (Kind::Int64, Device::Cpu))),
+            false,
+        )
+    })?;
+    assert!(cached_output
+        .lm_logits
+        .allclose(&output.lm_logits.narrow(1, 4, 2), 1e-5, 1e-5, false));
+
+    Ok(())
+}
+
+#[test]
+fn gpt_neox_sequential_residual() -> anyhow::Result<()> {
+    tch::manual_seed(42);
+    let input_ids = Tensor::of_slice(&[3i64, 17, 8, 42]).unsqueeze(0);
+
+    // Both residual variants share the same parameters but compute different outputs
+    let mut parallel_vs = nn::VarStore::new(Device::Cpu);
+    let parallel_model = GptNeoXForCausalLM::new(parallel_vs.root(), &tiny_gpt_neox_config());
+    let sequential_vs = nn::VarStore::new(Device::Cpu);
+    let sequential_model = GptNeoXForCausalLM::new(
+        sequential_vs.root(),
+        &GptNeoXConfig {
+            use_parallel_residual: Some(false),
+            ..tiny_gpt_neox_config()
+        },
+    );
+    parallel_vs.copy(&sequential_vs)?;
+
+    let parallel_output =
+        no_grad(|| parallel_model.forward_t(Some(&input_ids), None, None, None, None, false))?;
+    let sequential_output =
+        no_grad(|| sequential_model.forward_t(Some(&input_ids), None, None, None, None, false))?;
+    assert_eq!(sequential_output.lm_logits.size(), vec![1, 4, 64]);
+    assert!(!parallel_output
+        .lm_logits
+        .allclose(&sequential_output.lm_logits, 1e-5, 1e-5, false));
+
+    Ok(())
+}
+
+#[test]
+fn gpt_neox_reference_logits() -> anyhow::Result<()> {
+    let vs = nn::VarStore::new(Device::Cpu);
+    // Rotating only half of the head dimensions catches deviations from the reference partial rotary
+    // embeddings and fused query/key/value layout
+    let config = GptNeoXConfig {
+        rotary_pct: 0.5,
+        ..tiny_gpt_neox_config()
+    };
+    let model = GptNeoXForCausalLM::new(vs.root(), &config);
+    assert_eq!(vs.variables().len(), 28);
+    set_reference_weights(&vs);
+
+    let input_ids = Tensor::of_slice(&[3i64, 17, 8, 42, 5, 11]).unsqueeze(0);
+    let output = no_grad(|| model.forward_t(Some(&input_ids), None, None, None, None, false))?;
+
+    // Reference logits of the Transformers implementation, printed by `utils/reference_logits.py`
+    let expected_position_2 =
+        Tensor::of_slice(&[-0.5461, -0.3107, -0.4334, 0.2893, -0.5249, -0.0451]);
+    let expected_position_5 = Tensor::of_slice(&[0.3092, -0.6265, 0.9436, -0.2219, 0.1340, 0.8964]);
+    let logits = output.lm_logits.get(0).to_kind(Kind::Double);
+    assert!(logits
+        .get(2)
+        .narrow(0, 0, 6)
+        .allclose(&expected_position_2, 1e-4, 1e-4, false));
+    assert!(logits
+        .get(5)
+        .narrow(0, 0, 6)
+        .allclose(&expected_position_5, 1e-4, 1e-4, false));
+
+    Ok(())
+}
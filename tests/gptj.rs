@@ -0,0 +1,100 @@
+mod common;
+
+use common::{set_reference_weights, tiny_gptj_config};
+use rust_bert::gptj::GptJForCausalLM;
+use tch::{nn, no_grad, Device, Kind, Tensor};
+
+#[test]
+fn gptj_parameter_names() -> anyhow::Result<()> {
+    let vs = nn::VarStore::new(Device::Cpu);
+    let config = tiny_gptj_config();
+    let _ = GptJForCausalLM::new(vs.root(), &config);
+
+    let variables = vs.variables();
+    assert_eq!(
+        variables["transformer.h.0.attn.q_proj.weight"].size(),
+        vec![32, 32]
+    );
+    assert!(!variables.contains_key("transformer.h.0.attn.q_proj.bias"));
+    assert_eq!(
+        variables["transformer.h.1.mlp.fc_in.weight"].size(),
+        vec![128, 32]
+    );
+    assert_eq!(variables["transformer.h.1.ln_1.weight"].size(), vec![32]);
+    assert!(!variables.contains_key("transformer.h.1.ln_2.weight"));
+    assert_eq!(variables["transformer.ln_f.weight"].size(), vec![32]);
+    assert_eq!(variables["lm_head.weight"].size(), vec![64, 32]);
+    assert_eq!(variables["lm_head.bias"].size(), vec![64]);
+
+    Ok(())
+}
+
+#[test]
+fn gptj_cached_decoding() -> anyhow::Result<()> {
+    tch::manual_seed(42);
+    let vs = nn::VarStore::new(Device::Cpu);
+    let config = tiny_gptj_config();
+    let model = GptJForCausalLM::new(vs.root(), &config);
+
+    let input_ids = Tensor::of_slice(&[3i64, 17, 8, 42, 5, 11]).unsqueeze(0);
+    let output = no_grad(|| model.forward_t(Some(&input_ids), None, None, None, None, false))?;
+    assert_eq!(output.lm_logits.size(), vec![1, 6, 64]);
+
+    // Decoding the last tokens with the cache of the first tokens gives the same logits
+    let prefix_output = no_grad(|| {
+        model.forward_t(
+            Some(&input_ids.narrow(1, 0, 4)),
+            None,
+            None,
+            None,
+            None,
+            false,
+        )
+    })?;
+    let cached_output = no_grad(|| {
+        model.forward_t(
+            Some(&input_ids.narrow(1, 4, 2)),
+            None,
+            None,
+            prefix_output.next_cache,
+            Some(&Tensor::ones(&[1, 6], (Kind::Int64, Device::Cpu))),
+            false,
+        )
+    })?;
+    assert!(cached_output
+        .lm_logits
+        .allclose(&output.lm_logits.narrow(1, 4, 2), 1e-5, 1e-5, false));
+
+    Ok(())
+}
+
+#[test]
+fn gptj_reference_logits() -> anyhow::Result<()> {
+    let vs = nn::VarStore::new(Device::Cpu);
+    // Rotating only half of the head dimensions with interleaved pairs catches deviations from the
+    // reference partial rotary embeddings
+    let config = tiny_gptj_config();
+    let model = GptJForCausalLM::new(vs.root(), &config);
+    assert_eq!(vs.variables().len(), 25);
+    set_reference_weights(&vs);
+
+    let input_ids = Tensor::of_slice(&[3i64, 17, 8, 42, 5, 11]).unsqueeze(0);
+    let output = no_grad(|| model.forward_t(Some(&input_ids), None, None, None, None, false))?;
+
+    // Reference logits of the Transformers implementation, printed by `utils/reference_logits.py`
+    let expected_position_2 =
+        Tensor::of_slice(&[0.2796, -1.4956, -0.3720, 0.3739, -0.8338, -0.0275]);
+    let expected_position_5 =
+        Tensor::of_slice(&[-0.6404, -1.7014, -0.4938, -0.0942, -0.1605, -0.7272]);
+    let logits = output.lm_logits.get(0).to_kind(Kind::Double);
+    assert!(logits
+        .get(2)
+        .narrow(0, 0, 6)
+        .allclose(&expected_position_2, 1e-4, 1e-4, false));
+    assert!(logits
+        .get(5)
+        .narrow(0, 0, 6)
+        .allclose(&expected_position_5, 1e-4, 1e-4, false));
+
+    Ok(())
+}
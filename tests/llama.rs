@@ -0,0 +1,147 @@
+mod common;
+
+use common::{set_reference_weights, tiny_llama_config};
+use rust_bert::llama::{LlamaConfig, LlamaForCausalLM};
+use tch::{nn, no_grad, Device, Kind, Tensor};
+
+#[test]
+fn llama_parameter_names() -> anyhow::Result<()> {
+    let vs = nn::VarStore::new(Device::Cpu);
+    let config = tiny_llama_config();
+    let _ = LlamaForCausalLM::new(vs.root(), &config);
+
+    let variables = vs.variables();
+    assert_eq!(
+        variables["model.layers.0.self_attn.q_proj.weight"].size(),
+        vec![32, 32]
+    );
+    assert_eq!(
+        variables["model.layers.0.self_attn.k_proj.weight"].size(),
+        vec![16, 32]
+    );
+    assert_eq!(
+        variables["model.layers.1.mlp.gate_proj.weight"].size(),
+        vec![48, 32]
+    );
+    assert_eq!(
+        variables["model.layers.1.post_attention_layernorm.weight"].size(),
+        vec![32]
+    );
+    assert_eq!(variables["model.norm.weight"].size(), vec![32]);
+    assert_eq!(variables["lm_head.weight"].size(), vec![64, 32]);
+
+    let vs = nn::VarStore::new(Device::Cpu);
+    let config = LlamaConfig {
+        tie_word_embeddings: Some(true),
+        ..tiny_llama_config()
+    };
+    let _ = LlamaForCausalLM::new(vs.root(), &config);
+    assert!(!vs.variables().contains_key("lm_head.weight"));
+
+    Ok(())
+}
+
+#[test]
+fn llama_cached_decoding() -> anyhow::Result<()> {
+    tch::manual_seed(42);
+    let vs = nn::VarStore::new(Device::Cpu);
+    let config = tiny_llama_config();
+    let model = LlamaForCausalLM::new(vs.root(), &config);
+
+    let input_ids = Tensor::of_slice(&[3i64, 17, 8, 42, 5, 11]).unsqueeze(0);
+    let output = no_grad(|| model.forward_t(Some(&input_ids), None, None, None, None, false))?;
+    assert_eq!(output.lm_logits.size(), vec![1, 6, 64]);
+
+    // Decoding the last tokens with the cache of the first tokens gives the same logits
+    let prefix_output = no_grad(|| {
+        model.forward_t(
+            Some(&input_ids.narrow(1, 0, 4)),
+            None,
+            None,
+            None,
+            None,
+            false,
+        )
+    })?;
+    let cached_output = no_grad(|| {
+        model.forward_t(
+            Some(&input_ids.narrow(1, 4, 2)),
+            None,
+            None,
+            prefix_output.next_cache,
+            Some(&Tensor::ones(&[1, 6], (Kind::Int64, Device::Cpu))),
+            false,
+        )
+    })?;
+    assert!(cached_output
+        .lm_logits
+        .allclose(&output.lm_logits.narrow(1, 4, 2), 1e-5, 1e-5, false));
+
+    Ok(())
+}
+
+#[test]
+fn llama_left_padding() -> anyhow::Result<()> {
+    tch::manual_seed(42);
+    let vs = nn::VarStore::new(Device::Cpu);
+    let config = tiny_llama_config();
+    let model = LlamaForCausalLM::new(vs.root(), &config);
+
+    let input_ids = Tensor::of_slice(&[3i64, 17, 8]).unsqueeze(0);
+    let output = no_grad(|| model.forward_t(Some(&input_ids), None, None, None, None, false))?;
+
+    // Left-padded input with the padding masked and the positions starting at the first token
+    let padded_input_ids = Tensor::of_slice(&[0i64, 0, 3, 17, 8]).unsqueeze(0);
+    let attention_mask = Tensor::of_slice(&[0i64, 0, 1, 1, 1]).unsqueeze(0);
+    let position_ids = Tensor::of_slice(&[1i64, 1, 0, 1, 2]).unsqueeze(0);
+    let padded_output = no_grad(|| {
+        model.forward_t(
+            Some(&padded_input_ids),
+            None,
+            Some(&position_ids),
+            None,
+            Some(&attention_mask),
+            false,
+        )
+    })?;
+    assert!(padded_output
+        .lm_logits
+        .narrow(1, 2, 3)
+        .allclose(&output.lm_logits, 1e-5, 1e-5, false));
+
+    Ok(())
+}
+
+#[test]
+fn llama_reference_logits() -> anyhow::Result<()> {
+    let vs = nn::VarStore::new(Device::Cpu);
+    // A large normalization epsilon and grouped-query attention catch deviations from the reference
+    // RMS normalization, rotary embeddings layout and key/value heads mapping
+    let config = LlamaConfig {
+        rms_norm_eps: 1e-2,
+        ..tiny_llama_config()
+    };
+    let model = LlamaForCausalLM::new(vs.root(), &config);
+    assert_eq!(vs.variables().len(), 21);
+    set_reference_weights(&vs);
+
+    let input_ids = Tensor::of_slice(&[3i64, 17, 8, 42, 5, 11]).unsqueeze(0);
+    let output = no_grad(|| model.forward_t(Some(&input_ids), None, None, None, None, false))?;
+
+    // Reference logits of the Transformers implementation, printed by `utils/reference_logits.py`
+    let expected_position_2 =
+        Tensor::of_slice(&[-1.3764, 0.0668, 0.4051, -1.5427, -1.4483, -0.2850]);
+    let expected_position_5 =
+        Tensor::of_slice(&[-0.7525, -1.2156, 0.3702, -0.0747, -1.3806, 0.6124]);
+    let logits = output.lm_logits.get(0).to_kind(Kind::Double);
+    assert!(logits
+        .get(2)
+        .narrow(0, 0, 6)
+        .allclose(&expected_position_2, 1e-4, 1e-4, false));
+    assert!(logits
+        .get(5)
+        .narrow(0, 0, 6)
+        .allclose(&expected_position_5, 1e-4, 1e-4, false));
+
+    Ok(())
+}
@@ -95,6 +95,7 @@ fn longformer_masked_lm() -> anyhow::Result<()> {
             None,
             None,
             None,
+            None,
             false,
         )
     })?;
@@ -229,6 +230,7 @@ fn longformer_for_sequence_classification() -> anyhow::Result<()> {
             None,
             None,
             None,
+            None,
             false,
         )
     })?;
@@ -369,6 +371,7 @@ fn longformer_for_token_classification() -> anyhow::Result<()> {
             None,
             None,
             None,
+            None,
             false,
         )
     })?;
@@ -61,8 +61,9 @@ fn mobilebert_masked_model() -> anyhow::Result<()> {
     let input_tensor = Tensor::stack(tokenized_input.as_slice(), 0).to(device);
 
     //    Forward pass
-    let model_output =
-        no_grad(|| mobilebert_model.forward_t(Some(&input_tensor), None, None, None, None, false))?;
+    let model_output = no_grad(|| {
+        mobilebert_model.forward_t(Some(&input_tensor), None, None, None, None, None, false)
+    })?;
 
     //    Print masked tokens
     let index_1 = model_output.logits.get(0).get(4).argmax(0, false);
@@ -152,8 +153,17 @@ fn mobilebert_for_sequence_classification() -> anyhow::Result<()> {
     let input_tensor = Tensor::stack(tokenized_input.as_slice(), 0).to(device);
 
     //    Forward pass
-    let model_output =
-        no_grad(|| model.forward_t(Some(input_tensor.as_ref()), None, None, None, None, false))?;
+    let model_output = no_grad(|| {
+        model.forward_t(
+            Some(input_tensor.as_ref()),
+            None,
+            None,
+            None,
+            None,
+            None,
+            false,
+        )
+    })?;
 
     assert_eq!(model_output.logits.size(), &[2, 3]);
     Ok(())
@@ -262,8 +272,17 @@ fn mobilebert_for_token_classification() -> anyhow::Result<()> {
     let input_tensor = Tensor::stack(tokenized_input.as_slice(), 0).to(device);
 
     //    Forward pass
-    let model_output =
-        no_grad(|| model.forward_t(Some(input_tensor.as_ref()), None, None, None, None, false))?;
+    let model_output = no_grad(|| {
+        model.forward_t(
+            Some(input_tensor.as_ref()),
+            None,
+            None,
+            None,
+            None,
+            None,
+            false,
+        )
+    })?;
 
     assert_eq!(model_output.logits.size(), &[2, 7, 4]);
 
@@ -314,8 +333,18 @@ fn mobilebert_for_question_answering() -> anyhow::Result<()> {
     let input_tensor = Tensor::stack(tokenized_input.as_slice(), 0).to(device);
 
     //    Forward pass
-    let model_output =
-        no_grad(|| model.forward_t(Some(input_tensor.as_ref()), None, None, None, None, false))?;
+    let model_output = no_grad(|| {
+        model.forward_t(
+            Some(input_tensor.as_ref()),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            false,
+        )
+    })?;
 
     assert_eq!(model_output.start_logits.size(), &[1, 16]);
     assert_eq!(model_output.end_logits.size(), &[1, 16]);
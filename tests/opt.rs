@@ -0,0 +1,156 @@
+mod common;
+
+use common::{set_reference_weights, tiny_opt_config};
+use rust_bert::opt::{OptConfig, OptForCausalLM};
+use tch::{nn, no_grad, Device, Kind, Tensor};
+
+#[test]
+fn opt_parameter_names() -> anyhow::Result<()> {
+    let vs = nn::VarStore::new(Device::Cpu);
+    let config = tiny_opt_config();
+    let _ = OptForCausalLM::new(vs.root(), &config);
+
+    let variables = vs.variables();
+    assert_eq!(
+        variables["model.decoder.embed_positions.weight"].size(),
+        vec![66, 32]
+    );
+    assert_eq!(
+        variables["model.decoder.layers.0.self_attn.q_proj.bias"].size(),
+        vec![32]
+    );
+    assert_eq!(
+        variables["model.decoder.layers.1.fc1.weight"].size(),
+        vec![48, 32]
+    );
+    assert_eq!(
+        variables["model.decoder.final_layer_norm.weight"].size(),
+        vec![32]
+    );
+    assert!(!variables.contains_key("lm_head.weight"));
+
+    // Post-normalization checkpoints with projected word embeddings (e.g. OPT-350M)
+    let vs = nn::VarStore::new(Device::Cpu);
+    let config = OptConfig {
+        do_layer_norm_before: Some(false),
+        word_embed_proj_dim: Some(16),
+        ..tiny_opt_config()
+    };
+    let _ = OptForCausalLM::new(vs.root(), &config);
+    let variables = vs.variables();
+    assert_eq!(
+        variables["model.decoder.embed_tokens.weight"].size(),
+        vec![64, 16]
+    );
+    assert_eq!(
+        variables["model.decoder.project_in.weight"].size(),
+        vec![32, 16]
+    );
+    assert_eq!(
+        variables["model.decoder.project_out.weight"].size(),
+        vec![16, 32]
+    );
+    assert!(!variables.contains_key("model.decoder.final_layer_norm.weight"));
+
+    Ok(())
+}
+
+#[test]
+fn opt_cached_decoding() -> anyhow::Result<()> {
+    tch::manual_seed(42);
+    let vs = nn::VarStore::new(Device::Cpu);
+    let config = tiny_opt_config();
+    let model = OptForCausalLM::new(vs.root(), &config);
+
+    let input_ids = Tensor::of_slice(&[3i64, 17, 8, 42, 5, 11]).unsqueeze(0);
+    let output = no_grad(|| model.forward_t(Some(&input_ids), None, None, None, None, false))?;
+    assert_eq!(output.lm_logits.size(), vec![1, 6, 64]);
+
+    // Decoding the last tokens with the cache of the first tokens gives the same logits
+    let prefix_output = no_grad(|| {
+        model.forward_t(
+            Some(&input_ids.narrow(1, 0, 4)),
+            None,
+            None,
+            None,
+            None,
+            false,
+        )
+    })?;
+    let cached_output = no_grad(|| {
+        model.forward_t(
+            Some(&input_ids.narrow(1, 4, 2)),
+            None,
+            None,
+            prefix_output.next_cache,
+            Some(&Tensor::ones(&[1, 6], (Kind::Int64, Device::Cpu))),
+            false,
+        )
+    })?;
+    assert!(cached_output
+        .lm_logits
+        .allclose(&output.lm_logits.narrow(1, 4, 2), 1e-5, 1e-5, false));
+
+    Ok(())
+}
+
+#[test]
+fn opt_left_padding() -> anyhow::Result<()> {
+    tch::manual_seed(42);
+    let vs = nn::VarStore::new(Device::Cpu);
+    let config = tiny_opt_config();
+    let model = OptForCausalLM::new(vs.root(), &config);
+
+    let input_ids = Tensor::of_slice(&[3i64, 17, 8]).unsqueeze(0);
+    let output = no_grad(|| model.forward_t(Some(&input_ids), None, None, None, None, false))?;
+
+    // The positions are computed from the attention mask and start at the first token
+    let padded_input_ids = Tensor::of_slice(&[1i64, 1, 3, 17, 8]).unsqueeze(0);
+    let attention_mask = Tensor::of_slice(&[0i64, 0, 1, 1, 1]).unsqueeze(0);
+    let padded_output = no_grad(|| {
+        model.forward_t(
+            Some(&padded_input_ids),
+            None,
+            None,
+            None,
+            Some(&attention_mask),
+            false,
+        )
+    })?;
+    assert!(padded_output
+        .lm_logits
+        .narrow(1, 2, 3)
+        .allclose(&output.lm_logits, 1e-5, 1e-5, false));
+
+    Ok(())
+}
+
+#[test]
+fn opt_reference_logits() -> anyhow::Result<()> {
+    let vs = nn::VarStore::new(Device::Cpu);
+    // The learned position embeddings are looked up with an offset of 2
+    let config = tiny_opt_config();
+    let model = OptForCausalLM::new(vs.root(), &config);
+    assert_eq!(vs.variables().len(), 36);
+    set_reference_weights(&vs);
+
+    let input_ids = Tensor::of_slice(&[3i64, 17, 8, 42, 5, 11]).unsqueeze(0);
+    let output = no_grad(|| model.forward_t(Some(&input_ids), None, None, None, None, false))?;
+
+    // Reference logits of the Transformers implementation, printed by `utils/reference_logits.py`
+    let expected_position_2 =
+        Tensor::of_slice(&[0.1701, -0.7860, -0.6143, 0.1196, 0.8311, -0.4484]);
+    let expected_position_5 =
+        Tensor::of_slice(&[0.3160, -0.5948, -0.9272, -0.2204, 0.9368, -0.6572]);
+    let logits = output.lm_logits.get(0).to_kind(Kind::Double);
+    assert!(logits
+        .get(2)
+        .narrow(0, 0, 6)
+        .allclose(&expected_position_2, 1e-4, 1e-4, false));
+    assert!(logits
+        .get(5)
+        .narrow(0, 0, 6)
+        .allclose(&expected_position_5, 1e-4, 1e-4, false));
+
+    Ok(())
+}
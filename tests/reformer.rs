@@ -123,8 +123,9 @@ fn reformer_for_sequence_classification() -> anyhow::Result<()> {
     let input_tensor = Tensor::stack(tokenized_input.as_slice(), 0).to(device);
 
     //    Forward pass
-    let model_output =
-        no_grad(|| reformer_model.forward_t(Some(&input_tensor), None, None, None, None, false))?;
+    let model_output = no_grad(|| {
+        reformer_model.forward_t(Some(&input_tensor), None, None, None, None, None, false)
+    })?;
 
     assert_eq!(model_output.logits.size(), &[2, 3]);
     assert_eq!(
@@ -184,8 +185,18 @@ fn reformer_for_question_answering() -> anyhow::Result<()> {
     let input_tensor = Tensor::stack(tokenized_input.as_slice(), 0).to(device);
 
     //    Forward pass
-    let model_output =
-        no_grad(|| reformer_model.forward_t(Some(&input_tensor), None, None, None, None, false))?;
+    let model_output = no_grad(|| {
+        reformer_model.forward_t(
+            Some(&input_tensor),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            false,
+        )
+    })?;
 
     assert_eq!(model_output.start_logits.size(), &[2, 19]);
     assert_eq!(model_output.end_logits.size(), &[2, 19]);
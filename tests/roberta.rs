@@ -3,8 +3,14 @@ use rust_bert::pipelines::ner::NERModel;
 use rust_bert::pipelines::question_answering::{
     QaInput, QuestionAnsweringConfig, QuestionAnsweringModel,
 };
+use rust_bert::pipelines::sentiment::{
+    AspectSentimentConfig, AspectSentimentMethod, AspectSentimentModel, SentimentPolarity,
+};
+use rust_bert::pipelines::sequence_classification::{
+    SequenceClassificationConfig, SequenceClassificationModel,
+};
 use rust_bert::pipelines::token_classification::TokenClassificationConfig;
-use rust_bert::resources::{RemoteResource, ResourceProvider};
+use rust_bert::resources::{LocalResource, RemoteResource, ResourceProvider};
 use rust_bert::roberta::{
     RobertaConfig, RobertaConfigResources, RobertaForMaskedLM, RobertaForMultipleChoice,
     RobertaForSequenceClassification, RobertaForTokenClassification, RobertaMergesResources,
@@ -83,6 +89,7 @@ fn roberta_masked_lm() -> anyhow::Result<()> {
             None,
             None,
             None,
+            None,
             false,
         )
     });
@@ -161,8 +168,9 @@ fn roberta_for_sequence_classification() -> anyhow::Result<()> {
     let input_tensor = Tensor::stack(tokenized_input.as_slice(), 0).to(device);
 
     //    Forward pass
-    let model_output =
-        no_grad(|| roberta_model.forward_t(Some(&input_tensor), None, None, None, None, false));
+    let model_output = no_grad(|| {
+        roberta_model.forward_t(Some(&input_tensor), None, None, None, None, None, false)
+    });
 
     assert_eq!(model_output.logits.size(), &[2, 3]);
     assert_eq!(
@@ -298,8 +306,9 @@ fn roberta_for_token_classification() -> anyhow::Result<()> {
     let input_tensor = Tensor::stack(tokenized_input.as_slice(), 0).to(device);
 
     //    Forward pass
-    let model_output =
-        no_grad(|| roberta_model.forward_t(Some(&input_tensor), None, None, None, None, false));
+    let model_output = no_grad(|| {
+        roberta_model.forward_t(Some(&input_tensor), None, None, None, None, None, false)
+    });
 
     assert_eq!(model_output.logits.size(), &[2, 9, 4]);
     assert_eq!(
@@ -400,3 +409,144 @@ fn xlm_roberta_german_ner() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+/// Saves a randomly initialized XLM-RoBERTa sequence classifier (with the XLM-RoBERTa vocabulary size
+/// and a single token type) to `directory`, returning a pipeline configuration using the XLM-RoBERTa tokenizer
+fn tiny_xlm_roberta_classifier(
+    directory: &std::path::Path,
+    labels: &[&str],
+) -> anyhow::Result<SequenceClassificationConfig> {
+    let config = RobertaConfig {
+        hidden_size: 16,
+        intermediate_size: 32,
+        num_attention_heads: 2,
+        num_hidden_layers: 1,
+        max_position_embeddings: 514,
+        type_vocab_size: 1,
+        vocab_size: 250002,
+        id2label: Some(
+            labels
+                .iter()
+                .enumerate()
+                .map(|(id, label)| (id as i64, label.to_string()))
+                .collect(),
+        ),
+        ..Default::default()
+    };
+    std::fs::write(
+        directory.join("config.json"),
+        serde_json::to_string(&config)?,
+    )?;
+    let vs = nn::VarStore::new(Device::Cpu);
+    let _ = RobertaForSequenceClassification::new(vs.root(), &config)?;
+    vs.save(directory.join("rust_model.ot"))?;
+    Ok(xlm_roberta_classifier_config(directory))
+}
+
+/// Pipeline configuration of the classifier saved by `tiny_xlm_roberta_classifier`
+fn xlm_roberta_classifier_config(directory: &std::path::Path) -> SequenceClassificationConfig {
+    let mut classification_config = SequenceClassificationConfig::new(
+        ModelType::XLMRoberta,
+        LocalResource::from(directory.join("rust_model.ot")),
+        LocalResource::from(directory.join("config.json")),
+        RemoteResource::from_pretrained(RobertaVocabResources::XLM_ROBERTA_NER_EN),
+        None,
+        false,
+        None,
+        None,
+    );
+    classification_config.device = Device::Cpu;
+    classification_config
+}
+
+#[test]
+fn xlm_roberta_sequence_classification_pairs() -> anyhow::Result<()> {
+    // The XLM-RoBERTa tokenizer assigns a segment id of 1 to the second sequence of a pair,
+    // which must not be used as a token type by models with a single token type
+    let directory = tempfile::tempdir()?;
+    let model = SequenceClassificationModel::new(tiny_xlm_roberta_classifier(
+        directory.path(),
+        &["contradiction", "entailment"],
+    )?)?;
+
+    let input = [
+        ("Ich lebe in Berlin.", "Berlin ist eine Stadt."),
+        (
+            "This is a sentence",
+            "A much longer hypothesis for the pair input.",
+        ),
+    ];
+    let labels = model.predict_pairs(&input);
+    assert_eq!(labels.len(), 2);
+    let multilabels = model.predict_multilabel_pairs(&input, 0.0)?;
+    assert_eq!(multilabels.len(), 2);
+    assert_eq!(multilabels[0].len(), 2);
+
+    Ok(())
+}
+
+#[test]
+fn xlm_roberta_aspect_sentiment_pairs() -> anyhow::Result<()> {
+    // Pair classification model taking (sentence, aspect) pairs, with sentiment labels
+    let directory = tempfile::tempdir()?;
+    let model = AspectSentimentModel::new(AspectSentimentConfig::new(
+        tiny_xlm_roberta_classifier(directory.path(), &["negative", "neutral", "positive"])?,
+        AspectSentimentMethod::PairClassification,
+    ))?;
+
+    let input = [
+        "Das Essen war köstlich. The service, however, was slow!",
+        "Nothing to see here.",
+        "Great location; the rooms were noisy.",
+    ];
+    let output = model.predict(&input, &["essen", "service", "rooms", "room"]);
+    assert_eq!(output.len(), 3);
+    assert!(output[1].is_empty());
+
+    let aspects = output
+        .iter()
+        .map(|sentiments| {
+            sentiments
+                .iter()
+                .map(|sentiment| {
+                    (
+                        sentiment.aspect.as_str(),
+                        sentiment.span.as_str(),
+                        sentiment.offset.begin,
+                        sentiment.offset.end,
+                    )
+                })
+                .collect::<Vec<_>>()
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(
+        aspects[0],
+        [
+            ("essen", "Das Essen war köstlich.", 0, 23),
+            ("service", "The service, however, was slow!", 24, 55),
+        ]
+    );
+    // Aspects are matched as whole words: "room" does not match "rooms"
+    assert_eq!(aspects[2], [("rooms", "the rooms were noisy.", 16, 37)]);
+
+    // The polarities and scores are those of the classifier for the (span, aspect) pairs
+    let classifier =
+        SequenceClassificationModel::new(xlm_roberta_classifier_config(directory.path()))?;
+    let sentiments = output.iter().flatten().collect::<Vec<_>>();
+    let pairs = sentiments
+        .iter()
+        .map(|sentiment| (sentiment.span.as_str(), sentiment.aspect.as_str()))
+        .collect::<Vec<_>>();
+    let labels = classifier.predict_pairs(&pairs);
+    for (sentiment, label) in sentiments.iter().zip(labels) {
+        let polarity = match label.text.as_str() {
+            "negative" => SentimentPolarity::Negative,
+            "neutral" => SentimentPolarity::Neutral,
+            _ => SentimentPolarity::Positive,
+        };
+        assert_eq!(sentiment.polarity, polarity);
+        assert!((sentiment.score - label.score).abs() < 1e-6);
+    }
+
+    Ok(())
+}
@@ -1,9 +1,26 @@
+use rust_bert::bert::{BertConfig, BertVocabResources};
+use rust_bert::deberta::{DebertaConfig, DebertaMergesResources, DebertaVocabResources};
+use rust_bert::deberta_v2::{DebertaV2Config, DebertaV2VocabResources};
+use rust_bert::electra::{ElectraConfig, ElectraVocabResources};
+use rust_bert::fnet::{FNetConfig, FNetVocabResources};
+use rust_bert::longformer::{
+    LongformerConfig, LongformerMergesResources, LongformerVocabResources,
+};
+use rust_bert::mobilebert::{MobileBertConfig, MobileBertVocabResources};
+use rust_bert::pipelines::common::{ConfigOption, ModelType};
 use rust_bert::pipelines::keywords_extraction::{
     KeywordExtractionConfig, KeywordExtractionModel, KeywordScorerType,
 };
 use rust_bert::pipelines::sentence_embeddings::{
     SentenceEmbeddingsBuilder, SentenceEmbeddingsConfig, SentenceEmbeddingsModelType,
+    SentenceEmbeddingsModuleConfig, SentenceEmbeddingsModuleType, SentenceEmbeddingsModulesConfig,
+    SentenceEmbeddingsOption, SentenceEmbeddingsTokenizerConfig,
 };
+use rust_bert::resources::{RemoteResource, ResourceProvider};
+use rust_bert::roberta::{RobertaConfig, RobertaVocabResources};
+use rust_bert::Config;
+use std::path::Path;
+use tch::{nn, Device};
 
 #[test]
 fn sbert_distilbert() -> anyhow::Result<()> {
@@ -58,6 +75,44 @@ fn sbert_bert() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[test]
+fn sbert_truncate_dim() -> anyhow::Result<()> {
+    let mut model = SentenceEmbeddingsBuilder::remote(SentenceEmbeddingsModelType::AllMiniLmL12V2)
+        .create_model()?;
+
+    let sentences = ["this is an example sentence", "each sentence is converted"];
+    let full_embeddings = model.encode(&sentences)?;
+
+    model.set_truncate_dim(Some(64));
+    let embeddings = model.encode(&sentences)?;
+    for (embedding, full_embedding) in embeddings.iter().zip(full_embeddings.iter()) {
+        assert_eq!(embedding.len(), 64);
+        // The truncated embeddings are normalized after truncation
+        let norm = embedding
+            .iter()
+            .map(|value| value * value)
+            .sum::<f32>()
+            .sqrt();
+        assert!((norm - 1.0).abs() < 1e-4);
+        let prefix_norm = full_embedding[..64]
+            .iter()
+            .map(|value| value * value)
+            .sum::<f32>()
+            .sqrt();
+        for (value, full_value) in embedding.iter().zip(full_embedding.iter()) {
+            assert!((value - full_value / prefix_norm).abs() < 1e-4);
+        }
+    }
+
+    // Dimensions larger than the embeddings size keep the full embeddings
+    model.set_truncate_dim(Some(1024));
+    assert_eq!(model.encode(&sentences)?[0].len(), full_embeddings[0].len());
+    model.set_truncate_dim(None);
+    assert_eq!(model.encode(&sentences)?[0].len(), full_embeddings[0].len());
+
+    Ok(())
+}
+
 #[test]
 fn sbert_bert_small() -> anyhow::Result<()> {
     let model = SentenceEmbeddingsBuilder::remote(SentenceEmbeddingsModelType::AllMiniLmL12V2)
@@ -159,6 +214,463 @@ fn sbert_t5() -> anyhow::Result<()> {
     Ok(())
 }
 
+#[test]
+fn sbert_paraphrase_multilingual() -> anyhow::Result<()> {
+    // BERT encoder with an XLM-RoBERTa tokenizer
+    let model = SentenceEmbeddingsBuilder::remote(
+        SentenceEmbeddingsModelType::ParaphraseMultilingualMiniLmL12V2,
+    )
+    .create_model()?;
+
+    let sentences = [
+        "This is an example sentence",
+        "Dies ist ein Beispielsatz",
+        "The stock market closed lower today",
+    ];
+    let embeddings = model.encode(&sentences)?;
+
+    assert_eq!(embeddings.len(), 3);
+    assert!(embeddings.iter().all(|embedding| embedding.len() == 384));
+    // Translations are closer to each other than unrelated sentences
+    assert!(
+        cosine_similarity(&embeddings[0], &embeddings[1])
+            > cosine_similarity(&embeddings[0], &embeddings[2])
+    );
+
+    Ok(())
+}
+
+#[test]
+fn sbert_labse() -> anyhow::Result<()> {
+    // BERT encoder followed by a Dense layer and a normalization layer
+    let model =
+        SentenceEmbeddingsBuilder::remote(SentenceEmbeddingsModelType::Labse).create_model()?;
+
+    let sentences = [
+        "This is an example sentence",
+        "Dies ist ein Beispielsatz",
+        "The stock market closed lower today",
+    ];
+    let embeddings = model.encode(&sentences)?;
+
+    assert_eq!(embeddings.len(), 3);
+    for embedding in embeddings.iter() {
+        assert_eq!(embedding.len(), 768);
+        let norm = embedding
+            .iter()
+            .map(|value| value * value)
+            .sum::<f32>()
+            .sqrt();
+        assert!((norm - 1.0).abs() < 1e-4);
+    }
+    assert!(
+        cosine_similarity(&embeddings[0], &embeddings[1])
+            > cosine_similarity(&embeddings[0], &embeddings[2])
+    );
+
+    Ok(())
+}
+
+#[test]
+fn sbert_local_backbones() -> anyhow::Result<()> {
+    let backbones: Vec<(&str, serde_json::Value, &str, Vec<TokenizerFile>)> = vec![
+        (
+            "deberta",
+            serde_json::to_value(DebertaConfig {
+                hidden_size: 32,
+                intermediate_size: 64,
+                max_position_embeddings: 32,
+                num_attention_heads: 4,
+                num_hidden_layers: 2,
+                pooler_hidden_size: Some(32),
+                ..Default::default()
+            })?,
+            "DebertaTokenizer",
+            vec![
+                ("vocab.json", DebertaVocabResources::DEBERTA_BASE),
+                ("merges.txt", DebertaMergesResources::DEBERTA_BASE),
+            ],
+        ),
+        (
+            "deberta-v2",
+            serde_json::to_value(DebertaV2Config {
+                hidden_size: 32,
+                intermediate_size: 64,
+                max_position_embeddings: 32,
+                num_attention_heads: 4,
+                num_hidden_layers: 2,
+                ..Default::default()
+            })?,
+            "DebertaV2Tokenizer",
+            vec![("spm.model", DebertaV2VocabResources::DEBERTA_V3_BASE)],
+        ),
+        (
+            "xlm-roberta",
+            serde_json::to_value(RobertaConfig {
+                hidden_size: 32,
+                intermediate_size: 64,
+                max_position_embeddings: 32,
+                num_attention_heads: 4,
+                num_hidden_layers: 2,
+                type_vocab_size: 1,
+                vocab_size: 250002,
+                ..Default::default()
+            })?,
+            "XLMRobertaTokenizer",
+            vec![(
+                "sentencepiece.bpe.model",
+                RobertaVocabResources::XLM_ROBERTA_NER_EN,
+            )],
+        ),
+        (
+            "electra",
+            serde_json::to_value(ElectraConfig {
+                embedding_size: 16,
+                hidden_size: 32,
+                intermediate_size: 64,
+                max_position_embeddings: 32,
+                num_attention_heads: 4,
+                num_hidden_layers: 2,
+                ..Default::default()
+            })?,
+            "ElectraTokenizer",
+            vec![("vocab.txt", ElectraVocabResources::BASE_DISCRIMINATOR)],
+        ),
+        (
+            "longformer",
+            serde_json::to_value(LongformerConfig {
+                attention_window: vec![4, 4],
+                hidden_size: 32,
+                intermediate_size: 64,
+                max_position_embeddings: 32,
+                num_attention_heads: 4,
+                num_hidden_layers: 2,
+                type_vocab_size: 1,
+                vocab_size: 50265,
+                pad_token_id: Some(1),
+                ..Default::default()
+            })?,
+            "LongformerTokenizer",
+            vec![
+                ("vocab.json", LongformerVocabResources::LONGFORMER_BASE_4096),
+                (
+                    "merges.txt",
+                    LongformerMergesResources::LONGFORMER_BASE_4096,
+                ),
+            ],
+        ),
+        (
+            "mobilebert",
+            serde_json::to_value(MobileBertConfig {
+                embedding_size: 16,
+                hidden_size: 32,
+                intermediate_size: 32,
+                intra_bottleneck_size: Some(16),
+                max_position_embeddings: 32,
+                num_attention_heads: 4,
+                num_feedforward_networks: Some(2),
+                num_hidden_layers: 2,
+                ..Default::default()
+            })?,
+            "MobileBertTokenizer",
+            vec![("vocab.txt", MobileBertVocabResources::MOBILEBERT_UNCASED)],
+        ),
+        (
+            "fnet",
+            serde_json::to_value(FNetConfig {
+                hidden_size: 32,
+                intermediate_size: 64,
+                max_position_embeddings: 32,
+                num_hidden_layers: 2,
+                ..Default::default()
+            })?,
+            "FNetTokenizer",
+            vec![("spiece.model", FNetVocabResources::BASE)],
+        ),
+    ];
+
+    let sentences = ["This is an example sentence", "Short"];
+    for (model_type, config, tokenizer_class, tokenizer_files) in backbones {
+        let model_dir = tempfile::tempdir()?;
+        save_local_model(
+            model_dir.path(),
+            model_type,
+            config,
+            tokenizer_class,
+            &tokenizer_files,
+            &[16, 8],
+        )?;
+        let model = SentenceEmbeddingsBuilder::local(model_dir.path())
+            .with_device(Device::Cpu)
+            .create_model()?;
+
+        let embeddings = model.encode(&sentences)?;
+        assert_eq!(embeddings.len(), 2, "{}", model_type);
+        for embedding in embeddings.iter() {
+            assert_eq!(embedding.len(), 8, "{}", model_type);
+            let norm = embedding
+                .iter()
+                .map(|value| value * value)
+                .sum::<f32>()
+                .sqrt();
+            assert!((norm - 1.0).abs() < 1e-4, "{}", model_type);
+        }
+
+        // FNet does not take an attention mask: the padding changes the embeddings
+        if model_type != "fnet" {
+            let unpadded_embedding = model.encode(&sentences[1..])?.remove(0);
+            for (value, unpadded_value) in embeddings[1].iter().zip(unpadded_embedding.iter()) {
+                assert!((value - unpadded_value).abs() < 1e-4, "{}", model_type);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[test]
+fn sbert_local_dense_modules() -> anyhow::Result<()> {
+    let model_dir = tempfile::tempdir()?;
+    let config = BertConfig {
+        hidden_size: 32,
+        intermediate_size: 64,
+        max_position_embeddings: 32,
+        num_attention_heads: 4,
+        num_hidden_layers: 2,
+        ..Default::default()
+    };
+    save_local_model(
+        model_dir.path(),
+        "bert",
+        serde_json::to_value(config)?,
+        "BertTokenizer",
+        &[("vocab.txt", BertVocabResources::BERT)],
+        &[16, 8],
+    )?;
+
+    let modules_config = model_dir.path().join("modules.json");
+    let modules = SentenceEmbeddingsModulesConfig::from_file(&modules_config);
+    let dense_paths = modules
+        .dense_modules()
+        .iter()
+        .map(|module| module.path.clone())
+        .collect::<Vec<String>>();
+    assert_eq!(dense_paths, ["2_Dense", "3_Dense"]);
+    assert!(modules.has_normalization());
+
+    let sentences = ["This is an example sentence", "Each sentence is converted"];
+    let embeddings = SentenceEmbeddingsBuilder::local(model_dir.path())
+        .with_device(Device::Cpu)
+        .create_model()?
+        .encode_as_tensor(&sentences)?
+        .embeddings;
+
+    // Same transformer without the Dense and normalization layers
+    let pooling_modules = SentenceEmbeddingsModulesConfig(modules[..2].to_vec());
+    std::fs::write(&modules_config, serde_json::to_string(&pooling_modules)?)?;
+    let pooled_embeddings = SentenceEmbeddingsBuilder::local(model_dir.path())
+        .with_device(Device::Cpu)
+        .create_model()?
+        .encode_as_tensor(&sentences)?
+        .embeddings;
+
+    // The Dense layers are applied in order, followed by the normalization
+    let mut expected_embeddings = pooled_embeddings;
+    for (path, in_features, out_features) in [("2_Dense", 32, 16), ("3_Dense", 16, 8)] {
+        let mut vs = nn::VarStore::new(Device::Cpu);
+        let linear = nn::linear(vs.root(), in_features, out_features, Default::default());
+        vs.load(model_dir.path().join(path).join("rust_model.ot"))?;
+        expected_embeddings = expected_embeddings.apply(&linear).tanh();
+    }
+    let norm = expected_embeddings.norm_scalaropt_dim(2, &[1], true);
+    let expected_embeddings = &expected_embeddings / norm.expand_as(&expected_embeddings);
+
+    assert_eq!(embeddings.size(), [2, 8]);
+    assert!(embeddings.allclose(&expected_embeddings, 1e-5, 1e-5, false));
+
+    Ok(())
+}
+
+#[test]
+fn sbert_local_tokenizer_type() -> anyhow::Result<()> {
+    let tokenizer_config = |tokenizer_class: Option<&str>| SentenceEmbeddingsTokenizerConfig {
+        add_prefix_space: None,
+        strip_accents: None,
+        do_lower_case: None,
+        tokenizer_class: tokenizer_class.map(str::to_string),
+    };
+    for (tokenizer_class, tokenizer_type) in [
+        (Some("BertTokenizer"), Some(ModelType::Bert)),
+        (Some("ElectraTokenizerFast"), Some(ModelType::Bert)),
+        (Some("XLMRobertaTokenizerFast"), Some(ModelType::XLMRoberta)),
+        (Some("DebertaV2Tokenizer"), Some(ModelType::DebertaV2)),
+        (Some("MobileBertTokenizerFast"), Some(ModelType::MobileBert)),
+        (Some("PreTrainedTokenizerFast"), None),
+        (None, None),
+    ] {
+        assert_eq!(
+            tokenizer_config(tokenizer_class).tokenizer_type(),
+            tokenizer_type
+        );
+    }
+
+    // BERT encoder with an XLM-RoBERTa tokenizer: the vocabulary file is the one of the tokenizer
+    let model_dir = tempfile::tempdir()?;
+    let config = BertConfig {
+        hidden_size: 32,
+        intermediate_size: 64,
+        max_position_embeddings: 32,
+        num_attention_heads: 4,
+        num_hidden_layers: 2,
+        vocab_size: 250002,
+        ..Default::default()
+    };
+    save_local_model(
+        model_dir.path(),
+        "bert",
+        serde_json::to_value(config)?,
+        "XLMRobertaTokenizerFast",
+        &[(
+            "sentencepiece.bpe.model",
+            RobertaVocabResources::XLM_ROBERTA_NER_EN,
+        )],
+        &[],
+    )?;
+    let model = SentenceEmbeddingsBuilder::local(model_dir.path())
+        .with_device(Device::Cpu)
+        .create_model()?;
+
+    let tokenizer_output = model.tokenize(&["Dies ist ein Beispielsatz"]);
+    // <s> and </s> special tokens of XLM-RoBERTa
+    let tokens_ids = Vec::<i64>::from(&tokenizer_output.tokens_ids[0]);
+    assert_eq!(tokens_ids.first(), Some(&0));
+    assert_eq!(tokens_ids.last(), Some(&2));
+    assert_eq!(model.encode(&["Dies ist ein Beispielsatz"])?[0].len(), 32);
+
+    Ok(())
+}
+
+/// Tokenizer file name and its remote resource
+type TokenizerFile = (&'static str, (&'static str, &'static str));
+
+fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    let dot = a.iter().zip(b.iter()).map(|(x, y)| x * y).sum::<f32>();
+    let norm_a = a.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let norm_b = b.iter().map(|x| x * x).sum::<f32>().sqrt();
+    dot / (norm_a * norm_b)
+}
+
+/// Saves a Sentence Transformers model with randomly initialized weights to `model_dir`: the
+/// transformer defined by `config`, a mean pooling layer, Dense layers with the given output sizes
+/// and a normalization layer.
+fn save_local_model(
+    model_dir: &Path,
+    model_type: &str,
+    mut config: serde_json::Value,
+    tokenizer_class: &str,
+    tokenizer_files: &[TokenizerFile],
+    dense_sizes: &[i64],
+) -> anyhow::Result<()> {
+    // Unset fields are omitted, as in the Hugging Face configuration files
+    let config_fields = config.as_object_mut().unwrap();
+    config_fields.retain(|_, value| !value.is_null());
+    config_fields.insert("model_type".to_string(), model_type.into());
+    let config_path = model_dir.join("config.json");
+    std::fs::write(&config_path, config.to_string())?;
+    let transformer_type: ModelType = serde_json::from_value(model_type.into())?;
+    let vs = nn::VarStore::new(Device::Cpu);
+    let _ = SentenceEmbeddingsOption::new(
+        transformer_type,
+        vs.root(),
+        &ConfigOption::from_file(transformer_type, &config_path),
+    )?;
+    vs.save(model_dir.join("rust_model.ot"))?;
+
+    for (file_name, resource) in tokenizer_files {
+        std::fs::copy(
+            RemoteResource::from_pretrained(*resource).get_local_path()?,
+            model_dir.join(file_name),
+        )?;
+    }
+    std::fs::write(
+        model_dir.join("tokenizer_config.json"),
+        serde_json::json!({ "tokenizer_class": tokenizer_class }).to_string(),
+    )?;
+    std::fs::write(
+        model_dir.join("sentence_bert_config.json"),
+        serde_json::json!({ "max_seq_length": 16, "do_lower_case": false }).to_string(),
+    )?;
+
+    let hidden_size = config["hidden_size"].as_i64().unwrap();
+    std::fs::create_dir(model_dir.join("1_Pooling"))?;
+    std::fs::write(
+        model_dir.join("1_Pooling").join("config.json"),
+        serde_json::json!({
+            "word_embedding_dimension": hidden_size,
+            "pooling_mode_cls_token": false,
+            "pooling_mode_max_tokens": false,
+            "pooling_mode_mean_tokens": true,
+            "pooling_mode_mean_sqrt_len_tokens": false,
+        })
+        .to_string(),
+    )?;
+
+    let module = |idx: usize, path: &str, module_type: SentenceEmbeddingsModuleType| {
+        SentenceEmbeddingsModuleConfig {
+            idx,
+            name: idx.to_string(),
+            path: path.to_string(),
+            module_type,
+        }
+    };
+    let mut modules = vec![
+        module(0, "", SentenceEmbeddingsModuleType::Transformer),
+        module(1, "1_Pooling", SentenceEmbeddingsModuleType::Pooling),
+    ];
+    let mut in_features = hidden_size;
+    for &out_features in dense_sizes {
+        let path = format!("{}_Dense", modules.len());
+        let dense_dir = model_dir.join(&path);
+        std::fs::create_dir(&dense_dir)?;
+        std::fs::write(
+            dense_dir.join("config.json"),
+            serde_json::json!({
+                "in_features": in_features,
+                "out_features": out_features,
+                "bias": true,
+                "activation_function": "torch.nn.modules.activation.Tanh",
+            })
+            .to_string(),
+        )?;
+        let dense_vs = nn::VarStore::new(Device::Cpu);
+        let _ = nn::linear(
+            dense_vs.root(),
+            in_features,
+            out_features,
+            Default::default(),
+        );
+        dense_vs.save(dense_dir.join("rust_model.ot"))?;
+        modules.push(module(
+            modules.len(),
+            &path,
+            SentenceEmbeddingsModuleType::Dense,
+        ));
+        in_features = out_features;
+    }
+    let path = format!("{}_Normalize", modules.len());
+    modules.push(module(
+        modules.len(),
+        &path,
+        SentenceEmbeddingsModuleType::Normalize,
+    ));
+    std::fs::write(
+        model_dir.join("modules.json"),
+        serde_json::to_string(&SentenceEmbeddingsModulesConfig(modules))?,
+    )?;
+
+    Ok(())
+}
+
 #[test]
 fn keyword_extraction_cosine_similarity() -> anyhow::Result<()> {
     let keyword_extraction_config = KeywordExtractionConfig {
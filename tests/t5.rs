@@ -1,9 +1,15 @@
+mod common;
+
+use common::tiny_t5_config;
 use rust_bert::pipelines::common::ModelType;
 use rust_bert::pipelines::summarization::{SummarizationConfig, SummarizationModel};
 use rust_bert::pipelines::translation::{Language, TranslationConfig, TranslationModel};
 use rust_bert::resources::RemoteResource;
-use rust_bert::t5::{T5ConfigResources, T5ModelResources, T5VocabResources};
-use tch::Device;
+use rust_bert::t5::{
+    EncoderAttentionType, FeedForwardProj, T5Config, T5ConfigResources, T5ForConditionalGeneration,
+    T5ForSentenceEmbeddings, T5ModelResources, T5VocabResources,
+};
+use tch::{nn, no_grad, Device, Kind, Tensor};
 
 #[test]
 fn test_translation_t5() -> anyhow::Result<()> {
@@ -111,3 +117,140 @@ about exoplanets like K2-18b."];
 
     Ok(())
 }
+
+#[test]
+fn t5_v1_1_parameter_names() -> anyhow::Result<()> {
+    //    Flan-T5 / T5 v1.1 layout: gated-GELU feed-forward and untied language modeling head
+    let config: T5Config = serde_json::from_str(
+        r#"{
+            "d_model": 32, "d_ff": 64, "d_kv": 8, "num_heads": 4, "num_layers": 2, "num_decoder_layers": 3,
+            "relative_attention_num_buckets": 8, "vocab_size": 64, "dropout_rate": 0.1,
+            "initializer_factor": 1.0, "layer_norm_epsilon": 1e-6,
+            "feed_forward_proj": "gated-gelu", "tie_word_embeddings": false
+        }"#,
+    )?;
+    let vs = nn::VarStore::new(Device::Cpu);
+    let _ = T5ForConditionalGeneration::new(vs.root(), &config);
+
+    let variables = vs.variables();
+    assert_eq!(
+        variables["encoder.block.1.layer.1.DenseReluDense.wi_0.weight"].size(),
+        vec![64, 32]
+    );
+    assert_eq!(
+        variables["decoder.block.2.layer.2.DenseReluDense.wi_1.weight"].size(),
+        vec![64, 32]
+    );
+    assert!(!variables.contains_key("decoder.block.3.layer.0.SelfAttention.q.weight"));
+    assert!(!variables.contains_key("encoder.block.2.layer.0.SelfAttention.q.weight"));
+    assert_eq!(variables["lm_head.weight"].size(), vec![64, 32]);
+
+    Ok(())
+}
+
+#[test]
+fn longt5_parameter_names() -> anyhow::Result<()> {
+    let vs = nn::VarStore::new(Device::Cpu);
+    let mut config = tiny_t5_config();
+    config.encoder_attention_type = Some(EncoderAttentionType::TransientGlobal);
+    config.local_radius = Some(3);
+    config.global_block_size = Some(4);
+    config.feed_forward_proj = Some(FeedForwardProj::GatedGelu);
+    let _ = T5ForConditionalGeneration::new(vs.root(), &config);
+
+    let variables = vs.variables();
+    let prefix = "encoder.block.0.layer.0.TransientGlobalSelfAttention";
+    assert_eq!(
+        variables[&format!("{prefix}.global_relative_attention_bias.weight")].size(),
+        vec![8, 4]
+    );
+    assert_eq!(
+        variables[&format!("{prefix}.global_input_layer_norm.weight")].size(),
+        vec![32]
+    );
+    assert!(!variables.contains_key(
+        "encoder.block.1.layer.0.TransientGlobalSelfAttention.global_relative_attention_bias.weight"
+    ));
+    assert!(variables.contains_key(
+        "encoder.block.1.layer.0.TransientGlobalSelfAttention.global_input_layer_norm.weight"
+    ));
+    // The decoder keeps the standard T5 attention
+    assert!(variables
+        .contains_key("decoder.block.0.layer.0.SelfAttention.relative_attention_bias.weight"));
+
+    Ok(())
+}
+
+#[test]
+fn longt5_local_attention_matches_full_attention() -> anyhow::Result<()> {
+    //    With a local radius covering the whole sequence, local attention is equivalent to full attention
+    tch::manual_seed(42);
+    let config = tiny_t5_config();
+    let vs = nn::VarStore::new(Device::Cpu);
+    let model = T5ForSentenceEmbeddings::new(vs.root(), &config);
+
+    let mut local_config = tiny_t5_config();
+    local_config.encoder_attention_type = Some(EncoderAttentionType::Local);
+    local_config.local_radius = Some(16);
+    let mut local_vs = nn::VarStore::new(Device::Cpu);
+    let local_model = T5ForSentenceEmbeddings::new(local_vs.root(), &local_config);
+    let variables = vs.variables();
+    no_grad(|| {
+        for (name, mut variable) in local_vs.variables() {
+            variable.copy_(&variables[&name.replace("LocalSelfAttention", "SelfAttention")]);
+        }
+    });
+    local_vs.freeze();
+
+    let input_ids = Tensor::of_slice(&[3i64, 17, 8, 42, 5, 11, 9, 1, 0, 0, 0, 0]).view((2, 6));
+    let mask = input_ids.ne(0).to_kind(Kind::Int64);
+    let (output, _) = no_grad(|| model.forward(&input_ids, &mask))?;
+    let (local_output, _) = no_grad(|| local_model.forward(&input_ids, &mask))?;
+
+    assert_eq!(local_output.size(), vec![2, 6, 32]);
+    let mask = mask.unsqueeze(-1);
+    assert!(
+        (output * &mask - local_output * &mask)
+            .abs()
+            .max()
+            .double_value(&[])
+            < 1e-5
+    );
+
+    Ok(())
+}
+
+#[test]
+fn longt5_transient_global_padding() -> anyhow::Result<()> {
+    tch::manual_seed(42);
+    let vs = nn::VarStore::new(Device::Cpu);
+    let mut config = tiny_t5_config();
+    config.encoder_attention_type = Some(EncoderAttentionType::TransientGlobal);
+    config.local_radius = Some(2);
+    config.global_block_size = Some(3);
+    let model = T5ForSentenceEmbeddings::new(vs.root(), &config);
+
+    let input_ids = Tensor::of_slice(&[3i64, 17, 8, 42, 5, 11, 9, 23, 1]).unsqueeze(0);
+    let (output, _) = no_grad(|| model.forward(&input_ids, &input_ids.ones_like()))?;
+    assert_eq!(output.size(), vec![1, 9, 32]);
+
+    // Right-padding the sequence leaves the outputs of the actual tokens unchanged
+    let padded_input_ids = Tensor::cat(
+        &[
+            input_ids,
+            Tensor::zeros(&[1, 5], (Kind::Int64, Device::Cpu)),
+        ],
+        1,
+    );
+    let padded_mask = padded_input_ids.ne(0).to_kind(Kind::Int64);
+    let (padded_output, _) = no_grad(|| model.forward(&padded_input_ids, &padded_mask))?;
+    assert!(
+        (padded_output.narrow(1, 0, 9) - output)
+            .abs()
+            .max()
+            .double_value(&[])
+            < 1e-5
+    );
+
+    Ok(())
+}
@@ -288,6 +288,7 @@ fn xlnet_for_sequence_classification() -> anyhow::Result<()> {
             None,
             None,
             None,
+            None,
             false,
         )
     });
@@ -427,6 +428,7 @@ fn xlnet_for_token_classification() -> anyhow::Result<()> {
             None,
             None,
             None,
+            None,
             false,
         )
     });
@@ -489,6 +491,8 @@ fn xlnet_for_question_answering() -> anyhow::Result<()> {
             None,
             None,
             None,
+            None,
+            None,
             false,
         )
     });
@@ -0,0 +1,114 @@
+"""Reference logits of tiny decoder models with deterministic weights.
+
+The integration tests of the LLaMA, GPT-J, GPT-NeoX, OPT and BLOOM models (and of BERT with relative position
+embeddings) fill every variable of a tiny model with deterministic values and compare the logits (hidden states
+for BERT) of a short input to the values printed by this script, which runs the Transformers implementation of
+each model with the same configuration and weights:
+
+    pip install torch transformers
+    python utils/reference_logits.py
+
+The weights are generated from the variable names: `0.2 * sin(0.37 * k^2 + s * (k + 1))` for the k-th element
+of a variable whose name has a byte sum of `100 * s`, shifted by 1 for normalization weights (see
+`set_reference_weights` in `tests/common/mod.rs`). Tied output embeddings are only counted once, and variables
+without a counterpart in the Rust model (the unused BERT absolute position embeddings) do not affect the outputs.
+"""
+
+import math
+
+import torch
+from transformers import (
+    BertConfig,
+    BertModel,
+    BloomConfig,
+    BloomForCausalLM,
+    GPTJConfig,
+    GPTJForCausalLM,
+    GPTNeoXConfig,
+    GPTNeoXForCausalLM,
+    LlamaConfig,
+    LlamaForCausalLM,
+    OPTConfig,
+    OPTForCausalLM,
+)
+
+INPUT_IDS = [3, 17, 8, 42, 5, 11]
+
+
+def is_norm_weight(name):
+    return (
+        name.endswith("norm.weight")
+        or name.endswith("LayerNorm.weight")
+        or ("ln_" in name and name.endswith(".weight"))
+    )
+
+
+def reference_weights(name, shape):
+    seed = sum(name.encode()) / 100.0
+    offset = 1.0 if is_norm_weight(name) else 0.0
+    numel = math.prod(shape)
+    values = [offset + 0.2 * math.sin(0.37 * k * k + seed * (k + 1)) for k in range(numel)]
+    return torch.tensor(values, dtype=torch.float64).view(shape)
+
+
+def llama():
+    # A large normalization epsilon and grouped-query attention (tests/llama.rs)
+    config = LlamaConfig(vocab_size=64, hidden_size=32, intermediate_size=48, num_hidden_layers=2,
+                         num_attention_heads=4, num_key_value_heads=2, max_position_embeddings=64,
+                         hidden_act="silu", rms_norm_eps=1e-2, rope_theta=10000.0, tie_word_embeddings=False)
+    return LlamaForCausalLM(config)
+
+
+def gptj():
+    # Partial rotary embeddings with interleaved pairs (tests/gptj.rs)
+    config = GPTJConfig(vocab_size=64, n_positions=64, n_embd=32, n_layer=2, n_head=4, rotary_dim=4,
+                        activation_function="gelu_new", layer_norm_epsilon=1e-5, tie_word_embeddings=False)
+    return GPTJForCausalLM(config)
+
+
+def gpt_neox():
+    # Partial rotary embeddings and parallel residual (tests/gpt_neox.rs)
+    config = GPTNeoXConfig(vocab_size=64, hidden_size=32, intermediate_size=48, num_hidden_layers=2,
+                           num_attention_heads=4, max_position_embeddings=64, rotary_pct=0.5,
+                           rotary_emb_base=10000, hidden_act="gelu", use_parallel_residual=True,
+                           layer_norm_eps=1e-5, tie_word_embeddings=False)
+    return GPTNeoXForCausalLM(config)
+
+
+def opt():
+    # Learned position embeddings with an offset of 2 and tied output embeddings (tests/opt.rs)
+    config = OPTConfig(vocab_size=64, hidden_size=32, num_hidden_layers=2, ffn_dim=48, max_position_embeddings=64,
+                       num_attention_heads=4, word_embed_proj_dim=32, do_layer_norm_before=True,
+                       activation_function="relu", pad_token_id=1, tie_word_embeddings=True)
+    return OPTForCausalLM(config)
+
+
+def bloom():
+    # A number of heads that is not a power of 2 exercises the interpolated ALiBi slopes (tests/bloom.rs)
+    config = BloomConfig(vocab_size=64, hidden_size=48, n_layer=2, n_head=6, layer_norm_epsilon=1e-5,
+                         tie_word_embeddings=True)
+    return BloomForCausalLM(config)
+
+
+def bert():
+    # Relative position embeddings interacting with the queries and keys (tests/bert.rs)
+    config = BertConfig(vocab_size=100, hidden_size=32, intermediate_size=64, num_hidden_layers=2,
+                        num_attention_heads=4, max_position_embeddings=16, type_vocab_size=2, hidden_act="gelu",
+                        layer_norm_eps=1e-12, position_embedding_type="relative_key_query")
+    return BertModel(config)
+
+
+if __name__ == "__main__":
+    for build_model in [llama, gptj, gpt_neox, opt, bloom, bert]:
+        model = build_model().double().eval()
+        # `named_parameters` yields tied parameters once, under the name of the input embeddings
+        parameters = list(model.named_parameters())
+        with torch.no_grad():
+            for name, parameter in parameters:
+                parameter.copy_(reference_weights(name, list(parameter.shape)))
+            # Logits of the causal language models, last hidden state of BERT
+            logits = model(torch.tensor([INPUT_IDS]))[0][0]
+        print(f"{build_model.__name__} ({len(parameters)} variables)")
+        for position in [2, 5]:
+            values = ", ".join(f"{value:.4f}" for value in logits[position, :6].tolist())
+            print(f"  position {position}: [{values}]")